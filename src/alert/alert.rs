@@ -12,7 +12,7 @@ pub enum AlertLevel {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone, TlsEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
 #[repr(u8)]
 pub enum AlertDescription {
     close_notify = 0,
@@ -37,15 +37,24 @@ pub enum AlertDescription {
     protocol_version = 70,
     insufficient_security = 71,
     internal_error = 80,
+    inappropriate_fallback = 86,
     user_canceled = 90,
     no_renegotiation = 100,
+    missing_extension = 109,
     unsupported_extension = 110, /* new */
+    certificate_unobtainable_RESERVED = 111,
+    unrecognized_name = 112,
+    bad_certificate_status_response = 113,
+    bad_certificate_hash_value_RESERVED = 114,
+    unknown_psk_identity = 115,
+    certificate_required = 116,
+    no_application_protocol = 120,
 }
 
 #[derive(Debug, Default, TlsDerive)]
 pub struct Alert {
-    level: AlertLevel,
-    description: AlertDescription,
+    pub level: AlertLevel,
+    pub description: AlertDescription,
 }
 
 pub type AlertRecord = RecordLayer<Alert>;
@@ -0,0 +1,207 @@
+// the supported public surface of the crate. Paths below follow semantic versioning with the
+// crate version: an item is only removed or changed in a major release, after being deprecated.
+// Other modules are public for the binary and the tests, and may change in any release
+//
+// the items, as reached from the crate root, are checked against public-api.txt, regenerated with
+// UPDATE_PUBLIC_API=1 cargo test
+
+// the wire format traits
+pub use crate::derive_tls::TlsDerive;
+pub use crate::scanner::check::Check;
+
+// records and messages
+pub use crate::alert::alert::Alert;
+pub use crate::alert::alert::AlertDescription;
+pub use crate::alert::alert::AlertLevel;
+pub use crate::dissector::summary::ClientHelloSummary;
+pub use crate::dissector::summary::ServerHelloSummary;
+pub use crate::handshake::builder::ClientHelloBuilder;
+pub use crate::handshake::client_hello::ClientHello;
+pub use crate::handshake::common::CipherSuite;
+pub use crate::handshake::common::ContentType;
+pub use crate::handshake::common::ProtocolVersion;
+pub use crate::handshake::common::Random;
+pub use crate::handshake::common::VariableLengthVector;
+pub use crate::handshake::finished::Finished;
+pub use crate::handshake::finished::FinishedSecret;
+pub use crate::handshake::finished::Sender;
+pub use crate::handshake::handshake::Handshake;
+pub use crate::handshake::handshake::HandshakeType;
+pub use crate::handshake::record_layer::RecordHeader;
+pub use crate::handshake::record_layer::RecordLayer;
+pub use crate::handshake::server_hello::ServerHello;
+pub use crate::handshake::templates::template;
+pub use crate::handshake::transcript::Transcript;
+
+// the client
+pub use crate::scanner::connector::Connector;
+pub use crate::scanner::connector::ServerResponse;
+pub use crate::scanner::flight::FlightError;
+pub use crate::scanner::info::ConnectionInfo;
+
+// the scanner
+pub use crate::scanner::check::CheckRegistry;
+pub use crate::scanner::check::Finding;
+pub use crate::scanner::check::Severity;
+pub use crate::scanner::checks::builtin_checks;
+pub use crate::scanner::messages::Catalog;
+pub use crate::scanner::messages::Message;
+pub use crate::scanner::report::render;
+pub use crate::scanner::report::render_localized;
+pub use crate::scanner::report::OutputFormat;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::{type_name, type_name_of_val};
+    use std::io::Result;
+    use std::path::Path;
+
+    const SNAPSHOT: &str = "public-api.txt";
+
+    // one line per item reached through crate::api: the path it resolves to, and the signature of
+    // functions and methods. An item gone or with another signature fails to compile, a moved one
+    // fails the snapshot. Items added above must be listed here
+    macro_rules! surface {
+        (@ $lines:ident) => {};
+        (@ $lines:ident type $name:ident; $($rest:tt)*) => {
+            $lines.push(format!(
+                "tls_explore::api::{} = {}",
+                stringify!($name),
+                type_name::<crate::api::$name>()
+            ));
+            surface!(@ $lines $($rest)*);
+        };
+        (@ $lines:ident type $name:ident<$($arg:tt),+>; $($rest:tt)*) => {
+            $lines.push(format!(
+                "tls_explore::api::{} = {}",
+                stringify!($name),
+                type_name::<crate::api::$name<$($arg),+>>()
+            ));
+            surface!(@ $lines $($rest)*);
+        };
+        (@ $lines:ident trait $name:ident; $($rest:tt)*) => {
+            $lines.push(format!(
+                "tls_explore::api::{} = {}",
+                stringify!($name),
+                type_name::<dyn crate::api::$name>()
+            ));
+            surface!(@ $lines $($rest)*);
+        };
+        (@ $lines:ident fn $name:ident: $sig:ty; $($rest:tt)*) => {
+            let f: $sig = crate::api::$name;
+            $lines.push(format!(
+                "tls_explore::api::{} = {}: {}",
+                stringify!($name),
+                type_name_of_val(&crate::api::$name),
+                type_name_of_val(&f)
+            ));
+            surface!(@ $lines $($rest)*);
+        };
+        (@ $lines:ident fn $type:ident::$method:ident: $sig:ty; $($rest:tt)*) => {
+            let f: $sig = crate::api::$type::$method;
+            $lines.push(format!(
+                "tls_explore::api::{}::{}: {}",
+                stringify!($type),
+                stringify!($method),
+                type_name_of_val(&f)
+            ));
+            surface!(@ $lines $($rest)*);
+        };
+        ($($items:tt)*) => {{
+            let mut lines: Vec<String> = Vec::new();
+            surface!(@ lines $($items)*);
+            lines.sort();
+            lines.iter().map(|x| format!("{}\n", x)).collect::<String>()
+        }};
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn public_api() -> String {
+        surface! {
+            trait TlsDerive;
+            trait Check;
+
+            type Alert;
+            type AlertDescription;
+            type AlertLevel;
+            type ClientHelloSummary;
+            type ServerHelloSummary;
+            type ClientHelloBuilder;
+            type ClientHello;
+            type CipherSuite;
+            type ContentType;
+            type ProtocolVersion;
+            type Random;
+            type VariableLengthVector<u8, 0, 1>;
+            type Finished;
+            type FinishedSecret;
+            type Sender;
+            type Handshake<ClientHello>;
+            type HandshakeType;
+            type RecordHeader;
+            type RecordLayer<ClientHello>;
+            type ServerHello;
+            type Transcript;
+            type Connector;
+            type ServerResponse;
+            type FlightError;
+            type ConnectionInfo;
+            type CheckRegistry;
+            type Finding;
+            type Severity;
+            type Catalog;
+            type Message;
+            type OutputFormat;
+
+            fn template: fn(&str, &str) -> Option<ClientHello>;
+            fn builtin_checks: fn() -> Vec<Box<dyn Check>>;
+            fn render: fn(OutputFormat, &str, &[&dyn Check], &[Finding]) -> String;
+            fn render_localized:
+                fn(OutputFormat, &str, &[&dyn Check], &[Finding], &Catalog) -> String;
+
+            fn TlsDerive::tls_len: fn(&ClientHello) -> usize;
+            fn TlsDerive::to_network_bytes: fn(&ClientHello, &mut Vec<u8>) -> Result<usize>;
+            fn Check::run: fn(&(dyn Check + 'static), &Connector) -> Finding;
+            fn Connector::new: fn(&str) -> Connector;
+            fn Connector::client_hello: fn(&Connector) -> ClientHello;
+            fn Connector::send: fn(&Connector, &ClientHello) -> Result<ServerResponse>;
+            fn Connector::connection_info:
+                fn(&Connector, &ClientHello) -> Result<Option<ConnectionInfo>>;
+            fn ClientHelloSummary::parse: fn(&[u8]) -> Result<ClientHelloSummary>;
+            fn ServerHelloSummary::parse: fn(&[u8]) -> Result<ServerHelloSummary>;
+            fn CheckRegistry::with_builtin: fn() -> CheckRegistry;
+            fn CheckRegistry::register: fn(&mut CheckRegistry, Box<dyn Check>) -> Result<()>;
+            fn CheckRegistry::run:
+                fn(&CheckRegistry, &Connector, &[String], &[String]) -> Result<Vec<Finding>>;
+            fn Finding::new: fn(&str, Severity, &str) -> Finding;
+            fn Severity::from_name: fn(&str) -> Option<Severity>;
+            fn Message::new: fn(&'static str, &[&str]) -> Message;
+            fn Catalog::parse: fn(&str) -> Result<Catalog>;
+            fn Catalog::load: fn(&Path) -> Result<Catalog>;
+            fn OutputFormat::from_name: fn(&str) -> Option<OutputFormat>;
+            fn ClientHelloBuilder::sni: fn(ClientHelloBuilder, &str) -> ClientHelloBuilder;
+            fn ClientHelloBuilder::build: fn(&ClientHelloBuilder) -> Result<ClientHello>;
+            fn Finished::compute: fn(FinishedSecret, &Transcript) -> Finished;
+            fn Transcript::add: fn(&mut Transcript, &[u8]);
+        }
+    }
+
+    // the paths are those of the compiler's type names, which may change with the toolchain
+    #[test]
+    fn snapshot() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src")
+            .join(SNAPSHOT);
+        let current = public_api();
+        if std::env::var_os("UPDATE_PUBLIC_API").is_some() {
+            std::fs::write(&path, &current).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            current, expected,
+            "the public API changed, rerun with UPDATE_PUBLIC_API=1 if it's intended"
+        );
+    }
+}
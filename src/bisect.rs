@@ -0,0 +1,315 @@
+// find which part of a ClientHello makes a server reject it, by stripping extensions and
+// cipher suites until the server accepts the hello
+use std::fmt;
+use std::io::{Read, Result, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::derive_tls::TlsDerive;
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::client_hello::{ClientHello, ExtensionType};
+use crate::handshake::common::{CipherSuite, ContentType};
+use crate::handshake::constants::TLS10;
+use crate::handshake::handshake::{Handshake, HandshakeType};
+use crate::handshake::record_layer::{RecordHeader, RecordLayer};
+use crate::scanner::connector::split_host;
+
+// a single change made to a ClientHello
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutation {
+    RemoveExtension(ExtensionType),
+    RemoveSuite(CipherSuite),
+}
+
+impl Mutation {
+    pub fn apply(&self, ch: &ClientHello) -> ClientHello {
+        match self {
+            Mutation::RemoveExtension(extension_type) => ch.with_extension_removed(*extension_type),
+            Mutation::RemoveSuite(suite) => ch.with_suite_removed(*suite),
+        }
+    }
+
+    // all the mutations which can be applied to a ClientHello, extensions first. Extensions
+    // unknown to ExtensionType, like GREASE, are kept
+    fn candidates(ch: &ClientHello) -> Vec<Mutation> {
+        ch.extensions()
+            .iter()
+            .map(|x| x.extension_type())
+            .filter(|x| !matches!(x, ExtensionType::Unknown(_)))
+            .map(Mutation::RemoveExtension)
+            .chain(ch.cipher_suites().iter().map(|x| Mutation::RemoveSuite(*x)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutation::RemoveExtension(extension_type) => {
+                write!(f, "remove extension {}", extension_type)
+            }
+            Mutation::RemoveSuite(suite) => {
+                write!(
+                    f,
+                    "remove cipher suite 0x{:04X}",
+                    u16::from_be_bytes(*suite)
+                )
+            }
+        }
+    }
+}
+
+// send a ClientHello and tell whether the server accepted it
+pub trait Probe {
+    fn accepts(&mut self, ch: &ClientHello) -> Result<bool>;
+}
+
+// closures can be used as probes, e.g. to replay known behaviors
+impl<F: FnMut(&ClientHello) -> Result<bool>> Probe for F {
+    fn accepts(&mut self, ch: &ClientHello) -> Result<bool> {
+        self(ch)
+    }
+}
+
+// probe a real server: the hello is accepted if a ServerHello comes back
+pub struct TcpProbe {
+    pub address: String,
+    pub timeout: Duration,
+}
+
+impl TcpProbe {
+    pub fn new(host: &str) -> Self {
+        let (_, address) = split_host(host);
+
+        Self {
+            address,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Probe for TcpProbe {
+    fn accepts(&mut self, ch: &ClientHello) -> Result<bool> {
+        let mut record = RecordLayer {
+            header: RecordHeader {
+                content_type: ContentType::handshake,
+                version: TLS10,
+                length: 0,
+            },
+            data: Handshake::from_client_hello(ch.clone()),
+        };
+        record.set_length();
+
+        let mut buffer = Vec::new();
+        record.to_network_bytes(&mut buffer)?;
+
+        let mut stream = TcpStream::connect(&self.address)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.write_all(&buffer)?;
+
+        // read until the server closes, times out or enough data is there to decide
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => response.extend_from_slice(&chunk[..n]),
+            }
+            if !records(&response).is_empty() {
+                break;
+            }
+        }
+
+        Ok(is_server_hello(&response))
+    }
+}
+
+// true if the stream starts with a ServerHello, even if it's not complete
+fn is_server_hello(response: &[u8]) -> bool {
+    let records = records(response);
+    if let Some(message) = handshake_messages(&records).first() {
+        return message.msg_type == HandshakeType::server_hello as u8;
+    }
+
+    // ServerHello bigger than the data read so far
+    records
+        .first()
+        .map(|r| {
+            r.content_type == ContentType::handshake as u8
+                && r.fragment.first() == Some(&(HandshakeType::server_hello as u8))
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Default)]
+pub struct BisectResult {
+    pub accepted_as_is: bool,
+
+    // the minimal set of changes found to make the server accept the hello
+    pub culprits: Vec<Mutation>,
+
+    // number of hellos sent
+    pub probes: usize,
+}
+
+impl fmt::Display for BisectResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.accepted_as_is {
+            writeln!(f, "the server accepts the hello as is")?;
+        } else if self.culprits.is_empty() {
+            writeln!(f, "no change found to make the server accept the hello")?;
+        } else {
+            writeln!(f, "the server accepts the hello after these changes:")?;
+            for culprit in &self.culprits {
+                writeln!(f, "  {}", culprit)?;
+            }
+        }
+        writeln!(f, "{} hellos sent", self.probes)
+    }
+}
+
+pub fn bisect<P: Probe>(probe: &mut P, ch: &ClientHello) -> Result<BisectResult> {
+    let mut result = BisectResult::default();
+    let mut accepts = |ch: &ClientHello| {
+        result.probes += 1;
+        probe.accepts(ch)
+    };
+
+    if accepts(ch)? {
+        return Ok(BisectResult {
+            accepted_as_is: true,
+            probes: 1,
+            ..Default::default()
+        });
+    }
+
+    let candidates = Mutation::candidates(ch);
+
+    // a single change might be enough
+    for mutation in &candidates {
+        if accepts(&mutation.apply(ch))? {
+            let probes = result.probes;
+            return Ok(BisectResult {
+                culprits: vec![*mutation],
+                probes,
+                ..Default::default()
+            });
+        }
+    }
+
+    // otherwise, accumulate changes until the hello is accepted
+    let mut applied = Vec::new();
+    let mut current = ch.clone();
+    let mut found = false;
+    for mutation in &candidates {
+        current = mutation.apply(&current);
+        applied.push(*mutation);
+
+        // removing all suites can't be accepted
+        if current.cipher_suites().is_empty() {
+            break;
+        }
+        if accepts(&current)? {
+            found = true;
+            break;
+        }
+    }
+
+    if !found {
+        let probes = result.probes;
+        return Ok(BisectResult {
+            probes,
+            ..Default::default()
+        });
+    }
+
+    // then keep only the changes which are needed
+    let mut i = 0;
+    while i < applied.len() {
+        let mut without = applied.clone();
+        without.remove(i);
+
+        let hello = without.iter().fold(ch.clone(), |ch, m| m.apply(&ch));
+        if accepts(&hello)? {
+            applied = without;
+        } else {
+            i += 1;
+        }
+    }
+
+    let probes = result.probes;
+    Ok(BisectResult {
+        culprits: applied,
+        probes,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::templates::template;
+
+    fn has(ch: &ClientHello, extension_type: ExtensionType) -> bool {
+        ch.extensions()
+            .iter()
+            .any(|x| x.extension_type() == extension_type)
+    }
+
+    #[test]
+    fn single_culprit() {
+        let ch = template("chrome", "example.com").unwrap();
+        let mut probe = |ch: &ClientHello| Ok(!has(ch, ExtensionType::key_share));
+        let result = bisect(&mut probe, &ch).unwrap();
+
+        assert!(!result.accepted_as_is);
+        assert_eq!(
+            result.culprits,
+            vec![Mutation::RemoveExtension(ExtensionType::key_share)]
+        );
+    }
+
+    #[test]
+    fn accepted() {
+        let ch = template("minimal", "example.com").unwrap();
+        let mut probe = |ch: &ClientHello| Ok(!has(ch, ExtensionType::key_share));
+        let result = bisect(&mut probe, &ch).unwrap();
+        assert!(result.accepted_as_is);
+        assert_eq!(result.probes, 1);
+    }
+
+    // the server rejects any of 2 extensions, so both must be removed
+    #[test]
+    fn combination() {
+        let ch = template("chrome", "example.com").unwrap();
+        let mut probe = |ch: &ClientHello| {
+            Ok(!has(ch, ExtensionType::session_ticket) && !has(ch, ExtensionType::key_share))
+        };
+        let result = bisect(&mut probe, &ch).unwrap();
+        assert_eq!(
+            result.culprits,
+            vec![
+                Mutation::RemoveExtension(ExtensionType::session_ticket),
+                Mutation::RemoveExtension(ExtensionType::key_share)
+            ]
+        );
+    }
+
+    #[test]
+    fn not_found() {
+        let ch = template("minimal", "example.com").unwrap();
+        let result = bisect(&mut |_: &ClientHello| Ok(false), &ch).unwrap();
+        assert!(result.culprits.is_empty());
+    }
+
+    #[test]
+    fn server_hello() {
+        assert!(is_server_hello(&[
+            0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0x00, 0x00, 0x46
+        ]));
+        assert!(!is_server_hello(&[
+            0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28
+        ]));
+        assert!(!is_server_hello(&[]));
+    }
+}
@@ -0,0 +1,206 @@
+// a stable line-oriented text encoding of messages, meant to be stored alongside fixtures
+// and compared with diff. Each line is "field: value", fields always come in the same
+// order, and lists are written as one line per element with the same field name
+use std::fmt::Write;
+
+use crate::alert::alert::Alert;
+use crate::change_cipher_spec::change_cipher_spec::ChangeCipherSpec;
+use crate::crypto::to_hex;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::handshake::certificate::CertificateMessage;
+use crate::handshake::client_hello::{extension_name, GenericExtension};
+use crate::handshake::common::{version_name, ProtocolVersion};
+use crate::handshake::server_hello::ServerHello;
+use crate::heartbeat::heartbeat::HeartbeatMessage;
+
+pub trait Canonical {
+    fn write_canonical(&self, w: &mut CanonicalWriter);
+
+    fn to_canonical(&self) -> String {
+        let mut w = CanonicalWriter::default();
+        self.write_canonical(&mut w);
+        w.finish()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CanonicalWriter {
+    text: String,
+}
+
+impl CanonicalWriter {
+    // no trailing space for empty values, so lines don't change because of an editor
+    pub fn field<V: std::fmt::Display>(&mut self, name: &str, value: V) {
+        let value = value.to_string();
+        if value.is_empty() {
+            let _ = writeln!(self.text, "{}:", name);
+        } else {
+            let _ = writeln!(self.text, "{}: {}", name, value);
+        }
+    }
+
+    pub fn bytes(&mut self, name: &str, value: &[u8]) {
+        self.field(name, to_hex(value));
+    }
+
+    pub fn version(&mut self, name: &str, version: ProtocolVersion) {
+        self.field(
+            name,
+            format!(
+                "0x{:04X} {}",
+                u16::from_be_bytes(version),
+                version_name(version)
+            ),
+        );
+    }
+
+    pub fn code16(&mut self, name: &str, value: u16) {
+        self.field(name, format!("0x{:04X}", value));
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+}
+
+impl Canonical for ClientHelloSummary {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field("message", "client_hello");
+        w.version("client_version", self.client_version);
+        w.bytes("session_id", &self.session_id);
+        self.cipher_suites
+            .iter()
+            .for_each(|x| w.code16("cipher_suite", *x));
+        self.compression_methods
+            .iter()
+            .for_each(|x| w.field("compression_method", x));
+        self.extensions
+            .iter()
+            .for_each(|x| w.field("extension", extension_name(*x)));
+        if let Some(name) = &self.server_name {
+            w.field("server_name", name);
+        }
+        self.supported_groups
+            .iter()
+            .for_each(|x| w.code16("supported_group", *x));
+        self.ec_point_formats
+            .iter()
+            .for_each(|x| w.field("ec_point_format", x));
+        self.alpn.iter().for_each(|x| w.field("alpn", x));
+        self.supported_versions
+            .iter()
+            .for_each(|x| w.version("supported_version", *x));
+        if let Some(ticket) = &self.session_ticket {
+            w.bytes("session_ticket", ticket);
+        }
+    }
+}
+
+impl Canonical for GenericExtension {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field(
+            "extension",
+            format!(
+                "{} {}",
+                extension_name(self.type_code()),
+                to_hex(self.extension_data())
+            )
+            .trim_end(),
+        );
+    }
+}
+
+impl Canonical for ServerHello {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field(
+            "message",
+            if self.is_hello_retry_request() {
+                "hello_retry_request"
+            } else {
+                "server_hello"
+            },
+        );
+        w.version("server_version", self.server_version);
+        w.bytes("random", &self.random.as_bytes());
+        w.bytes("session_id", &self.session_id.data);
+        w.code16("cipher_suite", u16::from_be_bytes(self.cipher_suite));
+        w.field("compression_method", self.compression_method);
+        if let Some(extensions) = &self.extensions {
+            extensions
+                .as_slice()
+                .iter()
+                .for_each(|x| x.write_canonical(w));
+        }
+    }
+}
+
+impl Canonical for CertificateMessage {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field("message", "certificate");
+        if let CertificateMessage::Tls13(c) = self {
+            w.bytes(
+                "certificate_request_context",
+                &c.certificate_request_context.data,
+            );
+        }
+        self.certificates()
+            .iter()
+            .for_each(|x| w.bytes("certificate", x));
+    }
+}
+
+impl Canonical for Alert {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field("message", "alert");
+        w.field("level", self.level);
+        w.field("description", self.description);
+    }
+}
+
+impl Canonical for ChangeCipherSpec {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field("message", "change_cipher_spec");
+        w.field("type", self.r#type);
+    }
+}
+
+impl Canonical for HeartbeatMessage {
+    fn write_canonical(&self, w: &mut CanonicalWriter) {
+        w.field("message", self.r#type);
+        w.field("payload_length", self.payload_length);
+        w.bytes("payload", &self.payload);
+        w.field("padding_length", self.padding.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+
+    #[test]
+    fn client_hello() {
+        let summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let text = summary.to_canonical();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "message: client_hello");
+        assert_eq!(lines[1], "client_version: 0x0303 TLS 1.2");
+        assert_eq!(lines[2], "session_id:");
+        assert_eq!(lines[3], "cipher_suite: 0xCCA8");
+        assert!(lines.contains(&"extension: renegotiation_info(65281)"));
+        assert!(lines.contains(&"server_name: example.ulfheim.net"));
+        assert_eq!(lines.last(), Some(&"ec_point_format: 0"));
+
+        // encoding the same message twice gives the same text
+        assert_eq!(text, summary.to_canonical());
+    }
+
+    #[test]
+    fn change_cipher_spec() {
+        assert_eq!(
+            ChangeCipherSpec::default().to_canonical(),
+            "message: change_cipher_spec\ntype: 1\n"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+// https://datatracker.ietf.org/doc/html/rfc5246#section-7.1
+use std::io::{Cursor, Error, ErrorKind, Result};
+
+use byteorder::ReadBytesExt;
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::record_layer::RecordLayer;
+
+// the only value allowed for the ChangeCipherSpec message
+pub const CHANGE_CIPHER_SPEC: u8 = 1;
+
+#[derive(Debug, PartialEq)]
+pub struct ChangeCipherSpec {
+    pub r#type: u8,
+}
+
+impl Default for ChangeCipherSpec {
+    fn default() -> Self {
+        Self {
+            r#type: CHANGE_CIPHER_SPEC,
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(ChangeCipherSpec);
+
+/// ```
+/// use std::io::Cursor;
+/// use tls_explore::derive_tls::TlsDerive;
+/// use tls_explore::change_cipher_spec::change_cipher_spec::ChangeCipherSpecRecord;
+///
+/// let mut record = ChangeCipherSpecRecord::default();
+/// assert!(record.from_network_bytes(&mut Cursor::new(vec![0x14, 0x03, 0x03, 0x00, 0x01, 0x01])).is_ok());
+/// assert_eq!(record.data.r#type, 1);
+///
+/// let mut record = ChangeCipherSpecRecord::default();
+/// assert!(record.from_network_bytes(&mut Cursor::new(vec![0x14, 0x03, 0x03, 0x00, 0x01, 0x02])).is_err());
+/// ```
+impl TlsDerive for ChangeCipherSpec {
+    fn tls_len(&self) -> usize {
+        1
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        v.push(self.r#type);
+        Ok(1)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        let value = v.read_u8()?;
+        if value != CHANGE_CIPHER_SPEC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid ChangeCipherSpec value {}", value),
+            ));
+        }
+        self.r#type = value;
+        Ok(())
+    }
+}
+
+pub type ChangeCipherSpecRecord = RecordLayer<ChangeCipherSpec>;
@@ -0,0 +1,300 @@
+// defaults for the command line, read from tls_explore.toml or the file given with --config.
+// Only the subset of TOML needed is supported: comments, and top-level keys whose values
+// are strings, integers, booleans or arrays of strings:
+//
+//   template = "chrome"
+//   timeout = 5
+//   suites = ["TLS_AES_128_GCM_SHA256", "0xC02F"]
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::handshake::common::{cipher_suite_from_name, CipherSuite};
+use crate::handshake::templates::TEMPLATES;
+use crate::scanner::check::Severity;
+use crate::scanner::report::{OutputFormat, OUTPUT_FORMATS};
+
+// read from the current directory if no file is given
+pub const DEFAULT_CONFIG: &str = "tls_explore.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(u64),
+    Boolean(bool),
+    Array(Vec<String>),
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Config {
+    // suite policy, in order of preference
+    pub cipher_suites: Vec<CipherSuite>,
+
+    // network timeout for probes and handshakes
+    pub timeout: Option<Duration>,
+
+    // guards against servers keeping a handshake going
+    pub max_handshake_messages: Option<usize>,
+    pub handshake_budget: Option<Duration>,
+
+    // false to read ServerHellos with extensions the client didn't offer
+    pub strict_extensions: Option<bool>,
+    pub output: Option<OutputFormat>,
+
+    // CA certificates used to validate peers
+    pub trust_store: Option<PathBuf>,
+
+    // message catalog of scan reports, English if not given
+    pub locale: Option<PathBuf>,
+
+    // scan checks to enable, all of them if empty
+    pub checks: Vec<String>,
+
+    // lowest severity making a scan fail
+    pub fail_on: Option<Severity>,
+    pub template: Option<String>,
+    pub redact: bool,
+}
+
+pub(crate) fn invalid(line: usize, msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, msg))
+}
+
+// remove a comment, unless the # is in a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_string(s: &str, line: usize) -> Result<String> {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .filter(|s| !s.contains('"'))
+        .map(String::from)
+        .ok_or_else(|| invalid(line, &format!("invalid string {}", s)))
+}
+
+fn parse_value(s: &str, line: usize) -> Result<Value> {
+    if let Some(items) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        return items
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| parse_string(x, line))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array);
+    }
+
+    match s {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        _ if s.starts_with('"') => parse_string(s, line).map(Value::String),
+        _ => s
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| invalid(line, &format!("invalid value {}", s))),
+    }
+}
+
+// key/value pairs with the line where they're defined. Arrays can span several lines
+pub(crate) fn parse_pairs(text: &str) -> Result<Vec<(usize, String, Value)>> {
+    let mut pairs = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((i, line)) = lines.next() {
+        let line_number = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(line_number, "expected key = value"))?;
+        let mut value = value.trim().to_string();
+
+        if value.starts_with('[') {
+            while !value.ends_with(']') {
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| invalid(line_number, "unterminated array"))?;
+                value.push_str(strip_comment(next).trim());
+            }
+        }
+
+        pairs.push((
+            line_number,
+            key.trim().to_string(),
+            parse_value(&value, line_number)?,
+        ));
+    }
+
+    Ok(pairs)
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = Config::default();
+
+        for (line, key, value) in parse_pairs(text)? {
+            match (key.as_str(), value) {
+                ("suites", Value::Array(names)) => {
+                    for name in names {
+                        let suite = cipher_suite_from_name(&name).ok_or_else(|| {
+                            invalid(line, &format!("unknown cipher suite {}", name))
+                        })?;
+                        config.cipher_suites.push(suite);
+                    }
+                }
+                ("timeout", Value::Integer(seconds)) => {
+                    config.timeout = Some(Duration::from_secs(seconds))
+                }
+                ("max_handshake_messages", Value::Integer(max)) => {
+                    config.max_handshake_messages = Some(max as usize)
+                }
+                ("handshake_budget", Value::Integer(seconds)) => {
+                    config.handshake_budget = Some(Duration::from_secs(seconds))
+                }
+                ("strict_extensions", Value::Boolean(strict)) => {
+                    config.strict_extensions = Some(strict)
+                }
+                ("output", Value::String(output)) => {
+                    let format = OutputFormat::from_name(&output).ok_or_else(|| {
+                        invalid(
+                            line,
+                            &format!("unknown output {}, use one of {:?}", output, OUTPUT_FORMATS),
+                        )
+                    })?;
+                    config.output = Some(format);
+                }
+                ("trust_store", Value::String(path)) => config.trust_store = Some(path.into()),
+                ("locale", Value::String(path)) => config.locale = Some(path.into()),
+                ("checks", Value::Array(checks)) => config.checks = checks,
+                ("fail_on", Value::String(name)) => {
+                    let severity = Severity::from_name(&name)
+                        .ok_or_else(|| invalid(line, &format!("unknown severity {}", name)))?;
+                    config.fail_on = Some(severity);
+                }
+                ("template", Value::String(name)) => {
+                    if !TEMPLATES.contains(&name.as_str()) {
+                        return Err(invalid(
+                            line,
+                            &format!("unknown template {}, use one of {:?}", name, TEMPLATES),
+                        ));
+                    }
+                    config.template = Some(name);
+                }
+                ("redact", Value::Boolean(redact)) => config.redact = redact,
+                (
+                    "suites"
+                    | "timeout"
+                    | "max_handshake_messages"
+                    | "handshake_budget"
+                    | "strict_extensions"
+                    | "output"
+                    | "trust_store"
+                    | "locale"
+                    | "checks"
+                    | "fail_on"
+                    | "template"
+                    | "redact",
+                    _,
+                ) => return Err(invalid(line, &format!("wrong type for {}", key))),
+                _ => return Err(invalid(line, &format!("unknown key {}", key))),
+            }
+        }
+
+        Ok(config)
+    }
+
+    // the given file, or the default one if it exists
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => Path::new(path),
+            None if Path::new(DEFAULT_CONFIG).exists() => Path::new(DEFAULT_CONFIG),
+            None => return Ok(Config::default()),
+        };
+
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+# team defaults
+template = "minimal"
+redact = true
+timeout = 3   # seconds
+handshake_budget = 20
+max_handshake_messages = 16
+strict_extensions = false
+output = "sarif"
+trust_store = "/etc/ssl/certs/ca-certificates.crt"
+locale = "fr.toml"
+suites = [
+    "TLS_AES_128_GCM_SHA256", # TLS 1.3
+    "0xC02F",
+]
+checks = ["heartbeat", "renegotiation"]
+fail_on = "high"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.template.as_deref(), Some("minimal"));
+        assert!(config.redact);
+        assert_eq!(config.timeout, Some(Duration::from_secs(3)));
+        assert_eq!(config.handshake_budget, Some(Duration::from_secs(20)));
+        assert_eq!(config.max_handshake_messages, Some(16));
+        assert_eq!(config.strict_extensions, Some(false));
+        assert_eq!(config.output, Some(OutputFormat::Sarif));
+        assert_eq!(
+            config.trust_store,
+            Some(PathBuf::from("/etc/ssl/certs/ca-certificates.crt"))
+        );
+        assert_eq!(config.locale, Some(PathBuf::from("fr.toml")));
+        assert_eq!(
+            config.cipher_suites,
+            vec![
+                TLS_AES_128_GCM_SHA256,
+                TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+            ]
+        );
+        assert_eq!(config.checks, vec!["heartbeat", "renegotiation"]);
+        assert_eq!(config.fail_on, Some(Severity::High));
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn errors() {
+        let err = Config::parse("redact = true\nfoo = 1").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unknown key foo");
+
+        let err = Config::parse("timeout = \"5\"").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: wrong type for timeout");
+
+        assert!(Config::parse("suites = [\"TLS_FOO\"]").is_err());
+        assert!(Config::parse("template = \"curl\"").is_err());
+        assert!(Config::parse("output = \"xml\"").is_err());
+        assert!(Config::parse("fail_on = \"medium\"").is_err());
+        assert!(Config::parse("suites = [\"TLS_AES_128_GCM_SHA256\"").is_err());
+        assert!(Config::parse("redact").is_err());
+    }
+}
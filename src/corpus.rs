@@ -0,0 +1,305 @@
+// seed inputs for fuzzing the parsers: valid records of many shapes, across versions, cipher
+// suites and extensions. They're built with the same APIs as what's sent on the wire, so they
+// stay valid as the encoders change
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::ffdhe::{DhKeyPair, DhParams};
+use crate::crypto::nist::NistKeyPair;
+use crate::crypto::rsa::{pre_master_secret, RsaPublicKey};
+use crate::crypto::x25519::X25519KeyPair;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::handshake::builder::ClientHelloBuilder;
+use crate::handshake::certificate::{Certificate, Certificate13};
+use crate::handshake::client_hello::{NamedGroup, SignatureScheme};
+use crate::handshake::client_key_exchange::ClientKeyExchange;
+use crate::handshake::common::{ContentType, ProtocolVersion, VariableLengthVector};
+use crate::handshake::constants::*;
+use crate::handshake::handshake::{Handshake, HandshakeType};
+use crate::handshake::new_session_ticket::NewSessionTicket;
+use crate::handshake::record_layer::{RecordHeader, RecordLayer};
+use crate::handshake::server_key_exchange::{ServerDhParams, ServerKeyExchangeDhe};
+use crate::handshake::templates::{template, TEMPLATES};
+use crate::heartbeat::heartbeat::{HeartbeatMessage, HeartbeatMessageType, HeartbeatRecord};
+use crate::server::server::{change_cipher_spec, close_notify, fatal_alert, Server};
+
+#[derive(Debug)]
+pub struct Seed {
+    // used as the file name
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Seed {
+    fn new(name: &str, bytes: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            bytes,
+        }
+    }
+}
+
+// a handshake message in a single record
+fn handshake_record<T>(msg_type: HandshakeType, body: T) -> Result<Vec<u8>>
+where
+    T: std::fmt::Debug + Default + TlsDerive,
+{
+    let mut record = RecordLayer {
+        header: RecordHeader {
+            content_type: ContentType::handshake,
+            version: TLS12,
+            length: 0,
+        },
+        data: Handshake::from_message(msg_type, body),
+    };
+    record.set_length();
+
+    let mut v = Vec::new();
+    record.to_network_bytes(&mut v)?;
+    Ok(v)
+}
+
+fn version_label(version: ProtocolVersion) -> &'static str {
+    match version {
+        TLS10 => "tls10",
+        TLS11 => "tls11",
+        TLS13 => "tls13",
+        _ => "tls12",
+    }
+}
+
+// the builder variations, each with and without GREASE
+fn client_hellos() -> Vec<(String, ClientHelloBuilder)> {
+    let mut builders = Vec::new();
+    for version in [TLS10, TLS11, TLS12, TLS13] {
+        let label = version_label(version);
+        let base = ClientHelloBuilder::new().version(version);
+
+        builders.push((label.to_string(), base.clone()));
+        builders.push((
+            format!("{}_sni_alpn", label),
+            base.clone().sni("example.com").alpn(&["h2", "http/1.1"]),
+        ));
+        builders.push((
+            format!("{}_nist_groups", label),
+            base.clone()
+                .groups(&[NamedGroup::secp256r1, NamedGroup::secp384r1]),
+        ));
+        builders.push((
+            format!("{}_no_extensions", label),
+            base.groups(&[]).signature_algorithms(&[]),
+        ));
+    }
+    builders.push((
+        "tls12_rsa_cbc".to_string(),
+        ClientHelloBuilder::new()
+            .cipher_suites(&[TLS_RSA_WITH_AES_128_CBC_SHA, TLS_RSA_WITH_AES_256_CBC_SHA]),
+    ));
+
+    builders
+        .into_iter()
+        .flat_map(|(name, builder)| {
+            [
+                (format!("client_hello_{}", name), builder.clone()),
+                (
+                    format!("client_hello_{}_grease", name),
+                    builder.grease(true),
+                ),
+            ]
+        })
+        .collect()
+}
+
+// all seeds. Random values, like the hello randoms and key shares, differ from one call to
+// the next
+pub fn seeds() -> Result<Vec<Seed>> {
+    let mut seeds = Vec::new();
+
+    // the ServerHello flights answering the hellos
+    let mut server = Server::default();
+    server
+        .config
+        .resolver
+        .set_default(vec![vec![0x30, 0x03, 0x02, 0x01, 0x01]]);
+
+    for (name, builder) in client_hellos() {
+        let ch = builder.build()?;
+        let mut message = Vec::new();
+        Handshake::from_client_hello(ch.clone()).to_network_bytes(&mut message)?;
+        let flight = server.respond(&ClientHelloSummary::parse(&message[4..])?, &message)?;
+
+        seeds.push(Seed::new(
+            &name,
+            handshake_record(HandshakeType::client_hello, ch)?,
+        ));
+        seeds.push(Seed::new(&name.replace("client", "server"), flight));
+    }
+
+    for name in TEMPLATES {
+        // a template always exists for the names listed
+        let ch = template(name, "example.com").unwrap();
+        seeds.push(Seed::new(
+            &format!("client_hello_template_{}", name),
+            handshake_record(HandshakeType::client_hello, ch)?,
+        ));
+    }
+
+    let certificates: [&[u8]; 2] = [&[0x30, 0x03, 0x02, 0x01, 0x01], &[0x30, 0x00]];
+    seeds.push(Seed::new(
+        "certificate",
+        handshake_record(HandshakeType::certificate, Certificate::new(&certificates))?,
+    ));
+    seeds.push(Seed::new(
+        "certificate_tls13",
+        handshake_record(
+            HandshakeType::certificate,
+            Certificate13::new(&[], &certificates),
+        )?,
+    ));
+    seeds.push(Seed::new(
+        "client_key_exchange_x25519",
+        handshake_record(
+            HandshakeType::client_key_exchange,
+            ClientKeyExchange::x25519(&X25519KeyPair::generate()),
+        )?,
+    ));
+    for group in [NamedGroup::secp256r1, NamedGroup::secp384r1] {
+        // both groups are supported
+        let keys = NistKeyPair::generate(group).unwrap();
+        seeds.push(Seed::new(
+            &format!("client_key_exchange_{:?}", group),
+            handshake_record(
+                HandshakeType::client_key_exchange,
+                ClientKeyExchange::ecdhe(keys.public_key()),
+            )?,
+        ));
+    }
+
+    // a known group, so parameters are valid
+    let params = DhParams::ffdhe(NamedGroup::ffdhe2048).unwrap();
+    let keys = DhKeyPair::generate(&params).unwrap();
+    seeds.push(Seed::new(
+        "server_key_exchange_dhe",
+        handshake_record(
+            HandshakeType::server_key_exchange,
+            ServerKeyExchangeDhe {
+                params: ServerDhParams::new(&params, keys.public_key()),
+                signature_algorithm: SignatureScheme::rsa_pss_rsae_sha256,
+                signature: VariableLengthVector::from_slice(&[0x5A; 256]),
+            },
+        )?,
+    ));
+    seeds.push(Seed::new(
+        "client_key_exchange_dhe",
+        handshake_record(
+            HandshakeType::client_key_exchange,
+            ClientKeyExchange::dhe(&keys),
+        )?,
+    ));
+
+    // any odd modulus gives a well-formed message
+    let key = RsaPublicKey {
+        n: vec![0xFF; 256],
+        e: vec![0x01, 0x00, 0x01],
+    };
+    let message = ClientKeyExchange::rsa(&key, &pre_master_secret(TLS12)).unwrap();
+    seeds.push(Seed::new(
+        "client_key_exchange_rsa",
+        handshake_record(HandshakeType::client_key_exchange, message)?,
+    ));
+    seeds.push(Seed::new(
+        "new_session_ticket",
+        handshake_record(
+            HandshakeType::new_session_ticket,
+            NewSessionTicket::new(7200, &[0xAB; 48]),
+        )?,
+    ));
+
+    for description in [
+        AlertDescription::handshake_failure,
+        AlertDescription::protocol_version,
+        AlertDescription::unrecognized_name,
+    ] {
+        seeds.push(Seed::new(
+            &format!("alert_{:?}", description),
+            fatal_alert(description)?,
+        ));
+    }
+    seeds.push(Seed::new("alert_close_notify", close_notify()?));
+    seeds.push(Seed::new("change_cipher_spec", change_cipher_spec()?));
+
+    let mut heartbeat = HeartbeatRecord {
+        header: RecordHeader {
+            content_type: ContentType::heartbeat,
+            version: TLS12,
+            length: 0,
+        },
+        data: HeartbeatMessage::new(HeartbeatMessageType::heartbeat_request, b"corpus"),
+    };
+    heartbeat.set_length();
+    let mut bytes = Vec::new();
+    heartbeat.to_network_bytes(&mut bytes)?;
+    seeds.push(Seed::new("heartbeat_request", bytes));
+
+    Ok(seeds)
+}
+
+// write each seed to its own file in the directory, created if needed
+pub fn write_corpus(directory: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(directory)?;
+
+    seeds()?
+        .into_iter()
+        .map(|seed| {
+            let path = directory.join(&seed.name);
+            std::fs::write(&path, &seed.bytes)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::{handshake_messages, records};
+
+    #[test]
+    fn seeds_parse() {
+        let seeds = seeds().unwrap();
+        let mut names: Vec<_> = seeds.iter().map(|x| x.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), seeds.len());
+
+        // every seed is made of whole records, and hellos parse back
+        for seed in &seeds {
+            let parsed = records(&seed.bytes);
+            assert!(!parsed.is_empty(), "{}", seed.name);
+            assert_eq!(
+                parsed.iter().map(|x| 5 + x.fragment.len()).sum::<usize>(),
+                seed.bytes.len(),
+                "{}",
+                seed.name
+            );
+
+            if seed.name.starts_with("client_hello") {
+                let messages = handshake_messages(&parsed);
+                assert!(
+                    ClientHelloSummary::parse(&messages[0].body).is_ok(),
+                    "{}",
+                    seed.name
+                );
+            }
+        }
+
+        let directory = std::env::temp_dir().join(format!("corpus-{}", std::process::id()));
+        let paths = write_corpus(&directory).unwrap();
+        assert_eq!(paths.len(), seeds.len());
+        assert!(directory
+            .join("client_hello_tls13_sni_alpn_grease")
+            .exists());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+// the AES block cipher of https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197-upd1.pdf, for the
+// CBC suites. Table based, so not constant time: it's for exploring servers, not for secrets
+// that matter
+
+const BLOCK_LENGTH: usize = 16;
+
+// multiplication by x in GF(2^8), modulo x^8 + x^4 + x^3 + x + 1
+const fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if x & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+const fn gmul(mut x: u8, mut y: u8) -> u8 {
+    let mut product = 0;
+    while y != 0 {
+        if y & 1 != 0 {
+            product ^= x;
+        }
+        x = xtime(x);
+        y >>= 1;
+    }
+    product
+}
+
+// p walks the multiplicative group with generator 3, and q the inverses with 3^-1, each
+// inverse then goes through the affine transformation: FIPS 197 section 5.1.1
+const fn sbox() -> [u8; 256] {
+    let mut sbox = [0x63; 256];
+    let (mut p, mut q) = (1u8, 1u8);
+    loop {
+        p ^= xtime(p);
+        q ^= q << 1;
+        q ^= q << 2;
+        q ^= q << 4;
+        if q & 0x80 != 0 {
+            q ^= 0x09;
+        }
+        sbox[p as usize] =
+            q ^ q.rotate_left(1) ^ q.rotate_left(2) ^ q.rotate_left(3) ^ q.rotate_left(4) ^ 0x63;
+        if p == 1 {
+            break;
+        }
+    }
+    sbox
+}
+
+const fn inverse(sbox: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        inverse[sbox[i] as usize] = i as u8;
+        i += 1;
+    }
+    inverse
+}
+
+const SBOX: [u8; 256] = sbox();
+const INV_SBOX: [u8; 256] = inverse(&SBOX);
+
+// the state is kept as the 16 input bytes, column after column
+fn shift_rows(state: &mut [u8; BLOCK_LENGTH]) {
+    let old = *state;
+    for row in 1..4 {
+        for column in 0..4 {
+            state[row + 4 * column] = old[row + 4 * ((column + row) % 4)];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; BLOCK_LENGTH]) {
+    let old = *state;
+    for row in 1..4 {
+        for column in 0..4 {
+            state[row + 4 * ((column + row) % 4)] = old[row + 4 * column];
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8; BLOCK_LENGTH], factors: [u8; 4]) {
+    for column in state.chunks_mut(4) {
+        let old = [column[0], column[1], column[2], column[3]];
+        for (row, x) in column.iter_mut().enumerate() {
+            *x = (0..4).fold(0, |acc, i| acc ^ gmul(old[(row + i) % 4], factors[i]));
+        }
+    }
+}
+
+fn add_round_key(state: &mut [u8; BLOCK_LENGTH], key: &[u8; BLOCK_LENGTH]) {
+    state.iter_mut().zip(key).for_each(|(x, k)| *x ^= k);
+}
+
+#[derive(Clone)]
+pub struct Aes {
+    // one more than the number of rounds
+    round_keys: Vec<[u8; BLOCK_LENGTH]>,
+}
+
+// the round keys are not shown
+impl std::fmt::Debug for Aes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Aes")
+            .field("rounds", &(self.round_keys.len() - 1))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Aes {
+    pub const BLOCK_LENGTH: usize = BLOCK_LENGTH;
+
+    // None unless the key is 16, 24 or 32 bytes long. Key expansion: FIPS 197 section 5.2
+    pub fn new(key: &[u8]) -> Option<Self> {
+        if ![16, 24, 32].contains(&key.len()) {
+            return None;
+        }
+        let nk = key.len() / 4;
+        let rounds = nk + 6;
+
+        let mut words: Vec<[u8; 4]> = key.chunks(4).map(|x| [x[0], x[1], x[2], x[3]]).collect();
+        let mut rcon = 1u8;
+        for i in nk..4 * (rounds + 1) {
+            let mut word = words[i - 1];
+            if i % nk == 0 {
+                word.rotate_left(1);
+                word = word.map(|x| SBOX[x as usize]);
+                word[0] ^= rcon;
+                rcon = xtime(rcon);
+            } else if nk > 6 && i % nk == 4 {
+                word = word.map(|x| SBOX[x as usize]);
+            }
+            let previous = words[i - nk];
+            words.push([0, 1, 2, 3].map(|j| word[j] ^ previous[j]));
+        }
+
+        let round_keys = words
+            .chunks(4)
+            .map(|x| {
+                let mut key = [0; BLOCK_LENGTH];
+                for (i, word) in x.iter().enumerate() {
+                    key[4 * i..4 * i + 4].copy_from_slice(word);
+                }
+                key
+            })
+            .collect();
+        Some(Self { round_keys })
+    }
+
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_LENGTH]) {
+        let last = self.round_keys.len() - 1;
+        add_round_key(block, &self.round_keys[0]);
+        for (round, key) in self.round_keys.iter().enumerate().skip(1) {
+            block.iter_mut().for_each(|x| *x = SBOX[*x as usize]);
+            shift_rows(block);
+            if round != last {
+                mix_columns(block, [2, 3, 1, 1]);
+            }
+            add_round_key(block, key);
+        }
+    }
+
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_LENGTH]) {
+        let last = self.round_keys.len() - 1;
+        for (round, key) in self.round_keys.iter().enumerate().rev() {
+            add_round_key(block, key);
+            if round == 0 {
+                break;
+            }
+            if round != last {
+                mix_columns(block, [14, 11, 13, 9]);
+            }
+            inv_shift_rows(block);
+            block.iter_mut().for_each(|x| *x = INV_SBOX[*x as usize]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    // https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197-upd1.pdf, appendix C
+    #[test]
+    fn fips197_vectors() {
+        assert_eq!(SBOX[0x00], 0x63);
+        assert_eq!(SBOX[0x53], 0xed);
+        assert_eq!(INV_SBOX[0xed], 0x53);
+
+        let plaintext: [u8; 16] = std::array::from_fn(|i| (i as u8) * 0x11);
+        for (length, expected) in [
+            (16, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            (24, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            (32, "8ea2b7ca516745bfeafc49904b496089"),
+        ] {
+            let key: Vec<u8> = (0..length as u8).collect();
+            let aes = Aes::new(&key).unwrap();
+
+            let mut block = plaintext;
+            aes.encrypt_block(&mut block);
+            assert_eq!(to_hex(&block), expected);
+            aes.decrypt_block(&mut block);
+            assert_eq!(block, plaintext);
+        }
+
+        assert!(Aes::new(&[0; 20]).is_none());
+        assert!(!format!("{:?}", Aes::new(&[0; 16]).unwrap()).contains("round_keys"));
+    }
+}
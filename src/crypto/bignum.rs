@@ -0,0 +1,148 @@
+// big integers as vectors of 64-bit limbs, with the modular exponentiation of finite field
+// Diffie-Hellman and RSA. It uses Montgomery multiplication, the modulus being odd
+use std::cmp::Ordering;
+
+// big-endian bytes of constants written as lines of hex
+pub(crate) fn from_hex(lines: &[&str]) -> Vec<u8> {
+    let hex = lines.concat();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// big integers of a given number of limbs, least significant first
+pub(crate) fn from_be_bytes(bytes: &[u8], limbs: usize) -> Vec<u64> {
+    let mut n = vec![0u64; limbs];
+    for (i, byte) in bytes.iter().rev().enumerate() {
+        n[i / 8] |= (*byte as u64) << (8 * (i % 8));
+    }
+    n
+}
+
+// big-endian, left-padded to length bytes
+pub(crate) fn to_be_bytes(n: &[u64], length: usize) -> Vec<u8> {
+    let bytes: Vec<u8> = n.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect();
+    bytes[bytes.len() - length..].to_vec()
+}
+
+pub(crate) fn compare(a: &[u64], b: &[u64]) -> Ordering {
+    a.iter().rev().cmp(b.iter().rev())
+}
+
+// leading zeros removed
+pub(crate) fn strip(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|x| *x != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+// arithmetic modulo an odd p
+pub(crate) struct Modulus {
+    pub(crate) p: Vec<u64>,
+
+    // -p^-1 mod 2^64
+    p_inv: u64,
+
+    // R^2 mod p, with R = 2^(64 * limbs)
+    r2: Vec<u64>,
+}
+
+impl Modulus {
+    pub(crate) fn new(p: Vec<u64>) -> Self {
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p[0].wrapping_mul(inv)));
+        }
+
+        let mut modulus = Self {
+            r2: vec![0; p.len()],
+            p_inv: inv.wrapping_neg(),
+            p,
+        };
+        let mut r2 = vec![0u64; modulus.p.len()];
+        r2[0] = 1;
+        for _ in 0..128 * modulus.p.len() {
+            r2 = modulus.double(&r2);
+        }
+        modulus.r2 = r2;
+        modulus
+    }
+
+    // 2a mod p, for a below p
+    fn double(&self, a: &[u64]) -> Vec<u64> {
+        let mut n: Vec<u64> = Vec::with_capacity(a.len());
+        let mut carry = 0;
+        for limb in a {
+            n.push((limb << 1) | carry);
+            carry = limb >> 63;
+        }
+        if carry == 1 || compare(&n, &self.p) != Ordering::Less {
+            self.sub_p(&mut n);
+        }
+        n
+    }
+
+    // n - p, wrapping
+    fn sub_p(&self, n: &mut [u64]) {
+        let mut borrow = false;
+        for (limb, p) in n.iter_mut().zip(&self.p) {
+            let (d, b1) = limb.overflowing_sub(*p);
+            let (d, b2) = d.overflowing_sub(borrow as u64);
+            *limb = d;
+            borrow = b1 || b2;
+        }
+    }
+
+    // a * b / R mod p
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.p.len();
+        let mut t = vec![0u64; n + 2];
+        for bi in b {
+            let mut carry = 0u128;
+            for j in 0..n {
+                let s = t[j] as u128 + a[j] as u128 * *bi as u128 + carry;
+                t[j] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[n] as u128 + carry;
+            t[n] = s as u64;
+            t[n + 1] = (s >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.p_inv);
+            let mut carry = (t[0] as u128 + m as u128 * self.p[0] as u128) >> 64;
+            for j in 1..n {
+                let s = t[j] as u128 + m as u128 * self.p[j] as u128 + carry;
+                t[j - 1] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[n] as u128 + carry;
+            t[n - 1] = s as u64;
+            t[n] = t[n + 1] + (s >> 64) as u64;
+        }
+
+        let high = t[n];
+        t.truncate(n);
+        if high != 0 || compare(&t, &self.p) != Ordering::Less {
+            self.sub_p(&mut t);
+        }
+        t
+    }
+
+    // base^exponent mod p, the exponent being big-endian bytes
+    pub(crate) fn pow(&self, base: &[u64], exponent: &[u8]) -> Vec<u64> {
+        let mut one = vec![0u64; self.p.len()];
+        one[0] = 1;
+        let base = self.mul(base, &self.r2);
+        let mut n = self.mul(&one, &self.r2);
+
+        for byte in exponent {
+            for i in (0..8).rev() {
+                n = self.mul(&n, &n);
+                if (byte >> i) & 1 == 1 {
+                    n = self.mul(&n, &base);
+                }
+            }
+        }
+        self.mul(&n, &one)
+    }
+}
@@ -0,0 +1,331 @@
+// record protection of the legacy CBC suites: MAC-then-encrypt with AES-CBC, an explicit IV in
+// each record, and padding up to a whole block: https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.3.2.
+// TLS 1.0, whose IV is the last block of the previous record, is not supported
+use crate::alert::alert::AlertDescription;
+use crate::crypto::aes::Aes;
+use crate::crypto::hmac::{hmac, hmac_sha1};
+use crate::crypto::key_block::SuiteParameters;
+use crate::crypto::sha2::HashAlgorithm;
+use crate::handshake::common::{cipher_suite_name, CipherSuite, ContentType, ProtocolVersion};
+use crate::handshake::constants::TLS10;
+
+const BLOCK_LENGTH: usize = Aes::BLOCK_LENGTH;
+
+// the largest plaintext, and the most a ciphertext may add to it:
+// https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.3
+const MAX_PLAINTEXT_LENGTH: usize = 1 << 14;
+const MAX_CIPHERTEXT_LENGTH: usize = MAX_PLAINTEXT_LENGTH + 2048;
+
+// the HMAC of the suite, named by its last part
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MacAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+}
+
+impl MacAlgorithm {
+    pub fn output_len(&self) -> usize {
+        match self {
+            MacAlgorithm::Sha1 => 20,
+            MacAlgorithm::Sha256 => 32,
+            MacAlgorithm::Sha384 => 48,
+        }
+    }
+
+    pub fn mac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            MacAlgorithm::Sha1 => hmac_sha1(key, data),
+            MacAlgorithm::Sha256 => hmac(HashAlgorithm::Sha256, key, data),
+            MacAlgorithm::Sha384 => hmac(HashAlgorithm::Sha384, key, data),
+        }
+    }
+}
+
+// one direction of the connection, with its own keys and sequence number
+#[derive(Clone)]
+pub struct CbcCipher {
+    aes: Aes,
+    mac: MacAlgorithm,
+    mac_key: Vec<u8>,
+    version: ProtocolVersion,
+    sequence_number: u64,
+}
+
+// the keys are not shown
+impl std::fmt::Debug for CbcCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CbcCipher")
+            .field("mac", &self.mac)
+            .field("version", &self.version)
+            .field("sequence_number", &self.sequence_number)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CbcCipher {
+    // None for suites other than the AES CBC ones, for TLS 1.0, or if the keys don't have
+    // the lengths of the suite
+    pub fn new(
+        suite: CipherSuite,
+        version: ProtocolVersion,
+        mac_key: &[u8],
+        key: &[u8],
+    ) -> Option<Self> {
+        let parameters = SuiteParameters::from_suite(suite)?;
+        if !Self::supports(suite) || version <= TLS10 {
+            return None;
+        }
+
+        let mac = match parameters.mac_key_length {
+            20 => MacAlgorithm::Sha1,
+            32 => MacAlgorithm::Sha256,
+            48 => MacAlgorithm::Sha384,
+            _ => return None,
+        };
+        if mac_key.len() != parameters.mac_key_length || key.len() != parameters.enc_key_length {
+            return None;
+        }
+
+        Some(Self {
+            aes: Aes::new(key)?,
+            mac,
+            mac_key: mac_key.to_vec(),
+            version,
+            sequence_number: 0,
+        })
+    }
+
+    // the AES CBC suites, whatever the version
+    pub fn supports(suite: CipherSuite) -> bool {
+        let name = cipher_suite_name(suite);
+        SuiteParameters::from_suite(suite).is_some()
+            && name.contains("_WITH_AES_")
+            && name.contains("_CBC_")
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    // the MAC over the sequence number, the record header and the content
+    fn record_mac(&self, content_type: ContentType, content: &[u8]) -> Vec<u8> {
+        let mut data = self.sequence_number.to_be_bytes().to_vec();
+        data.push(content_type as u8);
+        data.extend_from_slice(&self.version);
+        data.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        data.extend_from_slice(content);
+        self.mac.mac(&self.mac_key, &data)
+    }
+
+    // the fragment of the record carrying the plaintext: the IV, then the encrypted content,
+    // MAC and padding
+    pub fn seal(
+        &mut self,
+        content_type: ContentType,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, AlertDescription> {
+        self.seal_with_iv(content_type, plaintext, rand::random())
+    }
+
+    fn seal_with_iv(
+        &mut self,
+        content_type: ContentType,
+        plaintext: &[u8],
+        iv: [u8; BLOCK_LENGTH],
+    ) -> Result<Vec<u8>, AlertDescription> {
+        if plaintext.len() > MAX_PLAINTEXT_LENGTH {
+            return Err(AlertDescription::record_overflow);
+        }
+
+        let mut data = plaintext.to_vec();
+        data.extend_from_slice(&self.record_mac(content_type, plaintext));
+
+        // the smallest padding, each byte set to its length
+        let padding_length = BLOCK_LENGTH - 1 - data.len() % BLOCK_LENGTH;
+        data.resize(data.len() + padding_length + 1, padding_length as u8);
+
+        let mut fragment = iv.to_vec();
+        let mut previous = iv;
+        for chunk in data.chunks(BLOCK_LENGTH) {
+            let mut block = previous;
+            block.iter_mut().zip(chunk).for_each(|(x, y)| *x ^= y);
+            self.aes.encrypt_block(&mut block);
+            fragment.extend_from_slice(&block);
+            previous = block;
+        }
+
+        self.sequence_number += 1;
+        Ok(fragment)
+    }
+
+    // the plaintext of the fragment. A bad padding or MAC are the same bad_record_mac, and
+    // both are checked whatever the other gives, so their timings are close:
+    // https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.3.2
+    pub fn open(
+        &mut self,
+        content_type: ContentType,
+        fragment: &[u8],
+    ) -> Result<Vec<u8>, AlertDescription> {
+        if fragment.len() > MAX_CIPHERTEXT_LENGTH {
+            return Err(AlertDescription::record_overflow);
+        }
+
+        // the IV, and enough blocks for the MAC and a padding length
+        let mac_length = self.mac.output_len();
+        let minimum = BLOCK_LENGTH + (mac_length + 1).div_ceil(BLOCK_LENGTH) * BLOCK_LENGTH;
+        if fragment.len() < minimum || !fragment.len().is_multiple_of(BLOCK_LENGTH) {
+            return Err(AlertDescription::bad_record_mac);
+        }
+
+        let mut data = Vec::with_capacity(fragment.len() - BLOCK_LENGTH);
+        let mut blocks = fragment.chunks(BLOCK_LENGTH).map(|x| {
+            let mut block = [0; BLOCK_LENGTH];
+            block.copy_from_slice(x);
+            block
+        });
+        // there's at least the IV
+        let mut previous = blocks.next().unwrap();
+        for ciphertext in blocks {
+            let mut block = ciphertext;
+            self.aes.decrypt_block(&mut block);
+            block.iter_mut().zip(previous).for_each(|(x, y)| *x ^= y);
+            data.extend_from_slice(&block);
+            previous = ciphertext;
+        }
+
+        // all of the last 256 bytes are looked at, whatever the padding length
+        let length = data.len();
+        let padding_length = data[length - 1] as usize;
+        let mut bad = u8::from(padding_length + 1 + mac_length > length);
+        for (i, byte) in data.iter().rev().take(256).enumerate() {
+            bad |= u8::from(i <= padding_length) & u8::from(*byte != padding_length as u8);
+        }
+
+        // a bad padding is taken as empty, so the MAC is still computed
+        let padding_length = if bad == 0 { padding_length } else { 0 };
+        let content_length = length - padding_length - 1 - mac_length;
+        let (content, rest) = data.split_at(content_length);
+
+        let expected = self.record_mac(content_type, content);
+        bad |= expected
+            .iter()
+            .zip(&rest[..mac_length])
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y));
+        if bad != 0 {
+            return Err(AlertDescription::bad_record_mac);
+        }
+        if content_length > MAX_PLAINTEXT_LENGTH {
+            return Err(AlertDescription::record_overflow);
+        }
+
+        self.sequence_number += 1;
+        Ok(content.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::key_block::KeyBlock;
+    use crate::crypto::to_hex;
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn seal_open() {
+        let suite = TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA;
+        let mut client = CbcCipher::new(suite, TLS12, &[0x01; 20], &[0x02; 16]).unwrap();
+        let mut server = client.clone();
+
+        // checked with Python's hmac and cryptography packages
+        let fragment = client
+            .seal_with_iv(ContentType::application_data, b"hello", [0x03; 16])
+            .unwrap();
+        assert_eq!(
+            to_hex(&fragment),
+            "03030303030303030303030303030303e58d1996116483869be036cf1e1e1dac\
+             1bc3d1c12c4267365d8ba33ceef85e70"
+        );
+        assert_eq!(client.sequence_number(), 1);
+
+        assert_eq!(
+            server.open(ContentType::application_data, &fragment),
+            Ok(b"hello".to_vec())
+        );
+
+        // the sequence number and content type are part of the MAC
+        let fragment = client.seal(ContentType::handshake, &[0xAB; 100]).unwrap();
+        assert_eq!(fragment.len(), 16 + 128);
+        assert_eq!(
+            server
+                .clone()
+                .open(ContentType::application_data, &fragment),
+            Err(AlertDescription::bad_record_mac)
+        );
+        assert_eq!(
+            server.open(ContentType::handshake, &fragment),
+            Ok(vec![0xAB; 100])
+        );
+        assert_eq!(
+            server.open(ContentType::handshake, &fragment),
+            Err(AlertDescription::bad_record_mac)
+        );
+    }
+
+    #[test]
+    fn bad_records() {
+        let suite = TLS_RSA_WITH_AES_256_CBC_SHA256;
+        let parameters = SuiteParameters::from_suite(suite).unwrap();
+        let keys = KeyBlock::new(&parameters, &[0x0F; 48], &[0x01; 32], &[0x02; 32]);
+        let mut client =
+            CbcCipher::new(suite, TLS12, &keys.client_mac_key, &keys.client_key).unwrap();
+        let server = CbcCipher::new(suite, TLS12, &keys.client_mac_key, &keys.client_key).unwrap();
+
+        let fragment = client.seal(ContentType::application_data, b"").unwrap();
+        assert_eq!(fragment.len(), 16 + 48);
+        assert!(server
+            .clone()
+            .open(ContentType::application_data, &fragment)
+            .is_ok());
+
+        // any changed byte, a partial block, or too short for the MAC
+        for i in 0..fragment.len() {
+            let mut changed = fragment.clone();
+            changed[i] ^= 0x01;
+            assert_eq!(
+                server.clone().open(ContentType::application_data, &changed),
+                Err(AlertDescription::bad_record_mac)
+            );
+        }
+        for length in [0, 32, 48, 63] {
+            assert_eq!(
+                server
+                    .clone()
+                    .open(ContentType::application_data, &fragment[..length]),
+                Err(AlertDescription::bad_record_mac)
+            );
+        }
+
+        assert_eq!(
+            client.seal(ContentType::application_data, &[0; (1 << 14) + 1]),
+            Err(AlertDescription::record_overflow)
+        );
+        assert_eq!(
+            server
+                .clone()
+                .open(ContentType::application_data, &[0; (1 << 14) + 2064]),
+            Err(AlertDescription::record_overflow)
+        );
+
+        // not an AES CBC suite, TLS 1.0, or a key of the wrong length
+        assert!(CbcCipher::supports(suite));
+        assert!(!CbcCipher::supports(TLS_RSA_WITH_3DES_EDE_CBC_SHA));
+        assert!(CbcCipher::new(TLS_RSA_WITH_3DES_EDE_CBC_SHA, TLS12, &[0; 20], &[0; 24]).is_none());
+        assert!(
+            CbcCipher::new(TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256, TLS12, &[], &[0; 16]).is_none()
+        );
+        assert!(CbcCipher::new(suite, TLS10, &[0; 32], &[0; 32]).is_none());
+        assert!(CbcCipher::new(suite, TLS12, &[0; 32], &[0; 16]).is_none());
+        assert!(!format!("{:?}", server).contains("key"));
+    }
+}
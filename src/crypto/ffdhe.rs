@@ -0,0 +1,265 @@
+// finite field Diffie-Hellman, with the groups of https://datatracker.ietf.org/doc/html/rfc7919
+// or the parameters sent in the ServerKeyExchange of classic DHE suites
+use std::cmp::Ordering;
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::bignum::{compare, from_be_bytes, from_hex, strip, to_be_bytes, Modulus};
+use crate::handshake::client_hello::{KeyShareEntry, NamedGroup};
+
+// https://datatracker.ietf.org/doc/html/rfc7919#appendix-A.1
+const FFDHE2048_P: [&str; 8] = [
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B423861285C97FFFFFFFFFFFFFFFF",
+];
+
+// https://datatracker.ietf.org/doc/html/rfc7919#appendix-A.2
+const FFDHE3072_P: [&str; 12] = [
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035B",
+    "BC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91C",
+    "AEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF",
+    "5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E",
+    "0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B66C62E37FFFFFFFFFFFFFFFF",
+];
+
+// private exponents are 512 bits, more than the 275 bits needed with ffdhe3072:
+// https://datatracker.ietf.org/doc/html/rfc7919#section-5.2
+const PRIVATE_LENGTH: usize = 64;
+
+// the prime p and generator g, big-endian
+#[derive(Debug, Clone, PartialEq)]
+pub struct DhParams {
+    pub p: Vec<u8>,
+    pub g: Vec<u8>,
+}
+
+impl DhParams {
+    // None for groups other than ffdhe2048 and ffdhe3072
+    pub fn ffdhe(group: NamedGroup) -> Option<Self> {
+        let p = match group {
+            NamedGroup::ffdhe2048 => from_hex(&FFDHE2048_P),
+            NamedGroup::ffdhe3072 => from_hex(&FFDHE3072_P),
+            _ => return None,
+        };
+        Some(Self { p, g: vec![2] })
+    }
+
+    // the RFC 7919 group of these parameters, if they're one of them
+    pub fn named_group(&self) -> Option<NamedGroup> {
+        [NamedGroup::ffdhe2048, NamedGroup::ffdhe3072]
+            .into_iter()
+            .find(|group| Self::ffdhe(*group).as_ref() == Some(self))
+    }
+
+    // the size of p, to judge parameters sent by a server
+    pub fn bits(&self) -> usize {
+        let p = strip(&self.p);
+        p.first()
+            .map_or(0, |x| 8 * p.len() - x.leading_zeros() as usize)
+    }
+
+    fn limbs(&self) -> usize {
+        strip(&self.p).len().div_ceil(8)
+    }
+
+    // the length of p, and of the public values and secrets
+    fn len(&self) -> usize {
+        strip(&self.p).len()
+    }
+
+    // an odd p, and 1 < g < p - 1
+    fn modulus(&self) -> Result<Modulus, AlertDescription> {
+        if self.bits() < 3 || strip(&self.g).len() > self.len() {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        let p = from_be_bytes(strip(&self.p), self.limbs());
+        if p[0] & 1 == 0 {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        let modulus = Modulus::new(p);
+        check_range(&modulus, &from_be_bytes(strip(&self.g), self.limbs()))?;
+        Ok(modulus)
+    }
+}
+
+// 1 < y < p - 1, ruling out the values of order 1 and 2:
+// https://datatracker.ietf.org/doc/html/rfc7919#section-5.1
+fn check_range(modulus: &Modulus, y: &[u64]) -> Result<(), AlertDescription> {
+    let mut p_minus_1 = modulus.p.clone();
+    p_minus_1[0] -= 1;
+
+    let mut one = vec![0u64; y.len()];
+    one[0] = 1;
+    if compare(y, &one) == Ordering::Greater && compare(y, &p_minus_1) == Ordering::Less {
+        Ok(())
+    } else {
+        Err(AlertDescription::illegal_parameter)
+    }
+}
+
+#[derive(Clone)]
+pub struct DhKeyPair {
+    params: DhParams,
+    private: Vec<u8>,
+
+    // g^private mod p, as long as p
+    public: Vec<u8>,
+}
+
+// the private key is not shown
+impl std::fmt::Debug for DhKeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DhKeyPair")
+            .field("group", &self.params.named_group())
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DhKeyPair {
+    // fails if the parameters are invalid
+    pub fn generate(params: &DhParams) -> Result<Self, AlertDescription> {
+        let mut private: Vec<u8> = (0..PRIVATE_LENGTH).map(|_| rand::random()).collect();
+        private[0] |= 0x80;
+        Self::from_private(params, &private)
+    }
+
+    pub fn from_private(params: &DhParams, private: &[u8]) -> Result<Self, AlertDescription> {
+        let modulus = params.modulus()?;
+        let g = from_be_bytes(strip(&params.g), params.limbs());
+        let public = to_be_bytes(&modulus.pow(&g, private), params.len());
+
+        Ok(Self {
+            params: params.clone(),
+            private: private.to_vec(),
+            public,
+        })
+    }
+
+    pub fn params(&self) -> &DhParams {
+        &self.params
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    // the shared secret with the peer's public value, left-padded to the length of p as in
+    // TLS 1.3: https://datatracker.ietf.org/doc/html/rfc8446#section-7.4.1
+    pub fn agree(&self, peer: &[u8]) -> Result<Vec<u8>, AlertDescription> {
+        let peer = strip(peer);
+        if peer.len() > self.params.len() {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        let modulus = self.params.modulus()?;
+        let y = from_be_bytes(peer, self.params.limbs());
+        check_range(&modulus, &y)?;
+
+        let shared = modulus.pow(&y, &self.private);
+        check_range(&modulus, &shared)?;
+        Ok(to_be_bytes(&shared, self.params.len()))
+    }
+
+    // the secret of a TLS 1.2 DHE suite, whose leading zeros are removed:
+    // https://datatracker.ietf.org/doc/html/rfc5246#section-8.1.2
+    pub fn pre_master_secret(&self, peer: &[u8]) -> Result<Vec<u8>, AlertDescription> {
+        self.agree(peer).map(|x| strip(&x).to_vec())
+    }
+
+    // the share to offer in a ClientHello, None if the parameters aren't an RFC 7919 group
+    pub fn key_share(&self) -> Option<KeyShareEntry> {
+        let group = self.params.named_group()?;
+        Some(KeyShareEntry::new(group, &self.public))
+    }
+
+    // the secret with the server's share of a ServerHello
+    pub fn agree_key_share(&self, share: &KeyShareEntry) -> Result<Vec<u8>, AlertDescription> {
+        if Some(share.group) != self.params.named_group() {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        self.agree(&share.key_exchange.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffdhe() {
+        let params = DhParams::ffdhe(NamedGroup::ffdhe2048).unwrap();
+        assert_eq!(params.bits(), 2048);
+        assert_eq!(params.named_group(), Some(NamedGroup::ffdhe2048));
+        assert_eq!(DhParams::ffdhe(NamedGroup::ffdhe3072).unwrap().bits(), 3072);
+        assert!(DhParams::ffdhe(NamedGroup::x25519).is_none());
+
+        // 2^2 and 2^8 mod p
+        let keys = DhKeyPair::from_private(&params, &[2]).unwrap();
+        assert_eq!(strip(keys.public_key()), &[4]);
+        assert_eq!(keys.public_key().len(), 256);
+        let shared = DhKeyPair::from_private(&params, &[4])
+            .unwrap()
+            .agree(keys.public_key())
+            .unwrap();
+        assert_eq!(strip(&shared), &[1, 0]);
+
+        let alice = DhKeyPair::generate(&params).unwrap();
+        let bob = DhKeyPair::generate(&params).unwrap();
+        let shared = alice.agree_key_share(&bob.key_share().unwrap()).unwrap();
+        assert_eq!(bob.agree(alice.public_key()), Ok(shared.clone()));
+        assert_eq!(
+            alice.pre_master_secret(bob.public_key()).unwrap(),
+            strip(&shared)
+        );
+        assert!(!format!("{:?}", alice).contains("private"));
+
+        // 1 and p - 1, and a value above p
+        let mut p_minus_1 = params.p.clone();
+        p_minus_1[255] -= 1;
+        for peer in [&[1][..], &p_minus_1, &[0xFF; 257]] {
+            assert_eq!(alice.agree(peer), Err(AlertDescription::illegal_parameter));
+        }
+    }
+
+    #[test]
+    fn custom_params() {
+        // p = 23, g = 5: 5^6 = 8 and 5^15 = 19 mod 23, 19^6 = 8^15 = 2 mod 23
+        let params = DhParams {
+            p: vec![23],
+            g: vec![5],
+        };
+        assert_eq!(params.named_group(), None);
+        let alice = DhKeyPair::from_private(&params, &[6]).unwrap();
+        let bob = DhKeyPair::from_private(&params, &[15]).unwrap();
+        assert_eq!(alice.public_key(), &[8]);
+        assert_eq!(bob.public_key(), &[19]);
+        assert_eq!(alice.agree(bob.public_key()), Ok(vec![2]));
+        assert!(alice.key_share().is_none());
+
+        // an even p, and g of order 2
+        let even = DhParams {
+            p: vec![24],
+            g: vec![5],
+        };
+        assert!(DhKeyPair::generate(&even).is_err());
+        let order_2 = DhParams {
+            p: vec![23],
+            g: vec![22],
+        };
+        assert!(DhKeyPair::generate(&order_2).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// HKDF as defined in https://datatracker.ietf.org/doc/html/rfc5869, and the labeled forms
+// used by the TLS 1.3 key schedule: https://datatracker.ietf.org/doc/html/rfc8446#section-7.1
+use crate::crypto::hmac::hmac;
+use crate::crypto::sha2::HashAlgorithm;
+
+// an empty salt is the same as a string of zeros of the hash length, HMAC padding keys
+// with zeros
+pub fn hkdf_extract(hash: HashAlgorithm, salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac(hash, salt, ikm)
+}
+
+// length is at most 255 times the hash length
+pub fn hkdf_expand(hash: HashAlgorithm, prk: &[u8], info: &[u8], length: usize) -> Vec<u8> {
+    assert!(length <= 255 * hash.output_len(), "HKDF output too long");
+
+    let mut okm = Vec::with_capacity(length);
+    let mut block = Vec::new();
+    let mut counter = 1u8;
+    while okm.len() < length {
+        let mut input = block;
+        input.extend_from_slice(info);
+        input.push(counter);
+        block = hmac(hash, prk, &input);
+        okm.extend_from_slice(&block);
+        counter = counter.wrapping_add(1);
+    }
+    okm.truncate(length);
+    okm
+}
+
+// the label is given without its "tls13 " prefix
+pub fn hkdf_expand_label(
+    hash: HashAlgorithm,
+    secret: &[u8],
+    label: &str,
+    context: &[u8],
+    length: usize,
+) -> Vec<u8> {
+    let label = format!("tls13 {}", label);
+
+    // HkdfLabel: uint16 length, opaque label<7..255>, opaque context<0..255>
+    let mut info = (length as u16).to_be_bytes().to_vec();
+    info.push(label.len() as u8);
+    info.extend_from_slice(label.as_bytes());
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+
+    hkdf_expand(hash, secret, &info, length)
+}
+
+// messages is the concatenation of the handshake messages hashed for the transcript
+pub fn derive_secret(hash: HashAlgorithm, secret: &[u8], label: &str, messages: &[u8]) -> Vec<u8> {
+    hkdf_expand_label(hash, secret, label, &hash.hash(messages), hash.output_len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    // https://datatracker.ietf.org/doc/html/rfc5869#appendix-A.1
+    #[test]
+    fn rfc5869_vectors() {
+        let salt: Vec<u8> = (0..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        let prk = hkdf_extract(HashAlgorithm::Sha256, &salt, &[0x0b; 22]);
+        assert_eq!(
+            to_hex(&prk),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+        assert_eq!(
+            to_hex(&hkdf_expand(HashAlgorithm::Sha256, &prk, &info, 42)),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    // the first secrets of https://datatracker.ietf.org/doc/html/rfc8448#section-3, without
+    // a PSK
+    #[test]
+    fn rfc8448_early_secret() {
+        let hash = HashAlgorithm::Sha256;
+        let early_secret = hkdf_extract(hash, &[], &[0; 32]);
+        assert_eq!(
+            to_hex(&early_secret),
+            "33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a"
+        );
+        assert_eq!(
+            to_hex(&derive_secret(hash, &early_secret, "derived", &[])),
+            "6f2615a108c702c5678f54fc9dbab69716c076189c48250cebeac3576c3611ba"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+// HMAC as defined in https://datatracker.ietf.org/doc/html/rfc2104
+use crate::crypto::sha1::sha1;
+use crate::crypto::sha2::HashAlgorithm;
+
+impl HashAlgorithm {
+    // size of the blocks processed by the compression function
+    pub fn block_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 64,
+            HashAlgorithm::Sha384 => 128,
+        }
+    }
+}
+
+/// ```
+/// use tls_explore::crypto::{hmac::hmac, sha2::HashAlgorithm, to_hex};
+///
+/// assert_eq!(
+///     to_hex(&hmac(HashAlgorithm::Sha256, b"Jefe", b"what do ya want for nothing?")),
+///     "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+/// );
+/// ```
+pub fn hmac(hash: HashAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_with(hash.block_len(), |x| hash.hash(x), key, data)
+}
+
+// HMAC-SHA1, for the MAC of the legacy CBC suites
+pub fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac_with(64, |x| sha1(x).to_vec(), key, data)
+}
+
+fn hmac_with(
+    block_len: usize,
+    hash: impl Fn(&[u8]) -> Vec<u8>,
+    key: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    // keys longer than a block are hashed first, then padded with zeros
+    let mut block = if key.len() > block_len {
+        hash(key)
+    } else {
+        key.to_vec()
+    };
+    block.resize(block_len, 0);
+
+    let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(data);
+
+    let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&hash(&inner));
+
+    hash(&outer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    // https://datatracker.ietf.org/doc/html/rfc4231#section-4
+    #[test]
+    fn rfc4231_vectors() {
+        assert_eq!(
+            to_hex(&hmac(HashAlgorithm::Sha256, &[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            to_hex(&hmac(HashAlgorithm::Sha384, &[0x0b; 20], b"Hi There")),
+            "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6"
+        );
+
+        // key longer than the block size
+        assert_eq!(
+            to_hex(&hmac(
+                HashAlgorithm::Sha256,
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    // https://datatracker.ietf.org/doc/html/rfc2202#section-3
+    #[test]
+    fn rfc2202_vectors() {
+        assert_eq!(
+            to_hex(&hmac_sha1(&[0x0b; 20], b"Hi There")),
+            "b617318655057264e28bc0b6fb378c8ef146be00"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha1(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+}
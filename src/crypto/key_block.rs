@@ -0,0 +1,221 @@
+// the keys of the TLS 1.2 record layer, cut from the key block expanded from the master
+// secret: https://datatracker.ietf.org/doc/html/rfc5246#section-6.3. TLS 1.0 also takes the
+// CBC IVs from it, with its own PRF, later versions send them in each record
+use crate::crypto::prf::prf;
+use crate::crypto::sha2::HashAlgorithm;
+use crate::handshake::common::{cipher_suite_name, CipherSuite};
+
+// sizes of the keys of a suite, from its name: https://datatracker.ietf.org/doc/html/rfc5246#appendix-C
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuiteParameters {
+    // 0 for AEAD ciphers
+    pub mac_key_length: usize,
+    pub enc_key_length: usize,
+
+    // the implicit part of AEAD nonces: https://datatracker.ietf.org/doc/html/rfc5288#section-3,
+    // and https://datatracker.ietf.org/doc/html/rfc7905#section-2 for ChaCha20-Poly1305
+    pub fixed_iv_length: usize,
+
+    // SHA-384 for the suites which name it, SHA-256 for the others
+    pub prf_hash: HashAlgorithm,
+}
+
+impl SuiteParameters {
+    // None for TLS 1.3 suites, which don't use a key block, and unknown suites
+    pub fn from_suite(suite: CipherSuite) -> Option<Self> {
+        let name = cipher_suite_name(suite);
+        let (bulk, hash) = name.split_once("_WITH_")?.1.rsplit_once('_')?;
+
+        let (enc_key_length, fixed_iv_length, aead) = match bulk {
+            "NULL" => (0, 0, false),
+            "RC4_128" => (16, 0, false),
+            "3DES_EDE_CBC" => (24, 0, false),
+            "AES_128_CBC" => (16, 0, false),
+            "AES_256_CBC" => (32, 0, false),
+            "AES_128_GCM" => (16, 4, true),
+            "AES_256_GCM" => (32, 4, true),
+            "CHACHA20_POLY1305" => (32, 12, true),
+            _ => return None,
+        };
+        let hash_length = match hash {
+            "NULL" => 0,
+            "MD5" => 16,
+            "SHA" => 20,
+            "SHA256" => 32,
+            "SHA384" => 48,
+            _ => return None,
+        };
+
+        Some(Self {
+            mac_key_length: if aead { 0 } else { hash_length },
+            enc_key_length,
+            fixed_iv_length,
+            prf_hash: if hash == "SHA384" {
+                HashAlgorithm::Sha384
+            } else {
+                HashAlgorithm::Sha256
+            },
+        })
+    }
+
+    // the length of the key block
+    pub fn key_block_length(&self) -> usize {
+        2 * (self.mac_key_length + self.enc_key_length + self.fixed_iv_length)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct KeyBlock {
+    pub client_mac_key: Vec<u8>,
+    pub server_mac_key: Vec<u8>,
+    pub client_key: Vec<u8>,
+    pub server_key: Vec<u8>,
+    pub client_iv: Vec<u8>,
+    pub server_iv: Vec<u8>,
+}
+
+// the keys are not shown
+impl std::fmt::Debug for KeyBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyBlock")
+            .field("mac_key_length", &self.client_mac_key.len())
+            .field("key_length", &self.client_key.len())
+            .field("iv_length", &self.client_iv.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyBlock {
+    // the server random comes first in the seed, unlike for the master secret
+    pub fn new(
+        parameters: &SuiteParameters,
+        master_secret: &[u8],
+        client_random: &[u8],
+        server_random: &[u8],
+    ) -> Self {
+        let mut seed = server_random.to_vec();
+        seed.extend_from_slice(client_random);
+        let block = prf(
+            parameters.prf_hash,
+            master_secret,
+            "key expansion",
+            &seed,
+            parameters.key_block_length(),
+        );
+
+        // in this order: MAC keys, encryption keys, then IVs, client first
+        let mut rest = &block[..];
+        let mut take = |length: usize| {
+            let (part, tail) = rest.split_at(length);
+            rest = tail;
+            part.to_vec()
+        };
+        Self {
+            client_mac_key: take(parameters.mac_key_length),
+            server_mac_key: take(parameters.mac_key_length),
+            client_key: take(parameters.enc_key_length),
+            server_key: take(parameters.enc_key_length),
+            client_iv: take(parameters.fixed_iv_length),
+            server_iv: take(parameters.fixed_iv_length),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn suite_parameters() {
+        let parameters = SuiteParameters::from_suite(TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384);
+        assert_eq!(
+            parameters,
+            Some(SuiteParameters {
+                mac_key_length: 0,
+                enc_key_length: 32,
+                fixed_iv_length: 4,
+                prf_hash: HashAlgorithm::Sha384,
+            })
+        );
+
+        let parameters = SuiteParameters::from_suite(TLS_RSA_WITH_3DES_EDE_CBC_SHA).unwrap();
+        assert_eq!(parameters.mac_key_length, 20);
+        assert_eq!(parameters.enc_key_length, 24);
+        assert_eq!(parameters.prf_hash, HashAlgorithm::Sha256);
+        let parameters =
+            SuiteParameters::from_suite(TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256).unwrap();
+        assert_eq!(parameters.key_block_length(), 88);
+        assert_eq!(
+            SuiteParameters::from_suite(TLS_NULL_WITH_NULL_NULL)
+                .unwrap()
+                .key_block_length(),
+            0
+        );
+
+        // every TLS 1.2 suite known
+        for (name, suite) in CIPHER_SUITES {
+            assert_eq!(
+                SuiteParameters::from_suite(suite).is_some(),
+                name.contains("_WITH_"),
+                "{}",
+                name
+            );
+        }
+        assert!(SuiteParameters::from_suite(TLS_AES_128_GCM_SHA256).is_none());
+        assert!(SuiteParameters::from_suite([0x0A, 0x0A]).is_none());
+    }
+
+    #[test]
+    fn key_block() {
+        let master_secret = [0x42; 48];
+        let client_random: Vec<u8> = (0..32).collect();
+        let server_random: Vec<u8> = (32..64).collect();
+        let keys = |suite| {
+            let parameters = SuiteParameters::from_suite(suite).unwrap();
+            KeyBlock::new(&parameters, &master_secret, &client_random, &server_random)
+        };
+
+        let block = keys(TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256);
+        assert!(block.client_mac_key.is_empty());
+        assert_eq!(
+            to_hex(&block.client_key),
+            "146607e842e68403e7cc92d9d32c81c8"
+        );
+        assert_eq!(
+            to_hex(&block.server_key),
+            "5276e982b63e18a1d1e6f7c0f4c39787"
+        );
+        assert_eq!(to_hex(&block.client_iv), "4ed71a18");
+        assert_eq!(to_hex(&block.server_iv), "87af8cb9");
+
+        // the same expansion, cut differently
+        let block = keys(TLS_RSA_WITH_AES_256_CBC_SHA);
+        assert_eq!(
+            to_hex(&block.client_mac_key),
+            "146607e842e68403e7cc92d9d32c81c85276e982"
+        );
+        assert_eq!(
+            to_hex(&block.server_mac_key),
+            "b63e18a1d1e6f7c0f4c397874ed71a1887af8cb9"
+        );
+        assert_eq!(
+            to_hex(&block.client_key),
+            "4301fde7aaac462affff6501ef10c06ae76e268c5bd3acade36e6fafa8a6ec88"
+        );
+        assert_eq!(
+            to_hex(&block.server_key),
+            "56f35455071d98bdeed70962b2fbc1d19ee7f1a8933e11542519977078052668"
+        );
+        assert!(block.client_iv.is_empty());
+
+        let block = keys(TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384);
+        assert_eq!(
+            to_hex(&block.client_key),
+            "ef6cd39fe8d9582b8b57f60eeea8e83849dc8ce225a60645af2d1e52b34937b6"
+        );
+        assert_eq!(to_hex(&block.server_iv), "7097f3a0");
+        assert!(!format!("{:?}", block).contains("server_key"));
+    }
+}
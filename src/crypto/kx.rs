@@ -0,0 +1,153 @@
+// checks of the public values received in a key exchange, before any use: a peer sending a
+// point of small order or off the curve can force or learn about the shared secret. Failures
+// are illegal_parameter: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.8.2
+use crate::alert::alert::AlertDescription;
+use crate::crypto::nist::decode_point;
+use crate::handshake::extensions::supported_groups::NamedGroup;
+
+type Result = std::result::Result<(), AlertDescription>;
+
+// u-coordinates of points of order 2, 4 or 8 besides 0 and 1, little endian. Any scalar
+// multiplied with them gives an all-zero secret
+const X25519_SMALL_ORDER: [[u8; 32]; 3] = [
+    [
+        0xE0, 0xEB, 0x7A, 0x7C, 0x3B, 0x41, 0xB8, 0xAE, 0x16, 0x56, 0xE3, 0xFA, 0xF1, 0x9F, 0xC4,
+        0x6A, 0xDA, 0x09, 0x8D, 0xEB, 0x9C, 0x32, 0xB1, 0xFD, 0x86, 0x62, 0x05, 0x16, 0x5F, 0x49,
+        0xB8, 0x00,
+    ],
+    [
+        0x5F, 0x9C, 0x95, 0xBC, 0xA3, 0x50, 0x8C, 0x24, 0xB1, 0xD0, 0xB1, 0x55, 0x9C, 0x83, 0xEF,
+        0x5B, 0x04, 0x44, 0x5C, 0xC4, 0x58, 0x1C, 0x8E, 0x86, 0xD8, 0x22, 0x4E, 0xDD, 0xD0, 0x9F,
+        0x11, 0x57,
+    ],
+    // p - 1
+    [
+        0xEC, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0x7F,
+    ],
+];
+
+// a 32-byte u-coordinate which isn't of small order. The top bit is ignored and values
+// above p are reduced, as in https://datatracker.ietf.org/doc/html/rfc7748#section-5: below
+// 2^255 only p and p + 1 reduce to a small order value, 0 and 1
+pub fn check_x25519_public(key: &[u8]) -> Result {
+    let mut u: [u8; 32] = key
+        .try_into()
+        .map_err(|_| AlertDescription::illegal_parameter)?;
+    u[31] &= 0x7F;
+
+    // p and p + 1 share all but their low byte with p - 1
+    let reduced = |low: u8| u[0] == low && u[1..] == X25519_SMALL_ORDER[2][1..];
+
+    let small_order = (u[0] <= 1 && u[1..].iter().all(|&b| b == 0))
+        || reduced(0xED)
+        || reduced(0xEE)
+        || X25519_SMALL_ORDER.contains(&u);
+    if small_order {
+        Err(AlertDescription::illegal_parameter)
+    } else {
+        Ok(())
+    }
+}
+
+// an all-zero X25519 output means the peer's value was of small order:
+// https://datatracker.ietf.org/doc/html/rfc7748#section-6.1
+pub fn check_x25519_shared(secret: &[u8]) -> Result {
+    if secret.iter().fold(0, |acc, b| acc | b) == 0 {
+        Err(AlertDescription::illegal_parameter)
+    } else {
+        Ok(())
+    }
+}
+
+// an uncompressed point, the only form allowed by TLS 1.3, whose coordinates are below p and
+// satisfy y^2 = x^3 - 3x + b. The point at infinity has no uncompressed form
+pub fn check_p256_public(key: &[u8]) -> Result {
+    decode_point(NamedGroup::secp256r1, key).map(|_| ())
+}
+
+// the public value of a key share or ServerKeyExchange. Groups other than x25519, secp256r1
+// and secp384r1 aren't checked
+pub fn check_public(group: NamedGroup, key: &[u8]) -> Result {
+    match group {
+        NamedGroup::x25519 => check_x25519_public(key),
+        NamedGroup::secp256r1 => check_p256_public(key),
+        NamedGroup::secp384r1 => decode_point(group, key).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the generator of P-256
+    fn generator() -> Vec<u8> {
+        let mut key = vec![4];
+        key.extend_from_slice(&[
+            0x6B, 0x17, 0xD1, 0xF2, 0xE1, 0x2C, 0x42, 0x47, 0xF8, 0xBC, 0xE6, 0xE5, 0x63, 0xA4,
+            0x40, 0xF2, 0x77, 0x03, 0x7D, 0x81, 0x2D, 0xEB, 0x33, 0xA0, 0xF4, 0xA1, 0x39, 0x45,
+            0xD8, 0x98, 0xC2, 0x96,
+        ]);
+        key.extend_from_slice(&[
+            0x4F, 0xE3, 0x42, 0xE2, 0xFE, 0x1A, 0x7F, 0x9B, 0x8E, 0xE7, 0xEB, 0x4A, 0x7C, 0x0F,
+            0x9E, 0x16, 0x2B, 0xCE, 0x33, 0x57, 0x6B, 0x31, 0x5E, 0xCE, 0xCB, 0xB6, 0x40, 0x68,
+            0x37, 0xBF, 0x51, 0xF5,
+        ]);
+        key
+    }
+
+    #[test]
+    fn p256() {
+        let key = generator();
+        assert_eq!(check_public(NamedGroup::secp256r1, &key), Ok(()));
+
+        let mut off_curve = key.clone();
+        off_curve[64] ^= 1;
+        assert_eq!(
+            check_p256_public(&off_curve),
+            Err(AlertDescription::illegal_parameter)
+        );
+
+        // compressed, and coordinates not reduced
+        let mut compressed = key[..33].to_vec();
+        compressed[0] = 2;
+        assert!(check_p256_public(&compressed).is_err());
+        let mut too_large = key.clone();
+        too_large[1..33].fill(0xFF);
+        assert!(check_p256_public(&too_large).is_err());
+    }
+
+    #[test]
+    fn x25519() {
+        // RFC 7748 public key of Alice
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&[
+            0x85, 0x20, 0xF0, 0x09, 0x89, 0x30, 0xA7, 0x54, 0x74, 0x8B, 0x7D, 0xDC, 0xB4, 0x3E,
+            0xF7, 0x5A, 0x0D, 0xBF, 0x3A, 0x0D, 0x26, 0x38, 0x1A, 0xF4, 0xEB, 0xA4, 0xA9, 0x8E,
+            0xAA, 0x9B, 0x4E, 0x6A,
+        ]);
+        assert_eq!(check_public(NamedGroup::x25519, &key), Ok(()));
+        assert!(check_x25519_public(&key[..31]).is_err());
+
+        for small_order in X25519_SMALL_ORDER {
+            assert!(check_x25519_public(&small_order).is_err());
+        }
+
+        // 0 and 1 with the top bit set, and as p and p + 1
+        let mut zero = [0u8; 32];
+        zero[31] = 0x80;
+        assert!(check_x25519_public(&zero).is_err());
+        let mut above_p = X25519_SMALL_ORDER[2];
+        above_p[0] = 0xED;
+        assert!(check_x25519_public(&above_p).is_err());
+        above_p[0] = 0xEE;
+        assert!(check_x25519_public(&above_p).is_err());
+        above_p[0] = 0xEF;
+        assert!(check_x25519_public(&above_p).is_ok());
+
+        assert!(check_x25519_shared(&[0; 32]).is_err());
+        assert!(check_x25519_shared(&key).is_ok());
+    }
+}
@@ -0,0 +1,20 @@
+pub mod aes;
+pub(crate) mod bignum;
+pub mod cbc;
+pub mod ffdhe;
+pub mod hkdf;
+pub mod hmac;
+pub mod key_block;
+pub mod kx;
+pub mod md5;
+pub mod nist;
+pub mod prf;
+pub mod rsa;
+pub mod sha1;
+pub mod sha2;
+pub mod x25519;
+
+// convert a digest to its usual lowercase hex representation
+pub fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,610 @@
+// ECDHE over the NIST curves secp256r1 and secp384r1:
+// https://datatracker.ietf.org/doc/html/rfc8422#section-5.10. Field elements are in Montgomery
+// form, points in projective coordinates added with the complete formulas of
+// https://eprint.iacr.org/2015/1060 (algorithm 4), so the scalar multiplication has no special
+// case depending on the private key
+use crate::alert::alert::AlertDescription;
+use crate::handshake::client_hello::{KeyShareEntry, NamedGroup};
+
+// up to 384-bit integers, least significant limb first
+type Limbs<const N: usize> = [u64; N];
+
+struct Curve<const N: usize> {
+    p: Limbs<N>,
+
+    // order of the base point
+    n: Limbs<N>,
+    b: Limbs<N>,
+    gx: Limbs<N>,
+    gy: Limbs<N>,
+}
+
+// https://www.secg.org/sec2-v2.pdf#section.2.4.2
+const P256: Curve<4> = Curve {
+    p: [
+        0xFFFF_FFFF_FFFF_FFFF,
+        0x0000_0000_FFFF_FFFF,
+        0x0000_0000_0000_0000,
+        0xFFFF_FFFF_0000_0001,
+    ],
+    n: [
+        0xF3B9_CAC2_FC63_2551,
+        0xBCE6_FAAD_A717_9E84,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_0000_0000,
+    ],
+    b: [
+        0x3BCE_3C3E_27D2_604B,
+        0x651D_06B0_CC53_B0F6,
+        0xB3EB_BD55_7698_86BC,
+        0x5AC6_35D8_AA3A_93E7,
+    ],
+    gx: [
+        0xF4A1_3945_D898_C296,
+        0x7703_7D81_2DEB_33A0,
+        0xF8BC_E6E5_63A4_40F2,
+        0x6B17_D1F2_E12C_4247,
+    ],
+    gy: [
+        0xCBB6_4068_37BF_51F5,
+        0x2BCE_3357_6B31_5ECE,
+        0x8EE7_EB4A_7C0F_9E16,
+        0x4FE3_42E2_FE1A_7F9B,
+    ],
+};
+
+// https://www.secg.org/sec2-v2.pdf#section.2.5.1
+const P384: Curve<6> = Curve {
+    p: [
+        0x0000_0000_FFFF_FFFF,
+        0xFFFF_FFFF_0000_0000,
+        0xFFFF_FFFF_FFFF_FFFE,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+    ],
+    n: [
+        0xECEC_196A_CCC5_2973,
+        0x581A_0DB2_48B0_A77A,
+        0xC763_4D81_F437_2DDF,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0xFFFF_FFFF_FFFF_FFFF,
+    ],
+    b: [
+        0x2A85_C8ED_D3EC_2AEF,
+        0xC656_398D_8A2E_D19D,
+        0x0314_088F_5013_875A,
+        0x181D_9C6E_FE81_4112,
+        0x988E_056B_E3F8_2D19,
+        0xB331_2FA7_E23E_E7E4,
+    ],
+    gx: [
+        0x3A54_5E38_7276_0AB7,
+        0x5502_F25D_BF55_296C,
+        0x59F7_41E0_8254_2A38,
+        0x6E1D_3B62_8BA7_9B98,
+        0x8EB1_C71E_F320_AD74,
+        0xAA87_CA22_BE8B_0537,
+    ],
+    gy: [
+        0x7A43_1D7C_90EA_0E5F,
+        0x0A60_B1CE_1D7E_819D,
+        0xE9DA_3113_B5F0_B8C0,
+        0xF8F4_1DBD_289A_147C,
+        0x5D9E_98BF_9292_DC29,
+        0x3617_DE4A_9626_2C6F,
+    ],
+};
+
+fn from_be_bytes<const N: usize>(bytes: &[u8]) -> Limbs<N> {
+    let mut n = [0u64; N];
+    for (i, chunk) in bytes.rchunks_exact(8).enumerate() {
+        n[i] = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    n
+}
+
+fn to_be_bytes<const N: usize>(n: &Limbs<N>) -> Vec<u8> {
+    n.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect()
+}
+
+fn less_than<const N: usize>(a: &Limbs<N>, b: &Limbs<N>) -> bool {
+    a.iter().rev().cmp(b.iter().rev()).is_lt()
+}
+
+fn is_zero<const N: usize>(a: &Limbs<N>) -> bool {
+    a.iter().fold(0, |acc, limb| acc | limb) == 0
+}
+
+fn one<const N: usize>() -> Limbs<N> {
+    let mut n = [0u64; N];
+    n[0] = 1;
+    n
+}
+
+// a if mask is all ones, b if it is zero
+fn select<const N: usize>(mask: u64, a: &Limbs<N>, b: &Limbs<N>) -> Limbs<N> {
+    std::array::from_fn(|i| (a[i] & mask) | (b[i] & !mask))
+}
+
+// a + b and the carry
+fn add_carry<const N: usize>(a: &Limbs<N>, b: &Limbs<N>) -> (Limbs<N>, u64) {
+    let mut n = [0u64; N];
+    let mut carry = 0u128;
+    for i in 0..N {
+        let s = a[i] as u128 + b[i] as u128 + carry;
+        n[i] = s as u64;
+        carry = s >> 64;
+    }
+    (n, carry as u64)
+}
+
+// a - b and the borrow
+fn sub_borrow<const N: usize>(a: &Limbs<N>, b: &Limbs<N>) -> (Limbs<N>, u64) {
+    let mut n = [0u64; N];
+    let mut borrow = 0u64;
+    for i in 0..N {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        n[i] = d;
+        borrow = (b1 | b2) as u64;
+    }
+    (n, borrow)
+}
+
+// arithmetic modulo p, values being below p
+struct Field<const N: usize> {
+    p: Limbs<N>,
+
+    // -p^-1 mod 2^64
+    p_inv: u64,
+
+    // R^2 mod p, with R = 2^(64N)
+    r2: Limbs<N>,
+}
+
+impl<const N: usize> Field<N> {
+    fn new(p: &Limbs<N>) -> Self {
+        // Newton's iteration doubles the number of correct bits each time
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p[0].wrapping_mul(inv)));
+        }
+
+        let mut field = Self {
+            p: *p,
+            p_inv: inv.wrapping_neg(),
+            r2: [0; N],
+        };
+        let mut r2 = one();
+        for _ in 0..128 * N {
+            r2 = field.add(&r2, &r2);
+        }
+        field.r2 = r2;
+        field
+    }
+
+    fn add(&self, a: &Limbs<N>, b: &Limbs<N>) -> Limbs<N> {
+        let (s, carry) = add_carry(a, b);
+        let (d, borrow) = sub_borrow(&s, &self.p);
+
+        // keep the sum if it is below p, meaning no carry and a borrow
+        select((!carry & borrow).wrapping_neg(), &s, &d)
+    }
+
+    fn sub(&self, a: &Limbs<N>, b: &Limbs<N>) -> Limbs<N> {
+        let (d, borrow) = sub_borrow(a, b);
+        let (s, _) = add_carry(&d, &self.p);
+        select(borrow.wrapping_neg(), &s, &d)
+    }
+
+    // a * b / R mod p
+    fn mul(&self, a: &Limbs<N>, b: &Limbs<N>) -> Limbs<N> {
+        let mut t = [0u64; 8];
+        for bi in b {
+            let mut carry = 0u128;
+            for j in 0..N {
+                let s = t[j] as u128 + a[j] as u128 * *bi as u128 + carry;
+                t[j] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[N] as u128 + carry;
+            t[N] = s as u64;
+            t[N + 1] = (s >> 64) as u64;
+
+            // add a multiple of p making the lowest limb zero, then shift it out
+            let m = t[0].wrapping_mul(self.p_inv);
+            let mut carry = (t[0] as u128 + m as u128 * self.p[0] as u128) >> 64;
+            for j in 1..N {
+                let s = t[j] as u128 + m as u128 * self.p[j] as u128 + carry;
+                t[j - 1] = s as u64;
+                carry = s >> 64;
+            }
+            let s = t[N] as u128 + carry;
+            t[N - 1] = s as u64;
+            t[N] = t[N + 1] + (s >> 64) as u64;
+        }
+
+        let n: Limbs<N> = std::array::from_fn(|i| t[i]);
+        let (d, borrow) = sub_borrow(&n, &self.p);
+        select((t[N] == 0) as u64 * borrow.wrapping_neg(), &n, &d)
+    }
+
+    fn to_montgomery(&self, a: &Limbs<N>) -> Limbs<N> {
+        self.mul(a, &self.r2)
+    }
+
+    // out of Montgomery form
+    fn reduce(&self, a: &Limbs<N>) -> Limbs<N> {
+        self.mul(a, &one())
+    }
+
+    // a^(p - 2), in Montgomery form. The exponent is public
+    fn invert(&self, a: &Limbs<N>) -> Limbs<N> {
+        let mut two = [0u64; N];
+        two[0] = 2;
+        let (e, _) = sub_borrow(&self.p, &two);
+
+        let mut n = self.to_montgomery(&one());
+        for i in (0..64 * N).rev() {
+            n = self.mul(&n, &n);
+            if (e[i / 64] >> (i % 64)) & 1 == 1 {
+                n = self.mul(&n, a);
+            }
+        }
+        n
+    }
+}
+
+// (X:Y:Z) with x = X/Z and y = Y/Z, the point at infinity being (0:1:0)
+#[derive(Clone, Copy)]
+struct Point<const N: usize> {
+    x: Limbs<N>,
+    y: Limbs<N>,
+    z: Limbs<N>,
+}
+
+impl<const N: usize> Curve<N> {
+    // P + Q for any P and Q, y^2 = x^3 - 3x + b
+    fn add(&self, f: &Field<N>, b: &Limbs<N>, p: &Point<N>, q: &Point<N>) -> Point<N> {
+        let triple = |a: &Limbs<N>| f.add(&f.add(a, a), a);
+
+        let xx = f.mul(&p.x, &q.x);
+        let yy = f.mul(&p.y, &q.y);
+        let zz = f.mul(&p.z, &q.z);
+        let xy = f.sub(
+            &f.mul(&f.add(&p.x, &p.y), &f.add(&q.x, &q.y)),
+            &f.add(&xx, &yy),
+        );
+        let yz = f.sub(
+            &f.mul(&f.add(&p.y, &p.z), &f.add(&q.y, &q.z)),
+            &f.add(&yy, &zz),
+        );
+        let xz = f.sub(
+            &f.mul(&f.add(&p.x, &p.z), &f.add(&q.x, &q.z)),
+            &f.add(&xx, &zz),
+        );
+
+        let bzz3 = triple(&f.sub(&xz, &f.mul(b, &zz)));
+        let yy_m_bzz3 = f.sub(&yy, &bzz3);
+        let yy_p_bzz3 = f.add(&yy, &bzz3);
+
+        let zz3 = triple(&zz);
+        let bxz3 = triple(&f.sub(&f.mul(b, &xz), &f.add(&zz3, &xx)));
+        let xx3_m_zz3 = f.sub(&triple(&xx), &zz3);
+
+        Point {
+            x: f.sub(&f.mul(&yy_p_bzz3, &xy), &f.mul(&yz, &bxz3)),
+            y: f.add(&f.mul(&yy_p_bzz3, &yy_m_bzz3), &f.mul(&xx3_m_zz3, &bxz3)),
+            z: f.add(&f.mul(&yy_m_bzz3, &yz), &f.mul(&xy, &xx3_m_zz3)),
+        }
+    }
+
+    // the length of a coordinate
+    fn len(&self) -> usize {
+        8 * N
+    }
+
+    // the coordinates of an uncompressed point, below p and on the curve
+    fn decode(&self, point: &[u8]) -> Result<(Limbs<N>, Limbs<N>), AlertDescription> {
+        if point.len() != 1 + 2 * self.len() || point[0] != 4 {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        let x = from_be_bytes(&point[1..1 + self.len()]);
+        let y = from_be_bytes(&point[1 + self.len()..]);
+        if !less_than(&x, &self.p) || !less_than(&y, &self.p) {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        let f = Field::new(&self.p);
+        let (xm, ym) = (f.to_montgomery(&x), f.to_montgomery(&y));
+        let x3 = f.mul(&f.mul(&xm, &xm), &xm);
+        let x3_m_3x = f.sub(&x3, &f.add(&f.add(&xm, &xm), &xm));
+        let rhs = f.add(&x3_m_3x, &f.to_montgomery(&self.b));
+        if f.mul(&ym, &ym) == rhs {
+            Ok((x, y))
+        } else {
+            Err(AlertDescription::illegal_parameter)
+        }
+    }
+
+    // the uncompressed encoding of scalar * (x, y). The scalar must be in [1, n - 1]
+    fn multiply(
+        &self,
+        scalar: &[u8],
+        x: &Limbs<N>,
+        y: &Limbs<N>,
+    ) -> Result<Vec<u8>, AlertDescription> {
+        let f = Field::new(&self.p);
+        let b = f.to_montgomery(&self.b);
+        let one = f.to_montgomery(&one());
+
+        let point = Point {
+            x: f.to_montgomery(x),
+            y: f.to_montgomery(y),
+            z: one,
+        };
+        let mut r = Point {
+            x: [0; N],
+            y: one,
+            z: [0; N],
+        };
+
+        // double and always add, keeping the sum depending on the bit
+        for byte in scalar {
+            for i in (0..8).rev() {
+                r = self.add(&f, &b, &r, &r);
+                let sum = self.add(&f, &b, &r, &point);
+                let mask = ((byte >> i) & 1) as u64;
+                let mask = mask.wrapping_neg();
+                r = Point {
+                    x: select(mask, &sum.x, &r.x),
+                    y: select(mask, &sum.y, &r.y),
+                    z: select(mask, &sum.z, &r.z),
+                };
+            }
+        }
+
+        if is_zero(&r.z) {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        let z_inv = f.invert(&r.z);
+        let mut encoded = vec![4];
+        encoded.extend(to_be_bytes(&f.reduce(&f.mul(&r.x, &z_inv))));
+        encoded.extend(to_be_bytes(&f.reduce(&f.mul(&r.y, &z_inv))));
+        Ok(encoded)
+    }
+
+    fn valid_scalar(&self, scalar: &[u8]) -> bool {
+        if scalar.len() != self.len() {
+            return false;
+        }
+        let k = from_be_bytes(scalar);
+        !is_zero(&k) && less_than(&k, &self.n)
+    }
+}
+
+// the uncompressed form of a point, the only one allowed in TLS 1.3 and the one sent in
+// ServerKeyExchange: https://datatracker.ietf.org/doc/html/rfc8422#section-5.4.1
+pub fn encode_point(x: &[u8], y: &[u8]) -> Vec<u8> {
+    let mut point = vec![4];
+    point.extend_from_slice(x);
+    point.extend_from_slice(y);
+    point
+}
+
+// the coordinates of an uncompressed point of the group, checked to be on the curve
+pub fn decode_point(
+    group: NamedGroup,
+    point: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), AlertDescription> {
+    match group {
+        NamedGroup::secp256r1 => P256
+            .decode(point)
+            .map(|(x, y)| (to_be_bytes(&x), to_be_bytes(&y))),
+        NamedGroup::secp384r1 => P384
+            .decode(point)
+            .map(|(x, y)| (to_be_bytes(&x), to_be_bytes(&y))),
+        _ => Err(AlertDescription::illegal_parameter),
+    }
+}
+
+#[derive(Clone)]
+pub struct NistKeyPair {
+    group: NamedGroup,
+    private: Vec<u8>,
+
+    // uncompressed
+    public: Vec<u8>,
+}
+
+// the private key is not shown
+impl std::fmt::Debug for NistKeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NistKeyPair")
+            .field("group", &self.group)
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NistKeyPair {
+    // None for groups other than secp256r1 and secp384r1
+    pub fn generate(group: NamedGroup) -> Option<Self> {
+        let len = match group {
+            NamedGroup::secp256r1 => P256.len(),
+            NamedGroup::secp384r1 => P384.len(),
+            _ => return None,
+        };
+
+        // random scalars until one is in [1, n - 1], which is almost always the first
+        loop {
+            let private: Vec<u8> = (0..len).map(|_| rand::random()).collect();
+            if let Some(keys) = Self::from_private(group, &private) {
+                return Some(keys);
+            }
+        }
+    }
+
+    // None if the group isn't supported or the big-endian scalar isn't in [1, n - 1]
+    pub fn from_private(group: NamedGroup, private: &[u8]) -> Option<Self> {
+        let public = match group {
+            NamedGroup::secp256r1 if P256.valid_scalar(private) => {
+                P256.multiply(private, &P256.gx, &P256.gy)
+            }
+            NamedGroup::secp384r1 if P384.valid_scalar(private) => {
+                P384.multiply(private, &P384.gx, &P384.gy)
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            group,
+            private: private.to_vec(),
+            public: public.ok()?,
+        })
+    }
+
+    pub fn group(&self) -> NamedGroup {
+        self.group
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    // the x-coordinate of the product with the peer's point, which must be on the curve:
+    // https://datatracker.ietf.org/doc/html/rfc8446#section-7.4.2
+    pub fn agree(&self, peer: &[u8]) -> Result<Vec<u8>, AlertDescription> {
+        let point = match self.group {
+            NamedGroup::secp256r1 => {
+                let (x, y) = P256.decode(peer)?;
+                P256.multiply(&self.private, &x, &y)?
+            }
+            _ => {
+                let (x, y) = P384.decode(peer)?;
+                P384.multiply(&self.private, &x, &y)?
+            }
+        };
+        let len = (point.len() - 1) / 2;
+        Ok(point[1..1 + len].to_vec())
+    }
+
+    // the secret with the server's share of a ServerHello
+    pub fn agree_key_share(&self, share: &KeyShareEntry) -> Result<Vec<u8>, AlertDescription> {
+        if share.group != self.group {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        self.agree(&share.key_exchange.data)
+    }
+
+    // the share to offer in a ClientHello
+    pub fn key_share(&self) -> KeyShareEntry {
+        KeyShareEntry::new(self.group, &self.public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // https://datatracker.ietf.org/doc/html/rfc5903#section-8.1
+    #[test]
+    fn p256() {
+        let alice = NistKeyPair::from_private(
+            NamedGroup::secp256r1,
+            &bytes("c88f01f510d9ac3f70a292daa2316de544e9aab8afe84049c62a9c57862d1433"),
+        )
+        .unwrap();
+        let bob = NistKeyPair::from_private(
+            NamedGroup::secp256r1,
+            &bytes("c6ef9c5d78ae012a011164acb397ce2088685d8f06bf9be0b283ab46476bee53"),
+        )
+        .unwrap();
+        assert_eq!(
+            to_hex(alice.public_key()),
+            "04dad0b65394221cf9b051e1feca5787d098dfe637fc90b9ef945d0c3772581180\
+             5271a0461cdb8252d61f1c456fa3e59ab1f45b33accf5f58389e0577b8990bb3"
+        );
+
+        let shared = alice.agree_key_share(&bob.key_share()).unwrap();
+        assert_eq!(
+            to_hex(&shared),
+            "d6840f6b42f6edafd13116e0e12565202fef8e9ece7dce03812464d04b9442de"
+        );
+        assert_eq!(bob.agree(alice.public_key()), Ok(shared));
+
+        // a point off the curve, a share of another group, and scalars out of range
+        let mut off_curve = bob.public_key().to_vec();
+        off_curve[64] ^= 1;
+        assert_eq!(
+            alice.agree(&off_curve),
+            Err(AlertDescription::illegal_parameter)
+        );
+        let share = KeyShareEntry::new(NamedGroup::secp384r1, bob.public_key());
+        assert!(alice.agree_key_share(&share).is_err());
+        assert!(NistKeyPair::from_private(NamedGroup::secp256r1, &[0; 32]).is_none());
+        assert!(NistKeyPair::from_private(NamedGroup::secp256r1, &[0xFF; 32]).is_none());
+        assert!(NistKeyPair::from_private(NamedGroup::secp256r1, &[1; 31]).is_none());
+    }
+
+    // https://datatracker.ietf.org/doc/html/rfc5903#section-8.2
+    #[test]
+    fn p384() {
+        let alice = NistKeyPair::from_private(
+            NamedGroup::secp384r1,
+            &bytes("099f3c7034d4a2c699884d73a375a67f7624ef7c6b3c0f160647b67414dce655e35b538041e649ee3faef896783ab194"),
+        )
+        .unwrap();
+        let bob = NistKeyPair::from_private(
+            NamedGroup::secp384r1,
+            &bytes("41cb0779b4bdb85d47846725fbec3c9430fab46cc8dc5060855cc9bda0aa2942e0308312916b8ed2960e4bd55a7448fc"),
+        )
+        .unwrap();
+        assert_eq!(
+            to_hex(bob.public_key()),
+            "04e558dbef53eecde3d3fccfc1aea08a89a987475d12fd950d83cfa41732bc509d0d1ac43a0336def96fda41d0774a3571\
+             dcfbec7aacf3196472169e838430367f66eebe3c6e70c416dd5f0c68759dd1fff83fa40142209dff5eaad96db9e6386c"
+        );
+
+        let shared = bob.agree_key_share(&alice.key_share()).unwrap();
+        assert_eq!(
+            to_hex(&shared),
+            "11187331c279962d93d604243fd592cb9d0a926f422e47187521287e7156c5c4d603135569b9e9d09cf5d4a270f59746"
+        );
+        assert_eq!(alice.agree(bob.public_key()), Ok(shared));
+
+        let generated = NistKeyPair::generate(NamedGroup::secp384r1).unwrap();
+        assert_eq!(generated.public_key().len(), 97);
+        assert_eq!(
+            generated.agree(bob.public_key()),
+            bob.agree(generated.public_key())
+        );
+        assert!(!format!("{:?}", generated).contains("private"));
+        assert!(NistKeyPair::generate(NamedGroup::x25519).is_none());
+    }
+
+    #[test]
+    fn points() {
+        let keys = NistKeyPair::generate(NamedGroup::secp256r1).unwrap();
+        let (x, y) = decode_point(NamedGroup::secp256r1, keys.public_key()).unwrap();
+        assert_eq!(encode_point(&x, &y), keys.public_key());
+
+        // compressed, or of the wrong length for the group
+        let mut compressed = keys.public_key()[..33].to_vec();
+        compressed[0] = 2;
+        assert!(decode_point(NamedGroup::secp256r1, &compressed).is_err());
+        assert!(decode_point(NamedGroup::secp384r1, keys.public_key()).is_err());
+        assert!(decode_point(NamedGroup::x25519, keys.public_key()).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+// the TLS 1.2 PRF: https://datatracker.ietf.org/doc/html/rfc5246#section-5, and the master
+// secret derived with it, from both randoms or from the session hash of
+// https://datatracker.ietf.org/doc/html/rfc7627 when extended_master_secret was negotiated
+use crate::crypto::hmac::hmac;
+use crate::crypto::sha2::HashAlgorithm;
+
+pub const MASTER_SECRET_LENGTH: usize = 48;
+
+// P_hash, with the hash of the cipher suite: SHA-256 unless the suite names another one
+pub fn prf(hash: HashAlgorithm, secret: &[u8], label: &str, seed: &[u8], length: usize) -> Vec<u8> {
+    let mut label_seed = label.as_bytes().to_vec();
+    label_seed.extend_from_slice(seed);
+
+    // A(0) is the seed, A(i) = HMAC(secret, A(i-1))
+    let mut output = Vec::with_capacity(length);
+    let mut a = label_seed.clone();
+    while output.len() < length {
+        a = hmac(hash, secret, &a);
+        let mut input = a.clone();
+        input.extend_from_slice(&label_seed);
+        output.extend_from_slice(&hmac(hash, secret, &input));
+    }
+    output.truncate(length);
+    output
+}
+
+// session_hash is the hash of the handshake messages up to and including ClientKeyExchange,
+// as given by Transcript::current_hash. It's only given when extended_master_secret was
+// negotiated, the randoms aren't used then: https://datatracker.ietf.org/doc/html/rfc7627#section-4
+pub fn master_secret(
+    hash: HashAlgorithm,
+    pre_master_secret: &[u8],
+    client_random: &[u8],
+    server_random: &[u8],
+    session_hash: Option<&[u8]>,
+) -> Vec<u8> {
+    match session_hash {
+        Some(session_hash) => prf(
+            hash,
+            pre_master_secret,
+            "extended master secret",
+            session_hash,
+            MASTER_SECRET_LENGTH,
+        ),
+        None => {
+            let mut seed = client_random.to_vec();
+            seed.extend_from_slice(server_random);
+            prf(
+                hash,
+                pre_master_secret,
+                "master secret",
+                &seed,
+                MASTER_SECRET_LENGTH,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    #[test]
+    fn prf_vectors() {
+        let output = prf(
+            HashAlgorithm::Sha256,
+            &[
+                0x9b, 0xbe, 0x43, 0x6b, 0xa9, 0x40, 0xf0, 0x17, 0xb1, 0x76, 0x52, 0x84, 0x9a, 0x71,
+                0xdb, 0x35,
+            ],
+            "test label",
+            &[
+                0xa0, 0xba, 0x9f, 0x93, 0x6c, 0xda, 0x31, 0x18, 0x27, 0xa6, 0xf7, 0x96, 0xff, 0xd5,
+                0x19, 0x8c,
+            ],
+            100,
+        );
+        assert_eq!(
+            to_hex(&output),
+            "e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a6b301791e90d35c9c9a46b4e\
+             14baf9af0fa022f7077def17abfd3797c0564bab4fbc91666e9def9b97fce34f796789baa48082d122ee42c5\
+             a72e5a5110fff70187347b66"
+        );
+
+        let output = prf(
+            HashAlgorithm::Sha384,
+            &[
+                0xb8, 0x0b, 0x73, 0x3d, 0x6c, 0xee, 0xfc, 0xdc, 0x71, 0x56, 0x6e, 0xa4, 0x8e, 0x55,
+                0x67, 0xdf,
+            ],
+            "test label",
+            &[
+                0xcd, 0x66, 0x5c, 0xf6, 0xa8, 0x44, 0x7d, 0xd6, 0xff, 0x8b, 0x27, 0x55, 0x5e, 0xdb,
+                0x74, 0x65,
+            ],
+            148,
+        );
+        assert_eq!(
+            to_hex(&output[..48]),
+            "7b0c18e9ced410ed1804f2cfa34a336a1c14dffb4900bb5fd7942107e81c83cde9ca0faa60be9fe34f82b123\
+             3c9146a0"
+        );
+        assert_eq!(to_hex(&output[136..]), "5eaa3be85e0990703d73e56f");
+    }
+
+    #[test]
+    fn master_secrets() {
+        let mut pre_master_secret = vec![0x03, 0x03];
+        pre_master_secret.extend(0..46);
+        let client_random: Vec<u8> = (0..32).collect();
+        let server_random: Vec<u8> = (32..64).collect();
+
+        let secret = master_secret(
+            HashAlgorithm::Sha256,
+            &pre_master_secret,
+            &client_random,
+            &server_random,
+            None,
+        );
+        assert_eq!(
+            to_hex(&secret),
+            "d2c76cb7c4c86a909737874ceccddc57f2c211e2a4c6f44532306227ba5173089e3a96968874bb7e9ff14f3e\
+             d8c6a41a"
+        );
+
+        // the randoms don't matter with a session hash
+        let session_hash = HashAlgorithm::Sha256.hash(b"handshake messages");
+        let secret = master_secret(
+            HashAlgorithm::Sha256,
+            &pre_master_secret,
+            &[],
+            &[],
+            Some(&session_hash),
+        );
+        assert_eq!(
+            to_hex(&secret),
+            "145dc9af47c08ed47170c8b5bc09e05718506a1607d3d2e7de1c4f9f44cdb398ff184e09e1e97eb6bd0752e0\
+             22d331e8"
+        );
+
+        let session_hash = HashAlgorithm::Sha384.hash(b"handshake messages");
+        let secret = master_secret(
+            HashAlgorithm::Sha384,
+            &pre_master_secret,
+            &client_random,
+            &server_random,
+            Some(&session_hash),
+        );
+        assert_eq!(secret.len(), MASTER_SECRET_LENGTH);
+        assert_eq!(
+            to_hex(&secret),
+            "02d8b44a4d4f7ccd660e4baea08f3264fb83542ff87f9689d2ddaaa029e0c7890983bac07469241e78a3f28f\
+             61c2579d"
+        );
+    }
+}
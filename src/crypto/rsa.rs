@@ -0,0 +1,505 @@
+// RSA encryption of the premaster secret of TLS_RSA_* suites, under the key of the server's
+// certificate, with the PKCS #1 v1.5 padding of https://datatracker.ietf.org/doc/html/rfc8017#section-7.2,
+// and verification of the signatures made with that key
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind};
+
+use rand::Rng;
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::bignum::{compare, from_be_bytes, strip, to_be_bytes, Modulus};
+use crate::crypto::sha2::HashAlgorithm;
+use crate::handshake::client_hello::SignatureScheme;
+use crate::handshake::common::ProtocolVersion;
+use crate::x509::certificate::{subject_public_key_info, RSA_ENCRYPTION};
+use crate::x509::der::{DerReader, INTEGER, SEQUENCE};
+
+// length of the premaster secret: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.7.1
+pub const PRE_MASTER_SECRET_LENGTH: usize = 48;
+
+// the 0x00 0x02 header, at least 8 bytes of padding, and the 0x00 separator
+const PADDING_OVERHEAD: usize = 11;
+
+// the DER encoding of the DigestInfo before the hash, in RSASSA-PKCS1-v1_5 signatures:
+// https://datatracker.ietf.org/doc/html/rfc8017#section-9.2
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+const SHA384_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05,
+    0x00, 0x04, 0x30,
+];
+
+// the modulus n and public exponent e, big-endian
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsaPublicKey {
+    pub n: Vec<u8>,
+    pub e: Vec<u8>,
+}
+
+impl RsaPublicKey {
+    // the RSAPublicKey of https://datatracker.ietf.org/doc/html/rfc8017#appendix-A.1.1
+    pub fn from_der(der: &[u8]) -> std::io::Result<Self> {
+        let mut key = DerReader::new(DerReader::new(der).expect(SEQUENCE)?);
+        let n = strip(key.expect(INTEGER)?).to_vec();
+        let e = strip(key.expect(INTEGER)?).to_vec();
+        Ok(Self { n, e })
+    }
+
+    // the key of a DER-encoded certificate, which must be an RSA one
+    pub fn from_certificate(cert: &[u8]) -> std::io::Result<Self> {
+        let info = subject_public_key_info(cert)?;
+        if info.algorithm != RSA_ENCRYPTION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("not an RSA key: {}", info.algorithm),
+            ));
+        }
+        Self::from_der(info.public_key)
+    }
+
+    // the size of n, to judge keys sent by a server
+    pub fn bits(&self) -> usize {
+        let n = strip(&self.n);
+        n.first()
+            .map_or(0, |x| 8 * n.len() - x.leading_zeros() as usize)
+    }
+
+    // the length of n, and of the ciphertexts
+    fn len(&self) -> usize {
+        strip(&self.n).len()
+    }
+
+    fn limbs(&self) -> usize {
+        self.len().div_ceil(8)
+    }
+
+    // an odd n, and e above 1
+    fn modulus(&self) -> Result<Modulus, AlertDescription> {
+        let n = from_be_bytes(strip(&self.n), self.limbs());
+        let e = strip(&self.e);
+        if n.first().is_none_or(|x| x & 1 == 0) || e.is_empty() || e == [1] {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        Ok(Modulus::new(n))
+    }
+
+    // RSAES-PKCS1-v1_5: the message after 0x00 0x02, random non-zero bytes and 0x00, raised
+    // to e. It must be at most 11 bytes shorter than n
+    pub fn encrypt_pkcs1(&self, message: &[u8]) -> Result<Vec<u8>, AlertDescription> {
+        let modulus = self.modulus()?;
+        let k = self.len();
+        if message.len() + PADDING_OVERHEAD > k {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        let mut encoded = vec![0x00, 0x02];
+        let mut rng = rand::thread_rng();
+        encoded.extend((0..k - message.len() - 3).map(|_| rng.gen_range(1..=255u8)));
+        encoded.push(0x00);
+        encoded.extend_from_slice(message);
+
+        let m = from_be_bytes(&encoded, self.limbs());
+        Ok(to_be_bytes(&modulus.pow(&m, strip(&self.e)), k))
+    }
+
+    // the signature of the message with one of the RSA schemes of rsaEncryption keys. A wrong
+    // signature is a decrypt_error, and a scheme which isn't checked an illegal_parameter:
+    // https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.3
+    pub fn verify(
+        &self,
+        scheme: SignatureScheme,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), AlertDescription> {
+        use SignatureScheme::*;
+        let (hash, pss) = match scheme {
+            rsa_pkcs1_sha256 => (HashAlgorithm::Sha256, false),
+            rsa_pkcs1_sha384 => (HashAlgorithm::Sha384, false),
+            rsa_pss_rsae_sha256 => (HashAlgorithm::Sha256, true),
+            rsa_pss_rsae_sha384 => (HashAlgorithm::Sha384, true),
+            _ => return Err(AlertDescription::illegal_parameter),
+        };
+
+        // the signature is as long as n, and below it
+        let modulus = self.modulus()?;
+        let k = self.len();
+        if signature.len() != k {
+            return Err(AlertDescription::decrypt_error);
+        }
+        let s = from_be_bytes(signature, self.limbs());
+        if compare(&s, &modulus.p) != Ordering::Less {
+            return Err(AlertDescription::decrypt_error);
+        }
+        let encoded = to_be_bytes(&modulus.pow(&s, strip(&self.e)), k);
+
+        let digest = hash.hash(message);
+        let valid = if pss {
+            pss_valid(hash, &digest, &encoded, self.bits() - 1)
+        } else {
+            encoded == pkcs1_encoding(hash, &digest, k)
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(AlertDescription::decrypt_error)
+        }
+    }
+}
+
+// EMSA-PKCS1-v1_5: 0x00 0x01, 0xFF bytes, 0x00, then the DigestInfo of the hash:
+// https://datatracker.ietf.org/doc/html/rfc8017#section-9.2
+fn pkcs1_encoding(hash: HashAlgorithm, digest: &[u8], k: usize) -> Vec<u8> {
+    let digest_info: &[u8] = match hash {
+        HashAlgorithm::Sha256 => &SHA256_DIGEST_INFO,
+        HashAlgorithm::Sha384 => &SHA384_DIGEST_INFO,
+    };
+
+    let mut encoded = vec![0x00, 0x01];
+    let length = digest_info.len() + digest.len();
+    encoded.resize(k.saturating_sub(length + 1), 0xFF);
+    encoded.push(0x00);
+    encoded.extend_from_slice(digest_info);
+    encoded.extend_from_slice(digest);
+    encoded
+}
+
+// the mask generation function MGF1: https://datatracker.ietf.org/doc/html/rfc8017#appendix-B.2.1
+fn mgf1(hash: HashAlgorithm, seed: &[u8], length: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(length + hash.output_len());
+    let mut counter = 0u32;
+    while mask.len() < length {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&counter.to_be_bytes());
+        mask.extend(hash.hash(&input));
+        counter += 1;
+    }
+    mask.truncate(length);
+    mask
+}
+
+// EMSA-PSS-VERIFY with MGF1 and a salt as long as the hash, as TLS requires:
+// https://datatracker.ietf.org/doc/html/rfc8017#section-9.1.2 and
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3
+fn pss_valid(hash: HashAlgorithm, digest: &[u8], encoded: &[u8], em_bits: usize) -> bool {
+    // the encoded message has em_bits, so one byte less than n if its size is 1 mod 8
+    let em_length = em_bits.div_ceil(8);
+    let (zeros, em) = encoded.split_at(encoded.len() - em_length);
+    let h_length = hash.output_len();
+    let s_length = h_length;
+    if zeros.iter().any(|x| *x != 0)
+        || em_length < h_length + s_length + 2
+        || em.last() != Some(&0xbc)
+    {
+        return false;
+    }
+
+    let (masked_db, h) = em[..em_length - 1].split_at(em_length - h_length - 1);
+    // none when n has 8k + 1 bits, the encoded message is then a whole number of bytes
+    let unused_bits = 8 * em_length - em_bits;
+    if unused_bits > 0 && masked_db[0] >> (8 - unused_bits) != 0 {
+        return false;
+    }
+
+    let mut db: Vec<u8> = masked_db
+        .iter()
+        .zip(mgf1(hash, h, masked_db.len()))
+        .map(|(x, y)| x ^ y)
+        .collect();
+    db[0] &= 0xFF >> unused_bits;
+
+    // zeros, 0x01, then the salt
+    let (padding, salt) = db.split_at(db.len() - s_length);
+    let Some((one, zeros)) = padding.split_last() else {
+        return false;
+    };
+    if *one != 0x01 || zeros.iter().any(|x| *x != 0) {
+        return false;
+    }
+
+    let mut m = vec![0; 8];
+    m.extend_from_slice(digest);
+    m.extend_from_slice(salt);
+    hash.hash(&m) == h
+}
+
+// the version offered in the ClientHello, which lets the server detect a rollback, then
+// 46 random bytes
+pub fn pre_master_secret(client_version: ProtocolVersion) -> [u8; PRE_MASTER_SECRET_LENGTH] {
+    let mut secret = [0u8; PRE_MASTER_SECRET_LENGTH];
+    rand::thread_rng().fill(&mut secret[2..]);
+    secret[..2].copy_from_slice(&client_version);
+    secret
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::crypto::bignum::from_hex;
+    use crate::handshake::constants::TLS12;
+    use crate::x509::certificate::test_certificate_with_key;
+    use crate::x509::der::{encode, BIT_STRING, OID};
+
+    const N: [&str; 8] = [
+        "ABF2E5F9463C8F9F2EF7BEED98B35910FCCBEE520D1E73C76D35DD631F59914A",
+        "DDFAADAA62C8FABD5FE8F037DE290FD7C3D2D9B46EE9204443F3433FA1D7F7FE",
+        "9E7F5A1A4D7EBA710BF29A5C87110D32821ECCA9F86A325087B1911A04892174",
+        "A7DB49B12784801A84028E444E6D601C2DD739EC59245CF0DA43DC1ADE026033",
+        "B7A24D37038E6C535E47B4A46D070896EC8324BC57CF65B7C99B93924B5933FA",
+        "52C7D462213F1C85410B699BDF31B521CC0E2688A7C7317D593982253CC1AE41",
+        "C85D76C6AF290397A2B177C3412DD135AE809601BAB3F6EFE4E938FDD35FC74F",
+        "1682FCB015523D970447D0640120B07093B248D94E118415BC8AE7BD1C3C2D9F",
+    ];
+
+    // the private exponent, to decrypt
+    const D: [&str; 8] = [
+        "A7C79F77187DC7768011E60F58CCC7BFB7AC15A3A281705FCBC49F7D34FDE4B3",
+        "B4DE01CF1C0D17FCE9351610AD8A4B9731D0EFF50BB416740609BC38BEE74968",
+        "FDA72E7833DDD076B63DC73939E1FD8AA744FE576BB9D3D698513F50AFD44EFC",
+        "D13D3C2ECC6C91C34DC460A56E1173928B785360B7AAAFB5A4844AA53CB3FCC1",
+        "601DB196F7DCDC29E3FB0825EA1CB83AB5E31B69F598412C7EE19769B6050BEF",
+        "E7C3AE258DB8463FB757F5E21E1424B00F294FAFC9F6E919A9F2F5F5F9EBC5AB",
+        "B6E01B3C04711F6663A03F6CC1C5E26BA72A786ECC4BF1D6AF9A64C541DC09E7",
+        "BC5099C582392988356BA2FAA9EB0E74C7F5C35C9A88E3A2C3EE63B978781441",
+    ];
+
+    // a 1024-bit key, with the signatures of SIGNED made by Python's cryptography package
+    pub(crate) const SIGNING_N: [&str; 4] = [
+        "EC228A472B95FE10A7D54AEA9D14C04467A6B0F6E79F506281658A87E851C38C",
+        "44DC4925E8D79E2CB223CE43461E4FD0CECEBAF762A936AF7425E93818D31AF2",
+        "DB5FE9A3FD1CF69CA0E141FD50BAA919646C14B4FFE2648F800355CBBC42FBD3",
+        "E0077BC204853E41A36AC4B5CEE4C94F38B89A4E1FE676C53D9284D3EA397CD1",
+    ];
+
+    // both randoms, of 0x01 and 0x02 bytes, then DH parameters p = 23, g = 5 and Ys = 8
+    pub(crate) fn signed() -> Vec<u8> {
+        let mut signed = vec![0x01; 32];
+        signed.extend([0x02; 32]);
+        signed.extend([0x00, 0x01, 23, 0x00, 0x01, 5, 0x00, 0x01, 8]);
+        signed
+    }
+
+    pub(crate) const SIGNATURES: [(SignatureScheme, [&str; 4]); 4] = [
+        (
+            SignatureScheme::rsa_pkcs1_sha256,
+            [
+                "74781eb699500792c0970ffc61f340b6c2d5b77e35621e68732f6f04612f738a",
+                "bb74b28a762d68cd94db98189ad9e1b55beb8fa4a5cb6ceaf633ee9b534ee0b7",
+                "a1cf7159317807128c3cfa56dd96ce3a5f199e8d5d279c5ddbf3747a117d4708",
+                "200385b1c45de3d9c41872403f7b9c03f398b8dcc49c4117ee628bbba3c0c3d1",
+            ],
+        ),
+        (
+            SignatureScheme::rsa_pss_rsae_sha256,
+            [
+                "86882107d5bba29983ebcc1aa0736334824b6938a088e51d24a09fb2ce97fc53",
+                "63730e706a4f7add57a67bc42001cda1039e5b0477113d2357bc38999c099b51",
+                "ac95edc902ec0dca23203f07a59883418114b701ef8a9f3f8f9e6117180a07c9",
+                "8ef8b2a5fb295ee9c4cf3fb01422e8e9a7ff980fa943df95bf2f4984700941e4",
+            ],
+        ),
+        (
+            SignatureScheme::rsa_pss_rsae_sha384,
+            [
+                "a7c93354ac532b74e1e65266f824dbb29d75910624e791c25680cb14189e61b1",
+                "feed62d6b52a9f17b11baf42ee78f16c440eabe3ab4900df205907b2d5f98325",
+                "b3ff6127da3718df68237a4bc759c64fa1aad9144c4ee14ac83c7e91903b45f9",
+                "4270519bb305a0db27b73a0f62344dea17e2fbc5318807682024a6712aa5b3c2",
+            ],
+        ),
+        (
+            SignatureScheme::rsa_pkcs1_sha384,
+            [
+                "ae11ba48e567e1bbf6eb4dd3ef897222b6c6f942f76ba9661dc281b62c243166",
+                "5bf5bd79f4fba04d5525a35d8a63761d87fac6ad5940e8ef3004a0f4d4a180fa",
+                "cc68c877150b55c4761ff4ef6646595bda489dd09505317c9f326bda33d0c47e",
+                "26aeec90464e2d4fed022764ca2af6d54f28e047a07d1dfc793bfe880afe18d9",
+            ],
+        ),
+    ];
+
+    pub(crate) fn signing_key() -> RsaPublicKey {
+        RsaPublicKey {
+            n: from_hex(&SIGNING_N),
+            e: vec![0x01, 0x00, 0x01],
+        }
+    }
+
+    // the server's side of RSAES-PKCS1-v1_5, without the checks a server must do in
+    // constant time
+    fn decrypt(key: &RsaPublicKey, d: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+        let c = from_be_bytes(ciphertext, key.limbs());
+        let encoded = to_be_bytes(&key.modulus().unwrap().pow(&c, d), key.len());
+        assert_eq!(&encoded[..2], &[0x00, 0x02]);
+        let separator = encoded[2..].iter().position(|x| *x == 0).unwrap();
+        assert!(separator >= 8);
+        encoded[2 + separator + 1..].to_vec()
+    }
+
+    // a certificate of the key, with a leading zero in the INTEGER of n as DER requires
+    pub(crate) fn certificate(key: &RsaPublicKey) -> Vec<u8> {
+        let mut n = vec![0];
+        n.extend_from_slice(&key.n);
+        let mut rsa_public_key = encode(INTEGER, &n);
+        rsa_public_key.extend(encode(INTEGER, &key.e));
+
+        // rsaEncryption, with NULL parameters
+        let mut algorithm = encode(OID, &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01]);
+        algorithm.extend([0x05, 0x00]);
+        let mut info = encode(SEQUENCE, &algorithm);
+        let mut bits = vec![0];
+        bits.extend(encode(SEQUENCE, &rsa_public_key));
+        info.extend(encode(BIT_STRING, &bits));
+
+        test_certificate_with_key(&encode(SEQUENCE, &info), &[])
+    }
+
+    #[test]
+    fn rsa() {
+        let key = RsaPublicKey {
+            n: from_hex(&N),
+            e: vec![0x01, 0x00, 0x01],
+        };
+        assert_eq!(key.bits(), 2048);
+        assert_eq!(
+            RsaPublicKey::from_certificate(&certificate(&key)).unwrap(),
+            key
+        );
+
+        let secret = pre_master_secret(TLS12);
+        assert_eq!(&secret[..2], &TLS12);
+        let ciphertext = key.encrypt_pkcs1(&secret).unwrap();
+        assert_eq!(ciphertext.len(), 256);
+        assert_eq!(decrypt(&key, &from_hex(&D), &ciphertext), secret);
+
+        // the padding is random
+        assert_ne!(key.encrypt_pkcs1(&secret).unwrap(), ciphertext);
+
+        assert_eq!(
+            key.encrypt_pkcs1(&[0; 246]),
+            Err(AlertDescription::illegal_parameter)
+        );
+        assert!(key.encrypt_pkcs1(&[0; 245]).is_ok());
+    }
+
+    #[test]
+    fn signatures() {
+        let key = signing_key();
+        let signed = signed();
+        for (scheme, signature) in SIGNATURES {
+            let signature = from_hex(&signature);
+            assert_eq!(
+                key.verify(scheme, &signed, &signature),
+                Ok(()),
+                "{}",
+                scheme
+            );
+
+            // another message, or another scheme with the same hash
+            assert_eq!(
+                key.verify(scheme, &signed[1..], &signature),
+                Err(AlertDescription::decrypt_error)
+            );
+            let other = match scheme {
+                SignatureScheme::rsa_pkcs1_sha256 => SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256 => SignatureScheme::rsa_pkcs1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha384 => SignatureScheme::rsa_pkcs1_sha384,
+                _ => SignatureScheme::rsa_pss_rsae_sha384,
+            };
+            assert_eq!(
+                key.verify(other, &signed, &signature),
+                Err(AlertDescription::decrypt_error)
+            );
+        }
+
+        // a truncated signature, n itself, and schemes which aren't RSA or aren't checked
+        let signature = from_hex(&SIGNATURES[0].1);
+        for (scheme, signature) in [
+            (SignatureScheme::rsa_pkcs1_sha256, &signature[1..]),
+            (SignatureScheme::rsa_pkcs1_sha256, &key.n),
+        ] {
+            assert_eq!(
+                key.verify(scheme, &signed, signature),
+                Err(AlertDescription::decrypt_error)
+            );
+        }
+        for scheme in [
+            SignatureScheme::ecdsa_secp256r1_sha256,
+            SignatureScheme::rsa_pkcs1_sha512,
+            SignatureScheme::rsa_pss_pss_sha256,
+        ] {
+            assert_eq!(
+                key.verify(scheme, &signed, &signature),
+                Err(AlertDescription::illegal_parameter)
+            );
+        }
+    }
+
+    // a 1025-bit key: the encoded message of PSS is a byte shorter than n, without unused
+    // bits. The second signature is of an encoded message with a leading 0x80 byte
+    #[test]
+    fn pss_whole_bytes() {
+        let key = RsaPublicKey {
+            n: from_hex(&[
+                "0183c7509f424cfd37e6e4e8c00e7add96ef5057518a4463b28f43f94734a0a3",
+                "00a9cf43483d98b67a378ff8602a74d5ca8ee47d34257b85d5e20054e74ad2ab",
+                "e0891e48a3dc86df7b965efc9a04450feb6e9e880f97b27ebb61a992083e03b0",
+                "5817afe66fbdd0822d2a3e9b3fe3a72df15d5f70ea9eeacf09854a01b6df3297",
+                "c3",
+            ]),
+            e: vec![0x01, 0x00, 0x01],
+        };
+        assert_eq!(key.bits(), 1025);
+
+        let signature = from_hex(&[
+            "009276227e78578fd7c2cd4c2b2ef7fb51f4830fe64b00468f04a73c1ef41011",
+            "4c1b5543e77e8aba13244a0992f8148093b16ccefe5fff910a0bff7317c2be32",
+            "52b3605bd514f7791daa4d7c7ce89a690f52578ab3658a6d391df60073a91893",
+            "e67bd4b9aa931dba304a91994f6a7412f5ed3efa162019a9c241824bdc83463a",
+            "2b",
+        ]);
+        let scheme = SignatureScheme::rsa_pss_rsae_sha256;
+        assert_eq!(key.verify(scheme, &signed(), &signature), Ok(()));
+
+        let crafted = from_hex(&[
+            "010f4e070110297b0abf8210de1fdd6973c9a86989da508d0d15303c2af7bba3",
+            "98ce9b557ad0361c9d2bcb51bed1a0519c4fccf19768b609fd81bf5ad67a88dd",
+            "ac7547792f7a6734cc41fd64ca1572d7f888dd2e0ad3e2c09591d61066d24702",
+            "28b8bd338b2dad8d51fd37f6975832c35f791215347839cfec90f34e6150eae6",
+            "4c",
+        ]);
+        assert_eq!(
+            key.verify(scheme, &signed(), &crafted),
+            Err(AlertDescription::decrypt_error)
+        );
+    }
+
+    #[test]
+    fn invalid_keys() {
+        // an even n, and e = 1
+        let mut even = vec![0xFF; 64];
+        even[63] = 0xFE;
+        for (n, e) in [(even, vec![3]), (vec![0xFF; 64], vec![1])] {
+            let key = RsaPublicKey { n, e };
+            assert_eq!(
+                key.encrypt_pkcs1(&[1; 48]),
+                Err(AlertDescription::illegal_parameter)
+            );
+        }
+
+        // a 464-bit key is too short for a premaster secret
+        let key = RsaPublicKey {
+            n: vec![0xFF; 58],
+            e: vec![3],
+        };
+        assert_eq!(
+            key.encrypt_pkcs1(&pre_master_secret(TLS12)),
+            Err(AlertDescription::illegal_parameter)
+        );
+
+        // not an RSA key
+        let cert = crate::x509::certificate::test_certificate(&[]);
+        assert!(RsaPublicKey::from_certificate(&cert).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+// X25519 key agreement: https://datatracker.ietf.org/doc/html/rfc7748#section-5. Field elements
+// are 16 limbs of 16 bits, as in TweetNaCl, and all operations run in constant time
+use crate::alert::alert::AlertDescription;
+use crate::crypto::kx::{check_x25519_public, check_x25519_shared};
+use crate::handshake::client_hello::{KeyShareEntry, NamedGroup};
+
+type Fe = [i64; 16];
+
+// propagate carries so each limb fits in 16 bits, 2^256 being 38 mod p
+fn carry(o: &mut Fe) {
+    for i in 0..16 {
+        o[i] += 1 << 16;
+        let c = o[i] >> 16;
+        if i < 15 {
+            o[i + 1] += c - 1;
+        } else {
+            o[0] += 38 * (c - 1);
+        }
+        o[i] -= c << 16;
+    }
+}
+
+// swap p and q if b is 1
+fn swap(p: &mut Fe, q: &mut Fe, b: i64) {
+    let mask = !(b - 1);
+    for i in 0..16 {
+        let t = mask & (p[i] ^ q[i]);
+        p[i] ^= t;
+        q[i] ^= t;
+    }
+}
+
+fn unpack(bytes: &[u8; 32]) -> Fe {
+    let mut o = [0; 16];
+    for i in 0..16 {
+        o[i] = bytes[2 * i] as i64 + ((bytes[2 * i + 1] as i64) << 8);
+    }
+    o[15] &= 0x7FFF;
+    o
+}
+
+// the canonical encoding, below p
+fn pack(n: &Fe) -> [u8; 32] {
+    let mut t = *n;
+    carry(&mut t);
+    carry(&mut t);
+    carry(&mut t);
+
+    for _ in 0..2 {
+        let mut m = [0i64; 16];
+        m[0] = t[0] - 0xFFED;
+        for i in 1..15 {
+            m[i] = t[i] - 0xFFFF - ((m[i - 1] >> 16) & 1);
+            m[i - 1] &= 0xFFFF;
+        }
+        m[15] = t[15] - 0x7FFF - ((m[14] >> 16) & 1);
+        let b = (m[15] >> 16) & 1;
+        m[14] &= 0xFFFF;
+        swap(&mut t, &mut m, 1 - b);
+    }
+
+    let mut o = [0u8; 32];
+    for i in 0..16 {
+        o[2 * i] = t[i] as u8;
+        o[2 * i + 1] = (t[i] >> 8) as u8;
+    }
+    o
+}
+
+fn add(a: &Fe, b: &Fe) -> Fe {
+    std::array::from_fn(|i| a[i] + b[i])
+}
+
+fn sub(a: &Fe, b: &Fe) -> Fe {
+    std::array::from_fn(|i| a[i] - b[i])
+}
+
+fn mul(a: &Fe, b: &Fe) -> Fe {
+    let mut t = [0i64; 31];
+    for i in 0..16 {
+        for j in 0..16 {
+            t[i + j] += a[i] * b[j];
+        }
+    }
+    for i in 0..15 {
+        t[i] += 38 * t[i + 16];
+    }
+
+    let mut o: Fe = std::array::from_fn(|i| t[i]);
+    carry(&mut o);
+    carry(&mut o);
+    o
+}
+
+// a^(p - 2)
+fn invert(a: &Fe) -> Fe {
+    let mut c = *a;
+    for i in (0..=253).rev() {
+        c = mul(&c, &c);
+        if i != 2 && i != 4 {
+            c = mul(&c, a);
+        }
+    }
+    c
+}
+
+// the Montgomery ladder of https://datatracker.ietf.org/doc/html/rfc7748#section-5
+pub fn x25519(scalar: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] = (k[31] & 127) | 64;
+
+    let x1 = unpack(u);
+    let a24: Fe = [0xDB41, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let (mut x2, mut z2, mut x3, mut z3) = ([0; 16], [0; 16], x1, [0; 16]);
+    x2[0] = 1;
+    z3[0] = 1;
+
+    for i in (0..255).rev() {
+        let bit = ((k[i >> 3] >> (i & 7)) & 1) as i64;
+        swap(&mut x2, &mut x3, bit);
+        swap(&mut z2, &mut z3, bit);
+
+        let a = add(&x2, &z2);
+        let aa = mul(&a, &a);
+        let b = sub(&x2, &z2);
+        let bb = mul(&b, &b);
+        let e = sub(&aa, &bb);
+        let c = add(&x3, &z3);
+        let d = sub(&x3, &z3);
+        let da = mul(&d, &a);
+        let cb = mul(&c, &b);
+
+        let t = add(&da, &cb);
+        x3 = mul(&t, &t);
+        let t = sub(&da, &cb);
+        z3 = mul(&x1, &mul(&t, &t));
+        x2 = mul(&aa, &bb);
+        z2 = mul(&e, &add(&aa, &mul(&a24, &e)));
+
+        swap(&mut x2, &mut x3, bit);
+        swap(&mut z2, &mut z3, bit);
+    }
+
+    pack(&mul(&x2, &invert(&z2)))
+}
+
+// the u-coordinate of the base point
+const BASE_POINT: [u8; 32] = {
+    let mut u = [0; 32];
+    u[0] = 9;
+    u
+};
+
+#[derive(Clone)]
+pub struct X25519KeyPair {
+    private: [u8; 32],
+    public: [u8; 32],
+}
+
+// the private key is not shown
+impl std::fmt::Debug for X25519KeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("X25519KeyPair")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+impl X25519KeyPair {
+    pub fn generate() -> Self {
+        Self::from_private(rand::random())
+    }
+
+    pub fn from_private(private: [u8; 32]) -> Self {
+        Self {
+            private,
+            public: x25519(&private, &BASE_POINT),
+        }
+    }
+
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public
+    }
+
+    // the shared secret with a peer's public key, which must not be of small order:
+    // https://datatracker.ietf.org/doc/html/rfc8446#section-7.4.2
+    pub fn agree(&self, peer: &[u8]) -> Result<[u8; 32], AlertDescription> {
+        check_x25519_public(peer)?;
+
+        // checked to be 32 bytes
+        let shared = x25519(&self.private, peer.try_into().unwrap());
+        check_x25519_shared(&shared)?;
+        Ok(shared)
+    }
+
+    // the secret with the server's share of a ServerHello
+    pub fn agree_key_share(&self, share: &KeyShareEntry) -> Result<[u8; 32], AlertDescription> {
+        if share.group != NamedGroup::x25519 {
+            return Err(AlertDescription::illegal_parameter);
+        }
+        self.agree(&share.key_exchange.data)
+    }
+
+    // the share to offer in a ClientHello
+    pub fn key_share(&self) -> KeyShareEntry {
+        KeyShareEntry::new(NamedGroup::x25519, &self.public)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::to_hex;
+
+    fn bytes(hex: &str) -> [u8; 32] {
+        std::array::from_fn(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+    }
+
+    // https://datatracker.ietf.org/doc/html/rfc7748#section-5.2
+    #[test]
+    fn rfc7748_vectors() {
+        let scalar = bytes("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let u = bytes("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        assert_eq!(
+            to_hex(&x25519(&scalar, &u)),
+            "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"
+        );
+
+        // the top bit of u is ignored
+        let scalar = bytes("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d");
+        let u = bytes("e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493");
+        assert_eq!(
+            to_hex(&x25519(&scalar, &u)),
+            "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957"
+        );
+    }
+
+    // https://datatracker.ietf.org/doc/html/rfc7748#section-6.1
+    #[test]
+    fn key_agreement() {
+        let alice = X25519KeyPair::from_private(bytes(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ));
+        let bob = X25519KeyPair::from_private(bytes(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ));
+        assert_eq!(
+            to_hex(alice.public_key()),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"
+        );
+        assert_eq!(
+            to_hex(bob.public_key()),
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"
+        );
+
+        let shared = alice.agree_key_share(&bob.key_share()).unwrap();
+        assert_eq!(
+            to_hex(&shared),
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742"
+        );
+        assert_eq!(bob.agree(alice.public_key()), Ok(shared));
+
+        // a point of small order, or a share of another group
+        assert_eq!(
+            alice.agree(&[0; 32]),
+            Err(AlertDescription::illegal_parameter)
+        );
+        let share = KeyShareEntry::new(NamedGroup::secp256r1, bob.public_key());
+        assert!(alice.agree_key_share(&share).is_err());
+
+        let generated = X25519KeyPair::generate();
+        assert_eq!(
+            generated.agree(bob.public_key()),
+            bob.agree(generated.public_key())
+        );
+        assert!(!format!("{:?}", generated).contains("private"));
+    }
+}
@@ -6,9 +6,14 @@ use std::io::Result;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::alert::alert::{AlertDescription, AlertLevel};
-use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::certificate_request::ClientCertificateType;
+use crate::handshake::client_hello::{
+    CertChainType, CertificateStatusType, ExtensionType, HeartbeatMode, IdentifierType,
+    MaxFragmentLength, NamedGroup, SctVersion, SignatureScheme, SrtpProtectionProfile,
+};
 use crate::handshake::common::{ContentType, Random, VariableLengthVector};
 use crate::handshake::handshake::HandshakeType;
+use crate::heartbeat::heartbeat::HeartbeatMessageType;
 
 use crate::{enum_from_network_bytes, enum_length, enum_to_network_bytes};
 
@@ -21,6 +26,7 @@ pub trait TlsDerive {
     fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize>;
 
     // copy structure data from a network-order buffer
+    #[allow(clippy::wrong_self_convention)]
     fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()>;
 }
 
@@ -113,6 +119,35 @@ impl TlsDerive for u32 {
     }
 }
 
+impl TlsDerive for u64 {
+    enum_length!(u64);
+
+    /// ```
+    /// use tls_explore::derive_tls::TlsDerive;
+    ///
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// assert!(0x0123456789ABCDEF_u64.to_network_bytes(&mut buffer).is_ok());
+    /// assert_eq!(buffer, &[0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+    /// ```
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        v.write_u64::<BigEndian>(*self)?;
+        Ok(8)
+    }
+    /// ```
+    /// use std::io::Cursor;
+    /// use tls_explore::derive_tls::TlsDerive;
+    ///
+    /// let mut buffer = Cursor::new(vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+    /// let mut v = 0u64;
+    /// assert!(v.from_network_bytes(&mut buffer).is_ok());
+    /// assert_eq!(v, 0x0123456789ABCDEF);
+    /// ```
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        *self = v.read_u64::<BigEndian>()?;
+        Ok(())
+    }
+}
+
 impl TlsDerive for [u8] {
     /// ```
     /// use tls_explore::derive_tls::TlsDerive;
@@ -253,6 +288,72 @@ impl TlsDerive for HandshakeType {
     enum_from_network_bytes!(HandshakeType, u8);
 }
 
+impl TlsDerive for NamedGroup {
+    enum_length!(NamedGroup);
+    enum_to_network_bytes!(NamedGroup, u16);
+    enum_from_network_bytes!(NamedGroup, u16);
+}
+
+impl TlsDerive for SignatureScheme {
+    enum_length!(SignatureScheme);
+    enum_to_network_bytes!(SignatureScheme, u16);
+    enum_from_network_bytes!(SignatureScheme, u16);
+}
+
+impl TlsDerive for CertificateStatusType {
+    enum_length!(CertificateStatusType);
+    enum_to_network_bytes!(CertificateStatusType);
+    enum_from_network_bytes!(CertificateStatusType, u8);
+}
+
+impl TlsDerive for IdentifierType {
+    enum_length!(IdentifierType);
+    enum_to_network_bytes!(IdentifierType);
+    enum_from_network_bytes!(IdentifierType, u8);
+}
+
+impl TlsDerive for CertChainType {
+    enum_length!(CertChainType);
+    enum_to_network_bytes!(CertChainType);
+    enum_from_network_bytes!(CertChainType, u8);
+}
+
+impl TlsDerive for MaxFragmentLength {
+    enum_length!(MaxFragmentLength);
+    enum_to_network_bytes!(MaxFragmentLength);
+    enum_from_network_bytes!(MaxFragmentLength, u8);
+}
+
+impl TlsDerive for HeartbeatMode {
+    enum_length!(HeartbeatMode);
+    enum_to_network_bytes!(HeartbeatMode);
+    enum_from_network_bytes!(HeartbeatMode, u8);
+}
+
+impl TlsDerive for SrtpProtectionProfile {
+    enum_length!(SrtpProtectionProfile);
+    enum_to_network_bytes!(SrtpProtectionProfile, u16);
+    enum_from_network_bytes!(SrtpProtectionProfile, u16);
+}
+
+impl TlsDerive for SctVersion {
+    enum_length!(SctVersion);
+    enum_to_network_bytes!(SctVersion);
+    enum_from_network_bytes!(SctVersion, u8);
+}
+
+impl TlsDerive for ClientCertificateType {
+    enum_length!(ClientCertificateType);
+    enum_to_network_bytes!(ClientCertificateType);
+    enum_from_network_bytes!(ClientCertificateType, u8);
+}
+
+impl TlsDerive for HeartbeatMessageType {
+    enum_length!(HeartbeatMessageType);
+    enum_to_network_bytes!(HeartbeatMessageType);
+    enum_from_network_bytes!(HeartbeatMessageType, u8);
+}
+
 impl TlsDerive for AlertDescription {
     enum_length!(AlertDescription);
     enum_to_network_bytes!(AlertDescription);
@@ -265,10 +366,21 @@ impl TlsDerive for AlertLevel {
     enum_from_network_bytes!(AlertLevel, u8);
 }
 
+// the Unknown variant makes the enum larger than its code, and can't be cast
 impl TlsDerive for ExtensionType {
-    enum_length!(ExtensionType);
-    enum_to_network_bytes!(ExtensionType);
-    enum_from_network_bytes!(ExtensionType, u8);
+    fn tls_len(&self) -> usize {
+        std::mem::size_of::<u16>()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        v.write_u16::<BigEndian>(u16::from(*self))?;
+        Ok(2)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        *self = ExtensionType::from(v.read_u16::<BigEndian>()?);
+        Ok(())
+    }
 }
 
 impl<T: TlsDerive> TlsDerive for Option<T> {
@@ -276,7 +388,7 @@ impl<T: TlsDerive> TlsDerive for Option<T> {
     /// use tls_explore::derive_tls::TlsDerive;
     ///
     /// assert_eq!(Some(0xFF_u8).tls_len(), 1);
-   ///
+    ///
     /// let r: Option<u8> = None;
     /// assert_eq!(r.tls_len(), 0);
     /// ```     
@@ -343,7 +455,7 @@ where
     /// assert_eq!(v.tls_len(), 2+3*2*3);
     /// ```
     fn tls_len(&self) -> usize {
-        BYTES as usize + self.data.len() * std::mem::size_of::<T>()
+        BYTES as usize + self.data.iter().map(|x| x.tls_len()).sum::<usize>()
     }
 
     /// ```
@@ -388,7 +500,6 @@ where
     /// assert_eq!(v.length, 4u32);
     /// assert_eq!(v.data, &[0x1234, 0x5678]);
     /// ```
-
     fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
         debug_assert!(BYTES <= 3);
 
@@ -397,7 +508,7 @@ where
         let mut buffer = [0u8; 4];
 
         // read BYTES bytes from cursor
-        v.take(BYTES as u64).read(&mut buffer)?;
+        v.read_exact(&mut buffer[..BYTES as usize])?;
 
         // build a new buffer to be used with from_be_bytes()
         match BYTES {
@@ -410,14 +521,25 @@ where
         // convert to big endian
         self.length = u32::from_be_bytes(buffer);
 
-        // the length field holds the length of data field in bytes
-        let length = self.length / std::mem::size_of::<T>() as u32;
-        println!("length={}", length);
-        for _ in 0..length {
+        // the length field holds the length of data field in bytes, so read elements
+        // until those bytes are consumed: elements are not necessarily fixed-size
+        let end = v.position() + self.length as u64;
+        while v.position() < end {
             let mut u: T = T::default();
             u.from_network_bytes(v)?;
             self.data.push(u);
         }
+
+        // last element shouldn't go past the announced length
+        if v.position() != end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "vector elements exceed the vector length of {} bytes",
+                    self.length
+                ),
+            ));
+        }
         Ok(())
     }
 }
@@ -476,25 +598,25 @@ where
     }
 }
 
-/// ```
-    /// use tls_explore::derive_tls::TlsDerive;
-/// use tls_explore::handshake::common::VariableLengthVector;
-/// use tls_derive::TlsDerive;
-///
-/// let mut vlv: VariableLengthVector<Box<dyn TlsDerive>, 1, 2> = VariableLengthVector::default();
-///
-/// #[derive(TlsDerive)] struct A { x: u16, y: u16 }
-/// #[derive(TlsDerive)] struct B { a: Option<[u16;3]>, b: Vec<u16> }
-///
-/// vlv = VariableLengthVector {
-///     length: 0,  
-///     data: vec! [
-///         Box::new(A { x: 0x1234, y: 0x5678 }),
-///         Box::new(B { a: Some([0x1234, 0x5678, 0x9ABC]), b: vec![0x1234, 0x5678] })
-///     ]
-/// }
-///
-/// ```
+// ```
+// use tls_explore::derive_tls::TlsDerive;
+// use tls_explore::handshake::common::VariableLengthVector;
+// use tls_derive::TlsDerive;
+//
+// let mut vlv: VariableLengthVector<Box<dyn TlsDerive>, 1, 2> = VariableLengthVector::default();
+//
+// #[derive(TlsDerive)] struct A { x: u16, y: u16 }
+// #[derive(TlsDerive)] struct B { a: Option<[u16;3]>, b: Vec<u16> }
+//
+// vlv = VariableLengthVector {
+//     length: 0,
+//     data: vec! [
+//         Box::new(A { x: 0x1234, y: 0x5678 }),
+//         Box::new(B { a: Some([0x1234, 0x5678, 0x9ABC]), b: vec![0x1234, 0x5678] })
+//     ]
+// }
+//
+// ```
 // impl<const MIN: u8, const BYTES: u8> TlsDerive
 //     for VariableLengthVector<Box<dyn TlsDerive>, MIN, BYTES>
 // {
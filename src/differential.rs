@@ -0,0 +1,247 @@
+// differential testing of the parsers: the same handshake messages are given to two parsers,
+// and the inputs one accepts and the other rejects are reported. Either side can be this
+// crate's strict structures, its lenient summaries, or an external program wrapping another
+// implementation, e.g. rustls' or OpenSSL's ClientHello parser. Inputs are the corpus hellos,
+// truncated and with single bytes changed
+use std::io::{Cursor, Result, Write};
+use std::process::{Command, Stdio};
+
+use crate::corpus::seeds;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::{ClientHelloSummary, ServerHelloSummary};
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::client_hello::ClientHello;
+use crate::handshake::handshake::HandshakeType;
+use crate::handshake::server_hello::ServerHello;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageKind {
+    ClientHello,
+    ServerHello,
+}
+
+impl MessageKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MessageKind::ClientHello => "client_hello",
+            MessageKind::ServerHello => "server_hello",
+        }
+    }
+
+    fn from_type(msg_type: u8) -> Option<Self> {
+        match HandshakeType::try_from(msg_type) {
+            Ok(HandshakeType::client_hello) => Some(MessageKind::ClientHello),
+            Ok(HandshakeType::server_hello) => Some(MessageKind::ServerHello),
+            _ => None,
+        }
+    }
+}
+
+pub trait Parser {
+    fn name(&self) -> &str;
+
+    // body is the handshake message without its header. An error means the parser couldn't
+    // be run, not that the input was rejected
+    fn accepts(&self, kind: MessageKind, body: &[u8]) -> Result<bool>;
+}
+
+// the whole body is decoded, with no trailing data
+fn decode<T: TlsDerive + Default>(body: &[u8]) -> Option<T> {
+    let mut cursor = Cursor::new(body.to_vec());
+    let mut message = T::default();
+    message.from_network_bytes(&mut cursor).ok()?;
+    (cursor.position() as usize == body.len()).then_some(message)
+}
+
+// the structures of the handshake module, extensions included
+#[derive(Debug)]
+pub struct StrictParser;
+
+impl Parser for StrictParser {
+    fn name(&self) -> &str {
+        "strict"
+    }
+
+    fn accepts(&self, kind: MessageKind, body: &[u8]) -> Result<bool> {
+        Ok(match kind {
+            MessageKind::ClientHello => {
+                decode::<ClientHello>(body).is_some_and(|x| x.parsed_extensions().is_ok())
+            }
+            MessageKind::ServerHello => {
+                decode::<ServerHello>(body).is_some_and(|x| x.parsed_extensions().is_ok())
+            }
+        })
+    }
+}
+
+// the summaries of the dissector
+#[derive(Debug)]
+pub struct LenientParser;
+
+impl Parser for LenientParser {
+    fn name(&self) -> &str {
+        "lenient"
+    }
+
+    fn accepts(&self, kind: MessageKind, body: &[u8]) -> Result<bool> {
+        Ok(match kind {
+            MessageKind::ClientHello => ClientHelloSummary::parse(body).is_ok(),
+            MessageKind::ServerHello => ServerHelloSummary::parse(body).is_ok(),
+        })
+    }
+}
+
+// a program run for each input, with the kind name as last argument and the body on its
+// standard input. It accepts the input by exiting with 0
+#[derive(Debug, Clone)]
+pub struct CommandParser {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CommandParser {
+    pub fn new(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            args: Vec::new(),
+        }
+    }
+}
+
+impl Parser for CommandParser {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn accepts(&self, kind: MessageKind, body: &[u8]) -> Result<bool> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .arg(kind.name())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // the program can exit without reading everything
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(body);
+        }
+        Ok(child.wait()?.success())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub kind: MessageKind,
+    pub input: Vec<u8>,
+    pub accepted_by: String,
+    pub rejected_by: String,
+}
+
+// the seed cut at each multiple of step, and with each byte inverted
+pub fn mutations(seed: &[u8], step: usize) -> Vec<Vec<u8>> {
+    let mut inputs: Vec<_> = (0..seed.len())
+        .step_by(step.max(1))
+        .map(|length| seed[..length].to_vec())
+        .collect();
+
+    for i in 0..seed.len() {
+        let mut input = seed.to_vec();
+        input[i] ^= 0xFF;
+        inputs.push(input);
+    }
+    inputs
+}
+
+// the ClientHello and ServerHello bodies of the corpus
+pub fn corpus_hellos() -> Result<Vec<(MessageKind, Vec<u8>)>> {
+    let mut hellos = Vec::new();
+    for seed in seeds()? {
+        for message in handshake_messages(&records(&seed.bytes)) {
+            if let Some(kind) = MessageKind::from_type(message.msg_type) {
+                hellos.push((kind, message.body));
+            }
+        }
+    }
+    Ok(hellos)
+}
+
+// the inputs on which the parsers disagree
+pub fn compare(
+    left: &dyn Parser,
+    right: &dyn Parser,
+    inputs: &[(MessageKind, Vec<u8>)],
+) -> Result<Vec<Divergence>> {
+    let mut divergences = Vec::new();
+
+    for (kind, input) in inputs {
+        let (accepted_by, rejected_by) =
+            match (left.accepts(*kind, input)?, right.accepts(*kind, input)?) {
+                (true, false) => (left, right),
+                (false, true) => (right, left),
+                _ => continue,
+            };
+        divergences.push(Divergence {
+            kind: *kind,
+            input: input.clone(),
+            accepted_by: accepted_by.name().to_string(),
+            rejected_by: rejected_by.name().to_string(),
+        });
+    }
+
+    Ok(divergences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::common::is_grease;
+
+    #[test]
+    fn corpus() {
+        let hellos = corpus_hellos().unwrap();
+        assert!(hellos.iter().any(|x| x.0 == MessageKind::ServerHello));
+
+        // valid messages are accepted by both sides, except GREASE groups which NamedGroup
+        // can't hold
+        let divergences = compare(&StrictParser, &LenientParser, &hellos).unwrap();
+        assert!(!divergences.is_empty());
+        for divergence in &divergences {
+            assert_eq!(divergence.accepted_by, "lenient");
+            let summary = ClientHelloSummary::parse(&divergence.input).unwrap();
+            assert!(summary.supported_groups.iter().any(|x| is_grease(*x)));
+        }
+
+        // truncated hellos are rejected by both, unless cut after the compression methods
+        let (kind, seed) = hellos[0].clone();
+        let inputs: Vec<_> = mutations(&seed, 16)
+            .into_iter()
+            .map(|x| (kind, x))
+            .collect();
+        assert_eq!(inputs.len(), seed.len().div_ceil(16) + seed.len());
+        compare(&StrictParser, &LenientParser, &inputs).unwrap();
+    }
+
+    #[test]
+    fn command() {
+        let inputs = vec![(MessageKind::ClientHello, vec![0x03, 0x03])];
+        let divergences = compare(&LenientParser, &CommandParser::new("true"), &inputs).unwrap();
+        assert_eq!(
+            divergences,
+            vec![Divergence {
+                kind: MessageKind::ClientHello,
+                input: vec![0x03, 0x03],
+                accepted_by: "true".to_string(),
+                rejected_by: "lenient".to_string(),
+            }]
+        );
+        assert!(
+            compare(&LenientParser, &CommandParser::new("false"), &inputs)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(CommandParser::new("/nonexistent")
+            .accepts(MessageKind::ClientHello, &[])
+            .is_err());
+    }
+}
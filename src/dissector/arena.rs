@@ -0,0 +1,183 @@
+// arena dissection: everything parsed from a connection lives in one region, and records and
+// handshake messages are views into it. Reusing the arena for each connection of a capture
+// frees all the nodes of the previous one at once and keeps allocations to a few buffers
+// which grow to the largest connection seen
+use std::ops::Range;
+
+use crate::handshake::common::{ContentType, ProtocolVersion};
+
+#[derive(Debug, Clone)]
+struct RecordNode {
+    content_type: u8,
+    version: ProtocolVersion,
+    fragment: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct MessageNode {
+    msg_type: u8,
+    body: Range<usize>,
+}
+
+// borrowed counterpart of Record
+#[derive(Debug, Clone, Copy)]
+pub struct RecordRef<'a> {
+    pub content_type: u8,
+    pub version: ProtocolVersion,
+    pub fragment: &'a [u8],
+}
+
+// borrowed counterpart of HandshakeMessage
+#[derive(Debug, Clone, Copy)]
+pub struct MessageRef<'a> {
+    pub msg_type: u8,
+    pub body: &'a [u8],
+}
+
+#[derive(Debug, Default)]
+pub struct ConnectionArena {
+    // the stream, followed by the handshake bytes of its records
+    region: Vec<u8>,
+    records: Vec<RecordNode>,
+    messages: Vec<MessageNode>,
+}
+
+impl ConnectionArena {
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            region: Vec::with_capacity(2 * bytes),
+            ..Default::default()
+        }
+    }
+
+    // free all the nodes of the previous connection, keeping the memory for the next one
+    pub fn clear(&mut self) {
+        self.region.clear();
+        self.records.clear();
+        self.messages.clear();
+    }
+
+    // bytes held by the arena, used or not
+    pub fn capacity(&self) -> usize {
+        self.region.capacity()
+    }
+
+    // same rules as records() and handshake_messages()
+    pub fn dissect(&mut self, stream: &[u8]) {
+        self.clear();
+        self.region.extend_from_slice(stream);
+
+        let mut offset = 0;
+        while stream.len() - offset >= 5 {
+            let data = &stream[offset..];
+            let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+            if data.len() < 5 + length {
+                break;
+            }
+
+            self.records.push(RecordNode {
+                content_type: data[0],
+                version: [data[1], data[2]],
+                fragment: offset + 5..offset + 5 + length,
+            });
+            offset += 5 + length;
+        }
+
+        // handshake messages can span records: their bytes are made contiguous
+        let start = self.region.len();
+        for record in &self.records {
+            match ContentType::try_from(record.content_type) {
+                Ok(ContentType::handshake) => {
+                    self.region.extend_from_within(record.fragment.clone())
+                }
+                Ok(ContentType::change_cipher_spec) => break,
+                _ => (),
+            }
+        }
+
+        let mut offset = start;
+        while self.region.len() - offset >= 4 {
+            let data = &self.region[offset..];
+            let length = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+            if data.len() < 4 + length {
+                break;
+            }
+
+            self.messages.push(MessageNode {
+                msg_type: data[0],
+                body: offset + 4..offset + 4 + length,
+            });
+            offset += 4 + length;
+        }
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = RecordRef<'_>> {
+        self.records.iter().map(|x| RecordRef {
+            content_type: x.content_type,
+            version: x.version,
+            fragment: &self.region[x.fragment.clone()],
+        })
+    }
+
+    pub fn handshake_messages(&self) -> impl Iterator<Item = MessageRef<'_>> {
+        self.messages.iter().map(|x| MessageRef {
+            msg_type: x.msg_type,
+            body: &self.region[x.body.clone()],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::{handshake_messages, records};
+
+    #[test]
+    fn same_as_dissector() {
+        let stream = vec![
+            0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0x00, 0x00, 0x03, // header
+            0x16, 0x03, 0x03, 0x00, 0x03, 0x01, 0x02, 0x03, // body
+            0x16, 0x03, 0x03, 0x00, 0x09, 0x0B, 0x00, 0x00, 0x01, 0xFF, 0x0E, 0x00, 0x00, 0x00,
+            0x14, 0x03, 0x03, 0x00, 0x01, 0x01, // ChangeCipherSpec
+            0x16, 0x03, 0x03, 0x00, 0x04, 0xDE, 0xAD, 0xBE, 0xEF, // encrypted
+            0x17, 0x03, 0x03, 0x00, 0x05, 0x00, // truncated
+        ];
+
+        let mut arena = ConnectionArena::with_capacity(stream.len());
+        arena.dissect(&stream);
+
+        let expected = records(&stream);
+        let found: Vec<_> = arena.records().collect();
+        assert_eq!(found.len(), expected.len());
+        for (x, y) in found.iter().zip(&expected) {
+            assert_eq!(x.content_type, y.content_type);
+            assert_eq!(x.version, y.version);
+            assert_eq!(x.fragment, y.fragment);
+        }
+
+        let expected = handshake_messages(&expected);
+        let found: Vec<_> = arena.handshake_messages().collect();
+        assert_eq!(found.len(), 3);
+        for (x, y) in found.iter().zip(&expected) {
+            assert_eq!(x.msg_type, y.msg_type);
+            assert_eq!(x.body, y.body);
+        }
+    }
+
+    #[test]
+    fn reuse() {
+        let mut arena = ConnectionArena::default();
+        arena.dissect(&[0x16, 0x03, 0x03, 0x00, 0x05, 0x01, 0x00, 0x00, 0x01, 0xAA]);
+        assert_eq!(arena.handshake_messages().count(), 1);
+        let capacity = arena.capacity();
+
+        // the memory of the first connection is reused
+        arena.dissect(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28]);
+        assert_eq!(arena.records().count(), 1);
+        assert_eq!(arena.handshake_messages().count(), 0);
+        assert_eq!(arena.capacity(), capacity);
+
+        arena.clear();
+        assert_eq!(arena.records().count(), 0);
+    }
+}
@@ -0,0 +1,365 @@
+// assess what can be established about a handshake when the capture stops before its end:
+// instead of failing, keep the candidates still possible for the version and cipher suite
+use std::fmt;
+
+use crate::dissector::summary::ClientHelloSummary;
+use crate::handshake::common::{is_grease, version_name, ContentType, ProtocolVersion};
+use crate::handshake::constants::{TLS12, TLS13};
+use crate::handshake::handshake::HandshakeType;
+
+// how much is known about a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Certainty {
+    // a single value, seen on the wire
+    Confirmed,
+    // several candidates remain
+    Partial,
+    // nothing could be established
+    Unknown,
+}
+
+impl fmt::Display for Certainty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Certainty::Confirmed => write!(f, "confirmed"),
+            Certainty::Partial => write!(f, "partial"),
+            Certainty::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl Certainty {
+    fn from_candidates<T>(candidates: &[T]) -> Self {
+        match candidates.len() {
+            0 => Certainty::Unknown,
+            1 => Certainty::Confirmed,
+            _ => Certainty::Partial,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HandshakeAssessment {
+    // how much of the hellos was captured
+    pub client_hello: Certainty,
+    pub server_hello: Certainty,
+
+    pub versions: Vec<ProtocolVersion>,
+    pub version_certainty: Certainty,
+
+    pub cipher_suites: Vec<u16>,
+    pub cipher_suite_certainty: Certainty,
+
+    pub server_name: Option<String>,
+}
+
+// the fields of a ServerHello which could be read
+#[derive(Debug, Default)]
+struct PartialServerHello {
+    server_version: Option<ProtocolVersion>,
+    cipher_suite: Option<u16>,
+
+    // selected version from supported_versions
+    selected_version: Option<ProtocolVersion>,
+
+    // true if all extensions were read
+    complete: bool,
+}
+
+impl HandshakeAssessment {
+    // client and server are the raw TLS streams sent by each peer, possibly truncated
+    pub fn assess(client: &[u8], server: &[u8]) -> Self {
+        let client_hello = first_handshake_message(client, HandshakeType::client_hello);
+        let server_hello = first_handshake_message(server, HandshakeType::server_hello);
+
+        let (summary, client_certainty) = match &client_hello {
+            Some((body, complete)) => {
+                let (summary, parsed) = ClientHelloSummary::parse_partial(body);
+                let certainty = if *complete && parsed {
+                    Certainty::Confirmed
+                } else {
+                    Certainty::Partial
+                };
+                (Some(summary), certainty)
+            }
+            None => (None, Certainty::Unknown),
+        };
+
+        let (server, server_certainty) = match &server_hello {
+            Some((body, complete)) => {
+                let sh = parse_server_hello(body);
+                let certainty = if *complete && sh.complete {
+                    Certainty::Confirmed
+                } else {
+                    Certainty::Partial
+                };
+                (sh, certainty)
+            }
+            None => (PartialServerHello::default(), Certainty::Unknown),
+        };
+
+        // versions: what the server selected, or else what the client offered
+        let offered = summary
+            .as_ref()
+            .map(|s| offered_versions(s, client_certainty == Certainty::Confirmed))
+            .unwrap_or_default();
+        let versions = match (server.selected_version, server.server_version) {
+            (Some(version), _) => vec![version],
+            // legacy_version of a TLS 1.3 ServerHello is TLS 1.2
+            (None, Some(TLS12)) if !server.complete && offered.contains(&TLS13) => {
+                vec![TLS12, TLS13]
+            }
+            (None, Some(version)) => vec![version],
+            (None, None) => offered,
+        };
+
+        // cipher suites: what the server selected, or else the offered ones usable
+        // with the remaining versions
+        let cipher_suites = match server.cipher_suite {
+            Some(suite) => vec![suite],
+            None => summary
+                .as_ref()
+                .map(|s| {
+                    s.cipher_suites
+                        .iter()
+                        .copied()
+                        .filter(|x| !is_grease(*x) && usable(*x, &versions))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+
+        Self {
+            client_hello: client_certainty,
+            server_hello: server_certainty,
+            version_certainty: Certainty::from_candidates(&versions),
+            versions,
+            cipher_suite_certainty: Certainty::from_candidates(&cipher_suites),
+            cipher_suites,
+            server_name: summary.and_then(|s| s.server_name),
+        }
+    }
+
+    // between 0 (nothing known) and 1 (version and cipher suite confirmed)
+    pub fn score(&self) -> f64 {
+        let confidence = |candidates: usize| {
+            if candidates == 0 {
+                0.0
+            } else {
+                1.0 / candidates as f64
+            }
+        };
+
+        (confidence(self.versions.len()) + confidence(self.cipher_suites.len())) / 2.0
+    }
+}
+
+// the candidates, e.g. "versions TLS 1.2, TLS 1.3 (partial), cipher suites 0xC02F (confirmed),
+// score 0.75"
+impl fmt::Display for HandshakeAssessment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions: Vec<_> = self.versions.iter().map(|x| version_name(*x)).collect();
+        let suites: Vec<_> = self
+            .cipher_suites
+            .iter()
+            .map(|x| format!("0x{:04X}", x))
+            .collect();
+        write!(
+            f,
+            "versions {} ({}), cipher suites {} ({}), score {:.2}",
+            versions.join(", "),
+            self.version_certainty,
+            suites.join(", "),
+            self.cipher_suite_certainty,
+            self.score()
+        )
+    }
+}
+
+// the body of the first handshake message of the given type, even if it's truncated.
+// The boolean is true if the message is complete
+fn first_handshake_message(stream: &[u8], msg_type: HandshakeType) -> Option<(Vec<u8>, bool)> {
+    // concatenate handshake fragments, keeping the truncated last record
+    let mut buffer = Vec::new();
+    let mut data = stream;
+    while data.len() >= 5 {
+        let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+        let end = (5 + length).min(data.len());
+
+        match ContentType::try_from(data[0]) {
+            Ok(ContentType::handshake) => buffer.extend_from_slice(&data[5..end]),
+            Ok(ContentType::change_cipher_spec) => break,
+            _ => (),
+        }
+        data = &data[end..];
+    }
+
+    let mut data = &buffer[..];
+    while data.len() >= 4 {
+        let length = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+        let end = (4 + length).min(data.len());
+
+        if data[0] == msg_type as u8 {
+            return Some((data[4..end].to_vec(), end == 4 + length));
+        }
+        data = &data[end..];
+    }
+
+    None
+}
+
+fn parse_server_hello(body: &[u8]) -> PartialServerHello {
+    let mut sh = PartialServerHello {
+        server_version: body.get(0..2).map(|x| [x[0], x[1]]),
+        ..Default::default()
+    };
+
+    // skip version and random
+    let Some(&session_id_length) = body.get(34) else {
+        return sh;
+    };
+    let offset = 35 + session_id_length as usize;
+
+    sh.cipher_suite = body
+        .get(offset..offset + 2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]));
+
+    // skip the compression method
+    let offset = offset + 3;
+    if offset == body.len() {
+        sh.complete = true;
+        return sh;
+    }
+    let Some(length) = body.get(offset..offset + 2) else {
+        return sh;
+    };
+    let end = offset + 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+
+    let mut offset = offset + 2;
+    while let Some(header) = body.get(offset..offset + 4) {
+        let extension_type = u16::from_be_bytes([header[0], header[1]]);
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let Some(data) = body.get(offset + 4..offset + 4 + length) else {
+            return sh;
+        };
+
+        // supported_versions
+        if extension_type == 43 && data.len() == 2 {
+            sh.selected_version = Some([data[0], data[1]]);
+        }
+        offset += 4 + length;
+    }
+
+    sh.complete = offset == end && end == body.len();
+    sh
+}
+
+// versions the client is willing to negotiate. Without the extensions, the client
+// might have sent supported_versions so TLS 1.3 can't be ruled out
+fn offered_versions(summary: &ClientHelloSummary, complete: bool) -> Vec<ProtocolVersion> {
+    let offered: Vec<_> = summary
+        .supported_versions
+        .iter()
+        .copied()
+        .filter(|x| !is_grease(u16::from_be_bytes(*x)))
+        .collect();
+    if !offered.is_empty() {
+        return offered;
+    }
+
+    let client_version = summary.client_version;
+    if client_version < [0x03, 0x00] {
+        return Vec::new();
+    }
+
+    let mut versions: Vec<_> = (0..=client_version[1]).map(|minor| [0x03, minor]).collect();
+    if !complete && client_version == TLS12 {
+        versions.push(TLS13);
+    }
+    versions
+}
+
+// TLS 1.3 cipher suites (0x13XX) can't be used with previous versions, and vice versa
+fn usable(suite: u16, versions: &[ProtocolVersion]) -> bool {
+    if suite >> 8 == 0x13 {
+        versions.contains(&TLS13)
+    } else {
+        versions.iter().any(|v| *v < TLS13)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::{handshake, record};
+
+    fn client_stream() -> Vec<u8> {
+        record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO))
+    }
+
+    #[test]
+    fn client_hello_only() {
+        let assessment = HandshakeAssessment::assess(&client_stream(), &[]);
+
+        assert_eq!(assessment.client_hello, Certainty::Confirmed);
+        assert_eq!(assessment.server_hello, Certainty::Unknown);
+        assert_eq!(
+            assessment.versions,
+            vec![[0x03, 0x00], [0x03, 0x01], [0x03, 0x02], [0x03, 0x03]]
+        );
+        assert_eq!(assessment.version_certainty, Certainty::Partial);
+        assert_eq!(assessment.cipher_suites.len(), 16);
+        assert_eq!(
+            assessment.server_name.as_deref(),
+            Some("example.ulfheim.net")
+        );
+    }
+
+    #[test]
+    fn truncated_client_hello() {
+        let stream = client_stream();
+        let assessment = HandshakeAssessment::assess(&stream[..60], &[]);
+
+        // TLS 1.3 might have been offered in the missing extensions
+        assert_eq!(assessment.client_hello, Certainty::Partial);
+        assert!(assessment.versions.contains(&TLS13));
+        assert_eq!(assessment.cipher_suites.len(), 7);
+        assert!(assessment.server_name.is_none());
+    }
+
+    #[test]
+    fn truncated_server_hello() {
+        // ServerHello cut before its extensions
+        let mut server = vec![
+            0x16, 0x03, 0x03, 0x00, 0x5A, 0x02, 0x00, 0x00, 0x56, 0x03, 0x03,
+        ];
+        server.extend_from_slice(&[0x70; 32]);
+        server.extend_from_slice(&[0x00, 0xC0, 0x2F, 0x00, 0x00, 0x2E]);
+
+        let mut client = client_stream();
+        let assessment = HandshakeAssessment::assess(&client, &server);
+        assert_eq!(assessment.server_hello, Certainty::Partial);
+        assert_eq!(assessment.versions, vec![TLS12]);
+        assert_eq!(assessment.cipher_suites, vec![0xC02F]);
+        assert_eq!(assessment.score(), 1.0);
+
+        // when the client hello is truncated, TLS 1.3 remains possible
+        client.truncate(60);
+        let assessment = HandshakeAssessment::assess(&client, &server);
+        assert_eq!(assessment.versions, vec![TLS12, TLS13]);
+        assert_eq!(assessment.cipher_suite_certainty, Certainty::Confirmed);
+        assert_eq!(assessment.score(), 0.75);
+        assert_eq!(
+            assessment.to_string(),
+            "versions TLS 1.2, TLS 1.3 (partial), cipher suites 0xC02F (confirmed), score 0.75"
+        );
+    }
+
+    #[test]
+    fn nothing() {
+        let assessment = HandshakeAssessment::assess(&[0x16, 0x03], &[]);
+        assert_eq!(assessment.version_certainty, Certainty::Unknown);
+        assert_eq!(assessment.score(), 0.0);
+    }
+}
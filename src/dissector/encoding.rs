@@ -0,0 +1,223 @@
+// flights pasted or piped as text: hex, like xxd -p output, or base64. The text is decoded as
+// it's read, whitespace and line breaks anywhere, so it never has to be held in memory whole.
+// A limit on the decoded size stops endless or runaway inputs
+use std::io::{Error, ErrorKind, Read, Result};
+
+// text read at a time
+const CHUNK: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Hex,
+
+    // standard alphabet, padding optional: https://datatracker.ietf.org/doc/html/rfc4648#section-4
+    Base64,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hex" => Some(Encoding::Hex),
+            "base64" => Some(Encoding::Base64),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Hex => "hex",
+            Encoding::Base64 => "base64",
+        }
+    }
+
+    // bits of a character
+    fn bits(&self) -> u8 {
+        match self {
+            Encoding::Hex => 4,
+            Encoding::Base64 => 6,
+        }
+    }
+
+    fn value(&self, c: u8) -> Option<u32> {
+        let value = match (self, c) {
+            (Encoding::Hex, b'0'..=b'9') => c - b'0',
+            (Encoding::Hex, b'a'..=b'f') => c - b'a' + 10,
+            (Encoding::Hex, b'A'..=b'F') => c - b'A' + 10,
+            (Encoding::Base64, b'A'..=b'Z') => c - b'A',
+            (Encoding::Base64, b'a'..=b'z') => c - b'a' + 26,
+            (Encoding::Base64, b'0'..=b'9') => c - b'0' + 52,
+            (Encoding::Base64, b'+') => 62,
+            (Encoding::Base64, b'/') => 63,
+            _ => return None,
+        };
+        Some(value as u32)
+    }
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[derive(Debug)]
+pub struct TextDecoder<R: Read> {
+    reader: R,
+    encoding: Encoding,
+
+    // decoded bits not yet making a byte, the most recent ones in the low bits
+    bits: u32,
+    bit_count: u8,
+
+    // bytes decoded so far, and the most allowed
+    decoded: u64,
+    limit: Option<u64>,
+}
+
+impl<R: Read> TextDecoder<R> {
+    pub fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            bits: 0,
+            bit_count: 0,
+            decoded: 0,
+            limit: None,
+        }
+    }
+
+    // reading fails once more than limit bytes are decoded
+    pub fn with_limit(self, limit: u64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    // the bits left must be the padding of a complete group: none for hex, 2 or 4 for base64
+    fn end_group(&mut self) -> Result<()> {
+        let valid = match self.encoding {
+            Encoding::Hex => self.bit_count == 0,
+            Encoding::Base64 => self.bit_count != 6,
+        };
+        if !valid {
+            return Err(invalid(format!("truncated {} data", self.encoding.name())));
+        }
+        self.bits = 0;
+        self.bit_count = 0;
+        Ok(())
+    }
+
+    // a character decodes to at most one byte
+    fn decode(&mut self, c: u8) -> Result<Option<u8>> {
+        if c.is_ascii_whitespace() {
+            return Ok(None);
+        }
+        // base64 groups can follow each other, padded
+        if c == b'=' && self.encoding == Encoding::Base64 {
+            if self.bit_count != 0 {
+                self.end_group()?;
+            }
+            return Ok(None);
+        }
+
+        let value = self.encoding.value(c).ok_or_else(|| {
+            invalid(format!(
+                "invalid {} character {:?}",
+                self.encoding.name(),
+                c as char
+            ))
+        })?;
+        self.bits = (self.bits << self.encoding.bits()) | value;
+        self.bit_count += self.encoding.bits();
+
+        if self.bit_count < 8 {
+            return Ok(None);
+        }
+        self.bit_count -= 8;
+        let byte = (self.bits >> self.bit_count) as u8;
+        self.bits &= (1 << self.bit_count) - 1;
+        Ok(Some(byte))
+    }
+}
+
+impl<R: Read> Read for TextDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut text = [0u8; CHUNK];
+
+        // whitespace decodes to nothing, 0 is only returned at the end
+        while !buf.is_empty() {
+            let length = self.reader.read(&mut text[..buf.len().min(CHUNK)])?;
+            if length == 0 {
+                self.end_group()?;
+                return Ok(0);
+            }
+
+            let mut written = 0;
+            for &c in &text[..length] {
+                if let Some(byte) = self.decode(c)? {
+                    buf[written] = byte;
+                    written += 1;
+                }
+            }
+
+            self.decoded += written as u64;
+            if let Some(limit) = self.limit.filter(|x| self.decoded > *x) {
+                return Err(invalid(format!("more than {} bytes decoded", limit)));
+            }
+            if written > 0 {
+                return Ok(written);
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(text: &str, encoding: Encoding) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        TextDecoder::new(text.as_bytes(), encoding).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(
+            decode("16 0303\n00 02\tAAbb\n", Encoding::Hex).unwrap(),
+            &[0x16, 0x03, 0x03, 0x00, 0x02, 0xAA, 0xBB]
+        );
+        assert_eq!(decode("", Encoding::Hex).unwrap(), &[]);
+        assert!(decode("160", Encoding::Hex).is_err());
+        assert_eq!(
+            decode("1g", Encoding::Hex).unwrap_err().to_string(),
+            "invalid hex character 'g'"
+        );
+    }
+
+    #[test]
+    fn base64() {
+        // "TLS", "TL" and "T", padded or not, across lines and in several groups
+        assert_eq!(decode("VExT", Encoding::Base64).unwrap(), b"TLS");
+        assert_eq!(decode("VE\nw=", Encoding::Base64).unwrap(), b"TL");
+        assert_eq!(decode("VA==VExT", Encoding::Base64).unwrap(), b"TTLS");
+        assert_eq!(decode("VA", Encoding::Base64).unwrap(), b"T");
+        assert!(decode("VExTV", Encoding::Base64).is_err());
+        assert!(decode("VE-T", Encoding::Base64).is_err());
+    }
+
+    #[test]
+    fn limit() {
+        let text = "00".repeat(CHUNK * 3);
+        let mut decoder = TextDecoder::new(text.as_bytes(), Encoding::Hex).with_limit(100);
+        let e = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(e.to_string(), "more than 100 bytes decoded");
+
+        let mut decoder =
+            TextDecoder::new(text.as_bytes(), Encoding::Hex).with_limit(CHUNK as u64 * 3);
+        let mut bytes = Vec::new();
+        decoder.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), CHUNK * 3);
+    }
+}
@@ -0,0 +1,102 @@
+// the first 4 bytes of the hello randoms were gmt_unix_time:
+// https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.2, but most implementations now
+// send random bytes instead: https://datatracker.ietf.org/doc/html/draft-mathewson-no-gmtunixtime.
+// A value within the tolerance of the local time is read as the peer's clock, the others as
+// randomized, a random value having little chance to fall in the window
+use std::fmt;
+use std::time::Duration;
+
+use crate::handshake::constants::HELLO_RETRY_REQUEST_RANDOM;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HelloTime {
+    // the peer's clock, with how far ahead of the local time it is, negative if behind
+    Clock { time: u32, skew: i64 },
+    Randomized,
+}
+
+impl HelloTime {
+    // local is the time the hello was seen, e.g. its capture timestamp. None if the random is
+    // too short, or is the HelloRetryRequest marker
+    pub fn from_random(random: &[u8], local: Duration, tolerance: Duration) -> Option<Self> {
+        if random == HELLO_RETRY_REQUEST_RANDOM {
+            return None;
+        }
+        let time = u32::from_be_bytes(random.get(..4)?.try_into().unwrap());
+
+        let skew = time as i64 - local.as_secs() as i64;
+        if skew.unsigned_abs() <= tolerance.as_secs() {
+            Some(HelloTime::Clock { time, skew })
+        } else {
+            Some(HelloTime::Randomized)
+        }
+    }
+
+    // coarse groups for statistics
+    pub fn bucket(&self) -> &'static str {
+        match self {
+            HelloTime::Randomized => "randomized",
+            HelloTime::Clock { skew, .. } => match skew.unsigned_abs() {
+                0..60 => "skew < 1m",
+                60..3600 => "skew < 1h",
+                3600..86400 => "skew < 1d",
+                _ => "skew >= 1d",
+            },
+        }
+    }
+}
+
+impl fmt::Display for HelloTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HelloTime::Clock { time, skew } => write!(f, "{} (skew {:+}s)", time, skew),
+            HelloTime::Randomized => write!(f, "randomized"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello_time() {
+        let local = Duration::from_secs(1_600_000_000);
+        let tolerance = Duration::from_secs(3600);
+        let random = |time: u32| {
+            let mut random = [0xAB; 32];
+            random[..4].copy_from_slice(&time.to_be_bytes());
+            random
+        };
+
+        let time = HelloTime::from_random(&random(1_600_000_042), local, tolerance).unwrap();
+        assert_eq!(
+            time,
+            HelloTime::Clock {
+                time: 1_600_000_042,
+                skew: 42
+            }
+        );
+        assert_eq!(time.to_string(), "1600000042 (skew +42s)");
+        assert_eq!(time.bucket(), "skew < 1m");
+
+        let time = HelloTime::from_random(&random(1_599_996_400), local, tolerance).unwrap();
+        assert_eq!(time.to_string(), "1599996400 (skew -3600s)");
+        assert_eq!(time.bucket(), "skew < 1d");
+
+        // outside the tolerance
+        let time = HelloTime::from_random(&random(1_599_996_399), local, tolerance).unwrap();
+        assert_eq!(time, HelloTime::Randomized);
+        assert_eq!(time.bucket(), "randomized");
+        assert_eq!(
+            HelloTime::from_random(&random(0xABCD_EF01), local, tolerance),
+            Some(HelloTime::Randomized)
+        );
+
+        assert_eq!(
+            HelloTime::from_random(&HELLO_RETRY_REQUEST_RANDOM, local, tolerance),
+            None
+        );
+        assert_eq!(HelloTime::from_random(&[1, 2], local, tolerance), None);
+    }
+}
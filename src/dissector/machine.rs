@@ -0,0 +1,328 @@
+// sans-io dissector: bytes are fed as they arrive and complete records and handshake messages
+// come out. Nothing is read or written here, so it can be embedded in proxies or sniffers.
+// The partially received data can be saved and restored, to resume a flow after a restart
+use std::collections::VecDeque;
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use super::{HandshakeMessage, Record};
+use crate::handshake::common::ContentType;
+
+// saved state starts with a magic and a format version
+const STATE_MAGIC: &[u8; 4] = b"TLSM";
+const STATE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Record(Record),
+    Handshake(HandshakeMessage),
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TlsMachine {
+    // bytes of an incomplete record
+    record: Vec<u8>,
+
+    // handshake bytes of an incomplete message, which can span several records
+    handshake: Vec<u8>,
+
+    // once ChangeCipherSpec has been seen, handshake records are encrypted
+    encrypted: bool,
+}
+
+impl TlsMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // number of bytes waiting for the rest of a record or message
+    pub fn pending(&self) -> usize {
+        self.record.len() + self.handshake.len()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    // feed data as it's received, get the records and handshake messages it completes
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Event> {
+        self.record.extend_from_slice(data);
+
+        let mut events = Vec::new();
+        let mut consumed = 0;
+
+        while self.record.len() - consumed >= 5 {
+            let data = &self.record[consumed..];
+            let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+            if data.len() < 5 + length {
+                break;
+            }
+
+            let record = Record {
+                content_type: data[0],
+                version: [data[1], data[2]],
+                fragment: data[5..5 + length].to_vec(),
+            };
+            consumed += 5 + length;
+
+            match record.content_type() {
+                Some(ContentType::handshake) if !self.encrypted => {
+                    self.handshake.extend_from_slice(&record.fragment);
+                    events.push(Event::Record(record));
+                    self.handshake_messages(&mut events);
+                }
+                Some(ContentType::change_cipher_spec) => {
+                    self.encrypted = true;
+                    self.handshake.clear();
+                    events.push(Event::Record(record));
+                }
+                _ => events.push(Event::Record(record)),
+            }
+        }
+
+        self.record.drain(..consumed);
+        events
+    }
+
+    // the complete messages at the start of the handshake buffer
+    fn handshake_messages(&mut self, events: &mut Vec<Event>) {
+        let mut consumed = 0;
+
+        while self.handshake.len() - consumed >= 4 {
+            let data = &self.handshake[consumed..];
+            let length = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+            if data.len() < 4 + length {
+                break;
+            }
+
+            events.push(Event::Handshake(HandshakeMessage {
+                msg_type: data[0],
+                body: data[4..4 + length].to_vec(),
+            }));
+            consumed += 4 + length;
+        }
+
+        self.handshake.drain(..consumed);
+    }
+
+    // the buffered state, to be restored later with restore()
+    pub fn save(&self) -> Vec<u8> {
+        let mut v = Vec::with_capacity(self.pending() + 14);
+        v.extend_from_slice(STATE_MAGIC);
+        v.push(STATE_VERSION);
+        v.push(self.encrypted as u8);
+        v.extend_from_slice(&(self.record.len() as u32).to_be_bytes());
+        v.extend_from_slice(&self.record);
+        v.extend_from_slice(&(self.handshake.len() as u32).to_be_bytes());
+        v.extend_from_slice(&self.handshake);
+        v
+    }
+
+    pub fn restore(state: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(state);
+
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if &magic != STATE_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a saved TLS state"));
+        }
+
+        let version = cursor.read_u8()?;
+        if version != STATE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported saved state version {}", version),
+            ));
+        }
+
+        let encrypted = match cursor.read_u8()? {
+            0 => false,
+            1 => true,
+            x => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid encrypted flag {}", x),
+                ))
+            }
+        };
+        let record = read_buffer(&mut cursor)?;
+        let handshake = read_buffer(&mut cursor)?;
+
+        if cursor.position() as usize != state.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "trailing data after saved state",
+            ));
+        }
+
+        Ok(Self {
+            record,
+            handshake,
+            encrypted,
+        })
+    }
+}
+
+// the events of a stream, read a chunk at a time so a large input is never held in memory
+// whole. Iteration stops at the end of the stream or at the first read error
+#[derive(Debug)]
+pub struct MachineReader<R: Read> {
+    reader: R,
+    machine: TlsMachine,
+    events: VecDeque<Event>,
+    failed: bool,
+}
+
+impl<R: Read> MachineReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            machine: TlsMachine::new(),
+            events: VecDeque::new(),
+            failed: false,
+        }
+    }
+
+    // at the end, pending() is the length of the incomplete record or message left
+    pub fn machine(&self) -> &TlsMachine {
+        &self.machine
+    }
+}
+
+impl<R: Read> Iterator for MachineReader<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = [0u8; 16384];
+
+        while self.events.is_empty() && !self.failed {
+            match self.reader.read(&mut buffer) {
+                Ok(0) => return None,
+                Ok(length) => self.events.extend(self.machine.feed(&buffer[..length])),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        self.events.pop_front().map(Ok)
+    }
+}
+
+// a length-prefixed buffer of the saved state
+fn read_buffer(cursor: &mut Cursor<&[u8]>) -> Result<Vec<u8>> {
+    let length = cursor.read_u32::<BigEndian>()? as usize;
+    let remaining = cursor.get_ref().len() - cursor.position() as usize;
+    if length > remaining {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "truncated saved state",
+        ));
+    }
+
+    let mut buffer = vec![0u8; length];
+    cursor.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::encoding::{Encoding, TextDecoder};
+
+    // same stream as the fragmented_handshake test of the dissector
+    const STREAM: [u8; 46] = [
+        0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0x00, 0x00, 0x03, // header
+        0x16, 0x03, 0x03, 0x00, 0x03, 0x01, 0x02, 0x03, // body
+        0x16, 0x03, 0x03, 0x00, 0x09, 0x0B, 0x00, 0x00, 0x01, 0xFF, 0x0E, 0x00, 0x00, 0x00, 0x14,
+        0x03, 0x03, 0x00, 0x01, 0x01, // ChangeCipherSpec
+        0x16, 0x03, 0x03, 0x00, 0x04, 0xDE, 0xAD, 0xBE, 0xEF, // encrypted
+    ];
+
+    fn messages(events: &[Event]) -> Vec<u8> {
+        events
+            .iter()
+            .filter_map(|x| match x {
+                Event::Handshake(message) => Some(message.msg_type),
+                Event::Record(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn byte_by_byte() {
+        let mut machine = TlsMachine::new();
+        let events: Vec<_> = STREAM.iter().flat_map(|x| machine.feed(&[*x])).collect();
+
+        assert_eq!(events.len(), 8);
+        assert_eq!(messages(&events), vec![2, 11, 14]);
+        assert!(machine.is_encrypted());
+        assert_eq!(machine.pending(), 0);
+    }
+
+    #[test]
+    fn reader() {
+        let mut reader = MachineReader::new(&STREAM[..STREAM.len() - 2]);
+        let events: Vec<_> = reader.by_ref().map(Result::unwrap).collect();
+        assert_eq!(events.len(), 7);
+        assert_eq!(messages(&events), vec![2, 11, 14]);
+        assert_eq!(reader.machine().pending(), 7);
+
+        // from hex, within the limit or not
+        let text: String = STREAM.iter().map(|x| format!("{:02x}\n", x)).collect();
+        let decoder = TextDecoder::new(text.as_bytes(), Encoding::Hex).with_limit(46);
+        let events: Vec<_> = MachineReader::new(decoder).map(Result::unwrap).collect();
+        assert_eq!(messages(&events), vec![2, 11, 14]);
+
+        let decoder = TextDecoder::new(text.as_bytes(), Encoding::Hex).with_limit(45);
+        let results: Vec<_> = MachineReader::new(decoder).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn save_restore() {
+        // stop in the middle of the second record: half a message is buffered
+        let mut machine = TlsMachine::new();
+        let events = machine.feed(&STREAM[..12]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(machine.pending(), 7);
+
+        let state = machine.save();
+        let mut restored = TlsMachine::restore(&state).unwrap();
+        assert_eq!(restored, machine);
+
+        let events = restored.feed(&STREAM[12..]);
+        assert_eq!(messages(&events), vec![2, 11, 14]);
+        assert!(restored.is_encrypted());
+
+        // an encrypted flow is saved as such
+        let restored = TlsMachine::restore(&restored.save()).unwrap();
+        assert!(restored.is_encrypted());
+    }
+
+    #[test]
+    fn invalid_state() {
+        let mut state = TlsMachine::new().save();
+        assert!(TlsMachine::restore(&state[..state.len() - 1]).is_err());
+        assert!(TlsMachine::restore(b"XXXX\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00").is_err());
+
+        state[4] = 2;
+        assert!(TlsMachine::restore(&state).is_err());
+
+        // a length larger than the data
+        let mut state = TlsMachine::new().save();
+        state[9] = 0xFF;
+        assert_eq!(
+            TlsMachine::restore(&state).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        let mut state = TlsMachine::new().save();
+        state.push(0);
+        assert!(TlsMachine::restore(&state).is_err());
+    }
+}
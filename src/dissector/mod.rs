@@ -0,0 +1,165 @@
+// split raw TLS streams into records and handshake messages. Unlike the structures
+// in the handshake module, dissection is lenient: unknown values are kept as is
+// and incomplete data is silently ignored
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod certainty;
+pub mod encoding;
+pub mod hello_time;
+pub mod machine;
+pub mod prefilter;
+pub mod redact;
+pub mod sampler;
+pub mod sni;
+pub mod summary;
+
+use crate::handshake::common::{ContentType, ProtocolVersion};
+use crate::handshake::handshake::HandshakeType;
+
+// TLS plaintext record as read from the wire
+#[derive(Debug, Default, Clone)]
+pub struct Record {
+    pub content_type: u8,
+    pub version: ProtocolVersion,
+    pub fragment: Vec<u8>,
+}
+
+impl Record {
+    pub fn content_type(&self) -> Option<ContentType> {
+        ContentType::try_from(self.content_type).ok()
+    }
+
+    pub fn type_name(&self) -> String {
+        match self.content_type() {
+            Some(content_type) => content_type.to_string(),
+            None => format!("unknown({})", self.content_type),
+        }
+    }
+}
+
+// a handshake message, reassembled from one or several records
+#[derive(Debug, Default, Clone)]
+pub struct HandshakeMessage {
+    pub msg_type: u8,
+    pub body: Vec<u8>,
+}
+
+impl HandshakeMessage {
+    pub fn type_name(&self) -> String {
+        match HandshakeType::try_from(self.msg_type) {
+            Ok(msg_type) => msg_type.to_string(),
+            Err(_) => format!("unknown({})", self.msg_type),
+        }
+    }
+
+    // the message as sent, with its header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v = vec![self.msg_type];
+        v.extend_from_slice(&(self.body.len() as u32).to_be_bytes()[1..]);
+        v.extend_from_slice(&self.body);
+        v
+    }
+}
+
+// split a stream into records. Stops at the first incomplete record
+pub fn records(stream: &[u8]) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut data = stream;
+
+    while data.len() >= 5 {
+        let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+        if data.len() < 5 + length {
+            break;
+        }
+
+        records.push(Record {
+            content_type: data[0],
+            version: [data[1], data[2]],
+            fragment: data[5..5 + length].to_vec(),
+        });
+        data = &data[5 + length..];
+    }
+
+    records
+}
+
+// extract handshake messages from records. Messages can span several records,
+// or several messages can be packed into a single record. Once ChangeCipherSpec has been
+// seen, handshake records are encrypted and can't be dissected anymore
+pub fn handshake_messages(records: &[Record]) -> Vec<HandshakeMessage> {
+    let mut buffer = Vec::new();
+
+    for record in records {
+        match record.content_type() {
+            Some(ContentType::handshake) => buffer.extend_from_slice(&record.fragment),
+            Some(ContentType::change_cipher_spec) => break,
+            _ => (),
+        }
+    }
+
+    let mut messages = Vec::new();
+    let mut data = &buffer[..];
+
+    while data.len() >= 4 {
+        let length = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+        if data.len() < 4 + length {
+            break;
+        }
+
+        messages.push(HandshakeMessage {
+            msg_type: data[0],
+            body: data[4..4 + length].to_vec(),
+        });
+        data = &data[4 + length..];
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_records() {
+        let stream = vec![
+            0x16, 0x03, 0x01, 0x00, 0x02, 0xAA, 0xBB, // handshake
+            0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28, // alert
+            0x17, 0x03, 0x03, 0x00, 0x05, 0x00, // truncated
+        ];
+
+        let records = records(&stream);
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            records[0].content_type(),
+            Some(ContentType::handshake)
+        ));
+        assert_eq!(records[0].version, [0x03, 0x01]);
+        assert_eq!(records[0].fragment, &[0xAA, 0xBB]);
+        assert!(matches!(
+            records[1].content_type(),
+            Some(ContentType::alert)
+        ));
+    }
+
+    #[test]
+    fn fragmented_handshake() {
+        // one message over 2 records, then 2 messages in one record
+        let stream = vec![
+            0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0x00, 0x00, 0x03, // header
+            0x16, 0x03, 0x03, 0x00, 0x03, 0x01, 0x02, 0x03, // body
+            0x16, 0x03, 0x03, 0x00, 0x09, 0x0B, 0x00, 0x00, 0x01, 0xFF, 0x0E, 0x00, 0x00, 0x00,
+            0x14, 0x03, 0x03, 0x00, 0x01, 0x01, // ChangeCipherSpec
+            0x16, 0x03, 0x03, 0x00, 0x04, 0xDE, 0xAD, 0xBE, 0xEF, // encrypted
+        ];
+
+        let messages = handshake_messages(&records(&stream));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].msg_type, 2);
+        assert_eq!(messages[0].body, &[0x01, 0x02, 0x03]);
+        assert_eq!(messages[1].msg_type, 11);
+        assert_eq!(messages[1].body, &[0xFF]);
+        assert_eq!(messages[2].msg_type, 14);
+        assert!(messages[2].body.is_empty());
+    }
+}
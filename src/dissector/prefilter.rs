@@ -0,0 +1,174 @@
+// cheap triage of the first bytes of a connection, before parsing a ClientHello. Only the
+// record header, the handshake header and the client version are looked at, so a flood of
+// garbage can be dropped at a constant cost per connection
+use std::fmt;
+
+use crate::handshake::common::ContentType;
+use crate::handshake::handshake::HandshakeType;
+use crate::handshake::record_layer::MAX_FRAGMENT_LENGTH;
+
+// shortest ClientHello body: version, random, empty session id, one suite, one compression method
+const MIN_HELLO_LENGTH: usize = 2 + 32 + 1 + 2 + 2 + 1 + 1;
+
+// longest ClientHello body accepted, as its extensions are limited to 2^16-1 bytes
+const MAX_HELLO_LENGTH: usize = 1 << 17;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    ContentType,
+    RecordVersion,
+    RecordLength,
+    HandshakeType,
+    HandshakeLength,
+    ClientVersion,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Reason::ContentType => "not a handshake record",
+            Reason::RecordVersion => "implausible record version",
+            Reason::RecordLength => "record length out of bounds",
+            Reason::HandshakeType => "not a ClientHello",
+            Reason::HandshakeLength => "ClientHello length out of bounds",
+            Reason::ClientVersion => "implausible client version",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    // worth parsing
+    Accept,
+
+    // nothing wrong so far, more bytes are needed to decide
+    Incomplete,
+    Reject(Reason),
+}
+
+// records sent by clients use SSL 3.0 to TLS 1.2, the latter being frozen by TLS 1.3
+fn plausible_version(major: u8, minor: u8) -> bool {
+    major == 3 && minor <= 3
+}
+
+pub fn prefilter(data: &[u8]) -> Verdict {
+    // record header
+    match data.first() {
+        None => return Verdict::Incomplete,
+        Some(x) if *x != ContentType::handshake as u8 => {
+            return Verdict::Reject(Reason::ContentType)
+        }
+        _ => (),
+    }
+    match data.get(1..3) {
+        Some(&[major, minor]) if !plausible_version(major, minor) => {
+            return Verdict::Reject(Reason::RecordVersion)
+        }
+        Some(_) => (),
+        None if data.get(1).is_some_and(|x| *x != 3) => {
+            return Verdict::Reject(Reason::RecordVersion)
+        }
+        None => return Verdict::Incomplete,
+    }
+    let Some(&[a, b]) = data.get(3..5) else {
+        return Verdict::Incomplete;
+    };
+    let record_length = u16::from_be_bytes([a, b]) as usize;
+    if !(4..=MAX_FRAGMENT_LENGTH).contains(&record_length) {
+        return Verdict::Reject(Reason::RecordLength);
+    }
+
+    // handshake header, which can't be split over records as its record is at least 4 bytes
+    match data.get(5) {
+        None => return Verdict::Incomplete,
+        Some(x) if *x != HandshakeType::client_hello as u8 => {
+            return Verdict::Reject(Reason::HandshakeType)
+        }
+        _ => (),
+    }
+    let Some(&[a, b, c]) = data.get(6..9) else {
+        return Verdict::Incomplete;
+    };
+    let hello_length = u32::from_be_bytes([0, a, b, c]) as usize;
+    if !(MIN_HELLO_LENGTH..=MAX_HELLO_LENGTH).contains(&hello_length) {
+        return Verdict::Reject(Reason::HandshakeLength);
+    }
+
+    // a record larger than the message would be followed by another message
+    if record_length - 4 > hello_length {
+        return Verdict::Reject(Reason::RecordLength);
+    }
+
+    match data.get(9..11) {
+        Some(&[major, minor]) if plausible_version(major, minor) => Verdict::Accept,
+        Some(_) => Verdict::Reject(Reason::ClientVersion),
+        None => Verdict::Incomplete,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // header of a 512 bytes ClientHello
+    const HELLO: [u8; 11] = [
+        0x16, 0x03, 0x01, 0x02, 0x00, 0x01, 0x00, 0x01, 0xFC, 0x03, 0x03,
+    ];
+
+    fn with(index: usize, value: u8) -> Vec<u8> {
+        let mut data = HELLO.to_vec();
+        data[index] = value;
+        data
+    }
+
+    #[test]
+    fn accept() {
+        assert_eq!(prefilter(&HELLO), Verdict::Accept);
+
+        // every prefix is incomplete
+        for n in 0..HELLO.len() {
+            assert_eq!(prefilter(&HELLO[..n]), Verdict::Incomplete);
+        }
+    }
+
+    #[test]
+    fn reject() {
+        assert_eq!(
+            prefilter(b"GET / HTTP/1.1"),
+            Verdict::Reject(Reason::ContentType)
+        );
+        assert_eq!(
+            prefilter(&with(1, 0x02)[..2]),
+            Verdict::Reject(Reason::RecordVersion)
+        );
+        assert_eq!(
+            prefilter(&with(2, 0x04)),
+            Verdict::Reject(Reason::RecordVersion)
+        );
+        assert_eq!(
+            prefilter(&with(3, 0x50)),
+            Verdict::Reject(Reason::RecordLength)
+        );
+        assert_eq!(
+            prefilter(&with(5, HandshakeType::server_hello as u8)),
+            Verdict::Reject(Reason::HandshakeType)
+        );
+        assert_eq!(
+            prefilter(&with(8, 0x10)),
+            Verdict::Reject(Reason::RecordLength)
+        );
+        assert_eq!(
+            prefilter(&with(6, 0x10)),
+            Verdict::Reject(Reason::HandshakeLength)
+        );
+        assert_eq!(
+            prefilter(&with(9, 0x16)),
+            Verdict::Reject(Reason::ClientVersion)
+        );
+        assert_eq!(
+            Reason::ClientVersion.to_string(),
+            "implausible client version"
+        );
+    }
+}
@@ -0,0 +1,471 @@
+// mask sensitive values from handshake messages while keeping their structure: lengths
+// are never changed, so redacted data can still be dissected and shared in bug reports
+use std::io::{Error, ErrorKind, Result};
+use std::sync::OnceLock;
+
+use crate::crypto::hmac::hmac;
+use crate::crypto::sha2::HashAlgorithm;
+use crate::crypto::to_hex;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::dissector::{records, HandshakeMessage};
+use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::common::ContentType;
+use crate::handshake::constants::HELLO_RETRY_REQUEST_RANDOM;
+use crate::handshake::handshake::HandshakeType;
+
+// value used to overwrite masked bytes
+const MASK: u8 = 0x00;
+
+// the key of the pseudonyms, drawn once per run
+static RUN_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+// the HMAC of the name under the key, in hex digits, with the dots of the name
+fn pseudonym(key: &[u8], name: &str) -> String {
+    let mac = to_hex(&hmac(HashAlgorithm::Sha256, key, name.as_bytes()));
+    let mut hex = mac.chars().cycle();
+
+    name.chars()
+        .map(|c| if c == '.' { '.' } else { hex.next().unwrap() })
+        .collect()
+}
+
+// replace a host name by a pseudonym of the same length, keeping the dots so the number of
+// labels is preserved. Pseudonyms are keyed with a random secret, so a list of candidate
+// names can't be hashed to find them back: the same name gives the same pseudonym within
+// one run only
+pub fn redact_host_name(name: &str) -> String {
+    pseudonym(RUN_KEY.get_or_init(rand::random), name)
+}
+
+impl ClientHelloSummary {
+    // mask values which could identify the client or the server
+    pub fn redact(&mut self) {
+        self.session_id.iter_mut().for_each(|b| *b = MASK);
+        if let Some(ticket) = self.session_ticket.as_mut() {
+            ticket.iter_mut().for_each(|b| *b = MASK);
+        }
+        if let Some(name) = &self.server_name {
+            self.server_name = Some(redact_host_name(name));
+        }
+    }
+}
+
+// bounds-checked walk over a mutable handshake body
+struct Walker<'a> {
+    data: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> Walker<'a> {
+    fn new(data: &'a mut [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    // reserve the next bytes and return their range
+    fn take(&mut self, length: usize) -> Result<std::ops::Range<usize>> {
+        let end = self.position + length;
+        if end > self.data.len() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("{} bytes needed at offset {}", length, self.position),
+            ));
+        }
+        let range = self.position..end;
+        self.position = end;
+        Ok(range)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<()> {
+        self.take(length).map(|_| ())
+    }
+
+    fn read_length(&mut self, bytes: usize) -> Result<usize> {
+        let range = self.take(bytes)?;
+        Ok(self.data[range]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize))
+    }
+
+    // a vector with a length prefix of 1, 2 or 3 bytes: return the range of its content
+    fn vector(&mut self, bytes: usize) -> Result<std::ops::Range<usize>> {
+        let length = self.read_length(bytes)?;
+        self.take(length)
+    }
+
+    fn mask(&mut self, range: std::ops::Range<usize>) {
+        self.data[range].iter_mut().for_each(|b| *b = MASK);
+    }
+
+    fn mask_vector(&mut self, bytes: usize) -> Result<()> {
+        let range = self.vector(bytes)?;
+        self.mask(range);
+        Ok(())
+    }
+}
+
+// redact a handshake message in place
+pub fn redact_handshake(message: &mut HandshakeMessage) -> Result<()> {
+    match HandshakeType::try_from(message.msg_type) {
+        Ok(HandshakeType::client_hello) => redact_client_hello(&mut message.body),
+        Ok(HandshakeType::server_hello) => redact_server_hello(&mut message.body),
+        Ok(HandshakeType::new_session_ticket) => redact_new_session_ticket(&mut message.body),
+        Ok(HandshakeType::certificate) => redact_certificate(&mut message.body),
+        Ok(HandshakeType::certificate_status) => redact_certificate_status(&mut message.body),
+        _ => Ok(()),
+    }
+}
+
+fn redact_random(w: &mut Walker) -> Result<()> {
+    let range = w.take(32)?;
+
+    // the HelloRetryRequest marker is not random
+    if w.data[range.clone()] == HELLO_RETRY_REQUEST_RANDOM {
+        return Ok(());
+    }
+
+    // keep the downgrade protection sentinel: https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+    let end = if w.data[range.end - 8..range.end - 1] == *b"DOWNGRD" {
+        range.end - 8
+    } else {
+        range.end
+    };
+    w.mask(range.start..end);
+    Ok(())
+}
+
+fn redact_client_hello(body: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(body);
+
+    w.skip(2)?;
+    redact_random(&mut w)?;
+    w.mask_vector(1)?;
+    w.vector(2)?;
+    w.vector(1)?;
+
+    if w.is_empty() {
+        return Ok(());
+    }
+
+    let extensions = w.vector(2)?;
+    redact_extensions(&mut w.data[extensions])
+}
+
+fn redact_server_hello(body: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(body);
+
+    w.skip(2)?;
+    redact_random(&mut w)?;
+    w.mask_vector(1)?;
+    w.skip(3)?;
+
+    if w.is_empty() {
+        return Ok(());
+    }
+
+    let extensions = w.vector(2)?;
+    redact_extensions(&mut w.data[extensions])
+}
+
+// TLS 1.2 NewSessionTicket: https://datatracker.ietf.org/doc/html/rfc5077#section-3.3
+fn redact_new_session_ticket(body: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(body);
+
+    w.skip(4)?;
+    w.mask_vector(2)
+}
+
+// the certificates name the server in their subject and SAN, so they are masked whole. This is
+// the TLS 1.2 form, the TLS 1.3 one being encrypted: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.2
+fn redact_certificate(body: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(body);
+    let list = w.vector(3)?;
+    let mut certificate = Walker::new(&mut w.data[list]);
+    while !certificate.is_empty() {
+        certificate.mask_vector(3)?;
+    }
+
+    Ok(())
+}
+
+// the OCSP response names the certificate: https://datatracker.ietf.org/doc/html/rfc6066#section-8
+fn redact_certificate_status(body: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(body);
+
+    w.skip(1)?;
+    w.mask_vector(3)
+}
+
+fn redact_extensions(data: &mut [u8]) -> Result<()> {
+    let mut w = Walker::new(data);
+
+    while !w.is_empty() {
+        let extension_type = w.read_length(2)? as u16;
+        let range = w.vector(2)?;
+        let body = &mut w.data[range];
+
+        match ExtensionType::from(extension_type) {
+            ExtensionType::server_name => redact_server_name(body)?,
+            ExtensionType::session_ticket => body.iter_mut().for_each(|b| *b = MASK),
+            ExtensionType::pre_shared_key => redact_pre_shared_key(body)?,
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn redact_server_name(data: &mut [u8]) -> Result<()> {
+    // ServerHello echoes an empty server_name extension
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    let mut w = Walker::new(data);
+    let list = w.vector(2)?;
+    let mut w = Walker::new(&mut w.data[list]);
+
+    while !w.is_empty() {
+        w.skip(1)?;
+        let range = w.vector(2)?;
+        let name = String::from_utf8_lossy(&w.data[range.clone()]).into_owned();
+
+        // pseudonym has the same length in bytes only for ASCII names
+        let pseudonym = redact_host_name(&name);
+        if pseudonym.len() == range.len() {
+            w.data[range].copy_from_slice(pseudonym.as_bytes());
+        } else {
+            w.mask(range);
+        }
+    }
+
+    Ok(())
+}
+
+// ClientHello form: identities and binders. ServerHello form: selected identity only
+fn redact_pre_shared_key(data: &mut [u8]) -> Result<()> {
+    if data.len() == 2 {
+        return Ok(());
+    }
+
+    let mut w = Walker::new(data);
+
+    let identities = w.vector(2)?;
+    let mut identity = Walker::new(&mut w.data[identities]);
+    while !identity.is_empty() {
+        identity.mask_vector(2)?;
+        identity.skip(4)?;
+    }
+
+    let binders = w.vector(2)?;
+    let mut binder = Walker::new(&mut w.data[binders]);
+    while !binder.is_empty() {
+        binder.mask_vector(1)?;
+    }
+
+    Ok(())
+}
+
+// redact all handshake messages found in a raw TLS stream. Record boundaries are kept,
+// even when a handshake message spans several records. A message or record cut by the end of
+// the stream can't be walked, so its content is masked whole
+pub fn redact_stream(stream: &[u8]) -> Vec<u8> {
+    let records = records(stream);
+
+    // concatenate handshake fragments until encryption starts, like the dissector does
+    let mut buffer = Vec::new();
+    let mut handshake_records = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        match record.content_type() {
+            Some(ContentType::handshake) => {
+                handshake_records.push(i);
+                buffer.extend_from_slice(&record.fragment);
+            }
+            Some(ContentType::change_cipher_spec) => break,
+            _ => (),
+        }
+    }
+
+    // redact messages in place
+    let mut offset = 0;
+    while offset + 4 <= buffer.len() {
+        let length = u32::from_be_bytes([
+            0,
+            buffer[offset + 1],
+            buffer[offset + 2],
+            buffer[offset + 3],
+        ]) as usize;
+        if offset + 4 + length > buffer.len() {
+            offset += 4;
+            break;
+        }
+
+        let mut message = HandshakeMessage {
+            msg_type: buffer[offset],
+            body: buffer[offset + 4..offset + 4 + length].to_vec(),
+        };
+
+        // a message we can't walk is masked completely rather than leaked
+        if redact_handshake(&mut message).is_err() {
+            message.body.iter_mut().for_each(|b| *b = MASK);
+        }
+        buffer[offset + 4..offset + 4 + length].copy_from_slice(&message.body);
+
+        offset += 4 + length;
+    }
+
+    // the body of a message cut by the end of the stream
+    let cut = offset.min(buffer.len());
+    buffer[cut..].iter_mut().for_each(|b| *b = MASK);
+
+    // write everything back using the original record boundaries
+    let mut output = Vec::with_capacity(stream.len());
+    let mut fragments = buffer.as_slice();
+    for (i, record) in records.iter().enumerate() {
+        output.push(record.content_type);
+        output.extend_from_slice(&record.version);
+        output.extend_from_slice(&(record.fragment.len() as u16).to_be_bytes());
+
+        if handshake_records.contains(&i) {
+            let (fragment, rest) = fragments.split_at(record.fragment.len());
+            output.extend_from_slice(fragment);
+            fragments = rest;
+        } else {
+            output.extend_from_slice(&record.fragment);
+        }
+    }
+
+    // the fragment of a record cut by the end of the stream, its header being kept
+    let tail = &stream[output.len()..];
+    let header = tail.len().min(5);
+    output.extend_from_slice(&tail[..header]);
+    output.resize(stream.len(), MASK);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::handshake_messages;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::{concat_parts, handshake, record};
+
+    #[test]
+    fn host_name() {
+        let pseudonym = redact_host_name("example.ulfheim.net");
+        assert_eq!(pseudonym.len(), "example.ulfheim.net".len());
+        assert_eq!(pseudonym.matches('.').count(), 2);
+        assert_ne!(pseudonym, "example.ulfheim.net");
+        assert_eq!(pseudonym, redact_host_name("example.ulfheim.net"));
+
+        // another run has another key
+        assert_ne!(
+            super::pseudonym(&[0x01; 32], "example.ulfheim.net"),
+            super::pseudonym(&[0x02; 32], "example.ulfheim.net")
+        );
+    }
+
+    #[test]
+    fn client_hello() {
+        let mut message = HandshakeMessage {
+            msg_type: 1,
+            body: CLIENT_HELLO.to_vec(),
+        };
+        assert!(redact_handshake(&mut message).is_ok());
+
+        // random is masked, structure is kept
+        assert_eq!(&message.body[2..34], &[MASK; 32]);
+        let summary = ClientHelloSummary::parse(&message.body).unwrap();
+        let original = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        assert_eq!(summary.cipher_suites, original.cipher_suites);
+        assert_eq!(summary.extensions, original.extensions);
+        assert_eq!(
+            summary.server_name.unwrap(),
+            redact_host_name("example.ulfheim.net")
+        );
+    }
+
+    #[test]
+    fn stream() {
+        // a ClientHello split over 2 records, followed by an alert
+        let message = handshake!(0x01, CLIENT_HELLO);
+        let stream = concat_parts!(
+            record!(0x16, [0x03, 0x01], &message[..0x10]),
+            record!(0x16, [0x03, 0x01], &message[0x10..]),
+            record!(0x15, [0x03, 0x03], [0x02, 0x28])
+        );
+
+        let redacted = redact_stream(&stream);
+        assert_eq!(redacted.len(), stream.len());
+        assert_eq!(&redacted[redacted.len() - 7..], &stream[stream.len() - 7..]);
+
+        let messages = handshake_messages(&records(&redacted));
+        assert_eq!(messages.len(), 1);
+        let summary = ClientHelloSummary::parse(&messages[0].body).unwrap();
+        assert_ne!(summary.server_name.unwrap(), "example.ulfheim.net");
+
+        // the ClientHello cut by the end of its second record, or of the stream
+        let name = b"ulfheim";
+        let contains = |data: &[u8]| data.windows(name.len()).any(|x| x == name);
+        let cut = &stream[..stream.len() - 7];
+        for stream in [
+            &concat_parts!(
+                &cut[..0x15],
+                record!(0x16, [0x03, 0x01], &message[0x10..0x80])
+            ),
+            &cut[..cut.len() - 20],
+        ] {
+            assert!(contains(stream));
+            let redacted = redact_stream(stream);
+            assert_eq!(redacted.len(), stream.len());
+            assert_eq!(&redacted[..5], &stream[..5]);
+            assert!(!contains(&redacted));
+            assert!(!redacted.windows(32).any(|x| x == &CLIENT_HELLO[2..34]));
+        }
+    }
+
+    #[test]
+    fn certificate() {
+        let name = b"example.ulfheim.net";
+        let mut certificate = vec![0x30, 0x13];
+        certificate.extend_from_slice(name);
+        let mut list = (certificate.len() as u32).to_be_bytes()[1..].to_vec();
+        list.extend_from_slice(&certificate);
+        let mut body = (list.len() as u32).to_be_bytes()[1..].to_vec();
+        body.extend_from_slice(&list);
+
+        let mut message = HandshakeMessage {
+            msg_type: 11,
+            body: body.clone(),
+        };
+        assert!(redact_handshake(&mut message).is_ok());
+        assert_eq!(&message.body[..6], &body[..6]);
+        assert!(message.body[6..].iter().all(|b| *b == MASK));
+
+        // OCSP response
+        let mut status = vec![0x01];
+        status.extend_from_slice(&list);
+        let mut message = HandshakeMessage {
+            msg_type: 22,
+            body: status,
+        };
+        assert!(redact_handshake(&mut message).is_ok());
+        assert!(message.body[4..].iter().all(|b| *b == MASK));
+    }
+
+    #[test]
+    fn summary() {
+        let mut summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        summary.session_id = vec![0xFF; 32];
+        summary.redact();
+        assert_eq!(summary.session_id, vec![MASK; 32]);
+        assert_eq!(
+            summary.server_name.unwrap(),
+            redact_host_name("example.ulfheim.net")
+        );
+    }
+}
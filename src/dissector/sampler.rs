@@ -0,0 +1,232 @@
+// metrics hook for proxies: a sampler is called for each record of a stream with a few fields
+// read in place, without reassembling messages or parsing the hellos into structures. The
+// server name is only given as a hash, so metrics don't carry host names
+use crate::crypto::md5::md5;
+use crate::dissector::sni::{peek_sni, Reader, SniPeek};
+use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::common::{ContentType, ProtocolVersion};
+use crate::handshake::handshake::HandshakeType;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RecordSample {
+    pub content_type: u8,
+    pub record_version: ProtocolVersion,
+
+    // of the fragment, without the header
+    pub length: usize,
+
+    // type and length of the handshake message starting the record, if it's not encrypted
+    pub handshake_type: Option<u8>,
+    pub handshake_length: Option<usize>,
+
+    // from a ClientHello, see sni_hash()
+    pub sni_hash: Option<u64>,
+
+    // from a ServerHello: the negotiated version, supported_versions for TLS 1.3, and suite
+    pub version: Option<ProtocolVersion>,
+    pub cipher_suite: Option<u16>,
+}
+
+pub trait Sampler {
+    fn sample(&mut self, sample: &RecordSample);
+}
+
+impl<F: FnMut(&RecordSample)> Sampler for F {
+    fn sample(&mut self, sample: &RecordSample) {
+        self(sample)
+    }
+}
+
+// the first 8 bytes of the MD5 of the lowercase name, as a key for per-host counters
+pub fn sni_hash(name: &str) -> u64 {
+    let digest = md5(name.to_ascii_lowercase().as_bytes());
+    let mut hash = [0u8; 8];
+    hash.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(hash)
+}
+
+// version and suite of a ServerHello body, None if it's cut by the end of the record
+fn server_hello(body: &[u8]) -> Option<(ProtocolVersion, u16)> {
+    let mut reader = Reader(body);
+    let legacy_version = reader.take(2)?;
+    let mut version = [legacy_version[0], legacy_version[1]];
+    reader.take(32)?;
+    reader.vector8()?;
+    let cipher_suite = reader.u16()?;
+    reader.u8()?;
+
+    if !reader.0.is_empty() {
+        let mut extensions = Reader(reader.vector16()?);
+        while !extensions.0.is_empty() {
+            let extension_type = extensions.u16()?;
+            let data = extensions.vector16()?;
+            if extension_type == u16::from(ExtensionType::supported_versions) && data.len() == 2 {
+                version = [data[0], data[1]];
+            }
+        }
+    }
+
+    Some((version, cipher_suite))
+}
+
+// one direction of a connection: bytes are fed as they arrive, and the sampler is called once
+// per complete record. Only an incomplete record is buffered
+#[derive(Debug)]
+pub struct RecordSampler<S: Sampler> {
+    sampler: S,
+    pending: Vec<u8>,
+
+    // after ChangeCipherSpec, handshake records are encrypted
+    encrypted: bool,
+}
+
+impl<S: Sampler> RecordSampler<S> {
+    pub fn new(sampler: S) -> Self {
+        Self {
+            sampler,
+            pending: Vec::new(),
+            encrypted: false,
+        }
+    }
+
+    pub fn sampler(&self) -> &S {
+        &self.sampler
+    }
+
+    pub fn into_sampler(self) -> S {
+        self.sampler
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+        let mut consumed = 0;
+
+        while self.pending.len() - consumed >= 5 {
+            let data = &self.pending[consumed..];
+            let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+            if data.len() < 5 + length {
+                break;
+            }
+
+            let sample = sample(&data[..5 + length], &mut self.encrypted);
+            self.sampler.sample(&sample);
+            consumed += 5 + length;
+        }
+
+        self.pending.drain(..consumed);
+    }
+}
+
+// record is complete, header included
+fn sample(record: &[u8], encrypted: &mut bool) -> RecordSample {
+    let fragment = &record[5..];
+    let mut sample = RecordSample {
+        content_type: record[0],
+        record_version: [record[1], record[2]],
+        length: fragment.len(),
+        ..RecordSample::default()
+    };
+
+    match ContentType::try_from(record[0]) {
+        Ok(ContentType::change_cipher_spec) => *encrypted = true,
+        Ok(ContentType::handshake) if !*encrypted && fragment.len() >= 4 => {
+            let msg_type = fragment[0];
+            sample.handshake_type = Some(msg_type);
+            sample.handshake_length =
+                Some(u32::from_be_bytes([0, fragment[1], fragment[2], fragment[3]]) as usize);
+
+            if msg_type == HandshakeType::client_hello as u8 {
+                if let SniPeek::Found(name) = peek_sni(record) {
+                    sample.sni_hash = Some(sni_hash(name));
+                }
+            } else if msg_type == HandshakeType::server_hello as u8 {
+                if let Some((version, suite)) = server_hello(&fragment[4..]) {
+                    sample.version = Some(version);
+                    sample.cipher_suite = Some(suite);
+                }
+            }
+        }
+        _ => (),
+    }
+
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_tls::TlsDerive;
+    use crate::handshake::builder::ClientHelloBuilder;
+    use crate::handshake::constants::*;
+    use crate::handshake::handshake::Handshake;
+    use crate::handshake::record_layer::{RecordHeader, RecordLayer};
+    use crate::{concat_parts, extension, handshake, record};
+
+    #[test]
+    fn sampler() {
+        let ch = ClientHelloBuilder::new()
+            .version(TLS13)
+            .sni("Example.com")
+            .build()
+            .unwrap();
+        let mut body = Vec::new();
+        ch.to_network_bytes(&mut body).unwrap();
+        let mut record = RecordLayer {
+            header: RecordHeader {
+                content_type: ContentType::handshake,
+                version: TLS10,
+                length: 0,
+            },
+            data: Handshake::from_message(HandshakeType::client_hello, ch),
+        };
+        record.set_length();
+        let mut client_bytes = Vec::new();
+        record.to_network_bytes(&mut client_bytes).unwrap();
+
+        // byte by byte: one sample for the record
+        let mut samples = Vec::new();
+        let mut client = RecordSampler::new(|x: &RecordSample| samples.push(*x));
+        for byte in &client_bytes {
+            client.feed(&[*byte]);
+        }
+        drop(client);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].handshake_type, Some(1));
+        assert_eq!(samples[0].length, client_bytes.len() - 5);
+        assert_eq!(samples[0].handshake_length, Some(body.len()));
+        assert_eq!(samples[0].sni_hash, Some(sni_hash("example.com")));
+        assert_eq!(samples[0].cipher_suite, None);
+
+        // a TLS 1.3 ServerHello, then an encrypted Certificate
+        let extensions = extension!(ExtensionType::supported_versions, TLS13);
+        let sh = handshake!(
+            0x02,
+            TLS12,
+            [0x11; 32],
+            [0x00],
+            TLS_AES_128_GCM_SHA256,
+            [0x00],
+            (extensions.len() as u16).to_be_bytes(),
+            extensions
+        );
+        let flight = concat_parts!(
+            record!(0x16, TLS12, sh),
+            record!(0x14, TLS12, [0x01]),
+            record!(0x16, TLS12, handshake!(0x0B, [0x00; 4]))
+        );
+        let mut samples = Vec::new();
+        let mut server = RecordSampler::new(|x: &RecordSample| samples.push(*x));
+        server.feed(&flight);
+        drop(server);
+
+        assert_eq!(samples[0].handshake_type, Some(2));
+        assert_eq!(samples[0].version, Some(TLS13));
+        assert_eq!(
+            samples[0].cipher_suite.map(u16::to_be_bytes),
+            Some(TLS_AES_128_GCM_SHA256)
+        );
+        assert_eq!(samples[1].content_type, 20);
+        assert_eq!(samples[2].handshake_type, None);
+        assert_eq!(samples[2].length, 8);
+    }
+}
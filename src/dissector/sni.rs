@@ -0,0 +1,196 @@
+// extract the server name from the first bytes of a connection, for SNI-based routing. Only the
+// fields leading to the server_name extension are walked: nothing is allocated and the work is
+// bounded by the size of the first record
+use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::common::ContentType;
+use crate::handshake::handshake::HandshakeType;
+
+#[derive(Debug, PartialEq)]
+pub enum SniPeek<'a> {
+    Found(&'a str),
+
+    // a ClientHello without a host name, or with extensions beyond the first record
+    Absent,
+
+    // the first record isn't complete yet
+    Incomplete,
+
+    // not a TLS ClientHello, or a malformed one
+    NotTls,
+}
+
+// a cursor over a slice, None if it's too short
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Some(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|x| x[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|x| u16::from_be_bytes([x[0], x[1]]))
+    }
+
+    // a vector with a 1 or 2 bytes length
+    pub(crate) fn vector8(&mut self) -> Option<&'a [u8]> {
+        let length = self.u8()? as usize;
+        self.take(length)
+    }
+
+    pub(crate) fn vector16(&mut self) -> Option<&'a [u8]> {
+        let length = self.u16()? as usize;
+        self.take(length)
+    }
+}
+
+pub fn peek_sni(data: &[u8]) -> SniPeek<'_> {
+    if data.len() < 5 {
+        return SniPeek::Incomplete;
+    }
+    if data[0] != ContentType::handshake as u8 || data[1] != 0x03 {
+        return SniPeek::NotTls;
+    }
+
+    let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+    let Some(fragment) = data.get(5..5 + length) else {
+        return SniPeek::Incomplete;
+    };
+
+    let mut reader = Reader(fragment);
+    match reader.u8() {
+        Some(x) if x == HandshakeType::client_hello as u8 => (),
+        _ => return SniPeek::NotTls,
+    }
+
+    // the message can be larger than the record: parse what's in it
+    let message_length = match reader.take(3) {
+        Some(x) => u32::from_be_bytes([0, x[0], x[1], x[2]]) as usize,
+        None => return SniPeek::NotTls,
+    };
+    let truncated = message_length > reader.0.len();
+
+    match server_name(&mut reader) {
+        Some(Some(name)) => SniPeek::Found(name),
+        Some(None) => SniPeek::Absent,
+        None if truncated => SniPeek::Absent,
+        None => SniPeek::NotTls,
+    }
+}
+
+// None if the hello is malformed or cut, Some(None) if there's no host name
+fn server_name<'a>(reader: &mut Reader<'a>) -> Option<Option<&'a str>> {
+    // legacy_version, random, session_id, cipher_suites, compression_methods
+    reader.take(2 + 32)?;
+    reader.vector8()?;
+    reader.vector16()?;
+    reader.vector8()?;
+
+    // a hello without extensions
+    if reader.0.is_empty() {
+        return Some(None);
+    }
+
+    let mut extensions = Reader(reader.vector16()?);
+    while !extensions.0.is_empty() {
+        let extension_type = extensions.u16()?;
+        let extension_data = extensions.vector16()?;
+        if extension_type != u16::from(ExtensionType::server_name) {
+            continue;
+        }
+
+        // only host_name entries are defined: https://datatracker.ietf.org/doc/html/rfc6066#section-3
+        let mut names = Reader(Reader(extension_data).vector16()?);
+        while !names.0.is_empty() {
+            let name_type = names.u8()?;
+            let name = names.vector16()?;
+            if name_type == 0 {
+                return std::str::from_utf8(name).ok().map(Some);
+            }
+        }
+        return Some(None);
+    }
+
+    Some(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_tls::TlsDerive;
+    use crate::handshake::client_hello::ClientHello;
+    use crate::handshake::constants::TLS10;
+    use crate::handshake::handshake::Handshake;
+    use crate::handshake::record_layer::{RecordHeader, RecordLayer};
+    use crate::handshake::templates::template;
+
+    fn record(ch: ClientHello) -> Vec<u8> {
+        let mut record = RecordLayer {
+            header: RecordHeader {
+                content_type: ContentType::handshake,
+                version: TLS10,
+                length: 0,
+            },
+            data: Handshake::from_client_hello(ch),
+        };
+        record.set_length();
+
+        let mut buffer = Vec::new();
+        record.to_network_bytes(&mut buffer).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn found() {
+        let mut data = record(template("chrome", "example.ulfheim.net").unwrap());
+        assert_eq!(peek_sni(&data), SniPeek::Found("example.ulfheim.net"));
+
+        // bytes of the next records are ignored
+        data.extend_from_slice(&[0x14, 0x03, 0x03]);
+        assert_eq!(peek_sni(&data), SniPeek::Found("example.ulfheim.net"));
+    }
+
+    #[test]
+    fn incomplete() {
+        let data = record(template("chrome", "example.com").unwrap());
+        assert_eq!(peek_sni(&data[..3]), SniPeek::Incomplete);
+        assert_eq!(peek_sni(&data[..data.len() - 1]), SniPeek::Incomplete);
+    }
+
+    #[test]
+    fn absent() {
+        let ch = template("minimal", "example.com")
+            .unwrap()
+            .with_extension_removed(ExtensionType::server_name);
+        assert_eq!(peek_sni(&record(ch)), SniPeek::Absent);
+
+        // the hello is split over several records, the first one ending before the extensions
+        let data = record(template("chrome", "example.com").unwrap());
+        let mut first = vec![0x16, 0x03, 0x01, 0x00, 60];
+        first.extend_from_slice(&data[5..65]);
+        assert_eq!(peek_sni(&first), SniPeek::Absent);
+    }
+
+    #[test]
+    fn not_tls() {
+        assert_eq!(peek_sni(b"GET / HTTP/1.1\r\n"), SniPeek::NotTls);
+
+        let mut data = record(template("chrome", "example.com").unwrap());
+        data[5] = HandshakeType::server_hello as u8;
+        assert_eq!(peek_sni(&data), SniPeek::NotTls);
+
+        // a complete message too short for a ClientHello
+        let data = [
+            0x16, 0x03, 0x01, 0x00, 0x07, 0x01, 0x00, 0x00, 0x03, 0x03, 0x03, 0x00,
+        ];
+        assert_eq!(peek_sni(&data), SniPeek::NotTls);
+    }
+}
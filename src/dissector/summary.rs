@@ -0,0 +1,368 @@
+// a lenient view of a ClientHello, keeping only what's needed for fingerprinting
+// and statistics. All values are kept as raw codes so unknown ones aren't lost
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::common::ProtocolVersion;
+use crate::handshake::constants::HELLO_RETRY_REQUEST_RANDOM;
+
+#[derive(Debug, Default, Clone)]
+pub struct ClientHelloSummary {
+    pub client_version: ProtocolVersion,
+    pub random: Vec<u8>,
+    pub session_id: Vec<u8>,
+    pub cipher_suites: Vec<u16>,
+    pub compression_methods: Vec<u8>,
+
+    // extension types in the order they were sent
+    pub extensions: Vec<u16>,
+
+    // decoded content of some extensions
+    pub server_name: Option<String>,
+    pub supported_groups: Vec<u16>,
+    pub ec_point_formats: Vec<u8>,
+    pub alpn: Vec<String>,
+    pub supported_versions: Vec<ProtocolVersion>,
+    pub signature_algorithms: Vec<u16>,
+
+    // empty if the extension is absent, signature_algorithms then also applies to certificates
+    pub signature_algorithms_cert: Vec<u16>,
+
+    // Some if the extension is present, the ticket being empty when a new one is requested
+    pub session_ticket: Option<Vec<u8>>,
+
+    // raw code of the requested max_fragment_length
+    pub max_fragment_length: Option<u8>,
+
+    // group and public value of each key share
+    pub key_shares: Vec<(u16, Vec<u8>)>,
+}
+
+impl ClientHelloSummary {
+    // parse the body of a ClientHello handshake message
+    pub fn parse(body: &[u8]) -> Result<Self> {
+        let mut summary = ClientHelloSummary::default();
+        summary.fill(body)?;
+        Ok(summary)
+    }
+
+    // parse as much as possible of a truncated ClientHello. The boolean is true if the
+    // whole message could be parsed
+    pub fn parse_partial(body: &[u8]) -> (Self, bool) {
+        let mut summary = ClientHelloSummary::default();
+        let complete = summary.fill(body).is_ok();
+        (summary, complete)
+    }
+
+    // fields are set in wire order, so on error everything before the failure is kept
+    fn fill(&mut self, body: &[u8]) -> Result<()> {
+        let mut v = Cursor::new(body);
+
+        v.read_exact(&mut self.client_version)?;
+        self.random = read_bytes(&mut v, 32)?;
+        self.session_id = read_vector8(&mut v)?;
+
+        let (suites, complete) = read_partial_vector16(&mut v)?;
+        self.cipher_suites = suites
+            .chunks_exact(2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]))
+            .collect();
+        truncated(complete)?;
+
+        self.compression_methods = read_vector8(&mut v)?;
+
+        // extensions are optional
+        if (v.position() as usize) >= body.len() {
+            return Ok(());
+        }
+
+        let (extensions, complete) = read_partial_vector16(&mut v)?;
+        let mut v = Cursor::new(&extensions[..]);
+
+        while (v.position() as usize) < extensions.len() {
+            let extension_type = v.read_u16::<BigEndian>()?;
+            let data = read_vector16(&mut v)?;
+
+            self.extensions.push(extension_type);
+            self.decode_extension(extension_type, &data)?;
+        }
+
+        truncated(complete)
+    }
+
+    // the highest version the client is willing to negotiate
+    pub fn max_version(&self) -> ProtocolVersion {
+        self.supported_versions
+            .iter()
+            .filter(|v| !crate::handshake::common::is_grease(u16::from_be_bytes(**v)))
+            .max()
+            .copied()
+            .unwrap_or(self.client_version)
+    }
+
+    fn decode_extension(&mut self, extension_type: u16, data: &[u8]) -> Result<()> {
+        let mut v = Cursor::new(data);
+
+        match ExtensionType::from(extension_type) {
+            ExtensionType::server_name => {
+                let list = read_vector16(&mut v)?;
+                let mut v = Cursor::new(&list[..]);
+
+                while (v.position() as usize) < list.len() {
+                    let name_type = v.read_u8()?;
+                    let name = read_vector16(&mut v)?;
+
+                    // host_name is the only type defined
+                    if name_type == 0 && self.server_name.is_none() {
+                        self.server_name = Some(String::from_utf8_lossy(&name).into_owned());
+                    }
+                }
+            }
+            ExtensionType::supported_groups => {
+                let groups = read_vector16(&mut v)?;
+                self.supported_groups = groups
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                    .collect();
+            }
+            ExtensionType::signature_algorithms => {
+                let schemes = read_vector16(&mut v)?;
+                self.signature_algorithms = schemes
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                    .collect();
+            }
+            ExtensionType::signature_algorithms_cert => {
+                let schemes = read_vector16(&mut v)?;
+                self.signature_algorithms_cert = schemes
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                    .collect();
+            }
+            ExtensionType::ec_point_formats => {
+                self.ec_point_formats = read_vector8(&mut v)?;
+            }
+            ExtensionType::application_layer_protocol_negotiation => {
+                let list = read_vector16(&mut v)?;
+                let mut v = Cursor::new(&list[..]);
+
+                while (v.position() as usize) < list.len() {
+                    let protocol = read_vector8(&mut v)?;
+                    self.alpn
+                        .push(String::from_utf8_lossy(&protocol).into_owned());
+                }
+            }
+            ExtensionType::max_fragment_length => {
+                self.max_fragment_length = Some(v.read_u8()?);
+            }
+            ExtensionType::session_ticket => {
+                self.session_ticket = Some(data.to_vec());
+            }
+            ExtensionType::supported_versions => {
+                let versions = read_vector8(&mut v)?;
+                self.supported_versions = versions.chunks_exact(2).map(|x| [x[0], x[1]]).collect();
+            }
+            ExtensionType::key_share => {
+                let shares = read_vector16(&mut v)?;
+                let mut v = Cursor::new(&shares[..]);
+
+                while (v.position() as usize) < shares.len() {
+                    let group = v.read_u16::<BigEndian>()?;
+                    self.key_shares.push((group, read_vector16(&mut v)?));
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+// a lenient view of a ServerHello, so extensions unknown to ExtensionType don't prevent
+// a scan from reading the answer
+#[derive(Debug, Default, Clone)]
+pub struct ServerHelloSummary {
+    pub server_version: ProtocolVersion,
+    pub random: Vec<u8>,
+    pub session_id: Vec<u8>,
+    pub cipher_suite: u16,
+    pub compression_method: u8,
+
+    // extension types and data, in the order they were sent
+    pub extensions: Vec<(u16, Vec<u8>)>,
+}
+
+impl ServerHelloSummary {
+    // parse the body of a ServerHello handshake message
+    pub fn parse(body: &[u8]) -> Result<Self> {
+        let mut v = Cursor::new(body);
+        let mut summary = ServerHelloSummary::default();
+
+        v.read_exact(&mut summary.server_version)?;
+        summary.random = read_bytes(&mut v, 32)?;
+        summary.session_id = read_vector8(&mut v)?;
+        summary.cipher_suite = v.read_u16::<BigEndian>()?;
+        summary.compression_method = v.read_u8()?;
+
+        // extensions are optional
+        if (v.position() as usize) >= body.len() {
+            return Ok(summary);
+        }
+
+        let extensions = read_vector16(&mut v)?;
+        let mut v = Cursor::new(&extensions[..]);
+        while (v.position() as usize) < extensions.len() {
+            let extension_type = v.read_u16::<BigEndian>()?;
+            let data = read_vector16(&mut v)?;
+            summary.extensions.push((extension_type, data));
+        }
+
+        Ok(summary)
+    }
+
+    pub fn extension(&self, extension_type: u16) -> Option<&[u8]> {
+        self.extensions
+            .iter()
+            .find(|(x, _)| *x == extension_type)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    pub fn is_hello_retry_request(&self) -> bool {
+        self.random == HELLO_RETRY_REQUEST_RANDOM
+    }
+
+    // the negotiated version, taking supported_versions into account
+    pub fn version(&self) -> ProtocolVersion {
+        match self.extension(u16::from(ExtensionType::supported_versions)) {
+            Some(&[a, b]) => [a, b],
+            _ => self.server_version,
+        }
+    }
+}
+
+// read a vector with a 1-byte length prefix
+fn read_vector8(v: &mut Cursor<&[u8]>) -> Result<Vec<u8>> {
+    let length = v.read_u8()? as usize;
+    read_bytes(v, length)
+}
+
+// read a vector with a 2-byte length prefix
+fn read_vector16(v: &mut Cursor<&[u8]>) -> Result<Vec<u8>> {
+    let length = v.read_u16::<BigEndian>()? as usize;
+    read_bytes(v, length)
+}
+
+// read a vector with a 2-byte length prefix, keeping what's available if it's truncated
+fn read_partial_vector16(v: &mut Cursor<&[u8]>) -> Result<(Vec<u8>, bool)> {
+    let length = v.read_u16::<BigEndian>()? as usize;
+    let remaining = v.get_ref().len().saturating_sub(v.position() as usize);
+    let buffer = read_bytes(v, length.min(remaining))?;
+    Ok((buffer, length <= remaining))
+}
+
+fn truncated(complete: bool) -> Result<()> {
+    if complete {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::UnexpectedEof, "truncated vector"))
+    }
+}
+
+fn read_bytes(v: &mut Cursor<&[u8]>, length: usize) -> Result<Vec<u8>> {
+    let remaining = v.get_ref().len().saturating_sub(v.position() as usize);
+    if length > remaining {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "vector of {} bytes but only {} remaining",
+                length, remaining
+            ),
+        ));
+    }
+
+    let mut buffer = vec![0u8; length];
+    v.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // ClientHello body from https://tls12.xargs.org/#client-hello
+    pub(crate) const CLIENT_HELLO: [u8; 161] = [
+        0x03, 0x03, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+        0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+        0x1c, 0x1d, 0x1e, 0x1f, 0x00, 0x00, 0x20, 0xcc, 0xa8, 0xcc, 0xa9, 0xc0, 0x2f, 0xc0, 0x30,
+        0xc0, 0x2b, 0xc0, 0x2c, 0xc0, 0x13, 0xc0, 0x09, 0xc0, 0x14, 0xc0, 0x0a, 0x00, 0x9c, 0x00,
+        0x9d, 0x00, 0x2f, 0x00, 0x35, 0xc0, 0x12, 0x00, 0x0a, 0x01, 0x00, 0x00, 0x58, 0x00, 0x00,
+        0x00, 0x18, 0x00, 0x16, 0x00, 0x00, 0x13, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e,
+        0x75, 0x6c, 0x66, 0x68, 0x65, 0x69, 0x6d, 0x2e, 0x6e, 0x65, 0x74, 0x00, 0x05, 0x00, 0x05,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x0a, 0x00, 0x08, 0x00, 0x1d, 0x00, 0x17,
+        0x00, 0x18, 0x00, 0x19, 0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x0d, 0x00, 0x12, 0x00,
+        0x10, 0x04, 0x01, 0x04, 0x03, 0x05, 0x01, 0x05, 0x03, 0x06, 0x01, 0x06, 0x03, 0x02, 0x01,
+        0x02, 0x03, 0xff, 0x01, 0x00, 0x01, 0x00, 0x00, 0x12, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn summary() {
+        let summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+
+        assert_eq!(summary.client_version, [0x03, 0x03]);
+        assert!(summary.session_id.is_empty());
+        assert_eq!(summary.cipher_suites.len(), 16);
+        assert_eq!(summary.cipher_suites[0], 0xCCA8);
+        assert_eq!(summary.compression_methods, &[0]);
+        assert_eq!(summary.extensions, &[0, 5, 10, 11, 13, 0xFF01, 18]);
+        assert_eq!(summary.server_name.as_deref(), Some("example.ulfheim.net"));
+        assert_eq!(summary.supported_groups, &[0x1D, 0x17, 0x18, 0x19]);
+        assert_eq!(summary.ec_point_formats, &[0]);
+        assert_eq!(summary.signature_algorithms.len(), 8);
+        assert!(summary.signature_algorithms_cert.is_empty());
+        assert_eq!(summary.max_version(), [0x03, 0x03]);
+    }
+
+    #[test]
+    fn server_hello() {
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0x11; 32]);
+        body.extend_from_slice(&[
+            0x00, // session_id
+            0x13, 0x01, // TLS_AES_128_GCM_SHA256
+            0x00, // null compression
+            0x00, 0x0A, // extensions length
+            0x00, 0x2B, 0x00, 0x02, 0x03, 0x04, // supported_versions
+            0xFF, 0x01, 0x00, 0x00, // renegotiation_info, unknown to ExtensionType
+        ]);
+
+        let summary = ServerHelloSummary::parse(&body).unwrap();
+        assert_eq!(summary.cipher_suite, 0x1301);
+        assert_eq!(summary.version(), [0x03, 0x04]);
+        assert_eq!(summary.extension(0xFF01), Some(&[][..]));
+        assert!(!summary.is_hello_retry_request());
+
+        assert!(ServerHelloSummary::parse(&body[..40]).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(ClientHelloSummary::parse(&CLIENT_HELLO[..100]).is_err());
+
+        // cut in the middle of the cipher suites
+        let (summary, complete) = ClientHelloSummary::parse_partial(&CLIENT_HELLO[..50]);
+        assert!(!complete);
+        assert_eq!(
+            summary.cipher_suites,
+            &[0xCCA8, 0xCCA9, 0xC02F, 0xC030, 0xC02B, 0xC02C]
+        );
+        assert!(summary.extensions.is_empty());
+
+        // cut in the middle of the extensions: server_name is complete
+        let (summary, complete) = ClientHelloSummary::parse_partial(&CLIENT_HELLO[..120]);
+        assert!(!complete);
+        assert_eq!(summary.cipher_suites.len(), 16);
+        assert_eq!(summary.server_name.as_deref(), Some("example.ulfheim.net"));
+    }
+}
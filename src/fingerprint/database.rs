@@ -0,0 +1,206 @@
+// map JA3 and JA4 fingerprints to the client software which likely sent the ClientHello. The
+// builtin database holds the fingerprints of common clients (see clients.txt) and, apart from
+// them, those of the crate's templates. It can be extended with files of lines like:
+//
+//   # comment
+//   ja3 e7d705a3286e19ea42f587b344ee6865 Firefox 102
+//   ja4 t13d1516h2_8daaf6152771_e5627efa2ab1 Chrome 120
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::fingerprint::ja3::ja3_hash;
+use crate::fingerprint::ja4::ja4;
+use crate::handshake::templates::{template, TEMPLATES};
+
+#[derive(Debug, Default, Clone)]
+pub struct FingerprintDatabase {
+    ja3: HashMap<String, String>,
+    ja4: HashMap<String, String>,
+}
+
+impl FingerprintDatabase {
+    // known clients and the crate's templates
+    pub fn builtin() -> Self {
+        let mut database = Self::clients();
+        database.merge(Self::templates());
+        database
+    }
+
+    pub fn clients() -> Self {
+        // the bundled file is checked by the tests
+        Self::parse(include_str!("clients.txt")).unwrap()
+    }
+
+    // templates are named "tls_explore <name> template", so as not to be mistaken for clients
+    pub fn templates() -> Self {
+        let mut database = Self::default();
+
+        for name in TEMPLATES {
+            // templates exist and can be encoded
+            let mut buffer = Vec::new();
+            template(name, "example.com")
+                .unwrap()
+                .to_network_bytes(&mut buffer)
+                .unwrap();
+            let summary = ClientHelloSummary::parse(&buffer).unwrap();
+
+            let client = format!("tls_explore {} template", name);
+            database.ja3.insert(ja3_hash(&summary), client.clone());
+            database.ja4.insert(ja4(&summary), client);
+        }
+
+        database
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut database = Self::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid =
+                |msg: &str| Error::new(ErrorKind::InvalidData, format!("line {}: {}", i + 1, msg));
+
+            let mut fields = line.splitn(3, char::is_whitespace);
+            let (kind, fingerprint, client) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(kind), Some(fingerprint), Some(client)) if !client.trim().is_empty() => (
+                    kind,
+                    fingerprint.to_ascii_lowercase(),
+                    client.trim().to_string(),
+                ),
+                _ => return Err(invalid("expected <ja3|ja4> <fingerprint> <client>")),
+            };
+
+            match kind {
+                "ja3"
+                    if fingerprint.len() == 32
+                        && fingerprint.chars().all(|c| c.is_ascii_hexdigit()) =>
+                {
+                    database.ja3.insert(fingerprint, client);
+                }
+                "ja4" if fingerprint.len() == 36 && fingerprint.matches('_').count() == 2 => {
+                    database.ja4.insert(fingerprint, client);
+                }
+                "ja3" | "ja4" => return Err(invalid(&format!("invalid {} {}", kind, fingerprint))),
+                _ => return Err(invalid(&format!("unknown fingerprint type {}", kind))),
+            }
+        }
+
+        Ok(database)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
+
+    // entries of the other database replace those with the same fingerprint
+    pub fn merge(&mut self, other: FingerprintDatabase) {
+        self.ja3.extend(other.ja3);
+        self.ja4.extend(other.ja4);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ja3.len() + self.ja4.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // JA4 is tried first, being less sensitive to extension order randomization
+    pub fn lookup(&self, summary: &ClientHelloSummary) -> Option<&str> {
+        self.ja4
+            .get(&ja4(summary))
+            .or_else(|| self.ja3.get(&ja3_hash(summary)))
+            .map(|x| x.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+
+    #[test]
+    fn lookup() {
+        let summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+
+        let mut database = FingerprintDatabase::builtin();
+        assert_eq!(
+            database.len(),
+            FingerprintDatabase::clients().len() + 2 * TEMPLATES.len()
+        );
+        assert!(database.lookup(&summary).is_none());
+
+        let text = format!(
+            "# from the capture\nja3 {}   curl 7.81 # old\n\nja4 {} wget\n",
+            ja3_hash(&summary).to_uppercase(),
+            "t13d1516h2_8daaf6152771_e5627efa2ab1"
+        );
+        database.merge(FingerprintDatabase::parse(&text).unwrap());
+        assert_eq!(database.lookup(&summary), Some("curl 7.81"));
+
+        // JA4 first
+        let text = format!("ja4 {} OpenSSL", ja4(&summary));
+        database.merge(FingerprintDatabase::parse(&text).unwrap());
+        assert_eq!(database.lookup(&summary), Some("OpenSSL"));
+    }
+
+    #[test]
+    fn templates() {
+        let mut buffer = Vec::new();
+        template("chrome", "www.example.org")
+            .unwrap()
+            .to_network_bytes(&mut buffer)
+            .unwrap();
+        let summary = ClientHelloSummary::parse(&buffer).unwrap();
+
+        assert_eq!(
+            FingerprintDatabase::builtin().lookup(&summary),
+            Some("tls_explore chrome template")
+        );
+    }
+
+    #[test]
+    fn clients() {
+        let database = FingerprintDatabase::clients();
+        assert_eq!(database.len(), 15);
+        assert_eq!(
+            database
+                .ja4
+                .get("t13d3112h2_e8f1e7e78f70_b26ce05bbdd6")
+                .map(|x| x.as_str()),
+            Some("curl 7.88 (OpenSSL 3.0)")
+        );
+        assert_eq!(
+            database
+                .ja3
+                .get("e7d705a3286e19ea42f587b344ee6865")
+                .map(|x| x.as_str()),
+            Some("Tor")
+        );
+
+        // no template passes for a client
+        let templates = FingerprintDatabase::templates();
+        assert!(templates.ja3.keys().all(|x| !database.ja3.contains_key(x)));
+        assert!(templates.ja4.keys().all(|x| !database.ja4.contains_key(x)));
+    }
+
+    #[test]
+    fn errors() {
+        let err = FingerprintDatabase::parse("ja3 1234 foo").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid ja3 1234");
+
+        assert!(FingerprintDatabase::parse("ja5 1234 foo").is_err());
+        assert!(FingerprintDatabase::parse("ja3 0123456789abcdef0123456789abcdef").is_err());
+        assert!(FingerprintDatabase::parse("ja4 t13d1516h2 foo").is_err());
+    }
+}
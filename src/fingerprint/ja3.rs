@@ -0,0 +1,56 @@
+// JA3 TLS client fingerprinting: https://github.com/salesforce/ja3
+use crate::crypto::{md5::md5, to_hex};
+use crate::dissector::summary::ClientHelloSummary;
+use crate::handshake::common::is_grease;
+
+// join values with '-', GREASE values excluded
+fn join<T: Copy + Into<u16>>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|x| (*x).into())
+        .filter(|x| !is_grease(*x))
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// SSLVersion,Cipher,SSLExtension,EllipticCurve,EllipticCurvePointFormat
+pub fn ja3_string(summary: &ClientHelloSummary) -> String {
+    format!(
+        "{},{},{},{},{}",
+        u16::from_be_bytes(summary.client_version),
+        join(&summary.cipher_suites),
+        join(&summary.extensions),
+        join(&summary.supported_groups),
+        join(&summary.ec_point_formats),
+    )
+}
+
+// the fingerprint is the MD5 of the JA3 string
+pub fn ja3_hash(summary: &ClientHelloSummary) -> String {
+    to_hex(&md5(ja3_string(summary).as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+
+    #[test]
+    fn ja3() {
+        let mut summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        assert_eq!(
+            ja3_string(&summary),
+            "771,52392-52393-49199-49200-49195-49196-49171-49161-49172-49162-156-157-47-53-49170-10,0-5-10-11-13-65281-18,29-23-24-25,0"
+        );
+
+        // GREASE values are ignored
+        summary.cipher_suites.insert(0, 0x2A2A);
+        summary.extensions.insert(0, 0xDADA);
+        let hash = ja3_hash(&summary);
+        summary.cipher_suites.remove(0);
+        summary.extensions.remove(0);
+        assert_eq!(hash, ja3_hash(&summary));
+        assert_eq!(hash.len(), 32);
+    }
+}
@@ -0,0 +1,146 @@
+// JA4 TLS client fingerprinting: https://github.com/FoxIO-LLC/ja4/blob/main/technical_details/JA4.md
+// e.g. t13d1516h2_8daaf6152771_e5627efa2ab1
+use crate::crypto::{sha2::sha256, to_hex};
+use crate::dissector::summary::ClientHelloSummary;
+use crate::handshake::client_hello::ExtensionType;
+use crate::handshake::common::is_grease;
+
+fn version_code(summary: &ClientHelloSummary) -> &'static str {
+    match summary.max_version() {
+        [0x03, 0x04] => "13",
+        [0x03, 0x03] => "12",
+        [0x03, 0x02] => "11",
+        [0x03, 0x01] => "10",
+        [0x03, 0x00] => "s3",
+        _ => "00",
+    }
+}
+
+// first and last characters of the first ALPN value, or of its hex form if they're not
+// alphanumeric
+fn alpn_code(summary: &ClientHelloSummary) -> String {
+    let Some(alpn) = summary.alpn.first().filter(|x| !x.is_empty()) else {
+        return String::from("00");
+    };
+
+    let (first, last) = (alpn.chars().next(), alpn.chars().last());
+    match (first, last) {
+        (Some(first), Some(last))
+            if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() =>
+        {
+            format!("{}{}", first, last)
+        }
+        _ => {
+            let hex = to_hex(alpn.as_bytes());
+            format!("{}{}", &hex[..1], &hex[hex.len() - 1..])
+        }
+    }
+}
+
+// 4-digit lowercase hex values, GREASE excluded
+fn hex_list(values: &[u16]) -> Vec<String> {
+    values
+        .iter()
+        .filter(|x| !is_grease(**x))
+        .map(|x| format!("{:04x}", x))
+        .collect()
+}
+
+// first 12 hex digits of the SHA-256, or zeros for an empty list
+fn truncated_hash(text: &str) -> String {
+    if text.is_empty() {
+        return String::from("000000000000");
+    }
+    to_hex(&sha256(text.as_bytes()))[..12].to_string()
+}
+
+// JA4 for a ClientHello sent over TCP
+pub fn ja4(summary: &ClientHelloSummary) -> String {
+    let ciphers = hex_list(&summary.cipher_suites);
+    let extensions = hex_list(&summary.extensions);
+
+    let sni = if summary
+        .extensions
+        .contains(&u16::from(ExtensionType::server_name))
+    {
+        'd'
+    } else {
+        'i'
+    };
+
+    let prefix = format!(
+        "t{}{}{:02}{:02}{}",
+        version_code(summary),
+        sni,
+        ciphers.len().min(99),
+        extensions.len().min(99),
+        alpn_code(summary)
+    );
+
+    let mut sorted_ciphers = ciphers;
+    sorted_ciphers.sort();
+
+    // SNI and ALPN are already in the prefix
+    let mut sorted_extensions: Vec<_> = extensions
+        .into_iter()
+        .filter(|x| x != "0000" && x != "0010")
+        .collect();
+    sorted_extensions.sort();
+
+    // signature algorithms are kept in their original order
+    let mut extension_text = sorted_extensions.join(",");
+    let signature_algorithms = hex_list(&summary.signature_algorithms);
+    if !sorted_extensions.is_empty() && !signature_algorithms.is_empty() {
+        extension_text.push('_');
+        extension_text.push_str(&signature_algorithms.join(","));
+    }
+
+    format!(
+        "{}_{}_{}",
+        prefix,
+        truncated_hash(&sorted_ciphers.join(",")),
+        truncated_hash(&extension_text)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+
+    #[test]
+    fn chrome() {
+        // the example of the JA4 specification, with GREASE values
+        let summary = ClientHelloSummary {
+            client_version: [0x03, 0x03],
+            cipher_suites: vec![
+                0x0A0A, 0x1301, 0x1302, 0x1303, 0xC02B, 0xC02F, 0xC02C, 0xC030, 0xCCA9, 0xCCA8,
+                0xC013, 0xC014, 0x009C, 0x009D, 0x002F, 0x0035,
+            ],
+            extensions: vec![
+                0x1A1A, 0x0000, 0x0017, 0xFF01, 0x000A, 0x000B, 0x0023, 0x0010, 0x0005, 0x000D,
+                0x0012, 0x0033, 0x002D, 0x002B, 0x001B, 0x4469, 0x0015, 0x2A2A,
+            ],
+            alpn: vec![String::from("h2"), String::from("http/1.1")],
+            supported_versions: vec![[0x4A, 0x4A], [0x03, 0x04], [0x03, 0x03]],
+            signature_algorithms: vec![
+                0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(ja4(&summary), "t13d1516h2_8daaf6152771_e5627efa2ab1");
+    }
+
+    #[test]
+    fn tls12() {
+        let mut summary = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        assert!(ja4(&summary).starts_with("t12d160700_"));
+
+        summary.alpn = vec![String::from("\u{1}x")];
+        summary.extensions.clear();
+        let fingerprint = ja4(&summary);
+        assert!(fingerprint.starts_with("t12i160008_"));
+        assert!(fingerprint.ends_with("_000000000000"));
+    }
+}
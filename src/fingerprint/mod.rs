@@ -0,0 +1,3 @@
+pub mod database;
+pub mod ja3;
+pub mod ja4;
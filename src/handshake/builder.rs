@@ -0,0 +1,291 @@
+// build a ClientHello from what it offers, instead of assembling extensions by hand. Lengths
+// are set by the ClientHello methods used, so the result can be sent as is:
+//
+//   let ch = ClientHelloBuilder::new().version(TLS13).sni("example.com").alpn(&["h2"]).build()?;
+use std::io::Result;
+
+use crate::crypto::x25519::X25519KeyPair;
+use crate::handshake::client_hello::{
+    ClientHello, ExtensionType, GenericExtension, KeyShare, NamedGroup, ProtocolNameList,
+    SignatureAlgorithms, SignatureScheme, SupportedVersions, UnknownExtension,
+};
+use crate::handshake::common::{CipherSuite, ProtocolVersion};
+use crate::handshake::constants::*;
+
+// a random GREASE value: https://datatracker.ietf.org/doc/html/rfc8701#section-2
+fn grease() -> u16 {
+    let byte = rand::random::<u8>() & 0xF0 | 0x0A;
+    u16::from_be_bytes([byte, byte])
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientHelloBuilder {
+    version: ProtocolVersion,
+
+    // depends on the version when not set
+    cipher_suites: Option<Vec<CipherSuite>>,
+    sni: Option<String>,
+    alpn: Vec<String>,
+    groups: Vec<NamedGroup>,
+    signature_algorithms: Vec<SignatureScheme>,
+    grease: bool,
+
+    // the x25519 share offered with TLS 1.3, a new one when not set
+    key_share: Option<X25519KeyPair>,
+}
+
+impl Default for ClientHelloBuilder {
+    fn default() -> Self {
+        Self {
+            version: TLS12,
+            cipher_suites: None,
+            sni: None,
+            alpn: Vec::new(),
+            groups: vec![NamedGroup::x25519, NamedGroup::secp256r1],
+            signature_algorithms: vec![
+                SignatureScheme::ecdsa_secp256r1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pkcs1_sha256,
+            ],
+            grease: false,
+            key_share: None,
+        }
+    }
+}
+
+impl ClientHelloBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the highest version offered. TLS 1.3 is offered with supported_versions, along with
+    // TLS 1.2
+    pub fn version(mut self, version: ProtocolVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn cipher_suites(mut self, suites: &[CipherSuite]) -> Self {
+        self.cipher_suites = Some(suites.to_vec());
+        self
+    }
+
+    // keys whose public part is offered, so the caller can compute the shared secret from
+    // the server's share
+    pub fn key_share(mut self, keys: &X25519KeyPair) -> Self {
+        self.key_share = Some(keys.clone());
+        self
+    }
+
+    // see ClientHello::with_server_name for the names accepted
+    pub fn sni(mut self, host: &str) -> Self {
+        self.sni = Some(host.to_string());
+        self
+    }
+
+    pub fn alpn(mut self, protocols: &[&str]) -> Self {
+        self.alpn = protocols.iter().map(|x| x.to_string()).collect();
+        self
+    }
+
+    pub fn groups(mut self, groups: &[NamedGroup]) -> Self {
+        self.groups = groups.to_vec();
+        self
+    }
+
+    pub fn signature_algorithms(mut self, schemes: &[SignatureScheme]) -> Self {
+        self.signature_algorithms = schemes.to_vec();
+        self
+    }
+
+    // add GREASE values to the cipher suites, groups and versions, and 2 GREASE extensions,
+    // first and last, as browsers do
+    pub fn grease(mut self, grease: bool) -> Self {
+        self.grease = grease;
+        self
+    }
+
+    fn default_suites(&self) -> Vec<CipherSuite> {
+        let tls12 = [
+            TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            TLS_RSA_WITH_AES_128_CBC_SHA,
+        ];
+        let tls13 = [
+            TLS_AES_128_GCM_SHA256,
+            TLS_AES_256_GCM_SHA384,
+            TLS_CHACHA20_POLY1305_SHA256,
+        ];
+
+        match self.version {
+            TLS13 => tls13.iter().chain(&tls12).copied().collect(),
+            _ => tls12.to_vec(),
+        }
+    }
+
+    // fails if the SNI host name can't be sent
+    pub fn build(&self) -> Result<ClientHello> {
+        let mut suites = self
+            .cipher_suites
+            .clone()
+            .unwrap_or_else(|| self.default_suites());
+        if self.grease {
+            suites.insert(0, grease().to_be_bytes());
+        }
+
+        // TLS 1.3 keeps TLS 1.2 as the legacy version
+        let tls13 = self.version == TLS13;
+        let mut ch = ClientHello::new(&suites);
+        if !tls13 {
+            ch = ch.with_version(self.version);
+        }
+
+        let first_grease = grease();
+        if self.grease {
+            ch.push_extension(GenericExtension::from(UnknownExtension {
+                type_code: first_grease,
+                data: Vec::new(),
+            }));
+        }
+
+        if let Some(host) = &self.sni {
+            ch = ch.with_server_name(host)?;
+        }
+
+        if !self.groups.is_empty() {
+            // encoded by hand, GREASE not being a NamedGroup
+            let mut groups: Vec<u16> = self.groups.iter().map(|&x| x as u16).collect();
+            if self.grease {
+                groups.insert(0, grease());
+            }
+            let mut data = ((groups.len() * 2) as u16).to_be_bytes().to_vec();
+            groups
+                .iter()
+                .for_each(|x| data.extend_from_slice(&x.to_be_bytes()));
+            ch.push_extension(GenericExtension::new(
+                ExtensionType::supported_groups,
+                &data,
+            ));
+            ch.push_extension(GenericExtension::new(
+                ExtensionType::ec_point_formats,
+                &[0x01, 0x00],
+            ));
+        }
+
+        if !self.signature_algorithms.is_empty() {
+            ch.add_extension(&SignatureAlgorithms::new(&self.signature_algorithms))?;
+        }
+
+        if !self.alpn.is_empty() {
+            let protocols: Vec<&str> = self.alpn.iter().map(|x| x.as_str()).collect();
+            ch.add_extension(&ProtocolNameList::new(&protocols))?;
+        }
+
+        if tls13 {
+            // a share for x25519 only. Servers preferring another group answer with a
+            // HelloRetryRequest
+            if self.groups.contains(&NamedGroup::x25519) {
+                let keys = self
+                    .key_share
+                    .clone()
+                    .unwrap_or_else(X25519KeyPair::generate);
+                ch.add_extension(&KeyShare::new(vec![keys.key_share()]))?;
+            }
+            ch.push_extension(GenericExtension::new(
+                ExtensionType::psk_key_exchange_modes,
+                &[0x01, 0x01],
+            ));
+
+            let mut versions = vec![TLS13, TLS12];
+            if self.grease {
+                versions.insert(0, grease().to_be_bytes());
+            }
+            ch.add_extension(&SupportedVersions::new(&versions))?;
+        }
+
+        // 2 GREASE extensions can't have the same type
+        if self.grease {
+            ch.push_extension(GenericExtension::from(UnknownExtension {
+                type_code: first_grease ^ 0x1010,
+                data: vec![0x00],
+            }));
+        }
+
+        Ok(ch)
+    }
+}
+
+impl ClientHello {
+    pub fn builder() -> ClientHelloBuilder {
+        ClientHelloBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_tls::TlsDerive;
+    use crate::dissector::summary::ClientHelloSummary;
+    use crate::handshake::common::is_grease;
+
+    fn parse(ch: &ClientHello) -> ClientHelloSummary {
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+        ClientHelloSummary::parse(&buffer).unwrap()
+    }
+
+    #[test]
+    fn tls12() {
+        let ch = ClientHello::builder()
+            .cipher_suites(&[TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256])
+            .sni("example.com")
+            .build()
+            .unwrap();
+        let summary = parse(&ch);
+        assert_eq!(summary.server_name.as_deref(), Some("example.com"));
+        assert_eq!(summary.cipher_suites, vec![0xC02F]);
+        assert!(summary.supported_versions.is_empty());
+        assert!(summary.alpn.is_empty());
+        assert_eq!(ch.client_version(), TLS12);
+
+        // SNI is left out for IP literals
+        let ch = ClientHello::builder().sni("192.0.2.1").build().unwrap();
+        assert!(parse(&ch).server_name.is_none());
+        assert!(ClientHello::builder().sni("a..b").build().is_err());
+    }
+
+    #[test]
+    fn tls13() {
+        let keys = X25519KeyPair::generate();
+        let ch = ClientHelloBuilder::new()
+            .version(TLS13)
+            .sni("example.com")
+            .alpn(&["h2", "http/1.1"])
+            .groups(&[NamedGroup::x25519])
+            .signature_algorithms(&[SignatureScheme::ed25519])
+            .grease(true)
+            .key_share(&keys)
+            .build()
+            .unwrap();
+        assert_eq!(ch.client_version(), TLS12);
+
+        let summary = parse(&ch);
+        assert_eq!(summary.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(&summary.supported_versions[1..], &[TLS13, TLS12]);
+        assert!(is_grease(u16::from_be_bytes(summary.supported_versions[0])));
+        assert!(is_grease(summary.cipher_suites[0]));
+        assert_eq!(summary.cipher_suites[1], 0x1301);
+
+        // GREASE first and last, with different values
+        let first = summary.extensions[0];
+        let last = *summary.extensions.last().unwrap();
+        assert!(is_grease(first) && is_grease(last) && first != last);
+        assert_eq!(summary.key_shares, vec![(29, keys.public_key().to_vec())]);
+
+        // without GREASE, which the typed extensions can't hold, the hello can be parsed back
+        let ch = ClientHelloBuilder::new().version(TLS13).build().unwrap();
+        assert_eq!(ch.parsed_extensions().unwrap().len(), ch.extensions().len());
+    }
+}
@@ -0,0 +1,267 @@
+// the Certificate handshake message. Its layout depends on the negotiated version:
+// TLS 1.2: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.2
+// TLS 1.3: https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.2
+use std::io::{Cursor, Result};
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::{
+    CertificateStatusType, DelegatedCredential, ExtensionType, GenericExtension,
+};
+use crate::handshake::common::{ProtocolVersion, VariableLengthVector};
+use crate::handshake::constants::TLS13;
+use crate::x509::inspect::{inspect, CertificateInfo};
+use tls_derive::TlsDerive;
+
+// DER-encoded X.509 certificate
+pub type ASN1Cert = VariableLengthVector<u8, 1, 3>;
+
+// TLS 1.2 and below: only a list of certificates
+#[derive(Debug, Default, TlsDerive)]
+pub struct Certificate {
+    pub certificate_list: VariableLengthVector<ASN1Cert, 0, 3>,
+}
+
+impl Certificate {
+    pub fn new(certificates: &[&[u8]]) -> Self {
+        let mut certificate_list = VariableLengthVector::default();
+
+        for cert in certificates {
+            let cert: ASN1Cert = VariableLengthVector::from_slice(cert);
+            certificate_list.length += cert.tls_len() as u32;
+            certificate_list.data.push(cert);
+        }
+
+        Self { certificate_list }
+    }
+
+    // DER encoding of each certificate, leaf first
+    pub fn certificates(&self) -> Vec<&[u8]> {
+        self.certificate_list
+            .data
+            .iter()
+            .map(|cert| cert.data.as_slice())
+            .collect()
+    }
+}
+
+// TLS 1.3: each certificate comes with its own extensions
+#[derive(Debug, Default, TlsDerive)]
+pub struct CertificateEntry {
+    pub cert_data: ASN1Cert,
+    pub extensions: VariableLengthVector<GenericExtension, 0, 2>,
+}
+
+impl CertificateEntry {
+    // the delegated credential sent with the end-entity certificate, if any
+    pub fn delegated_credential(&self) -> Option<Result<DelegatedCredential>> {
+        self.extensions
+            .data
+            .iter()
+            .find(|x| x.extension_type() == ExtensionType::delegated_credential)
+            .map(|x| DelegatedCredential::from_extension_data(x.extension_data()))
+    }
+}
+
+#[derive(Debug, Default, TlsDerive)]
+pub struct Certificate13 {
+    pub certificate_request_context: VariableLengthVector<u8, 0, 1>,
+    pub certificate_list: VariableLengthVector<CertificateEntry, 0, 3>,
+}
+
+impl Certificate13 {
+    // certificates are sent without extensions
+    pub fn new(context: &[u8], certificates: &[&[u8]]) -> Self {
+        let mut certificate_list = VariableLengthVector::default();
+
+        for cert in certificates {
+            let entry = CertificateEntry {
+                cert_data: VariableLengthVector::from_slice(cert),
+                extensions: VariableLengthVector::default(),
+            };
+            certificate_list.length += entry.tls_len() as u32;
+            certificate_list.data.push(entry);
+        }
+
+        Self {
+            certificate_request_context: VariableLengthVector::from_slice(context),
+            certificate_list,
+        }
+    }
+
+    // DER encoding of each certificate, leaf first
+    pub fn certificates(&self) -> Vec<&[u8]> {
+        self.certificate_list
+            .data
+            .iter()
+            .map(|entry| entry.cert_data.data.as_slice())
+            .collect()
+    }
+}
+
+// both layouts can't be distinguished from the bytes alone, so the negotiated
+// version selects the one to use
+#[derive(Debug)]
+pub enum CertificateMessage {
+    Tls12(Certificate),
+    Tls13(Certificate13),
+}
+
+impl Default for CertificateMessage {
+    fn default() -> Self {
+        CertificateMessage::Tls12(Certificate::default())
+    }
+}
+
+impl CertificateMessage {
+    // an empty message ready to be parsed with the layout of the negotiated version
+    pub fn for_version(version: ProtocolVersion) -> Self {
+        if version >= TLS13 {
+            CertificateMessage::Tls13(Certificate13::default())
+        } else {
+            CertificateMessage::Tls12(Certificate::default())
+        }
+    }
+
+    pub fn certificates(&self) -> Vec<&[u8]> {
+        match self {
+            CertificateMessage::Tls12(c) => c.certificates(),
+            CertificateMessage::Tls13(c) => c.certificates(),
+        }
+    }
+
+    // the fields of each certificate of the chain, in order
+    pub fn inspect(&self) -> Vec<Result<CertificateInfo>> {
+        self.certificates().into_iter().map(inspect).collect()
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(CertificateMessage);
+
+impl TlsDerive for CertificateMessage {
+    fn tls_len(&self) -> usize {
+        match self {
+            CertificateMessage::Tls12(c) => c.tls_len(),
+            CertificateMessage::Tls13(c) => c.tls_len(),
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        match self {
+            CertificateMessage::Tls12(c) => c.to_network_bytes(v),
+            CertificateMessage::Tls13(c) => c.to_network_bytes(v),
+        }
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        match self {
+            CertificateMessage::Tls12(c) => c.from_network_bytes(v),
+            CertificateMessage::Tls13(c) => c.from_network_bytes(v),
+        }
+    }
+}
+
+// OCSP response stapled by the server after its Certificate: https://datatracker.ietf.org/doc/html/rfc6066#section-8
+#[derive(Debug, Default, TlsDerive)]
+pub struct CertificateStatus {
+    pub status_type: CertificateStatusType,
+    pub response: VariableLengthVector<u8, 1, 3>,
+}
+
+impl CertificateStatus {
+    pub fn ocsp(response: &[u8]) -> Self {
+        Self {
+            status_type: CertificateStatusType::ocsp,
+            response: VariableLengthVector::from_slice(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::TLS12;
+
+    #[test]
+    fn certificate12() {
+        let data = vec![
+            0x00, 0x00, 0x0B, // certificate_list length
+            0x00, 0x00, 0x03, 0x30, 0x01, 0x02, // first cert
+            0x00, 0x00, 0x02, 0x30, 0x00, // second cert
+        ];
+
+        let mut cert = CertificateMessage::for_version(TLS12);
+        assert!(cert
+            .from_network_bytes(&mut Cursor::new(data.clone()))
+            .is_ok());
+        assert!(matches!(cert, CertificateMessage::Tls12(_)));
+        assert_eq!(
+            cert.certificates(),
+            vec![&[0x30, 0x01, 0x02][..], &[0x30, 0x00][..]]
+        );
+        assert!(cert.inspect().iter().all(|x| x.is_err()));
+
+        let built = Certificate::new(&[&[0x30, 0x01, 0x02], &[0x30, 0x00]]);
+        let mut buffer = Vec::new();
+        assert_eq!(built.to_network_bytes(&mut buffer).unwrap(), data.len());
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn certificate13() {
+        let data = vec![
+            0x01, 0xAA, // certificate_request_context
+            0x00, 0x00, 0x13, // certificate_list length
+            0x00, 0x00, 0x02, 0x30, 0x00, // cert_data
+            0x00, 0x06, 0x00, 0x05, 0x00, 0x02, 0xFF, 0xFF, // status_request extension
+            0x00, 0x00, 0x01, 0x30, // cert_data
+            0x00, 0x00, // no extension
+        ];
+
+        let mut cert = CertificateMessage::for_version(TLS13);
+        assert!(cert
+            .from_network_bytes(&mut Cursor::new(data.clone()))
+            .is_ok());
+        assert_eq!(cert.certificates(), vec![&[0x30, 0x00][..], &[0x30][..]]);
+        assert_eq!(cert.tls_len(), data.len());
+
+        if let CertificateMessage::Tls13(c) = &cert {
+            assert_eq!(c.certificate_request_context.data, &[0xAA]);
+            assert_eq!(c.certificate_list.data[0].extensions.data.len(), 1);
+            assert!(c.certificate_list.data[0].delegated_credential().is_none());
+            assert!(c.certificate_list.data[1].extensions.data.is_empty());
+        } else {
+            panic!("not a TLS 1.3 certificate");
+        }
+
+        let mut buffer = Vec::new();
+        assert!(cert.to_network_bytes(&mut buffer).is_ok());
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn build13() {
+        let cert = Certificate13::new(&[], &[&[0x30, 0x00]]);
+        let mut buffer = Vec::new();
+        assert!(cert.to_network_bytes(&mut buffer).is_ok());
+        assert_eq!(
+            buffer,
+            &[0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x02, 0x30, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn certificate_status() {
+        let status = CertificateStatus::ocsp(&[0x30, 0x03, 0x0A, 0x01, 0x00]);
+        let mut buffer = Vec::new();
+        assert!(status.to_network_bytes(&mut buffer).is_ok());
+        assert_eq!(
+            buffer,
+            &[0x01, 0x00, 0x00, 0x05, 0x30, 0x03, 0x0A, 0x01, 0x00]
+        );
+
+        let mut parsed = CertificateStatus::default();
+        assert!(parsed.from_network_bytes(&mut Cursor::new(buffer)).is_ok());
+        assert_eq!(parsed.response.data, status.response.data);
+    }
+}
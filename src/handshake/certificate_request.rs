@@ -0,0 +1,114 @@
+// TLS 1.2 CertificateRequest message: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.4
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::SignatureScheme;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum ClientCertificateType {
+    rsa_sign = 1,
+    dss_sign = 2,
+    rsa_fixed_dh = 3,
+    dss_fixed_dh = 4,
+    ecdsa_sign = 64,
+}
+
+// DER-encoded distinguished name of an acceptable certificate authority
+pub type DistinguishedName = VariableLengthVector<u8, 1, 2>;
+
+#[derive(Debug, Default, TlsDerive)]
+pub struct CertificateRequest {
+    pub certificate_types: VariableLengthVector<ClientCertificateType, 1, 1>,
+    pub supported_signature_algorithms: VariableLengthVector<SignatureScheme, 2, 2>,
+    pub certificate_authorities: VariableLengthVector<DistinguishedName, 0, 2>,
+}
+
+impl CertificateRequest {
+    pub fn new(
+        certificate_types: &[ClientCertificateType],
+        schemes: &[SignatureScheme],
+        authorities: &[&[u8]],
+    ) -> Self {
+        let mut certificate_authorities = VariableLengthVector::default();
+        for authority in authorities {
+            let name: DistinguishedName = VariableLengthVector::from_slice(authority);
+            certificate_authorities.length += name.tls_len() as u32;
+            certificate_authorities.data.push(name);
+        }
+
+        Self {
+            certificate_types: VariableLengthVector::from_slice(certificate_types),
+            supported_signature_algorithms: VariableLengthVector::from_slice(schemes),
+            certificate_authorities,
+        }
+    }
+
+    pub fn schemes(&self) -> &[SignatureScheme] {
+        &self.supported_signature_algorithms.data
+    }
+}
+
+// TLS 1.2 CertificateVerify message: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.8
+#[derive(Debug, Default, TlsDerive)]
+pub struct CertificateVerify {
+    pub algorithm: SignatureScheme,
+    pub signature: VariableLengthVector<u8, 0, 2>,
+}
+
+impl CertificateVerify {
+    pub fn new(algorithm: SignatureScheme, signature: &[u8]) -> Self {
+        Self {
+            algorithm,
+            signature: VariableLengthVector::from_slice(signature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn certificate_request() {
+        let cr = CertificateRequest::new(
+            &[
+                ClientCertificateType::rsa_sign,
+                ClientCertificateType::ecdsa_sign,
+            ],
+            &[SignatureScheme::ecdsa_secp256r1_sha256],
+            &[&[0x30, 0x00]],
+        );
+
+        let mut buffer = Vec::new();
+        cr.to_network_bytes(&mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            &[0x02, 0x01, 0x40, 0x00, 0x02, 0x04, 0x03, 0x00, 0x04, 0x00, 0x02, 0x30, 0x00]
+        );
+
+        let mut parsed = CertificateRequest::default();
+        parsed.from_network_bytes(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(parsed.schemes(), &[SignatureScheme::ecdsa_secp256r1_sha256]);
+        assert_eq!(parsed.certificate_authorities.data[0].data, &[0x30, 0x00]);
+    }
+
+    #[test]
+    fn certificate_verify() {
+        let data = vec![0x08, 0x04, 0x00, 0x02, 0xAA, 0xBB];
+
+        let mut cv = CertificateVerify::default();
+        cv.from_network_bytes(&mut Cursor::new(data.clone()))
+            .unwrap();
+        assert_eq!(cv.algorithm, SignatureScheme::rsa_pss_rsae_sha256);
+        assert_eq!(cv.signature.data, &[0xAA, 0xBB]);
+
+        let mut buffer = Vec::new();
+        CertificateVerify::new(SignatureScheme::rsa_pss_rsae_sha256, &[0xAA, 0xBB])
+            .to_network_bytes(&mut buffer)
+            .unwrap();
+        assert_eq!(buffer, data);
+    }
+}
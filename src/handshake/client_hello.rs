@@ -1,36 +1,67 @@
 // the definition of the client_hello structure as defined in https://datatracker.ietf.org/doc/html/rfc5246#appendix-A.4
 //use std::io::Result;
 
+use crate::alert::alert::AlertDescription;
 use crate::derive_tls::TlsDerive;
-use crate::ext_type;
 use crate::handshake::common::{
-    CipherSuite, CompressionMethod, ProtocolVersion, Random, SessionID, VariableLengthVector,
+    is_grease, CipherSuite, CompressionMethod, ProtocolVersion, Random, SessionID,
+    VariableLengthVector,
 };
 use crate::handshake::constants::*;
+use crate::handshake::extensions::{registration, Extension};
+use crate::handshake::handshake::Handshake;
+use crate::handshake::server_hello::ServerHello;
 use tls_derive::{TlsDerive, TlsEnum};
 
+// extensions were defined here, before having their own module
+pub use crate::handshake::extensions::alpn::*;
+pub use crate::handshake::extensions::application_settings::*;
+pub use crate::handshake::extensions::client_certificate_url::*;
+pub use crate::handshake::extensions::collection::*;
+pub use crate::handshake::extensions::cookie::*;
+pub use crate::handshake::extensions::delegated_credential::*;
+pub use crate::handshake::extensions::heartbeat::*;
+pub use crate::handshake::extensions::key_share::*;
+pub use crate::handshake::extensions::max_fragment_length::*;
+pub use crate::handshake::extensions::post_handshake_auth::*;
+pub use crate::handshake::extensions::pre_shared_key::*;
+pub use crate::handshake::extensions::quic_transport_parameters::*;
+pub use crate::handshake::extensions::signature_algorithms::*;
+pub use crate::handshake::extensions::signed_certificate_timestamp::*;
+pub use crate::handshake::extensions::sni::*;
+pub use crate::handshake::extensions::status_request::*;
+pub use crate::handshake::extensions::supported_groups::*;
+pub use crate::handshake::extensions::supported_versions::*;
+pub use crate::handshake::extensions::truncated_hmac::*;
+pub use crate::handshake::extensions::trusted_ca_keys::*;
+pub use crate::handshake::extensions::use_srtp::*;
+pub use crate::handshake::extensions::ExtensionContext;
+
 //
-#[derive(Debug, Default, TlsDerive)]
+#[derive(Debug, Default, Clone)]
 pub struct ClientHello {
     client_version: ProtocolVersion,
     random: Random,
     session_id: SessionID,
     cipher_suites: VariableLengthVector<CipherSuite, 2, 2>,
     compression_methods: VariableLengthVector<CompressionMethod, 1, 1>,
-    extensions: Option<VariableLengthVector<GenericExtension, 0, 2>>,
+    extensions: Option<Extensions>,
 }
 
 impl ClientHello {
     // create a new ClientHello without any extension
     pub fn new(suites: &[CipherSuite]) -> Self {
+        let mut cipher_suites = VariableLengthVector {
+            length: 0,
+            data: suites.to_vec(),
+        };
+        cipher_suites.fix_length();
+
         Self {
             client_version: TLS12,
             random: Random::new(),
-            session_id: rand::random(),
-            cipher_suites: VariableLengthVector {
-                length: 2,
-                data: suites.to_vec().clone(),
-            },
+            session_id: VariableLengthVector::from_slice(&rand::random::<[u8; 32]>()),
+            cipher_suites,
             compression_methods: VariableLengthVector {
                 length: 1,
                 data: vec![0u8],
@@ -39,47 +70,259 @@ impl ClientHello {
         }
     }
 
-    // add any type of extension
-    // pub fn add_extension<T: TlsToNetworkBytes + ExtType>(
-    //     &mut self,
-    //     extension: T,
-    // ) -> std::io::Result<()> {
-    //     // init a vector for converting to Vec<u8> and convert the extension
-    //     let mut v = Vec::new();
-    //     extension.to_network_bytes(&mut v)?;
-
-    //     // init extensions field if not already existing
-    //     // if self.extensions.is_none() {
-    //     //     self.extensions = Some(VariableLengthVector::new(0, None));
-    //     // }
-    //     let ext = Extension {
-    //         extension_type: extension.extension_type(),
-    //         extension_data: VariableLengthVector::<u16, u8, 0>::new(0, Some(&v)),
-    //     };
-
-    //     self.push_extension(ext);
-
-    //     Ok(())
-    // }
-
-    // fn push_extension(&mut self, extension: GenericExtension) {
-    //     // if no extension yet, create a new vec with the extension argument
-    //     if self.extensions.is_none() {
-    //         self.extensions = Some(VariableLengthVector {
-    //             length: extension.tls_len() as u16,
-    //             data: vec![extension],
-    //         });
-    //     } else {
-    //         let x = self.extensions.as_mut().unwrap();
-    //         x.length = extension.tls_len() as u16;
-    //         x.data.push(extension);
-    //     }
-    // }
+    pub fn client_version(&self) -> ProtocolVersion {
+        self.client_version
+    }
+
+    pub fn cipher_suites(&self) -> &[CipherSuite] {
+        &self.cipher_suites.data
+    }
+
+    pub fn compression_methods(&self) -> &[CompressionMethod] {
+        &self.compression_methods.data
+    }
+
+    pub fn extensions(&self) -> &[GenericExtension] {
+        self.extensions
+            .as_ref()
+            .map(|x| x.as_slice())
+            .unwrap_or_default()
+    }
+
+    // the typed body of each extension
+    pub fn parsed_extensions(&self) -> std::io::Result<Vec<ExtensionPayload>> {
+        self.extensions()
+            .iter()
+            .map(|x| x.parse(ExtensionContext::ClientHello))
+            .collect()
+    }
+
+    // the methods below return a modified copy with all lengths fixed up, so a hello can
+    // be changed step by step to find which part a peer doesn't accept
+
+    pub fn with_version(&self, version: ProtocolVersion) -> Self {
+        let mut ch = self.clone();
+        ch.client_version = version;
+        ch
+    }
+
+    pub fn with_suites(&self, suites: &[CipherSuite]) -> Self {
+        let mut ch = self.clone();
+        ch.cipher_suites.data = suites.to_vec();
+        ch.cipher_suites.fix_length();
+        ch
+    }
+
+    pub fn with_suite_removed(&self, suite: CipherSuite) -> Self {
+        let mut ch = self.clone();
+        ch.cipher_suites.data.retain(|x| *x != suite);
+        ch.cipher_suites.fix_length();
+        ch
+    }
+
+    // offer to resume a session, or no session with an empty ID
+    pub fn with_session_id(&self, session_id: &[u8]) -> Self {
+        let mut ch = self.clone();
+        ch.session_id = VariableLengthVector::from_slice(session_id);
+        ch
+    }
+
+    // replace the extension of the same type, or add it at the end if it's not there. A
+    // pre_shared_key extension being last, others are added before it
+    pub fn with_extension_replaced(&self, extension: GenericExtension) -> Self {
+        let mut ch = self.clone();
+        ch.extensions
+            .get_or_insert_with(Default::default)
+            .insert(extension);
+        ch
+    }
+
+    // the extensions in the order of a profile, see Extensions::sort_like()
+    pub fn with_extension_order<T: Copy + Into<u16>>(&self, profile: &[T]) -> Self {
+        let mut ch = self.clone();
+        if let Some(extensions) = ch.extensions.as_mut() {
+            extensions.sort_like(profile);
+        }
+        ch
+    }
+
+    // the ClientHello to send after a HelloRetryRequest must echo its cookie, if any
+    pub fn with_cookie_echoed(&self, hrr: &ServerHello) -> std::io::Result<Self> {
+        match hrr.hrr_cookie() {
+            Some(cookie) => Ok(self
+                .with_extension_replaced(GenericExtension::from_extension(&Cookie::new(cookie))?)),
+            None => Ok(self.clone()),
+        }
+    }
+
+    // offer PSKs, their binders computed over this hello. Ticket ages are 0, as for external
+    // PSKs, and psk_key_exchange_modes is added if missing: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.9
+    pub fn with_psks(&self, psks: &[Psk]) -> std::io::Result<Self> {
+        if psks.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no PSK to offer",
+            ));
+        }
+
+        let mut ch = self.clone();
+        let modes = u16::from(ExtensionType::psk_key_exchange_modes);
+        if !ch.extensions().iter().any(|x| x.type_code == modes) {
+            // psk_dhe_ke
+            ch.push_extension(GenericExtension::new(
+                ExtensionType::psk_key_exchange_modes,
+                &[0x01, 0x01],
+            ));
+        }
+
+        let offer = |binders: &[Vec<u8>]| {
+            let identities = psks
+                .iter()
+                .map(|x| PskIdentity::new(&x.identity, 0))
+                .collect();
+            let binders: Vec<&[u8]> = binders.iter().map(|x| x.as_slice()).collect();
+            PreSharedKeyExtension::new(identities, &binders)
+        };
+
+        // binders replace placeholders of the same length, so no length changes
+        let placeholders: Vec<_> = psks.iter().map(|x| vec![0; x.hash.output_len()]).collect();
+        let extension = offer(&placeholders);
+        let ch = ch.with_extension_replaced(GenericExtension::from_extension(&extension)?);
+
+        let mut message = Vec::new();
+        Handshake::from_client_hello(ch.clone()).to_network_bytes(&mut message)?;
+        let truncated = &message[..message.len() - extension.binders_len()];
+        let binders: Vec<_> = psks.iter().map(|x| x.binder(truncated)).collect();
+
+        Ok(ch.with_extension_replaced(GenericExtension::from_extension(&offer(&binders))?))
+    }
+
+    // SNI for a host given by a user, see ServerNameList::for_host. An IP literal removes SNI
+    pub fn with_server_name(&self, host: &str) -> std::io::Result<Self> {
+        Ok(match ServerNameList::for_host(host)? {
+            Some(list) => self.with_extension_replaced(GenericExtension::from_extension(&list)?),
+            None => self.with_extension_removed(ExtensionType::server_name),
+        })
+    }
+
+    // the extensions field is kept even if it's empty
+    pub fn with_extension_removed(&self, extension_type: ExtensionType) -> Self {
+        let mut ch = self.clone();
+        if let Some(extensions) = ch.extensions.as_mut() {
+            extensions.remove(extension_type);
+        }
+        ch
+    }
+
+    // append any type of extension, see push_extension()
+    pub fn add_extension<T: Extension>(&mut self, extension: &T) -> std::io::Result<()> {
+        self.push_extension(GenericExtension::from_extension(extension)?);
+        Ok(())
+    }
+
+    // append an extension, before pre_shared_key which must stay the last one, or replace the
+    // one of the same type. The length of the extensions vector is recomputed; those of the
+    // Handshake and record carrying the hello are computed when they're built
+    pub fn push_extension(&mut self, extension: GenericExtension) {
+        self.extensions
+            .get_or_insert_with(Default::default)
+            .insert(extension);
+    }
 }
 
+// null must be offered: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.2, and be
+// the only method of a hello offering TLS 1.3: https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.2.
+// RFC 5246 names no alert for the first case, decode_error is what OpenSSL sends
+pub fn check_compression_methods(
+    methods: &[CompressionMethod],
+    tls13: bool,
+) -> std::result::Result<(), AlertDescription> {
+    if tls13 && methods != [0] {
+        Err(AlertDescription::illegal_parameter)
+    } else if !methods.contains(&0) {
+        Err(AlertDescription::decode_error)
+    } else {
+        Ok(())
+    }
+}
+
+// the default hello has no compression method
+#[cfg(feature = "registry")]
+crate::tls_register!(ClientHello, ClientHello::new(&[[0x13, 0x01]]));
+
+// not derived because extensions are optional: they're only present if there're
+// remaining bytes after the compression methods
+impl TlsDerive for ClientHello {
+    fn tls_len(&self) -> usize {
+        self.client_version.tls_len()
+            + self.random.tls_len()
+            + self.session_id.tls_len()
+            + self.cipher_suites.tls_len()
+            + self.compression_methods.tls_len()
+            + self.extensions.tls_len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0usize;
+        length += self.client_version.to_network_bytes(v)?;
+        length += self.random.to_network_bytes(v)?;
+        length += self.session_id.to_network_bytes(v)?;
+        length += self.cipher_suites.to_network_bytes(v)?;
+        length += self.compression_methods.to_network_bytes(v)?;
+        length += self.extensions.to_network_bytes(v)?;
+        Ok(length)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        self.client_version.from_network_bytes(v)?;
+        self.random.from_network_bytes(v)?;
+        self.session_id.from_network_bytes(v)?;
+        self.cipher_suites.from_network_bytes(v)?;
+        self.compression_methods.from_network_bytes(v)?;
+
+        self.extensions = if (v.position() as usize) < v.get_ref().len() {
+            let mut extensions = Extensions::new();
+            extensions.from_network_bytes(v)?;
+
+            // https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11
+            if let Some(index) = extensions
+                .as_slice()
+                .iter()
+                .position(|x| x.type_code == u16::from(ExtensionType::pre_shared_key))
+            {
+                if index != extensions.len() - 1 {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "pre_shared_key is not the last extension",
+                    ));
+                }
+            }
+            Some(extensions)
+        } else {
+            None
+        };
+
+        // a malformed supported_versions is left to the extension's parser
+        let tls13 = self
+            .extensions()
+            .iter()
+            .find(|x| x.type_code == u16::from(ExtensionType::supported_versions))
+            .and_then(|x| x.extension_data().get(1..))
+            .is_some_and(|x| x.chunks_exact(2).any(|v| v == TLS13));
+        check_compression_methods(&self.compression_methods.data, tls13).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid compression methods, {}", e),
+            )
+        })
+    }
+}
+
+// the IANA registry: https://www.iana.org/assignments/tls-extensiontype-values, with the
+// codes browsers send before they're assigned. Other codes, GREASE included, are kept as Unknown
 #[allow(unused_variables)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone, TlsEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
 #[repr(u16)]
 pub enum ExtensionType {
     server_name = 0,
@@ -88,7 +331,73 @@ pub enum ExtensionType {
     trusted_ca_keys = 3,
     truncated_hmac = 4,
     status_request = 5,
+    user_mapping = 6,
+    client_authz = 7,
+    server_authz = 8,
+    cert_type = 9,
+    supported_groups = 10,
+    ec_point_formats = 11,
+    srp = 12,
     signature_algorithms = 13,
+    use_srtp = 14,
+    heartbeat = 15,
+    application_layer_protocol_negotiation = 16,
+    status_request_v2 = 17,
+    signed_certificate_timestamp = 18,
+    client_certificate_type = 19,
+    server_certificate_type = 20,
+    padding = 21,
+    encrypt_then_mac = 22,
+    extended_master_secret = 23,
+    token_binding = 24,
+    cached_info = 25,
+    tls_lts = 26,
+    compress_certificate = 27,
+    record_size_limit = 28,
+    pwd_protect = 29,
+    pwd_clear = 30,
+    password_salt = 31,
+    ticket_pinning = 32,
+    tls_cert_with_extern_psk = 33,
+    delegated_credential = 34,
+    session_ticket = 35,
+    tlmsp = 36,
+    tlmsp_proxying = 37,
+    tlmsp_delegate = 38,
+    supported_ekt_ciphers = 39,
+    pre_shared_key = 41,
+    early_data = 42,
+    supported_versions = 43,
+    cookie = 44,
+    psk_key_exchange_modes = 45,
+    certificate_authorities = 47,
+    oid_filters = 48,
+    post_handshake_auth = 49,
+    signature_algorithms_cert = 50,
+    key_share = 51,
+    transparency_info = 52,
+    connection_id_deprecated = 53,
+    connection_id = 54,
+    external_id_hash = 55,
+    external_session_id = 56,
+    quic_transport_parameters = 57,
+    ticket_request = 58,
+    dnssec_chain = 59,
+    sequence_number_encryption_algorithms = 60,
+    rrc = 61,
+    application_settings = 17513,
+    ech_outer_extensions = 64768,
+    encrypted_client_hello = 65037,
+    renegotiation_info = 65281,
+    Unknown(u16),
+}
+
+// name of an extension from its code, even when it's not a known ExtensionType
+pub fn extension_name(value: u16) -> String {
+    match ExtensionType::from(value) {
+        ExtensionType::Unknown(_) if is_grease(value) => String::from("GREASE"),
+        extension_type => extension_type.to_string(),
+    }
 }
 
 // this trait is used fro the add() method, to make it more generic
@@ -96,97 +405,287 @@ pub trait ExtType {
     fn extension_type(&self) -> ExtensionType;
 }
 
-// extensions as described in https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.4
-#[derive(Debug, Default, TlsDerive)]
+impl<T: Extension> ExtType for T {
+    fn extension_type(&self) -> ExtensionType {
+        T::TYPE
+    }
+}
+
+// extensions as described in https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.4. The
+// type is kept as its code so extensions unknown to ExtensionType, like GREASE, don't fail the
+// parse of the whole message, and are sent back byte for byte
+#[derive(Debug, Default, Clone, TlsDerive)]
 pub struct GenericExtension {
-    extension_type: ExtensionType,
+    type_code: u16,
     extension_data: VariableLengthVector<u8, 0, 2>,
-    //extension_data: VariableLengthVector<u16, Box<dyn TlsToNetworkBytes>, 0>,
 }
 
 impl GenericExtension {
-    pub fn from_extension<T: TlsDerive + ExtType>(extension: &T) -> std::io::Result<Self> {
-        // get type from trait's method
-        let extension_type = extension.extension_type();
+    // an extension from its already encoded body
+    pub fn new(extension_type: ExtensionType, extension_data: &[u8]) -> Self {
+        Self {
+            type_code: u16::from(extension_type),
+            extension_data: VariableLengthVector::from_slice(extension_data),
+        }
+    }
 
-        // convert ext structure of type T to network bytes
-        let mut v = Vec::new();
-        extension.to_network_bytes(&mut v)?;
+    pub fn from_extension<T: Extension>(extension: &T) -> std::io::Result<Self> {
+        Ok(Self::new(T::TYPE, &extension.encode_body()?))
+    }
 
-        Ok(Self {
-            extension_type: extension_type,
-            extension_data: VariableLengthVector::from_slice(&v),
-        })
+    // ExtensionType::Unknown if the code is not registered
+    pub fn extension_type(&self) -> ExtensionType {
+        ExtensionType::from(self.type_code)
     }
-}
 
-// SNI extension
-#[derive(Debug, Default, TlsDerive)]
-pub struct ServerNameList {
-    length: u16,
-    host_name_type: u8,
-    host_name_length: u16,
-    host_name: Vec<u8>,
+    pub fn type_code(&self) -> u16 {
+        self.type_code
+    }
+
+    // the raw extension body, without the 2-byte length prefix
+    pub fn extension_data(&self) -> &[u8] {
+        &self.extension_data.data
+    }
+
+    // the extension as is, when its code is not an ExtensionType
+    pub fn as_unknown(&self) -> Option<UnknownExtension> {
+        match self.extension_type() {
+            ExtensionType::Unknown(type_code) => Some(UnknownExtension {
+                type_code,
+                data: self.extension_data().to_vec(),
+            }),
+            _ => None,
+        }
+    }
+
+    // the typed body, in the form used by the message carrying the extension
+    pub fn parse(&self, context: ExtensionContext) -> std::io::Result<ExtensionPayload> {
+        parse_extension_in(self.type_code, self.extension_data(), context)
+    }
 }
 
-impl ServerNameList {
-    pub fn new(host_name: &str) -> Self {
-        let length = host_name.len();
+// an extension whose code is not an ExtensionType
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UnknownExtension {
+    pub type_code: u16,
+    pub data: Vec<u8>,
+}
 
+impl From<UnknownExtension> for GenericExtension {
+    fn from(extension: UnknownExtension) -> Self {
         Self {
-            length: (length + 3) as u16,
-            host_name_type: 0,
-            host_name_length: length as u16,
-            host_name: host_name.as_bytes().to_vec(),
+            type_code: extension.type_code,
+            extension_data: VariableLengthVector::from_slice(&extension.data),
         }
     }
 }
 
-ext_type!(ServerNameList, server_name);
+// the body of an extension, decoded in the form used by the message carrying it
+#[derive(Debug)]
+pub enum ExtensionPayload {
+    ServerName(ServerNameList),
+    MaxFragmentLength(MaxFragmentLength),
+    TrustedCaKeys(TrustedAuthorities),
+    StatusRequest(CertificateStatusRequest),
+    SupportedGroups(SupportedGroups),
+    SignatureAlgorithms(SignatureAlgorithms),
+    SignatureAlgorithmsCert(SignatureAlgorithmsCert),
+    UseSrtp(UseSrtp),
+    Heartbeat(HeartbeatMode),
+    Alpn(ProtocolNameList),
+    SignedCertificateTimestamps(SignedCertificateTimestampList),
+    DelegatedCredentialSchemes(DelegatedCredentialSchemes),
+    DelegatedCredential(DelegatedCredential),
+
+    // a ticket, or a request for one if empty
+    SessionTicket(Vec<u8>),
+    SupportedVersions(SupportedVersions),
+    KeyShare(KeyShare),
+    PreSharedKey(PreSharedKeyExtension),
+    Cookie(Cookie),
+    ApplicationSettings(ApplicationSettings),
+    QuicTransportParameters(QuicTransportParameters),
+
+    // extensions without a body, or acknowledged by the server with an empty one
+    Empty(ExtensionType),
+
+    // extensions without a typed structure
+    Opaque(ExtensionType, Vec<u8>),
+
+    // codes which are not an ExtensionType
+    Unknown(UnknownExtension),
+}
+
+impl ExtensionPayload {
+    pub fn parse(
+        extension_type: ExtensionType,
+        data: &[u8],
+        context: ExtensionContext,
+    ) -> std::io::Result<Self> {
+        use ExtensionContext::ClientHello;
+        use ExtensionType as T;
+
+        let empty = || {
+            if data.is_empty() {
+                Ok(Self::Empty(extension_type))
+            } else {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{} should be empty in {:?}", extension_type, context),
+                ))
+            }
+        };
+
+        match (extension_type, context) {
+            (
+                T::extended_master_secret
+                | T::post_handshake_auth
+                | T::client_certificate_url
+                | T::truncated_hmac,
+                _,
+            ) => empty(),
+
+            // acknowledged by the server, or asked by the client, with an empty body
+            (T::server_name | T::status_request | T::trusted_ca_keys, context)
+                if context != ClientHello =>
+            {
+                empty()
+            }
+            (T::signed_certificate_timestamp, ClientHello) => empty(),
+
+            (T::delegated_credential, ClientHello) => Ok(Self::DelegatedCredentialSchemes(
+                DelegatedCredentialSchemes::decode_body(data, context)?,
+            )),
+            (T::session_ticket, _) => Ok(Self::SessionTicket(data.to_vec())),
+            _ => match registration(extension_type) {
+                Some(registration) => (registration.decode)(data, context),
+                None => Ok(Self::Opaque(extension_type, data.to_vec())),
+            },
+        }
+    }
+}
+
+/// Decode an extension body on its own, e.g. one taken from logs, in the form used by
+/// ClientHello. Codes unknown to [`ExtensionType`] give [`ExtensionPayload::Unknown`].
+///
+/// ```
+/// use tls_explore::handshake::client_hello::{parse_extension, ExtensionPayload, UnknownExtension};
+///
+/// let payload = parse_extension(0x10, b"\x00\x0C\x02h2\x08http/1.1").unwrap();
+/// match payload {
+///     ExtensionPayload::Alpn(list) => assert_eq!(list.protocols(), vec!["h2", "http/1.1"]),
+///     _ => unreachable!(),
+/// }
+///
+/// assert!(matches!(
+///     parse_extension(0x1234, &[0x01]).unwrap(),
+///     ExtensionPayload::Unknown(UnknownExtension { type_code: 0x1234, .. })
+/// ));
+/// assert!(parse_extension(0x10, &[0x00]).is_err());
+/// ```
+pub fn parse_extension(extension_type: u16, data: &[u8]) -> std::io::Result<ExtensionPayload> {
+    parse_extension_in(extension_type, data, ExtensionContext::ClientHello)
+}
+
+// same as parse_extension, for extensions taken from another message
+pub fn parse_extension_in(
+    extension_type: u16,
+    data: &[u8],
+    context: ExtensionContext,
+) -> std::io::Result<ExtensionPayload> {
+    match ExtensionType::from(extension_type) {
+        ExtensionType::Unknown(type_code) => Ok(ExtensionPayload::Unknown(UnknownExtension {
+            type_code,
+            data: data.to_vec(),
+        })),
+        extension_type => ExtensionPayload::parse(extension_type, data, context),
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn simple_ch() {
-        let ch = ClientHello::new(&vec![TLS_DHE_RSA_WITH_AES_256_CBC_SHA]);
+    fn parsed_extension() {
+        use ExtensionContext::*;
+
+        let sni = GenericExtension::from_extension(&ServerNameList::new("example.com")).unwrap();
+        match sni.parse(ClientHello).unwrap() {
+            ExtensionPayload::ServerName(list) => assert_eq!(list.host_name(), "example.com"),
+            other => panic!("unexpected {:?}", other),
+        }
 
-        assert_eq!(ch.tls_len(), 2 + 32 + 32 + 2 + 2 + 1 + 1);
+        // acknowledged with an empty body by the server
+        let ack = GenericExtension::new(ExtensionType::server_name, &[]);
+        for context in [ServerHello, EncryptedExtensions] {
+            assert!(matches!(
+                ack.parse(context).unwrap(),
+                ExtensionPayload::Empty(ExtensionType::server_name)
+            ));
+        }
+        assert!(ack.parse(ClientHello).is_err());
+
+        // the same type in different forms
+        let offered =
+            GenericExtension::from_extension(&SupportedVersions::new(&[TLS13, TLS12])).unwrap();
+        match offered.parse(ClientHello).unwrap() {
+            ExtensionPayload::SupportedVersions(v) => assert_eq!(v.versions(), &[TLS13, TLS12]),
+            other => panic!("unexpected {:?}", other),
+        }
+        let selected = GenericExtension::new(ExtensionType::supported_versions, &[0x03, 0x04]);
+        match selected.parse(ServerHello).unwrap() {
+            ExtensionPayload::SupportedVersions(v) => assert_eq!(v.versions(), &[TLS13]),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let alpn = GenericExtension::from_extension(&ProtocolNameList::new(&["h2"])).unwrap();
+        assert_eq!(alpn.extension_data(), b"\x00\x03\x02h2");
+        match alpn.parse(EncryptedExtensions).unwrap() {
+            ExtensionPayload::Alpn(list) => assert_eq!(list.protocols(), vec!["h2"]),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(matches!(
+            parse_extension_in(0x2B, &[0x03, 0x03], ServerHello).unwrap(),
+            ExtensionPayload::SupportedVersions(_)
+        ));
+        assert!(matches!(
+            parse_extension(0x0A0A, &[]).unwrap(),
+            ExtensionPayload::Unknown(UnknownExtension { type_code: 0x0A0A, data }) if data.is_empty()
+        ));
+
+        let ec_point_formats = GenericExtension::new(ExtensionType::ec_point_formats, &[1, 0]);
+        assert!(matches!(
+            ec_point_formats.parse(ClientHello).unwrap(),
+            ExtensionPayload::Opaque(ExtensionType::ec_point_formats, data) if data == [1, 0]
+        ));
+
+        // trailing bytes, or a form not allowed in the message
+        let groups = GenericExtension::new(ExtensionType::supported_groups, &[0, 2, 0, 0x1D, 0]);
+        assert!(groups.parse(ClientHello).is_err());
+        let key_share = GenericExtension::new(ExtensionType::key_share, &[0, 0x1D]);
+        assert!(key_share.parse(HelloRetryRequest).is_ok());
+        assert!(key_share.parse(NewSessionTicket).is_err());
     }
 
     #[test]
-    fn sni() {
-        let sni = ServerNameList::new("example.ulfheim.net");
-        assert_eq!(sni.length, 22);
-        assert_eq!(sni.host_name_type, 0);
-        assert_eq!(sni.host_name_length, 19);
-        assert_eq!(
-            sni.host_name,
-            &[
-                0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x75, 0x6c, 0x66, 0x68, 0x65, 0x69,
-                0x6d, 0x2e, 0x6e, 0x65, 0x74
-            ]
-        );
-
-        let ext = GenericExtension::from_extension(&sni).unwrap();
+    fn simple_ch() {
+        let ch = ClientHello::new(&[TLS_DHE_RSA_WITH_AES_256_CBC_SHA]);
 
-        //assert_eq!(ext.extension_type, ExtensionType::server_name);
-        //assert_eq!(ext.extension_data.data, &[0x00, 0x18, 0x00, 0x16, 0x00, 0x00, 0x13, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x75, 0x6c, 0x66, 0x68, 0x65, 0x69, 0x6d, 0x2e, 0x6e, 0x65, 0x74]);
+        assert_eq!(ch.tls_len(), 2 + 32 + (1 + 32) + (2 + 2) + (1 + 1));
     }
 
-    //#[test]
+    #[test]
     fn from_network() {
         let mut ch = ClientHello::default();
         let mut v = std::io::Cursor::new(vec![
             0x03, 0x03, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
             0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19,
-            0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0xcc, 0xa8,
-            0xcc, 0xa9, 0xc0, 0x2f, 0xc0, 0x30, 0xc0, 0x2b, 0xc0, 0x2c, 0xc0, 0x13, 0xc0, 0x09,
-            0xc0, 0x14, 0xc0, 0x0a, 0x00, 0x9c, 0x00, 0x9d, 0x00, 0x2f, 0x00, 0x35, 0xc0, 0x12,
-            0x00, 0x0a, 0x01, 0x00,
+            0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x00, 0x00, 0x20, 0xcc, 0xa8, 0xcc, 0xa9, 0xc0,
+            0x2f, 0xc0, 0x30, 0xc0, 0x2b, 0xc0, 0x2c, 0xc0, 0x13, 0xc0, 0x09, 0xc0, 0x14, 0xc0,
+            0x0a, 0x00, 0x9c, 0x00, 0x9d, 0x00, 0x2f, 0x00, 0x35, 0xc0, 0x12, 0x00, 0x0a, 0x01,
+            0x00,
         ]);
         let _ = ch.from_network_bytes(&mut v);
         assert_eq!(ch.client_version, [0x03, 0x03]);
@@ -197,7 +696,7 @@ mod tests {
                 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f
             ]
         );
-        assert_eq!(ch.session_id, [0u8; 32]);
+        assert!(ch.session_id.data.is_empty());
         assert_eq!(ch.cipher_suites.length, 32);
 
         let mut iter = ch.cipher_suites.data.iter();
@@ -233,5 +732,201 @@ mod tests {
 
         assert_eq!(ch.compression_methods.length, 1);
         assert_eq!(ch.compression_methods.data, [0u8]);
+        assert!(ch.extensions.is_none());
+    }
+
+    #[test]
+    fn mutations() {
+        let sni = GenericExtension::from_extension(&ServerNameList::new("example.com")).unwrap();
+        let ch = ClientHello::new(&[TLS_RSA_WITH_AES_128_CBC_SHA, TLS_RSA_WITH_AES_256_CBC_SHA])
+            .with_extension_replaced(sni);
+        let length = ch.tls_len();
+
+        let removed = ch.with_suite_removed(TLS_RSA_WITH_AES_128_CBC_SHA);
+        assert_eq!(removed.cipher_suites(), &[TLS_RSA_WITH_AES_256_CBC_SHA]);
+        assert_eq!(removed.cipher_suites.length, 2);
+        assert_eq!(removed.tls_len(), length - 2);
+
+        // original is left untouched
+        assert_eq!(ch.cipher_suites().len(), 2);
+
+        let other = GenericExtension::from_extension(&ServerNameList::new("a.com")).unwrap();
+        let replaced = ch.with_extension_replaced(other);
+        assert_eq!(replaced.extensions().len(), 1);
+        assert_eq!(replaced.tls_len(), length - 6);
+
+        let stripped = ch.with_extension_removed(ExtensionType::server_name);
+        assert!(stripped.extensions().is_empty());
+        assert_eq!(stripped.extensions.as_ref().unwrap().tls_len(), 2);
+
+        let tls10 = ch.with_version(TLS10);
+        assert_eq!(tls10.client_version(), TLS10);
+
+        // lengths on the wire are consistent, so the hello can be parsed back
+        let mut buffer = Vec::new();
+        removed.to_network_bytes(&mut buffer).unwrap();
+        let mut parsed = ClientHello::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(buffer))
+            .unwrap();
+        assert_eq!(parsed.cipher_suites(), removed.cipher_suites());
+        assert_eq!(
+            parsed.extensions()[0].extension_data(),
+            ch.extensions()[0].extension_data()
+        );
+    }
+
+    #[test]
+    fn unknown_extensions() {
+        let grease = UnknownExtension {
+            type_code: 0x0A0A,
+            data: vec![],
+        };
+        let unassigned = UnknownExtension {
+            type_code: 0x1234,
+            data: vec![0x00, 0x00, 0x01, 0x00, 0x01],
+        };
+
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256])
+            .with_extension_replaced(grease.clone().into())
+            .with_extension_replaced(
+                GenericExtension::from_extension(&ServerNameList::new("example.com")).unwrap(),
+            )
+            .with_extension_replaced(unassigned.clone().into())
+            .with_extension_replaced(GenericExtension::new(
+                ExtensionType::renegotiation_info,
+                &[0x00],
+            ));
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+
+        let mut parsed = ClientHello::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(buffer.clone()))
+            .unwrap();
+        let extensions = parsed.extensions();
+        assert_eq!(extensions.len(), 4);
+        assert_eq!(
+            extensions[0].extension_type(),
+            ExtensionType::Unknown(0x0A0A)
+        );
+        assert_eq!(extensions[0].as_unknown(), Some(grease));
+        assert!(extensions[1].as_unknown().is_none());
+        assert_eq!(extensions[2].type_code(), 0x1234);
+        assert_eq!(
+            extensions[3].extension_type(),
+            ExtensionType::renegotiation_info
+        );
+        let payloads = parsed.parsed_extensions().unwrap();
+        assert!(matches!(&payloads[2], ExtensionPayload::Unknown(x) if *x == unassigned));
+        assert!(matches!(&payloads[3], ExtensionPayload::Opaque(..)));
+
+        // unregistered codes convert both ways
+        assert_eq!(ExtensionType::from(0x1234), ExtensionType::Unknown(0x1234));
+        assert_eq!(u16::from(ExtensionType::Unknown(0x1234)), 0x1234);
+        assert_eq!(ExtensionType::Unknown(0x1234).to_string(), "unknown(4660)");
+        assert_eq!(extension_name(0x0A0A), "GREASE");
+        assert_eq!(extension_name(0xFF01), "renegotiation_info(65281)");
+
+        // sent back byte for byte
+        let mut reserialized = Vec::new();
+        parsed.to_network_bytes(&mut reserialized).unwrap();
+        assert_eq!(reserialized, buffer);
+    }
+
+    #[test]
+    fn add_extension() {
+        use crate::handshake::common::ContentType;
+        use crate::handshake::handshake::Handshake;
+        use crate::handshake::record_layer::{RecordHeader, RecordLayer};
+
+        let mut ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256]);
+        let length = ch.tls_len();
+        ch.add_extension(&ServerNameList::new("example.com"))
+            .unwrap();
+        ch.add_extension(&PreSharedKeyExtension::new(
+            vec![PskIdentity::new(&[1, 2, 3], 0)],
+            &[&[0; 32]],
+        ))
+        .unwrap();
+        ch.add_extension(&ProtocolNameList::new(&["h2"])).unwrap();
+
+        // pre_shared_key stays last
+        let types: Vec<_> = ch.extensions().iter().map(|x| x.type_code()).collect();
+        assert_eq!(types, vec![0, 16, 41]);
+        let extensions = ch.extensions.as_ref().unwrap();
+        assert_eq!(
+            extensions.tls_len(),
+            2 + ch.extensions().iter().map(|x| x.tls_len()).sum::<usize>()
+        );
+        assert_eq!(ch.tls_len(), length + extensions.tls_len());
+
+        // lengths of the handshake and record follow changes made to the hello
+        let mut record = RecordLayer {
+            header: RecordHeader {
+                content_type: ContentType::handshake,
+                version: TLS10,
+                length: 0,
+            },
+            data: Handshake::from_client_hello(ch),
+        };
+        record.data.update(|ch| {
+            ch.push_extension(GenericExtension::new(ExtensionType::cookie, &[0, 1, 0]))
+        });
+        record.set_length();
+
+        let mut buffer = Vec::new();
+        record.to_network_bytes(&mut buffer).unwrap();
+        assert_eq!(record.header.length as usize, buffer.len() - 5);
+        assert_eq!(
+            u32::from_be_bytes([0, buffer[6], buffer[7], buffer[8]]) as usize,
+            buffer.len() - 9
+        );
+
+        let mut parsed = ClientHello::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(buffer[9..].to_vec()))
+            .unwrap();
+        let types: Vec<_> = parsed.extensions().iter().map(|x| x.type_code()).collect();
+        assert_eq!(types, vec![0, 16, 44, 41]);
+    }
+
+    #[test]
+    fn compression_methods() {
+        assert_eq!(check_compression_methods(&[1, 0], false), Ok(()));
+        assert_eq!(check_compression_methods(&[0], true), Ok(()));
+        assert_eq!(
+            check_compression_methods(&[1], false),
+            Err(AlertDescription::decode_error)
+        );
+        assert_eq!(
+            check_compression_methods(&[1, 0], true),
+            Err(AlertDescription::illegal_parameter)
+        );
+
+        let parse = |ch: &ClientHello| {
+            let mut buffer = Vec::new();
+            ch.to_network_bytes(&mut buffer).unwrap();
+            let mut parsed = ClientHello::default();
+            parsed.from_network_bytes(&mut std::io::Cursor::new(buffer))
+        };
+
+        let mut ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256]);
+        ch.compression_methods = VariableLengthVector::from_slice(&[1, 0]);
+        assert!(parse(&ch).is_ok());
+
+        ch.add_extension(&SupportedVersions::new(&[TLS13])).unwrap();
+        let e = parse(&ch).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid compression methods, illegal_parameter(47)"
+        );
+
+        ch.compression_methods = VariableLengthVector::from_slice(&[1]);
+        assert!(parse(&ch.with_extension_replaced(GenericExtension::new(
+            ExtensionType::supported_versions,
+            &[0x02, 0x03, 0x03]
+        )))
+        .is_err());
     }
 }
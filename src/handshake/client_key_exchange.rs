@@ -0,0 +1,167 @@
+// TLS 1.2 ClientKeyExchange message, carrying the client's public value or the encrypted premaster
+// secret. Its form depends on the key exchange of the suite, so like key_share, parsing uses the
+// current variant as the expected form
+use std::io::{Cursor, Result};
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::ffdhe::DhKeyPair;
+use crate::crypto::rsa::RsaPublicKey;
+use crate::crypto::x25519::X25519KeyPair;
+use crate::derive_tls::TlsDerive;
+use crate::handshake::common::VariableLengthVector;
+
+#[derive(Debug, Clone)]
+pub enum ClientKeyExchange {
+    // ECPoint ecdh_Yc<1..2^8-1>, the 32 bytes of the public key for x25519 or an uncompressed
+    // point for NIST curves: https://datatracker.ietf.org/doc/html/rfc8422#section-5.7
+    Ecdhe(VariableLengthVector<u8, 1, 1>),
+
+    // opaque dh_Yc<1..2^16-1>: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.7.2
+    Dhe(VariableLengthVector<u8, 1, 2>),
+
+    // the premaster secret encrypted with the server's RSA key, with the length prefix of TLS
+    // 1.0 and later: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.7.1
+    Rsa(VariableLengthVector<u8, 1, 2>),
+}
+
+impl Default for ClientKeyExchange {
+    fn default() -> Self {
+        Self::Ecdhe(VariableLengthVector::default())
+    }
+}
+
+impl ClientKeyExchange {
+    pub fn ecdhe(public: &[u8]) -> Self {
+        Self::Ecdhe(VariableLengthVector::from_slice(public))
+    }
+
+    pub fn x25519(keys: &X25519KeyPair) -> Self {
+        Self::ecdhe(keys.public_key())
+    }
+
+    pub fn dhe(keys: &DhKeyPair) -> Self {
+        Self::Dhe(VariableLengthVector::from_slice(keys.public_key()))
+    }
+
+    // fails if the key is invalid or too short for the secret
+    pub fn rsa(
+        key: &RsaPublicKey,
+        pre_master_secret: &[u8],
+    ) -> std::result::Result<Self, AlertDescription> {
+        let encrypted = key.encrypt_pkcs1(pre_master_secret)?;
+        Ok(Self::Rsa(VariableLengthVector::from_slice(&encrypted)))
+    }
+
+    // the public value, or the encrypted premaster secret
+    pub fn public_value(&self) -> &[u8] {
+        match self {
+            Self::Ecdhe(public) => &public.data,
+            Self::Dhe(public) => &public.data,
+            Self::Rsa(encrypted) => &encrypted.data,
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(ClientKeyExchange);
+
+impl TlsDerive for ClientKeyExchange {
+    fn tls_len(&self) -> usize {
+        match self {
+            Self::Ecdhe(public) => public.tls_len(),
+            Self::Dhe(public) => public.tls_len(),
+            Self::Rsa(encrypted) => encrypted.tls_len(),
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        match self {
+            Self::Ecdhe(public) => public.to_network_bytes(v),
+            Self::Dhe(public) => public.to_network_bytes(v),
+            Self::Rsa(encrypted) => encrypted.to_network_bytes(v),
+        }
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        match self {
+            Self::Ecdhe(public) => public.from_network_bytes(v),
+            Self::Dhe(public) => public.from_network_bytes(v),
+            Self::Rsa(encrypted) => encrypted.from_network_bytes(v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::ffdhe::DhParams;
+    use crate::handshake::client_hello::NamedGroup;
+    use crate::handshake::handshake::{Handshake, HandshakeType};
+
+    #[test]
+    fn client_key_exchange() {
+        let keys = X25519KeyPair::from_private([1; 32]);
+        let message = Handshake::from_message(
+            HandshakeType::client_key_exchange,
+            ClientKeyExchange::x25519(&keys),
+        );
+
+        let mut v = Vec::new();
+        message.to_network_bytes(&mut v).unwrap();
+        assert_eq!(&v[..5], &[0x10, 0x00, 0x00, 0x21, 0x20]);
+        assert_eq!(&v[5..], keys.public_key());
+
+        let mut parsed = ClientKeyExchange::default();
+        parsed
+            .from_network_bytes(&mut Cursor::new(v[4..].to_vec()))
+            .unwrap();
+        assert_eq!(parsed.public_value(), keys.public_key());
+    }
+
+    #[test]
+    fn dhe() {
+        let params = DhParams::ffdhe(NamedGroup::ffdhe2048).unwrap();
+        let keys = DhKeyPair::generate(&params).unwrap();
+
+        let mut v = Vec::new();
+        ClientKeyExchange::dhe(&keys)
+            .to_network_bytes(&mut v)
+            .unwrap();
+        assert_eq!(&v[..2], &[0x01, 0x00]);
+
+        let mut parsed = ClientKeyExchange::Dhe(VariableLengthVector::default());
+        parsed.from_network_bytes(&mut Cursor::new(v)).unwrap();
+        assert_eq!(parsed.public_value(), keys.public_key());
+    }
+
+    #[test]
+    fn rsa() {
+        use crate::crypto::rsa::pre_master_secret;
+        use crate::handshake::constants::TLS12;
+
+        // a 1024-bit modulus, the secret is checked in crypto::rsa
+        let key = RsaPublicKey {
+            n: vec![0xFF; 128],
+            e: vec![0x01, 0x00, 0x01],
+        };
+        let message = ClientKeyExchange::rsa(&key, &pre_master_secret(TLS12)).unwrap();
+
+        let mut v = Vec::new();
+        message.to_network_bytes(&mut v).unwrap();
+        assert_eq!(&v[..2], &[0x00, 0x80]);
+        assert_eq!(v.len(), 130);
+
+        let mut parsed = ClientKeyExchange::Rsa(VariableLengthVector::default());
+        parsed.from_network_bytes(&mut Cursor::new(v)).unwrap();
+        assert_eq!(parsed.public_value(), message.public_value());
+
+        let key = RsaPublicKey {
+            n: vec![0xFF; 32],
+            e: vec![3],
+        };
+        assert_eq!(
+            ClientKeyExchange::rsa(&key, &[0; 48]).unwrap_err(),
+            AlertDescription::illegal_parameter
+        );
+    }
+}
@@ -15,6 +15,7 @@ pub enum ContentType {
     alert = 21,
     handshake = 22,
     application_data = 23,
+    heartbeat = 24,
     fake = 255,
 }
 
@@ -39,7 +40,7 @@ pub enum ContentType {
 pub type ProtocolVersion = [u8; 2];
 
 // Random struct
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Random {
     pub gmt_unix_time: u32,
     pub random_bytes: [u8; 28],
@@ -64,13 +65,21 @@ impl Random {
             random_bytes: [0xFF; 28],
         }
     }
+
+    // the 32 bytes of the random as sent on the wire
+    pub fn as_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..4].copy_from_slice(&self.gmt_unix_time.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.random_bytes);
+        bytes
+    }
 }
 
-// SessionID => always fixed
-pub type SessionID = [u8; 32];
+// SessionID: opaque SessionID<0..32>
+pub type SessionID = VariableLengthVector<u8, 0, 1>;
 
 // variable lenght vectors contain a length and an array: https://datatracker.ietf.org/doc/html/rfc5246#section-4.3
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct VariableLengthVector<T, const MIN: u8, const BYTES: u8> {
     pub length: u32,
     pub data: Vec<T>,
@@ -92,13 +101,23 @@ where
         //debug_assert!(N <= data.unwrap().len(), "wrong number of elements");
 
         VariableLengthVector {
-            length: (data.len() * mem::size_of::<T>()) as u32,
+            length: mem::size_of_val(data) as u32,
             data: data.to_vec(),
         }
     }
 }
 
 use crate::derive_tls::TlsDerive;
+impl<T, const MIN: u8, const BYTES: u8> VariableLengthVector<T, MIN, BYTES>
+where
+    T: TlsDerive,
+{
+    // recompute the length field after data has been changed
+    pub fn fix_length(&mut self) {
+        self.length = self.data.iter().map(|x| x.tls_len()).sum::<usize>() as u32;
+    }
+}
+
 impl<const MIN: u8, const BYTES: u8> std::default::Default
     for VariableLengthVector<Box<dyn TlsDerive>, MIN, BYTES>
 {
@@ -110,7 +129,7 @@ impl<const MIN: u8, const BYTES: u8> std::default::Default
     }
 }
 impl<const MIN: u8, const BYTES: u8> VariableLengthVector<Box<dyn TlsDerive>, MIN, BYTES> {
-    fn push(&mut self, elem: Box<dyn TlsDerive>) {
+    pub fn push(&mut self, elem: Box<dyn TlsDerive>) {
         self.length += elem.tls_len() as u32;
         self.data.push(elem);
     }
@@ -122,6 +141,45 @@ pub type CipherSuite = [u8; 2];
 // compression methods = one byte
 pub type CompressionMethod = u8;
 
+// GREASE values are reserved to check peers tolerate unknown values: https://datatracker.ietf.org/doc/html/rfc8701
+pub fn is_grease(value: u16) -> bool {
+    value & 0x0F0F == 0x0A0A && value >> 8 == value & 0xFF
+}
+
+// human readable name of a protocol version
+pub fn version_name(version: ProtocolVersion) -> String {
+    match version {
+        [0x03, 0x00] => String::from("SSL 3.0"),
+        [0x03, 0x01] => String::from("TLS 1.0"),
+        [0x03, 0x02] => String::from("TLS 1.1"),
+        [0x03, 0x03] => String::from("TLS 1.2"),
+        [0x03, 0x04] => String::from("TLS 1.3"),
+        [a, b] if is_grease(u16::from_be_bytes([a, b])) => String::from("GREASE"),
+        [a, b] => format!("0x{:02X}{:02X}", a, b),
+    }
+}
+
+// name of a cipher suite, or its hex value if unknown
+pub fn cipher_suite_name(suite: CipherSuite) -> String {
+    crate::handshake::constants::CIPHER_SUITES
+        .iter()
+        .find(|(_, x)| *x == suite)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| format!("0x{:02X}{:02X}", suite[0], suite[1]))
+}
+
+// a cipher suite from its name or its hex value like 0x1301
+pub fn cipher_suite_from_name(name: &str) -> Option<CipherSuite> {
+    if let Some(hex) = name.strip_prefix("0x") {
+        return u16::from_str_radix(hex, 16).ok().map(|x| x.to_be_bytes());
+    }
+
+    crate::handshake::constants::CIPHER_SUITES
+        .iter()
+        .find(|(x, _)| x.eq_ignore_ascii_case(name))
+        .map(|(_, suite)| *suite)
+}
+
 // a specific u24 = 3 bytes integer
 pub fn to_u24(n: u32) -> [u8; 3] {
     let mut a: [u8; 3] = [0; 3];
@@ -158,6 +216,42 @@ mod tests {
     //     let _v1 = VariableLengthVector::<u8, u8, 1>::new(1u8, Some(&vec![0u8, 1]));
     // }
 
+    #[test]
+    fn grease() {
+        assert!(is_grease(0x0A0A));
+        assert!(is_grease(0xFAFA));
+        assert!(!is_grease(0x0A1A));
+        assert!(!is_grease(0x1301));
+    }
+
+    #[test]
+    fn suite_names() {
+        use crate::handshake::constants::*;
+
+        assert_eq!(
+            cipher_suite_from_name("TLS_AES_128_GCM_SHA256"),
+            Some(TLS_AES_128_GCM_SHA256)
+        );
+        assert_eq!(
+            cipher_suite_from_name("tls_ecdhe_rsa_with_aes_128_gcm_sha256"),
+            Some(TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256)
+        );
+        assert_eq!(cipher_suite_from_name("0xC02F"), Some([0xC0, 0x2F]));
+        assert_eq!(cipher_suite_from_name("TLS_FOO"), None);
+        assert_eq!(
+            cipher_suite_name(TLS_CHACHA20_POLY1305_SHA256),
+            "TLS_CHACHA20_POLY1305_SHA256"
+        );
+        assert_eq!(cipher_suite_name([0x0A, 0x0A]), "0x0A0A");
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(version_name([0x03, 0x03]), "TLS 1.2");
+        assert_eq!(version_name([0x7A, 0x7A]), "GREASE");
+        assert_eq!(version_name([0x7F, 0x1C]), "0x7F1C");
+    }
+
     #[test]
     fn tls_enum() {
         #[derive(Debug, PartialEq, TlsEnum)]
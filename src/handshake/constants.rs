@@ -4,6 +4,17 @@ use crate::handshake::common::{CipherSuite, ProtocolVersion};
 pub const TLS10: ProtocolVersion = [0x03, 0x01];
 pub const TLS11: ProtocolVersion = [0x03, 0x02];
 pub const TLS12: ProtocolVersion = [0x03, 0x03];
+pub const TLS13: ProtocolVersion = [0x03, 0x04];
+
+// special ServerHello.random value for HelloRetryRequest: SHA-256("HelloRetryRequest")
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+pub const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xCF, 0x21, 0xAD, 0x74, 0xE5, 0x9A, 0x61, 0x11, 0xBE, 0x1D, 0x8C, 0x02, 0x1E, 0x65, 0xB8, 0x91,
+    0xC2, 0xA2, 0x11, 0x16, 0x7A, 0xBB, 0x8C, 0x5E, 0x07, 0x9E, 0x09, 0xE2, 0xC8, 0xA8, 0x33, 0x9C,
+];
+
+// signals support for secure renegotiation: https://datatracker.ietf.org/doc/html/rfc5746#section-3.3
+pub const TLS_EMPTY_RENEGOTIATION_INFO_SCSV: CipherSuite = [0x00, 0xFF];
 
 // cipher suites
 pub const TLS_NULL_WITH_NULL_NULL: CipherSuite = [0x00, 0x00];
@@ -81,3 +92,274 @@ pub const TLS_DH_DSS_WITH_AES_128_GCM_SHA256: CipherSuite = [0x00, 0xA4];
 pub const TLS_DH_DSS_WITH_AES_256_GCM_SHA384: CipherSuite = [0x00, 0xA5];
 pub const TLS_DH_ANON_WITH_AES_128_GCM_SHA256: CipherSuite = [0x00, 0xA6];
 pub const TLS_DH_ANON_WITH_AES_256_GCM_SHA384: CipherSuite = [0x00, 0xA7];
+
+// TLS 1.3 cipher suites: https://datatracker.ietf.org/doc/html/rfc8446#appendix-B.4
+pub const TLS_AES_128_GCM_SHA256: CipherSuite = [0x13, 0x01];
+pub const TLS_AES_256_GCM_SHA384: CipherSuite = [0x13, 0x02];
+pub const TLS_CHACHA20_POLY1305_SHA256: CipherSuite = [0x13, 0x03];
+pub const TLS_AES_128_CCM_SHA256: CipherSuite = [0x13, 0x04];
+pub const TLS_AES_128_CCM_8_SHA256: CipherSuite = [0x13, 0x05];
+
+// names of the suites above, to look them up from text
+pub const CIPHER_SUITES: [(&str, CipherSuite); 77] = [
+    ("TLS_NULL_WITH_NULL_NULL", TLS_NULL_WITH_NULL_NULL),
+    ("TLS_RSA_WITH_NULL_MD5", TLS_RSA_WITH_NULL_MD5),
+    ("TLS_RSA_WITH_NULL_SHA", TLS_RSA_WITH_NULL_SHA),
+    ("TLS_RSA_WITH_NULL_SHA256", TLS_RSA_WITH_NULL_SHA256),
+    ("TLS_RSA_WITH_RC4_128_MD5", TLS_RSA_WITH_RC4_128_MD5),
+    ("TLS_RSA_WITH_RC4_128_SHA", TLS_RSA_WITH_RC4_128_SHA),
+    (
+        "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        TLS_RSA_WITH_3DES_EDE_CBC_SHA,
+    ),
+    ("TLS_RSA_WITH_AES_128_CBC_SHA", TLS_RSA_WITH_AES_128_CBC_SHA),
+    ("TLS_RSA_WITH_AES_256_CBC_SHA", TLS_RSA_WITH_AES_256_CBC_SHA),
+    (
+        "TLS_RSA_WITH_AES_128_CBC_SHA256",
+        TLS_RSA_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_RSA_WITH_AES_256_CBC_SHA256",
+        TLS_RSA_WITH_AES_256_CBC_SHA256,
+    ),
+    (
+        "TLS_DH_DSS_WITH_3DES_EDE_CBC_SHA",
+        TLS_DH_DSS_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_DH_RSA_WITH_3DES_EDE_CBC_SHA",
+        TLS_DH_RSA_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA",
+        TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_128_CBC_SHA",
+        TLS_DH_DSS_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_128_CBC_SHA",
+        TLS_DH_RSA_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_128_CBC_SHA",
+        TLS_DHE_DSS_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_128_CBC_SHA",
+        TLS_DHE_RSA_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_256_CBC_SHA",
+        TLS_DH_DSS_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_256_CBC_SHA",
+        TLS_DH_RSA_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_256_CBC_SHA",
+        TLS_DHE_DSS_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_256_CBC_SHA",
+        TLS_DHE_RSA_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_128_CBC_SHA256",
+        TLS_DH_DSS_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_128_CBC_SHA256",
+        TLS_DH_RSA_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_128_CBC_SHA256",
+        TLS_DHE_DSS_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_128_CBC_SHA256",
+        TLS_DHE_RSA_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_256_CBC_SHA256",
+        TLS_DH_DSS_WITH_AES_256_CBC_SHA256,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_256_CBC_SHA256",
+        TLS_DH_RSA_WITH_AES_256_CBC_SHA256,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_256_CBC_SHA256",
+        TLS_DHE_DSS_WITH_AES_256_CBC_SHA256,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_256_CBC_SHA256",
+        TLS_DHE_RSA_WITH_AES_256_CBC_SHA256,
+    ),
+    ("TLS_DH_ANON_WITH_RC4_128_MD5", TLS_DH_ANON_WITH_RC4_128_MD5),
+    (
+        "TLS_DH_ANON_WITH_3DES_EDE_CBC_SHA",
+        TLS_DH_ANON_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_128_CBC_SHA",
+        TLS_DH_ANON_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_256_CBC_SHA",
+        TLS_DH_ANON_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_128_CBC_SHA256",
+        TLS_DH_ANON_WITH_AES_128_CBC_SHA256,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_256_CBC_SHA256",
+        TLS_DH_ANON_WITH_AES_256_CBC_SHA256,
+    ),
+    (
+        "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_PSK_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_PSK_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_ECDHE_PSK_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_ECDHE_PSK_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_DHE_PSK_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_DHE_PSK_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_RSA_PSK_WITH_CHACHA20_POLY1305_SHA256",
+        TLS_RSA_PSK_WITH_CHACHA20_POLY1305_SHA256,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_NULL_SHA",
+        TLS_ECDHE_ECDSA_WITH_NULL_SHA,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA",
+        TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+    ),
+    ("TLS_ECDHE_RSA_WITH_NULL_SHA", TLS_ECDHE_RSA_WITH_NULL_SHA),
+    (
+        "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+    ),
+    ("TLS_ECDH_ANON_WITH_NULL_SHA", TLS_ECDH_ANON_WITH_NULL_SHA),
+    (
+        "TLS_ECDH_ANON_WITH_3DES_EDE_CBC_SHA",
+        TLS_ECDH_ANON_WITH_3DES_EDE_CBC_SHA,
+    ),
+    (
+        "TLS_ECDH_ANON_WITH_AES_128_CBC_SHA",
+        TLS_ECDH_ANON_WITH_AES_128_CBC_SHA,
+    ),
+    (
+        "TLS_ECDH_ANON_WITH_AES_256_CBC_SHA",
+        TLS_ECDH_ANON_WITH_AES_256_CBC_SHA,
+    ),
+    (
+        "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        TLS_RSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        TLS_RSA_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256",
+        TLS_DHE_RSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384",
+        TLS_DHE_RSA_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_128_GCM_SHA256",
+        TLS_DH_RSA_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_DH_RSA_WITH_AES_256_GCM_SHA384",
+        TLS_DH_RSA_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_128_GCM_SHA256",
+        TLS_DHE_DSS_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_DHE_DSS_WITH_AES_256_GCM_SHA384",
+        TLS_DHE_DSS_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_128_GCM_SHA256",
+        TLS_DH_DSS_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_DH_DSS_WITH_AES_256_GCM_SHA384",
+        TLS_DH_DSS_WITH_AES_256_GCM_SHA384,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_128_GCM_SHA256",
+        TLS_DH_ANON_WITH_AES_128_GCM_SHA256,
+    ),
+    (
+        "TLS_DH_ANON_WITH_AES_256_GCM_SHA384",
+        TLS_DH_ANON_WITH_AES_256_GCM_SHA384,
+    ),
+    ("TLS_AES_128_GCM_SHA256", TLS_AES_128_GCM_SHA256),
+    ("TLS_AES_256_GCM_SHA384", TLS_AES_256_GCM_SHA384),
+    ("TLS_CHACHA20_POLY1305_SHA256", TLS_CHACHA20_POLY1305_SHA256),
+    ("TLS_AES_128_CCM_SHA256", TLS_AES_128_CCM_SHA256),
+    ("TLS_AES_128_CCM_8_SHA256", TLS_AES_128_CCM_8_SHA256),
+];
@@ -0,0 +1,43 @@
+// opaque ProtocolName<1..2^8-1>, as in ALPN
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ProtocolName {
+    pub(crate) name: VariableLengthVector<u8, 1, 1>,
+}
+
+// application_layer_protocol_negotiation extension: https://datatracker.ietf.org/doc/html/rfc7301#section-3.1.
+// The client lists the protocols it supports, the server answers with the one selected
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ProtocolNameList {
+    protocol_name_list: VariableLengthVector<ProtocolName, 2, 2>,
+}
+
+impl ProtocolNameList {
+    pub fn new(protocols: &[&str]) -> Self {
+        let mut protocol_name_list = VariableLengthVector {
+            length: 0,
+            data: protocols
+                .iter()
+                .map(|x| ProtocolName {
+                    name: VariableLengthVector::from_slice(x.as_bytes()),
+                })
+                .collect(),
+        };
+        protocol_name_list.fix_length();
+        Self { protocol_name_list }
+    }
+
+    pub fn protocols(&self) -> Vec<String> {
+        self.protocol_name_list
+            .data
+            .iter()
+            .map(|x| String::from_utf8_lossy(&x.name.data).into_owned())
+            .collect()
+    }
+}
+
+ext_type!(ProtocolNameList, application_layer_protocol_negotiation);
@@ -0,0 +1,65 @@
+// application_settings (ALPS) extension: https://datatracker.ietf.org/doc/html/draft-vvv-tls-alps.
+// The client lists the ALPN protocols for which it can exchange settings in the handshake
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use crate::handshake::extensions::alpn::ProtocolName;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ApplicationSettings {
+    supported_protocols: VariableLengthVector<ProtocolName, 2, 2>,
+}
+
+impl ApplicationSettings {
+    pub fn new(protocols: &[&str]) -> Self {
+        let mut supported_protocols = VariableLengthVector {
+            length: 0,
+            data: protocols
+                .iter()
+                .map(|x| ProtocolName {
+                    name: VariableLengthVector::from_slice(x.as_bytes()),
+                })
+                .collect(),
+        };
+        supported_protocols.fix_length();
+        Self {
+            supported_protocols,
+        }
+    }
+
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut extension = Self::default();
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    pub fn protocols(&self) -> Vec<String> {
+        self.supported_protocols
+            .data
+            .iter()
+            .map(|x| String::from_utf8_lossy(&x.name.data).into_owned())
+            .collect()
+    }
+}
+
+ext_type!(ApplicationSettings, application_settings);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+
+    #[test]
+    fn application_settings() {
+        let alps = ApplicationSettings::new(&["h2", "http/1.1"]);
+        let ext = GenericExtension::from_extension(&alps).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::application_settings);
+        assert_eq!(ext.extension_data(), b"\x00\x0C\x02h2\x08http/1.1");
+        assert_eq!(extension_name(0x4469), "application_settings(17513)");
+
+        let alps = ApplicationSettings::from_extension_data(ext.extension_data()).unwrap();
+        assert_eq!(alps.protocols(), vec!["h2", "http/1.1"]);
+        assert!(ApplicationSettings::from_extension_data(&[0x00, 0x03, 0x02, b'h']).is_err());
+    }
+}
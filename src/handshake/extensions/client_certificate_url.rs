@@ -0,0 +1,97 @@
+// client_certificate_url extension: https://datatracker.ietf.org/doc/html/rfc6066#section-5. Its
+// body is empty in both hellos, and lets the client send a CertificateURL message, listing URLs
+// of its certificates, instead of a Certificate message
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ClientCertificateUrl {}
+
+ext_type!(ClientCertificateUrl, client_certificate_url);
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum CertChainType {
+    individual_certs = 0,
+    pkipath = 1,
+}
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct UrlAndHash {
+    pub url: VariableLengthVector<u8, 1, 2>,
+
+    // always 1, for compatibility with earlier drafts
+    pub padding: u8,
+
+    // SHA-1 hash of the certificate or chain found at the URL
+    pub sha1_hash: [u8; 20],
+}
+
+impl UrlAndHash {
+    pub fn new(url: &str, sha1_hash: [u8; 20]) -> Self {
+        Self {
+            url: VariableLengthVector::from_slice(url.as_bytes()),
+            padding: 1,
+            sha1_hash,
+        }
+    }
+}
+
+// the CertificateURL handshake message
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct CertificateUrl {
+    pub cert_chain_type: CertChainType,
+    pub url_and_hash_list: VariableLengthVector<UrlAndHash, 1, 2>,
+}
+
+impl CertificateUrl {
+    pub fn new(cert_chain_type: CertChainType, urls: &[UrlAndHash]) -> Self {
+        let mut url_and_hash_list = VariableLengthVector {
+            length: 0,
+            data: urls.to_vec(),
+        };
+        url_and_hash_list.fix_length();
+        Self {
+            cert_chain_type,
+            url_and_hash_list,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionPayload, ExtensionType, GenericExtension};
+    use crate::handshake::extensions::ExtensionContext;
+    use std::io::Cursor;
+
+    #[test]
+    fn client_certificate_url() {
+        let ext = GenericExtension::from_extension(&ClientCertificateUrl {}).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::client_certificate_url);
+        assert!(matches!(
+            ext.parse(ExtensionContext::ServerHello).unwrap(),
+            ExtensionPayload::Empty(ExtensionType::client_certificate_url)
+        ));
+
+        let message = CertificateUrl::new(
+            CertChainType::pkipath,
+            &[UrlAndHash::new("http://a/c", [0x11; 20])],
+        );
+        let mut v = Vec::new();
+        message.to_network_bytes(&mut v).unwrap();
+        assert_eq!(v.len(), 1 + 2 + 2 + 10 + 1 + 20);
+        assert_eq!(&v[..5], &[0x01, 0x00, 0x21, 0x00, 0x0A]);
+        assert_eq!(v[15], 1);
+
+        let mut parsed = CertificateUrl::default();
+        parsed.from_network_bytes(&mut Cursor::new(v)).unwrap();
+        assert_eq!(parsed.cert_chain_type, CertChainType::pkipath);
+        let url = &parsed.url_and_hash_list.data[0];
+        assert_eq!(url.url.data, b"http://a/c");
+        assert_eq!(url.sha1_hash, [0x11; 20]);
+    }
+}
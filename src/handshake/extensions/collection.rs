@@ -0,0 +1,224 @@
+// the extensions of a message, sent in the order they were added. There can't be 2 extensions
+// of the same type in a block: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2, so
+// parsing rejects duplicates, and adding an extension replaces the one of the same type
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind, Result};
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+use crate::handshake::common::{is_grease, VariableLengthVector};
+
+// the type found twice when parsing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuplicateExtension(pub ExtensionType);
+
+impl fmt::Display for DuplicateExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "duplicate extension {}",
+            extension_name(u16::from(self.0))
+        )
+    }
+}
+
+impl std::error::Error for DuplicateExtension {}
+
+// the error can be recovered with get_ref() and downcast_ref()
+impl From<DuplicateExtension> for Error {
+    fn from(e: DuplicateExtension) -> Self {
+        Error::new(ErrorKind::InvalidData, e)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Extensions {
+    list: VariableLengthVector<GenericExtension, 0, 2>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_slice(&self) -> &[GenericExtension] {
+        &self.list.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.data.is_empty()
+    }
+
+    pub fn get(&self, extension_type: ExtensionType) -> Option<&GenericExtension> {
+        self.list
+            .data
+            .iter()
+            .find(|x| x.extension_type() == extension_type)
+    }
+
+    pub fn contains(&self, extension_type: ExtensionType) -> bool {
+        self.get(extension_type).is_some()
+    }
+
+    // replace the extension of the same type in place, or add it at the end. A pre_shared_key
+    // extension stays last: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11
+    pub fn insert(&mut self, extension: GenericExtension) {
+        let data = &mut self.list.data;
+        if let Some(x) = data
+            .iter_mut()
+            .find(|x| x.type_code() == extension.type_code())
+        {
+            *x = extension;
+        } else {
+            let psk = u16::from(ExtensionType::pre_shared_key);
+            match data.iter().position(|x| x.type_code() == psk) {
+                Some(index) => data.insert(index, extension),
+                None => data.push(extension),
+            }
+        }
+        self.list.fix_length();
+    }
+
+    pub fn remove(&mut self, extension_type: ExtensionType) -> Option<GenericExtension> {
+        let index = self
+            .list
+            .data
+            .iter()
+            .position(|x| x.extension_type() == extension_type)?;
+        let extension = self.list.data.remove(index);
+        self.list.fix_length();
+        Some(extension)
+    }
+
+    // reorder the extensions like those of a profile, e.g. the types of a captured ClientHello,
+    // to reproduce its fingerprint. A GREASE code matches any GREASE extension. Extensions
+    // missing from the profile come next in their current order, and pre_shared_key stays last
+    pub fn sort_like<T: Copy + Into<u16>>(&mut self, profile: &[T]) {
+        let profile: Vec<u16> = profile.iter().map(|&x| x.into()).collect();
+        let mut used = vec![false; profile.len()];
+
+        let psk = u16::from(ExtensionType::pre_shared_key);
+        let keys: Vec<(bool, usize)> = self
+            .list
+            .data
+            .iter()
+            .map(|x| {
+                let slot = profile.iter().enumerate().position(|(i, &code)| {
+                    !used[i]
+                        && (code == x.type_code() || (is_grease(code) && is_grease(x.type_code())))
+                });
+                if let Some(i) = slot {
+                    used[i] = true;
+                }
+                (x.type_code() == psk, slot.unwrap_or(profile.len()))
+            })
+            .collect();
+
+        // stable, so extensions with the same key keep their order
+        let mut sorted: Vec<_> = keys.into_iter().zip(self.list.data.drain(..)).collect();
+        sorted.sort_by_key(|(key, _)| *key);
+        self.list.data = sorted.into_iter().map(|(_, x)| x).collect();
+    }
+}
+
+impl From<Vec<GenericExtension>> for Extensions {
+    fn from(data: Vec<GenericExtension>) -> Self {
+        let mut list = VariableLengthVector { length: 0, data };
+        list.fix_length();
+        Self { list }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(Extensions);
+
+impl TlsDerive for Extensions {
+    fn tls_len(&self) -> usize {
+        self.list.tls_len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        self.list.to_network_bytes(v)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        self.list.from_network_bytes(v)?;
+
+        for (i, extension) in self.list.data.iter().enumerate() {
+            if self.list.data[..i]
+                .iter()
+                .any(|x| x.type_code() == extension.type_code())
+            {
+                return Err(DuplicateExtension(extension.extension_type()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ExtensionType::*;
+
+    fn types(extensions: &Extensions) -> Vec<u16> {
+        extensions
+            .as_slice()
+            .iter()
+            .map(|x| x.type_code())
+            .collect()
+    }
+
+    #[test]
+    fn order() {
+        let mut extensions = Extensions::new();
+        for extension_type in [key_share, pre_shared_key, server_name, Unknown(0x1A1A)] {
+            extensions.insert(GenericExtension::new(extension_type, &[]));
+        }
+        assert_eq!(types(&extensions), vec![51, 0, 0x1A1A, 41]);
+
+        // replaced in place
+        extensions.insert(GenericExtension::new(server_name, &[0, 0]));
+        assert_eq!(types(&extensions), vec![51, 0, 0x1A1A, 41]);
+        assert_eq!(
+            extensions.get(server_name).unwrap().extension_data(),
+            &[0, 0]
+        );
+        assert_eq!(extensions.tls_len(), 2 + 4 * 4 + 2);
+
+        // GREASE matches GREASE, others follow
+        extensions.sort_like(&[0x3A3Au16, 0, 41]);
+        assert_eq!(types(&extensions), vec![0x1A1A, 0, 51, 41]);
+        extensions.sort_like(&[pre_shared_key, key_share]);
+        assert_eq!(types(&extensions), vec![51, 0x1A1A, 0, 41]);
+
+        assert!(extensions.remove(key_share).is_some());
+        assert!(!extensions.contains(key_share));
+        assert_eq!(extensions.tls_len(), 2 + 3 * 4 + 2);
+    }
+
+    #[test]
+    fn duplicates() {
+        let extensions = Extensions::from(vec![
+            GenericExtension::new(server_name, &[]),
+            GenericExtension::new(renegotiation_info, &[0]),
+            GenericExtension::new(server_name, &[]),
+        ]);
+        let mut buffer = Vec::new();
+        extensions.to_network_bytes(&mut buffer).unwrap();
+
+        let e = Extensions::new()
+            .from_network_bytes(&mut Cursor::new(buffer))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "duplicate extension server_name(0)");
+        assert_eq!(
+            e.get_ref().unwrap().downcast_ref::<DuplicateExtension>(),
+            Some(&DuplicateExtension(server_name))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+// cookie extension: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.2. Sent by the
+// server in a HelloRetryRequest, and echoed by the client in its second ClientHello
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct Cookie {
+    cookie: VariableLengthVector<u8, 1, 2>,
+}
+
+impl Cookie {
+    pub fn new(cookie: &[u8]) -> Self {
+        Self {
+            cookie: VariableLengthVector::from_slice(cookie),
+        }
+    }
+
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut cookie = Self::default();
+        cookie.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+
+        // opaque cookie<1..2^16-1>
+        if cookie.cookie.data.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "empty cookie",
+            ));
+        }
+        Ok(cookie)
+    }
+
+    pub fn value(&self) -> &[u8] {
+        &self.cookie.data
+    }
+}
+
+ext_type!(Cookie, cookie, |data, _| Cookie::from_extension_data(data));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ClientHello, ExtensionType, GenericExtension};
+    use crate::handshake::constants::*;
+    use crate::handshake::extensions::pre_shared_key::{PreSharedKeyExtension, PskIdentity};
+    use crate::handshake::server_hello::ServerHello;
+
+    #[test]
+    fn cookie() {
+        let ext = GenericExtension::from_extension(&Cookie::new(&[0xDE, 0xAD])).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::cookie);
+        assert_eq!(ext.extension_data(), &[0x00, 0x02, 0xDE, 0xAD]);
+
+        let cookie = Cookie::from_extension_data(ext.extension_data()).unwrap();
+        assert_eq!(cookie.value(), &[0xDE, 0xAD]);
+        assert!(Cookie::from_extension_data(&[0x00, 0x00]).is_err());
+        assert!(Cookie::from_extension_data(&[0x00, 0x03, 0xDE, 0xAD]).is_err());
+
+        // HelloRetryRequest with a cookie
+        let mut data = vec![0x03, 0x03];
+        data.extend_from_slice(&HELLO_RETRY_REQUEST_RANDOM);
+        data.extend_from_slice(&[0x00, 0x13, 0x01, 0x00, 0x00, 0x08]);
+        data.extend_from_slice(&[0x00, 0x2C, 0x00, 0x04, 0x00, 0x02, 0xBE, 0xEF]);
+        let mut hrr = ServerHello::default();
+        hrr.from_network_bytes(&mut std::io::Cursor::new(data))
+            .unwrap();
+
+        // the cookie is echoed before pre_shared_key
+        let psk = PreSharedKeyExtension::new(vec![PskIdentity::new(b"ticket", 0)], &[&[0; 32]]);
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256])
+            .with_extension_replaced(GenericExtension::from_extension(&psk).unwrap());
+        let retry = ch.with_cookie_echoed(&hrr).unwrap();
+        let types: Vec<_> = retry
+            .extensions()
+            .iter()
+            .map(|x| x.extension_type())
+            .collect();
+        assert_eq!(
+            types,
+            vec![ExtensionType::cookie, ExtensionType::pre_shared_key]
+        );
+        assert_eq!(
+            retry.extensions()[0].extension_data(),
+            &[0x00, 0x02, 0xBE, 0xEF]
+        );
+        assert_eq!(retry.tls_len(), ch.tls_len() + 8);
+
+        // nothing to echo in a regular ServerHello
+        let ch = ch.with_cookie_echoed(&ServerHello::default()).unwrap();
+        assert_eq!(ch.extensions().len(), 1);
+    }
+}
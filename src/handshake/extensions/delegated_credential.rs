@@ -0,0 +1,106 @@
+// delegated_credential extension sent by the client: the signature schemes it accepts for
+// delegated credentials: https://datatracker.ietf.org/doc/html/rfc9345#section-4.1.1
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use crate::handshake::extensions::signature_algorithms::SignatureScheme;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, TlsDerive)]
+pub struct DelegatedCredentialSchemes {
+    signature_algorithms: VariableLengthVector<SignatureScheme, 2, 2>,
+}
+
+impl DelegatedCredentialSchemes {
+    pub fn new(schemes: &[SignatureScheme]) -> Self {
+        Self {
+            signature_algorithms: VariableLengthVector::from_slice(schemes),
+        }
+    }
+
+    pub fn schemes(&self) -> &[SignatureScheme] {
+        &self.signature_algorithms.data
+    }
+}
+
+ext_type!(DelegatedCredentialSchemes, delegated_credential);
+
+// short-lived key delegated by the owner of the certificate: https://datatracker.ietf.org/doc/html/rfc9345#section-4
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct Credential {
+    // seconds from the notBefore of the certificate to the expiry of the credential
+    pub valid_time: u32,
+    pub dc_cert_verify_algorithm: SignatureScheme,
+    pub asn1_subject_public_key_info: VariableLengthVector<u8, 1, 3>,
+}
+
+// delegated_credential extension sent by the server, in the extensions of the end-entity
+// CertificateEntry. The signature is made with the certificate key
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct DelegatedCredential {
+    pub cred: Credential,
+    pub algorithm: SignatureScheme,
+    pub signature: VariableLengthVector<u8, 1, 2>,
+}
+
+impl DelegatedCredential {
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut credential = Self::default();
+        credential.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(credential)
+    }
+
+    // DER encoding of the delegated public key
+    pub fn public_key(&self) -> &[u8] {
+        &self.cred.asn1_subject_public_key_info.data
+    }
+
+    // expiry time in seconds since the epoch, from the notBefore of the certificate
+    pub fn expires_at(&self, not_before: u64) -> u64 {
+        not_before + self.cred.valid_time as u64
+    }
+}
+
+ext_type!(DelegatedCredential, delegated_credential, |data, _| {
+    DelegatedCredential::from_extension_data(data)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+
+    #[test]
+    fn delegated_credential() {
+        let schemes = DelegatedCredentialSchemes::new(&[
+            SignatureScheme::ecdsa_secp256r1_sha256,
+            SignatureScheme::ed25519,
+        ]);
+        let ext = GenericExtension::from_extension(&schemes).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::delegated_credential);
+        assert_eq!(ext.extension_data(), &[0x00, 0x04, 0x04, 0x03, 0x08, 0x07]);
+        assert_eq!(extension_name(34), "delegated_credential(34)");
+
+        let data = vec![
+            0x00, 0x09, 0x3A, 0x80, // valid_time: 7 days
+            0x04, 0x03, // dc_cert_verify_algorithm
+            0x00, 0x00, 0x03, 0x30, 0x01, 0x00, // ASN1_subjectPublicKeyInfo
+            0x04, 0x01, // algorithm
+            0x00, 0x02, 0xAA, 0xBB, // signature
+        ];
+        let credential = DelegatedCredential::from_extension_data(&data).unwrap();
+        assert_eq!(credential.cred.valid_time, 7 * 86400);
+        assert_eq!(
+            credential.cred.dc_cert_verify_algorithm,
+            SignatureScheme::ecdsa_secp256r1_sha256
+        );
+        assert_eq!(credential.public_key(), &[0x30, 0x01, 0x00]);
+        assert_eq!(credential.algorithm, SignatureScheme::rsa_pkcs1_sha256);
+        assert_eq!(credential.signature.data, &[0xAA, 0xBB]);
+        assert_eq!(credential.expires_at(1_700_000_000), 1_700_604_800);
+
+        let ext = GenericExtension::from_extension(&credential).unwrap();
+        assert_eq!(ext.extension_data(), &data[..]);
+        assert!(DelegatedCredential::from_extension_data(&data[..10]).is_err());
+    }
+}
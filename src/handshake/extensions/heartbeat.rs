@@ -0,0 +1,34 @@
+// heartbeat extension: whether the sender accepts HeartbeatRequest messages from its peer:
+// https://datatracker.ietf.org/doc/html/rfc6520#section-2
+use crate::ext_type;
+use tls_derive::TlsEnum;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum HeartbeatMode {
+    peer_allowed_to_send = 1,
+    peer_not_allowed_to_send = 2,
+}
+
+ext_type!(HeartbeatMode, heartbeat);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+
+    #[test]
+    fn heartbeat() {
+        let ext = GenericExtension::from_extension(&HeartbeatMode::peer_allowed_to_send).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::heartbeat);
+        assert_eq!(ext.extension_data(), &[0x01]);
+        assert_eq!(extension_name(15), "heartbeat(15)");
+
+        assert_eq!(
+            HeartbeatMode::try_from(2u8),
+            Ok(HeartbeatMode::peer_not_allowed_to_send)
+        );
+        assert!(HeartbeatMode::try_from(0u8).is_err());
+    }
+}
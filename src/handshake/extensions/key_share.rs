@@ -0,0 +1,190 @@
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.8
+use crate::alert::alert::AlertDescription;
+use crate::crypto::kx::check_public;
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use crate::handshake::extensions::supported_groups::NamedGroup;
+use crate::handshake::extensions::ExtensionContext;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct KeyShareEntry {
+    pub group: NamedGroup,
+    pub key_exchange: VariableLengthVector<u8, 1, 2>,
+}
+
+impl KeyShareEntry {
+    pub fn new(group: NamedGroup, key_exchange: &[u8]) -> Self {
+        Self {
+            group,
+            key_exchange: VariableLengthVector::from_slice(key_exchange),
+        }
+    }
+
+    // see kx::check_public()
+    pub fn check(&self) -> Result<(), AlertDescription> {
+        check_public(self.group, &self.key_exchange.data)
+    }
+}
+
+// key_share extension. Like supported_versions, parsing uses the current variant as the
+// expected form
+#[derive(Debug, Clone)]
+pub enum KeyShare {
+    // KeyShareEntry client_shares<0..2^16-1> in a ClientHello
+    ClientHello(VariableLengthVector<KeyShareEntry, 0, 2>),
+
+    // KeyShareEntry server_share in a ServerHello
+    ServerHello(KeyShareEntry),
+
+    // NamedGroup selected_group in a HelloRetryRequest
+    HelloRetryRequest(NamedGroup),
+}
+
+impl Default for KeyShare {
+    fn default() -> Self {
+        Self::ClientHello(VariableLengthVector::default())
+    }
+}
+
+impl KeyShare {
+    pub fn new(entries: Vec<KeyShareEntry>) -> Self {
+        let mut client_shares = VariableLengthVector {
+            length: 0,
+            data: entries,
+        };
+        client_shares.fix_length();
+        Self::ClientHello(client_shares)
+    }
+
+    pub fn server_share(entry: KeyShareEntry) -> Self {
+        Self::ServerHello(entry)
+    }
+
+    pub fn selected_group(group: NamedGroup) -> Self {
+        Self::HelloRetryRequest(group)
+    }
+
+    // parse the extension data in the form used by the handshake message
+    pub fn from_extension_data(data: &[u8], context: ExtensionContext) -> std::io::Result<Self> {
+        let mut extension = match context {
+            ExtensionContext::ClientHello => Self::default(),
+            ExtensionContext::ServerHello => Self::ServerHello(KeyShareEntry::default()),
+            ExtensionContext::HelloRetryRequest => Self::HelloRetryRequest(NamedGroup::default()),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("key_share not allowed in {:?}", context),
+                ))
+            }
+        };
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    // the key shares sent, none for a HelloRetryRequest
+    pub fn entries(&self) -> &[KeyShareEntry] {
+        match self {
+            Self::ClientHello(entries) => &entries.data,
+            Self::ServerHello(entry) => std::slice::from_ref(entry),
+            Self::HelloRetryRequest(_) => &[],
+        }
+    }
+
+    // groups of the key shares, or the group selected by a HelloRetryRequest
+    pub fn groups(&self) -> Vec<NamedGroup> {
+        match self {
+            Self::HelloRetryRequest(group) => vec![*group],
+            _ => self.entries().iter().map(|entry| entry.group).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(KeyShare);
+
+impl TlsDerive for KeyShare {
+    fn tls_len(&self) -> usize {
+        match self {
+            Self::ClientHello(entries) => entries.tls_len(),
+            Self::ServerHello(entry) => entry.tls_len(),
+            Self::HelloRetryRequest(group) => group.tls_len(),
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        match self {
+            Self::ClientHello(entries) => entries.to_network_bytes(v),
+            Self::ServerHello(entry) => entry.to_network_bytes(v),
+            Self::HelloRetryRequest(group) => group.to_network_bytes(v),
+        }
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        match self {
+            Self::ClientHello(entries) => entries.from_network_bytes(v),
+            Self::ServerHello(entry) => entry.from_network_bytes(v),
+            Self::HelloRetryRequest(group) => group.from_network_bytes(v),
+        }
+    }
+}
+
+ext_type!(KeyShare, key_share, KeyShare::from_extension_data);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn key_share() {
+        let client_shares = KeyShare::new(vec![
+            KeyShareEntry::new(NamedGroup::x25519, &[0xAA; 32]),
+            KeyShareEntry::new(NamedGroup::secp256r1, &[0xBB; 65]),
+        ]);
+        let ext = GenericExtension::from_extension(&client_shares).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::key_share);
+        assert_eq!(
+            &ext.extension_data()[..6],
+            &[0x00, 0x69, 0x00, 0x1D, 0x00, 0x20]
+        );
+        assert_eq!(ext.extension_data().len(), 2 + (4 + 32) + (4 + 65));
+
+        let parsed =
+            KeyShare::from_extension_data(ext.extension_data(), ExtensionContext::ClientHello)
+                .unwrap();
+        assert_eq!(
+            parsed.groups(),
+            vec![NamedGroup::x25519, NamedGroup::secp256r1]
+        );
+        assert_eq!(parsed.entries()[1].key_exchange.data, vec![0xBB; 65]);
+
+        let server_share =
+            KeyShare::server_share(KeyShareEntry::new(NamedGroup::x25519, &[0xCC; 32]));
+        let ext = GenericExtension::from_extension(&server_share).unwrap();
+        assert_eq!(&ext.extension_data()[..4], &[0x00, 0x1D, 0x00, 0x20]);
+        let parsed =
+            KeyShare::from_extension_data(ext.extension_data(), ExtensionContext::ServerHello)
+                .unwrap();
+        assert_eq!(parsed.entries()[0].key_exchange.data, vec![0xCC; 32]);
+
+        let hrr = KeyShare::selected_group(NamedGroup::secp384r1);
+        let ext = GenericExtension::from_extension(&hrr).unwrap();
+        assert_eq!(ext.extension_data(), &[0x00, 0x18]);
+        let parsed = KeyShare::from_extension_data(
+            ext.extension_data(),
+            ExtensionContext::HelloRetryRequest,
+        )
+        .unwrap();
+        assert!(parsed.entries().is_empty());
+        assert_eq!(parsed.groups(), vec![NamedGroup::secp384r1]);
+
+        // truncated key_exchange
+        assert!(KeyShare::from_extension_data(
+            &[0x00, 0x1D, 0x00, 0x20, 0xCC],
+            ExtensionContext::ServerHello
+        )
+        .is_err());
+    }
+}
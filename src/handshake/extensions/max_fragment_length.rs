@@ -0,0 +1,67 @@
+// max_fragment_length extension: https://datatracker.ietf.org/doc/html/rfc6066#section-4
+use crate::alert::alert::AlertDescription;
+use crate::ext_type;
+use tls_derive::TlsEnum;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum MaxFragmentLength {
+    length_512 = 1,
+    length_1024 = 2,
+    length_2048 = 3,
+    length_4096 = 4,
+}
+
+impl MaxFragmentLength {
+    // the maximum length of a record fragment, in bytes
+    pub fn length(&self) -> usize {
+        1 << (8 + *self as usize)
+    }
+
+    // the limit in use once the server has answered. The server either echoes the requested
+    // value or ignores the extension
+    pub fn negotiate(
+        requested: Option<Self>,
+        echoed: Option<&[u8]>,
+    ) -> std::result::Result<Option<Self>, AlertDescription> {
+        match (requested, echoed) {
+            (_, None) => Ok(None),
+            (None, Some(_)) => Err(AlertDescription::unsupported_extension),
+            (Some(requested), Some(&[value])) if value == requested as u8 => Ok(Some(requested)),
+            (Some(_), Some(_)) => Err(AlertDescription::illegal_parameter),
+        }
+    }
+}
+
+ext_type!(MaxFragmentLength, max_fragment_length);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn max_fragment_length() {
+        let ext = GenericExtension::from_extension(&MaxFragmentLength::length_1024).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::max_fragment_length);
+        assert_eq!(ext.extension_data(), &[0x02]);
+        assert_eq!(MaxFragmentLength::length_512.length(), 512);
+        assert_eq!(MaxFragmentLength::length_4096.length(), 4096);
+
+        let requested = Some(MaxFragmentLength::length_2048);
+        assert_eq!(
+            MaxFragmentLength::negotiate(requested, Some(&[0x03])),
+            Ok(requested)
+        );
+        assert_eq!(MaxFragmentLength::negotiate(requested, None), Ok(None));
+        assert_eq!(
+            MaxFragmentLength::negotiate(requested, Some(&[0x01])),
+            Err(AlertDescription::illegal_parameter)
+        );
+        assert_eq!(
+            MaxFragmentLength::negotiate(None, Some(&[0x01])),
+            Err(AlertDescription::unsupported_extension)
+        );
+    }
+}
@@ -1,2 +1,149 @@
-pub mod extensions;
+// one module per extension. Each implements the Extension trait, and those with a typed body
+// are listed in the registry, which ExtensionPayload::parse uses to decode them. Adding an
+// extension is adding its module, its ExtensionType code, its ExtensionPayload variant and its
+// registry line
+use std::io::{Cursor, Error, ErrorKind, Result};
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::{ExtensionPayload, ExtensionType};
+
+pub mod alpn;
+pub mod application_settings;
+pub mod client_certificate_url;
+pub mod collection;
+pub mod cookie;
+pub mod delegated_credential;
+pub mod heartbeat;
+pub mod key_share;
+pub mod max_fragment_length;
+pub mod post_handshake_auth;
+pub mod pre_shared_key;
+pub mod quic_transport_parameters;
+pub mod signature_algorithms;
+pub mod signed_certificate_timestamp;
 pub mod sni;
+pub mod status_request;
+pub mod supported_groups;
+pub mod supported_versions;
+pub mod truncated_hmac;
+pub mod trusted_ca_keys;
+pub mod use_srtp;
+
+// the message carrying an extension, for extensions whose layout depends on it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExtensionContext {
+    ClientHello,
+    ServerHello,
+    HelloRetryRequest,
+
+    // TLS 1.3 messages
+    EncryptedExtensions,
+    NewSessionTicket,
+}
+
+// an extension body, without the type and length which GenericExtension adds. Implemented with
+// the ext_type! macro
+pub trait Extension: Sized {
+    const TYPE: ExtensionType;
+
+    fn encode_body(&self) -> Result<Vec<u8>>;
+
+    // data is the whole body, in the form used by the message carrying the extension
+    fn decode_body(data: &[u8], context: ExtensionContext) -> Result<Self>;
+}
+
+// decode a whole extension body
+pub(crate) fn decode<T: TlsDerive + Default>(data: &[u8]) -> Result<T> {
+    let mut cursor = Cursor::new(data.to_vec());
+    let mut extension = T::default();
+    extension.from_network_bytes(&mut cursor)?;
+
+    if cursor.position() as usize != data.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "trailing data after extension",
+        ));
+    }
+    Ok(extension)
+}
+
+// an extension with a typed body, and how to decode it into its ExtensionPayload variant
+pub struct Registration {
+    pub extension_type: ExtensionType,
+    pub decode: fn(&[u8], ExtensionContext) -> Result<ExtensionPayload>,
+}
+
+macro_rules! registry {
+    ($($t:ty => $variant:ident,)*) => {
+        pub const REGISTRY: &[Registration] = &[$(
+            Registration {
+                extension_type: <$t as Extension>::TYPE,
+                decode: |data, context| {
+                    Ok(ExtensionPayload::$variant(<$t>::decode_body(data, context)?))
+                },
+            },
+        )*];
+    };
+}
+
+// extensions sent with an empty body in some messages, or whose type depends on the message,
+// are handled by ExtensionPayload::parse before the registry
+registry! {
+    sni::ServerNameList => ServerName,
+    max_fragment_length::MaxFragmentLength => MaxFragmentLength,
+    trusted_ca_keys::TrustedAuthorities => TrustedCaKeys,
+    status_request::CertificateStatusRequest => StatusRequest,
+    supported_groups::SupportedGroups => SupportedGroups,
+    signature_algorithms::SignatureAlgorithms => SignatureAlgorithms,
+    signature_algorithms::SignatureAlgorithmsCert => SignatureAlgorithmsCert,
+    use_srtp::UseSrtp => UseSrtp,
+    heartbeat::HeartbeatMode => Heartbeat,
+    alpn::ProtocolNameList => Alpn,
+    signed_certificate_timestamp::SignedCertificateTimestampList => SignedCertificateTimestamps,
+    delegated_credential::DelegatedCredential => DelegatedCredential,
+    supported_versions::SupportedVersions => SupportedVersions,
+    key_share::KeyShare => KeyShare,
+    pre_shared_key::PreSharedKeyExtension => PreSharedKey,
+    cookie::Cookie => Cookie,
+    application_settings::ApplicationSettings => ApplicationSettings,
+    quic_transport_parameters::QuicTransportParameters => QuicTransportParameters,
+}
+
+pub fn registration(extension_type: ExtensionType) -> Option<&'static Registration> {
+    REGISTRY.iter().find(|x| x.extension_type == extension_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::GenericExtension;
+
+    #[test]
+    fn registry() {
+        for (i, registration) in REGISTRY.iter().enumerate() {
+            assert!(
+                REGISTRY[..i]
+                    .iter()
+                    .all(|x| x.extension_type != registration.extension_type),
+                "{} registered twice",
+                registration.extension_type
+            );
+        }
+        assert!(registration(ExtensionType::ec_point_formats).is_none());
+
+        // encoded and decoded through the trait
+        let list = sni::ServerNameList::new("example.com");
+        let ext = GenericExtension::from_extension(&list).unwrap();
+        assert_eq!(ext.type_code(), 0);
+        let decoded =
+            sni::ServerNameList::decode_body(ext.extension_data(), ExtensionContext::ClientHello)
+                .unwrap();
+        assert_eq!(decoded.host_name(), "example.com");
+
+        let registration = registration(ExtensionType::server_name).unwrap();
+        assert!(matches!(
+            (registration.decode)(ext.extension_data(), ExtensionContext::ClientHello).unwrap(),
+            ExtensionPayload::ServerName(_)
+        ));
+    }
+}
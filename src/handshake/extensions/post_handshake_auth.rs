@@ -0,0 +1,32 @@
+// post_handshake_auth extension: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.6.
+// Its body is empty, the client only signals it accepts a CertificateRequest after the handshake
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct PostHandshakeAuth {}
+
+ext_type!(PostHandshakeAuth, post_handshake_auth);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{
+        extension_name, ClientHello, ExtensionType, GenericExtension,
+    };
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn post_handshake_auth() {
+        let ext = GenericExtension::from_extension(&PostHandshakeAuth {}).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::post_handshake_auth);
+        assert!(ext.extension_data().is_empty());
+        assert_eq!(extension_name(49), "post_handshake_auth(49)");
+
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256]).with_extension_replaced(ext);
+        let mut v = Vec::new();
+        ch.to_network_bytes(&mut v).unwrap();
+        assert_eq!(&v[v.len() - 6..], &[0x00, 0x04, 0x00, 0x31, 0x00, 0x00]);
+    }
+}
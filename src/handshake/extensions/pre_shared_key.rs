@@ -0,0 +1,272 @@
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11
+use crate::crypto::hkdf::{derive_secret, hkdf_expand_label, hkdf_extract};
+use crate::crypto::hmac::hmac;
+use crate::crypto::sha2::HashAlgorithm;
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use crate::handshake::extensions::ExtensionContext;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct PskIdentity {
+    pub identity: VariableLengthVector<u8, 1, 2>,
+    pub obfuscated_ticket_age: u32,
+}
+
+impl PskIdentity {
+    pub fn new(identity: &[u8], obfuscated_ticket_age: u32) -> Self {
+        Self {
+            identity: VariableLengthVector::from_slice(identity),
+            obfuscated_ticket_age,
+        }
+    }
+}
+
+pub type PskBinderEntry = VariableLengthVector<u8, 32, 1>;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct OfferedPsks {
+    pub identities: VariableLengthVector<PskIdentity, 7, 2>,
+    pub binders: VariableLengthVector<PskBinderEntry, 33, 2>,
+}
+
+// pre_shared_key extension, which must be the last one of a ClientHello. Like
+// supported_versions, parsing uses the current variant as the expected form
+#[derive(Debug, Clone)]
+pub enum PreSharedKeyExtension {
+    // OfferedPsks in a ClientHello, one binder per identity
+    ClientHello(OfferedPsks),
+
+    // uint16 selected_identity in a ServerHello
+    ServerHello(u16),
+}
+
+impl Default for PreSharedKeyExtension {
+    fn default() -> Self {
+        Self::ClientHello(OfferedPsks::default())
+    }
+}
+
+impl PreSharedKeyExtension {
+    pub fn new(identities: Vec<PskIdentity>, binders: &[&[u8]]) -> Self {
+        let mut offered = OfferedPsks::default();
+        offered.identities.data = identities;
+        offered.identities.fix_length();
+        for binder in binders {
+            let binder: PskBinderEntry = VariableLengthVector::from_slice(binder);
+            offered.binders.length += binder.tls_len() as u32;
+            offered.binders.data.push(binder);
+        }
+        Self::ClientHello(offered)
+    }
+
+    pub fn selected(index: u16) -> Self {
+        Self::ServerHello(index)
+    }
+
+    // parse the extension data in the form used by the handshake message
+    pub fn from_extension_data(data: &[u8], context: ExtensionContext) -> std::io::Result<Self> {
+        let mut extension = match context {
+            ExtensionContext::ClientHello => Self::default(),
+            _ => Self::ServerHello(0),
+        };
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    pub fn identities(&self) -> &[PskIdentity] {
+        match self {
+            Self::ClientHello(offered) => &offered.identities.data,
+            Self::ServerHello(_) => &[],
+        }
+    }
+
+    pub fn binders(&self) -> Vec<&[u8]> {
+        match self {
+            Self::ClientHello(offered) => {
+                offered.binders.data.iter().map(|x| &x.data[..]).collect()
+            }
+            Self::ServerHello(_) => Vec::new(),
+        }
+    }
+
+    // index of the identity chosen by the server
+    pub fn selected_identity(&self) -> Option<u16> {
+        match self {
+            Self::ClientHello(_) => None,
+            Self::ServerHello(index) => Some(*index),
+        }
+    }
+
+    // length of the binders list, which is left out of the transcript hashed for binders:
+    // https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11.2
+    pub fn binders_len(&self) -> usize {
+        match self {
+            Self::ClientHello(offered) => offered.binders.tls_len(),
+            Self::ServerHello(_) => 0,
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(PreSharedKeyExtension);
+
+impl TlsDerive for PreSharedKeyExtension {
+    fn tls_len(&self) -> usize {
+        match self {
+            Self::ClientHello(offered) => offered.tls_len(),
+            Self::ServerHello(index) => index.tls_len(),
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        match self {
+            Self::ClientHello(offered) => offered.to_network_bytes(v),
+            Self::ServerHello(index) => index.to_network_bytes(v),
+        }
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        match self {
+            Self::ClientHello(offered) => offered.from_network_bytes(v),
+            Self::ServerHello(index) => index.from_network_bytes(v),
+        }
+    }
+}
+
+// the binder label depends on where the PSK comes from: https://datatracker.ietf.org/doc/html/rfc8446#section-7.1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PskKind {
+    // provisioned out of band
+    External,
+
+    // from a NewSessionTicket
+    Resumption,
+}
+
+#[derive(Debug, Clone)]
+pub struct Psk {
+    pub identity: Vec<u8>,
+    pub key: Vec<u8>,
+
+    // the hash of the cipher suites the PSK can be used with, SHA-256 if not specified
+    pub hash: HashAlgorithm,
+    pub kind: PskKind,
+}
+
+impl Psk {
+    pub fn external(identity: &[u8], key: &[u8]) -> Self {
+        Self {
+            identity: identity.to_vec(),
+            key: key.to_vec(),
+            hash: HashAlgorithm::Sha256,
+            kind: PskKind::External,
+        }
+    }
+
+    // the HMAC of the ClientHello up to its binders: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11.2.
+    // truncated_hello is the handshake message, header included, without the binders list
+    pub fn binder(&self, truncated_hello: &[u8]) -> Vec<u8> {
+        let early_secret = hkdf_extract(self.hash, &[], &self.key);
+        let label = match self.kind {
+            PskKind::External => "ext binder",
+            PskKind::Resumption => "res binder",
+        };
+        let binder_key = derive_secret(self.hash, &early_secret, label, &[]);
+        let finished_key = hkdf_expand_label(
+            self.hash,
+            &binder_key,
+            "finished",
+            &[],
+            self.hash.output_len(),
+        );
+        hmac(self.hash, &finished_key, &self.hash.hash(truncated_hello))
+    }
+
+    // compared in constant time
+    pub fn verify_binder(&self, truncated_hello: &[u8], binder: &[u8]) -> bool {
+        let expected = self.binder(truncated_hello);
+        expected.len() == binder.len()
+            && expected
+                .iter()
+                .zip(binder)
+                .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+                == 0
+    }
+}
+
+ext_type!(
+    PreSharedKeyExtension,
+    pre_shared_key,
+    PreSharedKeyExtension::from_extension_data
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ClientHello, ExtensionType, GenericExtension};
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn pre_shared_key() {
+        let psk = PreSharedKeyExtension::new(
+            vec![PskIdentity::new(&[0x01, 0x02, 0x03], 0x11223344)],
+            &[&[0xBB; 32]],
+        );
+        let ext = GenericExtension::from_extension(&psk).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::pre_shared_key);
+
+        let data = ext.extension_data();
+        assert_eq!(
+            &data[..11],
+            &[0x00, 0x09, 0x00, 0x03, 0x01, 0x02, 0x03, 0x11, 0x22, 0x33, 0x44]
+        );
+        assert_eq!(&data[11..14], &[0x00, 0x21, 0x20]);
+        assert_eq!(psk.binders_len(), 2 + 33);
+
+        let parsed =
+            PreSharedKeyExtension::from_extension_data(data, ExtensionContext::ClientHello)
+                .unwrap();
+        assert_eq!(parsed.identities().len(), 1);
+        assert_eq!(parsed.identities()[0].identity.data, &[0x01, 0x02, 0x03]);
+        assert_eq!(parsed.identities()[0].obfuscated_ticket_age, 0x11223344);
+        assert_eq!(parsed.binders(), vec![&[0xBB; 32][..]]);
+        assert_eq!(parsed.selected_identity(), None);
+
+        let parsed = PreSharedKeyExtension::from_extension_data(
+            &[0x00, 0x01],
+            ExtensionContext::ServerHello,
+        )
+        .unwrap();
+        assert_eq!(parsed.selected_identity(), Some(1));
+        assert!(parsed.identities().is_empty());
+
+        // pre_shared_key stays last
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256])
+            .with_extension_replaced(ext.clone())
+            .with_extension_replaced(GenericExtension::new(ExtensionType::session_ticket, &[]));
+        let types: Vec<_> = ch.extensions().iter().map(|x| x.extension_type()).collect();
+        assert_eq!(
+            types,
+            vec![ExtensionType::session_ticket, ExtensionType::pre_shared_key]
+        );
+
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+        let mut parsed = ClientHello::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(buffer.clone()))
+            .unwrap();
+
+        // swap both extensions: session_ticket is 4 bytes at the end
+        let psk_start = buffer.len() - 4 - (4 + data.len());
+        let mut swapped = buffer[..psk_start].to_vec();
+        swapped.extend_from_slice(&buffer[buffer.len() - 4..]);
+        swapped.extend_from_slice(&buffer[psk_start..buffer.len() - 4]);
+        let mut parsed = ClientHello::default();
+        assert!(parsed
+            .from_network_bytes(&mut std::io::Cursor::new(swapped))
+            .is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// quic_transport_parameters extension, with the QUIC encodings it uses
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+
+// QUIC variable-length integer: https://datatracker.ietf.org/doc/html/rfc9000#section-16. The 2
+// high bits of the first byte give the length, 1, 2, 4 or 8 bytes
+pub fn read_varint(v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<u64> {
+    use std::io::Read;
+
+    let mut first = [0u8; 1];
+    v.read_exact(&mut first)?;
+    let length = 1 << (first[0] >> 6);
+
+    let mut value = (first[0] & 0x3F) as u64;
+    let mut rest = [0u8; 7];
+    v.read_exact(&mut rest[..length - 1])?;
+    for byte in &rest[..length - 1] {
+        value = (value << 8) | *byte as u64;
+    }
+    Ok(value)
+}
+
+// the shortest encoding, values must be below 2^62
+pub fn write_varint(value: u64, v: &mut Vec<u8>) -> std::io::Result<usize> {
+    let (length, prefix) = match value {
+        0..=0x3F => (1, 0x00),
+        0x40..=0x3FFF => (2, 0x40),
+        0x4000..=0x3FFF_FFFF => (4, 0x80),
+        0x4000_0000..=0x3FFF_FFFF_FFFF_FFFF => (8, 0xC0),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is too large for a varint", value),
+            ))
+        }
+    };
+
+    let bytes = value.to_be_bytes();
+    v.push(bytes[8 - length] | prefix);
+    v.extend_from_slice(&bytes[9 - length..]);
+    Ok(length)
+}
+
+fn varint_len(value: u64) -> usize {
+    match value {
+        0..=0x3F => 1,
+        0x40..=0x3FFF => 2,
+        0x4000..=0x3FFF_FFFF => 4,
+        _ => 8,
+    }
+}
+
+// transport parameters: https://datatracker.ietf.org/doc/html/rfc9000#section-18.2
+pub const ORIGINAL_DESTINATION_CONNECTION_ID: u64 = 0x00;
+pub const MAX_IDLE_TIMEOUT: u64 = 0x01;
+pub const STATELESS_RESET_TOKEN: u64 = 0x02;
+pub const MAX_UDP_PAYLOAD_SIZE: u64 = 0x03;
+pub const INITIAL_MAX_DATA: u64 = 0x04;
+pub const INITIAL_MAX_STREAM_DATA_BIDI_LOCAL: u64 = 0x05;
+pub const INITIAL_MAX_STREAM_DATA_BIDI_REMOTE: u64 = 0x06;
+pub const INITIAL_MAX_STREAM_DATA_UNI: u64 = 0x07;
+pub const INITIAL_MAX_STREAMS_BIDI: u64 = 0x08;
+pub const INITIAL_MAX_STREAMS_UNI: u64 = 0x09;
+pub const ACK_DELAY_EXPONENT: u64 = 0x0A;
+pub const MAX_ACK_DELAY: u64 = 0x0B;
+pub const DISABLE_ACTIVE_MIGRATION: u64 = 0x0C;
+pub const PREFERRED_ADDRESS: u64 = 0x0D;
+pub const ACTIVE_CONNECTION_ID_LIMIT: u64 = 0x0E;
+pub const INITIAL_SOURCE_CONNECTION_ID: u64 = 0x0F;
+pub const RETRY_SOURCE_CONNECTION_ID: u64 = 0x10;
+
+pub fn transport_parameter_name(id: u64) -> String {
+    let name = match id {
+        ORIGINAL_DESTINATION_CONNECTION_ID => "original_destination_connection_id",
+        MAX_IDLE_TIMEOUT => "max_idle_timeout",
+        STATELESS_RESET_TOKEN => "stateless_reset_token",
+        MAX_UDP_PAYLOAD_SIZE => "max_udp_payload_size",
+        INITIAL_MAX_DATA => "initial_max_data",
+        INITIAL_MAX_STREAM_DATA_BIDI_LOCAL => "initial_max_stream_data_bidi_local",
+        INITIAL_MAX_STREAM_DATA_BIDI_REMOTE => "initial_max_stream_data_bidi_remote",
+        INITIAL_MAX_STREAM_DATA_UNI => "initial_max_stream_data_uni",
+        INITIAL_MAX_STREAMS_BIDI => "initial_max_streams_bidi",
+        INITIAL_MAX_STREAMS_UNI => "initial_max_streams_uni",
+        ACK_DELAY_EXPONENT => "ack_delay_exponent",
+        MAX_ACK_DELAY => "max_ack_delay",
+        DISABLE_ACTIVE_MIGRATION => "disable_active_migration",
+        PREFERRED_ADDRESS => "preferred_address",
+        ACTIVE_CONNECTION_ID_LIMIT => "active_connection_id_limit",
+        INITIAL_SOURCE_CONNECTION_ID => "initial_source_connection_id",
+        RETRY_SOURCE_CONNECTION_ID => "retry_source_connection_id",
+        // reserved ids, like GREASE: https://datatracker.ietf.org/doc/html/rfc9000#section-18.1
+        _ if id % 31 == 27 => "reserved",
+        _ => "unknown",
+    };
+    format!("{}({})", name, id)
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TransportParameter {
+    pub id: u64,
+    pub value: Vec<u8>,
+}
+
+impl TransportParameter {
+    pub fn new(id: u64, value: &[u8]) -> Self {
+        Self {
+            id,
+            value: value.to_vec(),
+        }
+    }
+
+    // most parameters are a single varint
+    pub fn integer(id: u64, value: u64) -> std::io::Result<Self> {
+        let mut encoded = Vec::new();
+        write_varint(value, &mut encoded)?;
+        Ok(Self::new(id, &encoded))
+    }
+
+    // None if the value isn't exactly one varint
+    pub fn as_integer(&self) -> Option<u64> {
+        let mut v = std::io::Cursor::new(self.value.clone());
+        let value = read_varint(&mut v).ok()?;
+        (v.position() as usize == self.value.len()).then_some(value)
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(TransportParameter);
+
+impl TlsDerive for TransportParameter {
+    fn tls_len(&self) -> usize {
+        varint_len(self.id) + varint_len(self.value.len() as u64) + self.value.len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = write_varint(self.id, v)?;
+        length += write_varint(self.value.len() as u64, v)?;
+        v.extend_from_slice(&self.value);
+        Ok(length + self.value.len())
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        use std::io::Read;
+
+        self.id = read_varint(v)?;
+        let length = read_varint(v)?;
+        if length > (v.get_ref().len() as u64).saturating_sub(v.position()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "truncated transport parameter",
+            ));
+        }
+        self.value = vec![0u8; length as usize];
+        v.read_exact(&mut self.value)
+    }
+}
+
+// quic_transport_parameters extension: https://datatracker.ietf.org/doc/html/rfc9001#section-8.2.
+// Parameters follow each other up to the end of the extension, without a length prefix
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct QuicTransportParameters {
+    pub parameters: Vec<TransportParameter>,
+}
+
+impl QuicTransportParameters {
+    pub fn new(parameters: Vec<TransportParameter>) -> Self {
+        Self { parameters }
+    }
+
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut extension = Self::default();
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&TransportParameter> {
+        self.parameters.iter().find(|x| x.id == id)
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(QuicTransportParameters);
+
+impl TlsDerive for QuicTransportParameters {
+    fn tls_len(&self) -> usize {
+        self.parameters.iter().map(|x| x.tls_len()).sum()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = 0;
+        for parameter in &self.parameters {
+            length += parameter.to_network_bytes(v)?;
+        }
+        Ok(length)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        self.parameters.clear();
+        while (v.position() as usize) < v.get_ref().len() {
+            let mut parameter = TransportParameter::default();
+            parameter.from_network_bytes(v)?;
+            self.parameters.push(parameter);
+        }
+        Ok(())
+    }
+}
+
+ext_type!(
+    QuicTransportParameters,
+    quic_transport_parameters,
+    |data, _| { QuicTransportParameters::from_extension_data(data) }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn varints() {
+        // examples of https://datatracker.ietf.org/doc/html/rfc9000#appendix-A.1
+        for (value, encoded) in [
+            (
+                151_288_809_941_952_652,
+                &[0xC2, 0x19, 0x7C, 0x5E, 0xFF, 0x14, 0xE8, 0x8C][..],
+            ),
+            (494_878_333, &[0x9D, 0x7F, 0x3E, 0x7D][..]),
+            (15_293, &[0x7B, 0xBD][..]),
+            (37, &[0x25][..]),
+        ] {
+            let mut v = Vec::new();
+            assert_eq!(write_varint(value, &mut v).unwrap(), encoded.len());
+            assert_eq!(v, encoded);
+            assert_eq!(varint_len(value), encoded.len());
+            assert_eq!(read_varint(&mut std::io::Cursor::new(v)).unwrap(), value);
+        }
+
+        // not the shortest form, still valid
+        let mut v = std::io::Cursor::new(vec![0x40, 0x25]);
+        assert_eq!(read_varint(&mut v).unwrap(), 37);
+        assert!(write_varint(1 << 62, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn quic_transport_parameters() {
+        let parameters = QuicTransportParameters::new(vec![
+            TransportParameter::integer(INITIAL_MAX_DATA, 1_048_576).unwrap(),
+            TransportParameter::new(DISABLE_ACTIVE_MIGRATION, &[]),
+            TransportParameter::new(INITIAL_SOURCE_CONNECTION_ID, &[0xAB; 8]),
+        ]);
+        let ext = GenericExtension::from_extension(&parameters).unwrap();
+        assert_eq!(
+            ext.extension_type(),
+            ExtensionType::quic_transport_parameters
+        );
+        assert_eq!(
+            ext.extension_data()[..8],
+            [0x04, 0x04, 0x80, 0x10, 0x00, 0x00, 0x0C, 0x00]
+        );
+        assert_eq!(ext.extension_data().len(), parameters.tls_len());
+
+        let parsed = QuicTransportParameters::from_extension_data(ext.extension_data()).unwrap();
+        assert_eq!(parsed, parameters);
+        assert_eq!(
+            parsed.get(INITIAL_MAX_DATA).unwrap().as_integer(),
+            Some(1_048_576)
+        );
+        assert_eq!(
+            parsed
+                .get(INITIAL_SOURCE_CONNECTION_ID)
+                .unwrap()
+                .as_integer(),
+            None
+        );
+        assert!(parsed.get(MAX_IDLE_TIMEOUT).is_none());
+
+        assert_eq!(transport_parameter_name(4), "initial_max_data(4)");
+        assert_eq!(transport_parameter_name(27 + 31 * 2), "reserved(89)");
+
+        // value longer than the extension
+        assert!(QuicTransportParameters::from_extension_data(&[0x04, 0x04, 0x80]).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u16)]
+pub enum SignatureScheme {
+    // RSASSA-PKCS1-v1_5 algorithms
+    rsa_pkcs1_sha256 = 0x0401,
+    rsa_pkcs1_sha384 = 0x0501,
+    rsa_pkcs1_sha512 = 0x0601,
+
+    // ECDSA algorithms
+    ecdsa_secp256r1_sha256 = 0x0403,
+    ecdsa_secp384r1_sha384 = 0x0503,
+    ecdsa_secp521r1_sha512 = 0x0603,
+
+    // RSASSA-PSS algorithms with public key OID rsaEncryption
+    rsa_pss_rsae_sha256 = 0x0804,
+    rsa_pss_rsae_sha384 = 0x0805,
+    rsa_pss_rsae_sha512 = 0x0806,
+
+    // EdDSA algorithms
+    ed25519 = 0x0807,
+    ed448 = 0x0808,
+
+    // RSASSA-PSS algorithms with public key OID RSASSA-PSS
+    rsa_pss_pss_sha256 = 0x0809,
+    rsa_pss_pss_sha384 = 0x080A,
+    rsa_pss_pss_sha512 = 0x080B,
+
+    // legacy algorithms
+    rsa_pkcs1_sha1 = 0x0201,
+    ecdsa_sha1 = 0x0203,
+}
+
+// signature_algorithms extension
+#[derive(Debug, Default, TlsDerive)]
+pub struct SignatureAlgorithms {
+    supported_signature_algorithms: VariableLengthVector<SignatureScheme, 2, 2>,
+}
+
+impl SignatureAlgorithms {
+    pub fn new(schemes: &[SignatureScheme]) -> Self {
+        Self {
+            supported_signature_algorithms: VariableLengthVector::from_slice(schemes),
+        }
+    }
+
+    pub fn schemes(&self) -> &[SignatureScheme] {
+        &self.supported_signature_algorithms.data
+    }
+}
+
+ext_type!(SignatureAlgorithms, signature_algorithms);
+
+// signature_algorithms_cert extension: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3.
+// Same layout as signature_algorithms, but restricts the signatures in certificates, while
+// signature_algorithms then only applies to CertificateVerify
+#[derive(Debug, Default, TlsDerive)]
+pub struct SignatureAlgorithmsCert {
+    supported_signature_algorithms: VariableLengthVector<SignatureScheme, 2, 2>,
+}
+
+impl SignatureAlgorithmsCert {
+    pub fn new(schemes: &[SignatureScheme]) -> Self {
+        Self {
+            supported_signature_algorithms: VariableLengthVector::from_slice(schemes),
+        }
+    }
+
+    pub fn schemes(&self) -> &[SignatureScheme] {
+        &self.supported_signature_algorithms.data
+    }
+}
+
+ext_type!(SignatureAlgorithmsCert, signature_algorithms_cert);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+
+    #[test]
+    fn signature_algorithms() {
+        let algorithms = SignatureAlgorithms::new(&[
+            SignatureScheme::ecdsa_secp256r1_sha256,
+            SignatureScheme::rsa_pss_rsae_sha256,
+            SignatureScheme::rsa_pkcs1_sha256,
+        ]);
+
+        let ext = GenericExtension::from_extension(&algorithms).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::signature_algorithms);
+        assert_eq!(
+            ext.extension_data(),
+            &[0x00, 0x06, 0x04, 0x03, 0x08, 0x04, 0x04, 0x01]
+        );
+
+        let mut parsed = SignatureAlgorithms::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(ext.extension_data().to_vec()))
+            .unwrap();
+        assert_eq!(parsed.schemes(), algorithms.schemes());
+        assert_eq!(
+            SignatureScheme::try_from(0x0807u16),
+            Ok(SignatureScheme::ed25519)
+        );
+    }
+
+    #[test]
+    fn signature_algorithms_cert() {
+        let algorithms = SignatureAlgorithmsCert::new(&[
+            SignatureScheme::rsa_pkcs1_sha256,
+            SignatureScheme::rsa_pkcs1_sha1,
+        ]);
+
+        let ext = GenericExtension::from_extension(&algorithms).unwrap();
+        assert_eq!(
+            ext.extension_type(),
+            ExtensionType::signature_algorithms_cert
+        );
+        assert_eq!(ext.extension_data(), &[0x00, 0x04, 0x04, 0x01, 0x02, 0x01]);
+        assert_eq!(extension_name(50), "signature_algorithms_cert(50)");
+
+        let mut parsed = SignatureAlgorithmsCert::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(ext.extension_data().to_vec()))
+            .unwrap();
+        assert_eq!(parsed.schemes(), algorithms.schemes());
+    }
+}
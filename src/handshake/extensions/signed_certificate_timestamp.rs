@@ -0,0 +1,140 @@
+// certificate transparency: https://datatracker.ietf.org/doc/html/rfc6962#section-3.2
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use crate::handshake::extensions::signature_algorithms::SignatureScheme;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum SctVersion {
+    v1 = 0,
+}
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct SignedCertificateTimestamp {
+    pub version: SctVersion,
+
+    // SHA-256 hash of the log's public key
+    pub log_id: [u8; 32],
+
+    // milliseconds since the epoch
+    pub timestamp: u64,
+    pub extensions: VariableLengthVector<u8, 0, 2>,
+    pub algorithm: SignatureScheme,
+    pub signature: VariableLengthVector<u8, 0, 2>,
+}
+
+impl SignedCertificateTimestamp {
+    pub fn time(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_millis(self.timestamp)
+    }
+}
+
+// each SCT is serialized in its own length-prefixed vector: https://datatracker.ietf.org/doc/html/rfc6962#section-3.3
+pub type SerializedSct = VariableLengthVector<u8, 1, 2>;
+
+// signed_certificate_timestamp extension. Empty in the ClientHello, the server answers
+// with the list of SCTs for its certificate
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct SignedCertificateTimestampList {
+    pub sct_list: VariableLengthVector<SerializedSct, 1, 2>,
+}
+
+impl SignedCertificateTimestampList {
+    pub fn new(scts: &[SignedCertificateTimestamp]) -> std::io::Result<Self> {
+        let mut sct_list = VariableLengthVector::default();
+        for sct in scts {
+            let mut v = Vec::new();
+            sct.to_network_bytes(&mut v)?;
+
+            let serialized: SerializedSct = VariableLengthVector::from_slice(&v);
+            sct_list.length += serialized.tls_len() as u32;
+            sct_list.data.push(serialized);
+        }
+        Ok(Self { sct_list })
+    }
+
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut list = Self::default();
+        list.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(list)
+    }
+
+    // decode each serialized SCT
+    pub fn scts(&self) -> std::io::Result<Vec<SignedCertificateTimestamp>> {
+        self.sct_list
+            .data
+            .iter()
+            .map(|serialized| {
+                let mut sct = SignedCertificateTimestamp::default();
+                let mut cursor = std::io::Cursor::new(serialized.data.clone());
+                sct.from_network_bytes(&mut cursor)?;
+                if cursor.position() as usize != serialized.data.len() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "trailing data after SCT",
+                    ));
+                }
+                Ok(sct)
+            })
+            .collect()
+    }
+}
+
+ext_type!(
+    SignedCertificateTimestampList,
+    signed_certificate_timestamp,
+    |data, _| SignedCertificateTimestampList::from_extension_data(data)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn signed_certificate_timestamp() {
+        let sct = SignedCertificateTimestamp {
+            version: SctVersion::v1,
+            log_id: [0xAB; 32],
+            timestamp: 1_600_000_000_000,
+            extensions: VariableLengthVector::default(),
+            algorithm: SignatureScheme::ecdsa_secp256r1_sha256,
+            signature: VariableLengthVector::from_slice(&[0x30, 0x01, 0x02]),
+        };
+        let list = SignedCertificateTimestampList::new(&[sct.clone(), sct]).unwrap();
+
+        let ext = GenericExtension::from_extension(&list).unwrap();
+        assert_eq!(
+            ext.extension_type(),
+            ExtensionType::signed_certificate_timestamp
+        );
+
+        // SCT is 1 + 32 + 8 + 2 + 2 + 2 + 3 bytes
+        let data = ext.extension_data();
+        assert_eq!(data.len(), 2 + 2 * (2 + 50));
+        assert_eq!(&data[..5], &[0x00, 0x68, 0x00, 0x32, 0x00]);
+
+        let parsed = SignedCertificateTimestampList::from_extension_data(data).unwrap();
+        let scts = parsed.scts().unwrap();
+        assert_eq!(scts.len(), 2);
+        assert_eq!(scts[1].version, SctVersion::v1);
+        assert_eq!(scts[1].log_id, [0xAB; 32]);
+        assert_eq!(
+            scts[1].time(),
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000)
+        );
+        assert_eq!(scts[1].algorithm, SignatureScheme::ecdsa_secp256r1_sha256);
+        assert_eq!(scts[1].signature.data, &[0x30, 0x01, 0x02]);
+
+        // a serialized SCT longer than its content
+        let mut data = data.to_vec();
+        data[1] += 1;
+        data[3] += 1;
+        data.insert(2 + 2 + 50, 0x00);
+        let parsed = SignedCertificateTimestampList::from_extension_data(&data).unwrap();
+        assert!(parsed.scts().is_err());
+    }
+}
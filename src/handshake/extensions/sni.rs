@@ -1,15 +1,339 @@
-// server name indication: RFC 6066
+// SNI extension: https://datatracker.ietf.org/doc/html/rfc6066#section-3
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
 use crate::handshake::common::VariableLengthVector;
-use crate::tls_derive::TlsDerive;
-use tls_derive::{TlsDerive};
+use crate::handshake::extensions::decode;
+use tls_derive::TlsDerive;
 
-#[derive(Debug, Default, TlsDerive)]
+#[derive(Debug, Default, Clone, TlsDerive)]
 pub struct ServerName {
     name_type: u8,
-    name: VariableLengthVector<u16, u8, 1>,
+    name: VariableLengthVector<u8, 1, 2>,
 }
 
-#[derive(Debug, Default, TlsDerive)]
+impl ServerName {
+    // host_name is the only name type defined
+    pub const HOST_NAME: u8 = 0;
+
+    pub fn host_name(host_name: &str) -> Self {
+        Self {
+            name_type: Self::HOST_NAME,
+            name: VariableLengthVector::from_slice(host_name.as_bytes()),
+        }
+    }
+
+    pub fn name_type(&self) -> u8 {
+        self.name_type
+    }
+
+    pub fn name(&self) -> &[u8] {
+        &self.name.data
+    }
+}
+
+#[derive(Debug, Default, Clone, TlsDerive)]
 pub struct ServerNameList {
-    server_name_list: VariableLengthVector<u16, ServerName, 1>,
+    server_name_list: VariableLengthVector<ServerName, 1, 2>,
+}
+
+impl ServerNameList {
+    pub fn new(host_name: &str) -> Self {
+        Self::from_names(vec![ServerName::host_name(host_name)])
+    }
+
+    // SNI for a host given by a user: None for IP literals, which get no SNI, and an error for
+    // names which can't be sent
+    pub fn for_host(host: &str) -> std::io::Result<Option<Self>> {
+        if is_ip_literal(host) {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(&to_ascii_host(host)?)))
+    }
+
+    pub fn from_names(names: Vec<ServerName>) -> Self {
+        let mut server_name_list = VariableLengthVector {
+            length: 0,
+            data: names,
+        };
+        server_name_list.fix_length();
+        Self { server_name_list }
+    }
+
+    // the list can't be empty, nor hold 2 names of the same type
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let list: Self = decode(data)?;
+        let names = list.names();
+
+        let invalid = |msg: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid server_name: {}", msg),
+            )
+        };
+        if names.is_empty() {
+            return Err(invalid("empty list"));
+        }
+        if (1..names.len()).any(|i| names[..i].iter().any(|x| x.name_type == names[i].name_type)) {
+            return Err(invalid("duplicate name type"));
+        }
+
+        Ok(list)
+    }
+
+    pub fn names(&self) -> &[ServerName] {
+        &self.server_name_list.data
+    }
+
+    // empty if there's no host_name entry
+    pub fn host_name(&self) -> String {
+        self.names()
+            .iter()
+            .find(|x| x.name_type == ServerName::HOST_NAME)
+            .map(|x| String::from_utf8_lossy(x.name()).into_owned())
+            .unwrap_or_default()
+    }
+}
+
+// literal IPv4 and IPv6 addresses aren't permitted in SNI: https://datatracker.ietf.org/doc/html/rfc6066#section-3.
+// IPv6 addresses may be bracketed, or carry a zone
+pub fn is_ip_literal(host: &str) -> bool {
+    let host = host.trim_end_matches('.');
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.strip_suffix(']').unwrap_or(bracketed),
+        None => host,
+    };
+    let host = host.split('%').next().unwrap_or(host);
+    host.parse::<std::net::IpAddr>().is_ok()
+}
+
+// the host name as sent in SNI: lowercase, without the trailing dot of a FQDN, and with Unicode
+// labels converted to A-labels (punycode). Only case folding is done of the IDNA mapping, which
+// covers names typed by users
+pub fn to_ascii_host(host: &str) -> std::io::Result<String> {
+    let invalid = |msg: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid host name {}: {}", host, msg),
+        )
+    };
+
+    // ideographic and fullwidth full stops separate labels too
+    let name = host
+        .replace(['\u{3002}', '\u{FF0E}', '\u{FF61}'], ".")
+        .to_lowercase();
+    let name = name.strip_suffix('.').unwrap_or(&name);
+
+    let mut labels = Vec::new();
+    for label in name.split('.') {
+        let label = if label.is_ascii() {
+            label.to_string()
+        } else {
+            let chars: Vec<char> = label.chars().collect();
+            format!(
+                "xn--{}",
+                punycode(&chars).ok_or_else(|| invalid("label too long"))?
+            )
+        };
+
+        // letters, digits and hyphens. Underscores are seen in the wild and accepted
+        if label.is_empty() || label.len() > 63 {
+            return Err(invalid("labels must have 1 to 63 characters"));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(invalid("labels can't start or end with a hyphen"));
+        }
+        if !label
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+        {
+            return Err(invalid("unexpected character"));
+        }
+        labels.push(label);
+    }
+
+    let name = labels.join(".");
+    if name.len() > 253 {
+        return Err(invalid("longer than 253 characters"));
+    }
+    Ok(name)
+}
+
+// punycode parameters: https://datatracker.ietf.org/doc/html/rfc3492#section-5
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+
+// https://datatracker.ietf.org/doc/html/rfc3492#section-6.1
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+
+    let mut k = 0;
+    while delta > ((BASE - TMIN) * TMAX) / 2 {
+        delta /= BASE - TMIN;
+        k += BASE;
+    }
+    k + (BASE - TMIN + 1) * delta / (delta + SKEW)
+}
+
+fn digit(d: u32) -> char {
+    // d < BASE
+    if d < 26 {
+        (b'a' + d as u8) as char
+    } else {
+        (b'0' + (d - 26) as u8) as char
+    }
+}
+
+// encode a label: https://datatracker.ietf.org/doc/html/rfc3492#section-6.3. None on overflow
+fn punycode(input: &[char]) -> Option<String> {
+    let mut output: String = input.iter().filter(|c| c.is_ascii()).collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let (mut n, mut delta, mut bias) = (0x80u32, 0u32, 72u32);
+    let mut handled = basic;
+    while (handled as usize) < input.len() {
+        // the smallest code point not handled yet
+        let m = input.iter().map(|&c| c as u32).filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+
+        for &c in input {
+            let c = c as u32;
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = k.saturating_sub(bias).clamp(TMIN, TMAX);
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+
+    Some(output)
+}
+
+impl From<&str> for ServerNameList {
+    fn from(host_name: &str) -> Self {
+        Self::new(host_name)
+    }
+}
+
+ext_type!(ServerNameList, server_name, |data, _| {
+    ServerNameList::from_extension_data(data)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ClientHello, ExtensionType, GenericExtension};
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn sni() {
+        let sni = ServerNameList::new("example.ulfheim.net");
+        assert_eq!(sni.server_name_list.length, 22);
+        assert_eq!(sni.names().len(), 1);
+        assert_eq!(sni.names()[0].name_type(), ServerName::HOST_NAME);
+        assert_eq!(sni.names()[0].name.length, 19);
+        assert_eq!(
+            sni.names()[0].name(),
+            &[
+                0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x75, 0x6c, 0x66, 0x68, 0x65, 0x69,
+                0x6d, 0x2e, 0x6e, 0x65, 0x74
+            ]
+        );
+
+        let ext = GenericExtension::from_extension(&sni).unwrap();
+
+        assert_eq!(ext.extension_type(), ExtensionType::server_name);
+        assert_eq!(ext.extension_data()[..5], [0x00, 0x16, 0x00, 0x00, 0x13]);
+        assert_eq!(&ext.extension_data()[5..], b"example.ulfheim.net");
+
+        // parsed back from the wire
+        let parsed = ServerNameList::from_extension_data(ext.extension_data()).unwrap();
+        assert_eq!(parsed.host_name(), "example.ulfheim.net");
+        let mut buffer = Vec::new();
+        parsed.to_network_bytes(&mut buffer).unwrap();
+        assert_eq!(buffer, ext.extension_data());
+
+        // a name of another type is kept, but only one of each type is allowed
+        let mut data = ext.extension_data().to_vec();
+        data.extend_from_slice(&[0x01, 0x00, 0x01, b'x']);
+        data[..2].copy_from_slice(&26u16.to_be_bytes());
+        let parsed = ServerNameList::from_extension_data(&data).unwrap();
+        assert_eq!(parsed.names()[1].name(), b"x");
+        data[24] = ServerName::HOST_NAME;
+        assert!(ServerNameList::from_extension_data(&data).is_err());
+        assert!(ServerNameList::from_extension_data(&[0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn host_names() {
+        // https://datatracker.ietf.org/doc/html/rfc3492#section-7.1
+        let chars: Vec<char> = "他们为什么不说中文".chars().collect();
+        assert_eq!(punycode(&chars).unwrap(), "ihqwcrb4cv8a8dqg056pqjye");
+
+        assert_eq!(
+            to_ascii_host("Bücher.Example.").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(
+            to_ascii_host("例え。テスト").unwrap(),
+            "xn--r8jz45g.xn--zckzah"
+        );
+        assert_eq!(
+            to_ascii_host("_dmarc.example.com").unwrap(),
+            "_dmarc.example.com"
+        );
+        assert!(to_ascii_host("a..b").is_err());
+        assert!(to_ascii_host("-a.com").is_err());
+        assert!(to_ascii_host("a b.com").is_err());
+        assert!(to_ascii_host(&"a".repeat(64)).is_err());
+
+        for ip in ["192.0.2.1", "2001:db8::1", "[2001:db8::1]", "fe80::1%eth0"] {
+            assert!(is_ip_literal(ip), "{}", ip);
+            assert!(ServerNameList::for_host(ip).unwrap().is_none());
+        }
+        assert!(!is_ip_literal("1.2.3.example"));
+
+        let list = ServerNameList::for_host("MÜNCHEN.de").unwrap().unwrap();
+        assert_eq!(list.host_name(), "xn--mnchen-3ya.de");
+
+        // the position of SNI is kept, and an IP literal removes it
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256])
+            .with_extension_replaced(GenericExtension::new(ExtensionType::server_name, &[]))
+            .with_extension_replaced(GenericExtension::new(ExtensionType::session_ticket, &[]));
+        let named = ch.with_server_name("bücher.example").unwrap();
+        assert_eq!(
+            named.extensions()[0].extension_data(),
+            GenericExtension::from_extension(&ServerNameList::new("xn--bcher-kva.example"))
+                .unwrap()
+                .extension_data()
+        );
+        assert_eq!(named.extensions().len(), 2);
+        let unnamed = named.with_server_name("192.0.2.1").unwrap();
+        assert_eq!(unnamed.extensions().len(), 1);
+        assert!(ch.with_server_name("a..b").is_err());
+    }
 }
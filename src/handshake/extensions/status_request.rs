@@ -0,0 +1,85 @@
+// https://datatracker.ietf.org/doc/html/rfc6066#section-8
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum CertificateStatusType {
+    ocsp = 1,
+}
+
+// DER-encoded OCSP ResponderID
+pub type ResponderID = VariableLengthVector<u8, 1, 2>;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct OCSPStatusRequest {
+    pub responder_id_list: VariableLengthVector<ResponderID, 0, 2>,
+
+    // DER-encoded OCSP request extensions
+    pub request_extensions: VariableLengthVector<u8, 0, 2>,
+}
+
+// status_request extension, asking the server to staple an OCSP response. The server
+// answers with an empty extension
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct CertificateStatusRequest {
+    pub status_type: CertificateStatusType,
+    pub request: OCSPStatusRequest,
+}
+
+impl CertificateStatusRequest {
+    pub fn new(responder_ids: &[&[u8]], request_extensions: &[u8]) -> Self {
+        let mut responder_id_list = VariableLengthVector::default();
+        for id in responder_ids {
+            let id: ResponderID = VariableLengthVector::from_slice(id);
+            responder_id_list.length += id.tls_len() as u32;
+            responder_id_list.data.push(id);
+        }
+
+        Self {
+            status_type: CertificateStatusType::ocsp,
+            request: OCSPStatusRequest {
+                responder_id_list,
+                request_extensions: VariableLengthVector::from_slice(request_extensions),
+            },
+        }
+    }
+
+    // no known responder and no extension, as sent by browsers
+    pub fn ocsp() -> Self {
+        Self::new(&[], &[])
+    }
+}
+
+ext_type!(CertificateStatusRequest, status_request);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn status_request() {
+        let ext = GenericExtension::from_extension(&CertificateStatusRequest::ocsp()).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::status_request);
+        assert_eq!(ext.extension_data(), &[0x01, 0x00, 0x00, 0x00, 0x00]);
+
+        let request = CertificateStatusRequest::new(&[&[0xA1, 0x02], &[0xA2]], &[0x30, 0x00]);
+        let ext = GenericExtension::from_extension(&request).unwrap();
+        assert_eq!(
+            ext.extension_data(),
+            &[0x01, 0x00, 0x07, 0x00, 0x02, 0xA1, 0x02, 0x00, 0x01, 0xA2, 0x00, 0x02, 0x30, 0x00]
+        );
+
+        let mut parsed = CertificateStatusRequest::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(ext.extension_data().to_vec()))
+            .unwrap();
+        assert_eq!(parsed.status_type, CertificateStatusType::ocsp);
+        assert_eq!(parsed.request.responder_id_list.data.len(), 2);
+        assert_eq!(parsed.request.request_extensions.data, &[0x30, 0x00]);
+    }
+}
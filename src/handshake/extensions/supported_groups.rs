@@ -0,0 +1,77 @@
+// groups for key exchange: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.7
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u16)]
+pub enum NamedGroup {
+    // elliptic curve groups
+    secp256r1 = 23,
+    secp384r1 = 24,
+    secp521r1 = 25,
+    x25519 = 29,
+    x448 = 30,
+
+    // finite field groups
+    ffdhe2048 = 256,
+    ffdhe3072 = 257,
+    ffdhe4096 = 258,
+    ffdhe6144 = 259,
+    ffdhe8192 = 260,
+
+    // hybrid post-quantum group
+    x25519_mlkem768 = 4588,
+}
+
+// supported_groups extension
+#[derive(Debug, Default, TlsDerive)]
+pub struct SupportedGroups {
+    named_group_list: VariableLengthVector<NamedGroup, 2, 2>,
+}
+
+impl SupportedGroups {
+    pub fn new(groups: &[NamedGroup]) -> Self {
+        Self {
+            named_group_list: VariableLengthVector::from_slice(groups),
+        }
+    }
+
+    pub fn groups(&self) -> &[NamedGroup] {
+        &self.named_group_list.data
+    }
+}
+
+ext_type!(SupportedGroups, supported_groups);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+
+    #[test]
+    fn supported_groups() {
+        let groups = SupportedGroups::new(&[
+            NamedGroup::x25519,
+            NamedGroup::secp256r1,
+            NamedGroup::ffdhe2048,
+        ]);
+        assert_eq!(groups.tls_len(), 8);
+
+        let ext = GenericExtension::from_extension(&groups).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::supported_groups);
+        assert_eq!(
+            ext.extension_data(),
+            &[0x00, 0x06, 0x00, 0x1D, 0x00, 0x17, 0x01, 0x00]
+        );
+
+        let mut parsed = SupportedGroups::default();
+        parsed
+            .from_network_bytes(&mut std::io::Cursor::new(ext.extension_data().to_vec()))
+            .unwrap();
+        assert_eq!(parsed.groups(), groups.groups());
+        assert_eq!(NamedGroup::ffdhe2048.to_string(), "ffdhe2048(256)");
+    }
+}
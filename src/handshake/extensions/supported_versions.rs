@@ -0,0 +1,121 @@
+// supported_versions extension: https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.1
+// its layout depends on the message carrying it, so parsing uses the current variant
+// as the expected form
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::{ProtocolVersion, VariableLengthVector};
+use crate::handshake::extensions::ExtensionContext;
+
+#[derive(Debug, Clone)]
+pub enum SupportedVersions {
+    // ProtocolVersion versions<2..254> in a ClientHello
+    ClientHello(VariableLengthVector<ProtocolVersion, 2, 1>),
+
+    // ProtocolVersion selected_version in a ServerHello or HelloRetryRequest
+    ServerHello(ProtocolVersion),
+}
+
+impl Default for SupportedVersions {
+    fn default() -> Self {
+        Self::ClientHello(VariableLengthVector::default())
+    }
+}
+
+impl SupportedVersions {
+    pub fn new(versions: &[ProtocolVersion]) -> Self {
+        Self::ClientHello(VariableLengthVector::from_slice(versions))
+    }
+
+    pub fn selected(version: ProtocolVersion) -> Self {
+        Self::ServerHello(version)
+    }
+
+    // parse the extension data in the form used by the handshake message
+    pub fn from_extension_data(data: &[u8], context: ExtensionContext) -> std::io::Result<Self> {
+        let mut extension = match context {
+            ExtensionContext::ClientHello => Self::default(),
+            _ => Self::ServerHello(ProtocolVersion::default()),
+        };
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    // offered versions, or the selected one
+    pub fn versions(&self) -> &[ProtocolVersion] {
+        match self {
+            Self::ClientHello(versions) => &versions.data,
+            Self::ServerHello(version) => std::slice::from_ref(version),
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(SupportedVersions);
+
+impl TlsDerive for SupportedVersions {
+    fn tls_len(&self) -> usize {
+        match self {
+            Self::ClientHello(versions) => versions.tls_len(),
+            Self::ServerHello(version) => version.tls_len(),
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        match self {
+            Self::ClientHello(versions) => versions.to_network_bytes(v),
+            Self::ServerHello(version) => version.to_network_bytes(v),
+        }
+    }
+
+    fn from_network_bytes(&mut self, v: &mut std::io::Cursor<Vec<u8>>) -> std::io::Result<()> {
+        match self {
+            Self::ClientHello(versions) => versions.from_network_bytes(v),
+            Self::ServerHello(version) => version.from_network_bytes(v),
+        }
+    }
+}
+
+ext_type!(
+    SupportedVersions,
+    supported_versions,
+    SupportedVersions::from_extension_data
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{ExtensionType, GenericExtension};
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn supported_versions() {
+        let offered = SupportedVersions::new(&[TLS13, TLS12]);
+        let ext = GenericExtension::from_extension(&offered).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::supported_versions);
+        assert_eq!(ext.extension_data(), &[0x04, 0x03, 0x04, 0x03, 0x03]);
+
+        let parsed = SupportedVersions::from_extension_data(
+            ext.extension_data(),
+            ExtensionContext::ClientHello,
+        )
+        .unwrap();
+        assert_eq!(parsed.versions(), offered.versions());
+        assert!(matches!(parsed, SupportedVersions::ClientHello(_)));
+
+        let selected = SupportedVersions::selected(TLS13);
+        let ext = GenericExtension::from_extension(&selected).unwrap();
+        assert_eq!(ext.extension_data(), &[0x03, 0x04]);
+
+        let parsed = SupportedVersions::from_extension_data(
+            ext.extension_data(),
+            ExtensionContext::ServerHello,
+        )
+        .unwrap();
+        assert_eq!(parsed.versions(), &[TLS13]);
+
+        // a version list can't be read as a selected version
+        assert!(
+            SupportedVersions::from_extension_data(&[0x03], ExtensionContext::ServerHello).is_err()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+// truncated_hmac extension: https://datatracker.ietf.org/doc/html/rfc6066#section-7. Its body is
+// empty, the client asks for record MACs truncated to 80 bits, the server agrees by echoing it
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct TruncatedHmac {}
+
+ext_type!(TruncatedHmac, truncated_hmac);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{
+        extension_name, parse_extension, ExtensionPayload, ExtensionType, GenericExtension,
+    };
+
+    #[test]
+    fn truncated_hmac() {
+        let ext = GenericExtension::from_extension(&TruncatedHmac {}).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::truncated_hmac);
+        assert!(ext.extension_data().is_empty());
+        assert_eq!(extension_name(4), "truncated_hmac(4)");
+
+        assert!(matches!(
+            parse_extension(4, &[]).unwrap(),
+            ExtensionPayload::Empty(ExtensionType::truncated_hmac)
+        ));
+        assert!(parse_extension(4, &[0x00]).is_err());
+    }
+}
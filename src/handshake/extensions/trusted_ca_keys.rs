@@ -0,0 +1,172 @@
+// trusted_ca_keys extension: https://datatracker.ietf.org/doc/html/rfc6066#section-6. The client
+// lists the CA keys it has, so a server with several chains can send one they lead to. The
+// server acknowledges with an empty extension
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum IdentifierType {
+    pre_agreed = 0,
+    key_sha1_hash = 1,
+    x509_name = 2,
+    cert_sha1_hash = 3,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TrustedAuthority {
+    pub identifier_type: IdentifierType,
+
+    // empty for pre_agreed, a SHA-1 hash, or a DER-encoded DistinguishedName for x509_name
+    pub identifier: Vec<u8>,
+}
+
+impl TrustedAuthority {
+    pub fn pre_agreed() -> Self {
+        Self::default()
+    }
+
+    // SHA-1 hash of the CA's public key
+    pub fn key_sha1_hash(hash: [u8; 20]) -> Self {
+        Self {
+            identifier_type: IdentifierType::key_sha1_hash,
+            identifier: hash.to_vec(),
+        }
+    }
+
+    pub fn x509_name(name: &[u8]) -> Self {
+        Self {
+            identifier_type: IdentifierType::x509_name,
+            identifier: name.to_vec(),
+        }
+    }
+
+    // SHA-1 hash of the DER-encoded CA certificate
+    pub fn cert_sha1_hash(hash: [u8; 20]) -> Self {
+        Self {
+            identifier_type: IdentifierType::cert_sha1_hash,
+            identifier: hash.to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(TrustedAuthority);
+
+// not derived because the identifier depends on its type
+impl TlsDerive for TrustedAuthority {
+    fn tls_len(&self) -> usize {
+        match self.identifier_type {
+            IdentifierType::pre_agreed => 1,
+            IdentifierType::x509_name => 1 + 2 + self.identifier.len(),
+            _ => 1 + 20,
+        }
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        let mut length = self.identifier_type.to_network_bytes(v)?;
+        match self.identifier_type {
+            IdentifierType::pre_agreed => (),
+            IdentifierType::x509_name => {
+                length += VariableLengthVector::<u8, 1, 2>::from_slice(&self.identifier)
+                    .to_network_bytes(v)?;
+            }
+            _ => {
+                if self.identifier.len() != 20 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "SHA-1 hash is not 20 bytes",
+                    ));
+                }
+                v.extend_from_slice(&self.identifier);
+                length += 20;
+            }
+        }
+        Ok(length)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        self.identifier_type.from_network_bytes(v)?;
+        self.identifier = match self.identifier_type {
+            IdentifierType::pre_agreed => Vec::new(),
+            IdentifierType::x509_name => {
+                let mut name = VariableLengthVector::<u8, 1, 2>::default();
+                name.from_network_bytes(v)?;
+                name.data
+            }
+            _ => {
+                let mut hash = vec![0; 20];
+                v.read_exact(&mut hash)?;
+                hash
+            }
+        };
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct TrustedAuthorities {
+    trusted_authorities_list: VariableLengthVector<TrustedAuthority, 0, 2>,
+}
+
+impl TrustedAuthorities {
+    pub fn new(authorities: &[TrustedAuthority]) -> Self {
+        let mut trusted_authorities_list = VariableLengthVector {
+            length: 0,
+            data: authorities.to_vec(),
+        };
+        trusted_authorities_list.fix_length();
+        Self {
+            trusted_authorities_list,
+        }
+    }
+
+    pub fn authorities(&self) -> &[TrustedAuthority] {
+        &self.trusted_authorities_list.data
+    }
+}
+
+ext_type!(TrustedAuthorities, trusted_ca_keys);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{parse_extension, ExtensionPayload, GenericExtension};
+    use crate::handshake::extensions::{Extension, ExtensionContext};
+
+    #[test]
+    fn trusted_ca_keys() {
+        let authorities = TrustedAuthorities::new(&[
+            TrustedAuthority::pre_agreed(),
+            TrustedAuthority::key_sha1_hash([0xAA; 20]),
+            TrustedAuthority::x509_name(&[0x30, 0x00]),
+        ]);
+        let ext = GenericExtension::from_extension(&authorities).unwrap();
+        let data = ext.extension_data();
+        assert_eq!(data.len(), 2 + 1 + 21 + 5);
+        assert_eq!(&data[..4], &[0x00, 0x1B, 0x00, 0x01]);
+        assert_eq!(&data[data.len() - 5..], &[0x02, 0x00, 0x02, 0x30, 0x00]);
+
+        match parse_extension(3, data).unwrap() {
+            ExtensionPayload::TrustedCaKeys(parsed) => {
+                assert_eq!(parsed.authorities(), authorities.authorities())
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // an unknown identifier type, and a truncated hash
+        assert!(parse_extension(3, &[0x00, 0x01, 0x04]).is_err());
+        assert!(parse_extension(3, &[0x00, 0x03, 0x01, 0xAA, 0xAA]).is_err());
+
+        // acknowledged with an empty extension
+        assert!(TrustedAuthorities::decode_body(&[], ExtensionContext::ServerHello).is_err());
+        assert!(GenericExtension::new(TrustedAuthorities::TYPE, &[])
+            .parse(ExtensionContext::ServerHello)
+            .is_ok());
+    }
+}
@@ -0,0 +1,89 @@
+// SRTP protection profiles, as DTLS-SRTP negotiates keys for SRTP:
+// https://datatracker.ietf.org/doc/html/rfc5764#section-4.1.2
+use crate::derive_tls::TlsDerive;
+use crate::ext_type;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::{TlsDerive, TlsEnum};
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u16)]
+pub enum SrtpProtectionProfile {
+    SRTP_AES128_CM_HMAC_SHA1_80 = 0x0001,
+    SRTP_AES128_CM_HMAC_SHA1_32 = 0x0002,
+    SRTP_NULL_HMAC_SHA1_80 = 0x0005,
+    SRTP_NULL_HMAC_SHA1_32 = 0x0006,
+
+    // https://datatracker.ietf.org/doc/html/rfc7714#section-14.2
+    SRTP_AEAD_AES_128_GCM = 0x0007,
+    SRTP_AEAD_AES_256_GCM = 0x0008,
+}
+
+// use_srtp extension: the client offers profiles, the server answers with the one selected.
+// The MKI identifies the master key, it's usually empty
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct UseSrtp {
+    protection_profiles: VariableLengthVector<SrtpProtectionProfile, 2, 2>,
+    srtp_mki: VariableLengthVector<u8, 0, 1>,
+}
+
+impl UseSrtp {
+    pub fn new(profiles: &[SrtpProtectionProfile], mki: &[u8]) -> Self {
+        Self {
+            protection_profiles: VariableLengthVector::from_slice(profiles),
+            srtp_mki: VariableLengthVector::from_slice(mki),
+        }
+    }
+
+    pub fn from_extension_data(data: &[u8]) -> std::io::Result<Self> {
+        let mut extension = Self::default();
+        extension.from_network_bytes(&mut std::io::Cursor::new(data.to_vec()))?;
+        Ok(extension)
+    }
+
+    pub fn profiles(&self) -> &[SrtpProtectionProfile] {
+        &self.protection_profiles.data
+    }
+
+    pub fn mki(&self) -> &[u8] {
+        &self.srtp_mki.data
+    }
+}
+
+ext_type!(UseSrtp, use_srtp, |data, _| UseSrtp::from_extension_data(
+    data
+));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::client_hello::{extension_name, ExtensionType, GenericExtension};
+
+    #[test]
+    fn use_srtp() {
+        let offered = UseSrtp::new(
+            &[
+                SrtpProtectionProfile::SRTP_AEAD_AES_128_GCM,
+                SrtpProtectionProfile::SRTP_AES128_CM_HMAC_SHA1_80,
+            ],
+            &[],
+        );
+        let ext = GenericExtension::from_extension(&offered).unwrap();
+        assert_eq!(ext.extension_type(), ExtensionType::use_srtp);
+        assert_eq!(
+            ext.extension_data(),
+            &[0x00, 0x04, 0x00, 0x07, 0x00, 0x01, 0x00]
+        );
+        assert_eq!(extension_name(14), "use_srtp(14)");
+
+        // the server selects a single profile, with an MKI
+        let data = [0x00, 0x02, 0x00, 0x08, 0x02, 0xAA, 0xBB];
+        let selected = UseSrtp::from_extension_data(&data).unwrap();
+        assert_eq!(
+            selected.profiles(),
+            &[SrtpProtectionProfile::SRTP_AEAD_AES_256_GCM]
+        );
+        assert_eq!(selected.mki(), &[0xAA, 0xBB]);
+        assert!(UseSrtp::from_extension_data(&data[..5]).is_err());
+    }
+}
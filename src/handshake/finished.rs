@@ -0,0 +1,209 @@
+// Finished message, whose verify_data is a MAC of the transcript under a key only the peers
+// share: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.9 for TLS 1.2 and
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.4 for TLS 1.3
+use std::io::{Cursor, Read, Result};
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::hkdf::hkdf_expand_label;
+use crate::crypto::hmac::hmac;
+use crate::crypto::prf::prf;
+use crate::derive_tls::TlsDerive;
+use crate::handshake::transcript::Transcript;
+
+// the length of verify_data in TLS 1.2, it's the hash length in TLS 1.3
+pub const VERIFY_DATA_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sender {
+    Client,
+    Server,
+}
+
+// what verify_data is derived from. The hash is the one of the transcript
+#[derive(Clone, Copy)]
+pub enum FinishedSecret<'a> {
+    // the master secret, and the side sending the message
+    Tls12 {
+        master_secret: &'a [u8],
+        sender: Sender,
+    },
+
+    // the handshake traffic secret of the sender
+    Tls13 {
+        traffic_secret: &'a [u8],
+    },
+}
+
+// the secrets are not shown
+impl std::fmt::Debug for FinishedSecret<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tls12 { sender, .. } => f
+                .debug_struct("Tls12")
+                .field("sender", sender)
+                .finish_non_exhaustive(),
+            Self::Tls13 { .. } => f.debug_struct("Tls13").finish_non_exhaustive(),
+        }
+    }
+}
+
+// the body is verify_data alone, whose length depends on the version: all the bytes are read
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Finished {
+    pub verify_data: Vec<u8>,
+}
+
+impl Finished {
+    // the transcript has the handshake messages up to, but not including, this Finished
+    pub fn compute(secret: FinishedSecret, transcript: &Transcript) -> Self {
+        let hash = transcript.hash_algorithm();
+        let verify_data = match secret {
+            FinishedSecret::Tls12 {
+                master_secret,
+                sender,
+            } => {
+                let label = match sender {
+                    Sender::Client => "client finished",
+                    Sender::Server => "server finished",
+                };
+                prf(
+                    hash,
+                    master_secret,
+                    label,
+                    &transcript.current_hash(),
+                    VERIFY_DATA_LENGTH,
+                )
+            }
+            FinishedSecret::Tls13 { traffic_secret } => {
+                let finished_key =
+                    hkdf_expand_label(hash, traffic_secret, "finished", &[], hash.output_len());
+                hmac(hash, &finished_key, &transcript.current_hash())
+            }
+        };
+        Self { verify_data }
+    }
+
+    // compared in constant time, a mismatch is a decrypt_error:
+    // https://datatracker.ietf.org/doc/html/rfc8446#section-6.2
+    pub fn verify(
+        &self,
+        secret: FinishedSecret,
+        transcript: &Transcript,
+    ) -> std::result::Result<(), AlertDescription> {
+        let expected = Self::compute(secret, transcript).verify_data;
+        let equal = expected.len() == self.verify_data.len()
+            && expected
+                .iter()
+                .zip(&self.verify_data)
+                .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+                == 0;
+        if equal {
+            Ok(())
+        } else {
+            Err(AlertDescription::decrypt_error)
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(Finished);
+
+impl TlsDerive for Finished {
+    fn tls_len(&self) -> usize {
+        self.verify_data.len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        v.extend_from_slice(&self.verify_data);
+        Ok(self.verify_data.len())
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        self.verify_data.clear();
+        v.read_to_end(&mut self.verify_data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::sha2::HashAlgorithm;
+    use crate::crypto::to_hex;
+    use crate::handshake::handshake::{Handshake, HandshakeType};
+
+    fn transcript(hash: HashAlgorithm) -> Transcript {
+        let mut transcript = Transcript::new(hash);
+        transcript.add(&(0..100).collect::<Vec<u8>>());
+        transcript
+    }
+
+    #[test]
+    fn tls12() {
+        let master_secret = [0x42; 48];
+        let client = FinishedSecret::Tls12 {
+            master_secret: &master_secret,
+            sender: Sender::Client,
+        };
+        let server = FinishedSecret::Tls12 {
+            master_secret: &master_secret,
+            sender: Sender::Server,
+        };
+        let transcript = transcript(HashAlgorithm::Sha256);
+
+        let finished = Finished::compute(client, &transcript);
+        assert_eq!(to_hex(&finished.verify_data), "c34352cd661942d75888ddd0");
+        assert_eq!(
+            to_hex(&Finished::compute(server, &transcript).verify_data),
+            "4e813a973137107b614ea5d8"
+        );
+        assert_eq!(
+            to_hex(
+                &Finished::compute(client, &self::transcript(HashAlgorithm::Sha384)).verify_data
+            ),
+            "0e595c09246614faa327840b"
+        );
+
+        // the other side's label, or another master secret
+        assert_eq!(finished.verify(client, &transcript), Ok(()));
+        assert_eq!(
+            finished.verify(server, &transcript),
+            Err(AlertDescription::decrypt_error)
+        );
+        let other = FinishedSecret::Tls12 {
+            master_secret: &[0x43; 48],
+            sender: Sender::Client,
+        };
+        assert!(finished.verify(other, &transcript).is_err());
+        assert_eq!(format!("{:?}", client), "Tls12 { sender: Client, .. }");
+    }
+
+    #[test]
+    fn tls13() {
+        let secret = FinishedSecret::Tls13 {
+            traffic_secret: &[0x55; 32],
+        };
+        let transcript = transcript(HashAlgorithm::Sha256);
+        let finished = Finished::compute(secret, &transcript);
+        assert_eq!(
+            to_hex(&finished.verify_data),
+            "43c6bab15ee1a724d28d4bd6a89f558168a42787d6f29008614db04362627369"
+        );
+
+        // parsed back from a handshake message
+        let mut v = Vec::new();
+        Handshake::from_message(HandshakeType::finished, finished.clone())
+            .to_network_bytes(&mut v)
+            .unwrap();
+        assert_eq!(&v[..4], &[0x14, 0x00, 0x00, 0x20]);
+        let mut parsed = Finished::default();
+        parsed
+            .from_network_bytes(&mut Cursor::new(v[4..].to_vec()))
+            .unwrap();
+        assert_eq!(parsed.verify(secret, &transcript), Ok(()));
+
+        // a truncated verify_data
+        parsed.verify_data.pop();
+        assert!(parsed.verify(secret, &transcript).is_err());
+    }
+}
@@ -8,12 +8,13 @@ use tls_derive::{TlsDerive, TlsEnum};
 
 #[allow(unused_variables)]
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug, TlsEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, TlsEnum)]
 #[repr(u8)]
 pub enum HandshakeType {
     hello_request = 0,
     client_hello = 1,
     server_hello = 2,
+    new_session_ticket = 4,
     certificate = 11,
     server_key_exchange = 12,
     certificate_request = 13,
@@ -23,6 +24,8 @@ pub enum HandshakeType {
     finished = 20,
     certificate_url = 21,
     certificate_status = 22,
+    key_update = 24,
+    message_hash = 254,
     // fake value to use it when creating a default
     fake = 255,
 }
@@ -40,14 +43,40 @@ where
     body: T,
 }
 
+impl<T> Handshake<T>
+where
+    T: Debug + TlsDerive,
+{
+    // wrap a message body with its handshake header
+    pub fn from_message(msg_type: HandshakeType, body: T) -> Self {
+        Self {
+            msg_type,
+            length: to_u24(body.tls_len() as u32),
+            body,
+        }
+    }
+
+    pub fn msg_type(&self) -> HandshakeType {
+        self.msg_type
+    }
+
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+
+    // change the body, the length is then recomputed
+    pub fn update<F: FnOnce(&mut T)>(&mut self, f: F) {
+        f(&mut self.body);
+        self.length = to_u24(self.body.tls_len() as u32);
+    }
+}
+
 impl Handshake<ClientHello> {
     pub fn new(suites: &[CipherSuite]) -> Self {
-        let ch = ClientHello::new(suites);
+        Self::from_client_hello(ClientHello::new(suites))
+    }
 
-        Self {
-            msg_type: HandshakeType::client_hello,
-            length: to_u24(ch.tls_len() as u32),
-            body: ch,
-        }
+    pub fn from_client_hello(ch: ClientHello) -> Self {
+        Self::from_message(HandshakeType::client_hello, ch)
     }
 }
@@ -1,7 +1,19 @@
+pub mod builder;
+pub mod certificate;
+pub mod certificate_request;
 #[allow(non_upper_case_globals)]
 #[allow(dead_code)]
 pub mod client_hello;
+pub mod client_key_exchange;
 pub mod common;
 pub mod constants;
+pub mod extensions;
+pub mod finished;
+#[allow(clippy::module_inception)]
 pub mod handshake;
+pub mod new_session_ticket;
 pub mod record_layer;
+pub mod server_hello;
+pub mod server_key_exchange;
+pub mod templates;
+pub mod transcript;
@@ -0,0 +1,44 @@
+// TLS 1.2 NewSessionTicket message: https://datatracker.ietf.org/doc/html/rfc5077#section-3.3
+use crate::derive_tls::TlsDerive;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, TlsDerive)]
+pub struct NewSessionTicket {
+    // seconds, 0 means unspecified
+    pub ticket_lifetime_hint: u32,
+    pub ticket: VariableLengthVector<u8, 0, 2>,
+}
+
+impl NewSessionTicket {
+    pub fn new(ticket_lifetime_hint: u32, ticket: &[u8]) -> Self {
+        Self {
+            ticket_lifetime_hint,
+            ticket: VariableLengthVector::from_slice(ticket),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn new_session_ticket() {
+        let data = vec![0x00, 0x00, 0x1C, 0x20, 0x00, 0x03, 0xAA, 0xBB, 0xCC];
+
+        let mut nst = NewSessionTicket::default();
+        assert!(nst
+            .from_network_bytes(&mut Cursor::new(data.clone()))
+            .is_ok());
+        assert_eq!(nst.ticket_lifetime_hint, 7200);
+        assert_eq!(nst.ticket.data, &[0xAA, 0xBB, 0xCC]);
+
+        let mut buffer = Vec::new();
+        NewSessionTicket::new(7200, &[0xAA, 0xBB, 0xCC])
+            .to_network_bytes(&mut buffer)
+            .unwrap();
+        assert_eq!(buffer, data);
+    }
+}
@@ -5,8 +5,17 @@ use crate::derive_tls::TlsDerive;
 use crate::handshake::common::ContentType;
 use tls_derive::TlsDerive;
 
+use super::client_hello::MaxFragmentLength;
 use super::common::ProtocolVersion;
 
+// maximum length of a record fragment: https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.1
+pub const MAX_FRAGMENT_LENGTH: usize = 1 << 14;
+
+// the length records must not exceed, once max_fragment_length has been negotiated
+pub fn fragment_limit(negotiated: Option<MaxFragmentLength>) -> usize {
+    negotiated.map_or(MAX_FRAGMENT_LENGTH, |x| x.length())
+}
+
 // https://datatracker.ietf.org/doc/html/rfc5246#appendix-A.1
 #[derive(Debug, Default, TlsDerive)]
 pub struct RecordHeader {
@@ -0,0 +1,228 @@
+// the definition of the server_hello structure as defined in https://datatracker.ietf.org/doc/html/rfc5246#appendix-A.4
+// and its TLS 1.3 HelloRetryRequest flavor: https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+use std::io::{Cursor, Result};
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::{
+    ExtensionContext, ExtensionPayload, ExtensionType, Extensions, GenericExtension,
+    SupportedVersions,
+};
+use crate::handshake::common::{
+    CipherSuite, CompressionMethod, ProtocolVersion, Random, VariableLengthVector,
+};
+use crate::handshake::constants::HELLO_RETRY_REQUEST_RANDOM;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default)]
+pub struct ServerHello {
+    pub server_version: ProtocolVersion,
+    pub random: Random,
+    pub session_id: VariableLengthVector<u8, 0, 1>,
+    pub cipher_suite: CipherSuite,
+    pub compression_method: CompressionMethod,
+    pub extensions: Option<Extensions>,
+}
+
+impl ServerHello {
+    // a HelloRetryRequest is a ServerHello with a special random value
+    pub fn is_hello_retry_request(&self) -> bool {
+        self.random.as_bytes() == HELLO_RETRY_REQUEST_RANDOM
+    }
+
+    // find an extension by its type
+    pub fn extension(&self, extension_type: ExtensionType) -> Option<&GenericExtension> {
+        self.extensions.as_ref()?.get(extension_type)
+    }
+
+    // the typed body of each extension
+    pub fn parsed_extensions(&self) -> Result<Vec<ExtensionPayload>> {
+        let context = if self.is_hello_retry_request() {
+            ExtensionContext::HelloRetryRequest
+        } else {
+            ExtensionContext::ServerHello
+        };
+
+        self.extensions
+            .as_ref()
+            .map_or(&[][..], |x| x.as_slice())
+            .iter()
+            .map(|x| x.parse(context))
+            .collect()
+    }
+
+    // the version selected with supported_versions, if any
+    pub fn selected_version(&self) -> Option<ProtocolVersion> {
+        let data = self
+            .extension(ExtensionType::supported_versions)?
+            .extension_data();
+        let extension =
+            SupportedVersions::from_extension_data(data, ExtensionContext::ServerHello).ok()?;
+        extension.versions().first().copied()
+    }
+
+    // the cookie sent by the server in a HelloRetryRequest, without its length prefix
+    pub fn hrr_cookie(&self) -> Option<&[u8]> {
+        if !self.is_hello_retry_request() {
+            return None;
+        }
+
+        // opaque cookie<1..2^16-1>, its length being the rest of the extension
+        let data = self.extension(ExtensionType::cookie)?.extension_data();
+        let (length, cookie) = data.split_at_checked(2)?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        (length != 0 && length == cookie.len()).then_some(cookie)
+    }
+
+    // the group the server wants the client to use, as found in the HelloRetryRequest key_share
+    pub fn hrr_selected_group(&self) -> Option<u16> {
+        if !self.is_hello_retry_request() {
+            return None;
+        }
+
+        // for HRR, the key_share extension only holds the selected NamedGroup
+        let data = self.extension(ExtensionType::key_share)?.extension_data();
+        if data.len() != 2 {
+            return None;
+        }
+        Some(u16::from_be_bytes([data[0], data[1]]))
+    }
+}
+
+#[cfg(feature = "registry")]
+crate::tls_register!(ServerHello);
+
+// not derived because extensions are optional: they're only present if there're
+// remaining bytes after the compression method
+impl TlsDerive for ServerHello {
+    fn tls_len(&self) -> usize {
+        self.server_version.tls_len()
+            + self.random.tls_len()
+            + self.session_id.tls_len()
+            + self.cipher_suite.tls_len()
+            + self.compression_method.tls_len()
+            + self.extensions.tls_len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+        let mut length = 0usize;
+        length += self.server_version.to_network_bytes(v)?;
+        length += self.random.to_network_bytes(v)?;
+        length += self.session_id.to_network_bytes(v)?;
+        length += self.cipher_suite.to_network_bytes(v)?;
+        length += self.compression_method.to_network_bytes(v)?;
+        length += self.extensions.to_network_bytes(v)?;
+        Ok(length)
+    }
+
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> Result<()> {
+        self.server_version.from_network_bytes(v)?;
+        self.random.from_network_bytes(v)?;
+        self.session_id.from_network_bytes(v)?;
+        self.cipher_suite.from_network_bytes(v)?;
+        self.compression_method.from_network_bytes(v)?;
+
+        self.extensions = if (v.position() as usize) < v.get_ref().len() {
+            let mut extensions = Extensions::new();
+            extensions.from_network_bytes(v)?;
+            Some(extensions)
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+}
+
+// ServerHelloDone has an empty body: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.5
+#[derive(Debug, Default, TlsDerive)]
+pub struct ServerHelloDone {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::{TLS12, TLS13};
+
+    // HelloRetryRequest asking for secp256r1 with a cookie
+    fn hrr() -> Vec<u8> {
+        hrr_with_cookie(&[0x00, 0x04, 0xDE, 0xAD, 0xBE, 0xEF])
+    }
+
+    fn hrr_with_cookie(cookie: &[u8]) -> Vec<u8> {
+        let mut v = vec![0x03, 0x03];
+        v.extend_from_slice(&HELLO_RETRY_REQUEST_RANDOM);
+        v.extend_from_slice(&[
+            0x00, // session_id
+            0x13, 0x01, // TLS_AES_128_GCM_SHA256
+            0x00, // null compression
+        ]);
+        v.extend_from_slice(&(16 + cookie.len() as u16).to_be_bytes()); // extensions length
+        v.extend_from_slice(&[
+            0x00, 0x2B, 0x00, 0x02, 0x03, 0x04, // supported_versions
+            0x00, 0x33, 0x00, 0x02, 0x00, 0x17, // key_share
+            0x00, 0x2C, // cookie
+        ]);
+        v.extend_from_slice(&(cookie.len() as u16).to_be_bytes());
+        v.extend_from_slice(cookie);
+        v
+    }
+
+    #[test]
+    fn hello_retry_request() {
+        let mut sh = ServerHello::default();
+        assert!(sh.from_network_bytes(&mut Cursor::new(hrr())).is_ok());
+
+        assert_eq!(sh.server_version, TLS12);
+        assert!(sh.is_hello_retry_request());
+        assert_eq!(sh.cipher_suite, [0x13, 0x01]);
+        assert_eq!(sh.extensions.as_ref().unwrap().len(), 3);
+        assert_eq!(sh.selected_version(), Some(TLS13));
+        assert_eq!(sh.hrr_cookie().unwrap(), &[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(sh.hrr_selected_group().unwrap(), 0x0017);
+
+        let parsed = sh.parsed_extensions().unwrap();
+        assert!(matches!(parsed[1], ExtensionPayload::KeyShare(_)));
+        assert!(matches!(parsed[2], ExtensionPayload::Cookie(_)));
+
+        // the length of the cookie doesn't match the extension, or is zero
+        for cookie in [
+            &[0x00, 0x05, 0xDE, 0xAD, 0xBE, 0xEF][..],
+            &[0x00, 0x03, 0xDE, 0xAD, 0xBE, 0xEF],
+            &[0x00, 0x00],
+            &[0x00],
+        ] {
+            let data = hrr_with_cookie(cookie);
+            let mut sh = ServerHello::default();
+            assert!(sh.from_network_bytes(&mut Cursor::new(data)).is_ok());
+            assert!(sh.hrr_cookie().is_none());
+        }
+    }
+
+    #[test]
+    fn regular_server_hello() {
+        let mut sh = ServerHello::default();
+        let mut data = vec![0x03, 0x03];
+        data.extend_from_slice(&[0x11; 32]);
+        data.extend_from_slice(&[0x00, 0xC0, 0x2F, 0x00]);
+        assert!(sh.from_network_bytes(&mut Cursor::new(data)).is_ok());
+
+        assert!(!sh.is_hello_retry_request());
+        assert!(sh.extensions.is_none());
+        assert!(sh.selected_version().is_none());
+        assert!(sh.hrr_cookie().is_none());
+        assert!(sh.hrr_selected_group().is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = hrr();
+        let mut sh = ServerHello::default();
+        assert!(sh
+            .from_network_bytes(&mut Cursor::new(data.clone()))
+            .is_ok());
+        assert_eq!(sh.tls_len(), data.len());
+
+        let mut buffer = Vec::new();
+        assert_eq!(sh.to_network_bytes(&mut buffer).unwrap(), data.len());
+        assert_eq!(buffer, data);
+    }
+}
@@ -0,0 +1,177 @@
+// ServerKeyExchange message of DHE suites: https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.3
+use std::io::{Cursor, Error, ErrorKind, Result};
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::ffdhe::DhParams;
+use crate::crypto::rsa::RsaPublicKey;
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::SignatureScheme;
+use crate::handshake::common::VariableLengthVector;
+use tls_derive::TlsDerive;
+
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ServerDhParams {
+    pub dh_p: VariableLengthVector<u8, 1, 2>,
+    pub dh_g: VariableLengthVector<u8, 1, 2>,
+
+    // the server's public value
+    pub dh_ys: VariableLengthVector<u8, 1, 2>,
+}
+
+impl ServerDhParams {
+    pub fn new(params: &DhParams, public: &[u8]) -> Self {
+        Self {
+            dh_p: VariableLengthVector::from_slice(&params.p),
+            dh_g: VariableLengthVector::from_slice(&params.g),
+            dh_ys: VariableLengthVector::from_slice(public),
+        }
+    }
+
+    pub fn params(&self) -> DhParams {
+        DhParams {
+            p: self.dh_p.data.clone(),
+            g: self.dh_g.data.clone(),
+        }
+    }
+}
+
+// the TLS 1.2 form, with the scheme of the signature over both randoms and the parameters
+#[derive(Debug, Default, Clone, TlsDerive)]
+pub struct ServerKeyExchangeDhe {
+    pub params: ServerDhParams,
+    pub signature_algorithm: SignatureScheme,
+    pub signature: VariableLengthVector<u8, 0, 2>,
+}
+
+impl ServerKeyExchangeDhe {
+    // the body of the handshake message, which must have no trailing data
+    pub fn parse(body: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(body.to_vec());
+        let mut message = Self::default();
+        message.from_network_bytes(&mut cursor)?;
+        if cursor.position() as usize != body.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "trailing data after ServerKeyExchange",
+            ));
+        }
+        Ok(message)
+    }
+
+    // what the server signs: both randoms, then the parameters as sent
+    pub fn signed_data(&self, client_random: &[u8], server_random: &[u8]) -> Result<Vec<u8>> {
+        let mut data = client_random.to_vec();
+        data.extend_from_slice(server_random);
+        self.params.to_network_bytes(&mut data)?;
+        Ok(data)
+    }
+
+    // the signature, with the key of the server's certificate. Only RSA keys are supported,
+    // others are an unsupported_certificate
+    pub fn verify(
+        &self,
+        certificate: &[u8],
+        client_random: &[u8],
+        server_random: &[u8],
+    ) -> std::result::Result<(), AlertDescription> {
+        let key = RsaPublicKey::from_certificate(certificate)
+            .map_err(|_| AlertDescription::unsupported_certificate)?;
+        let data = self
+            .signed_data(client_random, server_random)
+            .map_err(|_| AlertDescription::internal_error)?;
+        key.verify(self.signature_algorithm, &data, &self.signature.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::ffdhe::DhKeyPair;
+    use crate::handshake::client_hello::NamedGroup;
+    use crate::handshake::client_key_exchange::ClientKeyExchange;
+
+    #[test]
+    fn server_key_exchange() {
+        let params = DhParams::ffdhe(NamedGroup::ffdhe2048).unwrap();
+        let server = DhKeyPair::generate(&params).unwrap();
+        let message = ServerKeyExchangeDhe {
+            params: ServerDhParams::new(&params, server.public_key()),
+            signature_algorithm: SignatureScheme::rsa_pkcs1_sha256,
+            signature: VariableLengthVector::from_slice(&[0xAA; 256]),
+        };
+        let mut body = Vec::new();
+        message.to_network_bytes(&mut body).unwrap();
+        assert_eq!(body.len(), 2 + 256 + 2 + 1 + 2 + 256 + 2 + 2 + 256);
+
+        let parsed = ServerKeyExchangeDhe::parse(&body).unwrap();
+        assert_eq!(
+            parsed.params.params().named_group(),
+            Some(NamedGroup::ffdhe2048)
+        );
+        assert_eq!(
+            parsed.signature_algorithm,
+            SignatureScheme::rsa_pkcs1_sha256
+        );
+
+        // the client computes the secret from the parameters, and answers with its value
+        let client = DhKeyPair::generate(&parsed.params.params()).unwrap();
+        let secret = client.pre_master_secret(&parsed.params.dh_ys.data).unwrap();
+        let cke = ClientKeyExchange::dhe(&client);
+        assert_eq!(server.pre_master_secret(cke.public_value()), Ok(secret));
+
+        body.push(0);
+        assert!(ServerKeyExchangeDhe::parse(&body).is_err());
+        assert!(ServerKeyExchangeDhe::parse(&body[..300]).is_err());
+    }
+
+    #[test]
+    fn signature() {
+        use crate::crypto::bignum::from_hex;
+        use crate::crypto::rsa::tests::{certificate, signed, signing_key, SIGNATURES};
+
+        let certificate = certificate(&signing_key());
+        let params = DhParams {
+            p: vec![23],
+            g: vec![5],
+        };
+        let (client_random, server_random) = ([0x01; 32], [0x02; 32]);
+
+        for (scheme, signature) in SIGNATURES {
+            let mut message = ServerKeyExchangeDhe {
+                params: ServerDhParams::new(&params, &[8]),
+                signature_algorithm: scheme,
+                signature: VariableLengthVector::from_slice(&from_hex(&signature)),
+            };
+            assert_eq!(
+                message.signed_data(&client_random, &server_random).unwrap(),
+                signed()
+            );
+            assert_eq!(
+                message.verify(&certificate, &client_random, &server_random),
+                Ok(())
+            );
+
+            // the randoms swapped, or a tampered public value
+            assert_eq!(
+                message.verify(&certificate, &server_random, &client_random),
+                Err(AlertDescription::decrypt_error)
+            );
+            message.params.dh_ys = VariableLengthVector::from_slice(&[9]);
+            assert_eq!(
+                message.verify(&certificate, &client_random, &server_random),
+                Err(AlertDescription::decrypt_error)
+            );
+        }
+
+        // not an RSA key
+        let message = ServerKeyExchangeDhe::default();
+        assert_eq!(
+            message.verify(
+                &crate::x509::certificate::test_certificate(&[]),
+                &client_random,
+                &server_random
+            ),
+            Err(AlertDescription::unsupported_certificate)
+        );
+    }
+}
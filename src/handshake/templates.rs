@@ -0,0 +1,249 @@
+// ClientHellos looking like the ones sent by common clients. GREASE values are left out
+// because they can't be represented by ExtensionType
+use crate::handshake::client_hello::{
+    ApplicationSettings, CertificateStatusRequest, ClientHello, ExtensionType, GenericExtension,
+    KeyShare, KeyShareEntry, NamedGroup, QuicTransportParameters, SignatureAlgorithms,
+    SignatureScheme, SupportedGroups, SupportedVersions, TransportParameter, INITIAL_MAX_DATA,
+    INITIAL_MAX_STREAMS_BIDI, INITIAL_MAX_STREAMS_UNI, INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+    INITIAL_MAX_STREAM_DATA_BIDI_REMOTE, INITIAL_MAX_STREAM_DATA_UNI, INITIAL_SOURCE_CONNECTION_ID,
+    MAX_IDLE_TIMEOUT, MAX_UDP_PAYLOAD_SIZE,
+};
+use crate::handshake::common::{CipherSuite, ProtocolVersion};
+use crate::handshake::constants::*;
+
+pub const TEMPLATES: [&str; 3] = ["chrome", "minimal", "quic"];
+
+// build the ClientHello of a template, for the given server name. IP literals, and names which
+// can't be sent, get no SNI
+pub fn template(name: &str, host: &str) -> Option<ClientHello> {
+    let ch = match name {
+        "chrome" => chrome(),
+        "minimal" => minimal(),
+        "quic" => quic(),
+        _ => return None,
+    };
+    Some(
+        ch.with_server_name(host)
+            .unwrap_or_else(|_| ch.with_extension_removed(ExtensionType::server_name)),
+    )
+}
+
+// keeps the position of SNI in the template, until template() sets the name
+fn server_name() -> GenericExtension {
+    GenericExtension::new(ExtensionType::server_name, &[])
+}
+
+fn supported_groups(groups: &[NamedGroup]) -> GenericExtension {
+    // encoding a list of groups can't fail
+    GenericExtension::from_extension(&SupportedGroups::new(groups)).unwrap()
+}
+
+fn signature_algorithms(schemes: &[SignatureScheme]) -> GenericExtension {
+    GenericExtension::from_extension(&SignatureAlgorithms::new(schemes)).unwrap()
+}
+
+fn supported_versions(versions: &[ProtocolVersion]) -> GenericExtension {
+    GenericExtension::from_extension(&SupportedVersions::new(versions)).unwrap()
+}
+
+fn hello(suites: &[CipherSuite], extensions: Vec<GenericExtension>) -> ClientHello {
+    extensions
+        .into_iter()
+        .fold(ClientHello::new(suites), |ch, ext| {
+            ch.with_extension_replaced(ext)
+        })
+}
+
+// a TLS 1.2 hello with only SNI and what's needed for ECDHE
+fn minimal() -> ClientHello {
+    hello(
+        &[
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            TLS_RSA_WITH_AES_128_CBC_SHA,
+        ],
+        vec![
+            server_name(),
+            supported_groups(&[NamedGroup::x25519, NamedGroup::secp256r1]),
+            GenericExtension::new(ExtensionType::ec_point_formats, &[0x01, 0x00]),
+            signature_algorithms(&[
+                SignatureScheme::ecdsa_secp256r1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pkcs1_sha256,
+            ]),
+        ],
+    )
+}
+
+// extensions in the order Chrome sends them
+fn chrome() -> ClientHello {
+    let key_share = KeyShare::new(vec![KeyShareEntry::new(
+        NamedGroup::x25519,
+        &rand::random::<[u8; 32]>(),
+    )]);
+
+    hello(
+        &[
+            TLS_AES_128_GCM_SHA256,
+            TLS_AES_256_GCM_SHA384,
+            TLS_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+            TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+            TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+            TLS_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_RSA_WITH_AES_256_GCM_SHA384,
+            TLS_RSA_WITH_AES_128_CBC_SHA,
+            TLS_RSA_WITH_AES_256_CBC_SHA,
+        ],
+        vec![
+            server_name(),
+            GenericExtension::new(ExtensionType::extended_master_secret, &[]),
+            supported_groups(&[
+                NamedGroup::x25519,
+                NamedGroup::secp256r1,
+                NamedGroup::secp384r1,
+            ]),
+            GenericExtension::new(ExtensionType::ec_point_formats, &[0x01, 0x00]),
+            GenericExtension::new(ExtensionType::session_ticket, &[]),
+            GenericExtension::new(
+                ExtensionType::application_layer_protocol_negotiation,
+                b"\x00\x0C\x02h2\x08http/1.1",
+            ),
+            GenericExtension::from_extension(&CertificateStatusRequest::ocsp()).unwrap(),
+            signature_algorithms(&[
+                SignatureScheme::ecdsa_secp256r1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pkcs1_sha256,
+                SignatureScheme::ecdsa_secp384r1_sha384,
+                SignatureScheme::rsa_pss_rsae_sha384,
+                SignatureScheme::rsa_pkcs1_sha384,
+                SignatureScheme::rsa_pss_rsae_sha512,
+                SignatureScheme::rsa_pkcs1_sha512,
+            ]),
+            GenericExtension::new(ExtensionType::signed_certificate_timestamp, &[]),
+            GenericExtension::from_extension(&key_share).unwrap(),
+            GenericExtension::new(ExtensionType::psk_key_exchange_modes, &[0x01, 0x01]),
+            supported_versions(&[TLS13, TLS12]),
+            GenericExtension::new(ExtensionType::compress_certificate, &[0x02, 0x00, 0x02]),
+            GenericExtension::from_extension(&ApplicationSettings::new(&["h2"])).unwrap(),
+        ],
+    )
+}
+
+// a TLS 1.3 hello as carried in QUIC Initial packets: no legacy session ID, HTTP/3 and the
+// QUIC transport parameters
+fn quic() -> ClientHello {
+    let key_share = KeyShare::new(vec![KeyShareEntry::new(
+        NamedGroup::x25519,
+        &rand::random::<[u8; 32]>(),
+    )]);
+
+    // a fresh connection ID, as the client's Initial packet would use
+    let parameters = QuicTransportParameters::new(vec![
+        TransportParameter::integer(MAX_IDLE_TIMEOUT, 30_000).unwrap(),
+        TransportParameter::integer(MAX_UDP_PAYLOAD_SIZE, 1472).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_DATA, 15_728_640).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_STREAM_DATA_BIDI_LOCAL, 6_291_456).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_STREAM_DATA_BIDI_REMOTE, 6_291_456).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_STREAM_DATA_UNI, 6_291_456).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_STREAMS_BIDI, 100).unwrap(),
+        TransportParameter::integer(INITIAL_MAX_STREAMS_UNI, 103).unwrap(),
+        TransportParameter::new(INITIAL_SOURCE_CONNECTION_ID, &rand::random::<[u8; 8]>()),
+    ]);
+
+    hello(
+        &[
+            TLS_AES_128_GCM_SHA256,
+            TLS_AES_256_GCM_SHA384,
+            TLS_CHACHA20_POLY1305_SHA256,
+        ],
+        vec![
+            server_name(),
+            supported_groups(&[
+                NamedGroup::x25519,
+                NamedGroup::secp256r1,
+                NamedGroup::secp384r1,
+            ]),
+            GenericExtension::new(
+                ExtensionType::application_layer_protocol_negotiation,
+                b"\x00\x03\x02h3",
+            ),
+            signature_algorithms(&[
+                SignatureScheme::ecdsa_secp256r1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pkcs1_sha256,
+            ]),
+            GenericExtension::from_extension(&key_share).unwrap(),
+            GenericExtension::new(ExtensionType::psk_key_exchange_modes, &[0x01, 0x01]),
+            supported_versions(&[TLS13]),
+            GenericExtension::from_extension(&parameters).unwrap(),
+        ],
+    )
+    .with_session_id(&[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_tls::TlsDerive;
+    use crate::dissector::summary::ClientHelloSummary;
+
+    #[test]
+    fn chrome() {
+        let ch = template("chrome", "example.com").unwrap();
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+
+        // the lenient parser agrees with the encoding
+        let summary = ClientHelloSummary::parse(&buffer).unwrap();
+        assert_eq!(summary.server_name.as_deref(), Some("example.com"));
+        assert_eq!(summary.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(summary.supported_versions, vec![TLS13, TLS12]);
+        assert_eq!(summary.max_version(), TLS13);
+        assert_eq!(summary.extensions.len(), 14);
+        assert_eq!(summary.extensions.last(), Some(&0x4469));
+
+        assert!(template("unknown", "example.com").is_none());
+
+        // each extension of the templates decodes to its typed form
+        for name in TEMPLATES {
+            let parsed = template(name, "example.com")
+                .unwrap()
+                .parsed_extensions()
+                .unwrap();
+            assert!(!parsed.is_empty());
+        }
+    }
+
+    #[test]
+    fn quic() {
+        let ch = template("quic", "example.com").unwrap();
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+
+        let summary = ClientHelloSummary::parse(&buffer).unwrap();
+        assert!(summary.session_id.is_empty());
+        assert_eq!(summary.alpn, vec!["h3"]);
+        assert_eq!(summary.supported_versions, vec![TLS13]);
+        assert_eq!(summary.extensions.last(), Some(&57));
+
+        let parameters = ch
+            .extensions()
+            .iter()
+            .find(|x| x.extension_type() == ExtensionType::quic_transport_parameters)
+            .map(|x| QuicTransportParameters::from_extension_data(x.extension_data()).unwrap())
+            .unwrap();
+        assert_eq!(
+            parameters
+                .get(INITIAL_MAX_STREAMS_BIDI)
+                .unwrap()
+                .as_integer(),
+            Some(100)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// the transcript hash of TLS 1.3: https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.1
+use crate::crypto::sha2::HashAlgorithm;
+use crate::handshake::constants::HELLO_RETRY_REQUEST_RANDOM;
+use crate::handshake::handshake::HandshakeType;
+
+// the synthetic handshake message replacing ClientHello1 in the transcript when the server
+// sends a HelloRetryRequest. client_hello is the whole message, including its 4-byte header
+pub fn message_hash(hash: HashAlgorithm, client_hello: &[u8]) -> Vec<u8> {
+    let mut message = vec![
+        HandshakeType::message_hash as u8,
+        0,
+        0,
+        hash.output_len() as u8,
+    ];
+    message.extend_from_slice(&hash.hash(client_hello));
+    message
+}
+
+// true if the handshake message is a ServerHello carrying the HelloRetryRequest random
+fn is_hello_retry_request(message: &[u8]) -> bool {
+    message.first() == Some(&(HandshakeType::server_hello as u8))
+        && message.get(6..38) == Some(&HELLO_RETRY_REQUEST_RANDOM[..])
+}
+
+// concatenation of the handshake messages seen so far
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    hash: HashAlgorithm,
+    messages: Vec<u8>,
+}
+
+impl Transcript {
+    pub fn new(hash: HashAlgorithm) -> Self {
+        Self {
+            hash,
+            messages: Vec::new(),
+        }
+    }
+
+    // add a handshake message, including its header. When a HelloRetryRequest is added,
+    // the messages already there (ClientHello1) are replaced by their message_hash
+    pub fn add(&mut self, message: &[u8]) {
+        if is_hello_retry_request(message) {
+            self.messages = message_hash(self.hash, &self.messages);
+        }
+        self.messages.extend_from_slice(message);
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash
+    }
+
+    // the messages as they are hashed
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.messages
+    }
+
+    pub fn current_hash(&self) -> Vec<u8> {
+        self.hash.hash(&self.messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::sha2::sha256;
+
+    fn server_hello(random: &[u8; 32]) -> Vec<u8> {
+        let mut message = vec![0x02, 0x00, 0x00, 0x26, 0x03, 0x03];
+        message.extend_from_slice(random);
+        message.extend_from_slice(&[0x00, 0x13, 0x01, 0x00, 0x00, 0x00]);
+        message
+    }
+
+    #[test]
+    fn synthetic_message() {
+        let client_hello = [0x01, 0x00, 0x00, 0x02, 0x03, 0x03];
+        let message = message_hash(HashAlgorithm::Sha384, &client_hello);
+
+        assert_eq!(&message[..4], &[0xFE, 0x00, 0x00, 0x30]);
+        assert_eq!(message.len(), 4 + 48);
+    }
+
+    #[test]
+    fn hello_retry_request() {
+        let client_hello1 = [0x01, 0x00, 0x00, 0x02, 0x03, 0x03];
+        let hrr = server_hello(&HELLO_RETRY_REQUEST_RANDOM);
+
+        let mut transcript = Transcript::new(HashAlgorithm::Sha256);
+        transcript.add(&client_hello1);
+        transcript.add(&hrr);
+
+        let mut expected = vec![0xFE, 0x00, 0x00, 0x20];
+        expected.extend_from_slice(&sha256(&client_hello1));
+        expected.extend_from_slice(&hrr);
+        assert_eq!(transcript.as_bytes(), expected);
+        assert_eq!(transcript.current_hash(), sha256(&expected));
+    }
+
+    #[test]
+    fn no_substitution() {
+        let client_hello = [0x01, 0x00, 0x00, 0x02, 0x03, 0x03];
+        let sh = server_hello(&[0x11; 32]);
+
+        let mut transcript = Transcript::new(HashAlgorithm::Sha256);
+        transcript.add(&client_hello);
+        transcript.add(&sh);
+
+        assert_eq!(transcript.as_bytes(), [&client_hello[..], &sh].concat());
+    }
+}
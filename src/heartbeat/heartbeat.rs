@@ -0,0 +1,113 @@
+// the Heartbeat protocol: https://datatracker.ietf.org/doc/html/rfc6520#section-4
+use std::io::{Cursor, Error, ErrorKind, Read};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::record_layer::RecordLayer;
+use tls_derive::TlsEnum;
+
+// the padding must be at least 16 bytes
+pub const MIN_PADDING_LENGTH: usize = 16;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, TlsEnum)]
+#[repr(u8)]
+pub enum HeartbeatMessageType {
+    heartbeat_request = 1,
+    heartbeat_response = 2,
+}
+
+// payload_length is kept apart from the payload, so messages announcing more data than
+// they carry can be built to probe peers
+#[derive(Debug, Default)]
+pub struct HeartbeatMessage {
+    pub r#type: HeartbeatMessageType,
+    pub payload_length: u16,
+    pub payload: Vec<u8>,
+    pub padding: Vec<u8>,
+}
+
+impl HeartbeatMessage {
+    // a well-formed message with random padding of the minimum length
+    pub fn new(r#type: HeartbeatMessageType, payload: &[u8]) -> Self {
+        Self {
+            r#type,
+            payload_length: payload.len() as u16,
+            payload: payload.to_vec(),
+            padding: (0..MIN_PADDING_LENGTH).map(|_| rand::random()).collect(),
+        }
+    }
+
+    // the response a peer is expected to send back
+    pub fn response(&self) -> Self {
+        Self::new(HeartbeatMessageType::heartbeat_response, &self.payload)
+    }
+}
+
+// the default message has no padding
+#[cfg(feature = "registry")]
+crate::tls_register!(
+    HeartbeatMessage,
+    HeartbeatMessage::new(HeartbeatMessageType::heartbeat_request, b"")
+);
+
+/// ```
+/// use std::io::Cursor;
+/// use tls_explore::derive_tls::TlsDerive;
+/// use tls_explore::heartbeat::heartbeat::{HeartbeatMessage, HeartbeatMessageType};
+///
+/// let hb = HeartbeatMessage::new(HeartbeatMessageType::heartbeat_request, b"ping");
+/// let mut buffer = Vec::new();
+/// assert_eq!(hb.to_network_bytes(&mut buffer).unwrap(), 1 + 2 + 4 + 16);
+/// assert_eq!(&buffer[..7], &[0x01, 0x00, 0x04, b'p', b'i', b'n', b'g']);
+///
+/// let mut parsed = HeartbeatMessage::default();
+/// assert!(parsed.from_network_bytes(&mut Cursor::new(buffer)).is_ok());
+/// assert_eq!(parsed.payload, b"ping");
+/// assert_eq!(parsed.padding.len(), 16);
+///
+/// // payload_length larger than the message
+/// let mut parsed = HeartbeatMessage::default();
+/// let data = vec![0x01, 0x40, 0x00, b'p', b'i', b'n', b'g'];
+/// assert!(parsed.from_network_bytes(&mut Cursor::new(data)).is_err());
+/// ```
+impl TlsDerive for HeartbeatMessage {
+    fn tls_len(&self) -> usize {
+        self.r#type.tls_len() + 2 + self.payload.len() + self.padding.len()
+    }
+
+    fn to_network_bytes(&self, v: &mut Vec<u8>) -> std::io::Result<usize> {
+        let mut length = self.r#type.to_network_bytes(v)?;
+        length += self.payload_length.to_network_bytes(v)?;
+        v.extend_from_slice(&self.payload);
+        v.extend_from_slice(&self.padding);
+        Ok(length + self.payload.len() + self.padding.len())
+    }
+
+    // the padding is the rest of the record, so the whole cursor is consumed
+    fn from_network_bytes(&mut self, v: &mut Cursor<Vec<u8>>) -> std::io::Result<()> {
+        self.r#type.from_network_bytes(v)?;
+        self.payload_length = v.read_u16::<BigEndian>()?;
+
+        let remaining = v.get_ref().len().saturating_sub(v.position() as usize);
+        if self.payload_length as usize + MIN_PADDING_LENGTH > remaining {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "heartbeat payload_length {} too large for a message of {} bytes",
+                    self.payload_length, remaining
+                ),
+            ));
+        }
+
+        self.payload = vec![0u8; self.payload_length as usize];
+        v.read_exact(&mut self.payload)?;
+
+        self.padding.clear();
+        v.read_to_end(&mut self.padding)?;
+        Ok(())
+    }
+}
+
+pub type HeartbeatRecord = RecordLayer<HeartbeatMessage>;
@@ -1,4 +1,28 @@
 pub mod alert;
+pub mod api;
+pub mod bisect;
+pub mod canonical;
+pub mod change_cipher_spec;
+pub mod config;
+pub mod corpus;
+pub mod crypto;
 pub mod derive_tls;
+#[cfg(feature = "differential")]
+pub mod differential;
+pub mod dissector;
+pub mod fingerprint;
 pub mod handshake;
+pub mod heartbeat;
 pub mod macros;
+pub mod pcap;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod replay;
+pub mod scanner;
+pub mod selftest;
+pub mod server;
+pub mod shutdown;
+pub mod stats;
+#[deprecated(since = "0.1.0", note = "use tls_explore::api::TlsDerive")]
+pub mod structurizer;
+pub mod x509;
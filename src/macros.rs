@@ -41,6 +41,13 @@ macro_rules! enum_to_network_bytes {
             Ok(1)
         }
     };
+
+    ($t:ty, u16) => {
+        fn to_network_bytes(&self, v: &mut Vec<u8>) -> Result<usize> {
+            v.write_u16::<BigEndian>(*self as u16)?;
+            Ok(2)
+        }
+    };
 }
 
 // auto-implement the conversion from network bytes for enums
@@ -68,20 +75,177 @@ macro_rules! enum_from_network_bytes {
                 *self = ct;
                 Ok(())
             } else {
-                Err(Error::new(ErrorKind::Other, "TryFrom() conversion error"))
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "TryFrom() conversion error",
+                ))
             }
         }
     };
 }
 
-// helper to implement the ExtType trait
+// helper to implement the Extension trait: the body is the TlsDerive encoding of the type, decoded
+// with the given function, or as a whole by default
 #[macro_export]
 macro_rules! ext_type {
     ($t:ty, $v:ident) => {
-        impl ExtType for $t {
-            fn extension_type(&self) -> ExtensionType {
-                ExtensionType::$v
+        $crate::ext_type!($t, $v, |data, _| $crate::handshake::extensions::decode(
+            data
+        ));
+    };
+    ($t:ty, $v:ident, $decode:expr) => {
+        impl $crate::handshake::extensions::Extension for $t {
+            const TYPE: $crate::handshake::client_hello::ExtensionType =
+                $crate::handshake::client_hello::ExtensionType::$v;
+
+            fn encode_body(&self) -> std::io::Result<Vec<u8>> {
+                let mut v = Vec::new();
+                $crate::derive_tls::TlsDerive::to_network_bytes(self, &mut v)?;
+                Ok(v)
+            }
+
+            fn decode_body(
+                data: &[u8],
+                context: $crate::handshake::extensions::ExtensionContext,
+            ) -> std::io::Result<Self> {
+                let decode: fn(
+                    &[u8],
+                    $crate::handshake::extensions::ExtensionContext,
+                ) -> std::io::Result<Self> = $decode;
+                decode(data, context)
             }
         }
     };
 }
+
+// the bytes of the parts of a message, each being anything with AsRef<[u8]>: byte arrays,
+// slices, vectors, or other messages built with the macros below
+#[macro_export]
+macro_rules! concat_parts {
+    ($($part:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut bytes: Vec<u8> = Vec::new();
+        $(bytes.extend_from_slice(::std::convert::AsRef::<[u8]>::as_ref(&$part));)*
+        bytes
+    }};
+}
+
+// a record from its content type, version and fragment parts, with its length computed
+#[macro_export]
+macro_rules! record {
+    ($content_type:expr, $version:expr $(, $part:expr)* $(,)?) => {{
+        let fragment = $crate::concat_parts!($($part),*);
+        $crate::concat_parts!(
+            [$content_type as u8],
+            $version,
+            (fragment.len() as u16).to_be_bytes(),
+            fragment
+        )
+    }};
+}
+
+// a handshake message from its type and body parts, with its 3-byte length computed
+#[macro_export]
+macro_rules! handshake {
+    ($msg_type:expr $(, $part:expr)* $(,)?) => {{
+        let body = $crate::concat_parts!($($part),*);
+        $crate::concat_parts!(
+            [$msg_type as u8],
+            &(body.len() as u32).to_be_bytes()[1..],
+            body
+        )
+    }};
+}
+
+// the code of an extension given to extension!, so a literal is taken as a u16
+pub trait ExtensionCode {
+    fn code(self) -> u16;
+}
+
+impl ExtensionCode for u16 {
+    fn code(self) -> u16 {
+        self
+    }
+}
+
+impl ExtensionCode for crate::handshake::client_hello::ExtensionType {
+    fn code(self) -> u16 {
+        u16::from(self)
+    }
+}
+
+// an extension from its type, a code or an ExtensionType, and body parts, with its length
+// computed
+#[macro_export]
+macro_rules! extension {
+    ($extension_type:expr $(, $part:expr)* $(,)?) => {{
+        let data = $crate::concat_parts!($($part),*);
+        $crate::concat_parts!(
+            $crate::macros::ExtensionCode::code($extension_type).to_be_bytes(),
+            (data.len() as u16).to_be_bytes(),
+            data
+        )
+    }};
+}
+
+// register a wire type in the registry at startup, before main() or the test harness runs,
+// with a function put in the constructors section. Deriving TlsDerive or TlsEnum does it for
+// non-generic types when the registry feature is enabled. The sample value, the default one
+// unless given, must be valid on the wire. Other targets than ELF ones, Apple's and Windows
+// have no such section, and fail to compile rather than register nothing
+#[macro_export]
+macro_rules! tls_register {
+    ($t:ident) => {
+        $crate::tls_register!($t, <$t>::default());
+    };
+
+    ($t:ident, $sample:expr) => {
+        const _: () = {
+            #[used]
+            #[cfg_attr(
+                any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+                link_section = ".init_array"
+            )]
+            #[cfg_attr(target_vendor = "apple", link_section = "__DATA,__mod_init_func")]
+            #[cfg_attr(windows, link_section = ".CRT$XCU")]
+            static REGISTER: extern "C" fn() = {
+                extern "C" fn register() {
+                    $crate::registry::register::<$t>(stringify!($t), module_path!(), || $sample);
+                }
+                register
+            };
+
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "freebsd",
+                target_vendor = "apple",
+                windows
+            )))]
+            compile_error!("tls_register! has no constructors section on this target");
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handshake::client_hello::ExtensionType;
+
+    #[test]
+    fn messages() {
+        let record = record!(0x16, [0x03, 0x03], handshake!(0x0E));
+        assert_eq!(
+            record,
+            &[0x16, 0x03, 0x03, 0x00, 0x04, 0x0E, 0x00, 0x00, 0x00]
+        );
+
+        let hello = handshake!(0x01, [0x03, 0x03], [0; 32], [0x00], extension!(0x0017));
+        assert_eq!(&hello[..4], &[0x01, 0x00, 0x00, 0x27]);
+        assert_eq!(&hello[hello.len() - 4..], &[0x00, 0x17, 0x00, 0x00]);
+
+        assert_eq!(
+            extension!(ExtensionType::server_name, vec![0x01]),
+            &[0x00, 0x00, 0x00, 0x01, 0x01]
+        );
+    }
+}
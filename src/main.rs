@@ -1,28 +1,737 @@
-#![allow(dead_code)]
+use std::io::prelude::*;
 use std::io::BufReader;
 use std::io::Cursor;
-use std::net::TcpStream;
-use std::{io::prelude::*, net::TcpListener};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "verify")]
+use std::time::SystemTime;
+use std::time::{Duration, Instant};
 
 //use tls_derive::TlsLength;
 
-mod alert;
-mod macros;
-
-mod handshake;
-use handshake::{
-    common::ContentType,
+use tls_explore::alert::alert::Alert;
+use tls_explore::bisect::{bisect, TcpProbe};
+use tls_explore::change_cipher_spec::change_cipher_spec::ChangeCipherSpecRecord;
+use tls_explore::config::Config;
+use tls_explore::corpus::write_corpus;
+use tls_explore::derive_tls::TlsDerive;
+use tls_explore::dissector::encoding::{Encoding, TextDecoder};
+use tls_explore::dissector::machine::{Event, MachineReader};
+use tls_explore::dissector::summary::ServerHelloSummary;
+use tls_explore::dissector::HandshakeMessage;
+use tls_explore::fingerprint::database::FingerprintDatabase;
+use tls_explore::handshake::{
+    builder::ClientHelloBuilder,
+    common::{version_name, ContentType},
     constants::*,
     handshake::Handshake,
     record_layer::{RecordHeader, RecordLayer},
+    templates::{template, TEMPLATES},
 };
+#[cfg(feature = "verify")]
+use tls_explore::handshake::{certificate::CertificateMessage, handshake::HandshakeType};
+use tls_explore::heartbeat::heartbeat::HeartbeatRecord;
+use tls_explore::pcap::dump::write_connections;
+use tls_explore::pcap::filter::CaptureFilter;
+use tls_explore::pcap::flow::connections_filtered;
+use tls_explore::pcap::parallel::default_threads;
+use tls_explore::pcap::reader::PcapReader;
+#[cfg(all(feature = "live", target_os = "linux"))]
+use tls_explore::pcap::{live::LiveCapture, sniff::HandshakeTracker};
+use tls_explore::replay::{recorded_hellos, replay};
+use tls_explore::scanner::check::{CheckRegistry, Severity};
+use tls_explore::scanner::connector::{split_host, Connector, ServerResponse};
+use tls_explore::scanner::histogram::LatencyHistogram;
+use tls_explore::scanner::info::{export_env, ConnectionInfo};
+use tls_explore::scanner::load::{self, parse_duration, LoadConfig};
+use tls_explore::scanner::matrix::CapabilityMatrix;
+use tls_explore::scanner::messages::Catalog;
+use tls_explore::scanner::report::{render_localized, OutputFormat, OUTPUT_FORMATS};
+use tls_explore::selftest::SELF_TESTS;
+use tls_explore::server::server::{EventHandler, Server};
+use tls_explore::shutdown::ShutdownToken;
+use tls_explore::stats::CaptureStats;
+#[cfg(feature = "verify")]
+use tls_explore::x509::verify::{Intermediates, TrustStore};
+
+const USAGE: &str = "usage: tls_explore [stats [--redact] [--threads <n>] [--filter <expression>] \
+[--split <directory> [--output <format>]] [--fingerprints <file>] [--unix-time <tolerance>] \
+<capture.pcap>] \
+[sniff -i <interface> [--redact] [--filter <expression>]] \
+[replay --from <capture.pcap> --to <host> [--fresh-random]] \
+[load <host> --rate <handshakes/s> --duration <60s> [--resume]] \
+[bisect <host> [--template <name>]] [raw <host>] [probe <host> [--template <name>] [--export-env] [--trust-store <roots.pem>]] \
+[trust-diff <roots.pem> <other-roots.pem> <host>...] [serve <address> [--cert <certificate.der>]] \
+[scan <host> [--only <checks>] [--skip <checks>] [--fail-on <severity>] [--output <format>] \
+[--locale <catalog.toml>]] \
+[matrix <host> [--output <format>]] [gen-corpus <directory>] \
+[decode [<file>] [--encoding hex|base64] [--max-size <bytes>]] [self-test] [--list-checks] \
+[--config <file.toml>]";
+
+// command line options: flags, and options followed by a value
+#[derive(Debug, Default)]
+struct Options {
+    redact: bool,
+    template: Option<String>,
+    cert: Option<String>,
+    config: Option<String>,
+    list_checks: bool,
+
+    // print what a probe negotiated as shell variables
+    export_env: bool,
+
+    // PEM file of the roots the chain of a probed server is checked against
+    trust_store: Option<String>,
+
+    // comma-separated check names
+    only: Vec<String>,
+    skip: Vec<String>,
+
+    // lowest severity making the scan fail
+    fail_on: Option<Severity>,
+    output: Option<OutputFormat>,
+
+    // message catalog of the scan report
+    locale: Option<String>,
+
+    // threads dissecting a capture, one per core if not given
+    threads: Option<usize>,
+
+    // network interface to capture from
+    interface: Option<String>,
 
-mod derive_tls;
-use derive_tls::TlsDerive;
+    // capture filter, like "port 443 and sni *.example.com"
+    filter: CaptureFilter,
 
-use crate::alert::alert::{Alert, AlertRecord};
+    // directory where each connection of a capture is written to its own file
+    split: Option<String>,
+
+    // JA3/JA4 fingerprints added to the builtin ones
+    fingerprints: Option<String>,
+
+    // read the start of the hello randoms as clocks, within this tolerance of the capture time
+    unix_time: Option<Duration>,
+
+    // replay the hellos of a capture to another server
+    from: Option<String>,
+    to: Option<String>,
+    fresh_random: bool,
+
+    // load generation: handshakes per second, for how long
+    rate: Option<u32>,
+    duration: Option<Duration>,
+    resume: bool,
+
+    // text given to decode, and the most bytes it may decode to
+    encoding: Option<Encoding>,
+    max_size: Option<u64>,
+}
+
+// exit code of a scan with findings at or above --fail-on
+const EXIT_FINDINGS: i32 = 2;
+
+// exit code of a self-test with failures
+const EXIT_SELF_TEST: i32 = 3;
+
+fn output_format(arg: Option<String>) -> std::result::Result<OutputFormat, String> {
+    let arg = arg.ok_or(USAGE)?;
+    OutputFormat::from_name(&arg)
+        .ok_or_else(|| format!("unknown output {}, use one of {:?}", arg, OUTPUT_FORMATS))
+}
+
+fn severity(arg: Option<String>) -> std::result::Result<Severity, String> {
+    let arg = arg.ok_or(USAGE)?;
+    Severity::from_name(&arg).ok_or_else(|| format!("unknown severity {}", arg))
+}
+
+fn threads(arg: Option<String>) -> std::result::Result<usize, String> {
+    let arg = arg.ok_or(USAGE)?;
+    arg.parse()
+        .ok()
+        .filter(|x| *x > 0)
+        .ok_or_else(|| format!("invalid number of threads {}", arg))
+}
+
+fn encoding(arg: Option<String>) -> std::result::Result<Encoding, String> {
+    let arg = arg.ok_or(USAGE)?;
+    Encoding::from_name(&arg).ok_or_else(|| format!("unknown encoding {}, use hex or base64", arg))
+}
+
+fn max_size(arg: Option<String>) -> std::result::Result<u64, String> {
+    let arg = arg.ok_or(USAGE)?;
+    arg.parse()
+        .ok()
+        .filter(|x| *x > 0)
+        .ok_or_else(|| format!("invalid size {}", arg))
+}
+
+fn rate(arg: Option<String>) -> std::result::Result<u32, String> {
+    let arg = arg.ok_or(USAGE)?;
+    arg.parse()
+        .ok()
+        .filter(|x| *x > 0)
+        .ok_or_else(|| format!("invalid rate {}", arg))
+}
+
+fn duration(arg: Option<String>) -> std::result::Result<Duration, String> {
+    let arg = arg.ok_or(USAGE)?;
+    parse_duration(&arg).ok_or_else(|| format!("invalid duration {}", arg))
+}
+
+fn check_names(arg: Option<String>) -> std::result::Result<Vec<String>, &'static str> {
+    let arg = arg.ok_or(USAGE)?;
+    Ok(arg.split(',').map(|x| x.trim().to_string()).collect())
+}
 
 fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let mut options = Options::default();
+    let mut args = Vec::new();
+
+    // --option=value is the same as --option value
+    let mut iter = std::env::args()
+        .skip(1)
+        .flat_map(|arg| match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => {
+                vec![name.to_string(), value.to_string()]
+            }
+            _ => vec![arg],
+        });
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--redact" => options.redact = true,
+            "--template" => options.template = Some(iter.next().ok_or(USAGE)?),
+            "-i" | "--interface" => options.interface = Some(iter.next().ok_or(USAGE)?),
+            "--filter" => options.filter = CaptureFilter::parse(&iter.next().ok_or(USAGE)?)?,
+            "--from" => options.from = Some(iter.next().ok_or(USAGE)?),
+            "--to" => options.to = Some(iter.next().ok_or(USAGE)?),
+            "--fresh-random" => options.fresh_random = true,
+            "--rate" => options.rate = Some(rate(iter.next())?),
+            "--duration" => options.duration = Some(duration(iter.next())?),
+            "--resume" => options.resume = true,
+            "--encoding" => options.encoding = Some(encoding(iter.next())?),
+            "--max-size" => options.max_size = Some(max_size(iter.next())?),
+            "--split" => options.split = Some(iter.next().ok_or(USAGE)?),
+            "--fingerprints" => options.fingerprints = Some(iter.next().ok_or(USAGE)?),
+            "--unix-time" => options.unix_time = Some(duration(iter.next())?),
+            "--cert" => options.cert = Some(iter.next().ok_or(USAGE)?),
+            "--config" => options.config = Some(iter.next().ok_or(USAGE)?),
+            "--list-checks" => options.list_checks = true,
+            "--export-env" => options.export_env = true,
+            "--trust-store" => options.trust_store = Some(iter.next().ok_or(USAGE)?),
+            "--only" => options.only = check_names(iter.next())?,
+            "--skip" => options.skip = check_names(iter.next())?,
+            "--fail-on" => options.fail_on = Some(severity(iter.next())?),
+            "--output" => options.output = Some(output_format(iter.next())?),
+            "--locale" => options.locale = Some(iter.next().ok_or(USAGE)?),
+            "--threads" => options.threads = Some(threads(iter.next())?),
+            _ if arg.starts_with("--") => return Err(USAGE.into()),
+            _ => args.push(arg),
+        }
+    }
+
+    // command line options take precedence over the config file
+    let config = Config::load(options.config.as_deref())?;
+
+    if options.list_checks {
+        for check in CheckRegistry::with_builtin().checks() {
+            println!("{:<20}{}", check.name(), check.description());
+        }
+        return Ok(());
+    }
+
+    match args.first().map(|x| x.as_str()) {
+        None => Err(USAGE.into()),
+        Some("stats") => {
+            let path = args.get(1).ok_or(USAGE)?;
+            let threads = options.threads.unwrap_or_else(default_threads);
+            let redact = options.redact || config.redact;
+
+            let mut fingerprints = FingerprintDatabase::builtin();
+            if let Some(file) = &options.fingerprints {
+                fingerprints.merge(FingerprintDatabase::load(file)?);
+            }
+
+            let reader = PcapReader::open(path)?;
+            let connections = connections_filtered(reader, &options.filter)?;
+            if let Some(directory) = &options.split {
+                let format = options.output.or(config.output).unwrap_or_default();
+                let paths = write_connections(
+                    &connections,
+                    directory.as_ref(),
+                    format,
+                    redact,
+                    threads,
+                    options.unix_time,
+                )?;
+                eprintln!("{} connections written to {}", paths.len(), directory);
+            }
+
+            let stats = CaptureStats::from_connections(
+                &connections,
+                redact,
+                threads,
+                &options.filter,
+                fingerprints,
+                options.unix_time,
+            );
+            print!("{}", stats);
+            Ok(())
+        }
+        Some("sniff") => {
+            let interface = options.interface.as_deref().ok_or(USAGE)?;
+            sniff(interface, options.redact || config.redact, options.filter)
+        }
+        Some("replay") => {
+            let (from, to) = match (&options.from, &options.to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => return Err(USAGE.into()),
+            };
+
+            let connector = connector(to, &config);
+
+            let connections = connections_filtered(PcapReader::open(from)?, &options.filter)?;
+            let hellos = recorded_hellos(&connections);
+            for result in replay(&connector, hellos, options.fresh_random) {
+                println!("{}", result);
+            }
+            Ok(())
+        }
+        Some("load") => {
+            let host = args.get(1).ok_or(USAGE)?;
+            let (rate, duration) = match (options.rate, options.duration) {
+                (Some(rate), Some(duration)) => (rate, duration),
+                _ => return Err(USAGE.into()),
+            };
+
+            let connector = connector(host, &config);
+
+            let mut load_config = LoadConfig::new(rate, duration);
+            load_config.resume = options.resume;
+            eprintln!("{} handshakes/s for {:?}, Ctrl-C to stop", rate, duration);
+            print!(
+                "{}",
+                load::run(&connector, &load_config, &ShutdownToken::ctrl_c())
+            );
+            Ok(())
+        }
+        Some("bisect") => {
+            let host = args.get(1).ok_or(USAGE)?;
+            let name = options
+                .template
+                .as_deref()
+                .or(config.template.as_deref())
+                .unwrap_or("chrome");
+
+            // SNI is the host name without the port
+            let (server_name, _) = split_host(host);
+            let ch = template(name, server_name)
+                .ok_or_else(|| format!("unknown template {}, use one of {:?}", name, TEMPLATES))?;
+            let ch = if config.cipher_suites.is_empty() {
+                ch
+            } else {
+                ch.with_suites(&config.cipher_suites)
+            };
+
+            let mut probe = TcpProbe::new(host);
+            if let Some(timeout) = config.timeout {
+                probe.timeout = timeout;
+            }
+
+            let result = bisect(&mut probe, &ch)?;
+            print!("{}", result);
+            Ok(())
+        }
+        Some("raw") => {
+            let host = args.get(1).ok_or(USAGE)?;
+            raw_probe(host)
+        }
+        Some("probe") => {
+            let host = args.get(1).ok_or(USAGE)?;
+            let connector = connector(host, &config);
+
+            let ch = match options.template.as_deref().or(config.template.as_deref()) {
+                Some(name) => template(name, &connector.server_name)
+                    .map(|ch| connector.with_policy(ch))
+                    .ok_or_else(|| {
+                        format!("unknown template {}, use one of {:?}", name, TEMPLATES)
+                    })?,
+                None => connector.client_hello(),
+            };
+            let (sh, messages) = match connector.first_flight(&ch)? {
+                (ServerResponse::Hello(sh), messages) => (sh, messages),
+                (answer, _) => return Err(format!("{} answered: {}", host, answer).into()),
+            };
+            let info = ConnectionInfo::new(&sh, &messages);
+
+            if options.export_env {
+                print!("{}", export_env(&info.environment()));
+            } else {
+                print!("{}", info);
+            }
+            let trust_store = options
+                .trust_store
+                .map(PathBuf::from)
+                .or(config.trust_store);
+            if let Some(path) = &trust_store {
+                let trust = trust(path, &connector.server_name, &sh, &messages)?;
+                println!("certificate: {}", trust);
+            }
+            Ok(())
+        }
+        Some("trust-diff") => {
+            let (first, second) = (args.get(1).ok_or(USAGE)?, args.get(2).ok_or(USAGE)?);
+            let hosts = args.get(3..).filter(|x| !x.is_empty()).ok_or(USAGE)?;
+            trust_diff([Path::new(first), Path::new(second)], hosts, &config)
+        }
+        Some("scan") => {
+            let host = args.get(1).ok_or(USAGE)?;
+
+            let connector = connector(host, &config);
+
+            let only = if options.only.is_empty() {
+                &config.checks
+            } else {
+                &options.only
+            };
+            let registry = CheckRegistry::with_builtin();
+            let checks = registry.select(only, &options.skip)?;
+            let mut check_latencies = LatencyHistogram::new();
+
+            // on Ctrl-C, the check running is completed and those run are reported
+            let shutdown = ShutdownToken::ctrl_c();
+            let findings: Vec<_> = checks
+                .iter()
+                .take_while(|_| !shutdown.is_shutdown())
+                .map(|x| {
+                    let start = Instant::now();
+                    let finding = x.run(&connector);
+                    check_latencies.record(start.elapsed());
+                    finding
+                })
+                .collect();
+            if findings.len() < checks.len() {
+                eprintln!(
+                    "interrupted, {} of {} checks run",
+                    findings.len(),
+                    checks.len()
+                );
+            }
+
+            let format = options.output.or(config.output).unwrap_or_default();
+            let catalog = match options.locale.map(PathBuf::from).or(config.locale) {
+                Some(path) => Catalog::load(&path)?,
+                None => Catalog::default(),
+            };
+            print!(
+                "{}",
+                render_localized(
+                    format,
+                    &connector.address,
+                    &checks[..findings.len()],
+                    &findings,
+                    &catalog
+                )
+            );
+
+            // other formats have a fixed schema
+            if format == OutputFormat::Text {
+                println!("handshake latency: {}", connector.latencies());
+                println!("check latency: {}", check_latencies);
+            }
+
+            if let Some(threshold) = options.fail_on.or(config.fail_on) {
+                if findings.iter().any(|x| x.severity >= threshold) {
+                    std::process::exit(EXIT_FINDINGS);
+                }
+            }
+            Ok(())
+        }
+        Some("matrix") => {
+            let host = args.get(1).ok_or(USAGE)?;
+
+            let connector = connector(host, &config);
+
+            let format = options.output.or(config.output).unwrap_or_default();
+            let matrix = CapabilityMatrix::enumerate_all(&connector, &ShutdownToken::ctrl_c());
+            print!("{}", matrix.render(&connector.address, format)?);
+            if format == OutputFormat::Text {
+                println!("handshake latency: {}", connector.latencies());
+            }
+            Ok(())
+        }
+        Some("gen-corpus") => {
+            let directory = args.get(1).ok_or(USAGE)?;
+            let paths = write_corpus(directory.as_ref())?;
+            eprintln!("{} seeds written to {}", paths.len(), directory);
+            Ok(())
+        }
+        // the records and handshake messages of a flight given as text, from stdin if no file
+        // is given
+        Some("decode") => {
+            let input: Box<dyn Read> = match args.get(1).map(|x| x.as_str()) {
+                None | Some("-") => Box::new(std::io::stdin()),
+                Some(path) => Box::new(std::fs::File::open(path)?),
+            };
+            let mut decoder = TextDecoder::new(input, options.encoding.unwrap_or(Encoding::Hex));
+            if let Some(max_size) = options.max_size {
+                decoder = decoder.with_limit(max_size);
+            }
+
+            let mut events = MachineReader::new(decoder);
+            for event in &mut events {
+                match event? {
+                    Event::Record(record) => println!(
+                        "record: {} {} {}",
+                        record.type_name(),
+                        version_name(record.version),
+                        record.fragment.len()
+                    ),
+                    Event::Handshake(message) => {
+                        println!("message: {} {}", message.type_name(), message.body.len())
+                    }
+                }
+            }
+            let pending = events.machine().pending();
+            if pending > 0 {
+                println!("incomplete: {} bytes", pending);
+            }
+            Ok(())
+        }
+        // the known answer tests of the crypto and the parser round trips
+        Some("self-test") => {
+            let mut failed = 0;
+            for test in SELF_TESTS {
+                match test.run() {
+                    Ok(()) => println!("pass  {}", test.name),
+                    Err(e) => {
+                        failed += 1;
+                        println!("FAIL  {}: {}", test.name, e);
+                    }
+                }
+            }
+            println!("{} passed, {} failed", SELF_TESTS.len() - failed, failed);
+            if failed > 0 {
+                std::process::exit(EXIT_SELF_TEST);
+            }
+            Ok(())
+        }
+        Some("serve") => {
+            let address = args.get(1).ok_or(USAGE)?;
+
+            let mut server = Server::default();
+            if !config.cipher_suites.is_empty() {
+                server.config.cipher_suites = config.cipher_suites.clone();
+            }
+            if let Some(timeout) = config.timeout {
+                server.config.limits.handshake_timeout = timeout;
+            }
+            if let Some(path) = &options.cert {
+                server
+                    .config
+                    .resolver
+                    .set_default(vec![std::fs::read(path)?]);
+            }
+
+            server.config.on_event = Some(EventHandler(Arc::new(|event| eprintln!("{}", event))));
+
+            let listener = TcpListener::bind(address)?;
+            eprintln!("listening on {}, Ctrl-C to stop", listener.local_addr()?);
+            server.serve(&listener, &ShutdownToken::ctrl_c())?;
+            Ok(())
+        }
+        Some(_) => Err(USAGE.into()),
+    }
+}
+
+// print handshakes as they're seen on an interface, until Ctrl-C
+#[cfg(all(feature = "live", target_os = "linux"))]
+fn sniff(
+    interface: &str,
+    redact: bool,
+    filter: CaptureFilter,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let mut capture = LiveCapture::open(interface)?;
+    let mut tracker = HandshakeTracker::new(redact);
+    tracker.filter = filter;
+
+    let shutdown = ShutdownToken::ctrl_c();
+    eprintln!("capturing on {}, Ctrl-C to stop", interface);
+    while !shutdown.is_shutdown() {
+        if let Some(info) = capture.next_packet()?.and_then(|x| tracker.add(&x)) {
+            println!("{}", info);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(all(feature = "live", target_os = "linux")))]
+fn sniff(
+    _interface: &str,
+    _redact: bool,
+    _filter: CaptureFilter,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    Err("live capture is not available, build on Linux with --features live".into())
+}
+
+// the certificates sent by the server, or why there are none to check
+#[cfg(feature = "verify")]
+type Chain = Result<Vec<Vec<u8>>, &'static str>;
+
+#[cfg(feature = "verify")]
+fn server_chain(
+    sh: &ServerHelloSummary,
+    messages: &[HandshakeMessage],
+) -> std::result::Result<Chain, Box<dyn std::error::Error>> {
+    if sh.version() >= TLS13 {
+        return Ok(Err("not checked, encrypted with TLS 1.3"));
+    }
+    let Some(message) = messages
+        .iter()
+        .find(|x| x.msg_type == HandshakeType::certificate as u8)
+    else {
+        return Ok(Err("not sent"));
+    };
+    let mut certificate = CertificateMessage::for_version(sh.version());
+    certificate.from_network_bytes(&mut Cursor::new(message.body.clone()))?;
+    Ok(Ok(certificate
+        .certificates()
+        .into_iter()
+        .map(|x| x.to_vec())
+        .collect()))
+}
+
+#[cfg(feature = "verify")]
+fn unix_time() -> std::result::Result<i64, Box<dyn std::error::Error>> {
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    Ok(now.as_secs() as i64)
+}
+
+// whether the chain sent by the server leads to a root of the trust store, and why not
+#[cfg(feature = "verify")]
+fn trust(
+    path: &Path,
+    host: &str,
+    sh: &ServerHelloSummary,
+    messages: &[HandshakeMessage],
+) -> std::result::Result<String, Box<dyn std::error::Error>> {
+    let store = TrustStore::from_pem(&std::fs::read_to_string(path)?)?;
+    let chain = match server_chain(sh, messages)? {
+        Ok(chain) => chain,
+        Err(reason) => return Ok(String::from(reason)),
+    };
+    let chain: Vec<_> = chain.iter().map(|x| x.as_slice()).collect();
+
+    Ok(match store.verify(&chain, host, unix_time()?) {
+        Ok(()) => String::from("trusted"),
+        Err(e) => format!("not trusted, {}", e),
+    })
+}
+
+#[cfg(not(feature = "verify"))]
+fn trust(
+    _path: &Path,
+    _host: &str,
+    _sh: &ServerHelloSummary,
+    _messages: &[HandshakeMessage],
+) -> std::result::Result<String, Box<dyn std::error::Error>> {
+    Err("certificate validation is not available, build with --features verify".into())
+}
+
+// the hosts trusted under one of the stores but not the other. All chains are fetched first,
+// an intermediate sent by one host completing the chain of another
+#[cfg(feature = "verify")]
+fn trust_diff(
+    paths: [&Path; 2],
+    hosts: &[String],
+    config: &Config,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let stores = [
+        TrustStore::from_pem(&std::fs::read_to_string(paths[0])?)?,
+        TrustStore::from_pem(&std::fs::read_to_string(paths[1])?)?,
+    ];
+
+    let mut intermediates = Intermediates::default();
+    let mut chains = Vec::new();
+    for host in hosts {
+        let connector = connector(host, config);
+        let chain = match connector.first_flight(&connector.client_hello()) {
+            Ok((ServerResponse::Hello(sh), messages)) => {
+                server_chain(&sh, &messages)?.map_err(String::from)
+            }
+            Ok((answer, _)) => Err(answer.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match chain {
+            Ok(chain) => {
+                intermediates.add_chain(&chain.iter().map(|x| x.as_slice()).collect::<Vec<_>>());
+                chains.push((host, connector.server_name.clone(), chain));
+            }
+            Err(e) => eprintln!("{}: {}", host, e),
+        }
+    }
+
+    let now = unix_time()?;
+    let mut differ = 0;
+    for (host, server_name, chain) in &chains {
+        let chain: Vec<_> = chain.iter().map(|x| x.as_slice()).collect();
+        let [first, second] = stores
+            .each_ref()
+            .map(|store| store.verify_with(&chain, &intermediates, server_name, now));
+        let (trusted, other, e) = match (first, second) {
+            (Ok(()), Err(e)) => (paths[0], paths[1], e),
+            (Err(e), Ok(())) => (paths[1], paths[0], e),
+            _ => continue,
+        };
+        differ += 1;
+        println!(
+            "{}: trusted by {} only, not by {}: {}",
+            host,
+            trusted.display(),
+            other.display(),
+            e
+        );
+    }
+    println!(
+        "{} of {} hosts trusted by one store only",
+        differ,
+        chains.len()
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "verify"))]
+fn trust_diff(
+    _paths: [&Path; 2],
+    _hosts: &[String],
+    _config: &Config,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    Err("certificate validation is not available, build with --features verify".into())
+}
+
+// a connector with the timeout, handshake limits and extension checks of the configuration
+fn connector(host: &str, config: &Config) -> Connector {
+    let mut connector = Connector::new(host);
+    connector.cipher_suites = config.cipher_suites.clone();
+    if let Some(timeout) = config.timeout {
+        connector.timeout = timeout;
+    }
+    if let Some(max) = config.max_handshake_messages {
+        connector.limits.max_messages = max;
+    }
+    if let Some(budget) = config.handshake_budget {
+        connector.limits.max_duration = budget;
+    }
+    if let Some(strict) = config.strict_extensions {
+        connector.strict_extensions = strict;
+    }
+    connector
+}
+
+// send a ClientHello and print the first record the server answers
+fn raw_probe(host: &str) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (server_name, address) = split_host(host);
+
     // define new handshake
     let mut record_layer = RecordLayer {
         header: RecordHeader {
@@ -30,51 +739,35 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
             version: [3, 1],
             length: 0,
         },
-        data: Handshake::new(&vec![TLS_DHE_RSA_WITH_AES_256_CBC_SHA]),
+        data: Handshake::from_client_hello(
+            ClientHelloBuilder::new()
+                .sni(server_name)
+                .cipher_suites(&[TLS_DHE_RSA_WITH_AES_256_CBC_SHA])
+                .build()?,
+        ),
     };
     record_layer.set_length();
     println!("{:#?}", record_layer);
 
     // send client_hello
-    let mut stream = TcpStream::connect("www.google.fr:443").unwrap();
-
-    // let x = [
-    //     0x16, 0x03, 0x01, 0x00, 0xa5, 0x01, 0x00, 0x00, 0xa1, 0x03, 0x03, 0x00, 0x01, 0x02, 0x03,
-    //     0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12,
-    //     0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x00, 0x00,
-    //     0x20, 0xcc, 0xa8, 0xcc, 0xa9, 0xc0, 0x2f, 0xc0, 0x30, 0xc0, 0x2b, 0xc0, 0x2c, 0xc0, 0x13,
-    //     0xc0, 0x09, 0xc0, 0x14, 0xc0, 0x0a, 0x00, 0x9c, 0x00, 0x9d, 0x00, 0x2f, 0x00, 0x35, 0xc0,
-    //     0x12, 0x00, 0x0a, 0x01, 0x00, 0x00, 0x58, 0x00, 0x00, 0x00, 0x18, 0x00, 0x16, 0x00, 0x00,
-    //     0x13, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x75, 0x6c, 0x66, 0x68, 0x65, 0x69,
-    //     0x6d, 0x2e, 0x6e, 0x65, 0x74, 0x00, 0x05, 0x00, 0x05, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
-    //     0x0a, 0x00, 0x0a, 0x00, 0x08, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18, 0x00, 0x19, 0x00, 0x0b,
-    //     0x00, 0x02, 0x01, 0x00, 0x00, 0x0d, 0x00, 0x12, 0x00, 0x10, 0x04, 0x01, 0x04, 0x03, 0x05,
-    //     0x01, 0x05, 0x03, 0x06, 0x01, 0x06, 0x03, 0x02, 0x01, 0x02, 0x03, 0xff, 0x01, 0x00, 0x01,
-    //     0x00, 0x00, 0x12, 0x00, 0x00,
-    // ];
+    let mut stream = TcpStream::connect(address)?;
 
     let mut v = Vec::new();
     let _ = record_layer.to_network_bytes(&mut v)?;
     println!("{:0X?}", v);
 
-    stream.write(&v).unwrap();
-
-    /*/
-    let mut response = [0; 1024];
-    stream.read(&mut response).unwrap();
-    println!("response={:?}", response);
-    */
-
-    // convert to network bytes
-    //println!("{:#?}", handshake);
-    //println!("{:x?}", handshake.to_network_bytes());
+    stream.write_all(&v)?;
 
     // receive from server
-    let response = read_data(&stream).unwrap();
+    let response = read_data(&stream)?.ok_or("connection closed by the server")?;
 
     // has the handshake started ?
     match ContentType::try_from(response[0]) {
-        Ok(ContentType::change_cipher_spec) => println!("change_cipher_spec"),
+        Ok(ContentType::change_cipher_spec) => {
+            let mut ccs = ChangeCipherSpecRecord::default();
+            ccs.from_network_bytes(&mut Cursor::new(response))?;
+            println!("{:#?}", ccs);
+        }
         Ok(ContentType::alert) => {
             let mut alert = RecordLayer::<Alert>::default();
             let _ = alert.from_network_bytes(&mut Cursor::new(response));
@@ -82,6 +775,11 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         }
         Ok(ContentType::handshake) => println!("handshake"),
         Ok(ContentType::application_data) => println!("application_data"),
+        Ok(ContentType::heartbeat) => {
+            let mut heartbeat = HeartbeatRecord::default();
+            heartbeat.from_network_bytes(&mut Cursor::new(response))?;
+            println!("{:#?}", heartbeat);
+        }
         Ok(ContentType::fake) => println!("error"),
         Err(e) => println!("error {}", e),
     };
@@ -89,21 +787,16 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn read_data(stream: &TcpStream) -> Option<Vec<u8>> {
+// what the server sent, or None when it closed the connection
+fn read_data(stream: &TcpStream) -> std::io::Result<Option<Vec<u8>>> {
     let mut buffer: Vec<u8> = vec![0; 1024];
     let mut reader = BufReader::new(stream);
 
-    let result = reader.read(&mut buffer);
-
-    match result {
-        Ok(read) => {
-            println!("READ {}", buffer.len()); //Returing zero, even sending raw data on the body.
-            if read == 0 {
-                return None; //Stop the loop
-            } else {
-                return Some(buffer);
-            }
-        }
-        Err(e) => panic!("Error: {}", e),
+    let read = reader.read(&mut buffer)?;
+    if read == 0 {
+        Ok(None)
+    } else {
+        buffer.truncate(read);
+        Ok(Some(buffer))
     }
 }
@@ -0,0 +1,368 @@
+// write each connection of a capture to its own file, named after its 5-tuple and start time,
+// with the records and handshake messages sent in both directions
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::canonical::CanonicalWriter;
+use crate::dissector::hello_time::HelloTime;
+use crate::dissector::redact::redact_stream;
+use crate::dissector::summary::{ClientHelloSummary, ServerHelloSummary};
+use crate::dissector::{handshake_messages, records, HandshakeMessage, Record};
+use crate::fingerprint::ja3::ja3_hash;
+use crate::handshake::common::{cipher_suite_name, version_name};
+use crate::handshake::handshake::HandshakeType;
+use crate::pcap::flow::Connection;
+use crate::pcap::parallel::map_connections;
+use crate::scanner::report::{json_string, OutputFormat};
+
+fn find_message(messages: &[HandshakeMessage], msg_type: HandshakeType) -> Option<&[u8]> {
+    messages
+        .iter()
+        .find(|x| x.msg_type == msg_type as u8)
+        .map(|x| x.body.as_slice())
+}
+
+// one direction of a dissected connection
+#[derive(Debug, Default)]
+struct Direction {
+    records: Vec<Record>,
+    messages: Vec<HandshakeMessage>,
+}
+
+impl Direction {
+    fn new(stream: &[u8]) -> Self {
+        let records = records(stream);
+        let messages = handshake_messages(&records);
+        Self { records, messages }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectionDump<'a> {
+    connection: &'a Connection,
+    client: Direction,
+    server: Direction,
+
+    sni: Option<String>,
+    ja3: Option<String>,
+    server_hello: Option<ServerHelloSummary>,
+
+    // the clocks of both ends against the capture time, if analyzed
+    client_time: Option<HelloTime>,
+    server_time: Option<HelloTime>,
+}
+
+impl<'a> ConnectionDump<'a> {
+    // the start of the hello randoms is read as a clock with unix_time as the tolerance, if
+    // given. Redacted connections are dissected from their redacted streams, whose randoms
+    // are masked and can't be read as clocks
+    pub fn new(connection: &'a Connection, redact: bool, unix_time: Option<Duration>) -> Self {
+        let stream = |data: Vec<u8>| if redact { redact_stream(&data) } else { data };
+        let client = Direction::new(&stream(connection.client_data()));
+        let server = Direction::new(&stream(connection.server_data()));
+        let unix_time = unix_time.filter(|_| !redact);
+
+        let client_hello = find_message(&client.messages, HandshakeType::client_hello)
+            .and_then(|x| ClientHelloSummary::parse(x).ok());
+        let server_hello = find_message(&server.messages, HandshakeType::server_hello)
+            .and_then(|x| ServerHelloSummary::parse(x).ok());
+
+        let sni = client_hello.as_ref().and_then(|x| x.server_name.clone());
+
+        let time = |random: &[u8]| {
+            unix_time.and_then(|x| HelloTime::from_random(random, connection.start, x))
+        };
+        let client_time = client_hello.as_ref().and_then(|x| time(&x.random));
+        let server_time = server_hello.as_ref().and_then(|x| time(&x.random));
+
+        Self {
+            connection,
+            client,
+            server,
+            sni,
+            ja3: client_hello.as_ref().map(ja3_hash),
+            server_hello,
+            client_time,
+            server_time,
+        }
+    }
+
+    // start time, protocol and both ends: 1600000000.123456_tcp_10.0.0.1_50000_10.0.0.2_443.json.
+    // IPv6 colons are replaced so names are valid everywhere
+    pub fn file_name(&self, format: OutputFormat) -> String {
+        let start = self.connection.start;
+        let address =
+            |x: std::net::SocketAddr| format!("{}_{}", x.ip(), x.port()).replace(':', "-");
+        let extension = match format {
+            OutputFormat::Json => "json",
+            _ => "txt",
+        };
+
+        format!(
+            "{}.{:06}_tcp_{}_{}.{}",
+            start.as_secs(),
+            start.subsec_micros(),
+            address(self.connection.client),
+            address(self.connection.server),
+            extension
+        )
+    }
+
+    pub fn render(&self, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Text => Ok(self.text()),
+            OutputFormat::Json => Ok(self.json()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "connections can only be written as text or JSON",
+            )),
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut w = CanonicalWriter::default();
+        w.field("client", self.connection.client);
+        w.field("server", self.connection.server);
+        w.field(
+            "start",
+            format!(
+                "{}.{:06}",
+                self.connection.start.as_secs(),
+                self.connection.start.subsec_micros()
+            ),
+        );
+        if let Some(sni) = &self.sni {
+            w.field("sni", sni);
+        }
+        if let Some(ja3) = &self.ja3 {
+            w.field("ja3", ja3);
+        }
+        if let Some(server_hello) = &self.server_hello {
+            w.field("version", version_name(server_hello.version()));
+            w.field(
+                "cipher_suite",
+                cipher_suite_name(server_hello.cipher_suite.to_be_bytes()),
+            );
+        }
+        if let Some(time) = &self.client_time {
+            w.field("client_time", time);
+        }
+        if let Some(time) = &self.server_time {
+            w.field("server_time", time);
+        }
+
+        for (name, direction) in [("client", &self.client), ("server", &self.server)] {
+            w.field("direction", name);
+            for record in &direction.records {
+                w.field(
+                    "record",
+                    format!(
+                        "{} {} {}",
+                        record.type_name(),
+                        version_name(record.version),
+                        record.fragment.len()
+                    ),
+                );
+            }
+            for message in &direction.messages {
+                w.field(
+                    "message",
+                    format!("{} {}", message.type_name(), message.body.len()),
+                );
+            }
+        }
+
+        w.finish()
+    }
+
+    fn json(&self) -> String {
+        let optional = |x: Option<String>| x.map(|x| json_string(&x)).unwrap_or("null".into());
+
+        let direction = |direction: &Direction| {
+            let records: Vec<String> = direction
+                .records
+                .iter()
+                .map(|x| {
+                    format!(
+                        "{{\"content_type\":{},\"version\":{},\"length\":{}}}",
+                        json_string(&x.type_name()),
+                        json_string(&version_name(x.version)),
+                        x.fragment.len()
+                    )
+                })
+                .collect();
+            let messages: Vec<String> = direction
+                .messages
+                .iter()
+                .map(|x| {
+                    format!(
+                        "{{\"type\":{},\"length\":{}}}",
+                        json_string(&x.type_name()),
+                        x.body.len()
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"records\":[{}],\"messages\":[{}]}}",
+                records.join(","),
+                messages.join(",")
+            )
+        };
+
+        format!(
+            "{{\"client\":{},\"server\":{},\"start\":{}.{:06},\"sni\":{},\"ja3\":{},\
+             \"version\":{},\"cipher_suite\":{},\"client_time\":{},\"server_time\":{},\
+             \"client_data\":{},\"server_data\":{}}}\n",
+            json_string(&self.connection.client.to_string()),
+            json_string(&self.connection.server.to_string()),
+            self.connection.start.as_secs(),
+            self.connection.start.subsec_micros(),
+            optional(self.sni.clone()),
+            optional(self.ja3.clone()),
+            optional(
+                self.server_hello
+                    .as_ref()
+                    .map(|x| version_name(x.version()))
+            ),
+            optional(
+                self.server_hello
+                    .as_ref()
+                    .map(|x| cipher_suite_name(x.cipher_suite.to_be_bytes()))
+            ),
+            optional(self.client_time.map(|x| x.to_string())),
+            optional(self.server_time.map(|x| x.to_string())),
+            direction(&self.client),
+            direction(&self.server)
+        )
+    }
+}
+
+// write one file per connection in the directory, which is created if needed. Files are
+// rendered on several threads, and their paths returned in capture order
+pub fn write_connections(
+    connections: &[Connection],
+    directory: &Path,
+    format: OutputFormat,
+    redact: bool,
+    threads: usize,
+    unix_time: Option<Duration>,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(directory)?;
+
+    map_connections(connections, threads, |_: &mut (), connection| {
+        let dump = ConnectionDump::new(connection, redact, unix_time);
+        let path = directory.join(dump.file_name(format));
+        std::fs::write(&path, dump.render(format)?)?;
+        Ok(path)
+    })
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::redact::redact_host_name;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::pcap::flow::connections;
+    use crate::pcap::flow::tests::capture;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::pcap::reader::PcapReader;
+    use crate::{handshake, record};
+    use std::io::Cursor;
+
+    fn capture_connections() -> Vec<Connection> {
+        let record = record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO));
+
+        let client = ([10, 0, 0, 1], 50000);
+        let server = ([10, 0, 0, 2], 443);
+        let frames = vec![
+            ethernet_frame(client, server, 0, 0x02, &[]),
+            ethernet_frame(client, server, 1, 0x10, &record),
+            ethernet_frame(server, client, 0, 0x12, &[]),
+            ethernet_frame(
+                server,
+                client,
+                1,
+                0x10,
+                &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28],
+            ),
+        ];
+
+        connections(PcapReader::new(Cursor::new(capture(&frames))).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn render() {
+        let connections = capture_connections();
+        let dump = ConnectionDump::new(&connections[0], false, None);
+
+        assert_eq!(
+            dump.file_name(OutputFormat::Json),
+            "0.000000_tcp_10.0.0.1_50000_10.0.0.2_443.json"
+        );
+
+        let text = dump.render(OutputFormat::Text).unwrap();
+        assert!(text.starts_with("client: 10.0.0.1:50000\nserver: 10.0.0.2:443\n"));
+        assert!(text.contains("sni: example.ulfheim.net\n"));
+        assert!(text.contains(
+            "direction: client\nrecord: handshake(22) TLS 1.0 165\nmessage: client_hello(1) 161\n"
+        ));
+        assert!(text.ends_with("direction: server\nrecord: alert(21) TLS 1.2 2\n"));
+
+        let json = dump.render(OutputFormat::Json).unwrap();
+        assert!(json.contains("\"sni\":\"example.ulfheim.net\""));
+        assert!(json.contains("\"version\":null"));
+        assert!(json.contains("\"client_time\":null"));
+        assert!(json.contains(
+            "\"server_data\":{\"records\":[{\"content_type\":\"alert(21)\",\"version\":\"TLS 1.2\",\"length\":2}],\"messages\":[]}"
+        ));
+
+        assert!(dump.render(OutputFormat::Csv).is_err());
+
+        let dump = ConnectionDump::new(&connections[0], true, Some(Duration::from_secs(86400)));
+        let text = dump.render(OutputFormat::Text).unwrap();
+        assert!(!text.contains("ulfheim"));
+        assert!(text.contains(&format!(
+            "sni: {}\n",
+            redact_host_name("example.ulfheim.net")
+        )));
+        assert!(!text.contains("client_time"));
+
+        // the random starts with 66051, 18 hours after the capture started
+        let dump = ConnectionDump::new(&connections[0], false, Some(Duration::from_secs(86400)));
+        let text = dump.render(OutputFormat::Text).unwrap();
+        assert!(text.contains("client_time: 66051 (skew +66051s)\n"));
+        assert!(!text.contains("server_time"));
+        let json = dump.render(OutputFormat::Json).unwrap();
+        assert!(json.contains("\"client_time\":\"66051 (skew +66051s)\",\"server_time\":null"));
+
+        let dump = ConnectionDump::new(&connections[0], false, Some(Duration::from_secs(3600)));
+        assert!(dump
+            .render(OutputFormat::Text)
+            .unwrap()
+            .contains("client_time: randomized\n"));
+    }
+
+    #[test]
+    fn write() {
+        let directory =
+            std::env::temp_dir().join(format!("tls_explore_dump_{}", std::process::id()));
+        let paths = write_connections(
+            &capture_connections(),
+            &directory,
+            OutputFormat::Text,
+            false,
+            2,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(paths.len(), 1);
+        assert!(std::fs::read_to_string(&paths[0])
+            .unwrap()
+            .contains("ja3: "));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
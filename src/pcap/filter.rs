@@ -0,0 +1,294 @@
+// BPF-style capture filters, like "port 443 and not host 10.0.0.1" or "sni *.example.com or
+// ja3 e7d705a3286e19ea42f587b344ee6865". Primitives are combined with and, or, not and
+// parentheses, not binding tighter than and, and and tighter than or.
+//
+// Addresses are known as soon as a packet is seen, but sni and ja3 need the ClientHello. So
+// a filter is first used to drop packets of connections which can't match, before anything
+// is dissected, and then checked again on each ClientHello
+use std::io::{Error, ErrorKind, Result};
+use std::net::{IpAddr, SocketAddr};
+
+use crate::dissector::summary::ClientHelloSummary;
+use crate::fingerprint::ja3::ja3_hash;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    // either side of the connection
+    Port(u16),
+    Host(IpAddr),
+
+    // glob pattern, matched without case
+    Sni(String),
+
+    // lowercase hex JA3 hash
+    Ja3(String),
+
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    // None when the result depends on a ClientHello which isn't known yet
+    fn eval(
+        &self,
+        client: &SocketAddr,
+        server: &SocketAddr,
+        hello: Option<&ClientHelloSummary>,
+    ) -> Option<bool> {
+        match self {
+            Expr::Port(port) => Some(client.port() == *port || server.port() == *port),
+            Expr::Host(ip) => Some(client.ip() == *ip || server.ip() == *ip),
+            Expr::Sni(pattern) => hello.map(|x| match &x.server_name {
+                Some(name) => glob(pattern, &name.to_ascii_lowercase()),
+                None => false,
+            }),
+            Expr::Ja3(hash) => hello.map(|x| ja3_hash(x) == *hash),
+            Expr::Not(expr) => expr.eval(client, server, hello).map(|x| !x),
+            Expr::And(a, b) => {
+                let (a, b) = (a.eval(client, server, hello), b.eval(client, server, hello));
+                match (a, b) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Expr::Or(a, b) => {
+                let (a, b) = (a.eval(client, server, hello), b.eval(client, server, hello));
+                match (a, b) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+// shell-like pattern: * matches any sequence of characters, ? a single one
+fn glob(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // position of the last * and of the value character it's matching up to
+    let (mut p, mut v) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                // let the last * match one more character
+                Some((sp, sv)) => {
+                    star = Some((sp, sv + 1));
+                    p = sp + 1;
+                    v = sv + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("invalid filter: {}", msg))
+}
+
+// recursive descent over whitespace-separated tokens
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while matches!(self.peek(), Some("or" | "||")) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while matches!(self.peek(), Some("and" | "&&")) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some("not" | "!") => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some("(") => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(")") => Ok(expr),
+                    _ => Err(invalid(String::from("missing )"))),
+                }
+            }
+            Some(primitive @ ("port" | "host" | "sni" | "ja3")) => {
+                let value = self
+                    .next()
+                    .ok_or_else(|| invalid(format!("missing value after {}", primitive)))?;
+                primitive_expr(primitive, value)
+            }
+            Some(token) => Err(invalid(format!("unexpected {}", token))),
+            None => Err(invalid(String::from("unexpected end"))),
+        }
+    }
+}
+
+fn primitive_expr(primitive: &str, value: &str) -> Result<Expr> {
+    let bad_value = || invalid(format!("bad value {} for {}", value, primitive));
+
+    match primitive {
+        "port" => value.parse().map(Expr::Port).map_err(|_| bad_value()),
+        "host" => value.parse().map(Expr::Host).map_err(|_| bad_value()),
+        "sni" => Ok(Expr::Sni(value.to_ascii_lowercase())),
+        _ => {
+            if value.len() != 32 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(bad_value());
+            }
+            Ok(Expr::Ja3(value.to_ascii_lowercase()))
+        }
+    }
+}
+
+// the default filter lets everything through
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureFilter {
+    expr: Option<Expr>,
+}
+
+impl CaptureFilter {
+    pub fn parse(text: &str) -> Result<Self> {
+        // parentheses and ! don't need to be separated by spaces
+        let spaced = text
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .replace('!', " ! ");
+        let tokens: Vec<_> = spaced.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {}", token)));
+        }
+
+        Ok(Self { expr: Some(expr) })
+    }
+
+    // false if no connection between these addresses can match, whatever its ClientHello
+    pub fn may_match(&self, client: &SocketAddr, server: &SocketAddr) -> bool {
+        match &self.expr {
+            Some(expr) => expr.eval(client, server, None) != Some(false),
+            None => true,
+        }
+    }
+
+    // the final decision, once the ClientHello is known. Without one, sni and ja3 don't match
+    pub fn matches(
+        &self,
+        client: &SocketAddr,
+        server: &SocketAddr,
+        hello: Option<&ClientHelloSummary>,
+    ) -> bool {
+        let empty = ClientHelloSummary::default();
+        match &self.expr {
+            Some(expr) => expr.eval(client, server, Some(hello.unwrap_or(&empty))) == Some(true),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+
+    #[test]
+    fn globs() {
+        assert!(glob("*.example.com", "www.example.com"));
+        assert!(!glob("*.example.com", "example.com"));
+        assert!(glob("*example.com", "example.com"));
+        assert!(glob("www.?oogle.*", "www.google.fr"));
+        assert!(glob("a*b*c", "aXXbYbc"));
+        assert!(!glob("a*b*c", "aXXbYb"));
+        assert!(glob("*", ""));
+    }
+
+    #[test]
+    fn filter() {
+        let client: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+        let server: SocketAddr = "10.0.0.2:443".parse().unwrap();
+        let hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let ja3 = ja3_hash(&hello);
+
+        let filter = CaptureFilter::parse(
+            "port 443 and (sni *.ULFHEIM.net or ja3 0123456789abcdef0123456789abcdef)",
+        )
+        .unwrap();
+        assert!(filter.may_match(&client, &server));
+        assert!(filter.matches(&client, &server, Some(&hello)));
+        assert!(!filter.matches(&client, &server, None));
+
+        // decided from addresses only
+        let filter = CaptureFilter::parse("not host 10.0.0.2 and sni *").unwrap();
+        assert!(!filter.may_match(&client, &server));
+        let filter = CaptureFilter::parse("port 443 or sni foo").unwrap();
+        assert!(filter.matches(&client, &server, None));
+
+        let filter = CaptureFilter::parse(&format!("!port 8443 && ja3 {}", ja3)).unwrap();
+        assert!(filter.matches(&client, &server, Some(&hello)));
+
+        assert_eq!(CaptureFilter::parse(" ").unwrap(), CaptureFilter::default());
+        assert!(CaptureFilter::default().matches(&client, &server, None));
+    }
+
+    #[test]
+    fn errors() {
+        for text in [
+            "port",
+            "port http",
+            "host example.com",
+            "ja3 1234",
+            "(port 443",
+            "port 443 sni foo",
+            "port 443 and",
+            "vlan 1",
+        ] {
+            assert!(CaptureFilter::parse(text).is_err(), "{}", text);
+        }
+
+        let err = CaptureFilter::parse("port 443 or )").unwrap_err();
+        assert_eq!(err.to_string(), "invalid filter: unexpected )");
+    }
+}
@@ -0,0 +1,245 @@
+// group TCP segments into connections and reassemble both directions
+use std::collections::HashMap;
+use std::io::{Read, Result};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::pcap::filter::CaptureFilter;
+use crate::pcap::packet::TcpSegment;
+use crate::pcap::reader::PcapReader;
+
+// one direction of a TCP connection
+#[derive(Debug, Default, Clone)]
+pub struct HalfFlow {
+    // initial sequence number, if the SYN was captured
+    isn: Option<u32>,
+
+    // segments as (sequence number, payload)
+    segments: Vec<(u32, Vec<u8>)>,
+}
+
+impl HalfFlow {
+    fn add(&mut self, segment: &TcpSegment) {
+        if segment.is_syn() {
+            self.isn = Some(segment.seq);
+        }
+        if !segment.payload.is_empty() {
+            self.segments.push((segment.seq, segment.payload.clone()));
+        }
+    }
+
+    // reassemble payloads in sequence order, dropping retransmissions. Reassembly stops
+    // at the first hole because data after it can't be reliably used
+    pub fn reassemble(&self) -> Vec<u8> {
+        let base = match (self.isn, self.segments.first()) {
+            (Some(isn), _) => isn.wrapping_add(1),
+            (None, Some((seq, _))) => *seq,
+            (None, None) => return Vec::new(),
+        };
+
+        // sequence numbers relative to the first byte, to cope with wrapping
+        let mut segments: Vec<_> = self
+            .segments
+            .iter()
+            .map(|(seq, data)| (seq.wrapping_sub(base), data))
+            .collect();
+        segments.sort_by_key(|(offset, _)| *offset);
+
+        let mut stream = Vec::new();
+        for (offset, data) in segments {
+            let offset = offset as usize;
+            let end = offset + data.len();
+
+            if offset > stream.len() {
+                break;
+            }
+            if end > stream.len() {
+                stream.extend_from_slice(&data[stream.len() - offset..]);
+            }
+        }
+
+        stream
+    }
+}
+
+// a TCP connection with both directions of data
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+
+    // timestamp of the first packet seen for this connection
+    pub start: Duration,
+
+    pub client_flow: HalfFlow,
+    pub server_flow: HalfFlow,
+
+    // whether the client was identified from the TCP handshake
+    client_from_syn: bool,
+}
+
+impl Connection {
+    pub(crate) fn new(segment: &TcpSegment, timestamp: Duration) -> Self {
+        // without a SYN, assume the server is the one with the lowest port number
+        let (client, server) = if segment.is_client_syn() || segment.src.port() > segment.dst.port()
+        {
+            (segment.src, segment.dst)
+        } else {
+            (segment.dst, segment.src)
+        };
+
+        Self {
+            client,
+            server,
+            start: timestamp,
+            client_flow: HalfFlow::default(),
+            server_flow: HalfFlow::default(),
+            client_from_syn: segment.is_client_syn(),
+        }
+    }
+
+    pub(crate) fn add(&mut self, segment: &TcpSegment) {
+        // a SYN tells us for sure who's the client
+        if segment.is_client_syn() && !self.client_from_syn {
+            if segment.src != self.client {
+                std::mem::swap(&mut self.client, &mut self.server);
+                std::mem::swap(&mut self.client_flow, &mut self.server_flow);
+            }
+            self.client_from_syn = true;
+        }
+
+        if segment.src == self.client {
+            self.client_flow.add(segment);
+        } else {
+            self.server_flow.add(segment);
+        }
+    }
+
+    // data sent by the client
+    pub fn client_data(&self) -> Vec<u8> {
+        self.client_flow.reassemble()
+    }
+
+    // data sent by the server
+    pub fn server_data(&self) -> Vec<u8> {
+        self.server_flow.reassemble()
+    }
+}
+
+// same key for both directions of a connection
+pub(crate) fn connection_key(segment: &TcpSegment) -> (SocketAddr, SocketAddr) {
+    if segment.src < segment.dst {
+        (segment.src, segment.dst)
+    } else {
+        (segment.dst, segment.src)
+    }
+}
+
+// read all packets from a capture and return TCP connections, in the order they were first seen
+pub fn connections<R: Read>(reader: PcapReader<R>) -> Result<Vec<Connection>> {
+    connections_filtered(reader, &CaptureFilter::default())
+}
+
+// same as connections(), skipping segments between addresses the filter can't match
+pub fn connections_filtered<R: Read>(
+    reader: PcapReader<R>,
+    filter: &CaptureFilter,
+) -> Result<Vec<Connection>> {
+    let mut index: HashMap<(SocketAddr, SocketAddr), usize> = HashMap::new();
+    let mut connections: Vec<Connection> = Vec::new();
+
+    for packet in reader {
+        let packet = packet?;
+        let segment = match TcpSegment::from_packet(&packet) {
+            Some(segment) => segment,
+            None => continue,
+        };
+        if !filter.may_match(&segment.src, &segment.dst) {
+            continue;
+        }
+
+        let key = connection_key(&segment);
+
+        // a new SYN on a known 4-tuple means the port has been reused
+        let i = match index.get(&key) {
+            Some(i) if !(segment.is_client_syn() && connections[*i].client_from_syn) => *i,
+            _ => {
+                connections.push(Connection::new(&segment, packet.timestamp));
+                index.insert(key, connections.len() - 1);
+                connections.len() - 1
+            }
+        };
+
+        connections[i].add(&segment);
+    }
+
+    Ok(connections)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::pcap::reader::LINKTYPE_ETHERNET;
+    use std::io::Cursor;
+
+    // pcap file from a list of Ethernet frames
+    pub(crate) fn capture(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xD4, 0xC3, 0xB2, 0xA1, 0x02, 0x00, 0x04, 0x00];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+        data.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+        for (i, frame) in frames.iter().enumerate() {
+            data.extend_from_slice(&(i as u32).to_le_bytes());
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(frame);
+        }
+
+        data
+    }
+
+    #[test]
+    fn reassembly() {
+        let client = ([192, 168, 1, 1], 40000);
+        let server = ([192, 168, 1, 2], 443);
+
+        let frames = vec![
+            ethernet_frame(client, server, 99, 0x02, &[]),
+            ethernet_frame(server, client, 499, 0x12, &[]),
+            // out of order, with a retransmission
+            ethernet_frame(client, server, 104, 0x10, b"efgh"),
+            ethernet_frame(client, server, 100, 0x10, b"abc"),
+            ethernet_frame(client, server, 100, 0x10, b"abcd"),
+            ethernet_frame(server, client, 500, 0x10, b"hello"),
+        ];
+
+        let connections =
+            connections(PcapReader::new(Cursor::new(capture(&frames))).unwrap()).unwrap();
+        assert_eq!(connections.len(), 1);
+
+        let connection = &connections[0];
+        assert_eq!(connection.client, "192.168.1.1:40000".parse().unwrap());
+        assert_eq!(connection.server, "192.168.1.2:443".parse().unwrap());
+        assert_eq!(connection.client_data(), b"abcdefgh");
+        assert_eq!(connection.server_data(), b"hello");
+    }
+
+    #[test]
+    fn hole() {
+        let client = ([192, 168, 1, 1], 40000);
+        let server = ([192, 168, 1, 2], 443);
+
+        // no SYN captured, and a missing segment
+        let frames = vec![
+            ethernet_frame(client, server, 1000, 0x10, b"abc"),
+            ethernet_frame(client, server, 1010, 0x10, b"xyz"),
+        ];
+
+        let connections =
+            connections(PcapReader::new(Cursor::new(capture(&frames))).unwrap()).unwrap();
+        assert_eq!(connections[0].client_data(), b"abc");
+    }
+}
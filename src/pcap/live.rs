@@ -0,0 +1,113 @@
+// capture packets from a network interface with a Linux AF_PACKET socket. Needs root or
+// the CAP_NET_RAW capability
+use std::ffi::CString;
+use std::io::{Error, ErrorKind, Result};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::pcap::reader::{Packet, LINKTYPE_ETHERNET};
+
+// reads return after this delay even without traffic, so a shutdown can be noticed
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+// large enough for a jumbo frame
+const SNAPLEN: usize = 65536;
+
+pub struct LiveCapture {
+    fd: libc::c_int,
+    buffer: Vec<u8>,
+}
+
+impl LiveCapture {
+    pub fn open(interface: &str) -> Result<Self> {
+        let name = CString::new(interface)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid interface name"))?;
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index == 0 {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("unknown interface {}", interface),
+            ));
+        }
+
+        // all protocols, in network byte order
+        let protocol = (libc::ETH_P_ALL as u16).to_be();
+        let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW, protocol as libc::c_int) };
+        if fd < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        // closed on drop if something fails below
+        let capture = Self {
+            fd,
+            buffer: vec![0; SNAPLEN],
+        };
+
+        let mut address: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+        address.sll_family = libc::AF_PACKET as u16;
+        address.sll_protocol = protocol;
+        address.sll_ifindex = index as libc::c_int;
+        let rc = unsafe {
+            libc::bind(
+                fd,
+                &address as *const libc::sockaddr_ll as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        let timeout = libc::timeval {
+            tv_sec: READ_TIMEOUT.as_secs() as libc::time_t,
+            tv_usec: READ_TIMEOUT.subsec_micros() as libc::suseconds_t,
+        };
+        let rc = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(capture)
+    }
+
+    // the next packet, or None if nothing was received before the read timeout
+    pub fn next_packet(&mut self) -> Result<Option<Packet>> {
+        let length = unsafe {
+            libc::recv(
+                self.fd,
+                self.buffer.as_mut_ptr() as *mut libc::c_void,
+                self.buffer.len(),
+                0,
+            )
+        };
+
+        if length < 0 {
+            let error = Error::last_os_error();
+            return match error.kind() {
+                ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted => Ok(None),
+                _ => Err(error),
+            };
+        }
+
+        Ok(Some(Packet {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            link_type: LINKTYPE_ETHERNET,
+            data: self.buffer[..length as usize].to_vec(),
+        }))
+    }
+}
+
+impl Drop for LiveCapture {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
@@ -0,0 +1,9 @@
+pub mod dump;
+pub mod filter;
+pub mod flow;
+#[cfg(all(feature = "live", target_os = "linux"))]
+pub mod live;
+pub mod packet;
+pub mod parallel;
+pub mod reader;
+pub mod sniff;
@@ -0,0 +1,247 @@
+// decode link, network and transport layers down to the TCP payload
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::pcap::reader::{
+    Packet, LINKTYPE_ETHERNET, LINKTYPE_IPV4, LINKTYPE_IPV6, LINKTYPE_LINUX_SLL,
+    LINKTYPE_LINUX_SLL2, LINKTYPE_NULL, LINKTYPE_RAW,
+};
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+
+const IPPROTO_TCP: u8 = 6;
+
+// TCP flags
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+// a TCP segment extracted from a captured packet
+#[derive(Debug, Clone)]
+pub struct TcpSegment {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub seq: u32,
+    pub flags: u8,
+    pub payload: Vec<u8>,
+}
+
+impl TcpSegment {
+    // decode a captured packet: None if it's not a TCP segment we can understand
+    pub fn from_packet(packet: &Packet) -> Option<Self> {
+        let (ethertype, ip) = match packet.link_type {
+            LINKTYPE_ETHERNET => ethernet(&packet.data)?,
+            LINKTYPE_LINUX_SLL => (be16(&packet.data, 14)?, packet.data.get(16..)?),
+            LINKTYPE_LINUX_SLL2 => (be16(&packet.data, 0)?, packet.data.get(20..)?),
+            LINKTYPE_NULL => (ip_version(packet.data.get(4..)?)?, packet.data.get(4..)?),
+            LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => {
+                (ip_version(&packet.data)?, &packet.data[..])
+            }
+            _ => return None,
+        };
+
+        match ethertype {
+            ETHERTYPE_IPV4 => ipv4(ip),
+            ETHERTYPE_IPV6 => ipv6(ip),
+            _ => None,
+        }
+    }
+
+    pub fn is_syn(&self) -> bool {
+        self.flags & TCP_SYN != 0
+    }
+
+    // the first segment of the three-way handshake is sent by the client
+    pub fn is_client_syn(&self) -> bool {
+        self.flags & (TCP_SYN | TCP_ACK) == TCP_SYN
+    }
+
+    pub fn is_fin(&self) -> bool {
+        self.flags & TCP_FIN != 0
+    }
+
+    pub fn is_rst(&self) -> bool {
+        self.flags & TCP_RST != 0
+    }
+}
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+// no link layer: guess the ethertype from the IP version nibble
+fn ip_version(data: &[u8]) -> Option<u16> {
+    match data.first()? >> 4 {
+        4 => Some(ETHERTYPE_IPV4),
+        6 => Some(ETHERTYPE_IPV6),
+        _ => None,
+    }
+}
+
+// skip the Ethernet header, including VLAN tags
+fn ethernet(data: &[u8]) -> Option<(u16, &[u8])> {
+    let mut offset = 12;
+    let mut ethertype = be16(data, offset)?;
+
+    while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+        offset += 4;
+        ethertype = be16(data, offset)?;
+    }
+
+    Some((ethertype, data.get(offset + 2..)?))
+}
+
+fn ipv4(data: &[u8]) -> Option<TcpSegment> {
+    let header_length = ((data.first()? & 0x0F) as usize) * 4;
+    let total_length = be16(data, 2)? as usize;
+
+    // fragments other than the first one are not supported
+    let fragment_offset = be16(data, 6)? & 0x1FFF;
+    if data.get(9)? != &IPPROTO_TCP || fragment_offset != 0 || header_length < 20 {
+        return None;
+    }
+
+    let addresses = data.get(12..20)?;
+    let src = Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+    let dst = Ipv4Addr::new(addresses[4], addresses[5], addresses[6], addresses[7]);
+
+    // Ethernet might add padding to small frames, so rely on the IP length
+    let end = total_length.min(data.len());
+    tcp(
+        IpAddr::V4(src),
+        IpAddr::V4(dst),
+        data.get(header_length..end)?,
+    )
+}
+
+fn ipv6(data: &[u8]) -> Option<TcpSegment> {
+    // only a TCP header directly following the fixed header is supported
+    if data.get(6)? != &IPPROTO_TCP {
+        return None;
+    }
+
+    let payload_length = be16(data, 4)? as usize;
+    let src: [u8; 16] = data.get(8..24)?.try_into().ok()?;
+    let dst: [u8; 16] = data.get(24..40)?.try_into().ok()?;
+
+    let end = (40 + payload_length).min(data.len());
+    tcp(
+        IpAddr::V6(Ipv6Addr::from(src)),
+        IpAddr::V6(Ipv6Addr::from(dst)),
+        data.get(40..end)?,
+    )
+}
+
+fn tcp(src: IpAddr, dst: IpAddr, data: &[u8]) -> Option<TcpSegment> {
+    let src_port = be16(data, 0)?;
+    let dst_port = be16(data, 2)?;
+    let seq = be32(data, 4)?;
+    let data_offset = ((data.get(12)? >> 4) as usize) * 4;
+    let flags = *data.get(13)?;
+
+    Some(TcpSegment {
+        src: SocketAddr::new(src, src_port),
+        dst: SocketAddr::new(dst, dst_port),
+        seq,
+        flags,
+        payload: data.get(data_offset..)?.to_vec(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // build an Ethernet/IPv4/TCP frame
+    pub(crate) fn ethernet_frame(
+        src: ([u8; 4], u16),
+        dst: ([u8; 4], u16),
+        seq: u32,
+        flags: u8,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&[0x08, 0x00]);
+
+        // IPv4 header without options
+        let total_length = (20 + 20 + payload.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0x00]);
+        frame.extend_from_slice(&total_length.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, IPPROTO_TCP, 0, 0]);
+        frame.extend_from_slice(&src.0);
+        frame.extend_from_slice(&dst.0);
+
+        // TCP header without options
+        frame.extend_from_slice(&src.1.to_be_bytes());
+        frame.extend_from_slice(&dst.1.to_be_bytes());
+        frame.extend_from_slice(&seq.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0, 0x50, flags, 0xFF, 0xFF, 0, 0, 0, 0]);
+        frame.extend_from_slice(payload);
+
+        frame
+    }
+
+    #[test]
+    fn ethernet_ipv4_tcp() {
+        let packet = Packet {
+            timestamp: Duration::default(),
+            link_type: LINKTYPE_ETHERNET,
+            data: ethernet_frame(
+                ([10, 0, 0, 1], 50000),
+                ([10, 0, 0, 2], 443),
+                1234,
+                TCP_ACK,
+                &[0x16, 0x03, 0x01],
+            ),
+        };
+
+        let segment = TcpSegment::from_packet(&packet).unwrap();
+        assert_eq!(segment.src, "10.0.0.1:50000".parse().unwrap());
+        assert_eq!(segment.dst, "10.0.0.2:443".parse().unwrap());
+        assert_eq!(segment.seq, 1234);
+        assert!(!segment.is_syn());
+        assert_eq!(segment.payload, &[0x16, 0x03, 0x01]);
+    }
+
+    #[test]
+    fn not_tcp() {
+        let mut data = ethernet_frame(([10, 0, 0, 1], 53), ([10, 0, 0, 2], 53), 0, 0, &[]);
+
+        // UDP
+        data[14 + 9] = 17;
+        let packet = Packet {
+            timestamp: Duration::default(),
+            link_type: LINKTYPE_ETHERNET,
+            data,
+        };
+        assert!(TcpSegment::from_packet(&packet).is_none());
+    }
+
+    #[test]
+    fn truncated_ipv4() {
+        let data = ethernet_frame(([10, 0, 0, 1], 50000), ([10, 0, 0, 2], 443), 0, 0, &[]);
+
+        // cut in the addresses
+        for length in [14 + 10, 14 + 13, 14 + 17, 14 + 19] {
+            let packet = Packet {
+                timestamp: Duration::default(),
+                link_type: LINKTYPE_ETHERNET,
+                data: data[..length].to_vec(),
+            };
+            assert!(TcpSegment::from_packet(&packet).is_none());
+        }
+    }
+}
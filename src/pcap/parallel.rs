@@ -0,0 +1,90 @@
+// dissect the connections of a capture on several threads. Workers pick the next connection
+// to process from a shared counter, so a few big connections don't leave the other threads
+// idle, and results are put back in capture order before being returned
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::pcap::flow::Connection;
+
+// number of threads used when none is given: one per core
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|x| x.get())
+        .unwrap_or(1)
+}
+
+// call f on each connection and return the results in the same order as connections.
+// Each worker has its own scratch state S, reused for all the connections it processes
+pub fn map_connections<S, T, F>(connections: &[Connection], threads: usize, f: F) -> Vec<T>
+where
+    S: Default,
+    T: Send,
+    F: Fn(&mut S, &Connection) -> T + Sync,
+{
+    let threads = threads.clamp(1, connections.len().max(1));
+    if threads == 1 {
+        let mut scratch = S::default();
+        return connections.iter().map(|x| f(&mut scratch, x)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, T)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut scratch = S::default();
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match connections.get(i) {
+                            Some(connection) => results.push((i, f(&mut scratch, connection))),
+                            None => break results,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|x| x.join().expect("dissection thread panicked"))
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, x)| x).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcap::flow::connections;
+    use crate::pcap::flow::tests::capture;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::pcap::reader::PcapReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn ordered() {
+        let server = ([10, 0, 0, 2], 443);
+        let frames: Vec<_> = (0..50)
+            .map(|i| ethernet_frame(([10, 0, 0, 1], 40000 + i), server, 0, 0x02, &[]))
+            .collect();
+        let connections =
+            connections(PcapReader::new(Cursor::new(capture(&frames))).unwrap()).unwrap();
+
+        let expected: Vec<_> = connections.iter().map(|x| x.client.port()).collect();
+        for threads in [0, 1, 4, 100] {
+            let ports = map_connections(&connections, threads, |_: &mut (), x| x.client.port());
+            assert_eq!(ports, expected);
+        }
+
+        // scratch state is kept between the connections of a worker
+        let counts = map_connections(&connections, 1, |n: &mut usize, _| {
+            *n += 1;
+            *n
+        });
+        assert_eq!(counts.last(), Some(&50));
+
+        assert!(map_connections(&[], 4, |_: &mut (), x| x.client.port()).is_empty());
+    }
+}
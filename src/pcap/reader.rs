@@ -0,0 +1,389 @@
+// read packets from a capture file, either in the classic pcap format:
+// https://wiki.wireshark.org/Development/LibpcapFileFormat
+// or in the pcapng format: https://datatracker.ietf.org/doc/html/draft-tuexen-opsawg-pcapng
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Result};
+use std::path::Path;
+use std::time::Duration;
+
+// packets and blocks longer than this are taken as corrupt, rather than allocated: tcpdump's
+// largest snapshot length
+const MAX_LENGTH: usize = 256 * 1024;
+
+// magic numbers found at the beginning of capture files
+const PCAP_MICROSECONDS: u32 = 0xA1B2C3D4;
+const PCAP_NANOSECONDS: u32 = 0xA1B23C4D;
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B3C4D;
+
+// pcapng block types we're interested in
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+// link layer types: https://www.tcpdump.org/linktypes.html
+pub const LINKTYPE_NULL: u32 = 0;
+pub const LINKTYPE_ETHERNET: u32 = 1;
+pub const LINKTYPE_RAW: u32 = 101;
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+pub const LINKTYPE_IPV4: u32 = 228;
+pub const LINKTYPE_IPV6: u32 = 229;
+pub const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+// a single captured packet
+#[derive(Debug, Default, Clone)]
+pub struct Packet {
+    // time since EPOCH when the packet was captured
+    pub timestamp: Duration,
+
+    // link layer type of the data
+    pub link_type: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+enum Format {
+    // classic pcap: link type and timestamp resolution are global
+    Pcap { link_type: u32, nanoseconds: bool },
+
+    // pcapng: each interface has its link type
+    PcapNg { link_types: Vec<u32> },
+}
+
+pub struct PcapReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+    format: Format,
+}
+
+impl PcapReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        PcapReader::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    // read the global header (pcap) or the first section header block (pcapng)
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+
+        let (big_endian, format) = match (u32::from_be_bytes(magic), u32::from_le_bytes(magic)) {
+            (PCAP_MICROSECONDS, _) => (
+                true,
+                Format::Pcap {
+                    link_type: 0,
+                    nanoseconds: false,
+                },
+            ),
+            (_, PCAP_MICROSECONDS) => (
+                false,
+                Format::Pcap {
+                    link_type: 0,
+                    nanoseconds: false,
+                },
+            ),
+            (PCAP_NANOSECONDS, _) => (
+                true,
+                Format::Pcap {
+                    link_type: 0,
+                    nanoseconds: true,
+                },
+            ),
+            (_, PCAP_NANOSECONDS) => (
+                false,
+                Format::Pcap {
+                    link_type: 0,
+                    nanoseconds: true,
+                },
+            ),
+            (PCAPNG_SECTION_HEADER, _) => {
+                // block length is written before the byte-order magic, so we need to read both
+                let mut header = [0u8; 8];
+                reader.read_exact(&mut header)?;
+                let big_endian = u32::from_be_bytes([header[4], header[5], header[6], header[7]])
+                    == PCAPNG_BYTE_ORDER;
+
+                let mut pcap = Self {
+                    reader,
+                    big_endian,
+                    format: Format::PcapNg {
+                        link_types: Vec::new(),
+                    },
+                };
+
+                // skip the rest of the section header block
+                let length = pcap.to_u32(&header[0..4]) as usize;
+                pcap.skip_bytes(length.saturating_sub(12))?;
+                return Ok(pcap);
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("not a pcap or pcapng file (magic {:02X?})", magic),
+                ))
+            }
+        };
+
+        let mut pcap = Self {
+            reader,
+            big_endian,
+            format,
+        };
+
+        // rest of the global header: version, thiszone, sigfigs, snaplen, network
+        let mut header = [0u8; 20];
+        pcap.reader.read_exact(&mut header)?;
+        let network = pcap.to_u32(&header[16..20]);
+        if let Format::Pcap { link_type, .. } = &mut pcap.format {
+            *link_type = network;
+        }
+
+        Ok(pcap)
+    }
+
+    // return the next packet, or None at the end of the file
+    pub fn next_packet(&mut self) -> Result<Option<Packet>> {
+        match self.format {
+            Format::Pcap { .. } => self.next_pcap_packet(),
+            Format::PcapNg { .. } => self.next_pcapng_packet(),
+        }
+    }
+
+    fn next_pcap_packet(&mut self) -> Result<Option<Packet>> {
+        let mut header = [0u8; 16];
+        if !self.read_or_eof(&mut header)? {
+            return Ok(None);
+        }
+
+        let seconds = self.to_u32(&header[0..4]) as u64;
+        let fraction = self.to_u32(&header[4..8]);
+        let included = self.to_u32(&header[8..12]) as usize;
+
+        let (link_type, nanoseconds) = match self.format {
+            Format::Pcap {
+                link_type,
+                nanoseconds,
+            } => (link_type, nanoseconds),
+            _ => unreachable!(),
+        };
+
+        let timestamp = if nanoseconds {
+            Duration::new(seconds, fraction)
+        } else {
+            Duration::new(seconds, 0) + Duration::from_micros(fraction as u64)
+        };
+
+        if included > MAX_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("pcap packet too long ({} bytes)", included),
+            ));
+        }
+        let mut data = vec![0u8; included];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(Packet {
+            timestamp,
+            link_type,
+            data,
+        }))
+    }
+
+    fn next_pcapng_packet(&mut self) -> Result<Option<Packet>> {
+        loop {
+            let mut header = [0u8; 8];
+            if !self.read_or_eof(&mut header)? {
+                return Ok(None);
+            }
+
+            let block_type = self.to_u32(&header[0..4]);
+            let length = self.to_u32(&header[4..8]) as usize;
+            if length < 12 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("pcapng block too short ({} bytes)", length),
+                ));
+            }
+            if length > MAX_LENGTH {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("pcapng block too long ({} bytes)", length),
+                ));
+            }
+
+            // body is followed by the trailing block length
+            let mut body = vec![0u8; length - 8];
+            self.reader.read_exact(&mut body)?;
+            body.truncate(length - 12);
+
+            match block_type {
+                PCAPNG_SECTION_HEADER if body.len() >= 4 => {
+                    // new section: interfaces are numbered again from 0
+                    self.big_endian = u32::from_be_bytes([body[0], body[1], body[2], body[3]])
+                        == PCAPNG_BYTE_ORDER;
+                    if let Format::PcapNg { link_types } = &mut self.format {
+                        link_types.clear();
+                    }
+                }
+                PCAPNG_INTERFACE_DESCRIPTION if body.len() >= 2 => {
+                    let link_type = self.to_u16(&body[0..2]) as u32;
+                    if let Format::PcapNg { link_types } = &mut self.format {
+                        link_types.push(link_type);
+                    }
+                }
+                PCAPNG_ENHANCED_PACKET if body.len() >= 20 => {
+                    let interface = self.to_u32(&body[0..4]) as usize;
+                    let timestamp =
+                        (self.to_u32(&body[4..8]) as u64) << 32 | self.to_u32(&body[8..12]) as u64;
+                    let captured = (self.to_u32(&body[12..16]) as usize).min(body.len() - 20);
+
+                    return Ok(Some(Packet {
+                        // default interface resolution is microseconds
+                        timestamp: Duration::from_micros(timestamp),
+                        link_type: self.link_type(interface),
+                        data: body[20..20 + captured].to_vec(),
+                    }));
+                }
+                PCAPNG_SIMPLE_PACKET if body.len() >= 4 => {
+                    return Ok(Some(Packet {
+                        timestamp: Duration::default(),
+                        link_type: self.link_type(0),
+                        data: body[4..].to_vec(),
+                    }));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn link_type(&self, interface: usize) -> u32 {
+        match &self.format {
+            Format::Pcap { link_type, .. } => *link_type,
+            Format::PcapNg { link_types } => link_types
+                .get(interface)
+                .copied()
+                .unwrap_or(LINKTYPE_ETHERNET),
+        }
+    }
+
+    // fill the buffer, returning false if we're at the end of the file
+    fn read_or_eof(&mut self, buffer: &mut [u8]) -> Result<bool> {
+        match self.reader.read_exact(buffer) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn skip_bytes(&mut self, length: usize) -> Result<()> {
+        let mut buffer = vec![0u8; length];
+        self.reader.read_exact(&mut buffer)
+    }
+
+    fn to_u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn to_u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn pcap() {
+        // little endian global header with Ethernet link type, then one 4-byte packet
+        let mut data = vec![0xD4, 0xC3, 0xB2, 0xA1, 0x02, 0x00, 0x04, 0x00];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0xFF, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&[0x10, 0, 0, 0, 0x20, 0, 0, 0, 4, 0, 0, 0, 4, 0, 0, 0]);
+        data.extend_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let mut reader = PcapReader::new(Cursor::new(data)).unwrap();
+        let packet = reader.next_packet().unwrap().unwrap();
+        assert_eq!(packet.link_type, LINKTYPE_ETHERNET);
+        assert_eq!(packet.timestamp, Duration::new(16, 32_000));
+        assert_eq!(packet.data, &[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert!(reader.next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn pcapng() {
+        // section header block
+        let mut data = vec![0x0A, 0x0D, 0x0D, 0x0A, 28, 0, 0, 0, 0x4D, 0x3C, 0x2B, 0x1A];
+        data.extend_from_slice(&[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        data.extend_from_slice(&[28, 0, 0, 0]);
+
+        // interface description block: raw IP
+        data.extend_from_slice(&[
+            1, 0, 0, 0, 20, 0, 0, 0, 101, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0,
+        ]);
+
+        // enhanced packet block with 3 bytes of data, padded to 4
+        data.extend_from_slice(&[6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0, 0x40, 0x42, 0x0F, 0, 3, 0, 0, 0, 3, 0, 0, 0]);
+        data.extend_from_slice(&[0x45, 0x00, 0x01, 0x00, 36, 0, 0, 0]);
+
+        let packets: Vec<_> = PcapReader::new(Cursor::new(data))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].link_type, LINKTYPE_RAW);
+        assert_eq!(packets[0].timestamp, Duration::from_secs(1));
+        assert_eq!(packets[0].data, &[0x45, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn not_a_capture() {
+        assert!(PcapReader::new(Cursor::new(vec![0u8; 24])).is_err());
+    }
+
+    #[test]
+    fn too_long() {
+        // a packet header claiming 4 GiB
+        let mut data = vec![0xD4, 0xC3, 0xB2, 0xA1, 0x02, 0x00, 0x04, 0x00];
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0xFF, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&[0xFF; 8]);
+        let mut reader = PcapReader::new(Cursor::new(data)).unwrap();
+        let e = reader.next_packet().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+        // a pcapng block of 4 GiB after the section header
+        let mut data = vec![0x0A, 0x0D, 0x0D, 0x0A, 28, 0, 0, 0, 0x4D, 0x3C, 0x2B, 0x1A];
+        data.extend_from_slice(&[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        data.extend_from_slice(&[28, 0, 0, 0]);
+        data.extend_from_slice(&[6, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let e = PcapReader::new(Cursor::new(data))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(e.to_string().contains("too long"), "{}", e);
+    }
+}
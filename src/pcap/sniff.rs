@@ -0,0 +1,266 @@
+// follow TLS handshakes packet by packet, as they're captured live, and report each
+// connection as soon as its hellos have been seen
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::dissector::redact::redact_host_name;
+use crate::dissector::summary::{ClientHelloSummary, ServerHelloSummary};
+use crate::dissector::{handshake_messages, records};
+use crate::fingerprint::ja3::ja3_hash;
+use crate::handshake::common::{cipher_suite_name, version_name, ProtocolVersion};
+use crate::handshake::handshake::HandshakeType;
+use crate::pcap::filter::CaptureFilter;
+use crate::pcap::flow::{connection_key, Connection};
+use crate::pcap::packet::TcpSegment;
+use crate::pcap::reader::Packet;
+
+// connections sending more than this without a hello are not TLS, and are not followed anymore
+const MAX_HANDSHAKE_BYTES: usize = 64 * 1024;
+
+// what's known of a handshake when it's reported
+#[derive(Debug, Clone)]
+pub struct HandshakeInfo {
+    // time of the first packet of the connection
+    pub start: Duration,
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+
+    pub sni: Option<String>,
+    pub ja3: String,
+
+    // None if the connection ended before the ServerHello
+    pub version: Option<ProtocolVersion>,
+    pub cipher_suite: Option<u16>,
+}
+
+impl fmt::Display for HandshakeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} {} -> {} sni={} ja3={}",
+            self.start.as_secs(),
+            self.start.subsec_micros(),
+            self.client,
+            self.server,
+            self.sni.as_deref().unwrap_or("<none>"),
+            self.ja3
+        )?;
+
+        match (self.version, self.cipher_suite) {
+            (Some(version), Some(suite)) => write!(
+                f,
+                " version={} suite={}",
+                version_name(version),
+                cipher_suite_name(suite.to_be_bytes())
+            ),
+            _ => write!(f, " no answer"),
+        }
+    }
+}
+
+// first message of the given type sent in a stream
+fn find_message(stream: &[u8], msg_type: HandshakeType) -> Option<Vec<u8>> {
+    handshake_messages(&records(stream))
+        .into_iter()
+        .find(|x| x.msg_type == msg_type as u8)
+        .map(|x| x.body)
+}
+
+#[derive(Debug)]
+struct Tracked {
+    connection: Connection,
+    client_hello: Option<ClientHelloSummary>,
+}
+
+#[derive(Debug, Default)]
+pub struct HandshakeTracker {
+    // replace host names by pseudonyms
+    pub redact: bool,
+
+    // only handshakes accepted by the filter are reported
+    pub filter: CaptureFilter,
+
+    connections: HashMap<(SocketAddr, SocketAddr), Tracked>,
+}
+
+impl HandshakeTracker {
+    pub fn new(redact: bool) -> Self {
+        Self {
+            redact,
+            ..Default::default()
+        }
+    }
+
+    // connections being followed
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    // add a captured packet, returning the handshake of its connection if it can be reported:
+    // the ServerHello has been received, or the connection ended after the ClientHello
+    pub fn add(&mut self, packet: &Packet) -> Option<HandshakeInfo> {
+        let segment = TcpSegment::from_packet(packet)?;
+        if !self.filter.may_match(&segment.src, &segment.dst) {
+            return None;
+        }
+        let key = connection_key(&segment);
+
+        // a new SYN on a known 4-tuple means the port has been reused
+        if segment.is_client_syn() {
+            self.connections.remove(&key);
+        }
+        let tracked = self.connections.entry(key).or_insert_with(|| Tracked {
+            connection: Connection::new(&segment, packet.timestamp),
+            client_hello: None,
+        });
+        tracked.connection.add(&segment);
+
+        let client_data = tracked.connection.client_data();
+        if tracked.client_hello.is_none() {
+            tracked.client_hello = find_message(&client_data, HandshakeType::client_hello)
+                .and_then(|x| ClientHelloSummary::parse(&x).ok());
+        }
+
+        let server_hello = match tracked.client_hello {
+            Some(_) => find_message(
+                &tracked.connection.server_data(),
+                HandshakeType::server_hello,
+            )
+            .and_then(|x| ServerHelloSummary::parse(&x).ok()),
+            None => None,
+        };
+
+        let ended = segment.is_fin() || segment.is_rst();
+        if server_hello.is_none() && !ended {
+            if tracked.client_hello.is_none() && client_data.len() > MAX_HANDSHAKE_BYTES {
+                self.connections.remove(&key);
+            }
+            return None;
+        }
+
+        let tracked = self.connections.remove(&key)?;
+        let client_hello = tracked.client_hello?;
+        let connection = &tracked.connection;
+        if !self
+            .filter
+            .matches(&connection.client, &connection.server, Some(&client_hello))
+        {
+            return None;
+        }
+
+        let sni = match client_hello.server_name {
+            Some(ref name) if self.redact => Some(redact_host_name(name)),
+            ref name => name.clone(),
+        };
+
+        Some(HandshakeInfo {
+            start: tracked.connection.start,
+            client: tracked.connection.client,
+            server: tracked.connection.server,
+            sni,
+            ja3: ja3_hash(&client_hello),
+            version: server_hello.as_ref().map(|x| x.version()),
+            cipher_suite: server_hello.map(|x| x.cipher_suite),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::pcap::reader::LINKTYPE_ETHERNET;
+    use crate::{extension, handshake, record};
+
+    fn packet(
+        src: ([u8; 4], u16),
+        dst: ([u8; 4], u16),
+        seq: u32,
+        flags: u8,
+        data: &[u8],
+    ) -> Packet {
+        Packet {
+            timestamp: Duration::from_secs(1),
+            link_type: LINKTYPE_ETHERNET,
+            data: ethernet_frame(src, dst, seq, flags, data),
+        }
+    }
+
+    #[test]
+    fn tracker() {
+        let client = ([10, 0, 0, 1], 50000);
+        let server = ([10, 0, 0, 2], 443);
+
+        let client_hello = record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO));
+
+        // TLS 1.3 ServerHello with TLS_AES_128_GCM_SHA256
+        let server_hello = record!(
+            0x16,
+            [0x03, 0x03],
+            handshake!(
+                0x02,
+                [0x03, 0x03],
+                [0x11; 32],
+                [0x00, 0x13, 0x01, 0x00, 0x00, 0x06],
+                extension!(0x002B, [0x03, 0x04])
+            )
+        );
+
+        let mut tracker = HandshakeTracker::new(false);
+        assert!(tracker.add(&packet(client, server, 0, 0x02, &[])).is_none());
+        assert!(tracker
+            .add(&packet(client, server, 1, 0x10, &client_hello[..40]))
+            .is_none());
+        assert!(tracker
+            .add(&packet(client, server, 41, 0x10, &client_hello[40..]))
+            .is_none());
+        assert_eq!(tracker.len(), 1);
+
+        let info = tracker
+            .add(&packet(server, client, 1, 0x10, &server_hello))
+            .unwrap();
+        assert_eq!(info.client, "10.0.0.1:50000".parse().unwrap());
+        assert_eq!(info.sni.as_deref(), Some("example.ulfheim.net"));
+        assert_eq!(info.version, Some([0x03, 0x04]));
+        assert_eq!(info.cipher_suite, Some(0x1301));
+        assert!(info
+            .to_string()
+            .ends_with("version=TLS 1.3 suite=TLS_AES_128_GCM_SHA256"));
+        assert!(tracker.is_empty());
+
+        // the connection is closed without an answer
+        let mut tracker = HandshakeTracker::new(true);
+        assert!(tracker
+            .add(&packet(client, server, 1, 0x10, &client_hello))
+            .is_none());
+        let info = tracker.add(&packet(server, client, 1, 0x11, &[])).unwrap();
+        assert_eq!(info.sni, Some(redact_host_name("example.ulfheim.net")));
+        assert!(info.to_string().ends_with("no answer"));
+
+        // filtered out, before or after the ClientHello
+        for filter in ["port 8443", "sni www.*"] {
+            tracker.filter = CaptureFilter::parse(filter).unwrap();
+            assert!(tracker
+                .add(&packet(client, server, 1, 0x10, &client_hello))
+                .is_none());
+            assert!(tracker.add(&packet(server, client, 1, 0x11, &[])).is_none());
+        }
+        tracker.filter = CaptureFilter::default();
+
+        // not TLS
+        assert!(tracker
+            .add(&packet(client, server, 1, 0x10, b"GET / HTTP/1.1"))
+            .is_none());
+        assert!(tracker
+            .add(&packet(client, server, 15, 0x11, &[]))
+            .is_none());
+        assert!(tracker.is_empty());
+    }
+}
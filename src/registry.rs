@@ -0,0 +1,110 @@
+// all the wire types of the crate, registered at startup by their TlsDerive or TlsEnum derive,
+// or by tls_register! for the ones implementing TlsDerive by hand. A single test can then go
+// over every type, and a new one can't be left out
+use std::fmt::Debug;
+use std::io::{Cursor, Error, ErrorKind, Result};
+use std::sync::{Arc, Mutex};
+
+use crate::derive_tls::TlsDerive;
+
+#[derive(Clone)]
+pub struct WireType {
+    pub name: &'static str,
+    pub module: &'static str,
+    round_trip: Arc<dyn Fn() -> Result<()> + Send + Sync>,
+}
+
+impl WireType {
+    // see round_trip()
+    pub fn round_trip(&self) -> Result<()> {
+        (self.round_trip)()
+    }
+}
+
+static TYPES: Mutex<Vec<WireType>> = Mutex::new(Vec::new());
+
+// called by tls_register!, the sample value being the default one unless given
+pub fn register<T: TlsDerive + Default + Debug + 'static>(
+    name: &'static str,
+    module: &'static str,
+    sample: fn() -> T,
+) {
+    if let Ok(mut types) = TYPES.lock() {
+        types.push(WireType {
+            name,
+            module,
+            round_trip: Arc::new(move || round_trip(sample)),
+        });
+    }
+}
+
+// the registered types, sorted by module and name
+pub fn wire_types() -> Vec<WireType> {
+    let mut types = TYPES.lock().map(|x| x.clone()).unwrap_or_default();
+    types.sort_by_key(|x| (x.module, x.name));
+    types
+}
+
+// the sample value is encoded with the length it announces, then decoded and encoded again to
+// the same bytes
+fn round_trip<T: TlsDerive + Default + Debug>(sample: fn() -> T) -> Result<()> {
+    let value = sample();
+    let mut bytes = Vec::new();
+    let length = value.to_network_bytes(&mut bytes)?;
+    if length != bytes.len() || length != value.tls_len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{:?}: {} bytes written, {} returned, tls_len() is {}",
+                value,
+                bytes.len(),
+                length,
+                value.tls_len()
+            ),
+        ));
+    }
+
+    let mut decoded = T::default();
+    decoded.from_network_bytes(&mut Cursor::new(bytes.clone()))?;
+    let mut again = Vec::new();
+    decoded.to_network_bytes(&mut again)?;
+    if again != bytes {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{:?} is encoded differently once decoded", value),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let types = wire_types();
+        for name in [
+            "ClientHello",
+            "KeyShareEntry",
+            "ContentType",
+            "AlertDescription",
+        ] {
+            assert!(
+                types.iter().any(|x| x.name == name),
+                "{} not registered",
+                name
+            );
+        }
+
+        let failures: Vec<_> = types
+            .iter()
+            .filter_map(|x| {
+                x.round_trip()
+                    .err()
+                    .map(|e| format!("{}::{}: {}", x.module, x.name, e))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+}
@@ -0,0 +1,198 @@
+// send the ClientHellos found in a capture to another server, and compare its answers with
+// the ones recorded in the capture
+use std::fmt;
+use std::io::Result;
+use std::net::SocketAddr;
+
+use crate::dissector::summary::ClientHelloSummary;
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::handshake::HandshakeType;
+use crate::pcap::flow::Connection;
+use crate::scanner::connector::{first_message, Connector, ServerResponse};
+
+// a ClientHello from a capture, with the answer the server sent at the time
+#[derive(Debug)]
+pub struct RecordedHello {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub sni: Option<String>,
+
+    // the whole message, including its header
+    pub message: Vec<u8>,
+    pub response: ServerResponse,
+}
+
+// the first ClientHello of each connection, in capture order
+pub fn recorded_hellos(connections: &[Connection]) -> Vec<RecordedHello> {
+    connections
+        .iter()
+        .filter_map(|connection| {
+            let message = handshake_messages(&records(&connection.client_data()))
+                .into_iter()
+                .find(|x| x.msg_type == HandshakeType::client_hello as u8)?;
+            let sni = ClientHelloSummary::parse(&message.body)
+                .ok()
+                .and_then(|x| x.server_name);
+
+            // an answer which can't be understood is the same as no answer for a client
+            let response = match first_message(&connection.server_data()) {
+                Ok(Some(response)) => response,
+                _ => ServerResponse::Closed,
+            };
+
+            Some(RecordedHello {
+                client: connection.client,
+                server: connection.server,
+                sni,
+                message: message.to_bytes(),
+                response,
+            })
+        })
+        .collect()
+}
+
+// the same ClientHello with a new random, so a server can't recognize a replay
+pub fn with_fresh_random(message: &[u8]) -> Vec<u8> {
+    let mut message = message.to_vec();
+
+    // header and client_version come first
+    if let Some(random) = message.get_mut(6..38) {
+        random.copy_from_slice(&rand::random::<[u8; 32]>());
+    }
+    message
+}
+
+#[derive(Debug)]
+pub struct ReplayResult {
+    pub hello: RecordedHello,
+    pub replayed: Result<ServerResponse>,
+}
+
+impl ReplayResult {
+    // answers are compared by their descriptions, so randoms and session IDs don't matter
+    pub fn is_same(&self) -> bool {
+        match &self.replayed {
+            Ok(response) => response.to_string() == self.hello.response.to_string(),
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ReplayResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} sni={}: recorded {}, replayed ",
+            self.hello.client,
+            self.hello.server,
+            self.hello.sni.as_deref().unwrap_or("<none>"),
+            self.hello.response
+        )?;
+
+        match &self.replayed {
+            Ok(response) => write!(f, "{}", response)?,
+            Err(e) => write!(f, "error {}", e)?,
+        }
+        write!(f, " [{}]", if self.is_same() { "same" } else { "changed" })
+    }
+}
+
+// send each hello to the connector's server, one after the other
+pub fn replay(
+    connector: &Connector,
+    hellos: Vec<RecordedHello>,
+    fresh_random: bool,
+) -> Vec<ReplayResult> {
+    hellos
+        .into_iter()
+        .map(|hello| {
+            let replayed = if fresh_random {
+                connector.send_raw(&with_fresh_random(&hello.message))
+            } else {
+                connector.send_raw(&hello.message)
+            };
+            ReplayResult { hello, replayed }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::pcap::flow::connections;
+    use crate::pcap::flow::tests::capture;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::pcap::reader::PcapReader;
+    use crate::server::server::Server;
+    use crate::{handshake, record};
+    use std::io::Cursor;
+    use std::net::TcpListener;
+
+    fn recorded() -> Vec<RecordedHello> {
+        let record = record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO));
+
+        let client = ([10, 0, 0, 1], 50000);
+        let server = ([10, 0, 0, 2], 443);
+        let frames = vec![
+            ethernet_frame(client, server, 1, 0x10, &record),
+            // handshake_failure
+            ethernet_frame(
+                server,
+                client,
+                1,
+                0x10,
+                &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28],
+            ),
+            // no ClientHello
+            ethernet_frame(([10, 0, 0, 3], 50001), server, 1, 0x10, b"GET /"),
+        ];
+
+        let connections =
+            connections(PcapReader::new(Cursor::new(capture(&frames))).unwrap()).unwrap();
+        recorded_hellos(&connections)
+    }
+
+    #[test]
+    fn extract() {
+        let hellos = recorded();
+        assert_eq!(hellos.len(), 1);
+        assert_eq!(hellos[0].sni.as_deref(), Some("example.ulfheim.net"));
+        assert_eq!(hellos[0].message.len(), 4 + CLIENT_HELLO.len());
+        assert_eq!(
+            hellos[0].response.to_string(),
+            "alert 2 handshake_failure(40)"
+        );
+
+        let fresh = with_fresh_random(&hellos[0].message);
+        assert_eq!(fresh.len(), hellos[0].message.len());
+        assert_eq!(fresh[..6], hellos[0].message[..6]);
+        assert_ne!(fresh[6..38], hellos[0].message[6..38]);
+        assert_eq!(fresh[38..], hellos[0].message[38..]);
+    }
+
+    #[test]
+    fn mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        connector.timeout = std::time::Duration::from_secs(2);
+
+        let mut server = Server::default();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+
+        let results = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = server.handle(&mut stream);
+            });
+
+            replay(&connector, recorded(), true)
+        });
+
+        assert_eq!(results.len(), 1);
+        let response = results[0].replayed.as_ref().unwrap();
+        assert!(matches!(response, ServerResponse::Hello(_)));
+        assert!(!results[0].is_same());
+        assert!(results[0].to_string().ends_with("[changed]"));
+    }
+}
@@ -0,0 +1,651 @@
+// the client side of an abbreviated handshake, resuming a session by its ID: the ClientHello
+// offers the ID of the session, and if the server echoes it, there's no key exchange. The
+// server sends its ChangeCipherSpec and Finished right after the ServerHello, then the
+// client answers with its own: https://datatracker.ietf.org/doc/html/rfc5246#section-7.3.
+// A session can also be resumed with a ticket, sent in the session_ticket extension along
+// with a random session ID the server echoes if it accepts the ticket:
+// https://datatracker.ietf.org/doc/html/rfc5077#section-3.4.
+// The keys are expanded from the master secret of the session and the new randoms, and only
+// the AES CBC suites of TLS 1.2 can protect the Finished messages
+use std::io::{Cursor, Result};
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::cbc::CbcCipher;
+use crate::crypto::key_block::{KeyBlock, SuiteParameters};
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ServerHelloSummary;
+use crate::dissector::{HandshakeMessage, Record};
+use crate::handshake::client_hello::{ClientHello, ExtensionType, GenericExtension};
+use crate::handshake::common::{CipherSuite, ContentType, ProtocolVersion};
+use crate::handshake::constants::TLS12;
+use crate::handshake::finished::{Finished, FinishedSecret, Sender};
+use crate::handshake::handshake::{Handshake, HandshakeType};
+use crate::handshake::new_session_ticket::NewSessionTicket;
+use crate::handshake::record_layer::RecordHeader;
+use crate::handshake::transcript::Transcript;
+use crate::scanner::connector::hello_record;
+use crate::scanner::flight::ServerFlight;
+use crate::server::server::change_cipher_spec;
+
+// what the client keeps of a completed handshake to resume its session. The suite and version
+// are checked when the session is made, and can't be changed after
+#[derive(Clone, PartialEq)]
+pub struct ClientSession {
+    pub session_id: Vec<u8>,
+    cipher_suite: CipherSuite,
+    version: ProtocolVersion,
+    master_secret: Vec<u8>,
+
+    // the last ticket of the server, and its lifetime hint in seconds, 0 if unspecified
+    pub ticket: Option<Vec<u8>>,
+    pub lifetime_hint: u32,
+}
+
+// the master secret is not shown
+impl std::fmt::Debug for ClientSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientSession")
+            .field("session_id", &self.session_id)
+            .field("cipher_suite", &self.cipher_suite)
+            .field("version", &self.version)
+            .field("ticket", &self.ticket)
+            .field("lifetime_hint", &self.lifetime_hint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientSession {
+    // None for an empty session ID, which can't be resumed, or if the records of the suite
+    // can't be protected. The keys are derived with the TLS 1.2 PRF, so the session must be
+    // a TLS 1.2 one
+    pub fn new(
+        session_id: &[u8],
+        cipher_suite: CipherSuite,
+        version: ProtocolVersion,
+        master_secret: &[u8],
+    ) -> Option<Self> {
+        if session_id.is_empty() || !CbcCipher::supports(cipher_suite) || version != TLS12 {
+            return None;
+        }
+
+        Some(Self {
+            session_id: session_id.to_vec(),
+            cipher_suite,
+            version,
+            master_secret: master_secret.to_vec(),
+            ticket: None,
+            lifetime_hint: 0,
+        })
+    }
+
+    // the session of a ticket, offered with a random session ID. None for an empty ticket
+    pub fn from_ticket(
+        ticket: &[u8],
+        lifetime_hint: u32,
+        cipher_suite: CipherSuite,
+        version: ProtocolVersion,
+        master_secret: &[u8],
+    ) -> Option<Self> {
+        if ticket.is_empty() {
+            return None;
+        }
+
+        let mut session = Self::new(
+            &rand::random::<[u8; 32]>(),
+            cipher_suite,
+            version,
+            master_secret,
+        )?;
+        session.ticket = Some(ticket.to_vec());
+        session.lifetime_hint = lifetime_hint;
+        Some(session)
+    }
+
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResumptionState {
+    WaitServerHello,
+
+    // the server echoed the session ID, a NewSessionTicket can come before its
+    // ChangeCipherSpec
+    WaitChangeCipherSpec,
+    WaitFinished,
+
+    // both Finished were exchanged
+    Done,
+
+    // the server didn't echo the session ID and goes on with a full handshake, which is left
+    // to the caller with AbbreviatedHandshake::full_handshake
+    Full,
+}
+
+#[derive(Debug)]
+pub struct AbbreviatedHandshake {
+    session: ClientSession,
+    client_hello: ClientHello,
+    client_random: Vec<u8>,
+    state: ResumptionState,
+    flight: ServerFlight,
+    transcript: Transcript,
+
+    // handshake bytes received, not yet a whole message. In the Full state, all those of the
+    // server from its ServerHello on
+    buffer: Vec<u8>,
+
+    // the client's and the server's, once the server resumed the session
+    ciphers: Option<(CbcCipher, CbcCipher)>,
+
+    // the NewSessionTicket the server sent, if any
+    new_ticket: Option<NewSessionTicket>,
+}
+
+// a record of the version of the session
+fn record(content_type: ContentType, version: ProtocolVersion, fragment: &[u8]) -> Result<Vec<u8>> {
+    let header = RecordHeader {
+        content_type,
+        version,
+        length: fragment.len() as u16,
+    };
+    let mut v = Vec::new();
+    header.to_network_bytes(&mut v)?;
+    v.extend_from_slice(fragment);
+    Ok(v)
+}
+
+impl AbbreviatedHandshake {
+    // the hello is sent with the ID of the session instead of its own, and its ticket if
+    // it has one
+    pub fn new(session: ClientSession, client_hello: &ClientHello) -> Result<Self> {
+        let mut client_hello = client_hello.with_session_id(&session.session_id);
+        if let Some(ticket) = &session.ticket {
+            client_hello = client_hello.with_extension_replaced(GenericExtension::new(
+                ExtensionType::session_ticket,
+                ticket,
+            ));
+        }
+
+        // the suite is known by ClientSession::new
+        let hash = SuiteParameters::from_suite(session.cipher_suite)
+            .unwrap()
+            .prf_hash;
+        let mut transcript = Transcript::new(hash);
+        let mut message = Vec::new();
+        Handshake::from_client_hello(client_hello.clone()).to_network_bytes(&mut message)?;
+        transcript.add(&message);
+
+        // after the message header and the version
+        Ok(Self {
+            session,
+            client_hello,
+            client_random: message[6..38].to_vec(),
+            state: ResumptionState::WaitServerHello,
+            flight: ServerFlight::new(),
+            transcript,
+            buffer: Vec::new(),
+            ciphers: None,
+            new_ticket: None,
+        })
+    }
+
+    pub fn state(&self) -> ResumptionState {
+        self.state
+    }
+
+    // the session to resume the next time, once the handshake is done: with the new ticket
+    // if the server sent one, as the ticket of a session can't be reused after it's renewed
+    pub fn next_session(&self) -> Option<ClientSession> {
+        if self.state != ResumptionState::Done {
+            return None;
+        }
+        let session = &self.session;
+        match &self.new_ticket {
+            Some(nst) => ClientSession::from_ticket(
+                &nst.ticket.data,
+                nst.ticket_lifetime_hint,
+                session.cipher_suite,
+                session.version,
+                &session.master_secret,
+            ),
+            None => Some(session.clone()),
+        }
+    }
+
+    // the handshake messages of the server from its ServerHello on, as received so far, once
+    // it went on with a full handshake. The last one may be incomplete
+    pub fn full_handshake(&self) -> Option<&[u8]> {
+        (self.state == ResumptionState::Full).then_some(&self.buffer[..])
+    }
+
+    // the first flight of the client
+    pub fn hello_record(&self) -> Result<Vec<u8>> {
+        hello_record(&self.client_hello)
+    }
+
+    // the handshake messages of the buffer which are complete
+    fn messages(&mut self) -> Vec<HandshakeMessage> {
+        let mut messages = Vec::new();
+        while self.buffer.len() >= 4 {
+            let length = u32::from_be_bytes([0, self.buffer[1], self.buffer[2], self.buffer[3]]);
+            let end = 4 + length as usize;
+            if self.buffer.len() < end {
+                break;
+            }
+            let message: Vec<u8> = self.buffer.drain(..end).collect();
+            messages.push(HandshakeMessage {
+                msg_type: message[0],
+                body: message[4..].to_vec(),
+            });
+        }
+        messages
+    }
+
+    // the server echoed the session ID: the suite and version must be the ones of the session
+    fn resume(&mut self, sh: &ServerHelloSummary) -> std::result::Result<(), AlertDescription> {
+        if sh.cipher_suite.to_be_bytes() != self.session.cipher_suite
+            || sh.server_version != self.session.version
+        {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        // the suite is an AES CBC one, with the key lengths of its parameters
+        let parameters = SuiteParameters::from_suite(self.session.cipher_suite).unwrap();
+        let keys = KeyBlock::new(
+            &parameters,
+            &self.session.master_secret,
+            &self.client_random,
+            &sh.random,
+        );
+        let cipher = |mac_key: &[u8], key: &[u8]| {
+            CbcCipher::new(
+                self.session.cipher_suite,
+                self.session.version,
+                mac_key,
+                key,
+            )
+            .ok_or(AlertDescription::illegal_parameter)
+        };
+        self.ciphers = Some((
+            cipher(&keys.client_mac_key, &keys.client_key)?,
+            cipher(&keys.server_mac_key, &keys.server_key)?,
+        ));
+
+        self.flight.abbreviated();
+        self.state = ResumptionState::WaitChangeCipherSpec;
+        Ok(())
+    }
+
+    // the plaintext messages of the server up to its ChangeCipherSpec
+    fn handshake(&mut self, fragment: &[u8]) -> std::result::Result<(), AlertDescription> {
+        self.buffer.extend_from_slice(fragment);
+        let mut messages = self.messages().into_iter();
+        while let Some(message) = messages.next() {
+            self.flight
+                .accept(message.msg_type)
+                .map_err(|_| AlertDescription::unexpected_message)?;
+            self.transcript.add(&message.to_bytes());
+
+            if message.msg_type == HandshakeType::new_session_ticket as u8 {
+                let mut nst = NewSessionTicket::default();
+                nst.from_network_bytes(&mut Cursor::new(message.body))
+                    .map_err(|_| AlertDescription::decode_error)?;
+                self.new_ticket = Some(nst);
+            } else if message.msg_type == HandshakeType::server_hello as u8 {
+                let sh = ServerHelloSummary::parse(&message.body)
+                    .map_err(|_| AlertDescription::decode_error)?;
+                if sh.session_id == self.session.session_id {
+                    self.resume(&sh)?;
+                } else {
+                    // the ServerHello and what follows are kept for the caller
+                    let mut full = message.to_bytes();
+                    messages.by_ref().for_each(|x| full.extend(x.to_bytes()));
+                    full.append(&mut self.buffer);
+                    self.buffer = full;
+                    self.state = ResumptionState::Full;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the encrypted Finished of the server, answered with the client's ChangeCipherSpec and
+    // Finished
+    fn finished(&mut self, fragment: &[u8]) -> std::result::Result<Vec<u8>, AlertDescription> {
+        // the ciphers are set when the server resumed the session
+        let plaintext = self
+            .ciphers
+            .as_mut()
+            .unwrap()
+            .1
+            .open(ContentType::handshake, fragment)?;
+        self.buffer.extend_from_slice(&plaintext);
+
+        let mut messages = self.messages();
+        if messages.len() != 1 || !self.buffer.is_empty() {
+            return Err(AlertDescription::unexpected_message);
+        }
+        let message = messages.remove(0);
+        if message.msg_type != HandshakeType::finished as u8 {
+            return Err(AlertDescription::unexpected_message);
+        }
+
+        let master_secret = &self.session.master_secret;
+        let finished = Finished {
+            verify_data: message.body.clone(),
+        };
+        finished.verify(
+            FinishedSecret::Tls12 {
+                master_secret,
+                sender: Sender::Server,
+            },
+            &self.transcript,
+        )?;
+        self.transcript.add(&message.to_bytes());
+
+        let finished = Finished::compute(
+            FinishedSecret::Tls12 {
+                master_secret,
+                sender: Sender::Client,
+            },
+            &self.transcript,
+        );
+        let mut message = Vec::new();
+        Handshake::from_message(HandshakeType::finished, finished)
+            .to_network_bytes(&mut message)
+            .map_err(|_| AlertDescription::internal_error)?;
+        self.transcript.add(&message);
+
+        let sealed = self
+            .ciphers
+            .as_mut()
+            .unwrap()
+            .0
+            .seal(ContentType::handshake, &message)?;
+        let mut records = change_cipher_spec().map_err(|_| AlertDescription::internal_error)?;
+        records.extend_from_slice(
+            &record(ContentType::handshake, self.session.version, &sealed)
+                .map_err(|_| AlertDescription::internal_error)?,
+        );
+
+        self.state = ResumptionState::Done;
+        Ok(records)
+    }
+
+    // a record of the server, and the records to answer it with. Alerts are left to the
+    // caller, like the records of a full handshake
+    pub fn receive(&mut self, record: &Record) -> std::result::Result<Vec<u8>, AlertDescription> {
+        use ResumptionState::*;
+
+        match (self.state, record.content_type()) {
+            (Full, Some(ContentType::handshake)) => {
+                self.buffer.extend_from_slice(&record.fragment);
+                Ok(Vec::new())
+            }
+            (_, Some(ContentType::alert)) | (Full, _) => Ok(Vec::new()),
+            (WaitServerHello | WaitChangeCipherSpec, Some(ContentType::handshake)) => {
+                self.handshake(&record.fragment)?;
+                Ok(Vec::new())
+            }
+
+            // a message can't span the ChangeCipherSpec
+            (WaitChangeCipherSpec, Some(ContentType::change_cipher_spec))
+                if self.buffer.is_empty() =>
+            {
+                self.state = WaitFinished;
+                Ok(Vec::new())
+            }
+            (WaitFinished, Some(ContentType::handshake)) => self.finished(&record.fragment),
+            _ => Err(AlertDescription::unexpected_message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::{handshake_messages, records};
+    use crate::handshake::common::{Random, VariableLengthVector};
+    use crate::handshake::constants::*;
+    use crate::handshake::server_hello::ServerHello;
+
+    const SUITE: CipherSuite = TLS_DHE_RSA_WITH_AES_128_CBC_SHA256;
+
+    fn session() -> ClientSession {
+        ClientSession::new(&[0x5A; 32], SUITE, TLS12, &[0x42; 48]).unwrap()
+    }
+
+    // the abbreviated flight of a server which resumes the session, with a ticket or not,
+    // and the server's keys and transcript
+    fn server_flight(
+        client: &AbbreviatedHandshake,
+        session_id: &[u8],
+        ticket: bool,
+    ) -> (Vec<Record>, CbcCipher, Transcript) {
+        let sh = ServerHello {
+            server_version: TLS12,
+            random: Random::new(),
+            session_id: VariableLengthVector::from_slice(session_id),
+            cipher_suite: SUITE,
+            compression_method: 0,
+            extensions: None,
+        };
+        let mut transcript = Transcript::new(client.transcript.hash_algorithm());
+        let mut messages = Vec::new();
+        Handshake::from_client_hello(client.client_hello.clone())
+            .to_network_bytes(&mut messages)
+            .unwrap();
+        Handshake::from_message(HandshakeType::server_hello, sh)
+            .to_network_bytes(&mut messages)
+            .unwrap();
+        if ticket {
+            Handshake::from_message(
+                HandshakeType::new_session_ticket,
+                NewSessionTicket::new(7200, &[0x01; 16]),
+            )
+            .to_network_bytes(&mut messages)
+            .unwrap();
+        }
+        transcript.add(&messages);
+
+        let hello = &messages[client.transcript.as_bytes().len()..];
+        let server_random = &hello[6..38];
+        let parameters = SuiteParameters::from_suite(SUITE).unwrap();
+        let keys = KeyBlock::new(
+            &parameters,
+            &[0x42; 48],
+            &client.client_random,
+            server_random,
+        );
+        let mut server =
+            CbcCipher::new(SUITE, TLS12, &keys.server_mac_key, &keys.server_key).unwrap();
+        let client_keys =
+            CbcCipher::new(SUITE, TLS12, &keys.client_mac_key, &keys.client_key).unwrap();
+
+        let finished = Finished::compute(
+            FinishedSecret::Tls12 {
+                master_secret: &[0x42; 48],
+                sender: Sender::Server,
+            },
+            &transcript,
+        );
+        let mut message = Vec::new();
+        Handshake::from_message(HandshakeType::finished, finished)
+            .to_network_bytes(&mut message)
+            .unwrap();
+        transcript.add(&message);
+
+        let mut stream = record(ContentType::handshake, TLS12, hello).unwrap();
+        stream.extend_from_slice(&change_cipher_spec().unwrap());
+        let sealed = server.seal(ContentType::handshake, &message).unwrap();
+        stream.extend_from_slice(&record(ContentType::handshake, TLS12, &sealed).unwrap());
+
+        (records(&stream), client_keys, transcript)
+    }
+
+    #[test]
+    fn resumed() {
+        for ticket in [false, true] {
+            let ch = ClientHello::new(&[SUITE]);
+            let mut client = AbbreviatedHandshake::new(session(), &ch).unwrap();
+            let hello = client.hello_record().unwrap();
+            assert_eq!(&hello[43..44], &[32]);
+            assert_eq!(&hello[44..76], &[0x5A; 32]);
+
+            let (server_records, mut client_keys, transcript) =
+                server_flight(&client, &[0x5A; 32], ticket);
+            let mut answer = Vec::new();
+            for record in &server_records {
+                answer = client.receive(record).unwrap();
+            }
+            assert_eq!(client.state(), ResumptionState::Done);
+
+            // ChangeCipherSpec, then the client's Finished
+            let answer = records(&answer);
+            assert_eq!(answer.len(), 2);
+            assert_eq!(
+                answer[0].content_type,
+                ContentType::change_cipher_spec as u8
+            );
+            let message = client_keys
+                .open(ContentType::handshake, &answer[1].fragment)
+                .unwrap();
+            assert_eq!(message[0], HandshakeType::finished as u8);
+            let finished = Finished {
+                verify_data: message[4..].to_vec(),
+            };
+            let secret = FinishedSecret::Tls12 {
+                master_secret: &[0x42; 48],
+                sender: Sender::Client,
+            };
+            assert_eq!(finished.verify(secret, &transcript), Ok(()));
+        }
+    }
+
+    #[test]
+    fn ticket() {
+        let ch = ClientHello::new(&[SUITE]);
+        let session =
+            ClientSession::from_ticket(&[0x77; 16], 3600, SUITE, TLS12, &[0x42; 48]).unwrap();
+        let session_id = session.session_id.clone();
+        assert_eq!(session_id.len(), 32);
+
+        let mut client = AbbreviatedHandshake::new(session.clone(), &ch).unwrap();
+        assert!(client
+            .client_hello
+            .extensions()
+            .iter()
+            .any(|x| x.extension_type() == ExtensionType::session_ticket
+                && x.extension_data() == [0x77; 16]));
+        assert!(client.next_session().is_none());
+
+        // a renewed ticket replaces the one of the session
+        let (server_records, _, _) = server_flight(&client, &session_id, true);
+        for record in &server_records {
+            client.receive(record).unwrap();
+        }
+        let next = client.next_session().unwrap();
+        assert_eq!(next.ticket, Some(vec![0x01; 16]));
+        assert_eq!(next.lifetime_hint, 7200);
+        assert_ne!(next.session_id, session_id);
+
+        let mut client = AbbreviatedHandshake::new(session.clone(), &ch).unwrap();
+        let (server_records, _, _) = server_flight(&client, &session_id, false);
+        for record in &server_records {
+            client.receive(record).unwrap();
+        }
+        assert_eq!(client.next_session(), Some(session));
+
+        assert!(ClientSession::from_ticket(&[], 0, SUITE, TLS12, &[0; 48]).is_none());
+    }
+
+    #[test]
+    fn not_resumed() {
+        let ch = ClientHello::new(&[SUITE]);
+
+        // another session ID is a full handshake, whose messages are kept
+        let mut client = AbbreviatedHandshake::new(session(), &ch).unwrap();
+        assert!(client.full_handshake().is_none());
+        let (server_records, _, _) = server_flight(&client, &[0x11; 32], true);
+        assert_eq!(client.receive(&server_records[0]), Ok(Vec::new()));
+        assert_eq!(client.state(), ResumptionState::Full);
+        assert_eq!(
+            client.full_handshake(),
+            Some(&server_records[0].fragment[..])
+        );
+        let certificate = Record {
+            content_type: ContentType::handshake as u8,
+            version: TLS12,
+            fragment: vec![0x0B, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00],
+        };
+        client.receive(&certificate).unwrap();
+        let messages = handshake_messages(&records(
+            &record(
+                ContentType::handshake,
+                TLS12,
+                client.full_handshake().unwrap(),
+            )
+            .unwrap(),
+        ));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].msg_type, HandshakeType::server_hello as u8);
+        assert_eq!(messages[2].msg_type, HandshakeType::certificate as u8);
+
+        // a changed Finished
+        let mut client = AbbreviatedHandshake::new(session(), &ch).unwrap();
+        let (mut server_records, _, _) = server_flight(&client, &[0x5A; 32], false);
+        server_records[2].fragment[20] ^= 0x01;
+        client.receive(&server_records[0]).unwrap();
+        client.receive(&server_records[1]).unwrap();
+        assert_eq!(
+            client.receive(&server_records[2]),
+            Err(AlertDescription::bad_record_mac)
+        );
+
+        // the Finished before the ChangeCipherSpec is taken as the start of a plaintext
+        // message, which the ChangeCipherSpec can't interrupt
+        let mut client = AbbreviatedHandshake::new(session(), &ch).unwrap();
+        let (server_records, _, _) = server_flight(&client, &[0x5A; 32], false);
+        client.receive(&server_records[0]).unwrap();
+        client.receive(&server_records[2]).unwrap();
+        assert_eq!(
+            client.receive(&server_records[1]),
+            Err(AlertDescription::unexpected_message)
+        );
+
+        // a Certificate after the ServerHello
+        let mut client = AbbreviatedHandshake::new(session(), &ch).unwrap();
+        client.receive(&server_records[0]).unwrap();
+        assert_eq!(
+            client.receive(&certificate),
+            Err(AlertDescription::unexpected_message)
+        );
+
+        // the session ID is echoed with another suite
+        let mut other = session();
+        other.cipher_suite = TLS_RSA_WITH_AES_128_CBC_SHA256;
+        let mut client = AbbreviatedHandshake::new(other, &ch).unwrap();
+        let (server_records, _, _) = server_flight(&client, &[0x5A; 32], false);
+        assert_eq!(
+            client.receive(&server_records[0]),
+            Err(AlertDescription::illegal_parameter)
+        );
+
+        assert!(ClientSession::new(&[], SUITE, TLS12, &[0; 48]).is_none());
+        for version in [TLS10, TLS11, TLS13] {
+            assert!(ClientSession::new(&[1], SUITE, version, &[0; 48]).is_none());
+        }
+        assert!(
+            ClientSession::new(&[1], TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256, TLS12, &[0; 48])
+                .is_none()
+        );
+        assert!(!format!("{:?}", session()).contains("master_secret"));
+    }
+}
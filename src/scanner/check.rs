@@ -0,0 +1,250 @@
+// the Check trait implemented by each scanner check, and the registry holding them
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::scanner::checks::builtin_checks;
+use crate::scanner::connector::Connector;
+use crate::scanner::messages::{Catalog, Message};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    // name of the check which produced it
+    pub check: String,
+    pub severity: Severity,
+    pub message: String,
+
+    // the parts of the message, to translate it. Empty for text without a code
+    pub messages: Vec<Message>,
+}
+
+impl Finding {
+    pub fn new(check: &str, severity: Severity, message: &str) -> Self {
+        Self {
+            check: check.to_string(),
+            severity,
+            message: message.to_string(),
+            messages: Vec::new(),
+        }
+    }
+
+    // the English message is the parts joined with commas
+    pub fn coded(check: &str, severity: Severity, messages: Vec<Message>) -> Self {
+        let english: Vec<_> = messages.iter().map(Message::english).collect();
+        Self {
+            messages,
+            ..Self::new(check, severity, &english.join(", "))
+        }
+    }
+
+    // the check couldn't talk to the server
+    pub fn error(check: &str, error: &Error) -> Self {
+        let message = Message::new("could_not_run", &[&error.to_string()]);
+        Self::coded(check, Severity::Info, vec![message])
+    }
+
+    // the code of the first part, stable across locales and releases
+    pub fn code(&self) -> Option<&str> {
+        self.messages.first().map(|x| x.code)
+    }
+
+    pub fn localized(&self, catalog: &Catalog) -> String {
+        if self.messages.is_empty() {
+            return self.message.clone();
+        }
+        let parts: Vec<_> = self.messages.iter().map(|x| catalog.message(x)).collect();
+        parts.join(", ")
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.severity, self.check, self.message)
+    }
+}
+
+pub trait Check: Send + Sync {
+    // unique name, used to select checks on the command line
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn run(&self, connector: &Connector) -> Finding;
+}
+
+#[derive(Default)]
+pub struct CheckRegistry {
+    checks: Vec<Box<dyn Check>>,
+}
+
+impl fmt::Debug for CheckRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.checks.iter().map(|x| x.name()))
+            .finish()
+    }
+}
+
+impl CheckRegistry {
+    // an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the checks shipped with the crate
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        for check in builtin_checks() {
+            // builtin names are unique
+            registry.register(check).unwrap();
+        }
+        registry
+    }
+
+    pub fn register(&mut self, check: Box<dyn Check>) -> Result<()> {
+        if self.get(check.name()).is_some() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("check {} is already registered", check.name()),
+            ));
+        }
+        self.checks.push(check);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Check> {
+        self.checks
+            .iter()
+            .find(|x| x.name() == name)
+            .map(|x| x.as_ref())
+    }
+
+    pub fn checks(&self) -> impl Iterator<Item = &dyn Check> {
+        self.checks.iter().map(|x| x.as_ref())
+    }
+
+    // checks in registration order: only those in only if not empty, without those in skip
+    pub fn select(&self, only: &[String], skip: &[String]) -> Result<Vec<&dyn Check>> {
+        if let Some(unknown) = only.iter().chain(skip).find(|x| self.get(x).is_none()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("unknown check {}", unknown),
+            ));
+        }
+
+        Ok(self
+            .checks()
+            .filter(|x| only.is_empty() || only.iter().any(|name| name == x.name()))
+            .filter(|x| !skip.iter().any(|name| name == x.name()))
+            .collect())
+    }
+
+    pub fn run(
+        &self,
+        connector: &Connector,
+        only: &[String],
+        skip: &[String],
+    ) -> Result<Vec<Finding>> {
+        Ok(self
+            .select(only, skip)?
+            .into_iter()
+            .map(|check| check.run(connector))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dummy(&'static str);
+
+    impl Check for Dummy {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "always warns"
+        }
+
+        fn run(&self, _connector: &Connector) -> Finding {
+            Finding::new(self.0, Severity::Warning, "dummy")
+        }
+    }
+
+    fn names(checks: &[&dyn Check]) -> Vec<String> {
+        checks.iter().map(|x| x.name().to_string()).collect()
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = CheckRegistry::new();
+        registry.register(Box::new(Dummy("a"))).unwrap();
+        registry.register(Box::new(Dummy("b"))).unwrap();
+        registry.register(Box::new(Dummy("c"))).unwrap();
+        assert!(registry.register(Box::new(Dummy("a"))).is_err());
+
+        let all = registry.select(&[], &[]).unwrap();
+        assert_eq!(names(&all), vec!["a", "b", "c"]);
+
+        let only = registry.select(&["c".into(), "a".into()], &[]).unwrap();
+        assert_eq!(names(&only), vec!["a", "c"]);
+
+        let skip = registry.select(&[], &["b".into()]).unwrap();
+        assert_eq!(names(&skip), vec!["a", "c"]);
+
+        assert!(registry.select(&["d".into()], &[]).is_err());
+
+        let findings = registry
+            .run(&Connector::new("localhost:1"), &["b".into()], &[])
+            .unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].to_string(), "[warning] b: dummy");
+    }
+
+    #[test]
+    fn severity() {
+        assert!(Severity::Critical > Severity::High);
+        assert!(Severity::Warning > Severity::Info);
+
+        for severity in [
+            Severity::Info,
+            Severity::Warning,
+            Severity::High,
+            Severity::Critical,
+        ] {
+            assert_eq!(Severity::from_name(&severity.to_string()), Some(severity));
+        }
+        assert_eq!(Severity::from_name("medium"), None);
+    }
+}
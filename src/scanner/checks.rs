@@ -0,0 +1,407 @@
+// checks shipped with the crate
+use std::io::Cursor;
+
+use crate::derive_tls::TlsDerive;
+use crate::handshake::certificate::{CertificateMessage, CertificateStatus};
+use crate::handshake::client_hello::{
+    CertificateStatusRequest, ClientHello, ExtensionType, GenericExtension,
+};
+use crate::handshake::common::{cipher_suite_name, version_name, CipherSuite};
+use crate::handshake::constants::{
+    CIPHER_SUITES, TLS10, TLS11, TLS13, TLS_EMPTY_RENEGOTIATION_INFO_SCSV,
+};
+use crate::handshake::handshake::HandshakeType;
+use crate::scanner::check::{Check, Finding, Severity};
+use crate::scanner::connector::{Connector, ServerResponse};
+use crate::scanner::messages::Message;
+use crate::x509::certificate::policy_name;
+
+pub fn builtin_checks() -> Vec<Box<dyn Check>> {
+    vec![
+        Box::new(LegacyVersions),
+        Box::new(WeakCipherSuites),
+        Box::new(SecureRenegotiation),
+        Box::new(OcspStapling),
+        Box::new(MustStaple),
+    ]
+}
+
+// what to report when the server didn't answer with a ServerHello
+fn no_hello(check: &str, response: &ServerResponse) -> Finding {
+    let message = match response {
+        ServerResponse::Alert { description, .. } => {
+            Message::new("alert_answer", &[&description.to_string()])
+        }
+        _ => Message::new("connection_closed", &[]),
+    };
+    Finding::coded(check, Severity::Info, vec![message])
+}
+
+// a ClientHello asking for an OCSP response
+fn with_status_request(connector: &Connector) -> std::io::Result<ClientHello> {
+    let status_request = GenericExtension::from_extension(&CertificateStatusRequest::ocsp())?;
+    Ok(connector
+        .client_hello()
+        .with_extension_replaced(status_request))
+}
+
+pub struct LegacyVersions;
+
+impl Check for LegacyVersions {
+    fn name(&self) -> &str {
+        "legacy_versions"
+    }
+
+    fn description(&self) -> &str {
+        "server accepts TLS 1.0 or TLS 1.1"
+    }
+
+    fn run(&self, connector: &Connector) -> Finding {
+        let mut accepted = Vec::new();
+
+        for version in [TLS10, TLS11] {
+            let ch = connector.client_hello().with_version(version);
+            match connector.send(&ch) {
+                Ok(ServerResponse::Hello(sh)) if sh.version() == version => {
+                    accepted.push(version_name(version))
+                }
+                Ok(_) => (),
+                Err(e) => return Finding::error(self.name(), &e),
+            }
+        }
+
+        if accepted.is_empty() {
+            Finding::coded(
+                self.name(),
+                Severity::Info,
+                vec![Message::new("legacy_rejected", &[])],
+            )
+        } else {
+            let message = Message::new("legacy_accepted", &[&accepted.join(", ")]);
+            Finding::coded(self.name(), Severity::Warning, vec![message])
+        }
+    }
+}
+
+pub struct WeakCipherSuites;
+
+impl WeakCipherSuites {
+    // suites without encryption or authentication, or with broken ciphers
+    fn weak_suites() -> Vec<CipherSuite> {
+        CIPHER_SUITES
+            .iter()
+            .filter(|(name, _)| {
+                ["NULL", "ANON", "RC4", "3DES"]
+                    .iter()
+                    .any(|x| name.contains(x))
+            })
+            .map(|(_, suite)| *suite)
+            .collect()
+    }
+}
+
+impl Check for WeakCipherSuites {
+    fn name(&self) -> &str {
+        "weak_suites"
+    }
+
+    fn description(&self) -> &str {
+        "server selects a NULL, anonymous, RC4 or 3DES cipher suite"
+    }
+
+    fn run(&self, connector: &Connector) -> Finding {
+        let weak = Self::weak_suites();
+        let ch = connector.client_hello().with_suites(&weak);
+
+        match connector.send(&ch) {
+            Ok(ServerResponse::Hello(sh)) => {
+                let suite = sh.cipher_suite.to_be_bytes();
+                if weak.contains(&suite) {
+                    let message = Message::new("weak_suite_selected", &[&cipher_suite_name(suite)]);
+                    Finding::coded(self.name(), Severity::High, vec![message])
+                } else {
+                    Finding::coded(
+                        self.name(),
+                        Severity::Info,
+                        vec![Message::new("no_weak_suite", &[])],
+                    )
+                }
+            }
+            Ok(response) => no_hello(self.name(), &response),
+            Err(e) => Finding::error(self.name(), &e),
+        }
+    }
+}
+
+pub struct SecureRenegotiation;
+
+impl Check for SecureRenegotiation {
+    fn name(&self) -> &str {
+        "renegotiation"
+    }
+
+    fn description(&self) -> &str {
+        "server doesn't support secure renegotiation (RFC 5746)"
+    }
+
+    fn run(&self, connector: &Connector) -> Finding {
+        let ch = connector.client_hello();
+        let mut suites = ch.cipher_suites().to_vec();
+        suites.push(TLS_EMPTY_RENEGOTIATION_INFO_SCSV);
+
+        match connector.send(&ch.with_suites(&suites)) {
+            // renegotiation doesn't exist in TLS 1.3
+            Ok(ServerResponse::Hello(sh)) if sh.version() >= TLS13 => Finding::coded(
+                self.name(),
+                Severity::Info,
+                vec![Message::new("tls13_negotiated", &[])],
+            ),
+            Ok(ServerResponse::Hello(sh))
+                if sh
+                    .extension(u16::from(ExtensionType::renegotiation_info))
+                    .is_some() =>
+            {
+                Finding::coded(
+                    self.name(),
+                    Severity::Info,
+                    vec![Message::new("renegotiation_supported", &[])],
+                )
+            }
+            Ok(ServerResponse::Hello(_)) => Finding::coded(
+                self.name(),
+                Severity::Warning,
+                vec![Message::new("no_renegotiation_info", &[])],
+            ),
+            Ok(response) => no_hello(self.name(), &response),
+            Err(e) => Finding::error(self.name(), &e),
+        }
+    }
+}
+
+pub struct OcspStapling;
+
+impl Check for OcspStapling {
+    fn name(&self) -> &str {
+        "ocsp_stapling"
+    }
+
+    fn description(&self) -> &str {
+        "server doesn't staple OCSP responses"
+    }
+
+    fn run(&self, connector: &Connector) -> Finding {
+        let ch = match with_status_request(connector) {
+            Ok(ch) => ch,
+            Err(e) => return Finding::error(self.name(), &e),
+        };
+
+        // in TLS 1.2, the server acknowledges with an empty status_request extension
+        match connector.send(&ch) {
+            Ok(ServerResponse::Hello(sh))
+                if sh
+                    .extension(u16::from(ExtensionType::status_request))
+                    .is_some() =>
+            {
+                Finding::coded(
+                    self.name(),
+                    Severity::Info,
+                    vec![Message::new("ocsp_supported", &[])],
+                )
+            }
+            Ok(ServerResponse::Hello(_)) => Finding::coded(
+                self.name(),
+                Severity::Info,
+                vec![Message::new("no_ocsp", &[])],
+            ),
+            Ok(response) => no_hello(self.name(), &response),
+            Err(e) => Finding::error(self.name(), &e),
+        }
+    }
+}
+
+pub struct MustStaple;
+
+impl Check for MustStaple {
+    fn name(&self) -> &str {
+        "must_staple"
+    }
+
+    fn description(&self) -> &str {
+        "certificate asserts must-staple (TLS Feature) but the server doesn't staple"
+    }
+
+    fn run(&self, connector: &Connector) -> Finding {
+        let ch = match with_status_request(connector) {
+            Ok(ch) => ch,
+            Err(e) => return Finding::error(self.name(), &e),
+        };
+
+        let (response, messages) = match connector.first_flight(&ch) {
+            Ok(flight) => flight,
+            Err(e) => return Finding::error(self.name(), &e),
+        };
+        let sh = match response {
+            ServerResponse::Hello(sh) => sh,
+            response => return no_hello(self.name(), &response),
+        };
+
+        // TLS 1.3 encrypts the certificate
+        if sh.version() >= TLS13 {
+            return Finding::coded(
+                self.name(),
+                Severity::Info,
+                vec![Message::new("tls13_negotiated", &[])],
+            );
+        }
+        let find = |msg_type: HandshakeType| messages.iter().find(|x| x.msg_type == msg_type as u8);
+        let Some(message) = find(HandshakeType::certificate) else {
+            return Finding::coded(
+                self.name(),
+                Severity::Info,
+                vec![Message::new("no_certificate", &[])],
+            );
+        };
+
+        let mut certificate = CertificateMessage::for_version(sh.version());
+        if let Err(e) = certificate.from_network_bytes(&mut Cursor::new(message.body.clone())) {
+            return Finding::error(self.name(), &e);
+        }
+        let info = match connector.certificates().chain(&certificate.certificates()) {
+            Ok(info) => info,
+            Err(e) => {
+                let message = Message::new("certificate_error", &[&e.to_string()]);
+                return Finding::coded(self.name(), Severity::Info, vec![message]);
+            }
+        };
+
+        // a malformed CertificateStatus still counts as a staple
+        let staple = find(HandshakeType::certificate_status);
+        if let Some(message) = staple {
+            let mut status = CertificateStatus::default();
+            if status
+                .from_network_bytes(&mut Cursor::new(message.body.clone()))
+                .is_ok()
+            {
+                connector
+                    .certificates()
+                    .record_staple(&info.fingerprint, &status.response.data);
+            }
+        }
+
+        // clients enforcing must-staple reject the connection
+        let (severity, code) = match (info.must_staple, staple.is_some()) {
+            (true, false) => (Severity::High, "must_staple_missing"),
+            (true, true) => (Severity::Info, "must_staple_stapled"),
+            (false, _) => (Severity::Info, "no_must_staple"),
+        };
+        let mut messages = vec![Message::new(code, &[])];
+
+        if !info.policies.is_empty() {
+            let names: Vec<_> = info
+                .policies
+                .iter()
+                .map(|oid| policy_name(oid).map_or(oid.clone(), |name| name.to_string()))
+                .collect();
+            messages.push(Message::new("policies", &[&names.join(", ")]));
+        }
+
+        Finding::coded(self.name(), severity, messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::*;
+    use crate::server::server::Server;
+    use crate::x509::certificate::must_staple_certificate;
+    use std::net::TcpListener;
+
+    // run the checks against the crate's own server
+    fn scan(suites: &[CipherSuite]) -> Vec<Finding> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        connector.timeout = std::time::Duration::from_secs(2);
+
+        let mut server = Server::default();
+        server.config.cipher_suites = suites.to_vec();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                // legacy_versions connects twice
+                for _ in 0..builtin_checks().len() + 1 {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let _ = server.handle(&mut stream);
+                }
+            });
+
+            builtin_checks()
+                .iter()
+                .map(|check| check.run(&connector))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn mock_server() {
+        let findings = scan(&[TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256]);
+        assert_eq!(findings.len(), 5);
+
+        // the mock server always answers with TLS 1.2
+        assert_eq!(findings[0].severity, Severity::Info);
+        assert_eq!(findings[1].severity, Severity::Info);
+        assert_eq!(findings[1].message, "server answered with alert 40");
+        assert_eq!(findings[2].severity, Severity::Warning);
+        assert_eq!(findings[3].message, "no OCSP stapling");
+        assert!(findings[4]
+            .message
+            .starts_with("could not parse certificate: "));
+    }
+
+    #[test]
+    fn must_staple() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        connector.timeout = std::time::Duration::from_secs(2);
+
+        let mut server = Server::default();
+        server
+            .config
+            .resolver
+            .set_default(vec![must_staple_certificate()]);
+
+        // without, then with a stapled response
+        let mut findings = Vec::new();
+        for response in [None, Some(vec![0x30, 0x03, 0x0A, 0x01, 0x00])] {
+            server.config.ocsp_response = response;
+            findings.push(std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let _ = server.handle(&mut stream);
+                });
+                MustStaple.run(&connector)
+            }));
+        }
+
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(
+            findings[0].message,
+            "must-staple asserted but no staple sent, policies: domain validated"
+        );
+        assert_eq!(findings[1].severity, Severity::Info);
+        assert!(findings[1]
+            .message
+            .starts_with("must-staple asserted and staple sent"));
+    }
+
+    #[test]
+    fn weak_suite() {
+        let findings = scan(&[TLS_RSA_WITH_RC4_128_SHA]);
+        assert_eq!(findings[1].severity, Severity::High);
+        assert_eq!(
+            findings[1].message,
+            "server selects TLS_RSA_WITH_RC4_128_SHA"
+        );
+    }
+}
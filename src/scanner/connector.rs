@@ -0,0 +1,596 @@
+// send ClientHellos to a server and read the first message it answers
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::alert::alert::AlertDescription;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ServerHelloSummary;
+use crate::dissector::{handshake_messages, records, HandshakeMessage};
+use crate::handshake::client_hello::ClientHello;
+use crate::handshake::common::{cipher_suite_name, version_name, CipherSuite, ContentType};
+use crate::handshake::constants::TLS10;
+use crate::handshake::handshake::{Handshake, HandshakeType};
+use crate::handshake::record_layer::{RecordHeader, RecordLayer, MAX_FRAGMENT_LENGTH};
+use crate::handshake::templates::template;
+use crate::scanner::flight::{check_extensions, check_flight, HandshakeLimits};
+use crate::scanner::histogram::LatencyHistogram;
+use crate::scanner::info::ConnectionInfo;
+use crate::server::server::fatal_alert;
+use crate::x509::cache::CertificateCache;
+
+#[derive(Debug)]
+pub enum ServerResponse {
+    Hello(ServerHelloSummary),
+
+    // raw codes, the description might be unknown
+    Alert { level: u8, description: u8 },
+
+    // the server closed the connection or didn't answer in time
+    Closed,
+}
+
+// a short description, the same for equivalent answers
+impl fmt::Display for ServerResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerResponse::Hello(sh) if sh.is_hello_retry_request() => write!(
+                f,
+                "HelloRetryRequest {}",
+                cipher_suite_name(sh.cipher_suite.to_be_bytes())
+            ),
+            ServerResponse::Hello(sh) => write!(
+                f,
+                "ServerHello {} {}",
+                version_name(sh.version()),
+                cipher_suite_name(sh.cipher_suite.to_be_bytes())
+            ),
+            ServerResponse::Alert { level, description } => {
+                match AlertDescription::try_from(*description) {
+                    Ok(description) => write!(f, "alert {} {}", level, description),
+                    Err(_) => write!(f, "alert {} unknown({})", level, description),
+                }
+            }
+            ServerResponse::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+// the name of a host given with an optional port, and the address to connect to, the port
+// being 443 by default. An IPv6 address followed by a port is bracketed, and may be bare
+// otherwise: https://datatracker.ietf.org/doc/html/rfc3986#section-3.2.2
+pub fn split_host(host: &str) -> (&str, String) {
+    if let Some((name, port)) = host
+        .strip_prefix('[')
+        .and_then(|bracketed| bracketed.split_once(']'))
+    {
+        let address = match port {
+            "" => format!("[{}]:443", name),
+            _ => host.to_string(),
+        };
+        return (name, address);
+    }
+
+    match host.split_once(':') {
+        // more than one colon is an IPv6 address
+        Some((_, port)) if port.contains(':') => (host, format!("[{}]:443", host)),
+        Some((name, _)) => (name, host.to_string()),
+        None => (host, format!("{}:443", host)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Connector {
+    pub address: String,
+    pub server_name: String,
+    pub timeout: Duration,
+
+    // suites offered by client_hello(), in order of preference. Those of the template if empty
+    pub cipher_suites: Vec<CipherSuite>,
+
+    // guards against servers keeping the handshake going
+    pub limits: HandshakeLimits,
+
+    // abort with an unsupported_extension alert when the ServerHello has an extension the
+    // ClientHello didn't offer, instead of reading the answer as is
+    pub strict_extensions: bool,
+
+    // time from connecting to the server's answer, shared by clones so checks running in
+    // parallel add to the same histogram
+    latencies: Arc<Mutex<LatencyHistogram>>,
+
+    // chains parsed by checks, also shared by clones
+    certificates: CertificateCache,
+}
+
+impl Connector {
+    // host with an optional port, see split_host(). IP addresses get no SNI
+    pub fn new(host: &str) -> Self {
+        let (server_name, address) = split_host(host);
+
+        Self {
+            address,
+            server_name: server_name.to_string(),
+            timeout: Duration::from_secs(5),
+            cipher_suites: Vec::new(),
+            limits: HandshakeLimits::default(),
+            strict_extensions: true,
+            latencies: Arc::default(),
+            certificates: CertificateCache::new(),
+        }
+    }
+
+    // latencies of the handshakes answered so far
+    pub fn latencies(&self) -> LatencyHistogram {
+        // only poisoned if a thread panicked while recording
+        self.latencies.lock().unwrap().clone()
+    }
+
+    pub fn certificates(&self) -> &CertificateCache {
+        &self.certificates
+    }
+
+    // a TLS 1.2 hello for the server, for checks to modify
+    pub fn client_hello(&self) -> ClientHello {
+        // the template exists
+        self.with_policy(template("minimal", &self.server_name).unwrap())
+    }
+
+    // the hello offering the suites of the connector, if any
+    pub fn with_policy(&self, ch: ClientHello) -> ClientHello {
+        if self.cipher_suites.is_empty() {
+            ch
+        } else {
+            ch.with_suites(&self.cipher_suites)
+        }
+    }
+
+    pub fn send(&self, ch: &ClientHello) -> Result<ServerResponse> {
+        self.send_bytes(&hello_record(ch)?, Some(ch))
+    }
+
+    // send a handshake message as is, including its header, split into as many records
+    // as needed
+    pub fn send_raw(&self, message: &[u8]) -> Result<ServerResponse> {
+        let mut buffer = Vec::with_capacity(message.len() + 5);
+        for fragment in message.chunks(MAX_FRAGMENT_LENGTH) {
+            let header = RecordHeader {
+                content_type: ContentType::handshake,
+                version: TLS10,
+                length: fragment.len() as u16,
+            };
+            header.to_network_bytes(&mut buffer)?;
+            buffer.extend_from_slice(fragment);
+        }
+        self.send_bytes(&buffer, None)
+    }
+
+    // the server's answer with, if it's a ServerHello, the messages of its first flight up
+    // to ServerHelloDone, or up to where the server stopped sending
+    pub fn first_flight(
+        &self,
+        ch: &ClientHello,
+    ) -> Result<(ServerResponse, Vec<HandshakeMessage>)> {
+        let start = Instant::now();
+        let mut stream = self.connect()?;
+        stream.write_all(&hello_record(ch)?)?;
+
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let mut answer = None;
+        loop {
+            if answer.is_none() {
+                answer = first_message(&response)?;
+                if let Some(answer) = &answer {
+                    self.latencies.lock().unwrap().record(start.elapsed());
+                    self.check_extensions(&mut stream, ch, answer)?;
+                }
+            }
+
+            let messages = handshake_messages(&records(&response));
+            check_flight(&messages)?;
+            let complete = messages
+                .iter()
+                .any(|x| x.msg_type == HandshakeType::server_hello_done as u8);
+            match answer {
+                Some(ServerResponse::Hello(_)) if !complete => (),
+                Some(answer @ ServerResponse::Hello(_)) => return Ok((answer, messages)),
+                Some(answer) => return Ok((answer, Vec::new())),
+                None => (),
+            }
+
+            // the last read can't go past the budget
+            let left = self.limits.check(start, messages.len())?;
+            stream.set_read_timeout(Some(left.min(self.timeout)))?;
+            match stream.read(&mut chunk) {
+                Ok(n) if n > 0 => response.extend_from_slice(&chunk[..n]),
+                _ => {
+                    self.limits.check(start, messages.len())?;
+                    return Ok((answer.unwrap_or(ServerResponse::Closed), messages));
+                }
+            }
+        }
+    }
+
+    // what the server negotiated in its first flight, None if it didn't answer with a
+    // ServerHello
+    pub fn connection_info(&self, ch: &ClientHello) -> Result<Option<ConnectionInfo>> {
+        Ok(match self.first_flight(ch)? {
+            (ServerResponse::Hello(sh), messages) => Some(ConnectionInfo::new(&sh, &messages)),
+            _ => None,
+        })
+    }
+
+    // a connection to the server, with the read timeout set
+    pub(crate) fn connect(&self) -> Result<TcpStream> {
+        let address = self
+            .address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no address for host"))?;
+        let stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+
+    // the ServerHello is checked against the ClientHello, if there's one
+    fn send_bytes(&self, buffer: &[u8], ch: Option<&ClientHello>) -> Result<ServerResponse> {
+        let start = Instant::now();
+        let mut stream = self.connect()?;
+        stream.write_all(buffer)?;
+
+        let mut response = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Some(answer) = first_message(&response)? {
+                self.latencies.lock().unwrap().record(start.elapsed());
+                if let Some(ch) = ch {
+                    self.check_extensions(&mut stream, ch, &answer)?;
+                }
+                return Ok(answer);
+            }
+
+            let left = self.limits.check(start, 0)?;
+            stream.set_read_timeout(Some(left.min(self.timeout)))?;
+            match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => {
+                    self.limits.check(start, 0)?;
+                    return Ok(ServerResponse::Closed);
+                }
+                Ok(n) => response.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    // the MUST of https://datatracker.ietf.org/doc/html/rfc8446#section-4.2: an extension
+    // the client didn't send aborts the handshake
+    fn check_extensions(
+        &self,
+        stream: &mut TcpStream,
+        ch: &ClientHello,
+        answer: &ServerResponse,
+    ) -> Result<()> {
+        let ServerResponse::Hello(sh) = answer else {
+            return Ok(());
+        };
+        if !self.strict_extensions {
+            return Ok(());
+        }
+
+        check_extensions(ch, sh).map_err(|e| {
+            // the error matters more than whether the alert could be sent
+            let _ = fatal_alert(AlertDescription::unsupported_extension)
+                .and_then(|alert| stream.write_all(&alert));
+            e.into()
+        })
+    }
+}
+
+// the ClientHello in a single handshake record
+pub(crate) fn hello_record(ch: &ClientHello) -> Result<Vec<u8>> {
+    let mut record = RecordLayer {
+        header: RecordHeader {
+            content_type: ContentType::handshake,
+            version: TLS10,
+            length: 0,
+        },
+        data: Handshake::from_client_hello(ch.clone()),
+    };
+    record.set_length();
+
+    let mut buffer = Vec::new();
+    record.to_network_bytes(&mut buffer)?;
+    Ok(buffer)
+}
+
+// the alert or ServerHello at the start of the stream, None if more data is needed
+pub(crate) fn first_message(stream: &[u8]) -> Result<Option<ServerResponse>> {
+    let records = records(stream);
+    let Some(first) = records.first() else {
+        return Ok(None);
+    };
+
+    if first.content_type == ContentType::alert as u8 {
+        return match first.fragment[..] {
+            [level, description, ..] => Ok(Some(ServerResponse::Alert { level, description })),
+            _ => Err(Error::new(ErrorKind::InvalidData, "truncated alert")),
+        };
+    }
+
+    let Some(message) = handshake_messages(&records).into_iter().next() else {
+        return Ok(None);
+    };
+    if message.msg_type != HandshakeType::server_hello as u8 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unexpected handshake message {}", message.msg_type),
+        ));
+    }
+
+    ServerHelloSummary::parse(&message.body).map(|sh| Some(ServerResponse::Hello(sh)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handshake, record};
+
+    #[test]
+    fn new() {
+        let connector = Connector::new("example.com");
+        assert_eq!(connector.address, "example.com:443");
+        assert_eq!(connector.server_name, "example.com");
+
+        let connector = Connector::new("localhost:8443");
+        assert_eq!(connector.address, "localhost:8443");
+        assert_eq!(connector.server_name, "localhost");
+    }
+
+    #[test]
+    fn policy() {
+        use crate::handshake::constants::TLS_RSA_WITH_AES_128_CBC_SHA;
+
+        let mut connector = Connector::new("example.com");
+        let suites = connector.client_hello().cipher_suites().to_vec();
+        assert!(suites.len() > 1);
+
+        connector.cipher_suites = vec![TLS_RSA_WITH_AES_128_CBC_SHA];
+        assert_eq!(
+            connector.client_hello().cipher_suites(),
+            &[TLS_RSA_WITH_AES_128_CBC_SHA]
+        );
+    }
+
+    #[test]
+    fn ipv6() {
+        use crate::handshake::client_hello::ExtensionType;
+
+        for (host, address) in [
+            ("[::1]:8443", "[::1]:8443"),
+            ("[::1]", "[::1]:443"),
+            ("2001:db8::1", "[2001:db8::1]:443"),
+        ] {
+            let connector = Connector::new(host);
+            assert_eq!(connector.address, address);
+            assert!(address.starts_with(&format!("[{}]", connector.server_name)));
+
+            // no SNI for IP literals
+            assert!(connector
+                .client_hello()
+                .extensions()
+                .iter()
+                .all(|x| x.extension_type() != ExtensionType::server_name));
+        }
+        assert_eq!(
+            split_host("127.0.0.1:443"),
+            ("127.0.0.1", "127.0.0.1:443".to_string())
+        );
+    }
+
+    #[test]
+    fn alert() {
+        let response = first_message(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28]).unwrap();
+        assert!(matches!(
+            response,
+            Some(ServerResponse::Alert {
+                level: 2,
+                description: 40
+            })
+        ));
+
+        // incomplete record
+        assert!(first_message(&[0x16, 0x03, 0x03, 0x00, 0x40, 0x02])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn duplicate_server_hello() {
+        use crate::dissector::summary::ClientHelloSummary;
+        use crate::scanner::flight::FlightError;
+        use crate::server::server::Server;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        let ch = connector.client_hello();
+
+        // the mock server's flight, with its ServerHello sent twice
+        let mut server = Server::default();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+        let mut hello = Vec::new();
+        Handshake::from_client_hello(ch.clone())
+            .to_network_bytes(&mut hello)
+            .unwrap();
+        let response = server
+            .respond(&ClientHelloSummary::parse(&hello[4..]).unwrap(), &hello)
+            .unwrap();
+        let messages = handshake_messages(&records(&response));
+
+        let record = record!(
+            0x16,
+            [0x03, 0x03],
+            messages[0].to_bytes(),
+            messages[0].to_bytes(),
+            messages[1].to_bytes()
+        );
+
+        let e = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0u8; 4096];
+                let _ = stream.read(&mut buffer).unwrap();
+                stream.write_all(&record).unwrap();
+            });
+            connector.first_flight(&ch).unwrap_err()
+        });
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            e.get_ref().unwrap().downcast_ref::<FlightError>(),
+            Some(&FlightError::Duplicate(HandshakeType::server_hello))
+        );
+    }
+
+    #[test]
+    fn unsolicited_extension() {
+        use crate::handshake::client_hello::ExtensionType;
+        use crate::scanner::flight::FlightError;
+        use crate::{concat_parts, extension};
+        use std::net::TcpListener;
+
+        // a ServerHello with an extended_master_secret the client didn't send
+        let hello = record!(
+            0x16,
+            [0x03, 0x03],
+            handshake!(
+                0x02,
+                [0x03, 0x03],
+                [0; 32],
+                [0x00, 0xC0, 0x2F, 0x00],
+                concat_parts!(
+                    [0x00, 0x04],
+                    extension!(ExtensionType::extended_master_secret)
+                )
+            )
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        let ch = connector.client_hello();
+        let serve = || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer).unwrap();
+            stream.write_all(&hello).unwrap();
+
+            // what the client answers
+            let mut alert = Vec::new();
+            let _ = stream.read_to_end(&mut alert);
+            alert
+        };
+
+        let (e, alert) = std::thread::scope(|scope| {
+            let server = scope.spawn(serve);
+            let e = connector.send(&ch).unwrap_err();
+            (e, server.join().unwrap())
+        });
+        assert_eq!(
+            e.get_ref().unwrap().downcast_ref::<FlightError>(),
+            Some(&FlightError::UnsolicitedExtension(
+                ExtensionType::extended_master_secret
+            ))
+        );
+        assert_eq!(alert, [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 110]);
+
+        connector.strict_extensions = false;
+        let response = std::thread::scope(|scope| {
+            scope.spawn(serve);
+            connector.send(&ch).unwrap()
+        });
+        assert!(matches!(response, ServerResponse::Hello(_)));
+    }
+
+    #[test]
+    fn connection_info() {
+        use crate::handshake::client_hello::ProtocolNameList;
+        use crate::scanner::info::Feature;
+        use crate::server::server::Server;
+        use crate::shutdown::ShutdownToken;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        let mut ch = connector.client_hello();
+        ch.add_extension(&ProtocolNameList::new(&["h2"])).unwrap();
+
+        let mut server = Server::default();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+        server.config.alpn_protocols = vec![String::from("h2")];
+        let shutdown = ShutdownToken::new();
+
+        let info = std::thread::scope(|scope| {
+            scope.spawn(|| server.serve(&listener, &shutdown).unwrap());
+            let info = connector.connection_info(&ch);
+            shutdown.shutdown();
+            info.unwrap().unwrap()
+        });
+        assert_eq!(info.alpn.as_deref(), Some("h2"));
+        assert_eq!(info.features(), vec![Feature::Alpn]);
+    }
+
+    #[test]
+    fn tarpit() {
+        use crate::scanner::flight::FlightError;
+        use std::net::TcpListener;
+
+        // a ServerHello, then what the server sends every 20ms
+        let run = |limits: HandshakeLimits, drip: Vec<u8>| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+            connector.limits = limits;
+            let ch = connector.client_hello();
+
+            std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut buffer = [0u8; 4096];
+                    let _ = stream.read(&mut buffer).unwrap();
+
+                    let hello = record!(
+                        0x16,
+                        [0x03, 0x03],
+                        handshake!(0x02, [0x03, 0x03], [0; 32], [0x00, 0xC0, 0x2F, 0x00])
+                    );
+                    stream.write_all(&hello).unwrap();
+                    while stream.write_all(&drip).is_ok() {
+                        std::thread::sleep(Duration::from_millis(20));
+                    }
+                });
+                connector.first_flight(&ch).unwrap_err()
+            })
+        };
+
+        // endless HelloRequests
+        let limits = HandshakeLimits {
+            max_messages: 8,
+            ..Default::default()
+        };
+        let e = run(limits, record!(0x16, [0x03, 0x03], handshake!(0x00)));
+        assert_eq!(
+            e.get_ref().unwrap().downcast_ref::<FlightError>(),
+            Some(&FlightError::TooManyMessages(8))
+        );
+
+        // a certificate a byte at a time
+        let limits = HandshakeLimits {
+            max_duration: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let e = run(limits, vec![0x16]);
+        assert_eq!(e.kind(), ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,365 @@
+// the client side of the handshake state machine: the messages of the server are checked
+// against the order of https://datatracker.ietf.org/doc/html/rfc5246#section-7.3 as they
+// arrive, so a duplicate or out of order message is rejected instead of being parsed as the
+// one expected at this point. Limits on the number of messages and on the duration of the
+// handshake stop servers which keep the client waiting, e.g. with endless HelloRequests or a
+// byte at a time. The ServerHello can only answer extensions the client sent, with the
+// exceptions of https://datatracker.ietf.org/doc/html/rfc8446#section-4.2
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::time::{Duration, Instant};
+
+use crate::dissector::summary::ServerHelloSummary;
+use crate::dissector::HandshakeMessage;
+use crate::handshake::client_hello::{extension_name, ClientHello, ExtensionType};
+use crate::handshake::constants::TLS_EMPTY_RENEGOTIATION_INFO_SCSV;
+use crate::handshake::handshake::HandshakeType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlightError {
+    // the flight must start with a ServerHello
+    NoServerHello(HandshakeType),
+    Duplicate(HandshakeType),
+    OutOfOrder {
+        message: HandshakeType,
+        after: HandshakeType,
+    },
+
+    // unknown types, and messages servers don't send
+    Unexpected(u8),
+
+    // limits of HandshakeLimits
+    TooManyMessages(usize),
+    TooLong(Duration),
+
+    // in the ServerHello but not in the ClientHello, answered with an unsupported_extension
+    // alert
+    UnsolicitedExtension(ExtensionType),
+}
+
+impl fmt::Display for FlightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlightError::NoServerHello(message) => write!(f, "{} before ServerHello", message),
+            FlightError::Duplicate(message) => write!(f, "duplicate {}", message),
+            FlightError::OutOfOrder { message, after } => {
+                write!(f, "{} out of order, after {}", message, after)
+            }
+            FlightError::Unexpected(msg_type) => {
+                write!(f, "unexpected handshake message {}", msg_type)
+            }
+            FlightError::TooManyMessages(max) => write!(f, "more than {} handshake messages", max),
+            FlightError::TooLong(max) => write!(f, "handshake longer than {:?}", max),
+            FlightError::UnsolicitedExtension(extension_type) => write!(
+                f,
+                "unsolicited extension {}",
+                extension_name(u16::from(*extension_type))
+            ),
+        }
+    }
+}
+
+impl FlightError {
+    // stable identifier of the error, for programs which shouldn't parse the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            FlightError::NoServerHello(_) => "no_server_hello",
+            FlightError::Duplicate(_) => "duplicate_message",
+            FlightError::OutOfOrder { .. } => "out_of_order",
+            FlightError::Unexpected(_) => "unexpected_message",
+            FlightError::TooManyMessages(_) => "too_many_messages",
+            FlightError::TooLong(_) => "handshake_too_long",
+            FlightError::UnsolicitedExtension(_) => "unsolicited_extension",
+        }
+    }
+}
+
+impl std::error::Error for FlightError {}
+
+// the error can be recovered with get_ref() and downcast_ref()
+impl From<FlightError> for Error {
+    fn from(e: FlightError) -> Self {
+        let kind = match e {
+            FlightError::TooLong(_) => ErrorKind::TimedOut,
+            _ => ErrorKind::InvalidData,
+        };
+        Error::new(kind, e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HandshakeLimits {
+    // messages received at most, HelloRequests included
+    pub max_messages: usize,
+
+    // from connecting to the end of the handshake, whatever the read timeout
+    pub max_duration: Duration,
+}
+
+impl Default for HandshakeLimits {
+    fn default() -> Self {
+        Self {
+            max_messages: 32,
+            max_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+impl HandshakeLimits {
+    // the time left for the handshake started at start, after these many messages
+    pub fn check(&self, start: Instant, messages: usize) -> Result<Duration, FlightError> {
+        if messages > self.max_messages {
+            return Err(FlightError::TooManyMessages(self.max_messages));
+        }
+        self.max_duration
+            .checked_sub(start.elapsed())
+            .filter(|x| !x.is_zero())
+            .ok_or(FlightError::TooLong(self.max_duration))
+    }
+}
+
+// position of a message in the server's flights. The NewSessionTicket comes right after the
+// ServerHello of an abbreviated handshake, and after ServerHelloDone otherwise
+fn rank(message: HandshakeType) -> Option<u8> {
+    match message {
+        HandshakeType::server_hello => Some(0),
+        HandshakeType::certificate => Some(1),
+        HandshakeType::certificate_status => Some(2),
+        HandshakeType::server_key_exchange => Some(3),
+        HandshakeType::certificate_request => Some(4),
+        HandshakeType::server_hello_done => Some(5),
+        HandshakeType::new_session_ticket => Some(6),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ServerFlight {
+    seen: Vec<HandshakeType>,
+
+    // the ServerHello resumed a session, so only a NewSessionTicket can follow it
+    abbreviated: bool,
+}
+
+impl ServerFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the messages accepted so far
+    pub fn seen(&self) -> &[HandshakeType] {
+        &self.seen
+    }
+
+    // the server resumed the session: https://datatracker.ietf.org/doc/html/rfc5246#section-7.3
+    pub fn abbreviated(&mut self) {
+        self.abbreviated = true;
+    }
+
+    pub fn accept(&mut self, msg_type: u8) -> Result<(), FlightError> {
+        let message =
+            HandshakeType::try_from(msg_type).map_err(|_| FlightError::Unexpected(msg_type))?;
+
+        // can be sent at any time, and is ignored during the handshake:
+        // https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.1
+        if message == HandshakeType::hello_request {
+            return Ok(());
+        }
+        let position = rank(message).ok_or(FlightError::Unexpected(msg_type))?;
+
+        if self.seen.contains(&message) {
+            return Err(FlightError::Duplicate(message));
+        }
+        match self.seen.last() {
+            None if message != HandshakeType::server_hello => {
+                return Err(FlightError::NoServerHello(message))
+            }
+            // seen messages have a rank
+            Some(&after) if rank(after).unwrap() > position => {
+                return Err(FlightError::OutOfOrder { message, after })
+            }
+            Some(&after) if self.abbreviated && message != HandshakeType::new_session_ticket => {
+                return Err(FlightError::OutOfOrder { message, after })
+            }
+            _ => (),
+        }
+
+        self.seen.push(message);
+        Ok(())
+    }
+}
+
+// check the messages received so far, from the start
+pub fn check_flight(messages: &[HandshakeMessage]) -> Result<(), FlightError> {
+    let mut flight = ServerFlight::new();
+    messages.iter().try_for_each(|x| flight.accept(x.msg_type))
+}
+
+// the first extension of the ServerHello the client didn't send. A HelloRetryRequest can
+// carry a cookie, and renegotiation_info answers the SCSV as well as the extension:
+// https://datatracker.ietf.org/doc/html/rfc5746#section-3.6
+pub fn check_extensions(ch: &ClientHello, sh: &ServerHelloSummary) -> Result<(), FlightError> {
+    let offered = |extension_type: ExtensionType| {
+        ch.extensions()
+            .iter()
+            .any(|x| x.extension_type() == extension_type)
+    };
+
+    for &(type_code, _) in &sh.extensions {
+        let extension_type = ExtensionType::from(type_code);
+        let solicited = offered(extension_type)
+            || match extension_type {
+                ExtensionType::cookie => sh.is_hello_retry_request(),
+                ExtensionType::renegotiation_info => ch
+                    .cipher_suites()
+                    .contains(&TLS_EMPTY_RENEGOTIATION_INFO_SCSV),
+                _ => false,
+            };
+        if !solicited {
+            return Err(FlightError::UnsolicitedExtension(extension_type));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use HandshakeType::*;
+
+    fn check(types: &[HandshakeType]) -> Result<(), FlightError> {
+        let mut flight = ServerFlight::new();
+        types.iter().try_for_each(|&x| flight.accept(x as u8))
+    }
+
+    #[test]
+    fn order() {
+        assert!(check(&[server_hello]).is_ok());
+        assert!(check(&[
+            server_hello,
+            certificate,
+            certificate_status,
+            server_key_exchange,
+            certificate_request,
+            server_hello_done,
+            new_session_ticket
+        ])
+        .is_ok());
+        assert!(check(&[server_hello, hello_request, new_session_ticket]).is_ok());
+
+        assert_eq!(
+            check(&[server_hello, certificate, server_hello]),
+            Err(FlightError::Duplicate(server_hello))
+        );
+        assert_eq!(
+            check(&[server_hello, server_key_exchange, certificate]),
+            Err(FlightError::OutOfOrder {
+                message: certificate,
+                after: server_key_exchange
+            })
+        );
+        assert_eq!(
+            check(&[certificate]),
+            Err(FlightError::NoServerHello(certificate))
+        );
+        assert_eq!(
+            check(&[server_hello, client_hello]),
+            Err(FlightError::Unexpected(1))
+        );
+
+        let mut flight = ServerFlight::new();
+        assert_eq!(flight.accept(99), Err(FlightError::Unexpected(99)));
+        flight.accept(2).unwrap();
+        assert_eq!(flight.seen(), &[server_hello]);
+
+        // no key exchange once the session is resumed
+        flight.clone().accept(new_session_ticket as u8).unwrap();
+        flight.abbreviated();
+        assert_eq!(
+            flight.accept(certificate as u8),
+            Err(FlightError::OutOfOrder {
+                message: certificate,
+                after: server_hello
+            })
+        );
+        assert!(flight.accept(new_session_ticket as u8).is_ok());
+
+        let limits = HandshakeLimits {
+            max_messages: 2,
+            max_duration: Duration::from_secs(60),
+        };
+        let start = Instant::now();
+        assert!(limits.check(start, 2).unwrap() > Duration::from_secs(59));
+        assert_eq!(limits.check(start, 3), Err(FlightError::TooManyMessages(2)));
+        let limits = HandshakeLimits {
+            max_duration: Duration::ZERO,
+            ..limits
+        };
+        assert_eq!(
+            limits.check(start, 0),
+            Err(FlightError::TooLong(Duration::ZERO))
+        );
+        let e = Error::from(FlightError::TooLong(Duration::ZERO));
+        assert_eq!(e.kind(), ErrorKind::TimedOut);
+
+        let e = Error::from(FlightError::Duplicate(server_hello));
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "duplicate server_hello(2)");
+        assert_eq!(
+            e.get_ref().unwrap().downcast_ref::<FlightError>(),
+            Some(&FlightError::Duplicate(server_hello))
+        );
+        assert_eq!(
+            e.get_ref()
+                .unwrap()
+                .downcast_ref::<FlightError>()
+                .map(FlightError::code),
+            Some("duplicate_message")
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        use crate::handshake::client_hello::ProtocolNameList;
+        use crate::handshake::constants::*;
+
+        let mut ch = ClientHello::new(&[TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256]);
+        ch.add_extension(&ProtocolNameList::new(&["h2"])).unwrap();
+        let alpn = u16::from(ExtensionType::application_layer_protocol_negotiation);
+        let mut sh = ServerHelloSummary {
+            extensions: vec![(alpn, vec![0x00, 0x03, 0x02, b'h', b'2'])],
+            ..Default::default()
+        };
+        assert!(check_extensions(&ch, &sh).is_ok());
+
+        // GREASE and unknown types are never solicited
+        for type_code in [0x0A0A, 0xFFFE, 44] {
+            sh.extensions.push((type_code, Vec::new()));
+            assert_eq!(
+                check_extensions(&ch, &sh),
+                Err(FlightError::UnsolicitedExtension(ExtensionType::from(
+                    type_code
+                )))
+            );
+            sh.extensions.pop();
+        }
+
+        // cookie is only sent in a HelloRetryRequest
+        sh.extensions
+            .push((u16::from(ExtensionType::cookie), vec![0x00, 0x01, 0x00]));
+        sh.random = HELLO_RETRY_REQUEST_RANDOM.to_vec();
+        assert!(check_extensions(&ch, &sh).is_ok());
+
+        sh.extensions = vec![(u16::from(ExtensionType::renegotiation_info), vec![0x00])];
+        let e = Error::from(check_extensions(&ch, &sh).unwrap_err());
+        assert_eq!(
+            e.to_string(),
+            "unsolicited extension renegotiation_info(65281)"
+        );
+        let ch = ClientHello::new(&[
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_EMPTY_RENEGOTIATION_INFO_SCSV,
+        ]);
+        assert!(check_extensions(&ch, &sh).is_ok());
+    }
+}
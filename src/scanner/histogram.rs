@@ -0,0 +1,207 @@
+// latency histogram in the style of HdrHistogram: values are counted in buckets whose width
+// grows with the value, so memory stays small while any percentile is known within 1/64 of
+// its value, from microseconds to hours
+use std::fmt;
+use std::time::Duration;
+
+// values below are counted exactly, in microseconds
+const LINEAR: u64 = 128;
+
+// buckets per power of 2 above LINEAR
+const SUB_BUCKETS: u64 = LINEAR / 2;
+
+fn bucket(micros: u64) -> usize {
+    if micros < LINEAR {
+        return micros as usize;
+    }
+
+    // micros >> shift is in [SUB_BUCKETS, LINEAR)
+    let shift = 63 - micros.leading_zeros() as u64 - 6;
+    let sub = (micros >> shift) - SUB_BUCKETS;
+    (LINEAR + (shift - 1) * SUB_BUCKETS + sub) as usize
+}
+
+// the highest value counted in a bucket
+fn highest(bucket: usize) -> u64 {
+    let bucket = bucket as u64;
+    if bucket < LINEAR {
+        return bucket;
+    }
+
+    let shift = (bucket - LINEAR) / SUB_BUCKETS + 1;
+    let sub = (bucket - LINEAR) % SUB_BUCKETS + SUB_BUCKETS;
+    ((sub + 1) << shift) - 1
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    min: u64,
+    max: u64,
+    sum: u128,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        let index = bucket(micros);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+
+        self.min = if self.total == 0 {
+            micros
+        } else {
+            self.min.min(micros)
+        };
+        self.max = self.max.max(micros);
+        self.sum += micros as u128;
+        self.total += 1;
+    }
+
+    // add the values of another histogram, e.g. one per worker
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.total == 0 {
+            return;
+        }
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+
+        self.min = if self.total == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.total += other.total;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        (self.total > 0).then(|| Duration::from_micros(self.min))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.total > 0).then(|| Duration::from_micros(self.max))
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.total > 0).then(|| Duration::from_micros((self.sum / self.total as u128) as u64))
+    }
+
+    // nearest-rank percentile, the highest value of its bucket but never above the maximum
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((p / 100.0 * self.total as f64).ceil() as u64).clamp(1, self.total);
+
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let micros = highest(index).clamp(self.min, self.max);
+                return Some(Duration::from_micros(micros));
+            }
+        }
+        self.max()
+    }
+}
+
+fn millis(latency: Duration) -> String {
+    format!("{:.2}ms", latency.as_secs_f64() * 1000.0)
+}
+
+// p50 1.20ms p95 3.40ms p99 5.00ms max 6.00ms (12 values)
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no values");
+        }
+
+        for (name, p) in [("p50", 50.0), ("p95", 95.0), ("p99", 99.0)] {
+            // not empty
+            write!(f, "{} {} ", name, millis(self.percentile(p).unwrap()))?;
+        }
+        write!(
+            f,
+            "max {} ({} values)",
+            millis(self.max().unwrap()),
+            self.total
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        for micros in [0, 1, 127, 128, 129, 255, 256, 1000, 123_456, 3_600_000_000] {
+            let index = bucket(micros);
+            assert!(highest(index) >= micros, "{}", micros);
+            assert!(index == 0 || highest(index - 1) < micros, "{}", micros);
+
+            // within 1/64 of the value
+            assert!((highest(index) - micros) as f64 <= micros as f64 / 64.0);
+        }
+        assert_eq!(bucket(127), 127);
+        assert_eq!(bucket(128), 128);
+        assert_eq!(bucket(129), 128);
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = LatencyHistogram::new();
+        assert!(histogram.percentile(50.0).is_none());
+        assert_eq!(histogram.to_string(), "no values");
+
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+
+        let p50 = histogram.percentile(50.0).unwrap();
+        assert!(p50 >= Duration::from_millis(50) && p50 <= Duration::from_micros(50_800));
+        assert_eq!(
+            histogram.percentile(100.0),
+            Some(Duration::from_millis(100))
+        );
+        let p0 = histogram.percentile(0.0).unwrap();
+        assert!(p0 >= Duration::from_millis(1) && p0 <= Duration::from_micros(1016));
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(50_500)));
+
+        // merging keeps percentiles
+        let mut merged = LatencyHistogram::new();
+        merged.merge(&LatencyHistogram::new());
+        merged.merge(&histogram);
+        assert_eq!(merged.count(), 100);
+        assert_eq!(merged.percentile(99.0), histogram.percentile(99.0));
+        assert_eq!(merged.min(), Some(Duration::from_millis(1)));
+
+        let mut histogram = LatencyHistogram::new();
+        histogram.record(Duration::from_micros(1500));
+        assert_eq!(
+            histogram.to_string(),
+            "p50 1.50ms p95 1.50ms p99 1.50ms max 1.50ms (1 values)"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// what was negotiated on a connection, derived from the server's ServerHello and the
+// handshake messages read after it, so callers don't decode them again
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::dissector::summary::ServerHelloSummary;
+use crate::dissector::HandshakeMessage;
+use crate::handshake::client_hello::{ExtensionContext, ExtensionType, ProtocolNameList};
+use crate::handshake::common::{cipher_suite_name, version_name, ProtocolVersion};
+use crate::handshake::extensions::Extension;
+use crate::handshake::handshake::HandshakeType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    // https://datatracker.ietf.org/doc/html/rfc7627
+    ExtendedMasterSecret,
+
+    // https://datatracker.ietf.org/doc/html/rfc5746
+    SecureRenegotiation,
+
+    // a CertificateStatus message was received
+    OcspStaple,
+
+    // a NewSessionTicket message was received
+    SessionTicket,
+
+    // the server selected an application protocol
+    Alpn,
+
+    // a KeyUpdate message was seen
+    KeyUpdate,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Feature::ExtendedMasterSecret => "extended_master_secret",
+            Feature::SecureRenegotiation => "secure_renegotiation",
+            Feature::OcspStaple => "ocsp_staple",
+            Feature::SessionTicket => "session_ticket",
+            Feature::Alpn => "alpn",
+            Feature::KeyUpdate => "key_update",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConnectionInfo {
+    pub version: ProtocolVersion,
+    pub cipher_suite: u16,
+    pub extended_master_secret: bool,
+    pub secure_renegotiation: bool,
+    pub ocsp_staple: bool,
+    pub session_ticket: bool,
+
+    // the protocol selected by the server, None if it didn't answer ALPN
+    pub alpn: Option<String>,
+    pub key_update: bool,
+}
+
+impl ConnectionInfo {
+    // messages are those read after the ServerHello, which may include it
+    pub fn new(sh: &ServerHelloSummary, messages: &[HandshakeMessage]) -> Self {
+        let received =
+            |msg_type: HandshakeType| messages.iter().any(|x| x.msg_type == msg_type as u8);
+        let extension = |extension_type: ExtensionType| sh.extension(u16::from(extension_type));
+
+        // a malformed answer is the same as none
+        let alpn = extension(ExtensionType::application_layer_protocol_negotiation)
+            .and_then(|data| {
+                ProtocolNameList::decode_body(data, ExtensionContext::ServerHello).ok()
+            })
+            .and_then(|list| list.protocols().into_iter().next());
+
+        Self {
+            version: sh.version(),
+            cipher_suite: sh.cipher_suite,
+            extended_master_secret: extension(ExtensionType::extended_master_secret).is_some(),
+            secure_renegotiation: extension(ExtensionType::renegotiation_info).is_some(),
+            ocsp_staple: received(HandshakeType::certificate_status),
+            session_ticket: received(HandshakeType::new_session_ticket),
+            alpn,
+            key_update: received(HandshakeType::key_update),
+        }
+    }
+
+    // the features negotiated, in the order of Feature
+    pub fn features(&self) -> Vec<Feature> {
+        [
+            (Feature::ExtendedMasterSecret, self.extended_master_secret),
+            (Feature::SecureRenegotiation, self.secure_renegotiation),
+            (Feature::OcspStaple, self.ocsp_staple),
+            (Feature::SessionTicket, self.session_ticket),
+            (Feature::Alpn, self.alpn.is_some()),
+            (Feature::KeyUpdate, self.key_update),
+        ]
+        .into_iter()
+        .filter_map(|(feature, on)| on.then_some(feature))
+        .collect()
+    }
+
+    // variables for scripts: TLS_VERSION, TLS_CIPHER, TLS_ALPN, empty without ALPN, and
+    // TLS_FEATURES, comma-separated
+    pub fn environment(&self) -> HashMap<String, String> {
+        let features: Vec<String> = self.features().iter().map(|x| x.to_string()).collect();
+        HashMap::from([
+            (String::from("TLS_VERSION"), version_name(self.version)),
+            (
+                String::from("TLS_CIPHER"),
+                cipher_suite_name(self.cipher_suite.to_be_bytes()),
+            ),
+            (
+                String::from("TLS_ALPN"),
+                self.alpn.clone().unwrap_or_default(),
+            ),
+            (String::from("TLS_FEATURES"), features.join(",")),
+        ])
+    }
+}
+
+impl fmt::Display for ConnectionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features: Vec<String> = self.features().iter().map(|x| x.to_string()).collect();
+        writeln!(f, "version: {}", version_name(self.version))?;
+        writeln!(
+            f,
+            "cipher suite: {}",
+            cipher_suite_name(self.cipher_suite.to_be_bytes())
+        )?;
+        writeln!(f, "alpn: {}", self.alpn.as_deref().unwrap_or("<none>"))?;
+        writeln!(f, "features: {}", features.join(", "))
+    }
+}
+
+// NAME='value' lines sorted by name, for eval in a shell. Values come from the server, so
+// they're single-quoted with their own quotes escaped
+pub fn export_env(environment: &HashMap<String, String>) -> String {
+    let mut names: Vec<&String> = environment.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("{}='{}'\n", name, environment[name].replace('\'', "'\\''")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::TLS12;
+
+    #[test]
+    fn features() {
+        let alpn = vec![0x00, 0x03, 0x02, b'h', b'2'];
+        let sh = ServerHelloSummary {
+            server_version: TLS12,
+            cipher_suite: 0xC02F,
+            extensions: vec![(23, Vec::new()), (0xFF01, vec![0]), (16, alpn)],
+            ..Default::default()
+        };
+        let message = |msg_type: HandshakeType| HandshakeMessage {
+            msg_type: msg_type as u8,
+            body: Vec::new(),
+        };
+
+        let info = ConnectionInfo::new(
+            &sh,
+            &[
+                message(HandshakeType::server_hello),
+                message(HandshakeType::certificate_status),
+            ],
+        );
+        assert_eq!(info.version, TLS12);
+        assert_eq!(info.alpn.as_deref(), Some("h2"));
+        assert!(!info.session_ticket);
+        assert_eq!(
+            info.features(),
+            vec![
+                Feature::ExtendedMasterSecret,
+                Feature::SecureRenegotiation,
+                Feature::OcspStaple,
+                Feature::Alpn
+            ]
+        );
+
+        assert_eq!(
+            export_env(&info.environment()),
+            "TLS_ALPN='h2'\n\
+             TLS_CIPHER='TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256'\n\
+             TLS_FEATURES='extended_master_secret,secure_renegotiation,ocsp_staple,alpn'\n\
+             TLS_VERSION='TLS 1.2'\n"
+        );
+
+        assert!(info.to_string().ends_with(
+            "alpn: h2\nfeatures: extended_master_secret, secure_renegotiation, ocsp_staple, alpn\n"
+        ));
+
+        let info = ConnectionInfo::new(&ServerHelloSummary::default(), &[]);
+        assert!(info.features().is_empty());
+        assert_eq!(info.environment()["TLS_ALPN"], "");
+
+        // a protocol name trying to run a command
+        let info = ConnectionInfo {
+            alpn: Some(String::from("h2'; rm -rf ~; '")),
+            ..info
+        };
+        assert_eq!(
+            export_env(&info.environment()).lines().next(),
+            Some("TLS_ALPN='h2'\\''; rm -rf ~; '\\'''")
+        );
+        assert_eq!(Feature::OcspStaple.to_string(), "ocsp_staple");
+    }
+}
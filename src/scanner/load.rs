@@ -0,0 +1,263 @@
+// open handshakes with a server at a steady rate, and report how many succeeded and how long
+// they took. A handshake is a ClientHello and the server's first answer, which is where the
+// server does most of its work
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::handshake::constants::TLS13;
+use crate::scanner::connector::{Connector, ServerResponse};
+use crate::scanner::histogram::LatencyHistogram;
+use crate::shutdown::ShutdownToken;
+use crate::stats::Distribution;
+
+// 500ms, 60s, 2m or a number of seconds
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let (value, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let value: u64 = value.parse().ok()?;
+
+    match unit {
+        "ms" => Some(Duration::from_millis(value)),
+        "s" => Some(Duration::from_secs(value)),
+        "m" => Some(Duration::from_secs(value * 60)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    // handshakes started per second
+    pub rate: u32,
+    pub duration: Duration,
+
+    // offer the session ID of the first handshake in all the others
+    pub resume: bool,
+
+    // handshakes in flight at most, so a slow server delays the schedule instead of piling
+    // up connections
+    pub workers: usize,
+}
+
+impl LoadConfig {
+    pub fn new(rate: u32, duration: Duration) -> Self {
+        Self {
+            rate,
+            duration,
+            resume: false,
+            workers: (rate as usize).clamp(1, 256),
+        }
+    }
+
+    // the time each handshake is due, from the start
+    fn schedule(&self, index: usize) -> Duration {
+        Duration::from_secs(index as u64) / self.rate.max(1)
+    }
+
+    fn handshakes(&self) -> usize {
+        (self.duration.as_secs_f64() * self.rate as f64) as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Full,
+    Resumed,
+
+    // description of the answer or of the error
+    Failed(String),
+}
+
+// what the server answered, and whether it resumed the offered session. TLS 1.3 servers echo
+// any session ID, so they can't resume this way
+fn outcome(response: std::io::Result<ServerResponse>, offered: &[u8]) -> Outcome {
+    match response {
+        Ok(ServerResponse::Hello(sh)) => {
+            if !offered.is_empty() && sh.session_id == offered && sh.version() != TLS13 {
+                Outcome::Resumed
+            } else {
+                Outcome::Full
+            }
+        }
+        Ok(response) => Outcome::Failed(response.to_string()),
+        Err(e) => Outcome::Failed(format!("error {}", e.kind())),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub elapsed: Duration,
+    pub attempted: usize,
+    pub full: usize,
+    pub resumed: usize,
+
+    // failed handshakes by answer
+    pub failures: Distribution,
+
+    // of successful handshakes
+    pub latencies: LatencyHistogram,
+}
+
+impl LoadReport {
+    pub fn succeeded(&self) -> usize {
+        self.full + self.resumed
+    }
+
+    fn add(&mut self, outcome: Outcome, latency: Duration) {
+        self.attempted += 1;
+        match outcome {
+            Outcome::Full => self.full += 1,
+            Outcome::Resumed => self.resumed += 1,
+            Outcome::Failed(answer) => {
+                self.failures.add(answer);
+                return;
+            }
+        }
+        self.latencies.record(latency);
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "handshakes: {} in {:.2}s ({:.1}/s)",
+            self.attempted,
+            seconds,
+            self.attempted as f64 / seconds.max(f64::EPSILON)
+        )?;
+        writeln!(
+            f,
+            "succeeded: {} ({:.2}%), {} full, {} resumed",
+            self.succeeded(),
+            100.0 * self.succeeded() as f64 / self.attempted.max(1) as f64,
+            self.full,
+            self.resumed
+        )?;
+
+        writeln!(f, "failed: {}", self.attempted - self.succeeded())?;
+        for (answer, count) in self.failures.sorted() {
+            writeln!(f, "{:>8}  {}", count, answer)?;
+        }
+
+        writeln!(f, "latency: {}", self.latencies)?;
+        Ok(())
+    }
+}
+
+// run handshakes until the duration is over or shutdown is requested. Handshakes start on
+// schedule as long as a worker is free
+pub fn run(connector: &Connector, config: &LoadConfig, shutdown: &ShutdownToken) -> LoadReport {
+    let mut report = LoadReport::default();
+    let start = Instant::now();
+    let mut first = 0;
+    let mut session_id = Vec::new();
+
+    // the first handshake gives the session to resume
+    if config.resume && config.handshakes() > 0 {
+        let ch = connector.client_hello();
+        let sent = Instant::now();
+        let response = connector.send(&ch);
+        if let Ok(ServerResponse::Hello(sh)) = &response {
+            session_id = sh.session_id.clone();
+        }
+        report.add(outcome(response, &[]), sent.elapsed());
+        first = 1;
+    }
+
+    let next = AtomicUsize::new(first);
+    let report = Mutex::new(report);
+
+    std::thread::scope(|scope| {
+        for _ in 0..config.workers.max(1) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                if index >= config.handshakes() || shutdown.is_shutdown() {
+                    break;
+                }
+
+                if let Some(wait) = config.schedule(index).checked_sub(start.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+
+                let ch = connector.client_hello().with_session_id(&session_id);
+                let sent = Instant::now();
+                let outcome = outcome(connector.send(&ch), &session_id);
+
+                // the lock is only poisoned if another worker panicked
+                report.lock().unwrap().add(outcome, sent.elapsed());
+            });
+        }
+    });
+
+    let mut report = report.into_inner().unwrap();
+    report.elapsed = start.elapsed();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::server::Server;
+    use std::net::TcpListener;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("60s"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_duration("5h"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn report() {
+        let mut report = LoadReport::default();
+        for ms in 1..=100 {
+            report.add(Outcome::Full, Duration::from_millis(ms));
+        }
+        report.add(Outcome::Resumed, Duration::from_millis(100));
+        report.add(Outcome::Failed(String::from("closed")), Duration::ZERO);
+
+        assert_eq!(report.succeeded(), 101);
+        assert_eq!(report.latencies.count(), 101);
+
+        report.elapsed = Duration::from_secs(1);
+        let text = report.to_string();
+        assert!(text.starts_with("handshakes: 102 in 1.00s (102.0/s)\n"));
+        assert!(text.contains("succeeded: 101 (99.02%), 100 full, 1 resumed\n"));
+        assert!(text.contains("failed: 1\n       1  closed\n"));
+        assert!(text.contains("latency: p50 "));
+    }
+
+    #[test]
+    fn mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let connector = Connector::new(&listener.local_addr().unwrap().to_string());
+
+        let mut server = Server::default();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+        let shutdown = ShutdownToken::new();
+
+        let mut config = LoadConfig::new(50, Duration::from_millis(200));
+        config.resume = true;
+
+        let report = std::thread::scope(|scope| {
+            scope.spawn(|| server.serve(&listener, &shutdown).unwrap());
+            let report = run(&connector, &config, &ShutdownToken::new());
+            shutdown.shutdown();
+            report
+        });
+
+        assert_eq!(report.attempted, 10);
+        assert_eq!(report.succeeded(), 10);
+        assert!(report.elapsed >= Duration::from_millis(180));
+        assert_eq!(report.latencies.count(), 10);
+        assert_eq!(connector.latencies().count(), 10);
+    }
+}
@@ -0,0 +1,349 @@
+// enumerate which cipher suites a server accepts for each protocol version, one connection per
+// (version, suite) pair
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+
+use crate::handshake::client_hello::{
+    ClientHello, GenericExtension, KeyShare, KeyShareEntry, NamedGroup, SupportedVersions,
+};
+use crate::handshake::common::{cipher_suite_name, version_name, CipherSuite, ProtocolVersion};
+use crate::handshake::constants::{CIPHER_SUITES, TLS10, TLS11, TLS12, TLS13};
+use crate::scanner::connector::{Connector, ServerResponse};
+use crate::scanner::report::{csv_field, json_string, OutputFormat};
+use crate::shutdown::ShutdownToken;
+
+pub const ENUMERATED_VERSIONS: [ProtocolVersion; 4] = [TLS10, TLS11, TLS12, TLS13];
+
+// what the server answered to a hello offering a single suite
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Accepted,
+
+    // the server answered with another version or suite
+    Declined,
+
+    // raw alert description
+    Alert(u8),
+    Closed,
+    Error(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Accepted => write!(f, "accepted"),
+            Outcome::Declined => write!(f, "declined"),
+            Outcome::Alert(description) => write!(f, "alert {}", description),
+            Outcome::Closed => write!(f, "closed"),
+            Outcome::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CapabilityMatrix {
+    pub versions: Vec<ProtocolVersion>,
+    pub suites: Vec<CipherSuite>,
+
+    // one row per suite, one column per version
+    pub outcomes: Vec<Vec<Outcome>>,
+}
+
+impl CapabilityMatrix {
+    // once shutdown is requested, the row being probed is completed and the matrix only has
+    // the suites probed so far
+    pub fn enumerate(
+        connector: &Connector,
+        versions: &[ProtocolVersion],
+        suites: &[CipherSuite],
+        shutdown: &ShutdownToken,
+    ) -> Self {
+        let outcomes: Vec<Vec<Outcome>> = suites
+            .iter()
+            .take_while(|_| !shutdown.is_shutdown())
+            .map(|suite| {
+                versions
+                    .iter()
+                    .map(|version| Self::probe(connector, *version, *suite))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            versions: versions.to_vec(),
+            suites: suites[..outcomes.len()].to_vec(),
+            outcomes,
+        }
+    }
+
+    // every known suite with every version
+    pub fn enumerate_all(connector: &Connector, shutdown: &ShutdownToken) -> Self {
+        let suites: Vec<_> = CIPHER_SUITES.iter().map(|(_, suite)| *suite).collect();
+        Self::enumerate(connector, &ENUMERATED_VERSIONS, &suites, shutdown)
+    }
+
+    // a hello offering only the version and the suite
+    fn client_hello(
+        connector: &Connector,
+        version: ProtocolVersion,
+        suite: CipherSuite,
+    ) -> ClientHello {
+        let ch = connector.client_hello().with_suites(&[suite]);
+        if version < TLS13 {
+            return ch.with_version(version);
+        }
+
+        // TLS 1.3 is only offered in supported_versions, and needs a key share
+        let key_share = KeyShare::new(vec![KeyShareEntry::new(
+            NamedGroup::x25519,
+            &rand::random::<[u8; 32]>(),
+        )]);
+        [
+            GenericExtension::from_extension(&SupportedVersions::new(&[version])),
+            GenericExtension::from_extension(&key_share),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(ch.with_version(TLS12), |ch, ext| {
+            ch.with_extension_replaced(ext)
+        })
+    }
+
+    fn probe(connector: &Connector, version: ProtocolVersion, suite: CipherSuite) -> Outcome {
+        match connector.send(&Self::client_hello(connector, version, suite)) {
+            // a HelloRetryRequest also selects the suite
+            Ok(ServerResponse::Hello(sh))
+                if sh.version() == version && sh.cipher_suite.to_be_bytes() == suite =>
+            {
+                Outcome::Accepted
+            }
+            Ok(ServerResponse::Hello(_)) => Outcome::Declined,
+            Ok(ServerResponse::Alert { description, .. }) => Outcome::Alert(description),
+            Ok(ServerResponse::Closed) => Outcome::Closed,
+            Err(e) => Outcome::Error(e.to_string()),
+        }
+    }
+
+    // suites accepted for the version
+    pub fn accepted(&self, version: ProtocolVersion) -> Vec<CipherSuite> {
+        let Some(column) = self.versions.iter().position(|x| *x == version) else {
+            return Vec::new();
+        };
+
+        self.suites
+            .iter()
+            .zip(&self.outcomes)
+            .filter(|(_, row)| row[column] == Outcome::Accepted)
+            .map(|(suite, _)| *suite)
+            .collect()
+    }
+
+    pub fn render(&self, target: &str, format: OutputFormat) -> Result<String> {
+        match format {
+            OutputFormat::Text => Ok(self.to_string()),
+            OutputFormat::Json => Ok(self.json(target)),
+            OutputFormat::Csv => Ok(self.csv()),
+            OutputFormat::Sarif => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "a capability matrix has no SARIF form",
+            )),
+        }
+    }
+
+    fn json(&self, target: &str) -> String {
+        let versions: Vec<String> = self
+            .versions
+            .iter()
+            .map(|x| json_string(&version_name(*x)))
+            .collect();
+
+        let rows: Vec<String> = self
+            .suites
+            .iter()
+            .zip(&self.outcomes)
+            .map(|(suite, row)| {
+                let outcomes: Vec<String> =
+                    row.iter().map(|x| json_string(&x.to_string())).collect();
+                format!(
+                    "{{\"suite\":{},\"code\":\"0x{:02X}{:02X}\",\"outcomes\":[{}]}}",
+                    json_string(&cipher_suite_name(*suite)),
+                    suite[0],
+                    suite[1],
+                    outcomes.join(",")
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"target\":{},\"versions\":[{}],\"suites\":[{}]}}\n",
+            json_string(target),
+            versions.join(","),
+            rows.join(",")
+        )
+    }
+
+    // one line per cell
+    fn csv(&self) -> String {
+        let mut csv = String::from("version,suite,outcome\n");
+        for (suite, row) in self.suites.iter().zip(&self.outcomes) {
+            for (version, outcome) in self.versions.iter().zip(row) {
+                csv.push_str(&format!(
+                    "{},{},{}\n",
+                    version_name(*version),
+                    cipher_suite_name(*suite),
+                    csv_field(&outcome.to_string())
+                ));
+            }
+        }
+        csv
+    }
+}
+
+// a table of the suites accepted for at least one version
+impl fmt::Display for CapabilityMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<50}", "cipher suite")?;
+        for version in &self.versions {
+            write!(f, "{:>9}", version_name(*version))?;
+        }
+        writeln!(f)?;
+
+        let mut rejected = 0;
+        for (suite, row) in self.suites.iter().zip(&self.outcomes) {
+            if !row.contains(&Outcome::Accepted) {
+                rejected += 1;
+                continue;
+            }
+
+            write!(f, "{:<50}", cipher_suite_name(*suite))?;
+            for outcome in row {
+                let cell = if *outcome == Outcome::Accepted {
+                    "yes"
+                } else {
+                    "-"
+                };
+                write!(f, "{:>9}", cell)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "{} suites not accepted", rejected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_tls::TlsDerive;
+    use crate::dissector::summary::ClientHelloSummary;
+    use crate::handshake::constants::*;
+    use crate::server::server::Server;
+    use std::net::TcpListener;
+
+    fn matrix() -> CapabilityMatrix {
+        CapabilityMatrix {
+            versions: vec![TLS11, TLS12],
+            suites: vec![TLS_RSA_WITH_AES_128_CBC_SHA, TLS_RSA_WITH_RC4_128_SHA],
+            outcomes: vec![
+                vec![Outcome::Declined, Outcome::Accepted],
+                vec![Outcome::Alert(40), Outcome::Error(String::from("a, \"b\""))],
+            ],
+        }
+    }
+
+    #[test]
+    fn mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        connector.timeout = std::time::Duration::from_secs(2);
+
+        let mut server = Server::default();
+        server.config.cipher_suites = vec![TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256];
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+
+        let versions = [TLS10, TLS12];
+        let suites = [
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            TLS_RSA_WITH_RC4_128_SHA,
+        ];
+
+        let matrix = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..versions.len() * suites.len() {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let _ = server.handle(&mut stream);
+                }
+            });
+
+            CapabilityMatrix::enumerate(&connector, &versions, &suites, &ShutdownToken::new())
+        });
+
+        // the mock server always answers with TLS 1.2
+        assert_eq!(
+            matrix.outcomes,
+            vec![
+                vec![Outcome::Declined, Outcome::Accepted],
+                vec![Outcome::Alert(40), Outcome::Alert(40)],
+            ]
+        );
+        assert_eq!(
+            matrix.accepted(TLS12),
+            vec![TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256]
+        );
+        assert!(matrix.accepted(TLS10).is_empty());
+        assert!(matrix.accepted(TLS13).is_empty());
+
+        // nothing is probed after shutdown
+        let shutdown = ShutdownToken::new();
+        shutdown.shutdown();
+        let matrix = CapabilityMatrix::enumerate(&connector, &versions, &suites, &shutdown);
+        assert!(matrix.suites.is_empty() && matrix.outcomes.is_empty());
+    }
+
+    #[test]
+    fn tls13_hello() {
+        let connector = Connector::new("example.com");
+        let ch = CapabilityMatrix::client_hello(&connector, TLS13, TLS_AES_128_GCM_SHA256);
+        assert_eq!(ch.cipher_suites(), &[TLS_AES_128_GCM_SHA256]);
+
+        let mut buffer = Vec::new();
+        ch.to_network_bytes(&mut buffer).unwrap();
+        let summary = ClientHelloSummary::parse(&buffer).unwrap();
+        assert_eq!(summary.supported_versions, vec![TLS13]);
+    }
+
+    #[test]
+    fn render() {
+        let matrix = matrix();
+
+        let text = matrix
+            .render("example.com:443", OutputFormat::Text)
+            .unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("  TLS 1.1  TLS 1.2"));
+        assert!(lines[1].starts_with("TLS_RSA_WITH_AES_128_CBC_SHA "));
+        assert!(lines[1].ends_with("        -      yes"));
+        assert_eq!(lines[2], "1 suites not accepted");
+
+        assert_eq!(
+            matrix.render("example.com:443", OutputFormat::Csv).unwrap(),
+            "version,suite,outcome\n\
+             TLS 1.1,TLS_RSA_WITH_AES_128_CBC_SHA,declined\n\
+             TLS 1.2,TLS_RSA_WITH_AES_128_CBC_SHA,accepted\n\
+             TLS 1.1,TLS_RSA_WITH_RC4_128_SHA,alert 40\n\
+             TLS 1.2,TLS_RSA_WITH_RC4_128_SHA,\"error: a, \"\"b\"\"\"\n"
+        );
+
+        assert_eq!(
+            matrix.render("example.com:443", OutputFormat::Json).unwrap(),
+            "{\"target\":\"example.com:443\",\"versions\":[\"TLS 1.1\",\"TLS 1.2\"],\"suites\":[\
+             {\"suite\":\"TLS_RSA_WITH_AES_128_CBC_SHA\",\"code\":\"0x002F\",\"outcomes\":[\"declined\",\"accepted\"]},\
+             {\"suite\":\"TLS_RSA_WITH_RC4_128_SHA\",\"code\":\"0x0005\",\"outcomes\":[\"alert 40\",\"error: a, \\\"b\\\"\"]}]}\n"
+        );
+
+        assert!(matrix
+            .render("example.com:443", OutputFormat::Sarif)
+            .is_err());
+    }
+}
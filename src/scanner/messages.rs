@@ -0,0 +1,184 @@
+// the text of findings, looked up by code in a catalog so reports can be written in another
+// language. English is built in, other locales are files in the config format, with a string
+// for each code:
+//
+//   # French
+//   legacy_accepted = "le serveur accepte {}"
+//   check.legacy_versions = "le serveur accepte TLS 1.0 ou TLS 1.1"
+//
+// {} are replaced by the arguments, in order. Keys starting with check. translate the
+// descriptions of checks. Missing codes fall back to English
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::config::{invalid, parse_pairs, Value};
+use crate::scanner::check::Check;
+
+// codes are stable, only their text may change
+pub const ENGLISH: [(&str, &str); 18] = [
+    ("could_not_run", "could not run: {}"),
+    ("alert_answer", "server answered with alert {}"),
+    ("connection_closed", "server closed the connection"),
+    ("tls13_negotiated", "TLS 1.3 negotiated"),
+    ("legacy_rejected", "TLS 1.0 and TLS 1.1 rejected"),
+    ("legacy_accepted", "server accepts {}"),
+    ("weak_suite_selected", "server selects {}"),
+    ("no_weak_suite", "no weak suite selected"),
+    ("renegotiation_supported", "secure renegotiation supported"),
+    (
+        "no_renegotiation_info",
+        "no renegotiation_info in ServerHello",
+    ),
+    ("ocsp_supported", "OCSP stapling supported"),
+    ("no_ocsp", "no OCSP stapling"),
+    ("no_certificate", "no certificate sent"),
+    ("certificate_error", "could not parse certificate: {}"),
+    (
+        "must_staple_missing",
+        "must-staple asserted but no staple sent",
+    ),
+    (
+        "must_staple_stapled",
+        "must-staple asserted and staple sent",
+    ),
+    ("no_must_staple", "no must-staple"),
+    ("policies", "policies: {}"),
+];
+
+// the English text of a code
+pub fn english(code: &str) -> Option<&'static str> {
+    ENGLISH
+        .iter()
+        .find(|(x, _)| *x == code)
+        .map(|(_, text)| *text)
+}
+
+// replace each {} by the next argument, missing ones by nothing
+fn fill(template: &str, args: &[String]) -> String {
+    let mut parts = template.split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        text.push_str(args.get(i).map_or("", |x| x.as_str()));
+        text.push_str(part);
+    }
+    text
+}
+
+// a message of a finding, with the values inserted in its text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub code: &'static str,
+    pub args: Vec<String>,
+}
+
+impl Message {
+    pub fn new(code: &'static str, args: &[&str]) -> Self {
+        Self {
+            code,
+            args: args.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    // the code itself if it isn't in the catalog
+    pub fn english(&self) -> String {
+        fill(english(self.code).unwrap_or(self.code), &self.args)
+    }
+}
+
+// translations of the English messages, English itself when empty
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    // codes must exist, and their text have as many {} as the English one
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut messages = HashMap::new();
+
+        for (line, key, value) in parse_pairs(text)? {
+            let Value::String(message) = value else {
+                return Err(invalid(line, &format!("wrong type for {}", key)));
+            };
+            let placeholders = if key.starts_with("check.") {
+                0
+            } else {
+                english(&key)
+                    .ok_or_else(|| invalid(line, &format!("unknown message {}", key)))?
+                    .matches("{}")
+                    .count()
+            };
+            if message.matches("{}").count() != placeholders {
+                return Err(invalid(
+                    line,
+                    &format!("{} needs {} {{}}", key, placeholders),
+                ));
+            }
+            messages.insert(key, message);
+        }
+
+        Ok(Self { messages })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn message(&self, message: &Message) -> String {
+        match self.messages.get(message.code) {
+            Some(template) => fill(template, &message.args),
+            None => message.english(),
+        }
+    }
+
+    pub fn description(&self, check: &dyn Check) -> String {
+        let key = format!("check.{}", check.name());
+        self.messages
+            .get(&key)
+            .cloned()
+            .unwrap_or_else(|| check.description().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::checks::LegacyVersions;
+
+    #[test]
+    fn catalog() {
+        let accepted = Message::new("legacy_accepted", &["TLS 1.0, TLS 1.1"]);
+        assert_eq!(accepted.english(), "server accepts TLS 1.0, TLS 1.1");
+        assert_eq!(Catalog::default().message(&accepted), accepted.english());
+
+        let catalog = Catalog::parse(
+            r#"
+# French
+legacy_accepted = "le serveur accepte {}"
+check.legacy_versions = "le serveur accepte TLS 1.0 ou TLS 1.1"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.message(&accepted),
+            "le serveur accepte TLS 1.0, TLS 1.1"
+        );
+        assert_eq!(
+            catalog.message(&Message::new("no_ocsp", &[])),
+            "no OCSP stapling"
+        );
+        assert_eq!(
+            catalog.description(&LegacyVersions),
+            "le serveur accepte TLS 1.0 ou TLS 1.1"
+        );
+
+        // unknown codes, placeholders which don't match, and values which aren't strings
+        assert!(Catalog::parse("legacy = \"x\"").is_err());
+        assert!(Catalog::parse("legacy_accepted = \"le serveur accepte\"").is_err());
+        assert!(Catalog::parse("no_ocsp = \"{}\"").is_err());
+        assert!(Catalog::parse("no_ocsp = 1").is_err());
+    }
+}
@@ -0,0 +1,15 @@
+// scan a server for weaknesses. Each check is a separate unit registered in a registry, so
+// new checks can be added without changing the scanner itself
+pub mod abbreviated;
+pub mod check;
+pub mod checks;
+pub mod connector;
+pub mod flight;
+pub mod histogram;
+pub mod info;
+pub mod load;
+pub mod matrix;
+pub mod messages;
+pub mod report;
+pub mod resumption;
+pub mod session_store;
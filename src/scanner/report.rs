@@ -0,0 +1,281 @@
+// render scan findings as text, JSON, CSV, or SARIF for code-scanning dashboards:
+// https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+use crate::scanner::check::{Check, Finding, Severity};
+use crate::scanner::messages::Catalog;
+
+pub const OUTPUT_FORMATS: [&str; 4] = ["text", "json", "csv", "sarif"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Sarif,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            "sarif" => Some(OutputFormat::Sarif),
+            _ => None,
+        }
+    }
+}
+
+// target is the scanned host, checks those which were run
+pub fn render(
+    format: OutputFormat,
+    target: &str,
+    checks: &[&dyn Check],
+    findings: &[Finding],
+) -> String {
+    render_localized(format, target, checks, findings, &Catalog::default())
+}
+
+// messages and descriptions in the language of the catalog. Check names, severities and
+// message codes are never translated, so reports can still be matched by programs
+pub fn render_localized(
+    format: OutputFormat,
+    target: &str,
+    checks: &[&dyn Check],
+    findings: &[Finding],
+    catalog: &Catalog,
+) -> String {
+    match format {
+        OutputFormat::Text => findings
+            .iter()
+            .map(|x| format!("[{}] {}: {}\n", x.severity, x.check, x.localized(catalog)))
+            .collect(),
+        OutputFormat::Json => json(target, findings, catalog),
+        OutputFormat::Csv => csv(findings, catalog),
+        OutputFormat::Sarif => sarif(target, checks, findings, catalog),
+    }
+}
+
+// null for findings without a code
+fn json_code(finding: &Finding) -> String {
+    finding.code().map_or(String::from("null"), json_string)
+}
+
+// a JSON string literal
+pub(crate) fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json(target: &str, findings: &[Finding], catalog: &Catalog) -> String {
+    let findings: Vec<String> = findings
+        .iter()
+        .map(|x| {
+            format!(
+                "{{\"check\":{},\"severity\":{},\"code\":{},\"message\":{}}}",
+                json_string(&x.check),
+                json_string(&x.severity.to_string()),
+                json_code(x),
+                json_string(&x.localized(catalog))
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"target\":{},\"findings\":[{}]}}\n",
+        json_string(target),
+        findings.join(",")
+    )
+}
+
+fn csv(findings: &[Finding], catalog: &Catalog) -> String {
+    let mut csv = String::from("check,severity,message\n");
+    for finding in findings {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&finding.check),
+            finding.severity,
+            csv_field(&finding.localized(catalog))
+        ));
+    }
+    csv
+}
+
+// quote fields containing a separator or a quote: https://datatracker.ietf.org/doc/html/rfc4180#section-2
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// SARIF only has 3 levels for results
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::High | Severity::Critical => "error",
+    }
+}
+
+fn sarif(target: &str, checks: &[&dyn Check], findings: &[Finding], catalog: &Catalog) -> String {
+    let rules: Vec<String> = checks
+        .iter()
+        .map(|x| {
+            format!(
+                "{{\"id\":{},\"shortDescription\":{{\"text\":{}}}}}",
+                json_string(x.name()),
+                json_string(&catalog.description(*x))
+            )
+        })
+        .collect();
+
+    // the scanned server is the only location
+    let location = format!(
+        "[{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}}}}}}]",
+        json_string(target)
+    );
+
+    let results: Vec<String> = findings
+        .iter()
+        .map(|x| {
+            format!(
+                "{{\"ruleId\":{},\"level\":\"{}\",\"message\":{{\"text\":{}}},\
+                 \"locations\":{},\"properties\":{{\"severity\":\"{}\",\"code\":{}}}}}",
+                json_string(&x.check),
+                sarif_level(x.severity),
+                json_string(&x.localized(catalog)),
+                location,
+                x.severity,
+                json_code(x)
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\
+         \"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"tls_explore\",\"version\":\"{}\",\
+         \"rules\":[{}]}}}},\"results\":[{}]}}]}}\n",
+        env!("CARGO_PKG_VERSION"),
+        rules.join(","),
+        results.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::checks::SecureRenegotiation;
+
+    fn findings() -> Vec<Finding> {
+        vec![Finding::new(
+            "renegotiation",
+            Severity::Warning,
+            "no \"renegotiation_info\"",
+        )]
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn json_report() {
+        assert_eq!(
+            render(OutputFormat::Json, "example.com:443", &[], &findings()),
+            "{\"target\":\"example.com:443\",\"findings\":[{\"check\":\"renegotiation\",\
+             \"severity\":\"warning\",\"code\":null,\"message\":\"no \\\"renegotiation_info\\\"\"}]}\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Text, "example.com:443", &[], &findings()),
+            "[warning] renegotiation: no \"renegotiation_info\"\n"
+        );
+        assert_eq!(
+            render(OutputFormat::Csv, "example.com:443", &[], &findings()),
+            "check,severity,message\nrenegotiation,warning,\"no \"\"renegotiation_info\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn sarif_report() {
+        let check = SecureRenegotiation;
+        let report = render(
+            OutputFormat::Sarif,
+            "example.com:443",
+            &[&check],
+            &findings(),
+        );
+
+        assert!(
+            report.starts_with("{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\"")
+        );
+        assert!(report.contains("\"rules\":[{\"id\":\"renegotiation\",\"shortDescription\":{\"text\":\"server doesn't support secure renegotiation (RFC 5746)\"}}]"));
+        assert!(report.contains("{\"ruleId\":\"renegotiation\",\"level\":\"warning\",\"message\":{\"text\":\"no \\\"renegotiation_info\\\"\"}"));
+        assert!(report.contains("\"artifactLocation\":{\"uri\":\"example.com:443\"}"));
+        assert_eq!(sarif_level(Severity::Critical), "error");
+    }
+
+    #[test]
+    fn localized_report() {
+        use crate::scanner::messages::Message;
+
+        let findings = [Finding::coded(
+            "legacy_versions",
+            Severity::Warning,
+            vec![Message::new("legacy_accepted", &["TLS 1.0"])],
+        )];
+        let catalog = Catalog::parse("legacy_accepted = \"le serveur accepte {}\"").unwrap();
+
+        assert_eq!(
+            render(OutputFormat::Text, "example.com:443", &[], &findings),
+            "[warning] legacy_versions: server accepts TLS 1.0\n"
+        );
+        assert_eq!(
+            render_localized(
+                OutputFormat::Text,
+                "example.com:443",
+                &[],
+                &findings,
+                &catalog
+            ),
+            "[warning] legacy_versions: le serveur accepte TLS 1.0\n"
+        );
+        assert!(render_localized(
+            OutputFormat::Json,
+            "example.com:443",
+            &[],
+            &findings,
+            &catalog
+        )
+        .contains("\"code\":\"legacy_accepted\",\"message\":\"le serveur accepte TLS 1.0\""));
+        assert!(render_localized(
+            OutputFormat::Sarif,
+            "example.com:443",
+            &[],
+            &findings,
+            &catalog
+        )
+        .contains("\"properties\":{\"severity\":\"warning\",\"code\":\"legacy_accepted\"}"));
+
+        // Display stays in English
+        assert_eq!(
+            findings[0].to_string(),
+            "[warning] legacy_versions: server accepts TLS 1.0"
+        );
+    }
+}
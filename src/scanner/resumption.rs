@@ -0,0 +1,319 @@
+// compare the latency of full and resumed handshakes, with a session ticket or a session ID.
+// A handshake is timed from connecting to the server's ChangeCipherSpec. Without key exchange,
+// the client's second flight is a bare ChangeCipherSpec, which only servers stopping before
+// the encrypted messages accept, like the mock server
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind, Read, Result, Write};
+use std::time::{Duration, Instant};
+
+use crate::alert::alert::AlertDescription;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ServerHelloSummary;
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::client_hello::{ClientHello, ExtensionType, GenericExtension};
+use crate::handshake::common::ContentType;
+use crate::handshake::handshake::HandshakeType;
+use crate::handshake::new_session_ticket::NewSessionTicket;
+use crate::scanner::connector::{hello_record, Connector};
+use crate::scanner::flight::check_flight;
+use crate::scanner::histogram::LatencyHistogram;
+use crate::server::server::change_cipher_spec;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResumptionPath {
+    Ticket,
+    SessionId,
+}
+
+impl fmt::Display for ResumptionPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResumptionPath::Ticket => write!(f, "ticket"),
+            ResumptionPath::SessionId => write!(f, "session ID"),
+        }
+    }
+}
+
+// what's needed to resume the session of a completed handshake
+#[derive(Debug, Default)]
+struct Completed {
+    session_id: Vec<u8>,
+    ticket: Option<Vec<u8>>,
+
+    // the server skipped Certificate and ServerHelloDone
+    resumed: bool,
+}
+
+// send the hello and read the server's answers up to its ChangeCipherSpec, sending the
+// client's own ChangeCipherSpec after a full first flight
+fn handshake(connector: &Connector, ch: &ClientHello) -> Result<Completed> {
+    let start = Instant::now();
+    let mut stream = connector.connect()?;
+    stream.write_all(&hello_record(ch)?)?;
+
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let mut second_flight_sent = false;
+
+    loop {
+        let records = records(&data);
+        // close_notify ends the connection like closing it does. Alerts after the server's
+        // ChangeCipherSpec are encrypted
+        let (close_notify, alerts): (Vec<_>, Vec<_>) = records
+            .iter()
+            .take_while(|x| x.content_type != ContentType::change_cipher_spec as u8)
+            .filter(|x| x.content_type == ContentType::alert as u8)
+            .partition(|x| x.fragment.get(1) == Some(&(AlertDescription::close_notify as u8)));
+        if let Some(alert) = alerts.first() {
+            let description = match alert
+                .fragment
+                .get(1)
+                .copied()
+                .map(AlertDescription::try_from)
+            {
+                Some(Ok(description)) => description.to_string(),
+                _ => String::from("unknown"),
+            };
+            return Err(Error::new(
+                ErrorKind::ConnectionAborted,
+                format!("alert {}", description),
+            ));
+        }
+
+        let messages = handshake_messages(&records);
+        check_flight(&messages)?;
+        let find = |msg_type: HandshakeType| messages.iter().find(|x| x.msg_type == msg_type as u8);
+        let full = find(HandshakeType::server_hello_done).is_some();
+
+        // the server may already have closed the connection, which the next read tells
+        if full && !second_flight_sent {
+            let _ = stream.write_all(&change_cipher_spec()?);
+            second_flight_sent = true;
+        }
+
+        // a server which neither issues a ticket nor caches the session closes the
+        // connection after its first flight
+        let done = records
+            .iter()
+            .any(|x| x.content_type == ContentType::change_cipher_spec as u8);
+        let closed = if done {
+            false
+        } else if !close_notify.is_empty() && second_flight_sent {
+            true
+        } else {
+            let left = connector.limits.check(start, messages.len())?;
+            stream.set_read_timeout(Some(left.min(connector.timeout)))?;
+            match stream.read(&mut chunk) {
+                Ok(0) if second_flight_sent => true,
+                Err(e) if second_flight_sent && e.kind() == ErrorKind::ConnectionReset => true,
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "connection closed during the handshake",
+                    ))
+                }
+                Ok(n) => {
+                    data.extend_from_slice(&chunk[..n]);
+                    false
+                }
+                Err(e) => {
+                    connector.limits.check(start, messages.len())?;
+                    return Err(e);
+                }
+            }
+        };
+
+        if done || closed {
+            let sh = find(HandshakeType::server_hello)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "no ServerHello"))?;
+            let sh = ServerHelloSummary::parse(&sh.body)?;
+
+            let ticket = match find(HandshakeType::new_session_ticket) {
+                Some(message) => {
+                    let mut nst = NewSessionTicket::default();
+                    nst.from_network_bytes(&mut Cursor::new(message.body.clone()))?;
+                    Some(nst.ticket.data)
+                }
+                None => None,
+            };
+
+            return Ok(Completed {
+                session_id: sh.session_id,
+                ticket,
+                resumed: !full,
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResumptionTiming {
+    pub path: ResumptionPath,
+    pub full: LatencyHistogram,
+    pub resumed: LatencyHistogram,
+
+    // trials where the server made a full handshake instead of resuming
+    pub not_resumed: usize,
+}
+
+impl ResumptionTiming {
+    // median resumed latency minus median full latency, in milliseconds. Negative when
+    // resuming is faster
+    pub fn delta(&self) -> Option<f64> {
+        let full = self.full.percentile(50.0)?;
+        let resumed = self.resumed.percentile(50.0)?;
+        Some((resumed.as_secs_f64() - full.as_secs_f64()) * 1000.0)
+    }
+}
+
+impl fmt::Display for ResumptionTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} resumption:", self.path)?;
+        writeln!(f, "  full: {}", self.full)?;
+        writeln!(f, "  resumed: {}", self.resumed)?;
+        if self.not_resumed > 0 {
+            writeln!(f, "  not resumed: {}", self.not_resumed)?;
+        }
+
+        match (self.delta(), self.full.percentile(50.0)) {
+            (Some(delta), Some(full)) => writeln!(
+                f,
+                "  delta p50: {:+.2}ms ({:+.1}%)",
+                delta,
+                delta / (full.as_secs_f64() * 1000.0).max(f64::EPSILON) * 100.0
+            ),
+            _ => writeln!(f, "  delta p50: unknown"),
+        }
+    }
+}
+
+// run full handshakes, each followed by an attempt to resume its session
+pub fn measure(
+    connector: &Connector,
+    path: ResumptionPath,
+    trials: usize,
+) -> Result<ResumptionTiming> {
+    let mut timing = ResumptionTiming {
+        path,
+        full: LatencyHistogram::new(),
+        resumed: LatencyHistogram::new(),
+        not_resumed: 0,
+    };
+
+    for _ in 0..trials {
+        // an empty session_ticket extension asks for a ticket
+        let ch = match path {
+            ResumptionPath::Ticket => connector
+                .client_hello()
+                .with_extension_replaced(GenericExtension::new(ExtensionType::session_ticket, &[])),
+            ResumptionPath::SessionId => connector.client_hello().with_session_id(&[]),
+        };
+
+        let start = Instant::now();
+        let completed = handshake(connector, &ch)?;
+        timing.full.record(start.elapsed());
+
+        let ch = match (path, completed.ticket) {
+            (ResumptionPath::Ticket, Some(ticket)) => connector
+                .client_hello()
+                .with_extension_replaced(GenericExtension::new(
+                    ExtensionType::session_ticket,
+                    &ticket,
+                )),
+            (ResumptionPath::Ticket, None) => {
+                return Err(Error::new(ErrorKind::Unsupported, "no ticket received"))
+            }
+            (ResumptionPath::SessionId, _) => connector
+                .client_hello()
+                .with_session_id(&completed.session_id),
+        };
+
+        let start = Instant::now();
+        let resumption = handshake(connector, &ch)?;
+        let elapsed: Duration = start.elapsed();
+        if resumption.resumed {
+            timing.resumed.record(elapsed);
+        } else {
+            timing.not_resumed += 1;
+        }
+    }
+
+    Ok(timing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::TLS_RSA_WITH_AES_128_CBC_SHA;
+    use crate::server::server::Server;
+    use crate::server::session_cache::SessionCache;
+    use crate::server::ticket::TicketKeys;
+    use crate::shutdown::ShutdownToken;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    // stops the server even if the test fails
+    struct Stop<'a>(&'a ShutdownToken);
+    impl Drop for Stop<'_> {
+        fn drop(&mut self) {
+            self.0.shutdown();
+        }
+    }
+
+    // the mock server with tickets and session IDs, on a local port
+    fn with_server<T>(session_cache: bool, f: impl FnOnce(&Connector) -> T) -> T {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut connector = Connector::new(&listener.local_addr().unwrap().to_string());
+        connector.timeout = Duration::from_secs(2);
+
+        let mut server = Server::default();
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+        // sessions are only resumed with the AES CBC suites
+        server.config.cipher_suites = vec![TLS_RSA_WITH_AES_128_CBC_SHA];
+        server.config.tickets = Some(Mutex::new(TicketKeys::default()));
+        if session_cache {
+            server.config.session_cache = Some(Mutex::new(SessionCache::default()));
+        }
+        let shutdown = ShutdownToken::new();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| server.serve(&listener, &shutdown).unwrap());
+            let _stop = Stop(&shutdown);
+            f(&connector)
+        })
+    }
+
+    #[test]
+    fn warm_cold() {
+        let (ticket, session_id) = with_server(true, |connector| {
+            (
+                measure(connector, ResumptionPath::Ticket, 5).unwrap(),
+                measure(connector, ResumptionPath::SessionId, 5).unwrap(),
+            )
+        });
+
+        for timing in [&ticket, &session_id] {
+            assert_eq!(timing.full.count(), 5);
+            assert_eq!(timing.resumed.count(), 5);
+            assert_eq!(timing.not_resumed, 0);
+            assert!(timing.delta().is_some());
+
+            let report = timing.to_string();
+            assert!(report.contains("  delta p50: "), "{}", report);
+        }
+        assert!(session_id
+            .to_string()
+            .starts_with("session ID resumption:\n"));
+    }
+
+    #[test]
+    fn no_session_cache() {
+        let timing = with_server(false, |connector| {
+            measure(connector, ResumptionPath::SessionId, 2).unwrap()
+        });
+        assert_eq!(timing.full.count(), 2);
+        assert_eq!(timing.not_resumed, 2);
+        assert!(timing.delta().is_none());
+        assert!(timing.to_string().ends_with("  delta p50: unknown\n"));
+    }
+}
@@ -0,0 +1,115 @@
+// sessions kept by the client to resume them on the next connection to the same server, with
+// their ticket or session ID. A ticket isn't offered after its lifetime hint:
+// https://datatracker.ietf.org/doc/html/rfc5077#section-3.3
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::scanner::abbreviated::ClientSession;
+
+// the lifetime hint of the session, if shorter than the one of the store
+fn lifetime(lifetime: Duration, session: &ClientSession) -> Duration {
+    match session.lifetime_hint {
+        0 => lifetime,
+        hint => lifetime.min(Duration::from_secs(u64::from(hint))),
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionStore {
+    // by server name, or address if there's no name
+    sessions: HashMap<String, (ClientSession, Instant)>,
+
+    // sessions kept at most, the oldest is forgotten first
+    pub capacity: usize,
+
+    // age after which a session isn't resumed, unless its lifetime hint is shorter
+    pub lifetime: Duration,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            capacity: 1_000,
+            lifetime: Duration::from_secs(7200),
+        }
+    }
+}
+
+impl SessionStore {
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    // replaces the session of the server, a renewed ticket replaces the previous one
+    pub fn insert(&mut self, server: &str, session: ClientSession) {
+        let limit = self.lifetime;
+        self.sessions
+            .retain(|_, (session, received)| received.elapsed() < lifetime(limit, session));
+        while !self.sessions.contains_key(server) && self.sessions.len() >= self.capacity.max(1) {
+            // not empty
+            let oldest = self
+                .sessions
+                .iter()
+                .min_by_key(|(_, (_, received))| *received)
+                .map(|(server, _)| server.clone())
+                .unwrap();
+            self.sessions.remove(&oldest);
+        }
+
+        self.sessions
+            .insert(server.to_string(), (session, Instant::now()));
+    }
+
+    // None if there's no session for the server, or it's too old
+    pub fn get(&self, server: &str) -> Option<ClientSession> {
+        self.sessions
+            .get(server)
+            .filter(|(session, received)| received.elapsed() < lifetime(self.lifetime, session))
+            .map(|(session, _)| session.clone())
+    }
+
+    // a session the server refused to resume
+    pub fn remove(&mut self, server: &str) {
+        self.sessions.remove(server);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::constants::*;
+
+    #[test]
+    fn store() {
+        let mut store = SessionStore {
+            capacity: 2,
+            ..Default::default()
+        };
+        let suite = TLS_RSA_WITH_AES_128_CBC_SHA;
+        let session = ClientSession::new(&[1; 32], suite, TLS12, &[0; 48]).unwrap();
+        let ticket = ClientSession::from_ticket(&[2; 16], 0, suite, TLS12, &[0; 48]).unwrap();
+
+        store.insert("a.example.com", session.clone());
+        store.insert("b.example.com", session.clone());
+        store.insert("b.example.com", ticket.clone());
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get("b.example.com"), Some(ticket.clone()));
+
+        store.insert("c.example.com", session.clone());
+        assert!(store.get("a.example.com").is_none());
+        store.remove("c.example.com");
+        assert_eq!(store.len(), 1);
+
+        // the lifetime hint of the ticket, or the lifetime of the store
+        let short = ClientSession::from_ticket(&[3; 16], 1, suite, TLS12, &[0; 48]).unwrap();
+        assert_eq!(lifetime(store.lifetime, &short), Duration::from_secs(1));
+        assert_eq!(lifetime(store.lifetime, &ticket), Duration::from_secs(7200));
+        store.lifetime = Duration::ZERO;
+        assert!(store.get("b.example.com").is_none());
+    }
+}
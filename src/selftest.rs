@@ -0,0 +1,325 @@
+// known answer tests of the crypto and round trips of the parsers, run by the self-test
+// command so a freshly built binary can be checked before its scan results are trusted. The
+// vectors are the ones of the RFCs, the same as the unit tests
+use std::io::Cursor;
+
+use crate::corpus::seeds;
+use crate::crypto::aes::Aes;
+use crate::crypto::cbc::CbcCipher;
+use crate::crypto::hkdf::{derive_secret, hkdf_expand, hkdf_extract};
+use crate::crypto::hmac::{hmac, hmac_sha1};
+use crate::crypto::md5::md5;
+use crate::crypto::prf::prf;
+use crate::crypto::sha1::sha1;
+use crate::crypto::sha2::{sha256, sha384, HashAlgorithm};
+use crate::crypto::to_hex;
+use crate::crypto::x25519::x25519;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::client_hello::ClientHello;
+use crate::handshake::common::ContentType;
+use crate::handshake::constants::{TLS12, TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA};
+use crate::handshake::handshake::HandshakeType;
+
+pub struct SelfTest {
+    pub name: &'static str,
+    test: fn() -> Result<(), String>,
+}
+
+impl SelfTest {
+    // a panic is a failure like the others, so the remaining tests still run
+    pub fn run(&self) -> Result<(), String> {
+        std::panic::catch_unwind(self.test).unwrap_or_else(|_| Err("panicked".to_string()))
+    }
+}
+
+pub const SELF_TESTS: &[SelfTest] = &[
+    SelfTest {
+        name: "md5",
+        test: hashes_md5,
+    },
+    SelfTest {
+        name: "sha1",
+        test: hashes_sha1,
+    },
+    SelfTest {
+        name: "sha256",
+        test: hashes_sha256,
+    },
+    SelfTest {
+        name: "sha384",
+        test: hashes_sha384,
+    },
+    SelfTest {
+        name: "hmac",
+        test: hmacs,
+    },
+    SelfTest {
+        name: "tls12-prf",
+        test: tls12_prf,
+    },
+    SelfTest {
+        name: "hkdf",
+        test: hkdf,
+    },
+    SelfTest {
+        name: "x25519",
+        test: x25519_agreement,
+    },
+    SelfTest {
+        name: "rfc8448-secrets",
+        test: rfc8448_secrets,
+    },
+    SelfTest {
+        name: "aes",
+        test: aes,
+    },
+    SelfTest {
+        name: "aes-cbc-records",
+        test: cbc_records,
+    },
+    SelfTest {
+        name: "client-hello-round-trip",
+        test: client_hello_round_trip,
+    },
+];
+
+fn expect(what: &str, actual: &[u8], expected: &str) -> Result<(), String> {
+    let actual = to_hex(actual);
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("{}: got {}, expected {}", what, actual, expected))
+    }
+}
+
+fn hex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// https://datatracker.ietf.org/doc/html/rfc1321#appendix-A.5
+fn hashes_md5() -> Result<(), String> {
+    expect("md5(abc)", &md5(b"abc"), "900150983cd24fb0d6963f7d28e17f72")
+}
+
+// https://datatracker.ietf.org/doc/html/rfc3174#section-7.3
+fn hashes_sha1() -> Result<(), String> {
+    expect(
+        "sha1(abc)",
+        &sha1(b"abc"),
+        "a9993e364706816aba3e25717850c26c9cd0d89d",
+    )
+}
+
+// the one-block messages of https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
+fn hashes_sha256() -> Result<(), String> {
+    expect(
+        "sha256(abc)",
+        &sha256(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    )
+}
+
+fn hashes_sha384() -> Result<(), String> {
+    expect(
+        "sha384(abc)",
+        &sha384(b"abc"),
+        "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+         8086072ba1e7cc2358baeca134c825a7",
+    )
+}
+
+// https://datatracker.ietf.org/doc/html/rfc4231#section-4.2 and
+// https://datatracker.ietf.org/doc/html/rfc2202#section-3
+fn hmacs() -> Result<(), String> {
+    expect(
+        "hmac-sha256",
+        &hmac(HashAlgorithm::Sha256, &[0x0b; 20], b"Hi There"),
+        "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+    )?;
+    expect(
+        "hmac-sha1",
+        &hmac_sha1(&[0x0b; 20], b"Hi There"),
+        "b617318655057264e28bc0b6fb378c8ef146be00",
+    )
+}
+
+// the SHA-256 vector of the unit tests of crypto::prf
+fn tls12_prf() -> Result<(), String> {
+    let output = prf(
+        HashAlgorithm::Sha256,
+        &hex("9bbe436ba940f017b17652849a71db35"),
+        "test label",
+        &hex("a0ba9f936cda311827a6f796ffd5198c"),
+        100,
+    );
+    expect(
+        "prf-sha256",
+        &output[..32],
+        "e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a",
+    )
+}
+
+// https://datatracker.ietf.org/doc/html/rfc5869#appendix-A.1
+fn hkdf() -> Result<(), String> {
+    let salt: Vec<u8> = (0..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+    let prk = hkdf_extract(HashAlgorithm::Sha256, &salt, &[0x0b; 22]);
+    expect(
+        "hkdf-extract",
+        &prk,
+        "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+    )?;
+    expect(
+        "hkdf-expand",
+        &hkdf_expand(HashAlgorithm::Sha256, &prk, &info, 42),
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+    )
+}
+
+// https://datatracker.ietf.org/doc/html/rfc7748#section-5.2
+fn x25519_agreement() -> Result<(), String> {
+    let scalar = hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+    let u = hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+    expect(
+        "x25519",
+        // both are 32 bytes
+        &x25519(&scalar.try_into().unwrap(), &u.try_into().unwrap()),
+        "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552",
+    )
+}
+
+// the key exchange and first secrets of the simple 1-RTT handshake of
+// https://datatracker.ietf.org/doc/html/rfc8448#section-3
+fn rfc8448_secrets() -> Result<(), String> {
+    let hash = HashAlgorithm::Sha256;
+    let client_private = hex("49af42ba7f7994852d713ef2784bcbcaa7911de26adc5642cb634540e7ea5005");
+    let server_public = hex("c9828876112095fe66762bdbf7c672e156d6cc253b833df1dd69b1b04e751f0f");
+    let shared = x25519(
+        &client_private.try_into().unwrap(),
+        &server_public.try_into().unwrap(),
+    );
+    expect(
+        "ecdhe",
+        &shared,
+        "8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d",
+    )?;
+
+    let early_secret = hkdf_extract(hash, &[], &[0; 32]);
+    expect(
+        "early secret",
+        &early_secret,
+        "33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a",
+    )?;
+    let derived = derive_secret(hash, &early_secret, "derived", &[]);
+    expect(
+        "handshake secret",
+        &hkdf_extract(hash, &derived, &shared),
+        "1dc826e93606aa6fdc0aadc12f741b01046aa6b99f691ed221a9f0ca043fbeac",
+    )
+}
+
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197-upd1.pdf, appendix C.1 and C.3
+fn aes() -> Result<(), String> {
+    let plaintext: [u8; 16] = std::array::from_fn(|i| (i as u8) * 0x11);
+    for (length, expected) in [
+        (16, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+        (32, "8ea2b7ca516745bfeafc49904b496089"),
+    ] {
+        let key: Vec<u8> = (0..length as u8).collect();
+        // the key lengths are valid
+        let aes = Aes::new(&key).unwrap();
+
+        let mut block = plaintext;
+        aes.encrypt_block(&mut block);
+        expect(&format!("aes-{}", 8 * length), &block, expected)?;
+        aes.decrypt_block(&mut block);
+        if block != plaintext {
+            return Err(format!("aes-{}: decryption differs", 8 * length));
+        }
+    }
+    Ok(())
+}
+
+// a record sealed by one side is opened by the other, and rejected once changed
+fn cbc_records() -> Result<(), String> {
+    let suite = TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA;
+    // the key lengths are the ones of the suite
+    let mut client = CbcCipher::new(suite, TLS12, &[0x01; 20], &[0x02; 16]).unwrap();
+    let mut server = client.clone();
+
+    let fragment = client
+        .seal(ContentType::application_data, b"self-test")
+        .map_err(|x| format!("seal: {:?}", x))?;
+    let mut changed = fragment.clone();
+    changed[20] ^= 0x01;
+    if server
+        .clone()
+        .open(ContentType::application_data, &changed)
+        .is_ok()
+    {
+        return Err("a changed record was opened".to_string());
+    }
+
+    match server.open(ContentType::application_data, &fragment) {
+        Ok(plaintext) if plaintext == b"self-test" => Ok(()),
+        Ok(_) => Err("open: plaintext differs".to_string()),
+        Err(e) => Err(format!("open: {:?}", e)),
+    }
+}
+
+// the hellos of the corpus are parsed and encoded back to the same bytes
+fn client_hello_round_trip() -> Result<(), String> {
+    let seeds = seeds().map_err(|x| x.to_string())?;
+    for seed in seeds.iter().filter(|x| x.name.starts_with("client_hello")) {
+        let messages = handshake_messages(&records(&seed.bytes));
+        let body = messages
+            .iter()
+            .find(|x| x.msg_type == HandshakeType::client_hello as u8)
+            .map(|x| &x.body)
+            .ok_or_else(|| format!("{}: no ClientHello", seed.name))?;
+
+        ClientHelloSummary::parse(body).map_err(|x| format!("{}: {}", seed.name, x))?;
+
+        let mut hello = ClientHello::default();
+        hello
+            .from_network_bytes(&mut Cursor::new(body.clone()))
+            .map_err(|x| format!("{}: {}", seed.name, x))?;
+        let mut encoded = Vec::new();
+        hello
+            .to_network_bytes(&mut encoded)
+            .map_err(|x| format!("{}: {}", seed.name, x))?;
+        if &encoded != body {
+            return Err(format!("{}: encoded back differently", seed.name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_tests() {
+        for test in SELF_TESTS {
+            assert_eq!(test.run(), Ok(()), "{}", test.name);
+        }
+
+        let failing = SelfTest {
+            name: "failing",
+            test: || expect("md5", &md5(b""), "00"),
+        };
+        assert!(failing.run().unwrap_err().starts_with("md5: got d41d8cd9"));
+        let panicking = SelfTest {
+            name: "panicking",
+            test: || panic!("self-test"),
+        };
+        assert_eq!(panicking.run(), Err("panicked".to_string()));
+    }
+}
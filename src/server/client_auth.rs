@@ -0,0 +1,383 @@
+// client authentication: the server sends a CertificateRequest and checks the client's
+// Certificate and CertificateVerify with a pluggable verifier. RsaClientCerts checks the
+// signatures of RSA keys, other keys are left to verifiers outside the crate
+use std::fmt::Debug;
+use std::io::Cursor;
+
+use crate::alert::alert::AlertDescription;
+use crate::crypto::rsa::RsaPublicKey;
+use crate::crypto::sha2::sha256;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::HandshakeMessage;
+use crate::handshake::certificate::Certificate;
+use crate::handshake::certificate_request::{
+    CertificateRequest, CertificateVerify, ClientCertificateType,
+};
+use crate::handshake::client_hello::SignatureScheme;
+use crate::handshake::handshake::HandshakeType;
+
+pub type VerifyResult = std::result::Result<(), AlertDescription>;
+
+pub trait ClientCertVerifier: Debug + Send + Sync {
+    // check the certificate chain sent by the client, leaf first
+    fn verify_chain(&self, chain: &[&[u8]]) -> VerifyResult;
+
+    // check the CertificateVerify signature over the handshake messages
+    fn verify_signature(
+        &self,
+        leaf: &[u8],
+        scheme: SignatureScheme,
+        message: &[u8],
+        signature: &[u8],
+    ) -> VerifyResult;
+}
+
+// accepts any certificate and any non-empty signature
+#[derive(Debug, Default)]
+pub struct AcceptAnyClientCert;
+
+impl ClientCertVerifier for AcceptAnyClientCert {
+    fn verify_chain(&self, _chain: &[&[u8]]) -> VerifyResult {
+        Ok(())
+    }
+
+    fn verify_signature(
+        &self,
+        _leaf: &[u8],
+        _scheme: SignatureScheme,
+        _message: &[u8],
+        signature: &[u8],
+    ) -> VerifyResult {
+        if signature.is_empty() {
+            return Err(AlertDescription::decrypt_error);
+        }
+        Ok(())
+    }
+}
+
+// only accepts leaf certificates whose SHA-256 fingerprint is known
+#[derive(Debug, Default)]
+pub struct PinnedClientCerts {
+    pub fingerprints: Vec<[u8; 32]>,
+}
+
+impl PinnedClientCerts {
+    pub fn new(certificates: &[&[u8]]) -> Self {
+        Self {
+            fingerprints: certificates.iter().map(|cert| sha256(cert)).collect(),
+        }
+    }
+}
+
+impl ClientCertVerifier for PinnedClientCerts {
+    fn verify_chain(&self, chain: &[&[u8]]) -> VerifyResult {
+        let leaf = chain.first().ok_or(AlertDescription::bad_certificate)?;
+        if !self.fingerprints.contains(&sha256(leaf)) {
+            return Err(AlertDescription::unknown_ca);
+        }
+        Ok(())
+    }
+
+    fn verify_signature(
+        &self,
+        leaf: &[u8],
+        scheme: SignatureScheme,
+        message: &[u8],
+        signature: &[u8],
+    ) -> VerifyResult {
+        AcceptAnyClientCert.verify_signature(leaf, scheme, message, signature)
+    }
+}
+
+// checks the signature with the RSA key of the leaf, and leaves the chain to another
+// verifier
+#[derive(Debug)]
+pub struct RsaClientCerts {
+    pub chain: Box<dyn ClientCertVerifier>,
+}
+
+impl ClientCertVerifier for RsaClientCerts {
+    fn verify_chain(&self, chain: &[&[u8]]) -> VerifyResult {
+        self.chain.verify_chain(chain)
+    }
+
+    fn verify_signature(
+        &self,
+        leaf: &[u8],
+        scheme: SignatureScheme,
+        message: &[u8],
+        signature: &[u8],
+    ) -> VerifyResult {
+        let key = RsaPublicKey::from_certificate(leaf)
+            .map_err(|_| AlertDescription::unsupported_certificate)?;
+        key.verify(scheme, message, signature)
+    }
+}
+
+#[derive(Debug)]
+pub struct ClientAuth {
+    // if false, a client can answer with an empty certificate list
+    pub required: bool,
+    pub signature_schemes: Vec<SignatureScheme>,
+
+    // DER-encoded names of acceptable CAs, sent to the client
+    pub authorities: Vec<Vec<u8>>,
+    pub verifier: Box<dyn ClientCertVerifier>,
+}
+
+impl ClientAuth {
+    pub fn new(verifier: Box<dyn ClientCertVerifier>) -> Self {
+        Self {
+            required: true,
+            signature_schemes: vec![
+                SignatureScheme::ecdsa_secp256r1_sha256,
+                SignatureScheme::rsa_pss_rsae_sha256,
+                SignatureScheme::rsa_pkcs1_sha256,
+                SignatureScheme::ecdsa_secp384r1_sha384,
+                SignatureScheme::rsa_pss_rsae_sha384,
+                SignatureScheme::rsa_pkcs1_sha384,
+            ],
+            authorities: Vec::new(),
+            verifier,
+        }
+    }
+
+    pub fn certificate_request(&self) -> CertificateRequest {
+        let authorities: Vec<&[u8]> = self.authorities.iter().map(|x| x.as_slice()).collect();
+        CertificateRequest::new(
+            &[
+                ClientCertificateType::rsa_sign,
+                ClientCertificateType::ecdsa_sign,
+            ],
+            &self.signature_schemes,
+            &authorities,
+        )
+    }
+
+    // check the client's second flight. The transcript holds the handshake messages
+    // exchanged before it, and is extended with the client's messages up to CertificateVerify:
+    // https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.8
+    pub fn verify(&self, transcript: &[u8], flight: &[HandshakeMessage]) -> VerifyResult {
+        // a client asked for a certificate sends one, even if its list is empty
+        let first = flight.first().ok_or(AlertDescription::unexpected_message)?;
+        if first.msg_type != HandshakeType::certificate as u8 {
+            return Err(AlertDescription::unexpected_message);
+        }
+
+        let mut certificate = Certificate::default();
+        certificate
+            .from_network_bytes(&mut Cursor::new(first.body.clone()))
+            .map_err(|_| AlertDescription::decode_error)?;
+
+        let chain = certificate.certificates();
+        let Some(leaf) = chain.first() else {
+            return match self.required {
+                true => Err(AlertDescription::handshake_failure),
+                false => Ok(()),
+            };
+        };
+        self.verifier.verify_chain(&chain)?;
+
+        // the signature covers all the messages before CertificateVerify
+        let mut message = transcript.to_vec();
+        let mut certificate_verify = None;
+        for msg in flight {
+            if msg.msg_type == HandshakeType::certificate_verify as u8 {
+                certificate_verify = Some(msg);
+                break;
+            }
+            message.extend_from_slice(&msg.to_bytes());
+        }
+
+        let msg = certificate_verify.ok_or(AlertDescription::unexpected_message)?;
+        let mut cv = CertificateVerify::default();
+        cv.from_network_bytes(&mut Cursor::new(msg.body.clone()))
+            .map_err(|_| AlertDescription::decode_error)?;
+
+        if !self.signature_schemes.contains(&cv.algorithm) {
+            return Err(AlertDescription::illegal_parameter);
+        }
+
+        self.verifier
+            .verify_signature(leaf, cv.algorithm, &message, &cv.signature.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn message(msg_type: HandshakeType, body: &[u8]) -> HandshakeMessage {
+        HandshakeMessage {
+            msg_type: msg_type as u8,
+            body: body.to_vec(),
+        }
+    }
+
+    fn certificate(chain: &[&[u8]]) -> HandshakeMessage {
+        let mut body = Vec::new();
+        Certificate::new(chain).to_network_bytes(&mut body).unwrap();
+        message(HandshakeType::certificate, &body)
+    }
+
+    fn certificate_verify(scheme: SignatureScheme, signature: &[u8]) -> HandshakeMessage {
+        let mut body = Vec::new();
+        CertificateVerify::new(scheme, signature)
+            .to_network_bytes(&mut body)
+            .unwrap();
+        message(HandshakeType::certificate_verify, &body)
+    }
+
+    // records the signed message to check what the verifier is given
+    #[derive(Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<u8>>>);
+
+    impl ClientCertVerifier for Recorder {
+        fn verify_chain(&self, _chain: &[&[u8]]) -> VerifyResult {
+            Ok(())
+        }
+
+        fn verify_signature(
+            &self,
+            _leaf: &[u8],
+            _scheme: SignatureScheme,
+            message: &[u8],
+            _signature: &[u8],
+        ) -> VerifyResult {
+            *self.0.lock().unwrap() = message.to_vec();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn accepted() {
+        let auth = ClientAuth::new(Box::new(PinnedClientCerts::new(&[&[0x30, 0x01]])));
+        let flight = [
+            certificate(&[&[0x30, 0x01]]),
+            message(HandshakeType::client_key_exchange, &[0x01, 0xAA]),
+            certificate_verify(SignatureScheme::ecdsa_secp256r1_sha256, &[0xBB]),
+        ];
+        assert_eq!(auth.verify(&[], &flight), Ok(()));
+
+        // unknown certificate
+        let flight = [certificate(&[&[0x30, 0x02]])];
+        assert_eq!(auth.verify(&[], &flight), Err(AlertDescription::unknown_ca));
+    }
+
+    #[test]
+    fn signed_messages() {
+        let signed = Arc::new(Mutex::new(Vec::new()));
+        let auth = ClientAuth::new(Box::new(Recorder(signed.clone())));
+        let flight = [
+            certificate(&[&[0x30, 0x01]]),
+            message(HandshakeType::client_key_exchange, &[0x01, 0xAA]),
+            certificate_verify(SignatureScheme::rsa_pss_rsae_sha256, &[0xBB]),
+        ];
+        assert_eq!(auth.verify(&[0x01, 0x02], &flight), Ok(()));
+
+        let mut expected = vec![0x01, 0x02];
+        expected.extend_from_slice(&flight[0].to_bytes());
+        expected.extend_from_slice(&[0x10, 0x00, 0x00, 0x02, 0x01, 0xAA]);
+        assert_eq!(*signed.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn rsa_signatures() {
+        use crate::crypto::bignum::from_hex;
+        use crate::crypto::rsa::tests::{
+            certificate as rsa_certificate, signed, signing_key, SIGNATURES,
+        };
+
+        let leaf = rsa_certificate(&signing_key());
+        let auth = ClientAuth::new(Box::new(RsaClientCerts {
+            chain: Box::new(PinnedClientCerts::new(&[&leaf])),
+        }));
+
+        // the signatures of the rsa tests
+        let verifier = RsaClientCerts {
+            chain: Box::new(AcceptAnyClientCert),
+        };
+        for (scheme, signature) in SIGNATURES {
+            let signature = from_hex(&signature);
+            assert_eq!(
+                verifier.verify_signature(&leaf, scheme, &signed(), &signature),
+                Ok(())
+            );
+            assert_eq!(
+                verifier.verify_signature(&leaf, scheme, &signed()[1..], &signature),
+                Err(AlertDescription::decrypt_error)
+            );
+        }
+
+        // through ClientAuth, a bad signature, and a leaf which isn't an RSA certificate
+        let flight = [
+            certificate(&[&leaf]),
+            certificate_verify(SignatureScheme::rsa_pkcs1_sha256, &[0xBB; 128]),
+        ];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::decrypt_error)
+        );
+        assert_eq!(
+            verifier.verify_signature(
+                &[0x30, 0x00],
+                SignatureScheme::rsa_pkcs1_sha256,
+                &[],
+                &[0xBB]
+            ),
+            Err(AlertDescription::unsupported_certificate)
+        );
+    }
+
+    #[test]
+    fn rejected() {
+        let auth = ClientAuth::new(Box::new(AcceptAnyClientCert));
+
+        // no certificate while one is required
+        let flight = [certificate(&[])];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::handshake_failure)
+        );
+        let optional = ClientAuth {
+            required: false,
+            ..ClientAuth::new(Box::new(AcceptAnyClientCert))
+        };
+        assert_eq!(optional.verify(&[], &flight), Ok(()));
+
+        // no Certificate message at all
+        let flight = [message(HandshakeType::client_key_exchange, &[0x00])];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::unexpected_message)
+        );
+
+        // missing CertificateVerify
+        let flight = [certificate(&[&[0x30, 0x01]])];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::unexpected_message)
+        );
+
+        // scheme not requested
+        let flight = [
+            certificate(&[&[0x30, 0x01]]),
+            certificate_verify(SignatureScheme::rsa_pkcs1_sha1, &[0xBB]),
+        ];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::illegal_parameter)
+        );
+
+        // empty signature
+        let flight = [
+            certificate(&[&[0x30, 0x01]]),
+            certificate_verify(SignatureScheme::rsa_pkcs1_sha256, &[]),
+        ];
+        assert_eq!(
+            auth.verify(&[], &flight),
+            Err(AlertDescription::decrypt_error)
+        );
+    }
+}
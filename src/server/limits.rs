@@ -0,0 +1,176 @@
+// accept-loop controls: maximum number of concurrent connections, per-IP caps and an
+// overall deadline for each handshake
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::net::{IpAddr, TcpStream};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct ServerLimits {
+    // concurrent connections, all clients included
+    pub max_connections: usize,
+
+    // concurrent connections from a single address
+    pub max_per_ip: usize,
+
+    // time given to a client to complete its handshake
+    pub handshake_timeout: Duration,
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        Self {
+            max_connections: 64,
+            max_per_ip: 8,
+            handshake_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+// counts open connections, globally and per address
+#[derive(Debug, Default)]
+pub struct ConnectionTracker {
+    open: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConnectionTracker {
+    // a slot for a new connection from this address, None if a limit is reached. The slot
+    // is released when dropped
+    pub fn acquire(&self, ip: IpAddr, limits: &ServerLimits) -> Option<ConnectionSlot<'_>> {
+        let mut open = self.open.lock().ok()?;
+
+        let total: usize = open.values().sum();
+        let from_ip = open.get(&ip).copied().unwrap_or(0);
+        if total >= limits.max_connections || from_ip >= limits.max_per_ip {
+            return None;
+        }
+
+        *open.entry(ip).or_insert(0) += 1;
+        Some(ConnectionSlot { tracker: self, ip })
+    }
+
+    pub fn open_connections(&self) -> usize {
+        self.open
+            .lock()
+            .map(|open| open.values().sum())
+            .unwrap_or(0)
+    }
+
+    fn release(&self, ip: IpAddr) {
+        if let Ok(mut open) = self.open.lock() {
+            if let Some(count) = open.get_mut(&ip) {
+                *count -= 1;
+                if *count == 0 {
+                    open.remove(&ip);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectionSlot<'a> {
+    tracker: &'a ConnectionTracker,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionSlot<'_> {
+    fn drop(&mut self) {
+        self.tracker.release(self.ip);
+    }
+}
+
+// a TCP stream whose reads and writes fail once the deadline has passed
+#[derive(Debug)]
+pub struct DeadlineStream {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl DeadlineStream {
+    pub fn new(stream: TcpStream, timeout: Duration) -> Self {
+        Self {
+            stream,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    // time left before the deadline, an error if it has passed
+    fn remaining(&self) -> Result<Duration> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::new(ErrorKind::TimedOut, "handshake timeout"));
+        }
+        Ok(remaining)
+    }
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buf).map_err(|e| match e.kind() {
+            // the timeout error depends on the platform
+            ErrorKind::WouldBlock => Error::new(ErrorKind::TimedOut, "handshake timeout"),
+            _ => e,
+        })
+    }
+}
+
+impl Write for DeadlineStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    #[test]
+    fn tracker() {
+        let limits = ServerLimits {
+            max_connections: 3,
+            max_per_ip: 2,
+            ..Default::default()
+        };
+        let tracker = ConnectionTracker::default();
+        let a = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let b = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+        let a1 = tracker.acquire(a, &limits).unwrap();
+        let _a2 = tracker.acquire(a, &limits).unwrap();
+        assert!(tracker.acquire(a, &limits).is_none());
+
+        let _b1 = tracker.acquire(b, &limits).unwrap();
+        assert!(tracker.acquire(b, &limits).is_none());
+        assert_eq!(tracker.open_connections(), 3);
+
+        // a released slot can be used again
+        drop(a1);
+        assert_eq!(tracker.open_connections(), 2);
+        assert!(tracker.acquire(a, &limits).is_some());
+    }
+
+    #[test]
+    fn deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        // the client never sends anything
+        let mut stream = DeadlineStream::new(stream, Duration::from_millis(50));
+        let err = stream.read(&mut [0u8; 16]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(
+            stream.read(&mut [0u8; 16]).unwrap_err().kind(),
+            ErrorKind::TimedOut
+        );
+    }
+}
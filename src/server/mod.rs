@@ -0,0 +1,10 @@
+// a mock server, answering ClientHellos in clear text so clients can be tested against a
+// controlled endpoint. It stops after the messages which are sent unencrypted
+pub mod client_auth;
+pub mod limits;
+pub mod psk;
+pub mod resolver;
+#[allow(clippy::module_inception)]
+pub mod server;
+pub mod session_cache;
+pub mod ticket;
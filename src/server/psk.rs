@@ -0,0 +1,159 @@
+// external PSKs offered by clients in their ClientHello. A PSK is only accepted once its
+// binder, which proves the client knows the key, is verified over the hello:
+// https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11.2
+use std::io::Cursor;
+
+use crate::alert::alert::AlertDescription;
+use crate::derive_tls::TlsDerive;
+use crate::handshake::client_hello::{
+    ClientHello, ExtensionContext, ExtensionType, PreSharedKeyExtension, Psk,
+};
+
+type AlertResult<T> = std::result::Result<T, AlertDescription>;
+
+// the index of the PSK to use among those offered in a ClientHello message, header included,
+// or None if the client offers none of the server's PSKs
+pub fn accept_psk(psks: &[Psk], message: &[u8]) -> AlertResult<Option<u16>> {
+    let mut ch = ClientHello::default();
+    ch.from_network_bytes(&mut Cursor::new(
+        message.get(4..).unwrap_or_default().to_vec(),
+    ))
+    .map_err(|_| AlertDescription::decode_error)?;
+
+    let find = |extension_type: ExtensionType| {
+        ch.extensions()
+            .iter()
+            .find(|x| x.type_code() == u16::from(extension_type))
+    };
+    let Some(extension) = find(ExtensionType::pre_shared_key) else {
+        return Ok(None);
+    };
+
+    // https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.9
+    if find(ExtensionType::psk_key_exchange_modes).is_none() {
+        return Err(AlertDescription::missing_extension);
+    }
+
+    let offered = PreSharedKeyExtension::from_extension_data(
+        extension.extension_data(),
+        ExtensionContext::ClientHello,
+    )
+    .map_err(|_| AlertDescription::decode_error)?;
+    let binders = offered.binders();
+    if binders.len() != offered.identities().len() {
+        return Err(AlertDescription::illegal_parameter);
+    }
+
+    // the first identity the server knows. Ticket ages don't apply to external PSKs
+    let Some((index, psk)) = offered.identities().iter().enumerate().find_map(|(i, x)| {
+        psks.iter()
+            .find(|psk| psk.identity == x.identity.data)
+            .map(|psk| (i, psk))
+    }) else {
+        return Ok(None);
+    };
+
+    // the pre_shared_key extension is last, so the binders end the message
+    let truncated = &message[..message.len() - offered.binders_len()];
+    if !psk.verify_binder(truncated, binders[index]) {
+        return Err(AlertDescription::decrypt_error);
+    }
+    Ok(Some(index as u16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::sha2::HashAlgorithm;
+    use crate::handshake::client_hello::{GenericExtension, PskIdentity};
+    use crate::handshake::constants::*;
+    use crate::handshake::handshake::Handshake;
+
+    fn message(ch: &ClientHello) -> Vec<u8> {
+        let mut message = Vec::new();
+        Handshake::from_client_hello(ch.clone())
+            .to_network_bytes(&mut message)
+            .unwrap();
+        message
+    }
+
+    #[test]
+    fn binders() {
+        let server = vec![
+            Psk::external(b"other", &[1; 32]),
+            Psk::external(b"client 1", &[2; 32]),
+        ];
+        let mut sha384 = Psk::external(b"client 2", &[3; 48]);
+        sha384.hash = HashAlgorithm::Sha384;
+
+        let ch = ClientHello::new(&[TLS_AES_128_GCM_SHA256, TLS_AES_256_GCM_SHA384]);
+        assert_eq!(accept_psk(&server, &message(&ch)), Ok(None));
+
+        // the second offered PSK is the first the server knows
+        let offered = ch
+            .with_psks(&[sha384.clone(), Psk::external(b"client 1", &[2; 32])])
+            .unwrap();
+        let types: Vec<_> = offered
+            .extensions()
+            .iter()
+            .map(|x| x.extension_type())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ExtensionType::psk_key_exchange_modes,
+                ExtensionType::pre_shared_key
+            ]
+        );
+        assert_eq!(accept_psk(&server, &message(&offered)), Ok(Some(1)));
+        assert_eq!(
+            accept_psk(&[sha384.clone()], &message(&offered)),
+            Ok(Some(0))
+        );
+        assert_eq!(accept_psk(&[], &message(&offered)), Ok(None));
+
+        // wrong key, and a hello changed after the binders were computed
+        let wrong = ch
+            .with_psks(&[Psk::external(b"client 1", &[9; 32])])
+            .unwrap();
+        assert_eq!(
+            accept_psk(&server, &message(&wrong)),
+            Err(AlertDescription::decrypt_error)
+        );
+        let mut tampered = message(&offered);
+        tampered[10] ^= 1;
+        assert_eq!(
+            accept_psk(&server, &tampered),
+            Err(AlertDescription::decrypt_error)
+        );
+
+        // binder of the wrong length
+        let short = ch.with_extension_replaced(GenericExtension::new(
+            ExtensionType::psk_key_exchange_modes,
+            &[0x01, 0x01],
+        ));
+        let short = short.with_extension_replaced(
+            GenericExtension::from_extension(&PreSharedKeyExtension::new(
+                vec![PskIdentity::new(b"client 1", 0)],
+                &[&[0; 48]],
+            ))
+            .unwrap(),
+        );
+        assert_eq!(
+            accept_psk(&server, &message(&short)),
+            Err(AlertDescription::decrypt_error)
+        );
+
+        // psk_key_exchange_modes is required
+        let no_modes = ClientHello::new(&[TLS_AES_128_GCM_SHA256])
+            .with_extension_replaced(offered.extensions().last().cloned().unwrap());
+        assert_eq!(
+            accept_psk(&server, &message(&no_modes)),
+            Err(AlertDescription::missing_extension)
+        );
+        assert_eq!(
+            accept_psk(&server, &[0x01, 0x00, 0x00, 0x01, 0x03]),
+            Err(AlertDescription::decode_error)
+        );
+    }
+}
@@ -0,0 +1,85 @@
+// select the certificate chain to send from the server name requested by the client
+use std::collections::HashMap;
+
+// DER-encoded certificates, leaf first
+pub type CertificateChain = Vec<Vec<u8>>;
+
+#[derive(Debug, Default)]
+pub struct CertificateResolver {
+    // exact names, lowercase
+    names: HashMap<String, CertificateChain>,
+
+    // wildcard names, stored without the "*." prefix
+    wildcards: HashMap<String, CertificateChain>,
+
+    // used when the client doesn't send SNI or when no name matches
+    default: Option<CertificateChain>,
+}
+
+// names are case insensitive and the trailing dot of a FQDN is ignored
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+impl CertificateResolver {
+    // add a chain for a name like "www.example.com" or "*.example.com"
+    pub fn add(&mut self, name: &str, chain: CertificateChain) {
+        let name = normalize(name);
+        match name.strip_prefix("*.") {
+            Some(domain) => self.wildcards.insert(domain.to_string(), chain),
+            None => self.names.insert(name, chain),
+        };
+    }
+
+    pub fn set_default(&mut self, chain: CertificateChain) {
+        self.default = Some(chain);
+    }
+
+    // exact names take precedence over wildcards, which only match a single label:
+    // *.example.com matches www.example.com but neither example.com nor a.b.example.com
+    pub fn resolve(&self, server_name: Option<&str>) -> Option<&CertificateChain> {
+        let found = server_name.map(normalize).and_then(|name| {
+            self.names.get(&name).or_else(|| {
+                let (label, domain) = name.split_once('.')?;
+                if label.is_empty() {
+                    return None;
+                }
+                self.wildcards.get(domain)
+            })
+        });
+
+        found.or(self.default.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let mut resolver = CertificateResolver::default();
+        resolver.add("www.example.com", vec![vec![1]]);
+        resolver.add("*.example.com", vec![vec![2]]);
+
+        assert_eq!(
+            resolver.resolve(Some("www.example.com")),
+            Some(&vec![vec![1]])
+        );
+        assert_eq!(
+            resolver.resolve(Some("WWW.Example.com.")),
+            Some(&vec![vec![1]])
+        );
+        assert_eq!(
+            resolver.resolve(Some("mail.example.com")),
+            Some(&vec![vec![2]])
+        );
+        assert_eq!(resolver.resolve(Some("example.com")), None);
+        assert_eq!(resolver.resolve(Some("a.b.example.com")), None);
+        assert_eq!(resolver.resolve(None), None);
+
+        resolver.set_default(vec![vec![3]]);
+        assert_eq!(resolver.resolve(Some("example.org")), Some(&vec![vec![3]]));
+        assert_eq!(resolver.resolve(None), Some(&vec![vec![3]]));
+    }
+}
@@ -0,0 +1,1331 @@
+use std::fmt;
+use std::io::{Error, Read, Result, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::alert::alert::{Alert, AlertDescription, AlertLevel};
+use crate::change_cipher_spec::change_cipher_spec::ChangeCipherSpec;
+use crate::crypto::cbc::CbcCipher;
+use crate::crypto::key_block::{KeyBlock, SuiteParameters};
+use crate::crypto::kx::check_public;
+use crate::derive_tls::TlsDerive;
+use crate::dissector::summary::ClientHelloSummary;
+use crate::dissector::{handshake_messages, records};
+use crate::handshake::certificate::{Certificate, CertificateStatus};
+use crate::handshake::client_hello::{
+    check_compression_methods, ExtensionType, Extensions, GenericExtension, MaxFragmentLength,
+    NamedGroup, Psk,
+};
+use crate::handshake::common::{CipherSuite, ContentType, Random, VariableLengthVector};
+use crate::handshake::constants::*;
+use crate::handshake::finished::{Finished, FinishedSecret, Sender};
+use crate::handshake::handshake::{Handshake, HandshakeType};
+use crate::handshake::new_session_ticket::NewSessionTicket;
+use crate::handshake::record_layer::{fragment_limit, RecordHeader, MAX_FRAGMENT_LENGTH};
+use crate::handshake::server_hello::{ServerHello, ServerHelloDone};
+use crate::handshake::transcript::Transcript;
+use crate::server::client_auth::ClientAuth;
+use crate::server::limits::{ConnectionTracker, DeadlineStream, ServerLimits};
+use crate::server::psk::accept_psk;
+use crate::server::resolver::CertificateResolver;
+use crate::server::session_cache::SessionCache;
+use crate::server::ticket::{SessionState, TicketKeys};
+use crate::shutdown::ShutdownToken;
+
+// how often the accept loop checks for shutdown. It's also how long a new connection may
+// wait to be accepted, which shows in handshake latencies measured against the server
+const SHUTDOWN_POLL: Duration = Duration::from_millis(5);
+
+// what happened to a connection the server didn't answer in full
+#[derive(Debug)]
+pub enum ServerEvent {
+    // closed right away, a connection limit being reached
+    Refused(SocketAddr),
+    Failed(SocketAddr, Error),
+
+    // a connection which couldn't be accepted, the server going on with the next ones
+    AcceptFailed(Error),
+}
+
+impl fmt::Display for ServerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServerEvent::Refused(peer) => write!(
+                f,
+                "connection limit reached, closing connection from {}",
+                peer
+            ),
+            ServerEvent::Failed(peer, e) => {
+                write!(f, "error handling connection from {}: {}", peer, e)
+            }
+            ServerEvent::AcceptFailed(e) => write!(f, "error accepting connection: {}", e),
+        }
+    }
+}
+
+// called by Server::serve for each event, from the thread of the connection
+#[derive(Clone)]
+pub struct EventHandler(pub Arc<dyn Fn(&ServerEvent) + Send + Sync>);
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerConfig {
+    pub resolver: CertificateResolver,
+
+    // in order of preference
+    pub cipher_suites: Vec<CipherSuite>,
+
+    // ALPN protocol names in order of preference. If empty, ALPN is ignored
+    pub alpn_protocols: Vec<String>,
+
+    // keys to issue and accept session tickets. If None, tickets are not used
+    pub tickets: Option<Mutex<TicketKeys>>,
+
+    // sessions to resume by session ID. If None, session IDs are not used
+    pub session_cache: Option<Mutex<SessionCache>>,
+
+    // if set, a certificate is requested from the client on full handshakes
+    pub client_auth: Option<ClientAuth>,
+
+    // DER-encoded OCSP response stapled when the client sends status_request
+    pub ocsp_response: Option<Vec<u8>>,
+
+    // PSKs provisioned out of band. Their binders are checked, but the answer stays a TLS 1.2
+    // full handshake, the server not implementing TLS 1.3
+    pub external_psks: Vec<Psk>,
+
+    pub limits: ServerLimits,
+
+    // if None, events are dropped
+    pub on_event: Option<EventHandler>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            resolver: CertificateResolver::default(),
+            cipher_suites: vec![
+                TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+                TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+                TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+                TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+                TLS_RSA_WITH_AES_128_GCM_SHA256,
+                TLS_RSA_WITH_AES_128_CBC_SHA,
+            ],
+            alpn_protocols: Vec::new(),
+            tickets: None,
+            session_cache: None,
+            client_auth: None,
+            ocsp_response: None,
+            external_psks: Vec::new(),
+            limits: ServerLimits::default(),
+            on_event: None,
+        }
+    }
+}
+
+// the first flight sent by the server, and what's left to do once the client has answered
+#[derive(Debug, Default)]
+struct Flight {
+    records: Vec<u8>,
+
+    // handshake messages of the flight, signed by the client in CertificateVerify
+    messages: Vec<u8>,
+
+    // session to issue a ticket for
+    ticket: Option<SessionState>,
+
+    // session to cache under its ID once the client's second flight is received
+    session: Option<(Vec<u8>, SessionState)>,
+    client_auth: bool,
+
+    // limit requested by the client, applied to all records sent
+    max_fragment_length: Option<MaxFragmentLength>,
+
+    // the keys of the server after its ChangeCipherSpec, sent in an abbreviated handshake
+    cipher: Option<CbcCipher>,
+}
+
+// how the records sent next by the server are protected. It has keys after its
+// ChangeCipherSpec in abbreviated handshakes only, making no key exchange in full ones
+#[derive(Debug)]
+enum WriteState {
+    Clear,
+    Sealed(CbcCipher),
+    Unkeyed,
+
+    // a fatal alert was sent
+    Closed,
+}
+
+impl WriteState {
+    // after the records are sent
+    fn sent(&mut self, records: &[u8], cipher: Option<CbcCipher>) {
+        for record in crate::dissector::records(records) {
+            match record.content_type() {
+                Some(ContentType::alert) if record.fragment.first() == Some(&2) => {
+                    *self = WriteState::Closed
+                }
+                Some(ContentType::change_cipher_spec) => {
+                    *self = cipher
+                        .clone()
+                        .map_or(WriteState::Unkeyed, WriteState::Sealed)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // the close_notify alert, protected as the next records. None if it can't be sent
+    fn close_notify(&mut self) -> Result<Option<Vec<u8>>> {
+        match self {
+            WriteState::Clear => close_notify().map(Some),
+            WriteState::Sealed(cipher) => {
+                let mut fragment = Vec::new();
+                Alert {
+                    level: AlertLevel::warning,
+                    description: AlertDescription::close_notify,
+                }
+                .to_network_bytes(&mut fragment)?;
+                let sealed = cipher
+                    .seal(ContentType::alert, &fragment)
+                    .map_err(|e| Error::other(format!("{:?}", e)))?;
+                to_records(ContentType::alert, &sealed, sealed.len()).map(Some)
+            }
+            WriteState::Unkeyed | WriteState::Closed => Ok(None),
+        }
+    }
+}
+
+impl Flight {
+    fn alert(description: AlertDescription) -> Result<Self> {
+        Ok(Self {
+            records: fatal_alert(description)?,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    pub config: ServerConfig,
+}
+
+// split a fragment into records of the given type, of at most limit bytes
+fn to_records(content_type: ContentType, fragment: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut v = Vec::new();
+
+    for chunk in fragment.chunks(limit) {
+        let header = RecordHeader {
+            content_type,
+            version: TLS12,
+            length: chunk.len() as u16,
+        };
+        header.to_network_bytes(&mut v)?;
+        v.extend_from_slice(chunk);
+    }
+
+    Ok(v)
+}
+
+// a fatal alert record
+pub fn fatal_alert(description: AlertDescription) -> Result<Vec<u8>> {
+    let alert = Alert {
+        level: AlertLevel::fatal,
+        description,
+    };
+
+    let mut fragment = Vec::new();
+    alert.to_network_bytes(&mut fragment)?;
+    to_records(ContentType::alert, &fragment, MAX_FRAGMENT_LENGTH)
+}
+
+// the warning alert sent before closing a connection: https://datatracker.ietf.org/doc/html/rfc5246#section-7.2.1
+pub fn close_notify() -> Result<Vec<u8>> {
+    let alert = Alert {
+        level: AlertLevel::warning,
+        description: AlertDescription::close_notify,
+    };
+
+    let mut fragment = Vec::new();
+    alert.to_network_bytes(&mut fragment)?;
+    to_records(ContentType::alert, &fragment, MAX_FRAGMENT_LENGTH)
+}
+
+// a ChangeCipherSpec record
+pub fn change_cipher_spec() -> Result<Vec<u8>> {
+    let mut fragment = Vec::new();
+    ChangeCipherSpec::default().to_network_bytes(&mut fragment)?;
+    to_records(
+        ContentType::change_cipher_spec,
+        &fragment,
+        MAX_FRAGMENT_LENGTH,
+    )
+}
+
+fn server_hello(
+    session_id: &[u8],
+    cipher_suite: CipherSuite,
+    extensions: Vec<GenericExtension>,
+) -> ServerHello {
+    ServerHello {
+        server_version: TLS12,
+        random: Random::new(),
+        session_id: VariableLengthVector::from_slice(session_id),
+        cipher_suite,
+        compression_method: 0,
+        extensions: (!extensions.is_empty()).then(|| Extensions::from(extensions)),
+    }
+}
+
+impl Server {
+    pub fn new(config: ServerConfig) -> Self {
+        Self { config }
+    }
+
+    // the first suite of the server preferences also offered by the client
+    fn select_suite(&self, client_hello: &ClientHelloSummary) -> Option<CipherSuite> {
+        self.config
+            .cipher_suites
+            .iter()
+            .find(|x| {
+                client_hello
+                    .cipher_suites
+                    .contains(&u16::from_be_bytes(**x))
+            })
+            .copied()
+    }
+
+    // the first protocol of the server preferences also offered by the client. None if
+    // ALPN is not used, an error if both sides use it but have no protocol in common:
+    // https://datatracker.ietf.org/doc/html/rfc7301#section-3.2
+    fn select_alpn(
+        &self,
+        client_hello: &ClientHelloSummary,
+    ) -> std::result::Result<Option<&str>, AlertDescription> {
+        let offered = client_hello.extensions.contains(&u16::from(
+            ExtensionType::application_layer_protocol_negotiation,
+        ));
+        if !offered || self.config.alpn_protocols.is_empty() {
+            return Ok(None);
+        }
+
+        self.config
+            .alpn_protocols
+            .iter()
+            .find(|x| client_hello.alpn.contains(x))
+            .map(|x| Some(x.as_str()))
+            .ok_or(AlertDescription::no_application_protocol)
+    }
+
+    // the session of a valid ticket sent by the client, if it can be resumed. Only the AES CBC
+    // suites can protect the server's Finished
+    fn resume(&self, client_hello: &ClientHelloSummary) -> Option<SessionState> {
+        let ticket = client_hello.session_ticket.as_deref()?;
+        let state = self.config.tickets.as_ref()?.lock().ok()?.open(ticket)?;
+
+        // the resumed session must match what the client asks for now
+        let offered = client_hello
+            .cipher_suites
+            .contains(&u16::from_be_bytes(state.cipher_suite));
+        (offered
+            && state.server_name == client_hello.server_name
+            && CbcCipher::supports(state.cipher_suite))
+        .then_some(state)
+    }
+
+    // the cached session of the ID offered by the client, if it can be resumed
+    fn resume_session(&self, client_hello: &ClientHelloSummary) -> Option<SessionState> {
+        let state = self
+            .config
+            .session_cache
+            .as_ref()?
+            .lock()
+            .ok()?
+            .get(&client_hello.session_id)?;
+
+        let offered = client_hello
+            .cipher_suites
+            .contains(&u16::from_be_bytes(state.cipher_suite));
+        (offered
+            && state.server_name == client_hello.server_name
+            && CbcCipher::supports(state.cipher_suite))
+        .then_some(state)
+    }
+
+    // the handshake message carrying a new ticket for the session
+    fn new_session_ticket(&self, state: &SessionState, v: &mut Vec<u8>) -> Result<()> {
+        let Some(tickets) = self.config.tickets.as_ref() else {
+            return Ok(());
+        };
+        let mut tickets = tickets
+            .lock()
+            .map_err(|_| Error::other("ticket keys lock poisoned"))?;
+
+        let nst = NewSessionTicket::new(tickets.lifetime_hint, &tickets.seal(state));
+        Handshake::from_message(HandshakeType::new_session_ticket, nst).to_network_bytes(v)?;
+        Ok(())
+    }
+
+    // ServerHello echoing the session ID, a new ticket if the session was resumed with one,
+    // then ChangeCipherSpec and the Finished encrypted with the keys expanded from the
+    // master secret of the session. The transcript starts with the ClientHello message
+    fn abbreviated_flight(
+        &self,
+        client_hello: &ClientHelloSummary,
+        message: &[u8],
+        state: &SessionState,
+        extensions: Vec<GenericExtension>,
+        ticket: bool,
+    ) -> Result<Flight> {
+        let sh = server_hello(&client_hello.session_id, state.cipher_suite, extensions);
+
+        let mut fragment = Vec::new();
+        Handshake::from_message(HandshakeType::server_hello, sh).to_network_bytes(&mut fragment)?;
+        // after the message header and the version
+        let server_random = fragment[6..38].to_vec();
+        if ticket {
+            self.new_session_ticket(state, &mut fragment)?;
+        }
+
+        // the suite is an AES CBC one, checked when resuming
+        let Some(parameters) = SuiteParameters::from_suite(state.cipher_suite) else {
+            return Flight::alert(AlertDescription::internal_error);
+        };
+        let mut transcript = Transcript::new(parameters.prf_hash);
+        transcript.add(message);
+        transcript.add(&fragment);
+
+        let finished = Finished::compute(
+            FinishedSecret::Tls12 {
+                master_secret: &state.master_secret,
+                sender: Sender::Server,
+            },
+            &transcript,
+        );
+        let mut plaintext = Vec::new();
+        Handshake::from_message(HandshakeType::finished, finished)
+            .to_network_bytes(&mut plaintext)?;
+
+        let keys = KeyBlock::new(
+            &parameters,
+            &state.master_secret,
+            &client_hello.random,
+            &server_random,
+        );
+        let mut cipher = CbcCipher::new(
+            state.cipher_suite,
+            TLS12,
+            &keys.server_mac_key,
+            &keys.server_key,
+        );
+        let sealed = cipher
+            .as_mut()
+            .ok_or(AlertDescription::internal_error)
+            .and_then(|cipher| cipher.seal(ContentType::handshake, &plaintext));
+        let sealed = match sealed {
+            Ok(sealed) => sealed,
+            Err(description) => return Flight::alert(description),
+        };
+
+        let mut records = to_records(ContentType::handshake, &fragment, MAX_FRAGMENT_LENGTH)?;
+        records.extend_from_slice(&change_cipher_spec()?);
+        records.extend_from_slice(&to_records(ContentType::handshake, &sealed, sealed.len())?);
+        Ok(Flight {
+            records,
+            cipher,
+            ..Default::default()
+        })
+    }
+
+    // the records sent in answer to a ClientHello: ServerHello, Certificate, optional
+    // CertificateStatus and CertificateRequest, and ServerHelloDone, or a fatal alert if the hello can't be accepted.
+    // The ClientHello message, header included, starts the transcript of an abbreviated
+    // handshake
+    pub fn respond(&self, client_hello: &ClientHelloSummary, message: &[u8]) -> Result<Vec<u8>> {
+        self.first_flight(client_hello, message)
+            .map(|flight| flight.records)
+    }
+
+    fn first_flight(&self, client_hello: &ClientHelloSummary, message: &[u8]) -> Result<Flight> {
+        let tls13 = client_hello.max_version() == TLS13;
+        if let Err(description) =
+            check_compression_methods(&client_hello.compression_methods, tls13)
+        {
+            return Flight::alert(description);
+        }
+
+        // key shares are checked even if the server doesn't negotiate TLS 1.3
+        for (group, key) in &client_hello.key_shares {
+            if let Ok(group) = NamedGroup::try_from(*group) {
+                if let Err(description) = check_public(group, key) {
+                    return Flight::alert(description);
+                }
+            }
+        }
+
+        let protocol = match self.select_alpn(client_hello) {
+            Ok(protocol) => protocol,
+            Err(description) => return Flight::alert(description),
+        };
+
+        // the selected protocol, as a list of a single name
+        let mut extensions = Vec::new();
+        if let Some(protocol) = protocol {
+            let mut data = ((protocol.len() + 1) as u16).to_be_bytes().to_vec();
+            data.push(protocol.len() as u8);
+            data.extend_from_slice(protocol.as_bytes());
+            extensions.push(GenericExtension::new(
+                ExtensionType::application_layer_protocol_negotiation,
+                &data,
+            ));
+        }
+
+        let max_fragment_length = match client_hello
+            .max_fragment_length
+            .map(MaxFragmentLength::try_from)
+        {
+            Some(Ok(length)) => Some(length),
+            Some(Err(_)) => return Flight::alert(AlertDescription::illegal_parameter),
+            None => None,
+        };
+
+        // abbreviated handshake with a new ticket: https://datatracker.ietf.org/doc/html/rfc5077#section-3.1
+        if let Some(state) = self.resume(client_hello) {
+            extensions.push(GenericExtension::new(ExtensionType::session_ticket, &[]));
+            return self.abbreviated_flight(client_hello, message, &state, extensions, true);
+        }
+
+        // abbreviated handshake: https://datatracker.ietf.org/doc/html/rfc5246#section-7.3
+        if let Some(state) = self.resume_session(client_hello) {
+            return self.abbreviated_flight(client_hello, message, &state, extensions, false);
+        }
+
+        let Some(cipher_suite) = self.select_suite(client_hello) else {
+            return Flight::alert(AlertDescription::handshake_failure);
+        };
+
+        let server_name = client_hello.server_name.as_deref();
+        let Some(chain) = self.config.resolver.resolve(server_name) else {
+            return Flight::alert(AlertDescription::unrecognized_name);
+        };
+
+        // the server acknowledges SNI with an empty extension: https://datatracker.ietf.org/doc/html/rfc6066#section-3
+        if server_name.is_some() {
+            extensions.insert(0, GenericExtension::new(ExtensionType::server_name, &[]));
+        }
+
+        // a requested limit is accepted by echoing it: https://datatracker.ietf.org/doc/html/rfc6066#section-4
+        if let Some(length) = max_fragment_length {
+            extensions.push(GenericExtension::from_extension(&length)?);
+        }
+
+        // an empty session_ticket extension announces a NewSessionTicket
+        // a stapled response is announced with an empty status_request extension
+        let ocsp_response = self.config.ocsp_response.as_ref().filter(|_| {
+            client_hello
+                .extensions
+                .contains(&u16::from(ExtensionType::status_request))
+        });
+        if ocsp_response.is_some() {
+            extensions.push(GenericExtension::new(ExtensionType::status_request, &[]));
+        }
+
+        // the server makes no key exchange and can't know the master secret of a full
+        // handshake: its sessions get a random one, so clients resuming them fail to verify
+        // the server's Finished
+        let master_secret: Vec<u8> = (0..48).map(|_| rand::random()).collect();
+        let ticket = (self.config.tickets.is_some() && client_hello.session_ticket.is_some())
+            .then(|| SessionState::new(cipher_suite, server_name, &master_secret));
+        if ticket.is_some() {
+            extensions.push(GenericExtension::new(ExtensionType::session_ticket, &[]));
+        }
+
+        let session_id = rand::random::<[u8; 32]>();
+        let session = self.config.session_cache.is_some().then(|| {
+            (
+                session_id.to_vec(),
+                SessionState::new(cipher_suite, server_name, &master_secret),
+            )
+        });
+
+        let sh = server_hello(&session_id, cipher_suite, extensions);
+        let certs: Vec<&[u8]> = chain.iter().map(|x| x.as_slice()).collect();
+
+        let mut fragment = Vec::new();
+        Handshake::from_message(HandshakeType::server_hello, sh).to_network_bytes(&mut fragment)?;
+        Handshake::from_message(HandshakeType::certificate, Certificate::new(&certs))
+            .to_network_bytes(&mut fragment)?;
+        if let Some(response) = ocsp_response {
+            Handshake::from_message(
+                HandshakeType::certificate_status,
+                CertificateStatus::ocsp(response),
+            )
+            .to_network_bytes(&mut fragment)?;
+        }
+        if let Some(client_auth) = &self.config.client_auth {
+            Handshake::from_message(
+                HandshakeType::certificate_request,
+                client_auth.certificate_request(),
+            )
+            .to_network_bytes(&mut fragment)?;
+        }
+        Handshake::from_message(HandshakeType::server_hello_done, ServerHelloDone::default())
+            .to_network_bytes(&mut fragment)?;
+
+        Ok(Flight {
+            records: to_records(
+                ContentType::handshake,
+                &fragment,
+                fragment_limit(max_fragment_length),
+            )?,
+            messages: fragment,
+            ticket,
+            session,
+            client_auth: self.config.client_auth.is_some(),
+            max_fragment_length,
+            cipher: None,
+        })
+    }
+
+    // index of the external PSK offered in a ClientHello message with a valid binder. Hellos
+    // are only parsed for PSKs when the server has some
+    pub fn accept_psk(&self, message: &[u8]) -> std::result::Result<Option<u16>, AlertDescription> {
+        if self.config.external_psks.is_empty() {
+            return Ok(None);
+        }
+        accept_psk(&self.config.external_psks, message)
+    }
+
+    // read a ClientHello from the stream and answer it. If a ticket was announced, a session
+    // is to be cached or a client certificate requested, the client's second flight is read
+    // up to its ChangeCipherSpec: the server then sends an alert if client authentication
+    // fails, or caches the session and sends the ticket and its own ChangeCipherSpec
+    pub fn handle<S: Read + Write>(&self, stream: &mut S) -> Result<()> {
+        self.exchange(stream, &mut WriteState::Clear)
+    }
+
+    // see handle(), the protection of the records the server sends being tracked
+    fn exchange<S: Read + Write>(&self, stream: &mut S, state: &mut WriteState) -> Result<()> {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        let message = loop {
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(());
+            }
+            data.extend_from_slice(&chunk[..n]);
+
+            if let Some(message) = handshake_messages(&records(&data)).into_iter().next() {
+                break message;
+            }
+        };
+
+        let flight = if message.msg_type != HandshakeType::client_hello as u8 {
+            Flight::alert(AlertDescription::unexpected_message)?
+        } else if let Err(description) = self.accept_psk(&message.to_bytes()) {
+            Flight::alert(description)?
+        } else {
+            match ClientHelloSummary::parse(&message.body) {
+                Ok(client_hello) => self.first_flight(&client_hello, &message.to_bytes())?,
+                Err(_) => Flight::alert(AlertDescription::decode_error)?,
+            }
+        };
+        stream.write_all(&flight.records)?;
+        state.sent(&flight.records, flight.cipher);
+
+        if flight.ticket.is_none() && flight.session.is_none() && !flight.client_auth {
+            return Ok(());
+        }
+
+        // wait for the client's second flight
+        while !records(&data)
+            .iter()
+            .any(|r| r.content_type == ContentType::change_cipher_spec as u8)
+        {
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(());
+            }
+            data.extend_from_slice(&chunk[..n]);
+        }
+
+        if let Some(client_auth) = self
+            .config
+            .client_auth
+            .as_ref()
+            .filter(|_| flight.client_auth)
+        {
+            // the ClientHello, then the server flight
+            let messages = handshake_messages(&records(&data));
+            let mut transcript = message.to_bytes();
+            transcript.extend_from_slice(&flight.messages);
+
+            if let Err(description) = client_auth.verify(&transcript, &messages[1..]) {
+                let alert = fatal_alert(description)?;
+                stream.write_all(&alert)?;
+                state.sent(&alert, None);
+                return Ok(());
+            }
+        }
+
+        // the handshake is complete, as far as the server can tell
+        if let (Some((session_id, state)), Some(cache)) =
+            (flight.session, &self.config.session_cache)
+        {
+            cache
+                .lock()
+                .map_err(|_| Error::other("session cache lock poisoned"))?
+                .insert(&session_id, state);
+        }
+
+        let mut fragment = Vec::new();
+        if let Some(state) = &flight.ticket {
+            self.new_session_ticket(state, &mut fragment)?;
+        }
+        let limit = fragment_limit(flight.max_fragment_length);
+        let mut records = to_records(ContentType::handshake, &fragment, limit)?;
+        records.extend_from_slice(&change_cipher_spec()?);
+        stream.write_all(&records)?;
+        state.sent(&records, None);
+        Ok(())
+    }
+
+    fn event(&self, event: &ServerEvent) {
+        if let Some(EventHandler(handler)) = &self.config.on_event {
+            handler(event);
+        }
+    }
+
+    // answer each connection in its own thread, within the configured limits. Connections
+    // over the limits are closed right away, and those which can't be accepted are reported.
+    // Connections are closed with close_notify, unless the server has no keys for it. Once
+    // shutdown is requested, no new connection is accepted and in-flight handshakes are
+    // completed
+    pub fn serve(&self, listener: &TcpListener, shutdown: &ShutdownToken) -> Result<()> {
+        let tracker = ConnectionTracker::default();
+
+        // accept() would block until the next client, so the listener is polled
+        listener.set_nonblocking(true)?;
+
+        std::thread::scope(|scope| {
+            while !shutdown.is_shutdown() {
+                let accepted = listener.accept().and_then(|(stream, peer)| {
+                    stream.set_nonblocking(false).map(|_| (stream, peer))
+                });
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::WouldBlock {
+                            self.event(&ServerEvent::AcceptFailed(e));
+                        }
+                        std::thread::sleep(SHUTDOWN_POLL);
+                        continue;
+                    }
+                };
+
+                let Some(slot) = tracker.acquire(peer.ip(), &self.config.limits) else {
+                    self.event(&ServerEvent::Refused(peer));
+                    continue;
+                };
+
+                scope.spawn(move || {
+                    let _slot = slot;
+                    let mut stream =
+                        DeadlineStream::new(stream, self.config.limits.handshake_timeout);
+                    let mut state = WriteState::Clear;
+                    if let Err(e) = self.exchange(&mut stream, &mut state) {
+                        self.event(&ServerEvent::Failed(peer, e));
+                    }
+                    if let Ok(Some(alert)) = state.close_notify() {
+                        let _ = stream.write_all(&alert);
+                    }
+                });
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::handshake::certificate::CertificateMessage;
+    use crate::handshake::certificate_request::CertificateVerify;
+    use crate::handshake::client_hello::SignatureScheme;
+    use crate::server::client_auth::PinnedClientCerts;
+    use crate::{handshake, record};
+    use std::io::Cursor;
+
+    fn client_hello_message() -> Vec<u8> {
+        handshake!(0x01, CLIENT_HELLO)
+    }
+
+    fn server() -> Server {
+        let mut server = Server::default();
+        server
+            .config
+            .resolver
+            .add("*.ulfheim.net", vec![vec![0x30, 0x01], vec![0x30, 0x02]]);
+        server
+    }
+
+    #[test]
+    fn certificate_by_sni() {
+        let client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let response = server()
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+
+        let messages = handshake_messages(&records(&response));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].msg_type, HandshakeType::server_hello as u8);
+        assert_eq!(messages[2].msg_type, HandshakeType::server_hello_done as u8);
+
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert_eq!(sh.cipher_suite, TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256);
+        assert!(sh.extension(ExtensionType::server_name).is_some());
+
+        let mut cert = CertificateMessage::for_version(TLS12);
+        cert.from_network_bytes(&mut Cursor::new(messages[1].body.clone()))
+            .unwrap();
+        assert_eq!(cert.certificates(), vec![&[0x30, 0x01][..], &[0x30, 0x02]]);
+    }
+
+    #[test]
+    fn unknown_name() {
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello.server_name = Some(String::from("example.com"));
+
+        let response = server()
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 112]);
+    }
+
+    #[test]
+    fn compression_methods() {
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello.compression_methods = vec![1, 0];
+        assert_eq!(
+            handshake_messages(&records(
+                &server()
+                    .respond(&client_hello, &client_hello_message())
+                    .unwrap()
+            ))
+            .len(),
+            3
+        );
+
+        client_hello.compression_methods = vec![1];
+        let response = server()
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 50]);
+
+        // null only with TLS 1.3
+        client_hello.compression_methods = vec![1, 0];
+        client_hello.supported_versions = vec![TLS13, TLS12];
+        let response = server()
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 47]);
+    }
+
+    #[test]
+    fn key_shares() {
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello.key_shares = vec![(u16::from(NamedGroup::x25519), vec![9; 32])];
+        assert_eq!(
+            handshake_messages(&records(
+                &server()
+                    .respond(&client_hello, &client_hello_message())
+                    .unwrap()
+            ))
+            .len(),
+            3
+        );
+
+        // a point of small order
+        client_hello
+            .key_shares
+            .push((u16::from(NamedGroup::x25519), vec![0; 32]));
+        let response = server()
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 47]);
+    }
+
+    #[test]
+    fn alpn() {
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello.extensions.push(u16::from(
+            ExtensionType::application_layer_protocol_negotiation,
+        ));
+        client_hello.alpn = vec![String::from("http/1.1"), String::from("h2")];
+
+        let mut server = server();
+        server.config.alpn_protocols = vec![String::from("h2"), String::from("http/1.1")];
+
+        // server preference wins
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        let messages = handshake_messages(&records(&response));
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert_eq!(
+            sh.extension(ExtensionType::application_layer_protocol_negotiation)
+                .unwrap()
+                .extension_data(),
+            b"\x00\x03\x02h2"
+        );
+
+        // nothing in common
+        server.config.alpn_protocols = vec![String::from("imap")];
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 120]);
+
+        // client without ALPN
+        let client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(handshake_messages(&records(&response)).len(), 3);
+    }
+
+    #[test]
+    fn ocsp_stapling() {
+        let mut server = server();
+        server.config.ocsp_response = Some(vec![0x30, 0x03, 0x0A, 0x01, 0x00]);
+
+        // the ClientHello sends status_request
+        let client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        let messages = handshake_messages(&records(&response));
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            messages[2].msg_type,
+            HandshakeType::certificate_status as u8
+        );
+        assert_eq!(&messages[2].body[..4], &[0x01, 0x00, 0x00, 0x05]);
+
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert!(sh.extension(ExtensionType::status_request).is_some());
+
+        let mut client_hello = client_hello;
+        client_hello
+            .extensions
+            .retain(|x| *x != u16::from(ExtensionType::status_request));
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(handshake_messages(&records(&response)).len(), 3);
+    }
+
+    #[test]
+    fn max_fragment_length() {
+        let mut server = server();
+        server
+            .config
+            .resolver
+            .add("example.ulfheim.net", vec![vec![0x30; 2000]]);
+
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello.max_fragment_length = Some(MaxFragmentLength::length_512 as u8);
+
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        let records = records(&response);
+        assert!(records.len() > 4);
+        assert!(records.iter().all(|x| x.fragment.len() <= 512));
+
+        let messages = handshake_messages(&records);
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert_eq!(
+            sh.extension(ExtensionType::max_fragment_length)
+                .unwrap()
+                .extension_data(),
+            &[0x01]
+        );
+
+        // unknown value
+        client_hello.max_fragment_length = Some(5);
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(response, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 47]);
+    }
+
+    #[test]
+    fn resumption() {
+        let mut server = server();
+        server.config.tickets = Some(Mutex::new(TicketKeys::default()));
+        server.config.cipher_suites = vec![TLS_RSA_WITH_AES_128_CBC_SHA];
+
+        let mut client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        client_hello
+            .extensions
+            .push(u16::from(ExtensionType::session_ticket));
+        client_hello.session_ticket = Some(Vec::new());
+
+        // full handshake: a ticket is announced
+        let flight = server
+            .first_flight(&client_hello, &client_hello_message())
+            .unwrap();
+        let state = flight.ticket.unwrap();
+        let messages = handshake_messages(&records(&flight.records));
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert!(sh.extension(ExtensionType::session_ticket).is_some());
+
+        // the ticket sent after the client's ChangeCipherSpec
+        let mut fragment = Vec::new();
+        server.new_session_ticket(&state, &mut fragment).unwrap();
+        let mut nst = NewSessionTicket::default();
+        nst.from_network_bytes(&mut Cursor::new(fragment[4..].to_vec()))
+            .unwrap();
+
+        // abbreviated handshake with this ticket
+        client_hello.session_ticket = Some(nst.ticket.data.clone());
+        client_hello.session_id = vec![0x42; 32];
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        let flight = records(&response);
+        assert_eq!(flight.len(), 3);
+        assert_eq!(
+            flight[1].content_type,
+            ContentType::change_cipher_spec as u8
+        );
+        assert_eq!(flight[2].content_type, ContentType::handshake as u8);
+
+        let messages = handshake_messages(&flight);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[1].msg_type,
+            HandshakeType::new_session_ticket as u8
+        );
+        let mut sh = ServerHello::default();
+        sh.from_network_bytes(&mut Cursor::new(messages[0].body.clone()))
+            .unwrap();
+        assert_eq!(sh.session_id.data, vec![0x42; 32]);
+        assert_eq!(sh.cipher_suite, state.cipher_suite);
+
+        // a ticket from another server falls back to a full handshake
+        client_hello.session_ticket = Some(vec![0x00; 80]);
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(handshake_messages(&records(&response)).len(), 3);
+
+        // as does a session whose Finished can't be protected
+        let state = SessionState::new(
+            TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            client_hello.server_name.as_deref(),
+            &[0; 48],
+        );
+        let ticket = server
+            .config
+            .tickets
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .seal(&state);
+        client_hello.session_ticket = Some(ticket);
+        let response = server
+            .respond(&client_hello, &client_hello_message())
+            .unwrap();
+        assert_eq!(handshake_messages(&records(&response)).len(), 3);
+    }
+
+    #[test]
+    fn abbreviated_finished() {
+        use crate::handshake::client_hello::ClientHello;
+        use crate::scanner::abbreviated::{AbbreviatedHandshake, ClientSession, ResumptionState};
+
+        let suite = TLS_RSA_WITH_AES_128_CBC_SHA;
+        let state = SessionState::new(suite, None, &[0x42; 48]);
+        let mut server = server();
+        server.config.tickets = Some(Mutex::new(TicketKeys::default()));
+        server.config.session_cache = Some(Mutex::new(SessionCache::default()));
+        server
+            .config
+            .session_cache
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .insert(&[0x5A; 32], state.clone());
+        let ticket = server
+            .config
+            .tickets
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .seal(&state);
+
+        for session in [
+            ClientSession::new(&[0x5A; 32], suite, TLS12, &[0x42; 48]).unwrap(),
+            ClientSession::from_ticket(&ticket, 0, suite, TLS12, &[0x42; 48]).unwrap(),
+        ] {
+            let mut client =
+                AbbreviatedHandshake::new(session, &ClientHello::new(&[suite])).unwrap();
+            let mut duplex = Duplex(Cursor::new(client.hello_record().unwrap()), Vec::new());
+            server.handle(&mut duplex).unwrap();
+
+            // the client verifies the server's Finished
+            for record in &records(&duplex.1) {
+                client.receive(record).unwrap();
+            }
+            assert_eq!(client.state(), ResumptionState::Done);
+        }
+    }
+
+    // a Read + Write stream: client messages in, response out
+    struct Duplex(Cursor<Vec<u8>>, Vec<u8>);
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.0.read(buf)
+        }
+    }
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.1.write(buf)
+        }
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn client_hello_record() -> Vec<u8> {
+        record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO))
+    }
+
+    #[test]
+    fn client_auth() {
+        let mut server = server();
+        server.config.client_auth = Some(ClientAuth::new(Box::new(PinnedClientCerts::new(&[&[
+            0x30, 0x0A,
+        ]]))));
+
+        // the CertificateRequest comes before ServerHelloDone
+        let client_hello = ClientHelloSummary::parse(&CLIENT_HELLO).unwrap();
+        let messages = handshake_messages(&records(
+            &server
+                .respond(&client_hello, &client_hello_message())
+                .unwrap(),
+        ));
+        assert_eq!(messages.len(), 4);
+        assert_eq!(
+            messages[2].msg_type,
+            HandshakeType::certificate_request as u8
+        );
+
+        // client second flight: Certificate, ClientKeyExchange, CertificateVerify, then
+        // ChangeCipherSpec
+        let second_flight = |cert: &[u8]| {
+            let mut fragment = Vec::new();
+            Handshake::from_message(HandshakeType::certificate, Certificate::new(&[cert]))
+                .to_network_bytes(&mut fragment)
+                .unwrap();
+            fragment.extend_from_slice(&[0x10, 0x00, 0x00, 0x02, 0x01, 0xAA]);
+            Handshake::from_message(
+                HandshakeType::certificate_verify,
+                CertificateVerify::new(SignatureScheme::ecdsa_secp256r1_sha256, &[0xBB]),
+            )
+            .to_network_bytes(&mut fragment)
+            .unwrap();
+
+            let mut stream = client_hello_record();
+            stream.extend_from_slice(
+                &to_records(ContentType::handshake, &fragment, MAX_FRAGMENT_LENGTH).unwrap(),
+            );
+            stream.extend_from_slice(&change_cipher_spec().unwrap());
+            stream
+        };
+
+        let mut duplex = Duplex(Cursor::new(second_flight(&[0x30, 0x0A])), Vec::new());
+        server.handle(&mut duplex).unwrap();
+        assert_eq!(
+            records(&duplex.1).last().unwrap().content_type,
+            ContentType::change_cipher_spec as u8
+        );
+
+        // unknown client certificate
+        let mut duplex = Duplex(Cursor::new(second_flight(&[0x30, 0x0B])), Vec::new());
+        server.handle(&mut duplex).unwrap();
+        assert!(duplex
+            .1
+            .ends_with(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 48]));
+    }
+
+    #[test]
+    fn shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let token = ShutdownToken::new();
+        let server = server();
+
+        std::thread::scope(|scope| {
+            let serving = scope.spawn(|| server.serve(&listener, &token));
+
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            client.write_all(&client_hello_record()).unwrap();
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).unwrap();
+            assert_eq!(response[0], ContentType::handshake as u8);
+
+            // the connection is closed with close_notify, the server not having sent its
+            // ChangeCipherSpec
+            assert!(response.ends_with(&close_notify().unwrap()));
+
+            token.shutdown();
+            assert!(serving.join().unwrap().is_ok());
+        });
+
+        // the listener is still bound, but nothing accepts connections anymore
+        assert!(listener.accept().is_err());
+    }
+
+    #[test]
+    fn write_state() {
+        let mut state = WriteState::Clear;
+        state.sent(&client_hello_record(), None);
+        assert_eq!(state.close_notify().unwrap(), Some(close_notify().unwrap()));
+
+        // protected with the keys of an abbreviated handshake, none after a full one
+        let suite = TLS_RSA_WITH_AES_128_CBC_SHA;
+        let cipher = CbcCipher::new(suite, TLS12, &[1; 20], &[2; 16]).unwrap();
+        let mut sealed = WriteState::Clear;
+        sealed.sent(&change_cipher_spec().unwrap(), Some(cipher));
+        let alert = sealed.close_notify().unwrap().unwrap();
+        assert_eq!(alert[0], ContentType::alert as u8);
+        assert_eq!(alert.len(), 5 + 16 + 32);
+
+        state.sent(&change_cipher_spec().unwrap(), None);
+        assert!(state.close_notify().unwrap().is_none());
+
+        let mut closed = WriteState::Clear;
+        closed.sent(
+            &fatal_alert(AlertDescription::handshake_failure).unwrap(),
+            None,
+        );
+        assert!(closed.close_notify().unwrap().is_none());
+    }
+
+    #[test]
+    fn events() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let token = ShutdownToken::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut server = server();
+        server.config.limits.max_connections = 0;
+        let sink = events.clone();
+        server.config.on_event = Some(EventHandler(Arc::new(move |event| {
+            sink.lock().unwrap().push(event.to_string())
+        })));
+
+        std::thread::scope(|scope| {
+            let serving = scope.spawn(|| server.serve(&listener, &token));
+
+            let client = std::net::TcpStream::connect(addr).unwrap();
+            for _ in 0..400 {
+                if !events.lock().unwrap().is_empty() {
+                    break;
+                }
+                std::thread::sleep(SHUTDOWN_POLL);
+            }
+
+            token.shutdown();
+            assert!(serving.join().unwrap().is_ok());
+            assert_eq!(
+                *events.lock().unwrap(),
+                vec![format!(
+                    "connection limit reached, closing connection from {}",
+                    client.local_addr().unwrap()
+                )]
+            );
+        });
+    }
+
+    #[test]
+    fn handle() {
+        let stream = client_hello_record();
+
+        let mut duplex = Duplex(Cursor::new(stream), Vec::new());
+        server().handle(&mut duplex).unwrap();
+        assert_eq!(handshake_messages(&records(&duplex.1)).len(), 3);
+    }
+
+    #[test]
+    fn psk_binders() {
+        use crate::handshake::client_hello::ClientHello;
+        use crate::handshake::record_layer::RecordLayer;
+
+        let mut server = server();
+        server.config.external_psks = vec![Psk::external(b"device 1", &[7; 32])];
+        server.config.resolver.set_default(vec![vec![0x30, 0x00]]);
+
+        let record = |ch: ClientHello| {
+            let mut record = RecordLayer {
+                header: RecordHeader {
+                    content_type: ContentType::handshake,
+                    version: TLS10,
+                    length: 0,
+                },
+                data: Handshake::from_client_hello(ch),
+            };
+            record.set_length();
+            let mut stream = Vec::new();
+            record.to_network_bytes(&mut stream).unwrap();
+            stream
+        };
+        let ch = ClientHello::new(&[TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256]);
+
+        let mut duplex = Duplex(
+            Cursor::new(record(
+                ch.with_psks(&[Psk::external(b"device 1", &[7; 32])])
+                    .unwrap(),
+            )),
+            Vec::new(),
+        );
+        server.handle(&mut duplex).unwrap();
+        assert_eq!(handshake_messages(&records(&duplex.1)).len(), 3);
+
+        let mut duplex = Duplex(
+            Cursor::new(record(
+                ch.with_psks(&[Psk::external(b"device 1", &[8; 32])])
+                    .unwrap(),
+            )),
+            Vec::new(),
+        );
+        server.handle(&mut duplex).unwrap();
+        assert_eq!(duplex.1, &[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 51]);
+    }
+}
@@ -0,0 +1,94 @@
+// sessions kept by the server so clients can resume them by offering their session ID again:
+// https://datatracker.ietf.org/doc/html/rfc5246#section-7.3
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::server::ticket::SessionState;
+
+#[derive(Debug)]
+pub struct SessionCache {
+    sessions: HashMap<Vec<u8>, (SessionState, Instant)>,
+
+    // sessions kept at most, the oldest is forgotten first
+    pub capacity: usize,
+
+    // age after which a session can't be resumed
+    pub lifetime: Duration,
+}
+
+impl Default for SessionCache {
+    fn default() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            capacity: 10_000,
+            lifetime: Duration::from_secs(7200),
+        }
+    }
+}
+
+impl SessionCache {
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    pub fn insert(&mut self, session_id: &[u8], state: SessionState) {
+        if session_id.is_empty() {
+            return;
+        }
+
+        self.sessions
+            .retain(|_, (_, created)| created.elapsed() < self.lifetime);
+        while self.sessions.len() >= self.capacity.max(1) {
+            // not empty
+            let oldest = self
+                .sessions
+                .iter()
+                .min_by_key(|(_, (_, created))| *created)
+                .map(|(id, _)| id.clone())
+                .unwrap();
+            self.sessions.remove(&oldest);
+        }
+
+        self.sessions
+            .insert(session_id.to_vec(), (state, Instant::now()));
+    }
+
+    // None if the session is unknown or too old
+    pub fn get(&self, session_id: &[u8]) -> Option<SessionState> {
+        self.sessions
+            .get(session_id)
+            .filter(|(_, created)| created.elapsed() < self.lifetime)
+            .map(|(state, _)| state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let mut cache = SessionCache {
+            capacity: 2,
+            ..Default::default()
+        };
+        let state = SessionState::new([0xC0, 0x2F], Some("www.example.com"), &[0x42; 48]);
+
+        cache.insert(&[], state.clone());
+        assert!(cache.is_empty());
+
+        cache.insert(&[1; 32], state.clone());
+        cache.insert(&[2; 32], state.clone());
+        cache.insert(&[3; 32], state.clone());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&[1; 32]).is_none());
+        assert_eq!(cache.get(&[3; 32]), Some(state.clone()));
+
+        cache.lifetime = Duration::ZERO;
+        assert!(cache.get(&[3; 32]).is_none());
+    }
+}
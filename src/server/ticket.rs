@@ -0,0 +1,313 @@
+// session tickets protected by rotating ticket encryption keys (STEK). The ticket layout
+// follows https://datatracker.ietf.org/doc/html/rfc5077#section-4:
+//
+//   key_name (16) | iv (16) | encrypted state | mac (32)
+//
+// the state is encrypted with AES-128-CBC and authenticated with HMAC-SHA256
+// (encrypt-then-MAC), as recommended there
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::crypto::aes::Aes;
+use crate::crypto::hmac::hmac;
+use crate::crypto::sha2::HashAlgorithm;
+use crate::handshake::common::CipherSuite;
+
+const KEY_NAME_LENGTH: usize = 16;
+const IV_LENGTH: usize = Aes::BLOCK_LENGTH;
+const MAC_LENGTH: usize = 32;
+
+// what the server needs to resume a session
+#[derive(Clone, PartialEq)]
+pub struct SessionState {
+    pub cipher_suite: CipherSuite,
+    pub server_name: Option<String>,
+
+    // seconds since the Unix epoch
+    pub created: u64,
+
+    // the keys of the resumed connection are expanded from it
+    pub(crate) master_secret: Vec<u8>,
+}
+
+// the master secret is not shown
+impl std::fmt::Debug for SessionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionState")
+            .field("cipher_suite", &self.cipher_suite)
+            .field("server_name", &self.server_name)
+            .field("created", &self.created)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionState {
+    pub fn new(cipher_suite: CipherSuite, server_name: Option<&str>, master_secret: &[u8]) -> Self {
+        let created = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            cipher_suite,
+            server_name: server_name.map(String::from),
+            created,
+            master_secret: master_secret.to_vec(),
+        }
+    }
+
+    // the master secret is prefixed by its length, the name takes the rest
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut v = self.cipher_suite.to_vec();
+        v.extend_from_slice(&self.created.to_be_bytes());
+        v.push(self.master_secret.len() as u8);
+        v.extend_from_slice(&self.master_secret);
+        if let Some(name) = &self.server_name {
+            v.extend_from_slice(name.as_bytes());
+        }
+        v
+    }
+
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        let cipher_suite = [*data.first()?, *data.get(1)?];
+        let created = u64::from_be_bytes(data.get(2..10)?.try_into().ok()?);
+        let length = *data.get(10)? as usize;
+        let master_secret = data.get(11..11 + length)?;
+        let name = &data[11 + length..];
+
+        Some(Self {
+            cipher_suite,
+            server_name: (!name.is_empty()).then(|| String::from_utf8_lossy(name).into_owned()),
+            created,
+            master_secret: master_secret.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TicketKey {
+    pub name: [u8; KEY_NAME_LENGTH],
+    encryption_key: [u8; 16],
+    mac_key: [u8; 32],
+    created: Instant,
+}
+
+impl TicketKey {
+    pub fn new() -> Self {
+        Self {
+            name: rand::random(),
+            encryption_key: rand::random(),
+            mac_key: rand::random(),
+            created: Instant::now(),
+        }
+    }
+
+    fn aes(&self) -> Aes {
+        // the key is 16 bytes long
+        Aes::new(&self.encryption_key).unwrap()
+    }
+
+    // CBC, padded up to a whole block with bytes giving the padding length
+    fn encrypt(&self, iv: &[u8; IV_LENGTH], data: &[u8]) -> Vec<u8> {
+        let aes = self.aes();
+        let padding = IV_LENGTH - data.len() % IV_LENGTH;
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + padding, padding as u8);
+
+        let mut previous = *iv;
+        for chunk in padded.chunks_mut(IV_LENGTH) {
+            chunk.iter_mut().zip(previous).for_each(|(b, x)| *b ^= x);
+            // blocks are whole
+            let block: &mut [u8; IV_LENGTH] = chunk.try_into().unwrap();
+            aes.encrypt_block(block);
+            previous = *block;
+        }
+        padded
+    }
+
+    // None if the data isn't made of whole blocks or the padding is wrong. The MAC is
+    // checked first, so the padding can't be used as an oracle
+    fn decrypt(&self, iv: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+        if data.is_empty() || !data.len().is_multiple_of(IV_LENGTH) {
+            return None;
+        }
+
+        let aes = self.aes();
+        let mut plaintext = data.to_vec();
+        let mut previous = iv;
+        for (chunk, ciphertext) in plaintext.chunks_mut(IV_LENGTH).zip(data.chunks(IV_LENGTH)) {
+            let block: &mut [u8; IV_LENGTH] = chunk.try_into().unwrap();
+            aes.decrypt_block(block);
+            block.iter_mut().zip(previous).for_each(|(b, x)| *b ^= x);
+            previous = ciphertext;
+        }
+
+        let padding = *plaintext.last()? as usize;
+        let content_length = plaintext.len().checked_sub(padding)?;
+        let valid = padding > 0
+            && plaintext[content_length..]
+                .iter()
+                .all(|x| *x as usize == padding);
+        valid.then(|| plaintext[..content_length].to_vec())
+    }
+
+    fn mac(&self, data: &[u8]) -> Vec<u8> {
+        hmac(HashAlgorithm::Sha256, &self.mac_key, data)
+    }
+}
+
+impl Default for TicketKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the current key is used to issue tickets, previous ones are kept to accept tickets
+// issued before a rotation
+#[derive(Debug)]
+pub struct TicketKeys {
+    // current key first
+    keys: Vec<TicketKey>,
+
+    // age after which a new key is used
+    pub rotation: Duration,
+
+    // number of keys kept, including the current one
+    pub max_keys: usize,
+
+    // sent to clients in NewSessionTicket, in seconds
+    pub lifetime_hint: u32,
+}
+
+impl Default for TicketKeys {
+    fn default() -> Self {
+        Self {
+            keys: vec![TicketKey::new()],
+            rotation: Duration::from_secs(3600),
+            max_keys: 2,
+            lifetime_hint: 7200,
+        }
+    }
+}
+
+impl TicketKeys {
+    pub fn current(&self) -> &TicketKey {
+        &self.keys[0]
+    }
+
+    // start using a new key, forgetting the oldest ones
+    pub fn rotate(&mut self) {
+        self.keys.insert(0, TicketKey::new());
+        self.keys.truncate(self.max_keys.max(1));
+    }
+
+    fn rotate_if_needed(&mut self) {
+        if self.current().created.elapsed() >= self.rotation {
+            self.rotate();
+        }
+    }
+
+    pub fn seal(&mut self, state: &SessionState) -> Vec<u8> {
+        self.rotate_if_needed();
+        let key = self.current();
+
+        let iv: [u8; IV_LENGTH] = rand::random();
+        let encrypted = key.encrypt(&iv, &state.to_bytes());
+
+        let mut ticket = key.name.to_vec();
+        ticket.extend_from_slice(&iv);
+        ticket.extend_from_slice(&encrypted);
+
+        let mac = key.mac(&ticket);
+        ticket.extend_from_slice(&mac);
+        ticket
+    }
+
+    // None if the key is unknown, the ticket was modified or can't be decoded
+    pub fn open(&self, ticket: &[u8]) -> Option<SessionState> {
+        if ticket.len() < KEY_NAME_LENGTH + IV_LENGTH + MAC_LENGTH {
+            return None;
+        }
+
+        let key = self
+            .keys
+            .iter()
+            .find(|k| k.name[..] == ticket[..KEY_NAME_LENGTH])?;
+
+        let (data, mac) = ticket.split_at(ticket.len() - MAC_LENGTH);
+        let equal = key
+            .mac(data)
+            .iter()
+            .zip(mac)
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0;
+        if !equal {
+            return None;
+        }
+
+        let iv = &data[KEY_NAME_LENGTH..KEY_NAME_LENGTH + IV_LENGTH];
+        let state = key.decrypt(iv, &data[KEY_NAME_LENGTH + IV_LENGTH..])?;
+
+        SessionState::from_bytes(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_open() {
+        let mut keys = TicketKeys::default();
+        let state = SessionState::new([0xC0, 0x2F], Some("www.example.com"), &[0x42; 48]);
+
+        let ticket = keys.seal(&state);
+        assert_eq!(&ticket[..16], &keys.current().name);
+        assert_eq!(keys.open(&ticket), Some(state.clone()));
+        assert_eq!(keys.open(&ticket).unwrap().master_secret, [0x42; 48]);
+        assert!(!format!("{:?}", state).contains("master_secret"));
+
+        // whole blocks of ciphertext
+        let ciphertext = ticket.len() - KEY_NAME_LENGTH - IV_LENGTH - MAC_LENGTH;
+        assert_eq!(ciphertext % 16, 0);
+        assert!(ciphertext > state.to_bytes().len());
+
+        // tampered ticket
+        let mut tampered = ticket.clone();
+        tampered[40] ^= 1;
+        assert!(keys.open(&tampered).is_none());
+
+        // still accepted after one rotation, not after two
+        keys.rotate();
+        assert_eq!(keys.open(&ticket), Some(state));
+        assert_ne!(
+            &keys.seal(&SessionState::new([0x13, 0x01], None, &[0; 48]))[..16],
+            &ticket[..16]
+        );
+        keys.rotate();
+        assert!(keys.open(&ticket).is_none());
+    }
+
+    #[test]
+    fn padding() {
+        let key = TicketKey::new();
+        for length in [0, 15, 16, 17] {
+            let data = vec![0x07; length];
+            let encrypted = key.encrypt(&[0x01; 16], &data);
+            assert_eq!(encrypted.len(), (length / 16 + 1) * 16);
+            assert_eq!(key.decrypt(&[0x01; 16], &encrypted), Some(data));
+        }
+        assert!(key.decrypt(&[0x01; 16], &[0x00; 15]).is_none());
+        assert!(key.decrypt(&[0x01; 16], &[]).is_none());
+    }
+
+    #[test]
+    fn automatic_rotation() {
+        let mut keys = TicketKeys {
+            rotation: Duration::ZERO,
+            ..Default::default()
+        };
+        let name = keys.current().name;
+        keys.seal(&SessionState::new([0xC0, 0x2F], None, &[0; 48]));
+        assert_ne!(keys.current().name, name);
+    }
+}
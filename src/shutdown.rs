@@ -0,0 +1,65 @@
+// coordinated shutdown of long-running modes: runners check the token between units of
+// work, finish what's in flight and stop
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
+
+// set by the SIGINT handler
+static CTRL_C: AtomicBool = AtomicBool::new(false);
+static HANDLER: Once = Once::new();
+
+extern "C" fn on_ctrl_c(_signal: libc::c_int) {
+    CTRL_C.store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken {
+    requested: Arc<AtomicBool>,
+
+    // also triggered by Ctrl-C
+    ctrl_c: bool,
+}
+
+impl ShutdownToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a token triggered by Ctrl-C. A second Ctrl-C doesn't kill the process, so it
+    // has to be polled
+    pub fn ctrl_c() -> Self {
+        HANDLER.call_once(|| unsafe {
+            libc::signal(
+                libc::SIGINT,
+                on_ctrl_c as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        });
+
+        Self {
+            requested: Arc::default(),
+            ctrl_c: true,
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.requested.load(Ordering::SeqCst) || (self.ctrl_c && CTRL_C.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared() {
+        let token = ShutdownToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_shutdown());
+
+        token.shutdown();
+        assert!(clone.is_shutdown());
+    }
+}
@@ -0,0 +1,453 @@
+// statistics computed over all ClientHellos found in a capture, and the candidates of the
+// handshakes whose hellos weren't captured whole
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Result};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(feature = "arena")]
+use crate::dissector::arena::ConnectionArena;
+use crate::dissector::certainty::{Certainty, HandshakeAssessment};
+use crate::dissector::hello_time::HelloTime;
+use crate::dissector::redact::redact_host_name;
+use crate::dissector::summary::ClientHelloSummary;
+#[cfg(not(feature = "arena"))]
+use crate::dissector::{handshake_messages, records};
+use crate::fingerprint::database::FingerprintDatabase;
+use crate::fingerprint::ja3::ja3_hash;
+use crate::fingerprint::ja4::ja4;
+use crate::handshake::client_hello::extension_name;
+use crate::handshake::common::{is_grease, version_name};
+use crate::handshake::handshake::HandshakeType;
+use crate::pcap::filter::CaptureFilter;
+use crate::pcap::flow::{connections_filtered, Connection};
+use crate::pcap::parallel::map_connections;
+use crate::pcap::reader::PcapReader;
+
+// number of occurences of each value
+#[derive(Debug, Default)]
+pub struct Distribution {
+    counts: HashMap<String, usize>,
+}
+
+impl Distribution {
+    pub fn add<S: Into<String>>(&mut self, value: S) {
+        *self.counts.entry(value.into()).or_default() += 1;
+    }
+
+    pub fn count(&self, value: &str) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    // values sorted by decreasing count, then by value
+    pub fn sorted(&self) -> Vec<(&str, usize)> {
+        let mut values: Vec<_> = self.counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        values
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CaptureStats {
+    // replace host names by pseudonyms so reports can be shared
+    pub redact: bool,
+
+    // to name the client software of each ClientHello
+    pub fingerprints: FingerprintDatabase,
+
+    // tolerance to read the start of the randoms as the client's clock, which isn't analyzed
+    // if None
+    pub unix_time: Option<Duration>,
+
+    pub connections: usize,
+    pub client_hellos: usize,
+
+    // ClientHellos which couldn't be parsed
+    pub errors: usize,
+
+    pub versions: Distribution,
+    pub cipher_suites: Distribution,
+    pub extensions: Distribution,
+    pub ja3: Distribution,
+    pub ja4: Distribution,
+    pub clients: Distribution,
+    pub sni: Distribution,
+
+    // clock skews against the capture time, or randomized
+    pub hello_times: Distribution,
+
+    // in capture order
+    pub incomplete: Vec<IncompleteHandshake>,
+}
+
+// a connection whose ClientHello or ServerHello was cut, with what can still be told
+#[derive(Debug, Clone)]
+pub struct IncompleteHandshake {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    pub assessment: HandshakeAssessment,
+}
+
+// state reused by a worker across the connections it dissects
+#[cfg(feature = "arena")]
+type Scratch = ConnectionArena;
+#[cfg(not(feature = "arena"))]
+type Scratch = ();
+
+// ClientHellos sent in a connection and accepted by the filter, None for those which
+// couldn't be parsed
+fn client_hellos(
+    _scratch: &mut Scratch,
+    connection: &Connection,
+    filter: &CaptureFilter,
+) -> Vec<Option<ClientHelloSummary>> {
+    let is_client_hello = |msg_type| msg_type == HandshakeType::client_hello as u8;
+
+    #[cfg(feature = "arena")]
+    let hellos: Vec<_> = {
+        _scratch.dissect(&connection.client_data());
+        _scratch
+            .handshake_messages()
+            .filter(|x| is_client_hello(x.msg_type))
+            .map(|x| ClientHelloSummary::parse(x.body).ok())
+            .collect()
+    };
+
+    #[cfg(not(feature = "arena"))]
+    let hellos: Vec<_> = handshake_messages(&records(&connection.client_data()))
+        .iter()
+        .filter(|x| is_client_hello(x.msg_type))
+        .map(|x| ClientHelloSummary::parse(&x.body).ok())
+        .collect();
+
+    hellos
+        .into_iter()
+        .filter(|x| filter.matches(&connection.client, &connection.server, x.as_ref()))
+        .collect()
+}
+
+// the assessment of a connection whose hellos weren't both captured whole. Connections where
+// the client sent no ClientHello aren't assessed. A whole ClientHello must have been accepted
+// by the filter, otherwise the filter is applied without one
+fn incomplete_handshake(
+    connection: &Connection,
+    hellos: &[Option<ClientHelloSummary>],
+    filter: &CaptureFilter,
+) -> Option<IncompleteHandshake> {
+    let assessment =
+        HandshakeAssessment::assess(&connection.client_data(), &connection.server_data());
+    let accepted = match assessment.client_hello {
+        Certainty::Unknown => false,
+        Certainty::Confirmed if assessment.server_hello == Certainty::Confirmed => false,
+        Certainty::Confirmed => !hellos.is_empty(),
+        Certainty::Partial => filter.matches(&connection.client, &connection.server, None),
+    };
+
+    accepted.then_some(IncompleteHandshake {
+        client: connection.client,
+        server: connection.server,
+        assessment,
+    })
+}
+
+impl CaptureStats {
+    pub fn from_pcap<P: AsRef<Path>>(
+        path: P,
+        redact: bool,
+        threads: usize,
+        filter: &CaptureFilter,
+    ) -> Result<Self> {
+        Self::from_reader_parallel(PcapReader::open(path)?, redact, threads, filter)
+    }
+
+    pub fn from_reader<R: Read>(reader: PcapReader<R>, redact: bool) -> Result<Self> {
+        Self::from_reader_parallel(reader, redact, 1, &CaptureFilter::default())
+    }
+
+    // connections are dissected on several threads, and their ClientHellos added in capture
+    // order so the result doesn't depend on the number of threads. Only connections and
+    // ClientHellos accepted by the filter are counted
+    pub fn from_reader_parallel<R: Read>(
+        reader: PcapReader<R>,
+        redact: bool,
+        threads: usize,
+        filter: &CaptureFilter,
+    ) -> Result<Self> {
+        let connections = connections_filtered(reader, filter)?;
+        Ok(Self::from_connections(
+            &connections,
+            redact,
+            threads,
+            filter,
+            FingerprintDatabase::builtin(),
+            None,
+        ))
+    }
+
+    // connections already read from a capture, and accepted by the filter
+    pub fn from_connections(
+        connections: &[Connection],
+        redact: bool,
+        threads: usize,
+        filter: &CaptureFilter,
+        fingerprints: FingerprintDatabase,
+        unix_time: Option<Duration>,
+    ) -> Self {
+        let mut stats = CaptureStats {
+            redact,
+            fingerprints,
+            unix_time,
+            connections: connections.len(),
+            ..Default::default()
+        };
+
+        for (start, hellos, incomplete) in
+            map_connections(connections, threads, |scratch, connection| {
+                let hellos = client_hellos(scratch, connection, filter);
+                let incomplete = incomplete_handshake(connection, &hellos, filter);
+                (connection.start, hellos, incomplete)
+            })
+        {
+            stats.incomplete.extend(incomplete);
+            for hello in hellos {
+                match hello {
+                    Some(summary) => {
+                        stats.add(&summary);
+                        stats.add_time(&summary, start);
+                    }
+                    None => stats.errors += 1,
+                }
+            }
+        }
+
+        stats
+    }
+
+    pub fn add(&mut self, summary: &ClientHelloSummary) {
+        self.client_hellos += 1;
+
+        // TLS 1.3 clients offer versions with the supported_versions extension
+        if summary.supported_versions.is_empty() {
+            self.versions.add(version_name(summary.client_version));
+        } else {
+            for version in &summary.supported_versions {
+                self.versions.add(version_name(*version));
+            }
+        }
+
+        for suite in &summary.cipher_suites {
+            self.cipher_suites.add(if is_grease(*suite) {
+                String::from("GREASE")
+            } else {
+                format!("0x{:04X}", suite)
+            });
+        }
+
+        for extension in &summary.extensions {
+            self.extensions.add(extension_name(*extension));
+        }
+
+        self.ja3.add(ja3_hash(summary));
+        self.ja4.add(ja4(summary));
+        self.clients
+            .add(self.fingerprints.lookup(summary).unwrap_or("<unknown>"));
+
+        let sni = match &summary.server_name {
+            Some(name) if self.redact => redact_host_name(name),
+            Some(name) => name.clone(),
+            None => String::from("<none>"),
+        };
+        self.sni.add(sni);
+    }
+
+    // the client's clock against the time the ClientHello was seen, if enabled
+    pub fn add_time(&mut self, summary: &ClientHelloSummary, seen: Duration) {
+        let Some(tolerance) = self.unix_time else {
+            return;
+        };
+        if let Some(time) = HelloTime::from_random(&summary.random, seen, tolerance) {
+            self.hello_times.add(time.bucket());
+        }
+    }
+}
+
+impl fmt::Display for CaptureStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "connections: {}", self.connections)?;
+        writeln!(f, "client hellos: {}", self.client_hellos)?;
+        writeln!(f, "parse errors: {}", self.errors)?;
+
+        let mut sections = vec![
+            ("versions offered", &self.versions),
+            ("cipher suites", &self.cipher_suites),
+            ("extensions", &self.extensions),
+            ("JA3", &self.ja3),
+            ("JA4", &self.ja4),
+            ("clients", &self.clients),
+            ("SNI", &self.sni),
+        ];
+        if self.unix_time.is_some() {
+            sections.push(("gmt_unix_time", &self.hello_times));
+        }
+
+        for (title, distribution) in sections {
+            writeln!(f, "\n{}:", title)?;
+
+            // percentages are relative to the number of ClientHellos
+            for (value, count) in distribution.sorted() {
+                let percent = 100.0 * count as f64 / self.client_hellos.max(1) as f64;
+                writeln!(f, "{:>8} {:>7.2}%  {}", count, percent, value)?;
+            }
+        }
+
+        if !self.incomplete.is_empty() {
+            writeln!(f, "\nincomplete handshakes:")?;
+            for handshake in &self.incomplete {
+                writeln!(
+                    f,
+                    "  {} -> {}: {}",
+                    handshake.client, handshake.server, handshake.assessment
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dissector::summary::tests::CLIENT_HELLO;
+    use crate::pcap::flow::tests::capture;
+    use crate::pcap::packet::tests::ethernet_frame;
+    use crate::{handshake, record};
+    use std::io::Cursor;
+
+    #[test]
+    fn stats() {
+        // the ClientHello record split over 2 segments
+        let record = record!(0x16, [0x03, 0x01], handshake!(0x01, CLIENT_HELLO));
+
+        let client = ([10, 0, 0, 1], 50000);
+        let server = ([10, 0, 0, 2], 443);
+        let frames = vec![
+            ethernet_frame(client, server, 0, 0x02, &[]),
+            ethernet_frame(client, server, 1, 0x10, &record[..50]),
+            ethernet_frame(client, server, 51, 0x10, &record[50..]),
+            ethernet_frame(([10, 0, 0, 3], 50001), server, 0, 0x02, &[]),
+        ];
+
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let stats = CaptureStats::from_reader(reader, false).unwrap();
+
+        assert_eq!(stats.connections, 2);
+        assert_eq!(stats.client_hellos, 1);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.versions.count("TLS 1.2"), 1);
+        assert_eq!(stats.cipher_suites.count("0xCCA8"), 1);
+        assert_eq!(stats.extensions.count("server_name(0)"), 1);
+        assert_eq!(stats.extensions.count("renegotiation_info(65281)"), 1);
+        assert_eq!(stats.sni.count("example.ulfheim.net"), 1);
+        assert_eq!(stats.ja3.sorted().len(), 1);
+        assert_eq!(stats.ja4.sorted().len(), 1);
+        assert_eq!(stats.clients.count("<unknown>"), 1);
+
+        // the server didn't answer
+        assert_eq!(stats.incomplete.len(), 1);
+        assert_eq!(
+            stats.incomplete[0].assessment.server_hello,
+            Certainty::Unknown
+        );
+
+        let report = stats.to_string();
+        assert!(report.contains("client hellos: 1"));
+        assert!(report.contains("100.00%  example.ulfheim.net"));
+        assert!(report.contains(
+            "\nincomplete handshakes:\n  10.0.0.1:50000 -> 10.0.0.2:443: versions SSL 3.0, TLS 1.0"
+        ));
+
+        // same capture, redacted
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let stats = CaptureStats::from_reader(reader, true).unwrap();
+        assert_eq!(stats.sni.count("example.ulfheim.net"), 0);
+        assert_eq!(stats.sni.count(&redact_host_name("example.ulfheim.net")), 1);
+
+        // same capture, on several threads
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let parallel =
+            CaptureStats::from_reader_parallel(reader, false, 4, &CaptureFilter::default())
+                .unwrap();
+        assert_eq!(parallel.to_string(), report);
+
+        // the client is known
+        let text = format!(
+            "ja3 {} curl",
+            ja3_hash(&ClientHelloSummary::parse(&CLIENT_HELLO).unwrap())
+        );
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let connections = connections_filtered(reader, &CaptureFilter::default()).unwrap();
+        let stats = CaptureStats::from_connections(
+            &connections,
+            false,
+            1,
+            &CaptureFilter::default(),
+            FingerprintDatabase::parse(&text).unwrap(),
+            None,
+        );
+        assert_eq!(stats.clients.count("curl"), 1);
+        assert!(stats
+            .to_string()
+            .contains("\nclients:\n       1  100.00%  curl\n"));
+
+        // the random starts with 66051, 18 hours after the capture started
+        let stats = CaptureStats::from_connections(
+            &connections,
+            false,
+            1,
+            &CaptureFilter::default(),
+            FingerprintDatabase::default(),
+            Some(Duration::from_secs(86400)),
+        );
+        assert_eq!(stats.hello_times.count("skew < 1d"), 1);
+        assert!(stats
+            .to_string()
+            .contains("\ngmt_unix_time:\n       1  100.00%  skew < 1d\n"));
+        assert!(!report.contains("gmt_unix_time"));
+
+        // only the connection without a ClientHello
+        let filter = CaptureFilter::parse("host 10.0.0.3").unwrap();
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let stats = CaptureStats::from_reader_parallel(reader, false, 1, &filter).unwrap();
+        assert_eq!(stats.connections, 1);
+        assert_eq!(stats.client_hellos, 0);
+
+        // the ClientHello doesn't match
+        let filter = CaptureFilter::parse("sni *.example.com").unwrap();
+        let reader = PcapReader::new(Cursor::new(capture(&frames))).unwrap();
+        let stats = CaptureStats::from_reader_parallel(reader, false, 1, &filter).unwrap();
+        assert_eq!(stats.connections, 2);
+        assert_eq!(stats.client_hellos, 0);
+        assert!(stats.incomplete.is_empty());
+
+        // the ClientHello cut, TLS 1.3 can't be ruled out
+        let reader = PcapReader::new(Cursor::new(capture(&frames[..2]))).unwrap();
+        let stats = CaptureStats::from_reader(reader, false).unwrap();
+        assert_eq!(stats.client_hellos, 0);
+        assert_eq!(stats.incomplete.len(), 1);
+        assert_eq!(
+            stats.incomplete[0].assessment.client_hello,
+            Certainty::Partial
+        );
+        assert!(stats.incomplete[0]
+            .assessment
+            .versions
+            .contains(&[0x03, 0x04]));
+    }
+}
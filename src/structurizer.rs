@@ -0,0 +1,20 @@
+// the traits of the first serializer, merged into TlsDerive. Kept so code written against the
+// old paths still compiles, and removed in the next major release
+/// ```
+/// #![allow(deprecated)]
+/// use tls_explore::structurizer::length::TlsLength;
+///
+/// let v = [0u8; 10];
+/// assert_eq!(v.tls_len(), 10);
+/// ```
+pub mod length {
+    pub use crate::derive_tls::TlsDerive as TlsLength;
+}
+
+pub mod to_network {
+    pub use crate::derive_tls::TlsDerive as TlsToNetworkBytes;
+}
+
+pub mod from_network {
+    pub use crate::derive_tls::TlsDerive as TlsFromNetworkBytes;
+}
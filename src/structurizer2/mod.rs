@@ -1,3 +0,0 @@
-pub mod from_network;
-pub mod length;
-pub mod to_network;
@@ -0,0 +1,149 @@
+// what's learnt from a certificate chain is computed once per leaf certificate: a scan probes
+// the same host many times and usually gets the same chain back. The cache is shared by
+// clones, so checks running in parallel fill the same one
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::{Arc, Mutex};
+
+use crate::crypto::sha2::sha256;
+use crate::handshake::client_hello::SignedCertificateTimestamp;
+use crate::x509::certificate::{certificate_policies, embedded_scts, must_staple};
+
+#[derive(Debug, Clone)]
+pub struct ChainInfo {
+    // SHA-256 of the leaf certificate
+    pub fingerprint: [u8; 32],
+    pub chain_length: usize,
+    pub must_staple: bool,
+    pub policies: Vec<String>,
+
+    // SCTs embedded in the leaf certificate
+    pub scts: Vec<SignedCertificateTimestamp>,
+}
+
+impl ChainInfo {
+    // parse a chain, leaf first
+    pub fn parse(chain: &[&[u8]]) -> Result<Self> {
+        let leaf = chain
+            .first()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "empty certificate chain"))?;
+
+        Ok(Self {
+            fingerprint: sha256(leaf),
+            chain_length: chain.len(),
+            must_staple: must_staple(leaf)?,
+            policies: certificate_policies(leaf)?,
+            scts: embedded_scts(leaf)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    chains: HashMap<[u8; 32], Arc<ChainInfo>>,
+
+    // the last OCSP response stapled with each leaf
+    staples: HashMap<[u8; 32], Vec<u8>>,
+    hits: usize,
+    misses: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CertificateCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl CertificateCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the parsed chain, parsed only the first time its leaf is seen. Chains which can't be
+    // parsed are not cached
+    pub fn chain(&self, chain: &[&[u8]]) -> Result<Arc<ChainInfo>> {
+        let fingerprint = chain.first().map(|leaf| sha256(leaf));
+
+        // only poisoned if a thread panicked while holding the lock
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(info) = fingerprint.and_then(|x| entries.chains.get(&x).cloned()) {
+                entries.hits += 1;
+                return Ok(info);
+            }
+        }
+
+        // parsed without holding the lock
+        let info = Arc::new(ChainInfo::parse(chain)?);
+        let mut entries = self.entries.lock().unwrap();
+        entries.misses += 1;
+        entries.chains.insert(info.fingerprint, info.clone());
+        Ok(info)
+    }
+
+    pub fn record_staple(&self, fingerprint: &[u8; 32], response: &[u8]) {
+        self.entries
+            .lock()
+            .unwrap()
+            .staples
+            .insert(*fingerprint, response.to_vec());
+    }
+
+    pub fn staple(&self, fingerprint: &[u8; 32]) -> Option<Vec<u8>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .staples
+            .get(fingerprint)
+            .cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().chains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // lookups answered from the cache, and those which parsed the chain
+    pub fn hits(&self) -> usize {
+        self.entries.lock().unwrap().hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.entries.lock().unwrap().misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x509::certificate::{must_staple_certificate, test_certificate};
+
+    #[test]
+    fn cache() {
+        let cache = CertificateCache::new();
+        let leaf = must_staple_certificate();
+        let other = test_certificate(&[]);
+
+        let info = cache.chain(&[&leaf, &other]).unwrap();
+        assert!(info.must_staple);
+        assert_eq!(info.chain_length, 2);
+        assert_eq!(info.policies, vec!["2.23.140.1.2.1"]);
+
+        // shared by clones
+        let clone = cache.clone();
+        assert!(clone.chain(&[&leaf, &other]).unwrap().must_staple);
+        assert!(!cache.chain(&[&other]).unwrap().must_staple);
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+
+        // errors are not cached
+        assert!(cache.chain(&[]).is_err());
+        assert!(cache.chain(&[&[0x30, 0x00]]).is_err());
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.staple(&info.fingerprint).is_none());
+        clone.record_staple(&info.fingerprint, &[0x30, 0x00]);
+        assert_eq!(cache.staple(&info.fingerprint), Some(vec![0x30, 0x00]));
+    }
+}
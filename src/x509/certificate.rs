@@ -0,0 +1,293 @@
+// extensions and public keys of X.509 certificates: https://datatracker.ietf.org/doc/html/rfc5280#section-4.2
+use std::io::{Error, ErrorKind, Result};
+
+use crate::handshake::client_hello::{
+    ExtensionType, SignedCertificateTimestamp, SignedCertificateTimestampList,
+};
+use crate::x509::der::{
+    self, context, DerReader, BIT_STRING, BOOLEAN, INTEGER, OCTET_STRING, OID, SEQUENCE,
+};
+
+// TLS Feature, aka must-staple: https://datatracker.ietf.org/doc/html/rfc7633
+pub const TLS_FEATURE: &str = "1.3.6.1.5.5.7.1.24";
+pub const CERTIFICATE_POLICIES: &str = "2.5.29.32";
+
+// SCTs embedded by the CA: https://datatracker.ietf.org/doc/html/rfc6962#section-3.3
+pub const EMBEDDED_SCT_LIST: &str = "1.3.6.1.4.1.11129.2.4.2";
+
+// https://datatracker.ietf.org/doc/html/rfc8017#appendix-A.1
+pub const RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension<'a> {
+    // dotted notation
+    pub oid: String,
+    pub critical: bool,
+
+    // DER encoding of the extension, without the OCTET STRING wrapping it
+    pub value: &'a [u8],
+}
+
+// the extensions of a DER-encoded certificate, in order. Empty for v1 certificates
+pub fn extensions(cert: &[u8]) -> Result<Vec<Extension<'_>>> {
+    let certificate = DerReader::new(cert).expect(SEQUENCE)?;
+    let mut tbs = DerReader::new(DerReader::new(certificate).expect(SEQUENCE)?);
+
+    // the fields before extensions are skipped, they are the only one tagged [3]
+    let mut extensions = Vec::new();
+    while !tbs.is_empty() {
+        let field = tbs.read()?;
+        if field.tag != context(3) {
+            continue;
+        }
+
+        let mut list = DerReader::new(DerReader::new(field.value).expect(SEQUENCE)?);
+        while !list.is_empty() {
+            let mut extension = DerReader::new(list.expect(SEQUENCE)?);
+            let oid = der::oid_to_string(extension.expect(OID)?);
+            let critical = extension.optional(BOOLEAN)?.is_some_and(|x| x != [0]);
+            let value = extension.expect(OCTET_STRING)?;
+            extensions.push(Extension {
+                oid,
+                critical,
+                value,
+            });
+        }
+    }
+
+    Ok(extensions)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubjectPublicKeyInfo<'a> {
+    // dotted notation
+    pub algorithm: String,
+
+    // the parameters when they're an OID, like the named curve of EC keys
+    pub parameters: Option<String>,
+
+    // content of the BIT STRING, e.g. the DER encoding of an RSAPublicKey
+    pub public_key: &'a [u8],
+}
+
+// the key of the subject: https://datatracker.ietf.org/doc/html/rfc5280#section-4.1.2.7
+pub fn subject_public_key_info(cert: &[u8]) -> Result<SubjectPublicKeyInfo<'_>> {
+    let certificate = DerReader::new(cert).expect(SEQUENCE)?;
+    let mut tbs = DerReader::new(DerReader::new(certificate).expect(SEQUENCE)?);
+
+    // version, then serial number, signature, issuer, validity and subject
+    tbs.optional(context(0))?;
+    for tag in [INTEGER, SEQUENCE, SEQUENCE, SEQUENCE, SEQUENCE] {
+        tbs.expect(tag)?;
+    }
+
+    let mut info = DerReader::new(tbs.expect(SEQUENCE)?);
+    let mut algorithm = DerReader::new(info.expect(SEQUENCE)?);
+    let oid = der::oid_to_string(algorithm.expect(OID)?);
+    let parameters = algorithm.optional(OID)?.map(der::oid_to_string);
+    match info.expect(BIT_STRING)? {
+        [0, public_key @ ..] => Ok(SubjectPublicKeyInfo {
+            algorithm: oid,
+            parameters,
+            public_key,
+        }),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            "DER: public key with unused bits",
+        )),
+    }
+}
+
+fn find<'a>(cert: &'a [u8], oid: &str) -> Result<Option<Extension<'a>>> {
+    Ok(extensions(cert)?.into_iter().find(|x| x.oid == oid))
+}
+
+// the TLS extensions the server must send with this certificate. None without TLS Feature
+pub fn tls_features(cert: &[u8]) -> Result<Option<Vec<u16>>> {
+    let Some(extension) = find(cert, TLS_FEATURE)? else {
+        return Ok(None);
+    };
+
+    let mut features = DerReader::new(DerReader::new(extension.value).expect(SEQUENCE)?);
+    let mut list = Vec::new();
+    while !features.is_empty() {
+        let feature = der::integer(features.expect(der::INTEGER)?)?;
+        list.push(feature as u16);
+    }
+    Ok(Some(list))
+}
+
+// the certificate requires an OCSP response to be stapled
+pub fn must_staple(cert: &[u8]) -> Result<bool> {
+    // status_request_v2 also satisfies a must-staple certificate
+    let staple = [
+        u16::from(ExtensionType::status_request),
+        u16::from(ExtensionType::status_request_v2),
+    ];
+    Ok(tls_features(cert)?.is_some_and(|features| features.iter().any(|x| staple.contains(x))))
+}
+
+// OIDs of the policies under which the certificate was issued, qualifiers are ignored
+pub fn certificate_policies(cert: &[u8]) -> Result<Vec<String>> {
+    let Some(extension) = find(cert, CERTIFICATE_POLICIES)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut policies = DerReader::new(DerReader::new(extension.value).expect(SEQUENCE)?);
+    let mut list = Vec::new();
+    while !policies.is_empty() {
+        let mut information = DerReader::new(policies.expect(SEQUENCE)?);
+        list.push(der::oid_to_string(information.expect(OID)?));
+    }
+    Ok(list)
+}
+
+// SCTs embedded in the certificate, empty if there are none
+pub fn embedded_scts(cert: &[u8]) -> Result<Vec<SignedCertificateTimestamp>> {
+    let Some(extension) = find(cert, EMBEDDED_SCT_LIST)? else {
+        return Ok(Vec::new());
+    };
+
+    // the TLS encoding of the list, wrapped in another OCTET STRING
+    let list = DerReader::new(extension.value).expect(OCTET_STRING)?;
+    SignedCertificateTimestampList::from_extension_data(list)?.scts()
+}
+
+// validation level of the CA/Browser Forum policies: https://cabforum.org/resources/object-registry/
+pub fn policy_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "2.23.140.1.1" => Some("extended validation"),
+        "2.23.140.1.2.1" => Some("domain validated"),
+        "2.23.140.1.2.2" => Some("organization validated"),
+        "2.23.140.1.2.3" => Some("individual validated"),
+        "2.5.29.32.0" => Some("any policy"),
+        _ => None,
+    }
+}
+
+// a minimal certificate with the given extensions, as (OID, value) pairs
+#[cfg(test)]
+pub(crate) fn test_certificate(extensions: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+    test_certificate_with_key(&der::encode(SEQUENCE, &[]), extensions)
+}
+
+// the same with a SubjectPublicKeyInfo
+#[cfg(test)]
+pub(crate) fn test_certificate_with_key(
+    public_key_info: &[u8],
+    extensions: &[(&[u8], Vec<u8>)],
+) -> Vec<u8> {
+    use crate::x509::der::encode;
+
+    // sha256WithRSAEncryption
+    let algorithm = encode(
+        SEQUENCE,
+        &encode(OID, &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B]),
+    );
+
+    let mut list = Vec::new();
+    for (oid, value) in extensions {
+        let mut extension = encode(OID, oid);
+        extension.extend(encode(OCTET_STRING, value));
+        list.extend(encode(SEQUENCE, &extension));
+    }
+
+    let mut tbs = encode(context(0), &encode(INTEGER, &[2]));
+    tbs.extend(encode(INTEGER, &[1]));
+    tbs.extend(&algorithm);
+    for _ in 0..3 {
+        // issuer, validity and subject, left empty
+        tbs.extend(encode(SEQUENCE, &[]));
+    }
+    tbs.extend(public_key_info);
+    if !extensions.is_empty() {
+        tbs.extend(encode(context(3), &encode(SEQUENCE, &list)));
+    }
+
+    let mut cert = encode(SEQUENCE, &tbs);
+    cert.extend(algorithm);
+    cert.extend(encode(BIT_STRING, &[0]));
+    encode(SEQUENCE, &cert)
+}
+
+// TLS Feature with status_request, and the domain validated policy
+#[cfg(test)]
+pub(crate) fn must_staple_certificate() -> Vec<u8> {
+    use crate::x509::der::{encode, INTEGER};
+
+    let features = encode(SEQUENCE, &encode(INTEGER, &[5]));
+    let policy = encode(OID, &[0x67, 0x81, 0x0C, 0x01, 0x02, 0x01]);
+    let policies = encode(SEQUENCE, &encode(SEQUENCE, &policy));
+
+    test_certificate(&[
+        (&[0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x18], features),
+        (&[0x55, 0x1D, 0x20], policies),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn must_staple_policies() {
+        let cert = must_staple_certificate();
+        let list = extensions(&cert).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].oid, TLS_FEATURE);
+        assert!(!list[0].critical);
+
+        assert_eq!(tls_features(&cert).unwrap(), Some(vec![5]));
+        assert!(must_staple(&cert).unwrap());
+
+        let policies = certificate_policies(&cert).unwrap();
+        assert_eq!(policies, vec!["2.23.140.1.2.1"]);
+        assert_eq!(policy_name(&policies[0]), Some("domain validated"));
+    }
+
+    #[test]
+    fn scts() {
+        use crate::derive_tls::TlsDerive;
+        use crate::handshake::client_hello::{SctVersion, SignatureScheme};
+        use crate::handshake::common::VariableLengthVector;
+        use crate::x509::der::encode;
+
+        let sct = SignedCertificateTimestamp {
+            version: SctVersion::v1,
+            log_id: [0xAB; 32],
+            timestamp: 1_600_000_000_000,
+            extensions: VariableLengthVector::default(),
+            algorithm: SignatureScheme::ecdsa_secp256r1_sha256,
+            signature: VariableLengthVector::from_slice(&[0x30, 0x01, 0x02]),
+        };
+        let mut list = Vec::new();
+        SignedCertificateTimestampList::new(&[sct])
+            .unwrap()
+            .to_network_bytes(&mut list)
+            .unwrap();
+
+        let oid = [0x2B, 0x06, 0x01, 0x04, 0x01, 0xD6, 0x79, 0x02, 0x04, 0x02];
+        let cert = test_certificate(&[(&oid, encode(OCTET_STRING, &list))]);
+        assert_eq!(extensions(&cert).unwrap()[0].oid, EMBEDDED_SCT_LIST);
+
+        let scts = embedded_scts(&cert).unwrap();
+        assert_eq!(scts.len(), 1);
+        assert_eq!(scts[0].log_id, [0xAB; 32]);
+        assert!(embedded_scts(&must_staple_certificate())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn no_extension() {
+        let cert = test_certificate(&[]);
+        assert!(extensions(&cert).unwrap().is_empty());
+        assert_eq!(tls_features(&cert).unwrap(), None);
+        assert!(!must_staple(&cert).unwrap());
+        assert!(certificate_policies(&cert).unwrap().is_empty());
+        assert!(subject_public_key_info(&cert).is_err());
+
+        assert!(extensions(&[0x30, 0x00]).is_err());
+        assert!(extensions(&cert[..cert.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+// DER tag-length-value reader: https://www.itu.int/rec/T-REC-X.690
+use std::io::{Error, ErrorKind, Result};
+
+pub const BOOLEAN: u8 = 0x01;
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OID: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+// constructed, context-specific tag [n]
+pub const fn context(n: u8) -> u8 {
+    0xA0 | n
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+}
+
+// reads the elements of a DER encoding one after the other
+#[derive(Debug, Clone)]
+pub struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("DER: {}", msg))
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    // only single byte tags and lengths of at most 4 bytes are supported, which is all
+    // certificates use
+    pub fn read(&mut self) -> Result<Tlv<'a>> {
+        let (&tag, rest) = self
+            .data
+            .split_first()
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        if tag & 0x1F == 0x1F {
+            return Err(invalid("multi-byte tag"));
+        }
+
+        let (&first, mut rest) = rest
+            .split_first()
+            .ok_or_else(|| invalid("missing length"))?;
+        let length = if first < 0x80 {
+            first as usize
+        } else {
+            let count = (first & 0x7F) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(invalid("invalid length"));
+            }
+            let length = rest[..count]
+                .iter()
+                .fold(0usize, |acc, x| (acc << 8) | *x as usize);
+            rest = &rest[count..];
+            length
+        };
+
+        if rest.len() < length {
+            return Err(invalid("truncated value"));
+        }
+        let (value, rest) = rest.split_at(length);
+        self.data = rest;
+        Ok(Tlv { tag, value })
+    }
+
+    // the value of the next element, which must have the given tag
+    pub fn expect(&mut self, tag: u8) -> Result<&'a [u8]> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(invalid(&format!(
+                "expected tag 0x{:02X}, found 0x{:02X}",
+                tag, tlv.tag
+            )));
+        }
+        Ok(tlv.value)
+    }
+
+    // the next element with its tag and length, as covered by signatures
+    pub fn read_encoded(&mut self) -> Result<&'a [u8]> {
+        let data = self.data;
+        self.read()?;
+        Ok(&data[..data.len() - self.data.len()])
+    }
+
+    // the value of the next element if it has the given tag, as for OPTIONAL fields
+    pub fn optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.expect(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// dotted notation of an encoded OBJECT IDENTIFIER, e.g. 2.5.29.32
+pub fn oid_to_string(value: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+
+    for byte in value {
+        arc = (arc << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                // the first byte packs the first two arcs
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+
+    arcs.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+// value of a non-negative INTEGER which fits in 64 bits
+pub fn integer(value: &[u8]) -> Result<u64> {
+    let value = match value {
+        [0, rest @ ..] => rest,
+        [first, ..] if first & 0x80 != 0 => return Err(invalid("negative integer")),
+        _ => value,
+    };
+    if value.len() > 8 {
+        return Err(invalid("integer too large"));
+    }
+    Ok(value.iter().fold(0, |acc, x| (acc << 8) | *x as u64))
+}
+
+// an element with its header, for building test certificates
+#[cfg(test)]
+pub(crate) fn encode(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut v = vec![tag];
+    match value.len() {
+        n if n < 0x80 => v.push(n as u8),
+        n if n <= 0xFF => v.extend_from_slice(&[0x81, n as u8]),
+        n => v.extend_from_slice(&[0x82, (n >> 8) as u8, n as u8]),
+    }
+    v.extend_from_slice(value);
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read() {
+        let data = [0x30, 0x06, 0x02, 0x01, 0x05, 0x04, 0x01, 0xFF, 0x01];
+        let mut reader = DerReader::new(&data);
+        let sequence = reader.expect(SEQUENCE).unwrap();
+        assert_eq!(reader.peek_tag(), Some(BOOLEAN));
+        assert!(reader.expect(OID).is_err());
+
+        let mut reader = DerReader::new(sequence);
+        assert_eq!(integer(reader.expect(INTEGER).unwrap()).unwrap(), 5);
+        assert_eq!(reader.optional(BOOLEAN).unwrap(), None);
+        assert_eq!(reader.optional(OCTET_STRING).unwrap(), Some(&[0xFF][..]));
+        assert!(reader.is_empty());
+        assert!(reader.read().is_err());
+        assert_eq!(DerReader::new(&data).read_encoded().unwrap(), &data[..8]);
+
+        // long form length
+        let long = encode(OCTET_STRING, &[0xAA; 300]);
+        assert_eq!(&long[..4], &[0x04, 0x82, 0x01, 0x2C]);
+        assert_eq!(DerReader::new(&long).read().unwrap().value.len(), 300);
+        assert!(DerReader::new(&long[..100]).read().is_err());
+    }
+
+    #[test]
+    fn oid() {
+        assert_eq!(oid_to_string(&[0x55, 0x1D, 0x20]), "2.5.29.32");
+        assert_eq!(
+            oid_to_string(&[0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x18]),
+            "1.3.6.1.5.5.7.1.24"
+        );
+        assert_eq!(
+            oid_to_string(&[0x67, 0x81, 0x0C, 0x01, 0x02, 0x01]),
+            "2.23.140.1.2.1"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+// the fields of a certificate worth showing when dumping a chain: names, validity, key and
+// signature algorithm: https://datatracker.ietf.org/doc/html/rfc5280#section-4.1
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::net::IpAddr;
+
+use crate::crypto::rsa::RsaPublicKey;
+use crate::x509::certificate::{extensions, subject_public_key_info, RSA_ENCRYPTION};
+use crate::x509::der::{self, context, DerReader, INTEGER, OID, SEQUENCE, SET};
+
+pub const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+
+// https://datatracker.ietf.org/doc/html/rfc5480#section-2.1.1 and
+// https://datatracker.ietf.org/doc/html/rfc8410#section-3
+const EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const RSASSA_PSS: &str = "1.2.840.113549.1.1.10";
+const ED25519: &str = "1.3.101.112";
+const ED448: &str = "1.3.101.113";
+
+// UTCTime and GeneralizedTime
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("X.509: {}", msg))
+}
+
+// short names of the usual attributes, as printed by OpenSSL
+fn attribute_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "2.5.4.3" => Some("CN"),
+        "2.5.4.5" => Some("serialNumber"),
+        "2.5.4.6" => Some("C"),
+        "2.5.4.7" => Some("L"),
+        "2.5.4.8" => Some("ST"),
+        "2.5.4.10" => Some("O"),
+        "2.5.4.11" => Some("OU"),
+        "1.2.840.113549.1.9.1" => Some("emailAddress"),
+        _ => None,
+    }
+}
+
+// the algorithms CAs sign with: https://datatracker.ietf.org/doc/html/rfc4055,
+// https://datatracker.ietf.org/doc/html/rfc5758#section-3.2 and
+// https://datatracker.ietf.org/doc/html/rfc8410#section-3
+pub fn signature_algorithm_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "1.2.840.113549.1.1.4" => Some("md5WithRSAEncryption"),
+        "1.2.840.113549.1.1.5" => Some("sha1WithRSAEncryption"),
+        "1.2.840.113549.1.1.10" => Some("RSASSA-PSS"),
+        "1.2.840.113549.1.1.11" => Some("sha256WithRSAEncryption"),
+        "1.2.840.113549.1.1.12" => Some("sha384WithRSAEncryption"),
+        "1.2.840.113549.1.1.13" => Some("sha512WithRSAEncryption"),
+        "1.2.840.10045.4.1" => Some("ecdsa-with-SHA1"),
+        "1.2.840.10045.4.3.2" => Some("ecdsa-with-SHA256"),
+        "1.2.840.10045.4.3.3" => Some("ecdsa-with-SHA384"),
+        "1.2.840.10045.4.3.4" => Some("ecdsa-with-SHA512"),
+        ED25519 => Some("Ed25519"),
+        ED448 => Some("Ed448"),
+        _ => None,
+    }
+}
+
+// the named curves of EC keys, with their size
+fn curve(oid: &str) -> Option<(&'static str, usize)> {
+    match oid {
+        "1.2.840.10045.3.1.7" => Some(("secp256r1", 256)),
+        "1.3.132.0.34" => Some(("secp384r1", 384)),
+        "1.3.132.0.35" => Some(("secp521r1", 521)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyType {
+    Rsa,
+    RsaPss,
+
+    // the name of the curve, or its OID if unknown
+    Ec(String),
+    Ed25519,
+    Ed448,
+
+    // the OID of the algorithm
+    Unknown(String),
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyType::Rsa => write!(f, "RSA"),
+            KeyType::RsaPss => write!(f, "RSASSA-PSS"),
+            KeyType::Ec(curve) => write!(f, "EC {}", curve),
+            KeyType::Ed25519 => write!(f, "Ed25519"),
+            KeyType::Ed448 => write!(f, "Ed448"),
+            KeyType::Unknown(oid) => write!(f, "{}", oid),
+        }
+    }
+}
+
+// the entries of subjectAltName which can be shown, the others are kept as their tag:
+// https://datatracker.ietf.org/doc/html/rfc5280#section-4.2.1.6
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneralName {
+    Email(String),
+    Dns(String),
+    Uri(String),
+    Ip(IpAddr),
+    Other(u8),
+}
+
+impl fmt::Display for GeneralName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneralName::Email(x) => write!(f, "email:{}", x),
+            GeneralName::Dns(x) => write!(f, "DNS:{}", x),
+            GeneralName::Uri(x) => write!(f, "URI:{}", x),
+            GeneralName::Ip(x) => write!(f, "IP:{}", x),
+            GeneralName::Other(tag) => write!(f, "other:0x{:02X}", tag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateInfo {
+    // big-endian, as encoded
+    pub serial_number: Vec<u8>,
+
+    // the attributes in their order, e.g. "C=FR, O=Explore, CN=example.com"
+    pub subject: String,
+    pub issuer: String,
+
+    // seconds since the Unix epoch
+    pub not_before: i64,
+    pub not_after: i64,
+
+    pub subject_alt_names: Vec<GeneralName>,
+    pub key_type: KeyType,
+
+    // 0 when the key type is unknown
+    pub key_bits: usize,
+
+    // OID of the algorithm the issuer signed with
+    pub signature_algorithm: String,
+}
+
+impl CertificateInfo {
+    pub fn is_valid_at(&self, unix_time: i64) -> bool {
+        self.not_before <= unix_time && unix_time <= self.not_after
+    }
+
+    // the name of the signature algorithm, or its OID
+    pub fn signature_algorithm_name(&self) -> &str {
+        signature_algorithm_name(&self.signature_algorithm).unwrap_or(&self.signature_algorithm)
+    }
+}
+
+impl fmt::Display for CertificateInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "subject: {}", self.subject)?;
+        writeln!(f, "issuer: {}", self.issuer)?;
+        writeln!(
+            f,
+            "validity: {} to {}",
+            time_string(self.not_before),
+            time_string(self.not_after)
+        )?;
+        if !self.subject_alt_names.is_empty() {
+            let names: Vec<_> = self
+                .subject_alt_names
+                .iter()
+                .map(|x| x.to_string())
+                .collect();
+            writeln!(f, "alt names: {}", names.join(", "))?;
+        }
+        writeln!(f, "key: {} {} bits", self.key_type, self.key_bits)?;
+        write!(f, "signature: {}", self.signature_algorithm_name())
+    }
+}
+
+// the attributes of a Name, the first value of multi-valued RDNs only
+fn name(value: &[u8]) -> Result<String> {
+    let mut rdns = DerReader::new(value);
+    let mut attributes = Vec::new();
+    while !rdns.is_empty() {
+        let mut set = DerReader::new(rdns.expect(SET)?);
+        let mut attribute = DerReader::new(set.expect(SEQUENCE)?);
+        let oid = der::oid_to_string(attribute.expect(OID)?);
+        let value = attribute.read()?;
+
+        let text = match value.tag {
+            // BMPString
+            0x1E => {
+                let units: Vec<u16> = value
+                    .value
+                    .chunks(2)
+                    .map(|x| u16::from_be_bytes([x[0], *x.get(1).unwrap_or(&0)]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            // UTF8String, PrintableString, T61String, IA5String and the like
+            _ => String::from_utf8_lossy(value.value).into_owned(),
+        };
+        let key = attribute_name(&oid).map_or(oid.clone(), String::from);
+        attributes.push(format!("{}={}", key, text));
+    }
+    Ok(attributes.join(", "))
+}
+
+// days since the Unix epoch of a date: http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// RFC 3339 representation of a time in seconds since the Unix epoch
+pub fn time_string(unix_time: i64) -> String {
+    let (year, month, day) = civil_from_days(unix_time.div_euclid(86400));
+    let seconds = unix_time.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// UTCTime with a 2-digit year, or GeneralizedTime, both in UTC with seconds:
+// https://datatracker.ietf.org/doc/html/rfc5280#section-4.1.2.5
+fn time(tag: u8, value: &[u8]) -> Result<i64> {
+    let digits = match (tag, value.len()) {
+        (UTC_TIME, 13) | (GENERALIZED_TIME, 15) => &value[..value.len() - 1],
+        _ => return Err(invalid("invalid time")),
+    };
+    if value.last() != Some(&b'Z') {
+        return Err(invalid("time not in UTC"));
+    }
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return Err(invalid("invalid time"));
+    }
+
+    // pairs of digits, from the end of the year
+    let pair = |i: usize| i64::from(digits[i] - b'0') * 10 + i64::from(digits[i + 1] - b'0');
+    let (year, rest) = match tag {
+        UTC_TIME => {
+            let year = pair(0);
+            (if year >= 50 { 1900 + year } else { 2000 + year }, 2)
+        }
+        _ => (pair(0) * 100 + pair(2), 4),
+    };
+    let (month, day, hour, minute, second) = (
+        pair(rest),
+        pair(rest + 2),
+        pair(rest + 4),
+        pair(rest + 6),
+        pair(rest + 8),
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid("invalid time"));
+    }
+    Ok(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+fn general_names(value: &[u8]) -> Result<Vec<GeneralName>> {
+    let mut list = DerReader::new(DerReader::new(value).expect(SEQUENCE)?);
+    let mut names = Vec::new();
+    while !list.is_empty() {
+        let entry = list.read()?;
+        let text = || String::from_utf8_lossy(entry.value).into_owned();
+        names.push(match entry.tag {
+            0x81 => GeneralName::Email(text()),
+            0x82 => GeneralName::Dns(text()),
+            0x86 => GeneralName::Uri(text()),
+            0x87 => match entry.value.len() {
+                // the lengths are checked
+                4 => GeneralName::Ip(IpAddr::from(<[u8; 4]>::try_from(entry.value).unwrap())),
+                16 => GeneralName::Ip(IpAddr::from(<[u8; 16]>::try_from(entry.value).unwrap())),
+                _ => return Err(invalid("invalid IP address")),
+            },
+            tag => GeneralName::Other(tag),
+        });
+    }
+    Ok(names)
+}
+
+// the type and size of the subject's key
+fn key(cert: &[u8]) -> Result<(KeyType, usize)> {
+    let info = subject_public_key_info(cert)?;
+    Ok(match info.algorithm.as_str() {
+        RSA_ENCRYPTION | RSASSA_PSS => {
+            let bits = RsaPublicKey::from_der(info.public_key)?.bits();
+            if info.algorithm == RSA_ENCRYPTION {
+                (KeyType::Rsa, bits)
+            } else {
+                (KeyType::RsaPss, bits)
+            }
+        }
+        EC_PUBLIC_KEY => {
+            let oid = info.parameters.unwrap_or_default();
+            match curve(&oid) {
+                Some((name, bits)) => (KeyType::Ec(name.to_string()), bits),
+                None => (KeyType::Ec(oid), 0),
+            }
+        }
+        ED25519 => (KeyType::Ed25519, 256),
+        ED448 => (KeyType::Ed448, 456),
+        _ => (KeyType::Unknown(info.algorithm), 0),
+    })
+}
+
+// the fields of a DER-encoded certificate
+pub fn inspect(cert: &[u8]) -> Result<CertificateInfo> {
+    let mut certificate = DerReader::new(DerReader::new(cert).expect(SEQUENCE)?);
+    let mut tbs = DerReader::new(certificate.expect(SEQUENCE)?);
+    let mut algorithm = DerReader::new(certificate.expect(SEQUENCE)?);
+    let signature_algorithm = der::oid_to_string(algorithm.expect(OID)?);
+
+    // version, serial number, then the signature algorithm again
+    tbs.optional(context(0))?;
+    let serial_number = tbs.expect(INTEGER)?.to_vec();
+    tbs.expect(SEQUENCE)?;
+    let issuer = name(tbs.expect(SEQUENCE)?)?;
+
+    let mut validity = DerReader::new(tbs.expect(SEQUENCE)?);
+    let not_before = validity.read()?;
+    let not_after = validity.read()?;
+    let subject = name(tbs.expect(SEQUENCE)?)?;
+
+    let subject_alt_names = match extensions(cert)?
+        .into_iter()
+        .find(|x| x.oid == SUBJECT_ALT_NAME)
+    {
+        Some(extension) => general_names(extension.value)?,
+        None => Vec::new(),
+    };
+    let (key_type, key_bits) = key(cert)?;
+
+    Ok(CertificateInfo {
+        serial_number,
+        subject,
+        issuer,
+        not_before: time(not_before.tag, not_before.value)?,
+        not_after: time(not_after.tag, not_after.value)?,
+        subject_alt_names,
+        key_type,
+        key_bits,
+        signature_algorithm,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::bignum::from_hex;
+
+    // self-signed by Python's cryptography package, with a P-256 key
+    const CERTIFICATE: [&str; 9] = [
+        "3082017d30820123a00302010202021234300a06082a8648ce3d04030230153113301106035504030c0a4578706c6f72",
+        "652043413020170d3234303130323033303430355a180f32303531303630373038303931305a3035310b300906035504",
+        "06130246523110300e060355040a0c074578706c6f72653114301206035504030c0b6578616d706c652e636f6d305930",
+        "1306072a8648ce3d020106082a8648ce3d030107034200049f5ebdec6eccbc30fdd57668777c4690fb37482076789535",
+        "4b8f64379d93483cc1ad950f2db387821481cb3f95a1b258f1f6b5623fa2db513df01ba68bcb7e0ba341303f303d0603",
+        "551d1104363034820b6578616d706c652e636f6d820d2a2e6578616d706c652e636f6d8704c0000201871020010db800",
+        "0000000000000000000001300a06082a8648ce3d040302034800304502205c65e8cd3be282cdc99877ea368a2470c8d0",
+        "5ee716b7648c63ac29ec6e825c8f022100d3c249af3951b814854423886b004e9b821c353e3c8aed93d0c911638daef2",
+        "74",
+    ];
+
+    #[test]
+    fn ec_certificate() {
+        let cert = from_hex(&CERTIFICATE);
+        let info = inspect(&cert).unwrap();
+
+        assert_eq!(info.serial_number, vec![0x12, 0x34]);
+        assert_eq!(info.subject, "C=FR, O=Explore, CN=example.com");
+        assert_eq!(info.issuer, "CN=Explore CA");
+        assert_eq!(info.not_before, 1704164645);
+        assert_eq!(info.not_after, 2569738150);
+        assert!(info.is_valid_at(1704164645));
+        assert!(!info.is_valid_at(1704164644));
+        assert_eq!(
+            info.subject_alt_names,
+            vec![
+                GeneralName::Dns("example.com".to_string()),
+                GeneralName::Dns("*.example.com".to_string()),
+                GeneralName::Ip("192.0.2.1".parse().unwrap()),
+                GeneralName::Ip("2001:db8::1".parse().unwrap()),
+            ]
+        );
+        assert_eq!(info.key_type, KeyType::Ec("secp256r1".to_string()));
+        assert_eq!(info.key_bits, 256);
+        assert_eq!(info.signature_algorithm_name(), "ecdsa-with-SHA256");
+
+        assert_eq!(
+            info.to_string(),
+            "subject: C=FR, O=Explore, CN=example.com\n\
+             issuer: CN=Explore CA\n\
+             validity: 2024-01-02T03:04:05Z to 2051-06-07T08:09:10Z\n\
+             alt names: DNS:example.com, DNS:*.example.com, IP:192.0.2.1, IP:2001:db8::1\n\
+             key: EC secp256r1 256 bits\n\
+             signature: ecdsa-with-SHA256"
+        );
+
+        assert!(inspect(&cert[..cert.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rsa_certificate() {
+        use crate::crypto::rsa::tests::{certificate, signing_key};
+
+        // empty names and validity are not valid times
+        let cert = certificate(&signing_key());
+        assert!(inspect(&cert).is_err());
+        assert_eq!(key(&cert).unwrap(), (KeyType::Rsa, 1024));
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(time(UTC_TIME, b"700101000000Z").unwrap(), 0);
+        assert_eq!(time(UTC_TIME, b"491231235959Z").unwrap(), 2524607999);
+        assert_eq!(time(GENERALIZED_TIME, b"19691231235959Z").unwrap(), -1);
+        assert_eq!(time_string(-1), "1969-12-31T23:59:59Z");
+        assert_eq!(time_string(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(
+            time(GENERALIZED_TIME, b"20000229000000Z").unwrap(),
+            951782400
+        );
+
+        for (tag, value) in [
+            (UTC_TIME, &b"7001010000Z"[..]),
+            (UTC_TIME, b"701301000000Z"),
+            (UTC_TIME, b"700101000000+"),
+            (GENERALIZED_TIME, b"700101000000Z"),
+            (UTC_TIME, "2\u{e9}010100000Z".as_bytes()),
+            (UTC_TIME, b"70010100000 Z"),
+            (UTC_TIME, b"+70101000000Z"),
+        ] {
+            assert!(time(tag, value).is_err());
+        }
+    }
+}
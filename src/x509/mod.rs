@@ -0,0 +1,8 @@
+// just enough DER and X.509 parsing to inspect the certificates sent by servers. Certificates
+// are only validated with the verify feature
+pub mod cache;
+pub mod certificate;
+pub mod der;
+pub mod inspect;
+#[cfg(feature = "verify")]
+pub mod verify;
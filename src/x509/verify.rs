@@ -0,0 +1,593 @@
+// validation of the chain sent by a server against a trust store: chain building from the
+// leaf to a trusted root, validity dates, the host name and the signatures of the issuers:
+// https://datatracker.ietf.org/doc/html/rfc5280#section-6. Only RSA PKCS #1 v1.5 signatures
+// with SHA-256 and SHA-384 are checked, a chain signed otherwise isn't trusted
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::net::IpAddr;
+
+use crate::crypto::rsa::RsaPublicKey;
+use crate::dissector::encoding::{Encoding, TextDecoder};
+use crate::handshake::client_hello::SignatureScheme;
+use crate::x509::certificate::extensions;
+use crate::x509::der::{self, context, DerReader, BIT_STRING, BOOLEAN, OID, SEQUENCE};
+use crate::x509::inspect::{inspect, time_string, CertificateInfo, GeneralName};
+
+const BASIC_CONSTRAINTS: &str = "2.5.29.19";
+
+// certificates between the leaf and the root, at most
+const MAX_DEPTH: usize = 8;
+
+// the signature algorithm of a certificate, as a TLS scheme
+fn signature_scheme(oid: &str) -> Option<SignatureScheme> {
+    match oid {
+        "1.2.840.113549.1.1.11" => Some(SignatureScheme::rsa_pkcs1_sha256),
+        "1.2.840.113549.1.1.12" => Some(SignatureScheme::rsa_pkcs1_sha384),
+        _ => None,
+    }
+}
+
+// why a chain isn't trusted
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainError {
+    Empty,
+
+    // the position of the certificate in the chain, and the parsing error
+    Malformed(usize, String),
+    NotYetValid { subject: String, not_before: i64 },
+    Expired { subject: String, not_after: i64 },
+
+    // the host the leaf doesn't name
+    NameMismatch(String),
+
+    // the issuer neither sent nor trusted
+    UnknownIssuer(String),
+
+    // an issuer without the cA flag of basicConstraints
+    NotCa(String),
+
+    // the subject of the certificate whose signature is wrong
+    BadSignature(String),
+    UnsupportedSignature { subject: String, algorithm: String },
+    TooLong,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainError::Empty => write!(f, "no certificate"),
+            ChainError::Malformed(index, e) => {
+                write!(f, "certificate {} is malformed: {}", index, e)
+            }
+            ChainError::NotYetValid {
+                subject,
+                not_before,
+            } => write!(
+                f,
+                "{} is not valid before {}",
+                subject,
+                time_string(*not_before)
+            ),
+            ChainError::Expired { subject, not_after } => {
+                write!(f, "{} expired on {}", subject, time_string(*not_after))
+            }
+            ChainError::NameMismatch(host) => {
+                write!(f, "no name of the certificate matches {}", host)
+            }
+            ChainError::UnknownIssuer(issuer) => write!(f, "unknown issuer {}", issuer),
+            ChainError::NotCa(subject) => write!(f, "{} is not a CA", subject),
+            ChainError::BadSignature(subject) => write!(f, "wrong signature of {}", subject),
+            ChainError::UnsupportedSignature { subject, algorithm } => {
+                write!(
+                    f,
+                    "{} is signed with {}, which isn't checked",
+                    subject, algorithm
+                )
+            }
+            ChainError::TooLong => write!(f, "more than {} certificates to a root", MAX_DEPTH),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+// the parts of a certificate the validation needs
+#[derive(Debug)]
+struct Parsed<'a> {
+    der: &'a [u8],
+
+    // the TBSCertificate with its header, as signed
+    tbs: &'a [u8],
+    signature_algorithm: String,
+    signature: &'a [u8],
+
+    // DER encodings of the names, compared as is
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    info: CertificateInfo,
+    ca: bool,
+}
+
+impl<'a> Parsed<'a> {
+    fn new(der: &'a [u8]) -> Result<Self> {
+        let mut certificate = DerReader::new(DerReader::new(der).expect(SEQUENCE)?);
+        let tbs = certificate.read_encoded()?;
+        let mut algorithm = DerReader::new(certificate.expect(SEQUENCE)?);
+        let signature_algorithm = der::oid_to_string(algorithm.expect(OID)?);
+        let signature = match certificate.expect(BIT_STRING)? {
+            [0, signature @ ..] => signature,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "DER: signature with unused bits",
+                ))
+            }
+        };
+
+        // version, serial number and signature, then issuer, validity and subject
+        let mut fields = DerReader::new(DerReader::new(tbs).expect(SEQUENCE)?);
+        fields.optional(context(0))?;
+        fields.read()?;
+        fields.expect(SEQUENCE)?;
+        let issuer = fields.expect(SEQUENCE)?;
+        fields.expect(SEQUENCE)?;
+        let subject = fields.expect(SEQUENCE)?;
+
+        // cA is a BOOLEAN defaulting to false: https://datatracker.ietf.org/doc/html/rfc5280#section-4.2.1.9
+        let ca = match extensions(der)?
+            .into_iter()
+            .find(|x| x.oid == BASIC_CONSTRAINTS)
+        {
+            Some(extension) => DerReader::new(DerReader::new(extension.value).expect(SEQUENCE)?)
+                .optional(BOOLEAN)?
+                .is_some_and(|x| x != [0]),
+            None => false,
+        };
+
+        Ok(Self {
+            der,
+            tbs,
+            signature_algorithm,
+            signature,
+            issuer,
+            subject,
+            info: inspect(der)?,
+            ca,
+        })
+    }
+
+    fn check_dates(&self, unix_time: i64) -> std::result::Result<(), ChainError> {
+        let subject = self.info.subject.clone();
+        if unix_time < self.info.not_before {
+            return Err(ChainError::NotYetValid {
+                subject,
+                not_before: self.info.not_before,
+            });
+        }
+        if unix_time > self.info.not_after {
+            return Err(ChainError::Expired {
+                subject,
+                not_after: self.info.not_after,
+            });
+        }
+        Ok(())
+    }
+
+    // the signature of the certificate with the key of its issuer
+    fn check_signature(&self, issuer: &Parsed) -> std::result::Result<(), ChainError> {
+        if !issuer.ca {
+            return Err(ChainError::NotCa(issuer.info.subject.clone()));
+        }
+
+        let unsupported = || ChainError::UnsupportedSignature {
+            subject: self.info.subject.clone(),
+            algorithm: self.info.signature_algorithm_name().to_string(),
+        };
+        let scheme = signature_scheme(&self.signature_algorithm).ok_or_else(unsupported)?;
+        let key = RsaPublicKey::from_certificate(issuer.der).map_err(|_| unsupported())?;
+        key.verify(scheme, self.tbs, self.signature)
+            .map_err(|_| ChainError::BadSignature(self.info.subject.clone()))
+    }
+}
+
+// a DNS name of the certificate, which may start with a wildcard matching a single label:
+// https://datatracker.ietf.org/doc/html/rfc6125#section-6.4.3
+fn matches_dns_name(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == domain),
+        None => pattern == host,
+    }
+}
+
+// names are only taken from subjectAltName, as browsers do
+fn matches_host(info: &CertificateInfo, host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok();
+
+    info.subject_alt_names.iter().any(|name| match (name, ip) {
+        (GeneralName::Ip(address), Some(ip)) => *address == ip,
+        (GeneralName::Dns(pattern), None) => matches_dns_name(pattern, &host),
+        _ => false,
+    })
+}
+
+// DER-encoded root certificates
+#[derive(Debug, Default, Clone)]
+pub struct TrustStore {
+    roots: Vec<Vec<u8>>,
+}
+
+impl TrustStore {
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    // fails if the certificate can't be parsed
+    pub fn add(&mut self, der: &[u8]) -> Result<()> {
+        Parsed::new(der)?;
+        self.roots.push(der.to_vec());
+        Ok(())
+    }
+
+    // the CERTIFICATE blocks of a PEM file, like the bundles of operating systems:
+    // https://datatracker.ietf.org/doc/html/rfc7468#section-5
+    pub fn from_pem(text: &str) -> Result<Self> {
+        let mut store = Self::default();
+        let mut block: Option<String> = None;
+
+        for line in text.lines().map(str::trim) {
+            match (line, &mut block) {
+                ("-----BEGIN CERTIFICATE-----", None) => block = Some(String::new()),
+                ("-----END CERTIFICATE-----", Some(base64)) => {
+                    let mut der = Vec::new();
+                    TextDecoder::new(base64.as_bytes(), Encoding::Base64).read_to_end(&mut der)?;
+                    store.add(&der)?;
+                    block = None;
+                }
+                (_, Some(base64)) => base64.push_str(line),
+                _ => (),
+            }
+        }
+
+        if block.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "PEM: unterminated certificate",
+            ));
+        }
+        Ok(store)
+    }
+
+    // Ok if the leaf names the host, and leads to a root of the store through the other
+    // certificates sent, all valid at the given time. The first failure is given
+    pub fn verify(
+        &self,
+        chain: &[&[u8]],
+        host: &str,
+        unix_time: i64,
+    ) -> std::result::Result<(), ChainError> {
+        self.verify_with(chain, &Intermediates::default(), host, unix_time)
+    }
+
+    // the same, an issuer missing from the chain being looked for in those of other servers
+    pub fn verify_with(
+        &self,
+        chain: &[&[u8]],
+        intermediates: &Intermediates,
+        host: &str,
+        unix_time: i64,
+    ) -> std::result::Result<(), ChainError> {
+        let sent = chain
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Parsed::new(x).map_err(|e| ChainError::Malformed(i, e.to_string())))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // the roots were parsed when added
+        let roots: Vec<_> = self
+            .roots
+            .iter()
+            .filter_map(|x| Parsed::new(x).ok())
+            .collect();
+        let cached: Vec<_> = intermediates
+            .certificates
+            .iter()
+            .filter_map(|x| Parsed::new(x).ok())
+            .collect();
+
+        let mut current = sent.first().ok_or(ChainError::Empty)?;
+        if !matches_host(&current.info, host) {
+            return Err(ChainError::NameMismatch(host.to_string()));
+        }
+
+        for _ in 0..MAX_DEPTH {
+            current.check_dates(unix_time)?;
+            if roots.iter().any(|x| x.der == current.der) {
+                return Ok(());
+            }
+
+            // a root signing the certificate ends the chain, otherwise another certificate
+            // sent, or seen before, goes on with it
+            let mut first_error = None;
+            let candidates = roots
+                .iter()
+                .map(|x| (x, true))
+                .chain(sent.iter().chain(&cached).map(|x| (x, false)))
+                .filter(|(x, _)| x.subject == current.issuer && x.der != current.der);
+            let mut next = None;
+            for (issuer, root) in candidates {
+                match current.check_signature(issuer) {
+                    Ok(()) if root => return Ok(()),
+                    Ok(()) => {
+                        next = Some(issuer);
+                        break;
+                    }
+                    Err(e) => {
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+
+            current = match (next, first_error) {
+                (Some(issuer), _) => issuer,
+                (None, Some(e)) => return Err(e),
+                (None, None) => return Err(ChainError::UnknownIssuer(current.info.issuer.clone())),
+            };
+        }
+
+        Err(ChainError::TooLong)
+    }
+}
+
+// the CA certificates sent by the servers of a scan, completing the chains of those which
+// don't send their intermediates, as browsers do. Issuers shared by many hosts are kept once
+#[derive(Debug, Default, Clone)]
+pub struct Intermediates {
+    certificates: Vec<Vec<u8>>,
+}
+
+impl Intermediates {
+    pub fn len(&self) -> usize {
+        self.certificates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.certificates.is_empty()
+    }
+
+    // the certificates after the leaf which are CAs, malformed ones being ignored
+    pub fn add_chain(&mut self, chain: &[&[u8]]) {
+        for der in chain.iter().skip(1) {
+            let ca = Parsed::new(der).is_ok_and(|x| x.ca);
+            if ca && !self.certificates.iter().any(|x| x == der) {
+                self.certificates.push(der.to_vec());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::bignum::from_hex;
+
+    // made by Python's cryptography package with 1024-bit RSA keys, valid from 2024-01-01 to
+    // 2034-01-01. The leaf names www.example.com, *.example.org and 192.0.2.1, and issued
+    // the last one
+    const ROOT: [&str; 10] = [
+        "308201b730820120a003020102020101300d06092a864886f70d01010b050030173115301306035504030c0c4578706c",
+        "6f726520526f6f74301e170d3234303130313030303030305a170d3334303130313030303030305a3017311530130603",
+        "5504030c0c4578706c6f726520526f6f7430819f300d06092a864886f70d010101050003818d0030818902818100d864",
+        "9b033f5a8b01263812be790b5ed278c76fa3e370e467beec4a573ca0cd016eb6ecffbc5a8f0ac9964435271051d47d0e",
+        "12a3587e6aaeacc4aa9ea0b2347363ecc65792aa3305a203ee6ba2dff36b2fa272f72e1e07ae3b7e28c0e1a204255878",
+        "96590cf80e626acaa4125f32331a07147b479da8fd3ead71b88e51ce1bfb0203010001a3133011300f0603551d130101",
+        "ff040530030101ff300d06092a864886f70d01010b050003818100c1f3eb622e0e30310cbf4d94b2207b09d56131ce5b",
+        "595920e854403434eee6853984ebc46a3e150ce52909e0e918a3879511af9fad801bae478d7bd04aed7927084d352e54",
+        "45119dc67bb85e9cb927725e27742cea894b09ffb4578583bb4cadc391b1af98ddfbce6f1f4aaba03d46a2f3b7b9ebe5",
+        "6d6df329166b79000e7324",
+    ];
+
+    const INTERMEDIATE: [&str; 10] = [
+        "308201bf30820128a003020102020102300d06092a864886f70d01010b050030173115301306035504030c0c4578706c",
+        "6f726520526f6f74301e170d3234303130313030303030305a170d3334303130313030303030305a301f311d301b0603",
+        "5504030c144578706c6f726520496e7465726d65646961746530819f300d06092a864886f70d010101050003818d0030",
+        "818902818100beb9309823bd954af36a2223e92be53ea4ebf07272a085003a808006f066ff3462f1ed21178092099230",
+        "527aed44849ea38837f440ca70c4294790a7cab1636c8410de75cfe8679ac20efdfbdec901a63330e31c30a27b9e9a3b",
+        "533f7a983f7c2385e067597be0b0e338628d5978d277acc85cd9532bdca0ec889a66cd5c9a290203010001a313301130",
+        "0f0603551d130101ff040530030101ff300d06092a864886f70d01010b050003818100673f48f5bfe3266c335bc0ed70",
+        "1445a284cc36a24e4250a9bf467d5c7ffbb19961d7cbb92e1310fbdc783bd033d019c325a9781cc020c46d4afb244e7b",
+        "b2559be9740ccecb89ff7069c9a76cfe1db1dfe817c5eeee30141e4d49305fb6e2166e2f4ed3eadaecbdb0b52def775c",
+        "af62f95465aab2a950599d2d9aae7f6349c27b",
+    ];
+
+    const LEAF: [&str; 11] = [
+        "308201f030820159a003020102020103300d06092a864886f70d01010b0500301f311d301b06035504030c144578706c",
+        "6f726520496e7465726d656469617465301e170d3234303130313030303030305a170d3334303130313030303030305a",
+        "301a3118301606035504030c0f7777772e6578616d706c652e636f6d30819f300d06092a864886f70d01010105000381",
+        "8d0030818902818100d35fe4b85fc2b093b152311acd79e104684fcaa8ebd1e079eb5db2ba1a0969de7581d89a68201c",
+        "cd7bc151a3c4f9a3dfdd359eeb4a2be8d635fb85286f8c1524305ae08eb9e2c8867f45eefe8fe86b83efd46764099cb9",
+        "11a681c823a6fcca926f98af21a0f811dae4c4c486d282721e62065f617dbcf21d8860b96d50a9f6490203010001a341",
+        "303f300c0603551d130101ff04023000302f0603551d1104283026820f7777772e6578616d706c652e636f6d820d2a2e",
+        "6578616d706c652e6f72678704c0000201300d06092a864886f70d01010b05000381810056dcc55a84fc79a3bc98784d",
+        "00e1634dad66e9b0bfea6656f11f52fa1fecef8716b1bf3bccdd9b911c9f732697c35d884b178af2ffc28d3890dc82d2",
+        "c5a245fbb7217ad5e52b652ac7622e411d5bac33e30435ed77a4e379ce6b12ef9614673f7a61115de875f02c4c1a964e",
+        "91ba09e751a8694a5890ee93fcf389471b98f1f4",
+    ];
+
+    const ISSUED_BY_LEAF: [&str; 10] = [
+        "308201cc30820135a003020102020104300d06092a864886f70d01010b0500301a3118301606035504030c0f7777772e",
+        "6578616d706c652e636f6d301e170d3234303130313030303030305a170d3334303130313030303030305a301c311a30",
+        "1806035504030c116f746865722e6578616d706c652e636f6d30819f300d06092a864886f70d010101050003818d0030",
+        "818902818100c621db9b3e2e412235d97c785883150dd010ec8360ac45ae91bcc95285bda0067e9d90b31232782bdc33",
+        "e499173c25cdaa7ad14b12b86cab3b5b0063f7a423590438ab56b5fa61e89c88f247eea8c6df9731665555847e153cd6",
+        "43e5896d2aeb6224c3cedfb1dfa41bb0d3c993f3e9ee50981f5a1f1dd5127485950d9957c6030203010001a320301e30",
+        "1c0603551d110415301382116f746865722e6578616d706c652e636f6d300d06092a864886f70d01010b050003818100",
+        "89fd3da3756eafffab499ae78f659d6ac07172f8bdcf7da0d2ad20ef6db8871efe3f03629f2e58add7041d5f447c0a8b",
+        "bcea66462545e49c2aa038be835ef70f05164a7b93dfcfbc96424131b28677f8abf0b6ae2716a7099dd2e6b78d290ffa",
+        "2a8bb6af2f5b38347e204bc3bdba0d0909961107650ca39e3001e1f492237603",
+    ];
+
+    // 2025-01-01
+    const NOW: i64 = 1_735_689_600;
+
+    fn store() -> TrustStore {
+        let mut store = TrustStore::default();
+        store.add(&from_hex(&ROOT)).unwrap();
+        store
+    }
+
+    #[test]
+    fn trusted() {
+        let (leaf, intermediate) = (from_hex(&LEAF), from_hex(&INTERMEDIATE));
+        let chain = [&leaf[..], &intermediate];
+
+        for host in [
+            "www.example.com",
+            "WWW.example.com.",
+            "a.example.org",
+            "192.0.2.1",
+        ] {
+            assert_eq!(store().verify(&chain, host, NOW), Ok(()), "{}", host);
+        }
+
+        // with the root sent, or the intermediate trusted
+        let root = from_hex(&ROOT);
+        assert_eq!(
+            store().verify(&[&leaf, &intermediate, &root], "www.example.com", NOW),
+            Ok(())
+        );
+        let mut store = TrustStore::default();
+        store.add(&intermediate).unwrap();
+        assert_eq!(store.verify(&[&leaf], "www.example.com", NOW), Ok(()));
+    }
+
+    #[test]
+    fn not_trusted() {
+        let (leaf, intermediate) = (from_hex(&LEAF), from_hex(&INTERMEDIATE));
+        let chain = [&leaf[..], &intermediate];
+
+        for host in ["example.com", "a.b.example.org", "example.org", "192.0.2.2"] {
+            assert_eq!(
+                store().verify(&chain, host, NOW),
+                Err(ChainError::NameMismatch(host.to_string()))
+            );
+        }
+        assert!(matches!(
+            store().verify(&chain, "www.example.com", NOW + 10 * 365 * 86400),
+            Err(ChainError::Expired { subject, .. }) if subject == "CN=www.example.com"
+        ));
+        assert!(matches!(
+            store().verify(&chain, "www.example.com", 0),
+            Err(ChainError::NotYetValid { .. })
+        ));
+
+        // the intermediate not sent, or the root not trusted
+        assert_eq!(
+            store().verify(&[&leaf], "www.example.com", NOW),
+            Err(ChainError::UnknownIssuer(String::from(
+                "CN=Explore Intermediate"
+            )))
+        );
+        assert_eq!(
+            TrustStore::default().verify(&chain, "www.example.com", NOW),
+            Err(ChainError::UnknownIssuer(String::from("CN=Explore Root")))
+        );
+
+        // the last byte of the signature changed
+        let mut changed = leaf.clone();
+        *changed.last_mut().unwrap() ^= 0x01;
+        assert_eq!(
+            store().verify(&[&changed, &intermediate], "www.example.com", NOW),
+            Err(ChainError::BadSignature(String::from("CN=www.example.com")))
+        );
+
+        // sha512WithRSAEncryption in place of sha256WithRSAEncryption, after the TBSCertificate
+        let mut sha512 = leaf.clone();
+        let oid = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B];
+        let position = sha512.windows(oid.len()).rposition(|x| x == oid).unwrap();
+        sha512[position + oid.len() - 1] = 0x0D;
+        assert_eq!(
+            store().verify(&[&sha512, &intermediate], "www.example.com", NOW),
+            Err(ChainError::UnsupportedSignature {
+                subject: String::from("CN=www.example.com"),
+                algorithm: String::from("sha512WithRSAEncryption"),
+            })
+        );
+
+        // issued by the leaf, which isn't a CA
+        let other = from_hex(&ISSUED_BY_LEAF);
+        assert_eq!(
+            store().verify(&[&other, &leaf, &intermediate], "other.example.com", NOW),
+            Err(ChainError::NotCa(String::from("CN=www.example.com")))
+        );
+
+        assert_eq!(
+            store().verify(&[], "www.example.com", NOW),
+            Err(ChainError::Empty)
+        );
+        assert!(matches!(
+            store().verify(&[&leaf, &[0x30, 0x00]], "www.example.com", NOW),
+            Err(ChainError::Malformed(1, _))
+        ));
+    }
+
+    #[test]
+    fn intermediates() {
+        let (leaf, intermediate) = (from_hex(&LEAF), from_hex(&INTERMEDIATE));
+        let other = from_hex(&ISSUED_BY_LEAF);
+
+        // the leaf isn't kept, nor an issuer which isn't a CA, and an intermediate only once
+        let mut intermediates = Intermediates::default();
+        intermediates.add_chain(&[&leaf, &intermediate]);
+        intermediates.add_chain(&[&other, &leaf, &intermediate, &[0x30, 0x00]]);
+        assert_eq!(intermediates.len(), 1);
+
+        // a server not sending the intermediate another one sent
+        assert_eq!(
+            store().verify_with(&[&leaf], &intermediates, "www.example.com", NOW),
+            Ok(())
+        );
+        assert_eq!(
+            TrustStore::default().verify_with(&[&leaf], &intermediates, "www.example.com", NOW),
+            Err(ChainError::UnknownIssuer(String::from("CN=Explore Root")))
+        );
+    }
+
+    #[test]
+    fn pem() {
+        let pem = concat!(
+            "the root of the tests\n",
+            "-----BEGIN CERTIFICATE-----\n",
+            "MIIBtzCCASCgAwIBAgIBATANBgkqhkiG9w0BAQsFADAXMRUwEwYDVQQDDAxFeHBs\n",
+            "b3JlIFJvb3QwHhcNMjQwMTAxMDAwMDAwWhcNMzQwMTAxMDAwMDAwWjAXMRUwEwYD\n",
+            "VQQDDAxFeHBsb3JlIFJvb3QwgZ8wDQYJKoZIhvcNAQEBBQADgY0AMIGJAoGBANhk\n",
+            "mwM/WosBJjgSvnkLXtJ4x2+j43DkZ77sSlc8oM0Bbrbs/7xajwrJlkQ1JxBR1H0O\n",
+            "EqNYfmqurMSqnqCyNHNj7MZXkqozBaID7mui3/NrL6Jy9y4eB647fijA4aIEJVh4\n",
+            "llkM+A5iasqkEl8yMxoHFHtHnaj9Pq1xuI5Rzhv7AgMBAAGjEzARMA8GA1UdEwEB\n",
+            "/wQFMAMBAf8wDQYJKoZIhvcNAQELBQADgYEAwfPrYi4OMDEMv02UsiB7CdVhMc5b\n",
+            "WVkg6FRANDTu5oU5hOvEaj4VDOUpCeDpGKOHlRGvn62AG65HjXvQSu15JwhNNS5U\n",
+            "RRGdxnu4Xpy5J3JeJ3Qs6olLCf+0V4WDu0ytw5Gxr5jd+85vH0qroD1GovO3uevl\n",
+            "bW3zKRZreQAOcyQ=\n",
+            "-----END CERTIFICATE-----\n",
+        );
+
+        let store = TrustStore::from_pem(pem).unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.roots[0], from_hex(&ROOT));
+
+        let truncated = "-----BEGIN CERTIFICATE-----\nMAA=\n";
+        assert!(TrustStore::from_pem(truncated).is_err());
+        let empty = "-----BEGIN CERTIFICATE-----\nMAA=\n-----END CERTIFICATE-----\n";
+        assert!(TrustStore::from_pem(empty).is_err());
+    }
+}
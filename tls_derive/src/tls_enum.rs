@@ -9,6 +9,9 @@
 //     z = 2,
 //     t = 255,
 // }
+//
+// A single variant holding a u16, like Unknown(u16), catches the values of no other variant:
+// TryFrom then never fails, and the value is kept to be sent back as is
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{Data, DataEnum, DeriveInput};
@@ -31,10 +34,30 @@ pub fn tls_enum(ast: &DeriveInput) -> TokenStream {
     let enum_name = &ast.ident;
     let enum_name_s = enum_name.to_string();
 
+    // the fallback variant, with a single unnamed field and no discriminant
+    let fallbacks: Vec<_> = enum_token
+        .variants
+        .iter()
+        .filter(|v| matches!(&v.fields, syn::Fields::Unnamed(f) if f.unnamed.len() == 1))
+        .collect();
+    if fallbacks.len() > 1 {
+        panic!("enum {} has more than one fallback variant!", enum_name);
+    }
+    let fallback = fallbacks.first().map(|v| {
+        if v.discriminant.is_some() {
+            panic!(
+                "fallback variant {} of enum {} can't have a value!",
+                v.ident, enum_name
+            );
+        }
+        &v.ident
+    });
+
     // get vector of tuples: (variant name, variant value)
     let variant_data: Vec<_> = enum_token
         .variants
         .iter()
+        .filter(|v| Some(&v.ident) != fallback)
         .map(|v| {
             //println!("{:?}", v);
 
@@ -106,7 +129,7 @@ pub fn tls_enum(ast: &DeriveInput) -> TokenStream {
     let display = variant_data.iter().map(|v| {
         // create value and identifier
         let value_variant = &v.0;
-        let value_int = v.1.parse::<u8>().unwrap();
+        let value_int = v.1.parse::<u16>().unwrap();
         let variant = format_ident!("{}", &v.0);
 
         quote! {
@@ -114,41 +137,101 @@ pub fn tls_enum(ast: &DeriveInput) -> TokenStream {
         }
     });
 
-    // now create code for implementation of Default, TryFrom<u8>, FromStr
-    let impls = quote! {
-        // impl Default
-        impl Default for #enum_name  {
-            fn default() -> Self {
-                #enum_name::#default_variant
-            }
+    let to_u16 = variant_data.iter().map(|v| {
+        let value = v.1.parse::<u16>().unwrap();
+        let variant = format_ident!("{}", &v.0);
+
+        quote! {
+            #enum_name::#variant => #value,
         }
+    });
 
-        // impl TryFrom<u8>
-        impl std::convert::TryFrom<u8> for #enum_name  {
-            type Error = String;
+    // with a fallback variant, all values convert: From<u16> is implemented, and TryFrom<u16>
+    // comes with it
+    let (conversions, display_fallback, to_u16_fallback) = match fallback {
+        Some(variant) => {
+            let from_u16 = variant_data.iter().map(|v| {
+                let value = v.1.parse::<u16>().unwrap();
+                let variant = format_ident!("{}", v.0);
 
-            fn try_from(value: u8) -> Result<Self, Self::Error> {
-                <#enum_name>::try_from(value as u16)
-            }
+                quote! {
+                    #value => #enum_name::#variant,
+                }
+            });
+
+            (
+                quote! {
+                    // impl From<u16>
+                    impl From<u16> for #enum_name {
+                        fn from(value: u16) -> Self {
+                            match value {
+                                #(#from_u16)*
+                                _ => #enum_name::#variant(value),
+                            }
+                        }
+                    }
+                },
+                quote! { #enum_name::#variant(value) => write!(f, "unknown({})", value), },
+                quote! { #enum_name::#variant(value) => value, },
+            )
         }
+        None => (
+            quote! {
+                // impl TryFrom<u8>
+                impl std::convert::TryFrom<u8> for #enum_name  {
+                    type Error = String;
 
-        // impl TryFrom<u16>
-        impl std::convert::TryFrom<u16> for #enum_name  {
-            type Error = String;
+                    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+                        <#enum_name>::try_from(value as u16)
+                    }
+                }
 
-            fn try_from(value: u16) -> Result<Self, Self::Error> {
-                match value {
-                    #(#try_from_u16)*
-                    _ => Err(format!("error converting <{}> to enum type {}", value, #enum_name_s)),
+                // impl TryFrom<u16>
+                impl std::convert::TryFrom<u16> for #enum_name  {
+                    type Error = String;
+
+                    fn try_from(value: u16) -> std::result::Result<Self, Self::Error> {
+                        match value {
+                            #(#try_from_u16)*
+                            _ => Err(format!("error converting <{}> to enum type {}", value, #enum_name_s)),
+                        }
+                    }
                 }
+            },
+            quote! {},
+            quote! {},
+        ),
+    };
+
+    // now create code for implementation of Default, TryFrom<u8>, FromStr
+    // the TlsDerive impl of enums is not derived, but each wire enum, having a fixed repr,
+    // has one
+    let register = if ast.attrs.iter().any(|a| a.path.is_ident("repr")) {
+        quote! {
+            #[cfg(feature = "registry")]
+            crate::tls_register!(#enum_name);
+        }
+    } else {
+        quote! {}
+    };
+
+    let impls = quote! {
+        #register
+
+        // impl Default
+        impl Default for #enum_name  {
+            fn default() -> Self {
+                #enum_name::#default_variant
             }
         }
 
+        #conversions
+
         // impl FromStr
         impl std::str::FromStr for #enum_name {
             type Err = String;
 
-            fn from_str(s: &str) -> Result<Self, Self::Err> {
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
                 match s {
                     #(#from_str)*
                     _ => Err(format!("error converting string <{}> to enum type {}", s, #enum_name_s)),
@@ -161,6 +244,17 @@ pub fn tls_enum(ast: &DeriveInput) -> TokenStream {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
                     #(#display)*
+                    #display_fallback
+                }
+            }
+        }
+
+        // impl From<enum> for u16, casts not being possible with a fallback variant
+        impl From<#enum_name> for u16 {
+            fn from(value: #enum_name) -> u16 {
+                match value {
+                    #(#to_u16)*
+                    #to_u16_fallback
                 }
             }
         }
@@ -133,6 +133,10 @@ pub fn tls_derive(ast: &DeriveInput) -> TokenStream {
         }
     } else {
         quote! {
+            // generic types can't be registered, the type parameter being unknown
+            #[cfg(feature = "registry")]
+            crate::tls_register!(#structure_name);
+
             // the generated impl.
             impl TlsDerive for #structure_name  {
                 fn tls_len(&self) -> usize {